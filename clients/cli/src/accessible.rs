@@ -0,0 +1,214 @@
+//! Accessible output
+//!
+//! `start --accessible` replaces the dashboard, which screen readers cannot follow (box drawing,
+//! a spinner, redraws of the whole screen several times a second, state shown only by color),
+//! with plain text written line by line:
+//!
+//! - each displayed event as one line, without emoji or color, e.g.
+//!   `14:05:03 Prover 1, error: Failed to submit proof`;
+//! - a status report at start and every `--status-interval`, whose lines always come in the same
+//!   order with the same labels, so a screen reader user can jump straight to the one they need:
+//!
+//! ```text
+//! Status report at 14:05 UTC
+//!   Status: running
+//!   Uptime: 1 hour 5 minutes
+//!   Tasks in progress: 1
+//!   Tasks fetched: 12
+//!   Proofs completed: 11
+//!   Last proof: 2 minutes ago
+//!   Proofs accepted: 10
+//!   Submissions failed: 0
+//!   Errors: 1
+//!   Last error: Failed to submit proof
+//! ```
+
+use crate::events::{Event, EventType, Worker};
+use crate::status_line::StatusLine;
+use chrono::{DateTime, Utc};
+use std::sync::OnceLock;
+
+static ENABLED: OnceLock<()> = OnceLock::new();
+
+/// Switches the node to accessible output. Only the first call has an effect.
+pub fn enable() {
+    let _ = ENABLED.set(());
+}
+
+/// Whether accessible output is in use.
+pub fn is_enabled() -> bool {
+    ENABLED.get().is_some()
+}
+
+/// `text` without emoji, box drawing and other symbols a screen reader would read out or skip
+/// unpredictably, and with runs of whitespace collapsed.
+fn plain(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c.is_ascii_punctuation() || c.is_whitespace() {
+                c
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn worker_name(worker: Worker) -> String {
+    match worker {
+        Worker::TaskFetcher => "Task fetcher".to_string(),
+        Worker::Prover(id) => format!("Prover {}", id),
+        Worker::ProofSubmitter => "Proof submitter".to_string(),
+        Worker::VersionChecker => "Version checker".to_string(),
+        Worker::Maintenance => "Maintenance".to_string(),
+        Worker::Scheduler => "Scheduler".to_string(),
+    }
+}
+
+/// An event as one plain line, led by its time of day.
+pub fn event_line(event: &Event) -> String {
+    let time = event
+        .timestamp
+        .rsplit(' ')
+        .next()
+        .unwrap_or(&event.timestamp);
+    let kind = match event.event_type {
+        EventType::Success => "done",
+        EventType::Error => "error",
+        EventType::Refresh => "update",
+        EventType::Shutdown => "stopping",
+    };
+    format!(
+        "{} {}, {}: {}",
+        time,
+        worker_name(event.worker),
+        kind,
+        plain(&event.msg)
+    )
+}
+
+/// A duration as read out, e.g. `1 hour 5 minutes`.
+fn spoken_duration(secs: u64) -> String {
+    let unit = |count: u64, name: &str| match count {
+        1 => format!("1 {}", name),
+        _ => format!("{} {}s", count, name),
+    };
+    let (days, hours, minutes) = (secs / 86_400, secs % 86_400 / 3600, secs % 3600 / 60);
+    if days > 0 {
+        format!("{} {}", unit(days, "day"), unit(hours, "hour"))
+    } else if hours > 0 {
+        format!("{} {}", unit(hours, "hour"), unit(minutes, "minute"))
+    } else if minutes > 0 {
+        unit(minutes, "minute")
+    } else {
+        unit(secs, "second")
+    }
+}
+
+/// The lines of a status report, always the same labels in the same order.
+fn status_report(status: &StatusLine, now: DateTime<Utc>) -> Vec<String> {
+    let last_proof = status
+        .last_proof_at
+        .as_deref()
+        .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+        .map(|at| {
+            let ago = (now - at.with_timezone(&Utc)).num_seconds().max(0) as u64;
+            format!("{} ago", spoken_duration(ago))
+        })
+        .unwrap_or_else(|| "none yet".to_string());
+    let last_error = status
+        .last_error
+        .as_deref()
+        .map(plain)
+        .unwrap_or_else(|| "none".to_string());
+    vec![
+        format!("Status report at {}", now.format("%H:%M UTC")),
+        format!("  Status: {}", status.status),
+        format!("  Uptime: {}", spoken_duration(status.uptime_secs)),
+        format!("  Tasks in progress: {}", status.tasks_in_flight),
+        format!("  Tasks fetched: {}", status.tasks_fetched),
+        format!("  Proofs completed: {}", status.proofs_completed),
+        format!("  Last proof: {}", last_proof),
+        format!("  Proofs accepted: {}", status.proofs_accepted),
+        format!("  Submissions failed: {}", status.submissions_failed),
+        format!("  Errors: {}", status.errors),
+        format!("  Last error: {}", last_error),
+    ]
+}
+
+/// Writes an event as a plain line (on stderr if stdout carries progress events).
+pub fn write_event(event: &Event) {
+    if crate::progress::on_stdout() {
+        eprintln!("{}", event_line(event));
+    } else {
+        println!("{}", event_line(event));
+    }
+}
+
+/// Writes a status report (on stderr if stdout carries progress events).
+pub fn print_status(status: &StatusLine) {
+    let report = status_report(status, Utc::now()).join("\n");
+    if crate::progress::on_stdout() {
+        eprintln!("{}", report);
+    } else {
+        println!("{}", report);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Events lose their emoji but keep their words and punctuation.
+    fn test_event_line_is_plain() {
+        let mut event = Event::prover(
+            1,
+            "✅ Proof completed  (Task ID: abc-1) ⏱".to_string(),
+            EventType::Success,
+        );
+        event.timestamp = "2026-10-15 14:05:03".to_string();
+        assert_eq!(
+            event_line(&event),
+            "14:05:03 Prover 1, done: Proof completed (Task ID: abc-1)"
+        );
+    }
+
+    #[test]
+    // A report has the same labels in the same order whether or not anything happened yet.
+    fn test_status_report_is_stable() {
+        let now = Utc::now();
+        let mut status = StatusLine {
+            timestamp: now.to_rfc3339(),
+            status: "running",
+            uptime_secs: 3900,
+            tasks_in_flight: 0,
+            tasks_fetched: 0,
+            proofs_completed: 0,
+            last_proof_at: None,
+            proofs_accepted: 0,
+            submissions_failed: 0,
+            errors: 0,
+            last_error: None,
+        };
+        let labels = |lines: Vec<String>| -> Vec<String> {
+            lines
+                .iter()
+                .map(|line| line.split(':').next().unwrap().to_string())
+                .collect()
+        };
+        let empty = status_report(&status, now);
+        assert_eq!(empty[2], "  Uptime: 1 hour 5 minutes");
+        assert_eq!(empty[6], "  Last proof: none yet");
+
+        status.last_proof_at = Some((now - chrono::Duration::seconds(125)).to_rfc3339());
+        status.last_error = Some("❌ Failed to submit proof".to_string());
+        let busy = status_report(&status, now);
+        assert_eq!(busy[6], "  Last proof: 2 minutes ago");
+        assert_eq!(busy[10], "  Last error: Failed to submit proof");
+        assert_eq!(labels(empty), labels(busy));
+    }
+}
@@ -34,14 +34,16 @@ pub const PRODUCTION_API_SECRET: &str = "3wxu8FjVSPqOlxSsZEnBOw";
 pub fn analytics_id(environment: &Environment) -> String {
     match environment {
         Environment::Production => PRODUCTION_MEASUREMENT_ID.to_string(),
-        Environment::Custom { .. } => String::new(), // Disable analytics for custom environments
+        // Disable analytics outside production
+        Environment::Staging | Environment::Custom { .. } => String::new(),
     }
 }
 
 pub fn analytics_api_key(environment: &Environment) -> String {
     match environment {
         Environment::Production => PRODUCTION_API_SECRET.to_string(),
-        Environment::Custom { .. } => String::new(), // Disable analytics for custom environments
+        // Disable analytics outside production
+        Environment::Staging | Environment::Custom { .. } => String::new(),
     }
 }
 
@@ -0,0 +1,283 @@
+//! Backpressure
+//!
+//! Fetching follows an explicit signal computed from what the node can get through, rather than
+//! topping the task queue up whenever it runs low: on a slow machine that held dozens of tasks
+//! that expired before a prover reached them. A fetcher may accept new tasks only while:
+//!
+//! - tasks in flight (queued, proving or awaiting upload) stay within what the active workers,
+//!   local and in connected prover processes (`--ipc-addr`, `--queue-dir`), can prove in half
+//!   a task's lifetime, judged by the slowest learned program profile, and at
+//!   most `MAX_TASKS_PER_WORKER` each (`DEFAULT_TASKS_PER_WORKER` until proofs were timed);
+//! - no more than `MAX_BACKLOG_PER_WORKER` finished proofs per worker wait for upload,
+//!   including those set aside to be retried;
+//! - the system has at least `MIN_MEMORY_HEADROOM` of memory available, and the process has
+//!   room to start a proof under `--max-memory-gb` (see `memory_limit`).
+//!
+//! Tasks being fetched count as in flight, so nodes fetching at once do not over-commit.
+
+use crate::control::control_state;
+use crate::memory_limit::MemoryLimit;
+use crate::submission_queue::TASK_LIFETIME;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Tasks in flight per worker before proving times are known.
+const DEFAULT_TASKS_PER_WORKER: usize = 2;
+
+/// Most tasks in flight per worker, however fast its proofs.
+const MAX_TASKS_PER_WORKER: usize = 4;
+
+/// Finished proofs per worker waiting for upload beyond which no new work is accepted.
+const MAX_BACKLOG_PER_WORKER: usize = 2;
+
+/// Available memory below which no new work is accepted.
+const MIN_MEMORY_HEADROOM: u64 = 1024 * 1024 * 1024;
+
+/// Provers started by this process
+static WORKERS: AtomicUsize = AtomicUsize::new(1);
+
+/// Provers in other processes this process hands tasks to
+static PROVER_SLOTS: AtomicUsize = AtomicUsize::new(0);
+
+/// Finished proofs waiting for upload
+static SUBMISSION_BACKLOG: AtomicUsize = AtomicUsize::new(0);
+
+/// Tasks requested from the orchestrator but not queued yet
+static FETCHING: AtomicUsize = AtomicUsize::new(0);
+
+/// Sets the number of provers started by this process; zero when proving is delegated.
+pub fn set_workers(workers: usize) {
+    WORKERS.store(workers, Ordering::Relaxed);
+}
+
+/// Provers in another process, counted in the capacity until dropped.
+#[derive(Debug, Default)]
+pub struct ProverSlots(usize);
+
+impl ProverSlots {
+    /// Counts `slots` provers of a connected prover process.
+    pub fn new(slots: usize) -> Self {
+        PROVER_SLOTS.fetch_add(slots, Ordering::Relaxed);
+        Self(slots)
+    }
+
+    /// Changes the number of provers counted, e.g. as provers come and go.
+    pub fn set(&mut self, slots: usize) {
+        PROVER_SLOTS.fetch_add(slots, Ordering::Relaxed);
+        release_slots(self.0);
+        self.0 = slots;
+    }
+}
+
+impl Drop for ProverSlots {
+    fn drop(&mut self) {
+        release_slots(self.0);
+    }
+}
+
+fn release_slots(slots: usize) {
+    let _ = PROVER_SLOTS.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+        Some(n.saturating_sub(slots))
+    });
+}
+
+/// Sets the number of finished proofs waiting for upload, or for a retry.
+pub fn set_submission_backlog(proofs: usize) {
+    SUBMISSION_BACKLOG.store(proofs, Ordering::Relaxed);
+}
+
+/// Whether a fetcher may accept new work.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Backpressure {
+    /// Up to this many more tasks can be completed
+    Accept(usize),
+    /// No new work until the reason clears
+    Hold(String),
+}
+
+impl fmt::Display for Backpressure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Backpressure::Accept(tasks) => write!(f, "room for {} more tasks", tasks),
+            Backpressure::Hold(reason) => f.write_str(reason),
+        }
+    }
+}
+
+/// What the node has downstream of its fetchers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capacity {
+    /// Workers allowed to prove at once, here and in prover processes
+    pub workers: usize,
+    /// Tasks queued, proving, awaiting upload or being fetched
+    pub in_flight: usize,
+    /// Finished proofs waiting for upload
+    pub submission_backlog: usize,
+    /// System memory available, if it can be read
+    pub available_memory: Option<u64>,
+    /// Memory used by this process
+    pub process_memory: u64,
+    /// Memory this process may use, if limited
+    pub memory_limit: Option<MemoryLimit>,
+    /// Proving time of the slowest program learned on this machine
+    pub slowest_proof: Option<Duration>,
+}
+
+impl Capacity {
+    /// The capacity of this process now.
+    pub fn current() -> Self {
+        Self {
+            workers: (WORKERS
+                .load(Ordering::Relaxed)
+                .min(control_state().worker_limit())
+                + PROVER_SLOTS.load(Ordering::Relaxed))
+            .max(1),
+            in_flight: control_state().tasks_in_flight() + FETCHING.load(Ordering::Relaxed),
+            submission_backlog: SUBMISSION_BACKLOG.load(Ordering::Relaxed),
+            available_memory: crate::system::available_memory_bytes(),
+            process_memory: crate::system::process_memory_bytes(),
+            memory_limit: crate::memory_limit::limit(),
+            slowest_proof: crate::program_profiles::slowest_expected_duration(),
+        }
+    }
+
+    /// Tasks each worker can take on and still prove them in time.
+    fn tasks_per_worker(&self) -> usize {
+        match self.slowest_proof {
+            Some(proof) if !proof.is_zero() => {
+                let fits = (TASK_LIFETIME / 2).as_secs_f64() / proof.as_secs_f64();
+                (fits as usize).clamp(1, MAX_TASKS_PER_WORKER)
+            }
+            _ => DEFAULT_TASKS_PER_WORKER,
+        }
+    }
+
+    /// Whether new tasks may be fetched, and how many.
+    pub fn signal(&self) -> Backpressure {
+        if self.submission_backlog > self.workers * MAX_BACKLOG_PER_WORKER {
+            return Backpressure::Hold(format!(
+                "{} proofs are waiting for upload",
+                self.submission_backlog
+            ));
+        }
+        if let Some(available) = self
+            .available_memory
+            .filter(|available| *available < MIN_MEMORY_HEADROOM)
+        {
+            return Backpressure::Hold(format!(
+                "only {} MB of memory is available",
+                available / (1024 * 1024)
+            ));
+        }
+        if let Some(limit) = self
+            .memory_limit
+            .filter(|limit| !limit.has_room(self.process_memory))
+        {
+            return Backpressure::Hold(format!(
+                "using {} MB of the {} MB memory limit",
+                self.process_memory / (1000 * 1000),
+                limit.bytes() / (1000 * 1000)
+            ));
+        }
+        let limit = self.workers * self.tasks_per_worker();
+        match limit.saturating_sub(self.in_flight) {
+            0 => Backpressure::Hold(format!(
+                "{} tasks in flight are all {} workers can finish in time",
+                self.in_flight, self.workers
+            )),
+            room => Backpressure::Accept(room),
+        }
+    }
+}
+
+/// Tasks counted as in flight while they are fetched.
+#[derive(Debug)]
+pub struct Reservation(usize);
+
+impl Reservation {
+    pub fn tasks(&self) -> usize {
+        self.0
+    }
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        let _ = FETCHING.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+            Some(n.saturating_sub(self.0))
+        });
+    }
+}
+
+/// Reserves room for up to `at_most` tasks to fetch, or says why there is none.
+pub fn reserve(at_most: usize) -> Result<Reservation, String> {
+    match Capacity::current().signal() {
+        Backpressure::Accept(room) => {
+            let tasks = room.min(at_most);
+            FETCHING.fetch_add(tasks, Ordering::Relaxed);
+            Ok(Reservation(tasks))
+        }
+        Backpressure::Hold(reason) => Err(reason),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capacity() -> Capacity {
+        Capacity {
+            workers: 2,
+            in_flight: 0,
+            submission_backlog: 0,
+            available_memory: Some(8 * MIN_MEMORY_HEADROOM),
+            process_memory: 0,
+            memory_limit: None,
+            slowest_proof: None,
+        }
+    }
+
+    #[test]
+    // Room follows the workers and how long their proofs take, and shrinks as tasks arrive.
+    fn test_room_follows_workers_and_proof_times() {
+        let mut capacity = capacity();
+        assert_eq!(capacity.signal(), Backpressure::Accept(4));
+        capacity.in_flight = 3;
+        assert_eq!(capacity.signal(), Backpressure::Accept(1));
+        capacity.in_flight = 4;
+        assert!(matches!(capacity.signal(), Backpressure::Hold(_)));
+
+        capacity.in_flight = 0;
+        capacity.slowest_proof = Some(Duration::from_secs(30));
+        assert_eq!(capacity.signal(), Backpressure::Accept(8));
+        // A proof taking most of a task's lifetime leaves one task per worker
+        capacity.slowest_proof = Some(TASK_LIFETIME);
+        assert_eq!(capacity.signal(), Backpressure::Accept(2));
+    }
+
+    #[test]
+    // An upload backlog, low memory or the memory limit holds off new work however idle the
+    // workers are.
+    fn test_hold_for_backlog_and_memory() {
+        let mut capacity = capacity();
+        capacity.submission_backlog = 5;
+        assert_eq!(
+            capacity.signal(),
+            Backpressure::Hold("5 proofs are waiting for upload".to_string())
+        );
+        capacity.submission_backlog = 4;
+        capacity.available_memory = Some(512 * 1024 * 1024);
+        assert_eq!(
+            capacity.signal(),
+            Backpressure::Hold("only 512 MB of memory is available".to_string())
+        );
+        capacity.available_memory = None;
+        assert_eq!(capacity.signal(), Backpressure::Accept(4));
+        capacity.memory_limit = Some(MemoryLimit::parse("4").unwrap());
+        capacity.process_memory = 3_500_000_000;
+        assert_eq!(
+            capacity.signal(),
+            Backpressure::Hold("using 3500 MB of the 4000 MB memory limit".to_string())
+        );
+    }
+}
@@ -0,0 +1,133 @@
+//! Node capability calibration
+//!
+//! Runs a short floating-point benchmark before proving starts and derives the capability
+//! figures reported to the orchestrator. The only channel the API offers for this is the
+//! `NodeTelemetry` attached to each proof submission, so the report is sent there; the
+//! last report is also saved next to the config file so `nexus status` can show it.
+
+use crate::system::{estimate_peak_gflops, get_memory_info, measure_gflops, num_cores};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Capability figures measured for this machine and reported to the orchestrator.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CapabilityReport {
+    /// Benchmarked GFLOP/s of a single prover thread.
+    pub measured_gflops_per_thread: f32,
+
+    /// Theoretical peak GFLOP/s for the configured number of prover threads.
+    pub estimated_peak_gflops: f64,
+
+    /// Number of prover threads the report was calibrated for.
+    pub num_provers: usize,
+
+    /// Number of logical cores on the machine.
+    pub num_cores: usize,
+
+    /// Total system memory, in the units sent in `NodeTelemetry::memory_capacity`.
+    pub memory_capacity: i32,
+
+    /// Value sent in `NodeTelemetry::flops_per_sec` (GFLOP/s).
+    pub reported_flops_per_sec: i32,
+
+    /// When the calibration ran (RFC 3339).
+    pub calibrated_at: String,
+}
+
+impl CapabilityReport {
+    /// Benchmarks the machine and builds a report for the given number of prover threads.
+    ///
+    /// This blocks for a few seconds, so call it from a blocking context.
+    pub fn calibrate(num_provers: usize) -> Self {
+        // The benchmark runs its per-core passes sequentially, so it measures one thread.
+        let measured_gflops_per_thread = measure_gflops();
+        let estimated_peak_gflops = estimate_peak_gflops(num_provers);
+        let (_, memory_capacity) = get_memory_info();
+        Self {
+            measured_gflops_per_thread,
+            estimated_peak_gflops,
+            num_provers,
+            num_cores: num_cores(),
+            memory_capacity,
+            reported_flops_per_sec: reported_flops(
+                measured_gflops_per_thread,
+                estimated_peak_gflops,
+                num_provers,
+            ),
+            calibrated_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    /// Loads the last saved report.
+    ///
+    /// # Errors
+    /// Returns an `std::io::Error` if the file cannot be read or parsed.
+    pub fn load_from_file(path: &Path) -> Result<Self, std::io::Error> {
+        let buf = fs::read(path)?;
+        serde_json::from_slice(&buf)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    /// Saves the report, creating parent directories as needed.
+    ///
+    /// # Errors
+    /// Returns an `std::io::Error` if the file cannot be written.
+    pub fn save(&self, path: &Path) -> Result<(), std::io::Error> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        fs::write(path, json)
+    }
+}
+
+/// Path to the last capability report, next to the config file.
+pub fn get_capability_path() -> Result<PathBuf, std::io::Error> {
+    crate::config::get_config_path().map(|path| path.with_file_name("capability.json"))
+}
+
+/// Sustained throughput for all prover threads, capped at the theoretical peak when known.
+fn reported_flops(measured_per_thread: f32, peak: f64, num_provers: usize) -> i32 {
+    let sustained = measured_per_thread as f64 * num_provers as f64;
+    let capped = if peak > 0.0 {
+        sustained.min(peak)
+    } else {
+        sustained
+    };
+    capped.round() as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    // Reported throughput should scale with threads but never exceed the theoretical peak.
+    fn test_reported_flops() {
+        assert_eq!(reported_flops(2.0, 100.0, 4), 8);
+        assert_eq!(reported_flops(50.0, 100.0, 4), 100);
+        // An unknown peak (e.g. no CPU frequency available) leaves the measurement uncapped.
+        assert_eq!(reported_flops(2.0, 0.0, 4), 8);
+    }
+
+    #[test]
+    // A saved report should load back unchanged.
+    fn test_save_and_load_round_trip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(".nexus").join("capability.json");
+        let report = CapabilityReport {
+            measured_gflops_per_thread: 1.5,
+            estimated_peak_gflops: 96.0,
+            num_provers: 2,
+            num_cores: 8,
+            memory_capacity: 16_000,
+            reported_flops_per_sec: 3,
+            calibrated_at: "2025-01-01T00:00:00+00:00".to_string(),
+        };
+        report.save(&path).unwrap();
+        assert_eq!(CapabilityReport::load_from_file(&path).unwrap(), report);
+    }
+}
@@ -0,0 +1,227 @@
+//! Task checkpoints
+//!
+//! The orchestrator API has no call to hand a claimed task back, so a task dropped on exit is
+//! wasted until it expires on the server. Tasks are tracked from the moment they are fetched
+//! until a prover is done with them; those still waiting or being proved when the node stops
+//! are saved to `~/.nexus/checkpoint.json` and proved first on the next start, as long as they
+//! have not expired (see `submission_queue::TASK_LIFETIME`) and their node is still run.
+//!
+//! Finished proofs are not checkpointed here: `proof_checkpoint` and the submission journal
+//! keep those.
+
+use crate::environment::Environment;
+use crate::nexus_orchestrator::TaskType;
+use crate::submission_queue::TASK_LIFETIME;
+use crate::task::Task;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Tasks fetched but not yet proved, by task ID
+static TRACKED: OnceLock<Mutex<HashMap<String, Task>>> = OnceLock::new();
+
+fn tracked() -> &'static Mutex<HashMap<String, Task>> {
+    TRACKED.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Path to the saved checkpoint, next to the config file.
+pub fn checkpoint_path(config_path: &Path) -> PathBuf {
+    config_path.with_file_name("checkpoint.json")
+}
+
+/// A task as saved in the checkpoint (or with its proof, see `proof_checkpoint`).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SavedTask {
+    task_id: String,
+    program_id: String,
+    public_inputs: Vec<u8>,
+    task_type: Option<i32>,
+    node_id: Option<u64>,
+    /// Seconds since the Unix epoch
+    created_at: Option<u64>,
+    proxy: Option<String>,
+}
+
+impl From<&Task> for SavedTask {
+    fn from(task: &Task) -> Self {
+        Self {
+            task_id: task.task_id.clone(),
+            program_id: task.program_id.clone(),
+            public_inputs: task.public_inputs.clone(),
+            task_type: task.task_type.map(|t| t as i32),
+            node_id: task.node_id,
+            created_at: task
+                .created_at
+                .and_then(|at| at.duration_since(UNIX_EPOCH).ok())
+                .map(|since| since.as_secs()),
+            proxy: task.proxy.clone(),
+        }
+    }
+}
+
+impl From<SavedTask> for Task {
+    fn from(saved: SavedTask) -> Self {
+        Task {
+            task_id: saved.task_id,
+            program_id: saved.program_id,
+            public_inputs: saved.public_inputs,
+            task_type: saved.task_type.and_then(|t| TaskType::try_from(t).ok()),
+            node_id: saved.node_id,
+            created_at: saved
+                .created_at
+                .map(|secs| UNIX_EPOCH + Duration::from_secs(secs)),
+            proxy: saved.proxy,
+        }
+    }
+}
+
+/// The tasks a node left unproved, and where they were fetched from.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct Checkpoint {
+    environment: String,
+    tasks: Vec<SavedTask>,
+}
+
+/// Tracks a fetched task until `untrack` is called for it.
+pub fn track(task: &Task) {
+    if let Ok(mut tasks) = tracked().lock() {
+        tasks.insert(task.task_id.clone(), task.clone());
+    }
+}
+
+/// Stops tracking a task once a prover is done with it, whether or not it was proved.
+pub fn untrack(task_id: &str) {
+    if let Ok(mut tasks) = tracked().lock() {
+        tasks.remove(task_id);
+    }
+}
+
+/// Saves the tasks still tracked, returning their IDs. Without any, a checkpoint not yet
+/// restored is left as it is.
+///
+/// # Errors
+/// Returns an `std::io::Error` if the checkpoint cannot be written.
+pub fn save(environment: &Environment) -> std::io::Result<Vec<String>> {
+    let path = checkpoint_path(&crate::config::get_config_path()?);
+    let tasks: Vec<Task> = tracked()
+        .lock()
+        .map(|tasks| tasks.values().cloned().collect())
+        .unwrap_or_default();
+    save_to_file(&path, environment, &tasks)?;
+    let mut task_ids: Vec<String> = tasks.into_iter().map(|task| task.task_id).collect();
+    task_ids.sort();
+    Ok(task_ids)
+}
+
+fn save_to_file(path: &Path, environment: &Environment, tasks: &[Task]) -> std::io::Result<()> {
+    if tasks.is_empty() {
+        return Ok(());
+    }
+    let checkpoint = Checkpoint {
+        environment: environment.label(),
+        tasks: tasks.iter().map(SavedTask::from).collect(),
+    };
+    let json = serde_json::to_string_pretty(&checkpoint).map_err(std::io::Error::other)?;
+    // Written aside and renamed, so a crash mid-write leaves the previous checkpoint intact
+    let partial = path.with_extension("json.partial");
+    std::fs::write(&partial, json)?;
+    std::fs::rename(partial, path)
+}
+
+/// Takes the tasks checkpointed at the last shutdown that can still be proved: fetched from
+/// `environment` for one of `node_ids`, and not expired by `now`. The checkpoint is then
+/// removed, so each task is resumed once.
+pub fn restore(environment: &Environment, node_ids: &[u64], now: SystemTime) -> Vec<Task> {
+    let Ok(config_path) = crate::config::get_config_path() else {
+        return Vec::new();
+    };
+    restore_from_file(&checkpoint_path(&config_path), environment, node_ids, now)
+}
+
+fn restore_from_file(
+    path: &Path,
+    environment: &Environment,
+    node_ids: &[u64],
+    now: SystemTime,
+) -> Vec<Task> {
+    let Ok(buf) = std::fs::read(path) else {
+        return Vec::new();
+    };
+    let checkpoint = serde_json::from_slice::<Checkpoint>(&buf).ok();
+    // Left for a run against the environment the tasks came from
+    if checkpoint
+        .as_ref()
+        .is_some_and(|checkpoint| checkpoint.environment != environment.label())
+    {
+        return Vec::new();
+    }
+    let _ = std::fs::remove_file(path);
+    let Some(checkpoint) = checkpoint else {
+        return Vec::new();
+    };
+    checkpoint
+        .tasks
+        .into_iter()
+        .map(Task::from)
+        .filter(|task| {
+            task.node_id
+                .is_some_and(|node_id| node_ids.contains(&node_id))
+        })
+        .filter(|task| {
+            // Without a creation time, a task may have expired long ago
+            task.created_at
+                .is_some_and(|created_at| created_at + TASK_LIFETIME > now)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn task(task_id: &str, node_id: u64, created_at: SystemTime) -> Task {
+        let mut task = Task::new(task_id.to_string(), "fast-fib".to_string(), vec![1, 2]);
+        task.task_type = Some(TaskType::ProofHash);
+        task.node_id = Some(node_id);
+        // Saved to the second
+        let secs = created_at.duration_since(UNIX_EPOCH).unwrap().as_secs();
+        task.created_at = Some(UNIX_EPOCH + Duration::from_secs(secs));
+        task.proxy = Some("socks5://127.0.0.1:1080".to_string());
+        task
+    }
+
+    #[test]
+    // Unexpired tasks of the nodes still run come back once, and only to the same environment.
+    fn test_restore_unexpired_tasks_once() {
+        let dir = tempdir().unwrap();
+        let path = checkpoint_path(&dir.path().join("config.json"));
+        let now = SystemTime::now();
+        let fresh = task("fresh", 1, now - Duration::from_secs(60));
+        let expired = task("expired", 1, now - TASK_LIFETIME);
+        let other_node = task("other", 2, now);
+        let environment = Environment::Production;
+        let tasks = [fresh.clone(), expired, other_node];
+
+        save_to_file(&path, &environment, &tasks).unwrap();
+        let local = Environment::Custom {
+            orchestrator_url: "http://localhost:8080".to_string(),
+        };
+        assert!(restore_from_file(&path, &local, &[1, 2], now).is_empty());
+        assert!(path.exists());
+
+        assert_eq!(
+            restore_from_file(&path, &environment, &[1], now),
+            vec![fresh]
+        );
+        assert!(!path.exists());
+        assert!(restore_from_file(&path, &environment, &[1], now).is_empty());
+
+        // A run with nothing to checkpoint keeps the one it did not restore
+        save_to_file(&path, &environment, &tasks).unwrap();
+        save_to_file(&path, &environment, &[]).unwrap();
+        assert!(path.exists());
+    }
+}
@@ -1,12 +1,60 @@
 //! Application configuration.
 
 use crate::environment::Environment;
+use crate::goal::EarningsGoal;
+use crate::hooks::Hooks;
+use crate::keys;
+use crate::metrics::MetricsBackend;
+use crate::profiles::{ProfileSchedule, ResourceProfile};
+use crate::task_filter::TaskFilter;
+use crate::wallets::Wallet;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::sync::OnceLock;
 use std::{fs, path::Path};
+use thiserror::Error;
+
+/// Version of the config file schema written by this release.
+pub const CONFIG_VERSION: u32 = 1;
+
+/// Directory that replaces `~/.nexus` under `--sandbox`.
+static SANDBOX_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Confines the node's state to `dir` for the rest of the process.
+///
+/// Everything the CLI writes lives next to the config file (capability, journal, control
+/// socket and tokens, goal progress, saved payloads), so moving the config file moves all of
+/// it. The sandbox has its own registration, so it proves under a separate identity.
+pub fn set_sandbox_dir(dir: PathBuf) -> Result<(), std::io::Error> {
+    fs::create_dir_all(&dir)?;
+    let dir = dir.canonicalize()?;
+    SANDBOX_DIR
+        .set(dir)
+        .map_err(|_| std::io::Error::new(std::io::ErrorKind::AlreadyExists, "Sandbox already set"))
+}
+
+/// The sandbox directory, if `--sandbox` was given.
+pub fn sandbox_dir() -> Option<&'static Path> {
+    SANDBOX_DIR.get().map(PathBuf::as_path)
+}
+
+/// Resolves a relative path the user asked to write to (e.g. a session recording) inside the
+/// sandbox, if there is one.
+pub fn sandboxed(path: PathBuf) -> PathBuf {
+    match sandbox_dir() {
+        Some(dir) if path.is_relative() => dir.join(path),
+        _ => path,
+    }
+}
 
-/// Get the path to the Nexus config file, typically located at ~/.nexus/config.json.
+/// Get the path to the Nexus config file, typically located at ~/.nexus/config.json, or in the
+/// sandbox directory if one is set.
 pub fn get_config_path() -> Result<PathBuf, std::io::Error> {
+    if let Some(dir) = sandbox_dir() {
+        return Ok(dir.join("config.json"));
+    }
     let home_path = home::home_dir().ok_or(std::io::Error::new(
         std::io::ErrorKind::NotFound,
         "Home directory not found",
@@ -17,6 +65,10 @@ pub fn get_config_path() -> Result<PathBuf, std::io::Error> {
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct Config {
+    /// Schema version of the file. Missing in files written before versioning was introduced.
+    #[serde(default)]
+    pub version: u32,
+
     /// Environment
     #[serde(default)]
     pub environment: String,
@@ -32,6 +84,43 @@ pub struct Config {
     /// The node's unique identifier, probably an integer. Empty when not yet registered.
     #[serde(default)]
     pub node_id: String,
+
+    /// Program IDs and task types this node accepts. Accepts everything when empty.
+    #[serde(default, skip_serializing_if = "TaskFilter::is_empty")]
+    pub task_filter: TaskFilter,
+
+    /// Worker counts for daily time windows, e.g. fewer workers during working hours.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub profiles: Vec<ResourceProfile>,
+
+    /// IANA time zone of the profile windows, e.g. "Europe/Berlin"; the system's when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+
+    /// Most tasks of a program proved at once, by program ID, e.g. fewer for memory-heavy ones.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub program_concurrency: BTreeMap<String, usize>,
+
+    /// Accepted proofs to aim for per day or week, tracked locally.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal: Option<EarningsGoal>,
+
+    /// Wallets besides the primary one, each with the nodes proving for it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub wallets: Vec<Wallet>,
+
+    /// Where metrics are exported, e.g. to Prometheus or statsd.
+    #[serde(default, skip_serializing_if = "MetricsBackend::is_none")]
+    pub metrics: MetricsBackend,
+
+    /// Scripts run when a task completes or fails and when the session ends.
+    #[serde(default, skip_serializing_if = "Hooks::is_empty")]
+    pub hooks: Hooks,
+
+    /// Keys this release does not know (e.g. written by a newer one), kept as they are so that
+    /// saving the file does not drop them.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 impl Config {
@@ -43,11 +132,111 @@ impl Config {
         environment: Environment,
     ) -> Self {
         Config {
+            version: CONFIG_VERSION,
             user_id,
             wallet_address,
             node_id,
             environment: environment.to_string(),
+            task_filter: TaskFilter::default(),
+            profiles: Vec::new(),
+            timezone: None,
+            program_concurrency: BTreeMap::new(),
+            goal: None,
+            wallets: Vec::new(),
+            metrics: MetricsBackend::None,
+            hooks: Hooks::default(),
+            extra: Map::new(),
+        }
+    }
+
+    /// Starts building a configuration; fields not set keep their defaults.
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+
+    /// The node IDs in `node_id`, which may list several separated by commas.
+    ///
+    /// # Errors
+    /// Returns [`ConfigError::InvalidNodeId`] for the first entry that is not a number.
+    pub fn node_ids(&self) -> Result<Vec<u64>, ConfigError> {
+        self.node_id
+            .split(',')
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(|id| {
+                id.parse::<u64>()
+                    .map_err(|_| ConfigError::InvalidNodeId(id.to_string()))
+            })
+            .collect()
+    }
+
+    /// The resource profiles in the config's time zone, with `default_workers` outside them
+    /// and worker counts capped at `max_workers`.
+    ///
+    /// # Errors
+    /// Returns a description of the first invalid profile, or of an unknown time zone.
+    pub fn profile_schedule(
+        &self,
+        default_workers: usize,
+        max_workers: usize,
+    ) -> Result<ProfileSchedule, String> {
+        ProfileSchedule::new(self.profiles.clone(), default_workers, max_workers)?
+            .in_timezone(self.timezone.as_deref())
+    }
+
+    /// Checks every field. Files are loaded without validation so that older or hand-edited
+    /// files can still be read and repaired.
+    ///
+    /// # Errors
+    /// Returns the first problem found.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if !self.wallet_address.is_empty() && !keys::is_valid_eth_address(&self.wallet_address) {
+            return Err(ConfigError::InvalidWalletAddress(
+                self.wallet_address.clone(),
+            ));
+        }
+        self.node_ids()?;
+        self.profile_schedule(1, usize::MAX)
+            .map_err(ConfigError::InvalidProfile)?;
+        if let Some((program_id, _)) = self.program_concurrency.iter().find(|(_, n)| **n == 0) {
+            return Err(ConfigError::InvalidProgramConcurrency(format!(
+                "'{}' needs at least one task at a time",
+                program_id
+            )));
+        }
+        if let Some(goal) = &self.goal {
+            if goal.proofs == 0 {
+                return Err(ConfigError::InvalidGoal(
+                    "needs at least one proof".to_string(),
+                ));
+            }
+        }
+
+        let mut names = vec![crate::wallets::PRIMARY_WALLET];
+        let mut linked = self.node_ids()?;
+        for wallet in &self.wallets {
+            if names.contains(&wallet.name.as_str()) {
+                return Err(ConfigError::InvalidWallet(format!(
+                    "'{}': name already used",
+                    wallet.name
+                )));
+            }
+            names.push(&wallet.name);
+            if !keys::is_valid_eth_address(&wallet.wallet_address) {
+                return Err(ConfigError::InvalidWallet(format!(
+                    "'{}': invalid address {}",
+                    wallet.name, wallet.wallet_address
+                )));
+            }
+            if let Some(node_id) = wallet.node_ids.iter().find(|id| linked.contains(id)) {
+                return Err(ConfigError::InvalidWallet(format!(
+                    "'{}': node {} is linked to another wallet",
+                    wallet.name, node_id
+                )));
+            }
+            linked.extend(&wallet.node_ids);
         }
+        Ok(())
     }
 
     /// Loads configuration from a JSON file at the given path.
@@ -81,6 +270,49 @@ impl Config {
         Ok(())
     }
 
+    /// Upgrades a config file written by an earlier release to the current schema.
+    ///
+    /// The original file is copied to `config.json.v<N>.bak` before it is rewritten. Returns
+    /// `None` if the file does not exist or is already current.
+    ///
+    /// # Errors
+    /// Returns an `std::io::Error` if the file cannot be read, backed up or rewritten, or if
+    /// the migrated contents are not a valid configuration.
+    pub fn migrate_file(path: &Path) -> Result<Option<Migration>, std::io::Error> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let buf = fs::read(path)?;
+        let Ok(Value::Object(mut fields)) = serde_json::from_slice::<Value>(&buf) else {
+            // Not a JSON object, so loading will report a proper error
+            return Ok(None);
+        };
+        let from_version = fields
+            .get("version")
+            .and_then(Value::as_u64)
+            .unwrap_or_default() as u32;
+        if from_version >= CONFIG_VERSION {
+            return Ok(None);
+        }
+
+        let changes = migrate_fields(&mut fields);
+        fields.insert("version".to_string(), Value::from(CONFIG_VERSION));
+        let config: Config = serde_json::from_value(Value::Object(fields))
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+        let mut backup_name = path.file_name().unwrap_or_default().to_os_string();
+        backup_name.push(format!(".v{}.bak", from_version));
+        let backup_path = path.with_file_name(backup_name);
+        fs::copy(path, &backup_path)?;
+        config.save(path)?;
+
+        Ok(Some(Migration {
+            from_version,
+            backup_path,
+            changes,
+        }))
+    }
+
     /// Clear the node ID configuration file.
     pub fn clear_node_config(path: &Path) -> std::io::Result<()> {
         // Check that the path ends with config.json
@@ -102,6 +334,155 @@ impl Config {
     }
 }
 
+/// A configuration field with an invalid value.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ConfigError {
+    #[error("Invalid wallet address: {0} (expected a 42-character hex string starting with '0x')")]
+    InvalidWalletAddress(String),
+
+    #[error("Invalid node ID: {0}")]
+    InvalidNodeId(String),
+
+    #[error("Invalid resource profile: {0}")]
+    InvalidProfile(String),
+
+    #[error("Invalid goal: {0}")]
+    InvalidGoal(String),
+
+    #[error("Invalid program concurrency: {0}")]
+    InvalidProgramConcurrency(String),
+
+    #[error("Invalid wallet {0}")]
+    InvalidWallet(String),
+}
+
+/// Builds a [`Config`], validating it on [`build`](ConfigBuilder::build).
+///
+/// ```ignore
+/// let config = Config::builder()
+///     .user_id(user_id)
+///     .wallet_address(wallet_address)
+///     .environment(&environment)
+///     .build()?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigBuilder {
+    config: Config,
+}
+
+impl Default for ConfigBuilder {
+    fn default() -> Self {
+        Self {
+            config: Config::new(
+                String::new(),
+                String::new(),
+                String::new(),
+                Environment::default(),
+            ),
+        }
+    }
+}
+
+impl ConfigBuilder {
+    pub fn user_id(mut self, user_id: impl Into<String>) -> Self {
+        self.config.user_id = user_id.into();
+        self
+    }
+
+    pub fn wallet_address(mut self, wallet_address: impl Into<String>) -> Self {
+        self.config.wallet_address = wallet_address.into();
+        self
+    }
+
+    /// Sets a single node, replacing any set before.
+    pub fn node_id(mut self, node_id: u64) -> Self {
+        self.config.node_id = node_id.to_string();
+        self
+    }
+
+    /// Sets several nodes, stored comma-separated.
+    pub fn node_ids(mut self, node_ids: &[u64]) -> Self {
+        self.config.node_id = node_ids
+            .iter()
+            .map(u64::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        self
+    }
+
+    pub fn environment(mut self, environment: &Environment) -> Self {
+        self.config.environment = environment.to_string();
+        self
+    }
+
+    pub fn task_filter(mut self, task_filter: TaskFilter) -> Self {
+        self.config.task_filter = task_filter;
+        self
+    }
+
+    pub fn profile(mut self, profile: ResourceProfile) -> Self {
+        self.config.profiles.push(profile);
+        self
+    }
+
+    pub fn timezone(mut self, timezone: impl Into<String>) -> Self {
+        self.config.timezone = Some(timezone.into());
+        self
+    }
+
+    pub fn program_concurrency(mut self, program_id: impl Into<String>, limit: usize) -> Self {
+        self.config
+            .program_concurrency
+            .insert(program_id.into(), limit);
+        self
+    }
+
+    pub fn goal(mut self, goal: EarningsGoal) -> Self {
+        self.config.goal = Some(goal);
+        self
+    }
+
+    pub fn wallet(mut self, wallet: Wallet) -> Self {
+        self.config.wallets.push(wallet);
+        self
+    }
+
+    pub fn metrics(mut self, metrics: MetricsBackend) -> Self {
+        self.config.metrics = metrics;
+        self
+    }
+
+    /// The configuration, if every field is valid.
+    ///
+    /// # Errors
+    /// Returns the first invalid field, as [`Config::validate`] does.
+    pub fn build(self) -> Result<Config, ConfigError> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+/// The result of upgrading a config file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Migration {
+    pub from_version: u32,
+    pub backup_path: PathBuf,
+    /// Human-readable description of each change.
+    pub changes: Vec<String>,
+}
+
+/// Applies the changes from unversioned files to version 1: node IDs stored as numbers.
+/// Every other key, known or not, is kept as it is.
+fn migrate_fields(fields: &mut Map<String, Value>) -> Vec<String> {
+    let mut changes = Vec::new();
+    if let Some(Value::Number(node_id)) = fields.get("node_id") {
+        let node_id = node_id.to_string();
+        fields.insert("node_id".to_string(), Value::String(node_id));
+        changes.push("stored 'node_id' as a string".to_string());
+    }
+    changes
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -112,10 +493,205 @@ mod tests {
     /// Helper function to create a test configuration.
     fn get_config() -> Config {
         Config {
+            version: CONFIG_VERSION,
             environment: "test".to_string(),
             user_id: "test_user_id".to_string(),
             wallet_address: "0x1234567890abcdef1234567890abcdef12345678".to_string(),
             node_id: "test_node_id".to_string(),
+            task_filter: TaskFilter::default(),
+            profiles: Vec::new(),
+            timezone: None,
+            program_concurrency: BTreeMap::new(),
+            goal: None,
+            wallets: Vec::new(),
+            metrics: MetricsBackend::None,
+            hooks: Hooks::default(),
+            extra: Map::new(),
+        }
+    }
+
+    const WALLET: &str = "0x1234567890abcdef1234567890abcdef12345678";
+
+    fn profile(name: &str, workers: usize, from: &str, until: &str) -> ResourceProfile {
+        ResourceProfile {
+            name: name.to_string(),
+            workers,
+            from: from.to_string(),
+            until: until.to_string(),
+        }
+    }
+
+    fn wallet(name: &str, node_ids: Vec<u64>) -> Wallet {
+        Wallet {
+            name: name.to_string(),
+            wallet_address: WALLET.replace("0x12", "0x34"),
+            user_id: format!("user-{}", name),
+            node_ids,
+        }
+    }
+
+    #[test]
+    // A builder with nothing set should produce the same configuration as new() with empty fields.
+    fn test_builder_defaults() {
+        let config = Config::builder().build().unwrap();
+        assert_eq!(
+            config,
+            Config::new(
+                String::new(),
+                String::new(),
+                String::new(),
+                Environment::default()
+            )
+        );
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.node_ids(), Ok(Vec::new()));
+    }
+
+    #[test]
+    // Every field set through the builder should survive a JSON roundtrip.
+    fn test_builder_serde_roundtrip() {
+        let config = Config::builder()
+            .user_id("user")
+            .wallet_address(WALLET)
+            .node_ids(&[1, 2])
+            .environment(&Environment::Production)
+            .task_filter(TaskFilter {
+                allow_programs: vec!["fast-fib".to_string()],
+                ..TaskFilter::default()
+            })
+            .profile(profile("day", 2, "09:00", "18:00"))
+            .goal(EarningsGoal {
+                proofs: 100,
+                period: crate::goal::GoalPeriod::Week,
+            })
+            .wallet(wallet("alice", vec![3]))
+            .metrics(MetricsBackend::Statsd {
+                addr: "127.0.0.1:8125".to_string(),
+                prefix: "nexus".to_string(),
+            })
+            .build()
+            .unwrap();
+        assert_eq!(config.node_id, "1,2");
+        assert_eq!(config.environment, "Production");
+
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(serde_json::from_str::<Config>(&json).unwrap(), config);
+    }
+
+    #[test]
+    // Optional sections should be left out of the file when empty.
+    fn test_empty_sections_are_not_serialized() {
+        let json = serde_json::to_value(Config::builder().node_id(7).build().unwrap()).unwrap();
+        for key in [
+            "task_filter",
+            "profiles",
+            "program_concurrency",
+            "goal",
+            "wallets",
+            "metrics",
+        ] {
+            assert!(json.get(key).is_none(), "{} should be omitted", key);
+        }
+        assert_eq!(json["node_id"], "7");
+    }
+
+    #[test]
+    // Node IDs should be parsed from a comma-separated list, rejecting non-numbers.
+    fn test_node_ids() {
+        let mut config = get_config();
+        config.node_id = " 12, 34 ,,56".to_string();
+        assert_eq!(config.node_ids(), Ok(vec![12, 34, 56]));
+        config.node_id = "12,abc".to_string();
+        assert_eq!(
+            config.node_ids(),
+            Err(ConfigError::InvalidNodeId("abc".to_string()))
+        );
+    }
+
+    #[test]
+    // Each invalid field should be reported by build().
+    fn test_builder_rejects_invalid_fields() {
+        assert!(matches!(
+            Config::builder().wallet_address("0xabc").build(),
+            Err(ConfigError::InvalidWalletAddress(_))
+        ));
+        let mut config = get_config();
+        config.node_id = "not-a-number".to_string();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidNodeId(_))
+        ));
+        assert!(matches!(
+            Config::builder()
+                .profile(profile("day", 2, "9am", "18:00"))
+                .build(),
+            Err(ConfigError::InvalidProfile(_))
+        ));
+        assert!(matches!(
+            Config::builder()
+                .profile(profile("idle", 0, "09:00", "18:00"))
+                .build(),
+            Err(ConfigError::InvalidProfile(_))
+        ));
+        assert!(matches!(
+            Config::builder().timezone("Berlin").build(),
+            Err(ConfigError::InvalidProfile(_))
+        ));
+        assert!(matches!(
+            Config::builder()
+                .goal(EarningsGoal {
+                    proofs: 0,
+                    period: crate::goal::GoalPeriod::Day,
+                })
+                .build(),
+            Err(ConfigError::InvalidGoal(_))
+        ));
+        assert!(matches!(
+            Config::builder().program_concurrency("heavy", 0).build(),
+            Err(ConfigError::InvalidProgramConcurrency(_))
+        ));
+        assert!(
+            Config::builder()
+                .program_concurrency("heavy", 2)
+                .build()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    // Wallets need unique names and valid addresses, and a node may only be linked once.
+    fn test_builder_rejects_invalid_wallets() {
+        let duplicate_name = Config::builder()
+            .wallet(wallet("alice", vec![1]))
+            .wallet(wallet("alice", vec![2]))
+            .build();
+        let reserved_name = Config::builder()
+            .wallet(wallet(crate::wallets::PRIMARY_WALLET, vec![]))
+            .build();
+        let mut bad_address = wallet("bob", vec![]);
+        bad_address.wallet_address = "bob".to_string();
+        let bad_address = Config::builder().wallet(bad_address).build();
+        let linked_twice = Config::builder()
+            .node_id(1)
+            .wallet(wallet("alice", vec![1]))
+            .build();
+        for result in [duplicate_name, reserved_name, bad_address, linked_twice] {
+            assert!(matches!(result, Err(ConfigError::InvalidWallet(_))));
+        }
+
+        let valid = Config::builder()
+            .node_id(1)
+            .wallet(wallet("alice", vec![2]))
+            .wallet(wallet("bob", vec![3]))
+            .build();
+        assert!(valid.is_ok());
+    }
+
+    #[test]
+    // Without a sandbox, paths should be left as given.
+    fn test_sandboxed_paths_without_sandbox() {
+        for path in ["/tmp/session.ndjson", "session.ndjson"] {
+            assert_eq!(sandboxed(PathBuf::from(path)), PathBuf::from(path));
         }
     }
 
@@ -251,10 +827,20 @@ mod tests {
         let path = dir.path().join("config.json");
 
         let config = Config {
+            version: CONFIG_VERSION,
             environment: "".to_string(),
             user_id: "".to_string(),
             wallet_address: "".to_string(),
             node_id: "12345".to_string(),
+            task_filter: TaskFilter::default(),
+            profiles: Vec::new(),
+            timezone: None,
+            program_concurrency: BTreeMap::new(),
+            goal: None,
+            wallets: Vec::new(),
+            metrics: MetricsBackend::None,
+            hooks: Hooks::default(),
+            extra: Map::new(),
         };
         config.save(&path).unwrap();
 
@@ -274,7 +860,28 @@ mod tests {
     }
 
     #[test]
-    // Should ignore unexpected fields in the JSON.
+    // Should load a task filter and keep it across a save/load roundtrip.
+    fn test_load_config_with_task_filter() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.json");
+
+        let mut file = File::create(&path).unwrap();
+        writeln!(
+            file,
+            r#"{{ "node_id": "12345", "task_filter": {{ "allow_programs": ["fast-fib"] }} }}"#
+        )
+        .unwrap();
+
+        let config = Config::load_from_file(&path).unwrap();
+        assert_eq!(config.task_filter.allow_programs, vec!["fast-fib"]);
+        assert!(config.task_filter.deny_programs.is_empty());
+
+        config.save(&path).unwrap();
+        assert_eq!(Config::load_from_file(&path).unwrap(), config);
+    }
+
+    #[test]
+    // Should accept unexpected fields in the JSON, and keep them when saving.
     fn test_load_config_with_additional_fields() {
         let dir = tempdir().unwrap();
         let path = dir.path().join("config.json");
@@ -291,10 +898,42 @@ mod tests {
                 assert!(config.user_id.is_empty());
                 assert!(config.wallet_address.is_empty());
                 assert!(config.environment.is_empty());
+                assert_eq!(config.extra["extra_field"], "value");
+
+                config.save(&path).unwrap();
+                let saved = Config::load_from_file(&path).unwrap();
+                assert_eq!(saved.extra["extra_field"], "value");
             }
             Err(e) => {
                 panic!("Failed to load config with additional fields: {}", e);
             }
         }
     }
+
+    #[test]
+    // Old files should be migrated to the current schema, keeping a backup of the original
+    // and every key the release does not know.
+    fn test_migrate_file_upgrades_old_files() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        let original = r#"{ "node_id": 12345, "wallet_address": "0xabc", "future_key": [1] }"#;
+        fs::write(&path, original).unwrap();
+
+        let migration = Config::migrate_file(&path).unwrap().unwrap();
+        assert_eq!(migration.from_version, 0);
+        assert_eq!(migration.changes.len(), 1);
+        assert_eq!(
+            fs::read_to_string(&migration.backup_path).unwrap(),
+            original
+        );
+
+        let config = Config::load_from_file(&path).unwrap();
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.node_id, "12345");
+        assert_eq!(config.wallet_address, "0xabc");
+        assert_eq!(config.extra["future_key"], serde_json::json!([1]));
+
+        // A current file is left alone
+        assert_eq!(Config::migrate_file(&path).unwrap(), None);
+    }
 }
@@ -0,0 +1,249 @@
+//! Effective configuration
+//!
+//! Implements `nexus config diff`, which lists each setting's effective value next to its
+//! default, along with where the value came from: a command-line flag, an environment
+//! variable, the config file, or the built-in default.
+
+use crate::config::Config;
+use crate::environment::{Environment, EnvironmentName};
+use crate::task_filter::TaskFilter;
+use std::error::Error;
+use std::fmt::Display;
+use std::path::Path;
+
+/// Where an effective setting came from, in order of increasing precedence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Default,
+    File,
+    Env,
+    Flag,
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::File => write!(f, "file"),
+            Source::Env => write!(f, "env"),
+            Source::Flag => write!(f, "flag"),
+        }
+    }
+}
+
+/// One row of the diff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Setting {
+    pub key: &'static str,
+    pub value: String,
+    pub default: String,
+    pub source: Source,
+}
+
+impl Setting {
+    fn new(key: &'static str, value: String, default: String, source: Source) -> Self {
+        Self {
+            key,
+            value,
+            default,
+            source,
+        }
+    }
+
+    /// Whether the effective value differs from the default.
+    pub fn is_changed(&self) -> bool {
+        self.value != self.default
+    }
+}
+
+/// Settings the diff can take from the command line. These mirror the `start` flags.
+#[derive(Debug, Clone, Default)]
+pub struct FlagOverrides {
+    pub node_ids: Vec<u64>,
+    pub environment: Option<EnvironmentName>,
+    pub orchestrator_url: Option<String>,
+    pub task_filter: TaskFilter,
+}
+
+/// Resolves each setting the same way `nexus start` does.
+pub fn effective_settings(
+    file: Option<&Config>,
+    env_environment: Option<&str>,
+    flags: &FlagOverrides,
+) -> Vec<Setting> {
+    let mut settings = Vec::new();
+
+    // Environment: --orchestrator-url or --environment, then NEXUS_ENVIRONMENT
+    let default_environment = Environment::default();
+    let env_environment = env_environment
+        .filter(|s| !s.is_empty())
+        .and_then(|s| s.parse::<Environment>().ok());
+    let flag_environment = (flags.environment.is_some() || flags.orchestrator_url.is_some())
+        .then(|| Environment::select(flags.environment, flags.orchestrator_url.clone()).ok())
+        .flatten();
+    let (environment, source) = match (flag_environment, env_environment) {
+        (Some(environment), _) => (environment, Source::Flag),
+        (None, Some(environment)) => (environment, Source::Env),
+        (None, None) => (default_environment.clone(), Source::Default),
+    };
+    settings.push(Setting::new(
+        "environment",
+        environment.to_string(),
+        default_environment.to_string(),
+        source,
+    ));
+    settings.push(Setting::new(
+        "orchestrator_url",
+        environment.orchestrator_url().to_string(),
+        default_environment.orchestrator_url().to_string(),
+        source,
+    ));
+
+    // Registration details: --node-id, then the config file
+    let from_file = |value: Option<&String>| match value.filter(|v| !v.is_empty()) {
+        Some(value) => (value.clone(), Source::File),
+        None => (String::new(), Source::Default),
+    };
+    let (node_id, source) = if flags.node_ids.is_empty() {
+        from_file(file.map(|c| &c.node_id))
+    } else {
+        let ids: Vec<String> = flags.node_ids.iter().map(u64::to_string).collect();
+        (ids.join(","), Source::Flag)
+    };
+    settings.push(Setting::new("node_id", node_id, String::new(), source));
+    let (user_id, source) = from_file(file.map(|c| &c.user_id));
+    settings.push(Setting::new("user_id", user_id, String::new(), source));
+    let (wallet_address, source) = from_file(file.map(|c| &c.wallet_address));
+    settings.push(Setting::new(
+        "wallet_address",
+        wallet_address,
+        String::new(),
+        source,
+    ));
+
+    // Task filter: each non-empty flag list replaces the list from the config file
+    let file_filter = file.map(|c| c.task_filter.clone()).unwrap_or_default();
+    let lists = [
+        (
+            "task_filter.allow_programs",
+            &file_filter.allow_programs,
+            &flags.task_filter.allow_programs,
+        ),
+        (
+            "task_filter.deny_programs",
+            &file_filter.deny_programs,
+            &flags.task_filter.deny_programs,
+        ),
+        (
+            "task_filter.allow_task_types",
+            &file_filter.allow_task_types,
+            &flags.task_filter.allow_task_types,
+        ),
+        (
+            "task_filter.deny_task_types",
+            &file_filter.deny_task_types,
+            &flags.task_filter.deny_task_types,
+        ),
+    ];
+    for (key, file_list, flag_list) in lists {
+        let (list, source) = if !flag_list.is_empty() {
+            (flag_list, Source::Flag)
+        } else if !file_list.is_empty() {
+            (file_list, Source::File)
+        } else {
+            (file_list, Source::Default)
+        };
+        settings.push(Setting::new(key, list.join(","), String::new(), source));
+    }
+
+    settings
+}
+
+/// Prints the effective configuration, marking settings that differ from their defaults.
+pub fn print_config_diff(config_path: &Path, flags: &FlagOverrides) -> Result<(), Box<dyn Error>> {
+    let file = if config_path.exists() {
+        Some(Config::load_from_file(config_path)?)
+    } else {
+        None
+    };
+    let env_environment = std::env::var("NEXUS_ENVIRONMENT").ok();
+    let settings = effective_settings(file.as_ref(), env_environment.as_deref(), flags);
+
+    println!("Config file: {}", config_path.display());
+    if let Some(file) = &file {
+        println!("Schema version: {}", file.version);
+    }
+    println!();
+    let key_width = settings.iter().map(|s| s.key.len()).max().unwrap_or(0);
+    for setting in &settings {
+        let marker = if setting.is_changed() { "*" } else { " " };
+        let display = |value: &str| {
+            if value.is_empty() {
+                "(unset)".to_string()
+            } else {
+                value.to_string()
+            }
+        };
+        print!(
+            "{} {:width$}  {:<8} {}",
+            marker,
+            setting.key,
+            setting.source,
+            display(&setting.value),
+            width = key_width
+        );
+        if setting.is_changed() {
+            print!("  (default: {})", display(&setting.default));
+        }
+        println!();
+    }
+    println!("\n* differs from the default");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find<'a>(settings: &'a [Setting], key: &str) -> &'a Setting {
+        settings.iter().find(|s| s.key == key).unwrap()
+    }
+
+    #[test]
+    // Flags should take precedence over the environment and the config file.
+    fn test_sources_follow_precedence() {
+        let mut file = Config::new(
+            "user".to_string(),
+            "0xabc".to_string(),
+            "12345".to_string(),
+            Environment::Production,
+        );
+        file.task_filter.allow_programs = vec!["fast-fib".to_string()];
+
+        let settings = effective_settings(Some(&file), None, &FlagOverrides::default());
+        assert_eq!(find(&settings, "environment").source, Source::Default);
+        assert!(!find(&settings, "environment").is_changed());
+        assert_eq!(find(&settings, "node_id").source, Source::File);
+        assert_eq!(
+            find(&settings, "task_filter.allow_programs").source,
+            Source::File
+        );
+
+        let flags = FlagOverrides {
+            node_ids: vec![1, 2],
+            environment: None,
+            orchestrator_url: Some("http://localhost:8080".to_string()),
+            task_filter: TaskFilter {
+                allow_programs: vec!["other".to_string()],
+                ..TaskFilter::default()
+            },
+        };
+        let settings = effective_settings(Some(&file), Some("production"), &flags);
+        assert_eq!(find(&settings, "orchestrator_url").source, Source::Flag);
+        assert_eq!(find(&settings, "node_id").value, "1,2");
+        assert_eq!(find(&settings, "node_id").source, Source::Flag);
+        assert_eq!(find(&settings, "task_filter.allow_programs").value, "other");
+        assert_eq!(find(&settings, "wallet_address").source, Source::File);
+        assert_eq!(find(&settings, "user_id").value, "user");
+    }
+}
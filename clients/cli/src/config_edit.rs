@@ -0,0 +1,370 @@
+//! Reading and editing settings
+//!
+//! Implements `config get`, `config set`, `config list` and `config validate`, so the settings
+//! users change most can be edited without hand-editing (and possibly corrupting) JSON. Each
+//! setting lives in the file that takes effect:
+//!
+//! - `node-id` and `wallet-address` in `config.json`, as written by registration;
+//! - `environment`, `orchestrator-url` and `proxy` in `config.toml`, the layered settings of
+//!   `start` (see `start_settings`).
+//!
+//! Values are checked before anything is written, and the whole file is validated again before
+//! it is saved. An empty value clears a setting. A config file that cannot be parsed is never
+//! overwritten; `config validate` reports what is wrong with it.
+
+use crate::config::Config;
+use crate::environment::{Environment, EnvironmentName};
+use crate::keys;
+use crate::start_settings::settings_path;
+use clap::{Parser, ValueEnum};
+use std::error::Error;
+use std::ffi::OsString;
+use std::fs;
+use std::path::Path;
+use toml_edit::DocumentMut;
+
+/// The file a setting lives in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Store {
+    /// `config.json`
+    Config,
+    /// `config.toml`
+    Settings,
+}
+
+/// A setting that can be read and edited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Setting {
+    key: &'static str,
+    store: Store,
+    about: &'static str,
+}
+
+const SETTINGS: &[Setting] = &[
+    Setting {
+        key: "node-id",
+        store: Store::Config,
+        about: "node IDs run by `start`, comma-separated",
+    },
+    Setting {
+        key: "wallet-address",
+        store: Store::Config,
+        about: "the primary wallet (0x and 40 hex digits)",
+    },
+    Setting {
+        key: "environment",
+        store: Store::Settings,
+        about: "production, staging or custom",
+    },
+    Setting {
+        key: "orchestrator-url",
+        store: Store::Settings,
+        about: "the orchestrator of the custom environment",
+    },
+    Setting {
+        key: "proxy",
+        store: Store::Settings,
+        about: "the proxy file used by `start`",
+    },
+];
+
+/// Finds a setting by key, with dashes or underscores.
+fn find(key: &str) -> Result<Setting, String> {
+    let key = key.trim().replace('_', "-");
+    SETTINGS
+        .iter()
+        .find(|setting| setting.key == key)
+        .copied()
+        .ok_or_else(|| {
+            let keys: Vec<&str> = SETTINGS.iter().map(|setting| setting.key).collect();
+            format!(
+                "Unknown setting '{}'; settings are {}",
+                key,
+                keys.join(", ")
+            )
+        })
+}
+
+/// Loads `config.json`, or a blank config if there is none yet.
+fn load_config(config_path: &Path) -> Result<Config, String> {
+    match Config::load_from_file(config_path) {
+        Ok(config) => Ok(config),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            Config::builder().build().map_err(|e| e.to_string())
+        }
+        Err(e) => Err(format!(
+            "Cannot read {}: {}. Run `nexus-cli config validate` for details",
+            config_path.display(),
+            e
+        )),
+    }
+}
+
+/// Loads `config.toml` for editing, or a blank document if there is none yet.
+fn load_settings(path: &Path) -> Result<DocumentMut, String> {
+    match fs::read_to_string(path) {
+        Ok(text) => text
+            .parse()
+            .map_err(|e| format!("Cannot parse {}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(DocumentMut::new()),
+        Err(e) => Err(format!("Cannot read {}: {}", path.display(), e)),
+    }
+}
+
+/// The value of a setting in `config.toml`, which may be keyed with underscores.
+fn settings_value(doc: &DocumentMut, key: &str) -> Option<String> {
+    [key.to_string(), key.replace('-', "_")]
+        .iter()
+        .find_map(|key| doc.get(key))
+        .and_then(|item| item.as_value())
+        .map(|value| match value.as_str() {
+            Some(s) => s.to_string(),
+            None => value.to_string().trim().to_string(),
+        })
+}
+
+/// The value of `key`, or `None` if it is not set.
+///
+/// # Errors
+/// Returns a message if the key is unknown or its file cannot be read.
+pub fn get(config_path: &Path, key: &str) -> Result<Option<String>, String> {
+    let setting = find(key)?;
+    let value = match setting.store {
+        Store::Config => {
+            let config = load_config(config_path)?;
+            match setting.key {
+                "node-id" => config.node_id,
+                _ => config.wallet_address,
+            }
+        }
+        Store::Settings => {
+            let doc = load_settings(&settings_path(config_path))?;
+            settings_value(&doc, setting.key).unwrap_or_default()
+        }
+    };
+    Ok(Some(value).filter(|value| !value.is_empty()))
+}
+
+/// Checks a value for `setting`, returning it as it is stored.
+fn checked_value(setting: Setting, value: &str) -> Result<String, String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(String::new());
+    }
+    match setting.key {
+        "node-id" => {
+            let ids = value
+                .split(',')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(|id| {
+                    id.parse::<u64>()
+                        .map(|id| id.to_string())
+                        .map_err(|_| format!("'{}' is not a node ID; node IDs are numbers", id))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(ids.join(","))
+        }
+        "wallet-address" if !keys::is_valid_eth_address(value) => Err(format!(
+            "'{}' is not a wallet address (expected 0x followed by 40 hex digits)",
+            value
+        )),
+        "environment" => EnvironmentName::from_str(value, true)
+            .map(|_| value.to_lowercase())
+            .map_err(|_| {
+                format!(
+                    "Unknown environment '{}' (expected production, staging or custom)",
+                    value
+                )
+            }),
+        "orchestrator-url" => {
+            Environment::select(None, Some(value.to_string()))?;
+            Ok(value.to_string())
+        }
+        "proxy" => {
+            let path = Path::new(value);
+            if !path.is_file() {
+                return Err(format!(
+                    "No proxy file at {}; create it first (one proxy URL per line)",
+                    value
+                ));
+            }
+            // `start` may run from another directory
+            let path = path
+                .canonicalize()
+                .map_err(|e| format!("Cannot resolve {}: {}", value, e))?;
+            Ok(path.display().to_string())
+        }
+        _ => Ok(value.to_string()),
+    }
+}
+
+/// Sets `key` to `value`, or clears it if `value` is empty. Returns notes for the user.
+///
+/// # Errors
+/// Returns a message if the key is unknown, the value is invalid, or the file cannot be read
+/// or written.
+pub fn set(config_path: &Path, key: &str, value: &str) -> Result<Vec<String>, String> {
+    let setting = find(key)?;
+    let value = checked_value(setting, value)?;
+    let mut notes = Vec::new();
+    match setting.store {
+        Store::Config => {
+            let mut config = load_config(config_path)?;
+            if setting.key == "node-id" {
+                config.node_id = value;
+            } else if config.wallet_address != value {
+                // The user ID acts for the old wallet on the orchestrator
+                if !config.user_id.is_empty() {
+                    config.user_id.clear();
+                    notes.push(
+                        "The user ID of the previous wallet was removed; register the new one \
+                         with: nexus-cli register-user --wallet-address <address>"
+                            .to_string(),
+                    );
+                }
+                config.wallet_address = value;
+            }
+            config.validate().map_err(|e| format!("Not saved: {}", e))?;
+            config
+                .save(config_path)
+                .map_err(|e| format!("Cannot write {}: {}", config_path.display(), e))?;
+        }
+        Store::Settings => {
+            let path = settings_path(config_path);
+            let mut doc = load_settings(&path)?;
+            doc.remove(&setting.key.replace('-', "_"));
+            if value.is_empty() {
+                doc.remove(setting.key);
+            } else {
+                doc[setting.key] = toml_edit::value(value);
+            }
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("Cannot create {}: {}", parent.display(), e))?;
+            }
+            fs::write(&path, doc.to_string())
+                .map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+            if setting.key == "environment" && std::env::var_os("NEXUS_ENVIRONMENT").is_some() {
+                notes.push("NEXUS_ENVIRONMENT is set and takes precedence".to_string());
+            }
+        }
+    }
+    Ok(notes)
+}
+
+/// Implements `config list`: every setting, its value and the file it lives in.
+pub fn print_list(config_path: &Path) -> Result<(), Box<dyn Error>> {
+    let settings = settings_path(config_path);
+    for setting in SETTINGS {
+        let value = get(config_path, setting.key)?.unwrap_or_else(|| "(not set)".to_string());
+        let file = match setting.store {
+            Store::Config => config_path,
+            Store::Settings => settings.as_path(),
+        };
+        println!("{:<16} {}", setting.key, value);
+        println!("{:<16} {}, in {}", "", setting.about, file.display());
+    }
+    Ok(())
+}
+
+/// Checks `config.json` field by field; a missing file is fine.
+///
+/// # Errors
+/// Returns the first problem found, or why the file cannot be parsed.
+pub fn validate_config(config_path: &Path) -> Result<(), String> {
+    let buf = match fs::read(config_path) {
+        Ok(buf) => buf,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(format!("cannot read it: {}", e)),
+    };
+    // serde_json reports the line and column of a syntax error or mistyped field
+    let config: Config = serde_json::from_slice(&buf).map_err(|e| e.to_string())?;
+    config.validate().map_err(|e| e.to_string())
+}
+
+/// Checks `config.toml`, and `NEXUS_*` variables, as `start` would read them.
+///
+/// # Errors
+/// Returns the first invalid setting, or why the file cannot be parsed.
+fn validate_settings<P: Parser>(config_path: &Path) -> Result<(), String> {
+    let argv = vec![OsString::from("nexus-cli"), OsString::from("start")];
+    crate::start_settings::parse_layered::<P>(argv, &settings_path(config_path))
+        .map(|_| ())
+        .map_err(|e| e.to_string().trim().to_string())
+}
+
+/// Implements `config validate`: checks both files, reporting each, and fails if either is
+/// invalid. `P` is the command line `start` is parsed with.
+pub fn print_validation<P: Parser>(config_path: &Path) -> Result<(), Box<dyn Error>> {
+    let settings = settings_path(config_path);
+    let results = [
+        (config_path, validate_config(config_path)),
+        (settings.as_path(), validate_settings::<P>(config_path)),
+    ];
+    let mut valid = true;
+    for (path, result) in results {
+        match result {
+            Ok(()) if !path.exists() => println!("✅ {}: not created yet", path.display()),
+            Ok(()) => println!("✅ {}: valid", path.display()),
+            Err(e) => {
+                valid = false;
+                println!("❌ {}: {}", path.display(), e);
+            }
+        }
+    }
+    if !valid {
+        return Err("Invalid configuration; fix it with `nexus-cli config set` or by hand".into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    // Values are checked before they are written, and land in the file where they take effect.
+    fn test_set_and_get() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.json");
+
+        assert!(set(&config_path, "node-id", "12, x").is_err());
+        assert!(set(&config_path, "wallet-address", "0x123").is_err());
+        assert!(set(&config_path, "environment", "mainnet").is_err());
+        assert!(set(&config_path, "colour", "blue").is_err());
+        assert!(!config_path.exists());
+
+        set(&config_path, "node_id", "12, 34").unwrap();
+        set(&config_path, "environment", "Production").unwrap();
+        assert_eq!(
+            get(&config_path, "node-id").unwrap().as_deref(),
+            Some("12,34")
+        );
+        assert_eq!(
+            get(&config_path, "environment").unwrap().as_deref(),
+            Some("production")
+        );
+        let settings = fs::read_to_string(settings_path(&config_path)).unwrap();
+        assert_eq!(settings.trim(), "environment = \"production\"");
+
+        set(&config_path, "environment", "").unwrap();
+        assert_eq!(get(&config_path, "environment").unwrap(), None);
+    }
+
+    #[test]
+    // A config file that does not parse is reported and left alone.
+    fn test_corrupt_config_is_not_overwritten() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.json");
+        fs::write(&config_path, "{\"node_id\": \"1\",").unwrap();
+
+        assert!(validate_config(&config_path).is_err());
+        assert!(set(&config_path, "node-id", "2").is_err());
+        assert_eq!(
+            fs::read_to_string(&config_path).unwrap(),
+            "{\"node_id\": \"1\","
+        );
+    }
+}
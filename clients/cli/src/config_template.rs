@@ -0,0 +1,202 @@
+//! Config export and fleet templates
+//!
+//! Implements `nexus config export`, which prints the config file with its secrets stripped:
+//! user IDs, which act for their wallet on the orchestrator, become variables. With
+//! `--template`, values that differ per host become variables as well, so a fleet operator can
+//! keep one golden template and render a config for each host, e.g. with `envsubst`:
+//!
+//! - `${USER_ID}` and `${<WALLET>_USER_ID}`: the user ID of the primary and each named wallet
+//! - `${NODE_IDS}` and `${<WALLET>_NODE_IDS}`: their node IDs, comma-separated
+//! - `${HOSTNAME}`: wherever the machine's host name appears, e.g. in a statsd prefix
+//!
+//! Everything else (task filter, profiles, goals, metrics) is shared by the fleet as it is.
+
+use crate::config::Config;
+use serde_json::Value;
+use std::error::Error;
+use std::path::Path;
+
+/// An exported config and the variables it expects.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Export {
+    pub config: Value,
+    /// Variable names, in the order they appear
+    pub variables: Vec<String>,
+}
+
+impl Export {
+    /// Replaces a value with a variable named `name`.
+    fn parameterize(&mut self, value: &mut Value, name: String) {
+        *value = Value::String(format!("${{{}}}", name));
+        if !self.variables.contains(&name) {
+            self.variables.push(name);
+        }
+    }
+}
+
+/// A variable name for a wallet's value, e.g. `ALICE_NODE_IDS`.
+fn wallet_variable(wallet: &str, suffix: &str) -> String {
+    let wallet: String = wallet
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}_{}", wallet, suffix)
+}
+
+/// Replaces `hostname` in every string of `value`, returning whether it appeared at all.
+fn replace_hostname(value: &mut Value, hostname: &str) -> bool {
+    match value {
+        Value::String(s) if s.contains(hostname) => {
+            *s = s.replace(hostname, "${HOSTNAME}");
+            true
+        }
+        Value::Array(values) => values
+            .iter_mut()
+            .fold(false, |found, v| replace_hostname(v, hostname) | found),
+        Value::Object(map) => map
+            .values_mut()
+            .fold(false, |found, v| replace_hostname(v, hostname) | found),
+        _ => false,
+    }
+}
+
+/// Exports `config` without its secrets and, for a template, with host-specific values of the
+/// host named `hostname` as variables.
+///
+/// # Errors
+/// Returns a `serde_json::Error` if the config cannot be serialized.
+pub fn export(
+    config: &Config,
+    template: bool,
+    hostname: Option<&str>,
+) -> Result<Export, serde_json::Error> {
+    let mut export = Export {
+        config: serde_json::to_value(config)?,
+        variables: Vec::new(),
+    };
+    let mut json = std::mem::take(&mut export.config);
+    if template {
+        // Before any variable is inserted, so only the config's own values are matched
+        if let Some(hostname) = hostname.filter(|hostname| !hostname.is_empty()) {
+            if replace_hostname(&mut json, hostname) {
+                export.variables.push("HOSTNAME".to_string());
+            }
+        }
+    }
+    if !config.user_id.is_empty() {
+        export.parameterize(&mut json["user_id"], "USER_ID".to_string());
+    }
+    if template {
+        export.parameterize(&mut json["node_id"], "NODE_IDS".to_string());
+    }
+    for (i, wallet) in config.wallets.iter().enumerate() {
+        let entry = &mut json["wallets"][i];
+        let name = wallet_variable(&wallet.name, "USER_ID");
+        export.parameterize(&mut entry["user_id"], name);
+        if template {
+            let name = wallet_variable(&wallet.name, "NODE_IDS");
+            export.parameterize(&mut entry["node_ids"], name);
+        }
+    }
+    export.config = json;
+    Ok(export)
+}
+
+/// Prints the config file without its secrets, as a template if `template` is set. The
+/// variables to fill in are listed on stderr, so stdout can be redirected to a file.
+pub fn print_export(config_path: &Path, template: bool) -> Result<(), Box<dyn Error>> {
+    let config = Config::load_from_file(config_path)
+        .map_err(|e| format!("Cannot read {}: {}", config_path.display(), e))?;
+    let hostname = crate::system::host_name();
+    let export = export(&config, template, hostname.as_deref())?;
+    println!("{}", serde_json::to_string_pretty(&export.config)?);
+    if !export.variables.is_empty() {
+        let variables: Vec<String> = export
+            .variables
+            .iter()
+            .map(|name| format!("${{{}}}", name))
+            .collect();
+        eprintln!("Variables to fill in: {}", variables.join(", "));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::MetricsBackend;
+    use crate::wallets::Wallet;
+
+    fn config() -> Config {
+        Config::builder()
+            .user_id("user-1")
+            .wallet_address("0x1111111111111111111111111111111111111111")
+            .node_ids(&[11, 12])
+            .wallet(Wallet {
+                name: "alice-2".to_string(),
+                wallet_address: "0x2222222222222222222222222222222222222222".to_string(),
+                user_id: "user-2".to_string(),
+                node_ids: vec![21],
+            })
+            .metrics(MetricsBackend::Statsd {
+                addr: "127.0.0.1:8125".to_string(),
+                prefix: "nexus.prover-07".to_string(),
+            })
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    // A plain export only strips the user IDs; host-specific values stay.
+    fn test_export_strips_secrets() {
+        let export = export(&config(), false, Some("prover-07")).unwrap();
+        let json = export.config.to_string();
+        assert!(!json.contains("user-1") && !json.contains("user-2"));
+        assert_eq!(export.config["node_id"], "11,12");
+        assert_eq!(
+            export.config["wallet_address"],
+            "0x1111111111111111111111111111111111111111"
+        );
+        assert_eq!(export.variables, ["USER_ID", "ALICE_2_USER_ID"]);
+    }
+
+    #[test]
+    // A template renders back into a config that loads, with each host's own values.
+    fn test_template_renders_per_host() {
+        let export = export(&config(), true, Some("prover-07")).unwrap();
+        assert_eq!(
+            export.variables,
+            [
+                "HOSTNAME",
+                "USER_ID",
+                "NODE_IDS",
+                "ALICE_2_USER_ID",
+                "ALICE_2_NODE_IDS"
+            ]
+        );
+
+        let rendered = serde_json::to_string(&export.config)
+            .unwrap()
+            .replace("${HOSTNAME}", "prover-08")
+            .replace("${USER_ID}", "user-1")
+            .replace("${NODE_IDS}", "31,32")
+            .replace("${ALICE_2_USER_ID}", "user-2")
+            .replace("${ALICE_2_NODE_IDS}", "41");
+        let rendered: Config = serde_json::from_str(&rendered).unwrap();
+        assert_eq!(rendered.node_ids().unwrap(), vec![31, 32]);
+        assert_eq!(rendered.wallets[0].node_ids, vec![41]);
+        assert_eq!(
+            rendered.metrics,
+            MetricsBackend::Statsd {
+                addr: "127.0.0.1:8125".to_string(),
+                prefix: "nexus.prover-08".to_string(),
+            }
+        );
+    }
+}
@@ -6,7 +6,6 @@ pub mod prover {
 
     // Task fetching thresholds
     pub const BATCH_SIZE: usize = TASK_QUEUE_SIZE / 5; // Fetch this many tasks at once
-    pub const LOW_WATER_MARK: usize = TASK_QUEUE_SIZE / 4; // Fetch new tasks when queue drops below this
     pub const MAX_404S_BEFORE_GIVING_UP: usize = 5; // Allow several 404s before stopping batch fetch
     pub const BACKOFF_DURATION: u64 = 120000; // 120 seconds
     pub const QUEUE_LOG_INTERVAL: u64 = 60000; // 1 minute
@@ -0,0 +1,403 @@
+//! Container entrypoint
+//!
+//! `nexus-network run-container` is meant to be the entrypoint of a container image, for
+//! docker-compose and Kubernetes deployments. It takes its settings from environment variables
+//! instead of flags, prints them on startup, waits for DNS and the orchestrator with bounded
+//! retries (so a node started before the network is up does not crash-loop), registers the
+//! node if needed, and then runs `start --headless`.
+//!
+//! Restarts are idempotent as long as the state directory (`NEXUS_STATE_DIR`) is on a volume:
+//! registration is skipped once the config holds a node, pending submissions are replayed from
+//! the journal, and a control socket left behind by the previous container is replaced.
+
+use crate::config::Config;
+use crate::environment::Environment;
+use crate::memory_limit::MemoryLimit;
+use crate::orchestrator::OrchestratorClient;
+use crate::register::{register_node, register_user};
+use std::error::Error;
+use std::path::Path;
+use std::time::Duration;
+
+/// Attempts at reaching the orchestrator before giving up, unless `NEXUS_WAIT_RETRIES` is set.
+const DEFAULT_WAIT_RETRIES: u32 = 10;
+
+/// Delay before the second attempt; it doubles with every attempt.
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Longest delay between attempts.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Settings read from the environment.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ContainerSettings {
+    /// `NEXUS_NODE_ID`: node IDs, separated by commas or spaces
+    pub node_ids: Vec<u64>,
+    /// `NEXUS_WALLET_ADDRESS`: registers a user and a node if the config has none
+    pub wallet_address: Option<String>,
+    /// `NEXUS_STATE_DIR`: where config, journal and caches are kept (mount a volume here)
+    pub state_dir: Option<String>,
+    /// `NEXUS_ORCHESTRATOR_URL`
+    pub orchestrator_url: Option<String>,
+    /// `NEXUS_MAX_THREADS`
+    pub max_threads: Option<u32>,
+    /// `NEXUS_MAX_MEMORY_GB`, e.g. `6` on an 8 GB host
+    pub max_memory_gb: Option<String>,
+    /// `NEXUS_PROXY_FILE`
+    pub proxy_file: Option<String>,
+    /// `NEXUS_NO_PROXY`
+    pub no_proxy: bool,
+    /// `NEXUS_WEB_ADDR`, e.g. `0.0.0.0:3030`
+    pub web_addr: Option<String>,
+    /// `NEXUS_METRICS_ADDR`, e.g. `0.0.0.0:9090`
+    pub metrics_addr: Option<String>,
+    /// `NEXUS_CONTROL_LISTEN`, with `NEXUS_CONTROL_PSK_FILE`
+    pub control_listen: Option<String>,
+    /// `NEXUS_CONTROL_PSK_FILE`
+    pub control_psk_file: Option<String>,
+    /// `NEXUS_EXTRA_ARGS`: further `start` flags, separated by spaces
+    pub extra_args: Vec<String>,
+    /// `NEXUS_WAIT_RETRIES`
+    pub wait_retries: u32,
+}
+
+fn parse_flag(name: &str, value: &str) -> Result<bool, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err(format!("{} must be true or false, got '{}'", name, value)),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} must be a number, got '{}'", name, value))
+}
+
+impl ContainerSettings {
+    /// Reads the settings from the process environment.
+    pub fn from_env() -> Result<Self, String> {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    /// Reads the settings through `lookup`; empty variables count as unset.
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, String> {
+        let var = |name: &str| {
+            lookup(name)
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        let node_ids = var("NEXUS_NODE_ID")
+            .unwrap_or_default()
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|id| !id.is_empty())
+            .map(|id| parse_number("NEXUS_NODE_ID", id))
+            .collect::<Result<_, _>>()?;
+        let addr = |name: &str| -> Result<Option<String>, String> {
+            var(name)
+                .map(|value| {
+                    value
+                        .parse::<std::net::SocketAddr>()
+                        .map(|_| value.clone())
+                        .map_err(|_| format!("{} must be an address like 0.0.0.0:3030", name))
+                })
+                .transpose()
+        };
+        let settings = Self {
+            node_ids,
+            wallet_address: var("NEXUS_WALLET_ADDRESS"),
+            state_dir: var("NEXUS_STATE_DIR"),
+            orchestrator_url: var("NEXUS_ORCHESTRATOR_URL"),
+            max_threads: var("NEXUS_MAX_THREADS")
+                .map(|value| parse_number("NEXUS_MAX_THREADS", &value))
+                .transpose()?,
+            max_memory_gb: var("NEXUS_MAX_MEMORY_GB")
+                .map(|value| {
+                    MemoryLimit::parse(&value)
+                        .map(|_| value.clone())
+                        .map_err(|e| format!("NEXUS_MAX_MEMORY_GB: {}", e))
+                })
+                .transpose()?,
+            proxy_file: var("NEXUS_PROXY_FILE"),
+            no_proxy: var("NEXUS_NO_PROXY")
+                .map(|value| parse_flag("NEXUS_NO_PROXY", &value))
+                .transpose()?
+                .unwrap_or(false),
+            web_addr: addr("NEXUS_WEB_ADDR")?,
+            metrics_addr: addr("NEXUS_METRICS_ADDR")?,
+            control_listen: addr("NEXUS_CONTROL_LISTEN")?,
+            control_psk_file: var("NEXUS_CONTROL_PSK_FILE"),
+            extra_args: var("NEXUS_EXTRA_ARGS")
+                .unwrap_or_default()
+                .split_whitespace()
+                .map(str::to_string)
+                .collect(),
+            wait_retries: var("NEXUS_WAIT_RETRIES")
+                .map(|value| parse_number("NEXUS_WAIT_RETRIES", &value))
+                .transpose()?
+                .unwrap_or(DEFAULT_WAIT_RETRIES),
+        };
+        if settings.control_listen.is_some() && settings.control_psk_file.is_none() {
+            return Err("NEXUS_CONTROL_LISTEN needs NEXUS_CONTROL_PSK_FILE".to_string());
+        }
+        Ok(settings)
+    }
+
+    /// The command line equivalent to these settings, starting with the program name.
+    pub fn start_args(&self) -> Vec<String> {
+        let mut args = vec!["nexus-network".to_string()];
+        if let Some(dir) = &self.state_dir {
+            args.extend(["--sandbox".to_string(), dir.clone()]);
+        }
+        if let Some(file) = &self.control_psk_file {
+            args.extend(["--control-psk".to_string(), file.clone()]);
+        }
+        args.extend(["start".to_string(), "--headless".to_string()]);
+        for node_id in &self.node_ids {
+            args.extend(["--node-id".to_string(), node_id.to_string()]);
+        }
+        let options = [
+            ("--orchestrator-url", &self.orchestrator_url),
+            ("--max-threads", &self.max_threads.map(|n| n.to_string())),
+            ("--max-memory-gb", &self.max_memory_gb),
+            ("--proxy", &self.proxy_file),
+            ("--web-addr", &self.web_addr),
+            ("--metrics-addr", &self.metrics_addr),
+            ("--control-listen", &self.control_listen),
+        ];
+        for (flag, value) in options {
+            if let Some(value) = value {
+                args.extend([flag.to_string(), value.clone()]);
+            }
+        }
+        if self.no_proxy {
+            args.push("--no-proxy".to_string());
+        }
+        args.extend(self.extra_args.iter().cloned());
+        args
+    }
+
+    /// Prints the effective settings; the wallet address is shortened.
+    pub fn print_summary(&self) {
+        let wallet = self.wallet_address.as_deref().map(|wallet| {
+            if wallet.len() > 10 {
+                format!("{}…{}", &wallet[..6], &wallet[wallet.len() - 4..])
+            } else {
+                wallet.to_string()
+            }
+        });
+        let node_ids = self
+            .node_ids
+            .iter()
+            .map(u64::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        let unset = || "(not set)".to_string();
+        let rows = [
+            (
+                "NEXUS_NODE_ID",
+                Some(node_ids).filter(|ids| !ids.is_empty()),
+            ),
+            ("NEXUS_WALLET_ADDRESS", wallet),
+            ("NEXUS_STATE_DIR", self.state_dir.clone()),
+            ("NEXUS_ENVIRONMENT", std::env::var("NEXUS_ENVIRONMENT").ok()),
+            ("NEXUS_ORCHESTRATOR_URL", self.orchestrator_url.clone()),
+            ("NEXUS_MAX_THREADS", self.max_threads.map(|n| n.to_string())),
+            ("NEXUS_MAX_MEMORY_GB", self.max_memory_gb.clone()),
+            ("NEXUS_PROXY_FILE", self.proxy_file.clone()),
+            ("NEXUS_NO_PROXY", Some(self.no_proxy.to_string())),
+            ("NEXUS_WEB_ADDR", self.web_addr.clone()),
+            ("NEXUS_METRICS_ADDR", self.metrics_addr.clone()),
+            ("NEXUS_CONTROL_LISTEN", self.control_listen.clone()),
+            ("NEXUS_CONTROL_PSK_FILE", self.control_psk_file.clone()),
+            (
+                "NEXUS_EXTRA_ARGS",
+                Some(self.extra_args.join(" ")).filter(|a| !a.is_empty()),
+            ),
+            ("NEXUS_WAIT_RETRIES", Some(self.wait_retries.to_string())),
+        ];
+        println!("nexus-network {} (container)", env!("CARGO_PKG_VERSION"));
+        for (name, value) in rows {
+            println!("  {:<24} {}", name, value.unwrap_or_else(unset));
+        }
+    }
+
+    /// Waits for the orchestrator, then makes sure a node is registered.
+    pub async fn prepare(
+        &self,
+        config_path: &Path,
+        environment: &Environment,
+    ) -> Result<(), Box<dyn Error>> {
+        wait_for_orchestrator(environment.orchestrator_url(), self.wait_retries).await?;
+        self.ensure_registered(config_path, environment).await
+    }
+
+    async fn ensure_registered(
+        &self,
+        config_path: &Path,
+        environment: &Environment,
+    ) -> Result<(), Box<dyn Error>> {
+        if !self.node_ids.is_empty() {
+            return Ok(());
+        }
+        let config = Config::load_from_file(config_path).ok();
+        if let Some(config) = config.as_ref().filter(|config| !config.node_id.is_empty()) {
+            println!(
+                "Using node {} from {} (registered earlier)",
+                config.node_id,
+                config_path.display()
+            );
+            return Ok(());
+        }
+        let Some(wallet_address) = &self.wallet_address else {
+            return Err("Set NEXUS_NODE_ID, or NEXUS_WALLET_ADDRESS to register a node".into());
+        };
+        register_user(
+            wallet_address,
+            config_path,
+            Box::new(OrchestratorClient::new(environment.clone())),
+        )
+        .await?;
+        register_node(
+            None,
+            config_path,
+            Box::new(OrchestratorClient::new(environment.clone())),
+        )
+        .await?;
+        if self.state_dir.is_none() {
+            crate::logging::warn(
+                "⚠️  NEXUS_STATE_DIR is not set: mount a volume there, or a restarted container registers another node",
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Delay before attempt `attempt` (counting from 1).
+fn retry_delay(attempt: u32) -> Duration {
+    INITIAL_RETRY_DELAY
+        .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+        .min(MAX_RETRY_DELAY)
+}
+
+/// Resolves the orchestrator's host and sends it a request, retrying up to `retries` times.
+/// Any HTTP response counts as reachable.
+async fn wait_for_orchestrator(url: &str, retries: u32) -> Result<(), String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid URL {}: {}", url, e))?;
+    let host = parsed.host_str().unwrap_or_default().to_string();
+    let port = parsed.port_or_known_default().unwrap_or(443);
+    let client = crate::orchestrator::tls::apply(crate::orchestrator::doh::apply(
+        reqwest::Client::builder().timeout(Duration::from_secs(10)),
+    ))
+    .build()
+    .map_err(|e| e.to_string())?;
+
+    let mut last_error = String::new();
+    for attempt in 1..=retries.max(1) {
+        if attempt > 1 {
+            tokio::time::sleep(retry_delay(attempt - 1)).await;
+        }
+        // With DNS-over-HTTPS the system resolver is not used, so only the request is checked
+        if crate::orchestrator::doh::doh_resolver().is_none() {
+            if let Err(e) = tokio::net::lookup_host((host.as_str(), port)).await {
+                last_error = format!("DNS lookup of {} failed: {}", host, e);
+                crate::logging::warn(format!(
+                    "⏳ Waiting for DNS ({}/{}): {}",
+                    attempt, retries, last_error
+                ));
+                continue;
+            }
+        }
+        match client.head(url).send().await {
+            Ok(_) => {
+                println!("✅ Orchestrator reachable at {}", url);
+                return Ok(());
+            }
+            Err(e) => {
+                last_error = crate::session::redact(&e.to_string());
+                crate::logging::warn(format!(
+                    "⏳ Waiting for the orchestrator ({}/{}): {}",
+                    attempt, retries, last_error
+                ));
+            }
+        }
+    }
+    Err(format!(
+        "Orchestrator at {} unreachable after {} attempts: {}",
+        url, retries, last_error
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn settings(vars: &[(&str, &str)]) -> Result<ContainerSettings, String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        ContainerSettings::from_lookup(|name| vars.get(name).cloned())
+    }
+
+    #[test]
+    // Environment variables map onto the equivalent `start` command line.
+    fn test_start_args_from_env() {
+        let settings = settings(&[
+            ("NEXUS_NODE_ID", "12, 34"),
+            ("NEXUS_STATE_DIR", "/data"),
+            ("NEXUS_MAX_THREADS", "4"),
+            ("NEXUS_MAX_MEMORY_GB", "6.5"),
+            ("NEXUS_NO_PROXY", "true"),
+            ("NEXUS_WEB_ADDR", ""),
+            ("NEXUS_METRICS_ADDR", "0.0.0.0:9090"),
+            ("NEXUS_EXTRA_ARGS", "--record /data/session.ndjson"),
+        ])
+        .unwrap();
+        assert_eq!(
+            settings.start_args(),
+            vec![
+                "nexus-network",
+                "--sandbox",
+                "/data",
+                "start",
+                "--headless",
+                "--node-id",
+                "12",
+                "--node-id",
+                "34",
+                "--max-threads",
+                "4",
+                "--max-memory-gb",
+                "6.5",
+                "--metrics-addr",
+                "0.0.0.0:9090",
+                "--no-proxy",
+                "--record",
+                "/data/session.ndjson",
+            ]
+        );
+        assert_eq!(settings.wait_retries, DEFAULT_WAIT_RETRIES);
+    }
+
+    #[test]
+    // Malformed values are reported with the variable's name.
+    fn test_invalid_env_rejected() {
+        let error = settings(&[("NEXUS_NODE_ID", "12,abc")]).unwrap_err();
+        assert!(error.contains("NEXUS_NODE_ID"));
+        assert!(settings(&[("NEXUS_NO_PROXY", "maybe")]).is_err());
+        assert!(settings(&[("NEXUS_MAX_MEMORY_GB", "0")]).is_err());
+        assert!(settings(&[("NEXUS_WEB_ADDR", "localhost")]).is_err());
+        assert!(settings(&[("NEXUS_CONTROL_LISTEN", "0.0.0.0:4000")]).is_err());
+    }
+
+    #[test]
+    // Retry delays double up to the cap.
+    fn test_retry_delay() {
+        assert_eq!(retry_delay(1), Duration::from_secs(2));
+        assert_eq!(retry_delay(3), Duration::from_secs(8));
+        assert_eq!(retry_delay(10), MAX_RETRY_DELAY);
+    }
+}
@@ -0,0 +1,716 @@
+//! Control plane
+//!
+//! A running node accepts control commands (status, pause, resume, drain, reload, log level,
+//! proxy statistics)
+//! from other processes on the same machine, e.g. `nexus-network status` or
+//! `nexus-network log-level debug`. The
+//! transport is a unix domain socket (`~/.nexus/control.sock`) on Linux and macOS, and a named
+//! pipe on Windows.
+//!
+//! Every message is a JSON document prefixed with its length as a big-endian `u32`. Requests
+//! carry a token that the node writes to `~/.nexus/control.token` on startup, so only the
+//! user running the node can control it. Nodes on other machines are controlled over TCP
+//! instead; see `remote_control`.
+
+use crate::environment::Environment;
+use crate::error_classifier::LogLevel;
+use crate::pretty::print_cmd_info;
+use crate::proxy::ProxyContext;
+use crate::proxy_stats::ProxyStats;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+/// Largest accepted response. Control messages are tiny, except proxy statistics, which grow
+/// with the proxy file.
+const MAX_MESSAGE_SIZE: usize = 4 * 1024 * 1024;
+
+/// Largest accepted request: a token and a command. Requests are read before the token is
+/// checked, so anyone who can reach the socket could otherwise make the node allocate
+/// `MAX_MESSAGE_SIZE` per connection.
+const MAX_REQUEST_SIZE: usize = 512;
+
+/// Named pipe used on Windows.
+#[cfg(windows)]
+const PIPE_NAME: &str = r"\\.\pipe\nexus-network-control";
+
+/// A control command.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Command {
+    /// Report the node's state.
+    Status,
+    /// Stop fetching new tasks; queued and in-progress tasks still finish.
+    Pause,
+    /// Resume fetching after a pause.
+    Resume,
+    /// Stop fetching, finish outstanding tasks, then shut down.
+    Drain,
+    /// Stop fetching, finish outstanding tasks, then re-execute the binary, keeping the session
+    /// (see `handoff`).
+    Handoff,
+    /// Re-read the proxy file.
+    Reload,
+    /// Change the log level.
+    LogLevel(LogLevel),
+    /// Report request statistics per proxy.
+    ProxyStats,
+}
+
+/// What a control token may do, from least to most.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// Only commands that report the node's state
+    Read,
+    /// Every command
+    Control,
+}
+
+impl Command {
+    /// The scope a token needs to send this command.
+    pub fn scope(self) -> Scope {
+        match self {
+            Command::Status | Command::ProxyStats => Scope::Read,
+            _ => Scope::Control,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Request {
+    token: String,
+    command: Command,
+}
+
+/// State of a running node, as reported by the `status` command.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RunningStatus {
+    pub pid: u32,
+    pub version: String,
+    /// Label of the environment the node works for; unknown for earlier releases.
+    #[serde(default)]
+    pub environment: Option<String>,
+    /// Seed of the node's scheduling randomness (see `rng`); unknown for earlier releases.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    pub uptime_secs: u64,
+    pub paused: bool,
+    pub draining: bool,
+    #[serde(default)]
+    pub handing_off: bool,
+    /// Tasks fetched but not yet proved and submitted.
+    pub tasks_in_flight: usize,
+}
+
+/// Reply to a control command.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Response {
+    pub ok: bool,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<RunningStatus>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_stats: Option<Vec<ProxyStats>>,
+}
+
+impl Response {
+    fn ok(message: impl Into<String>) -> Self {
+        Self {
+            ok: true,
+            message: message.into(),
+            status: None,
+            proxy_stats: None,
+        }
+    }
+
+    pub(crate) fn error(message: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            message: message.into(),
+            status: None,
+            proxy_stats: None,
+        }
+    }
+}
+
+/// Process-wide control state, consulted by the workers.
+#[derive(Debug)]
+pub struct ControlState {
+    started_at: Instant,
+    paused: AtomicBool,
+    draining: AtomicBool,
+    handing_off: AtomicBool,
+    /// Uptime of the processes this one took over from (see `handoff`)
+    resumed_uptime: AtomicU64,
+    in_flight: AtomicUsize,
+    worker_limit: AtomicUsize,
+    /// The proxies `reload` re-reads, once the node has set them up
+    proxies: OnceLock<Arc<ProxyContext>>,
+    /// Label of the environment the node works for
+    environment: OnceLock<String>,
+}
+
+impl ControlState {
+    fn new() -> Self {
+        Self {
+            started_at: Instant::now(),
+            paused: AtomicBool::new(false),
+            draining: AtomicBool::new(false),
+            handing_off: AtomicBool::new(false),
+            resumed_uptime: AtomicU64::new(0),
+            in_flight: AtomicUsize::new(0),
+            worker_limit: AtomicUsize::new(usize::MAX),
+            proxies: OnceLock::new(),
+            environment: OnceLock::new(),
+        }
+    }
+
+    /// Sets the environment reported by `status`. Only the first call has an effect.
+    pub fn set_environment(&self, environment: &Environment) {
+        let _ = self.environment.set(environment.label());
+    }
+
+    /// Sets the proxies the `reload` command re-reads. Only the first call has an effect.
+    pub fn attach_proxies(&self, proxies: Arc<ProxyContext>) {
+        let _ = self.proxies.set(proxies);
+    }
+
+    /// Whether task fetchers may request new tasks.
+    pub fn allow_fetch(&self) -> bool {
+        !self.paused.load(Ordering::Relaxed)
+            && !self.draining.load(Ordering::Relaxed)
+            && !self.handing_off.load(Ordering::Relaxed)
+    }
+
+    /// Whether fetching was paused with the `pause` command.
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// How long this session has been running, including processes it took over from.
+    pub fn uptime(&self) -> std::time::Duration {
+        self.started_at.elapsed()
+            + std::time::Duration::from_secs(self.resumed_uptime.load(Ordering::Relaxed))
+    }
+
+    /// Continues a session handed over by an earlier process.
+    pub fn resume_session(&self, uptime: std::time::Duration, paused: bool) {
+        self.resumed_uptime
+            .store(uptime.as_secs(), Ordering::Relaxed);
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    /// Records that a task was queued for proving.
+    pub fn task_started(&self) {
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Tasks queued for proving that have not left the pipeline yet.
+    pub fn tasks_in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+
+    /// Records that a task left the pipeline (submitted, rejected or failed).
+    pub fn task_finished(&self) {
+        let _ = self
+            .in_flight
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+                Some(n.saturating_sub(1))
+            });
+    }
+
+    /// How many provers may work at once (unlimited unless a resource profile is active).
+    pub fn worker_limit(&self) -> usize {
+        self.worker_limit.load(Ordering::Relaxed)
+    }
+
+    /// Limits how many provers may work at once.
+    pub fn set_worker_limit(&self, limit: usize) {
+        self.worker_limit.store(limit, Ordering::Relaxed);
+    }
+
+    /// Whether a drain was requested and every outstanding task has finished.
+    fn is_drained(&self) -> bool {
+        self.draining.load(Ordering::Relaxed) && self.in_flight.load(Ordering::Relaxed) == 0
+    }
+
+    /// Whether a handoff was requested and every outstanding task has finished.
+    fn is_ready_for_handoff(&self) -> bool {
+        self.handing_off.load(Ordering::Relaxed) && self.in_flight.load(Ordering::Relaxed) == 0
+    }
+
+    pub fn status(&self) -> RunningStatus {
+        RunningStatus {
+            pid: std::process::id(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            environment: self.environment.get().cloned(),
+            seed: Some(crate::rng::seed()),
+            uptime_secs: self.uptime().as_secs(),
+            paused: self.paused.load(Ordering::Relaxed),
+            draining: self.draining.load(Ordering::Relaxed),
+            handing_off: self.handing_off.load(Ordering::Relaxed),
+            tasks_in_flight: self.in_flight.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Applies a command and describes the result.
+    pub(crate) fn apply(&self, command: Command) -> Response {
+        match command {
+            Command::Status => Response {
+                status: Some(self.status()),
+                ..Response::ok("Node is running")
+            },
+            Command::Pause => {
+                self.paused.store(true, Ordering::Relaxed);
+                Response::ok("Paused: no new tasks will be fetched")
+            }
+            Command::Resume => {
+                if self.draining.load(Ordering::Relaxed) {
+                    return Response::error("Node is draining and cannot be resumed");
+                }
+                if self.handing_off.load(Ordering::Relaxed) {
+                    return Response::error("Node is handing off and cannot be resumed");
+                }
+                self.paused.store(false, Ordering::Relaxed);
+                Response::ok("Resumed fetching tasks")
+            }
+            Command::Drain => {
+                self.draining.store(true, Ordering::Relaxed);
+                Response::ok(format!(
+                    "Draining: the node exits after {} outstanding tasks finish",
+                    self.in_flight.load(Ordering::Relaxed)
+                ))
+            }
+            Command::Handoff => {
+                if !crate::handoff::supported() {
+                    return Response::error("Handoff is only available on Unix");
+                }
+                if self.draining.load(Ordering::Relaxed) {
+                    return Response::error("Node is draining and will exit instead");
+                }
+                self.handing_off.store(true, Ordering::Relaxed);
+                Response::ok(format!(
+                    "Handing off: the node restarts from its binary after {} outstanding tasks finish",
+                    self.in_flight.load(Ordering::Relaxed)
+                ))
+            }
+            Command::Reload => {
+                let reloaded = self
+                    .proxies
+                    .get()
+                    .ok_or_else(|| "the node is not using proxies".to_string())
+                    .and_then(|proxies| proxies.reload());
+                match reloaded {
+                    Ok(count) => Response::ok(format!("Reloaded {} proxies", count)),
+                    Err(e) => Response::error(format!("Proxy reload failed: {}", e)),
+                }
+            }
+            Command::LogLevel(level) => {
+                crate::logging::set_log_level(level);
+                Response::ok(format!("Log level set to {}", level))
+            }
+            Command::ProxyStats => match self.proxies.get() {
+                Some(proxies) => Response {
+                    status: Some(self.status()),
+                    proxy_stats: Some(proxies.stats()),
+                    ..Response::ok("Proxy statistics")
+                },
+                None => Response::error("The node is not using proxies"),
+            },
+        }
+    }
+}
+
+static CONTROL_STATE: OnceLock<ControlState> = OnceLock::new();
+
+/// The control state of this process.
+pub fn control_state() -> &'static ControlState {
+    CONTROL_STATE.get_or_init(ControlState::new)
+}
+
+/// Path of the token that authorizes control commands, next to the config file.
+pub fn get_token_path() -> Result<PathBuf, std::io::Error> {
+    Ok(crate::config::get_config_path()?.with_file_name("control.token"))
+}
+
+/// Path of the control socket, next to the config file.
+#[cfg(unix)]
+fn get_socket_path() -> Result<PathBuf, std::io::Error> {
+    Ok(crate::config::get_config_path()?.with_file_name("control.sock"))
+}
+
+/// Writes a fresh random token to `path`, readable only by the current user.
+pub(crate) fn create_token(path: &Path) -> std::io::Result<String> {
+    use rand::Rng;
+    let token: String = rand::thread_rng()
+        .r#gen::<[u8; 16]>()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // Written aside, created private so no other user can read it even briefly, and renamed
+    // over any earlier token
+    let partial = path.with_extension("partial");
+    let _ = std::fs::remove_file(&partial);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&partial)?;
+    std::io::Write::write_all(&mut file, token.as_bytes())?;
+    drop(file);
+    std::fs::rename(partial, path)?;
+    Ok(token)
+}
+
+/// Whether a presented token is `token`, compared in constant time so the comparison does not
+/// tell how much of it was right.
+pub(crate) fn token_matches(presented: &str, token: &str) -> bool {
+    presented.len() == token.len()
+        && presented
+            .bytes()
+            .zip(token.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+async fn write_json<W: AsyncWrite + Unpin, T: Serialize>(
+    writer: &mut W,
+    value: &T,
+) -> std::io::Result<()> {
+    let bytes = serde_json::to_vec(value)?;
+    writer.write_u32(bytes.len() as u32).await?;
+    writer.write_all(&bytes).await?;
+    writer.flush().await
+}
+
+/// Reads a message of at most `limit` bytes.
+async fn read_json<R: AsyncRead + Unpin, T: for<'de> Deserialize<'de>>(
+    reader: &mut R,
+    limit: usize,
+) -> std::io::Result<T> {
+    let len = reader.read_u32().await? as usize;
+    if len > limit {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("message of {} bytes exceeds the limit", len),
+        ));
+    }
+    let mut bytes = vec![0u8; len];
+    reader.read_exact(&mut bytes).await?;
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
+/// Answers one request on a connection.
+async fn handle_connection<S: AsyncRead + AsyncWrite + Unpin>(
+    mut stream: S,
+    token: &str,
+) -> std::io::Result<()> {
+    let request: Request = read_json(&mut stream, MAX_REQUEST_SIZE).await?;
+    let response = if !token_matches(&request.token, token) {
+        Response::error("Invalid control token")
+    } else {
+        control_state().apply(request.command)
+    };
+    write_json(&mut stream, &response).await
+}
+
+/// Starts accepting control commands, and shuts the node down once a drain or handoff
+/// completes.
+///
+/// # Errors
+/// Returns an `std::io::Error` if another node on this machine already accepts control
+/// commands, or if the socket cannot be bound or the token cannot be written.
+pub fn start_control_server(
+    shutdown_sender: broadcast::Sender<()>,
+) -> std::io::Result<JoinHandle<()>> {
+    let server = serve(shutdown_sender.subscribe())?;
+
+    // Watch for a completed drain
+    let drain_shutdown = shutdown_sender.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(1));
+        loop {
+            interval.tick().await;
+            if control_state().is_drained() {
+                let _ = drain_shutdown.send(());
+                break;
+            }
+            if control_state().is_ready_for_handoff() {
+                crate::handoff::mark_ready();
+                let _ = drain_shutdown.send(());
+                break;
+            }
+        }
+    });
+
+    Ok(server)
+}
+
+/// Binds the control socket at `path`, replacing one left behind by a crashed node.
+///
+/// # Errors
+/// Returns an `std::io::Error` of kind `AddrInUse` if another node is listening on it.
+#[cfg(unix)]
+fn bind_socket(path: &Path) -> std::io::Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::PermissionsExt;
+
+    if path.exists() {
+        // A live socket belongs to another node, which keeps it
+        if std::os::unix::net::UnixStream::connect(path).is_ok() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AddrInUse,
+                format!("another node is listening on {}", path.display()),
+            ));
+        }
+        // A socket left behind by a crashed node would make binding fail
+        std::fs::remove_file(path)?;
+    }
+    let listener = tokio::net::UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+/// Binds the control socket, then writes a fresh token, so a node that cannot take over the
+/// socket leaves the token of the node holding it alone.
+#[cfg(unix)]
+fn serve(mut shutdown: broadcast::Receiver<()>) -> std::io::Result<JoinHandle<()>> {
+    let path = get_socket_path()?;
+    let listener = bind_socket(&path)?;
+    let token = create_token(&get_token_path()?)?;
+
+    Ok(tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = shutdown.recv() => break,
+                accepted = listener.accept() => {
+                    if let Ok((stream, _)) = accepted {
+                        let token = token.clone();
+                        tokio::spawn(async move {
+                            let _ = handle_connection(stream, &token).await;
+                        });
+                    }
+                }
+            }
+        }
+        let _ = std::fs::remove_file(&path);
+    }))
+}
+
+/// Creates the control pipe, then writes a fresh token, so a node that cannot take over the
+/// pipe leaves the token of the node holding it alone.
+#[cfg(windows)]
+fn serve(mut shutdown: broadcast::Receiver<()>) -> std::io::Result<JoinHandle<()>> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    // Fails if another node already holds the pipe
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .create(PIPE_NAME)?;
+    let token = create_token(&get_token_path()?)?;
+
+    Ok(tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = shutdown.recv() => break,
+                connected = server.connect() => {
+                    if connected.is_err() {
+                        continue;
+                    }
+                    // Hand the connected instance off and create the next one
+                    let Ok(next) = ServerOptions::new().create(PIPE_NAME) else {
+                        break;
+                    };
+                    let stream = std::mem::replace(&mut server, next);
+                    let token = token.clone();
+                    tokio::spawn(async move {
+                        let _ = handle_connection(stream, &token).await;
+                    });
+                }
+            }
+        }
+    }))
+}
+
+/// Sends a command to the node running on this machine, or to the `--remote` node.
+///
+/// # Errors
+/// Returns an `std::io::Error` if no node is running or the token cannot be read.
+pub async fn send_command(command: Command) -> std::io::Result<Response> {
+    if crate::remote_control::remote_addr().is_some() {
+        return crate::remote_control::send_command(command).await;
+    }
+    let token = std::fs::read_to_string(get_token_path()?)?;
+    let request = Request {
+        token: token.trim().to_string(),
+        command,
+    };
+
+    #[cfg(unix)]
+    let mut stream = tokio::net::UnixStream::connect(get_socket_path()?).await?;
+    #[cfg(windows)]
+    let mut stream = tokio::net::windows::named_pipe::ClientOptions::new().open(PIPE_NAME)?;
+
+    write_json(&mut stream, &request).await?;
+    read_json(&mut stream, MAX_MESSAGE_SIZE).await
+}
+
+/// Runs a control command from the command line, printing the node's reply.
+pub async fn run_cli_command(command: Command) -> Result<(), Box<dyn std::error::Error>> {
+    let response =
+        send_command(command)
+            .await
+            .map_err(|e| match crate::remote_control::remote_addr() {
+                Some(addr) => format!("Could not control the node at {}: {}", addr, e),
+                None => format!(
+                    "No running node found ({}). Start one with `nexus-network start`.",
+                    e
+                ),
+            })?;
+    if !response.ok {
+        return Err(response.message.into());
+    }
+    print_cmd_info!("Node", "{}", response.message);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Pause and drain should stop fetching; a drained node has no outstanding tasks.
+    fn test_commands_update_state() {
+        let state = ControlState::new();
+        assert!(state.allow_fetch());
+
+        assert!(state.apply(Command::Pause).ok);
+        assert!(!state.allow_fetch());
+        assert!(state.apply(Command::Resume).ok);
+        assert!(state.allow_fetch());
+
+        state.task_started();
+        assert!(state.apply(Command::Drain).ok);
+        assert!(!state.allow_fetch());
+        assert!(!state.is_drained());
+        assert!(!state.apply(Command::Resume).ok);
+        state.task_finished();
+        state.task_finished(); // Finishing more tasks than started must not underflow
+        assert!(state.is_drained());
+
+        let status = state.apply(Command::Status).status.unwrap();
+        assert!(status.draining);
+        assert_eq!(status.tasks_in_flight, 0);
+    }
+
+    #[test]
+    // A handoff should stop fetching, wait for outstanding tasks and carry the session over.
+    fn test_handoff_and_resumed_session() {
+        let state = ControlState::new();
+        state.task_started();
+        let response = state.apply(Command::Handoff);
+        if !crate::handoff::supported() {
+            assert!(!response.ok);
+            return;
+        }
+        assert!(response.ok);
+        assert!(!state.allow_fetch());
+        assert!(!state.is_ready_for_handoff());
+        assert!(!state.apply(Command::Resume).ok);
+        state.task_finished();
+        assert!(state.is_ready_for_handoff());
+        assert!(!state.is_drained());
+
+        let resumed = ControlState::new();
+        resumed.resume_session(std::time::Duration::from_secs(600), true);
+        assert!(resumed.is_paused());
+        assert!(resumed.apply(Command::Status).status.unwrap().uptime_secs >= 600);
+    }
+
+    #[tokio::test]
+    // Requests with the wrong token should be refused.
+    async fn test_connection_checks_token() {
+        let (mut client, server) = tokio::io::duplex(1024);
+        let handle = tokio::spawn(async move { handle_connection(server, "secret").await });
+        write_json(
+            &mut client,
+            &Request {
+                token: "wrong".to_string(),
+                command: Command::Pause,
+            },
+        )
+        .await
+        .unwrap();
+        let response: Response = read_json(&mut client, MAX_MESSAGE_SIZE).await.unwrap();
+        assert!(!response.ok);
+        handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    // Oversized requests are refused before anything is allocated for them.
+    async fn test_oversized_request_is_refused() {
+        let (mut client, server) = tokio::io::duplex(1024);
+        let handle = tokio::spawn(async move { handle_connection(server, "secret").await });
+        client.write_u32(MAX_MESSAGE_SIZE as u32).await.unwrap();
+        let error = handle.await.unwrap().unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    // A token file should be private to its user, and a new token should replace the old one.
+    fn test_create_token_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("control.token");
+        let first = create_token(&path).unwrap();
+        let second = create_token(&path).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), second);
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    // Tokens should match only in full.
+    fn test_token_matches() {
+        assert!(token_matches("secret", "secret"));
+        assert!(!token_matches("secreT", "secret"));
+        assert!(!token_matches("secret-and-more", "secret"));
+        assert!(!token_matches("", "secret"));
+    }
+
+    #[test]
+    // The log level command should carry its level over the wire.
+    fn test_log_level_command_encoding() {
+        let command = Command::LogLevel(LogLevel::Debug);
+        let json = serde_json::to_string(&command).unwrap();
+        assert_eq!(json, r#"{"log_level":"debug"}"#);
+        assert_eq!(serde_json::from_str::<Command>(&json).unwrap(), command);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    // A live socket should be left to its node; a stale one should be replaced.
+    async fn test_bind_socket_refuses_live_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("control.sock");
+        let listener = bind_socket(&path).unwrap();
+        let err = bind_socket(&path).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::AddrInUse);
+
+        drop(listener);
+        assert!(bind_socket(&path).is_ok());
+    }
+}
@@ -0,0 +1,271 @@
+//! Task difficulty negotiation
+//!
+//! Proof tasks are requested with the largest difficulty bucket the node wants to handle
+//! (small, medium or large). Asking for large tasks on any hardware left slow machines with
+//! tasks that expired before they were proved. Once this machine is benchmarked with
+//! `nexus-cli benchmark`, the bucket follows how fast it proves:
+//!
+//! - at startup, from the saved benchmark: a known-answer proof (see `self_test`) within the
+//!   large threshold asks for large tasks, within the medium one for medium tasks, and anything
+//!   slower for small ones;
+//! - as proofs complete: a proof taking more than `SLOW_SHARE` of a task's lifetime steps down
+//!   a bucket, and `FAST_STREAK` proofs in a row within `FAST_SHARE` of it step back up.
+//!
+//! The thresholds are set when benchmarking (`--large-within`, `--medium-within`) and saved
+//! with the result. Their defaults are rough starting points, not calibrated against the
+//! orchestrator's buckets: the known-answer program is far smaller than real tasks, so tune
+//! them to the proving times `nexus-cli stats --programs` shows. Without a benchmark, nothing
+//! is negotiated and large tasks are requested, as before.
+//!
+//! The orchestrator only learns the bucket, as the `max_difficulty` of each task request; the
+//! benchmark itself is not reported. Fetchers and simulated provers keep asking for large
+//! tasks.
+
+use crate::nexus_orchestrator::TaskDifficulty;
+use crate::submission_queue::TASK_LIFETIME;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Default slowest known-answer proof that asks for large tasks, in seconds.
+pub const DEFAULT_LARGE_WITHIN_SECS: f64 = 5.0;
+
+/// Default slowest known-answer proof that asks for medium tasks, in seconds.
+pub const DEFAULT_MEDIUM_WITHIN_SECS: f64 = 20.0;
+
+/// Share of a task's lifetime beyond which a proof is too slow for its bucket.
+const SLOW_SHARE: f64 = 0.5;
+
+/// Share of a task's lifetime within which a proof counts toward stepping up.
+const FAST_SHARE: f64 = 0.1;
+
+/// Fast proofs in a row that step up a bucket.
+const FAST_STREAK: u32 = 5;
+
+/// Lower-case name of a difficulty bucket, e.g. `medium`.
+pub fn name(difficulty: TaskDifficulty) -> String {
+    difficulty.as_str_name().to_lowercase()
+}
+
+/// How fast this machine proves, saved next to the config file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Benchmark {
+    /// Time to prove and check the known-answer task, in seconds.
+    pub proof_secs: f64,
+
+    /// When the benchmark ran (RFC 3339).
+    pub benchmarked_at: String,
+
+    /// Slowest proof that asks for large tasks, in seconds.
+    #[serde(default = "default_large_within_secs")]
+    pub large_within_secs: f64,
+
+    /// Slowest proof that asks for medium tasks, in seconds.
+    #[serde(default = "default_medium_within_secs")]
+    pub medium_within_secs: f64,
+}
+
+fn default_large_within_secs() -> f64 {
+    DEFAULT_LARGE_WITHIN_SECS
+}
+
+fn default_medium_within_secs() -> f64 {
+    DEFAULT_MEDIUM_WITHIN_SECS
+}
+
+impl Benchmark {
+    /// A benchmark whose known-answer proof took `proof`, judged against the thresholds in
+    /// seconds for large and medium tasks.
+    pub fn new(proof: Duration, large_within_secs: f64, medium_within_secs: f64) -> Self {
+        Self {
+            proof_secs: proof.as_secs_f64(),
+            benchmarked_at: chrono::Utc::now().to_rfc3339(),
+            large_within_secs,
+            medium_within_secs,
+        }
+    }
+
+    /// The largest difficulty this machine should start out requesting.
+    pub fn difficulty(&self) -> TaskDifficulty {
+        if self.proof_secs <= self.large_within_secs {
+            TaskDifficulty::Large
+        } else if self.proof_secs <= self.medium_within_secs {
+            TaskDifficulty::Medium
+        } else {
+            TaskDifficulty::Small
+        }
+    }
+
+    /// Loads the last saved benchmark.
+    ///
+    /// # Errors
+    /// Returns an `std::io::Error` if the file cannot be read or parsed.
+    pub fn load_from_file(path: &Path) -> Result<Self, std::io::Error> {
+        let buf = fs::read(path)?;
+        serde_json::from_slice(&buf)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    /// Saves the benchmark, creating parent directories as needed.
+    ///
+    /// # Errors
+    /// Returns an `std::io::Error` if the file cannot be written.
+    pub fn save(&self, path: &Path) -> Result<(), std::io::Error> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        fs::write(path, json)
+    }
+}
+
+/// Path to the last benchmark, next to the config file.
+pub fn benchmark_path(config_path: &Path) -> PathBuf {
+    config_path.with_file_name("benchmark.json")
+}
+
+fn step_down(difficulty: TaskDifficulty) -> TaskDifficulty {
+    match difficulty {
+        TaskDifficulty::Large => TaskDifficulty::Medium,
+        _ => TaskDifficulty::Small,
+    }
+}
+
+fn step_up(difficulty: TaskDifficulty) -> TaskDifficulty {
+    match difficulty {
+        TaskDifficulty::Small => TaskDifficulty::Medium,
+        _ => TaskDifficulty::Large,
+    }
+}
+
+/// The difficulty requested, adjusted as proofs complete.
+#[derive(Debug)]
+struct Negotiator {
+    difficulty: TaskDifficulty,
+    /// Whether proofs adjust the difficulty
+    adjusting: bool,
+    fast_streak: u32,
+}
+
+impl Negotiator {
+    /// Adjusts the difficulty to a proof that took `duration`, returning it if it changed.
+    fn record_proof(&mut self, duration: Duration) -> Option<TaskDifficulty> {
+        if !self.adjusting {
+            return None;
+        }
+        let share = duration.as_secs_f64() / TASK_LIFETIME.as_secs_f64();
+        let adjusted = if share > SLOW_SHARE {
+            self.fast_streak = 0;
+            step_down(self.difficulty)
+        } else if share <= FAST_SHARE {
+            self.fast_streak += 1;
+            if self.fast_streak < FAST_STREAK {
+                return None;
+            }
+            self.fast_streak = 0;
+            step_up(self.difficulty)
+        } else {
+            self.fast_streak = 0;
+            return None;
+        };
+        if adjusted == self.difficulty {
+            return None;
+        }
+        self.difficulty = adjusted;
+        Some(adjusted)
+    }
+}
+
+static NEGOTIATOR: Mutex<Negotiator> = Mutex::new(Negotiator {
+    difficulty: TaskDifficulty::Large,
+    adjusting: false,
+    fast_streak: 0,
+});
+
+/// Requests tasks up to `difficulty` from now on, adjusted as proofs complete.
+pub fn start_at(difficulty: TaskDifficulty) {
+    if let Ok(mut negotiator) = NEGOTIATOR.lock() {
+        negotiator.difficulty = difficulty;
+        negotiator.adjusting = true;
+        negotiator.fast_streak = 0;
+    }
+}
+
+/// The largest difficulty to request tasks with.
+pub fn requested() -> TaskDifficulty {
+    NEGOTIATOR
+        .lock()
+        .map(|negotiator| negotiator.difficulty)
+        .unwrap_or(TaskDifficulty::Large)
+}
+
+/// Adjusts the requested difficulty to a proof that took `duration`. Returns the new difficulty
+/// if it changed.
+pub fn record_proof(duration: Duration) -> Option<TaskDifficulty> {
+    NEGOTIATOR.lock().ok()?.record_proof(duration)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Faster benchmarks ask for larger tasks, by the thresholds saved with them.
+    fn test_benchmark_difficulty() {
+        let difficulty = |secs: u64| {
+            Benchmark::new(
+                Duration::from_secs(secs),
+                DEFAULT_LARGE_WITHIN_SECS,
+                DEFAULT_MEDIUM_WITHIN_SECS,
+            )
+            .difficulty()
+        };
+        assert_eq!(difficulty(2), TaskDifficulty::Large);
+        assert_eq!(difficulty(12), TaskDifficulty::Medium);
+        assert_eq!(difficulty(60), TaskDifficulty::Small);
+        assert_eq!(
+            Benchmark::new(Duration::from_secs(12), 15.0, 30.0).difficulty(),
+            TaskDifficulty::Large
+        );
+
+        // Benchmarks saved before the thresholds were configurable use the defaults
+        let saved: Benchmark =
+            serde_json::from_str(r#"{"proof_secs":12.0,"benchmarked_at":"2026-10-01T00:00:00Z"}"#)
+                .unwrap();
+        assert_eq!(saved.difficulty(), TaskDifficulty::Medium);
+    }
+
+    #[test]
+    // A slow proof steps down at once; only a streak of fast proofs steps back up.
+    fn test_negotiation_follows_proofs() {
+        let mut negotiator = Negotiator {
+            difficulty: TaskDifficulty::Large,
+            adjusting: true,
+            fast_streak: 0,
+        };
+        let slow = TASK_LIFETIME.mul_f64(0.6);
+        let fast = TASK_LIFETIME.mul_f64(0.05);
+        let moderate = TASK_LIFETIME.mul_f64(0.3);
+
+        assert_eq!(negotiator.record_proof(slow), Some(TaskDifficulty::Medium));
+        assert_eq!(negotiator.record_proof(slow), Some(TaskDifficulty::Small));
+        assert_eq!(negotiator.record_proof(slow), None);
+
+        for _ in 1..FAST_STREAK {
+            assert_eq!(negotiator.record_proof(fast), None);
+        }
+        // A proof in between breaks the streak
+        assert_eq!(negotiator.record_proof(moderate), None);
+        for _ in 1..FAST_STREAK {
+            assert_eq!(negotiator.record_proof(fast), None);
+        }
+        assert_eq!(negotiator.record_proof(fast), Some(TaskDifficulty::Medium));
+
+        // Until negotiation starts, proofs change nothing
+        negotiator.adjusting = false;
+        assert_eq!(negotiator.record_proof(slow), None);
+    }
+}
@@ -0,0 +1,106 @@
+//! Fair scheduling between proving and the rest of the system
+//!
+//! On a single-core VPS a proof holds the CPU for minutes, freezing the TUI and making SSH
+//! sessions crawl. `--duty-cycle <PERCENT>` time-slices proving: each proof runs on a blocking
+//! thread, so the async runtime (TUI, control server, network) keeps being scheduled, and is
+//! followed by a rest long enough that proving takes at most PERCENT of the wall-clock time.
+//!
+//! The zkVM proves in a single call that cannot be paused, so slices end at proof boundaries
+//! rather than inside the prover's loops. The duty cycle therefore holds over a proof and the
+//! rest after it, not at every moment: while a proof runs it uses the CPU fully, however long
+//! it takes, and a proof of ten minutes at 50% is followed by ten minutes of rest. Only the
+//! rest keeps the machine responsive, so short proofs slice finer than long ones. Without
+//! `--duty-cycle`, proofs run as before.
+
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// Share of the time proving may use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DutyCycle {
+    percent: u8,
+}
+
+impl DutyCycle {
+    /// Parses a percentage between 1 and 100, with or without a `%` sign.
+    ///
+    /// # Errors
+    /// Returns a message suitable for clap if the value is not such a percentage.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let percent: u8 = value
+            .trim()
+            .trim_end_matches('%')
+            .parse()
+            .map_err(|_| format!("invalid duty cycle '{}', expected e.g. 90%", value))?;
+        if !(1..=100).contains(&percent) {
+            return Err(format!(
+                "duty cycle must be between 1% and 100%, got {}%",
+                percent
+            ));
+        }
+        Ok(Self { percent })
+    }
+
+    pub fn percent(self) -> u8 {
+        self.percent
+    }
+
+    /// How long to rest after `busy` of proving to stay within the duty cycle.
+    pub fn rest_after(self, busy: Duration) -> Duration {
+        busy * u32::from(100 - self.percent) / u32::from(self.percent)
+    }
+}
+
+static DUTY_CYCLE: OnceLock<DutyCycle> = OnceLock::new();
+
+/// Limits proving to a share of the time. Only the first call has an effect.
+pub fn set_duty_cycle(duty_cycle: DutyCycle) {
+    let _ = DUTY_CYCLE.set(duty_cycle);
+}
+
+/// The configured duty cycle, if proving is time-sliced.
+pub fn duty_cycle() -> Option<DutyCycle> {
+    DUTY_CYCLE.get().copied()
+}
+
+/// Runs a CPU-bound proving step, time-sliced if a duty cycle is configured: the step runs to
+/// completion, then the rest is taken.
+pub async fn run<T: Send + 'static>(work: impl FnOnce() -> T + Send + 'static) -> T {
+    let Some(duty_cycle) = duty_cycle() else {
+        return work();
+    };
+    let started = Instant::now();
+    let result = match tokio::task::spawn_blocking(work).await {
+        Ok(result) => result,
+        Err(e) => std::panic::resume_unwind(e.into_panic()),
+    };
+    tokio::time::sleep(duty_cycle.rest_after(started.elapsed())).await;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Percentages parse with or without a sign, and the rest keeps proving to that share.
+    fn test_parse_and_rest() {
+        let duty_cycle = DutyCycle::parse("90%").unwrap();
+        assert_eq!(duty_cycle.percent(), 90);
+        assert_eq!(DutyCycle::parse("50").unwrap().percent(), 50);
+        assert!(DutyCycle::parse("0").is_err());
+        assert!(DutyCycle::parse("101%").is_err());
+        assert!(DutyCycle::parse("fast").is_err());
+
+        assert_eq!(
+            duty_cycle.rest_after(Duration::from_secs(90)),
+            Duration::from_secs(10)
+        );
+        assert_eq!(
+            DutyCycle::parse("100")
+                .unwrap()
+                .rest_after(Duration::from_secs(90)),
+            Duration::ZERO
+        );
+    }
+}
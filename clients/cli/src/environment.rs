@@ -7,18 +7,90 @@ pub enum Environment {
     /// Production environment.
     #[default]
     Production,
+    /// Staging environment, running the next release of the orchestrator.
+    Staging,
     /// Custom environment with a specific orchestrator URL.
     Custom { orchestrator_url: String },
 }
 
+/// Environment names accepted by `--environment` and `NEXUS_ENVIRONMENT`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum EnvironmentName {
+    Production,
+    Staging,
+    /// The orchestrator at `--orchestrator-url`
+    Custom,
+}
+
 impl Environment {
     /// Returns the orchestrator service URL associated with the environment.
     pub fn orchestrator_url(&self) -> &str {
         match self {
             Environment::Production => "https://production.orchestrator.nexus.xyz",
+            Environment::Staging => "https://staging.orchestrator.nexus.xyz",
             Environment::Custom { orchestrator_url } => orchestrator_url,
         }
     }
+
+    /// The orchestrator's public key (hex) that submission receipts must be signed by, if it
+    /// has published one. None has yet, so no receipt is trusted (see `receipts`).
+    pub fn receipt_key(&self) -> Option<&'static str> {
+        match self {
+            Environment::Production | Environment::Staging | Environment::Custom { .. } => None,
+        }
+    }
+
+    /// The environment selected by `--environment` and `--orchestrator-url`; a URL overrides
+    /// the named environment, e.g. to reach an orchestrator running locally.
+    ///
+    /// # Errors
+    /// Returns a message if `custom` is named without a URL, or the URL is not http(s).
+    pub fn select(
+        name: Option<EnvironmentName>,
+        orchestrator_url: Option<String>,
+    ) -> Result<Self, String> {
+        if let Some(url) = orchestrator_url {
+            let valid = reqwest::Url::parse(&url)
+                .is_ok_and(|parsed| matches!(parsed.scheme(), "http" | "https"));
+            if !valid {
+                return Err(format!(
+                    "Invalid orchestrator URL '{}' (expected e.g. http://localhost:8080)",
+                    url
+                ));
+            }
+            return Ok(Environment::Custom {
+                orchestrator_url: url,
+            });
+        }
+        match name.unwrap_or(EnvironmentName::Production) {
+            EnvironmentName::Production => Ok(Environment::Production),
+            EnvironmentName::Staging => Ok(Environment::Staging),
+            EnvironmentName::Custom => {
+                Err("--environment custom needs --orchestrator-url <URL>".to_string())
+            }
+        }
+    }
+
+    /// Short name under which metrics and history are kept and reported, so runs against a
+    /// test orchestrator stay apart from production: the name of a known environment, or the
+    /// host (and port) of a custom orchestrator.
+    pub fn label(&self) -> String {
+        if *self == Environment::Staging {
+            return "staging".to_string();
+        }
+        let url = self.orchestrator_url().trim_end_matches('/');
+        if url == Environment::Production.orchestrator_url() {
+            return "production".to_string();
+        }
+        match reqwest::Url::parse(url) {
+            Ok(parsed) => match (parsed.host_str(), parsed.port()) {
+                (Some(host), Some(port)) => format!("{}:{}", host, port),
+                (Some(host), None) => host.to_string(),
+                (None, _) => url.to_string(),
+            },
+            Err(_) => url.to_string(),
+        }
+    }
 }
 
 impl FromStr for Environment {
@@ -27,6 +99,7 @@ impl FromStr for Environment {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
             "production" => Ok(Environment::Production),
+            "staging" => Ok(Environment::Staging),
             _ => Err(()),
         }
     }
@@ -36,6 +109,7 @@ impl Display for Environment {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             Environment::Production => write!(f, "Production"),
+            Environment::Staging => write!(f, "Staging"),
             Environment::Custom { orchestrator_url } => write!(f, "Custom({})", orchestrator_url),
         }
     }
@@ -46,3 +120,48 @@ impl Debug for Environment {
         write!(f, "Environment::{}, URL: {}", self, self.orchestrator_url())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Production is labelled by name, custom orchestrators by host and port.
+    fn test_label() {
+        assert_eq!(Environment::Production.label(), "production");
+        let custom = |url: &str| Environment::Custom {
+            orchestrator_url: url.to_string(),
+        };
+        assert_eq!(
+            custom("https://production.orchestrator.nexus.xyz/").label(),
+            "production"
+        );
+        assert_eq!(
+            custom("https://staging.orchestrator.nexus.xyz").label(),
+            "staging.orchestrator.nexus.xyz"
+        );
+        assert_eq!(custom("http://localhost:8080/").label(), "localhost:8080");
+        assert_eq!(Environment::Staging.label(), "staging");
+    }
+
+    #[test]
+    // A URL selects a custom environment whatever the name; `custom` alone is refused.
+    fn test_select() {
+        assert_eq!(Environment::select(None, None), Ok(Environment::Production));
+        assert_eq!(
+            Environment::select(Some(EnvironmentName::Staging), None),
+            Ok(Environment::Staging)
+        );
+        assert_eq!(
+            Environment::select(
+                Some(EnvironmentName::Staging),
+                Some("http://localhost:8080".to_string())
+            ),
+            Ok(Environment::Custom {
+                orchestrator_url: "http://localhost:8080".to_string()
+            })
+        );
+        assert!(Environment::select(Some(EnvironmentName::Custom), None).is_err());
+        assert!(Environment::select(None, Some("localhost:8080".to_string())).is_err());
+    }
+}
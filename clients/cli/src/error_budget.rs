@@ -0,0 +1,301 @@
+//! Error budget
+//!
+//! Tracks task outcomes (proving and submission) over a rolling window. When the share of
+//! failed tasks exceeds the budget, task fetching is paused so a systematically failing node
+//! stops wasting electricity and reputation until someone looks at it. While paused, a probe
+//! fetch is allowed periodically; the first task that succeeds resumes normal operation.
+
+use crate::error_classifier::LogLevel;
+use crate::events::{Event, EventType, Worker};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// Error budget settings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorBudgetConfig {
+    /// Maximum share of failed tasks (0.0 - 1.0) within the window before pausing.
+    pub max_failure_rate: f64,
+
+    /// Window over which task outcomes are counted.
+    pub window: Duration,
+
+    /// Minimum number of tasks in the window before the budget is enforced.
+    pub min_tasks: usize,
+
+    /// How often a probe fetch is allowed while paused.
+    pub probe_interval: Duration,
+}
+
+impl Default for ErrorBudgetConfig {
+    fn default() -> Self {
+        Self {
+            max_failure_rate: 0.5,
+            window: Duration::from_secs(30 * 60),
+            min_tasks: 10,
+            probe_interval: Duration::from_secs(10 * 60),
+        }
+    }
+}
+
+impl ErrorBudgetConfig {
+    /// Builds the settings from command-line flags. The budget is only enabled when a failure
+    /// rate is given; the window and probe interval are in minutes.
+    pub fn from_flags(
+        max_failure_rate: Option<f64>,
+        window_minutes: Option<u64>,
+        probe_minutes: Option<u64>,
+    ) -> Option<Self> {
+        let defaults = Self::default();
+        Some(Self {
+            max_failure_rate: max_failure_rate?.clamp(0.0, 1.0),
+            window: window_minutes
+                .map(|m| Duration::from_secs(m.max(1) * 60))
+                .unwrap_or(defaults.window),
+            probe_interval: probe_minutes
+                .map(|m| Duration::from_secs(m.max(1) * 60))
+                .unwrap_or(defaults.probe_interval),
+            ..defaults
+        })
+    }
+}
+
+/// A change in the pause state caused by a recorded outcome.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BudgetEvent {
+    /// The budget was exhausted and fetching is paused.
+    Paused { failure_rate: f64, tasks: usize },
+    /// A task succeeded while paused, so fetching resumes.
+    Resumed,
+}
+
+impl BudgetEvent {
+    /// Converts the change into a dashboard event for the given worker.
+    pub fn to_event(&self, worker: Worker, config: &ErrorBudgetConfig) -> Event {
+        match self {
+            BudgetEvent::Paused {
+                failure_rate,
+                tasks,
+            } => Event::new_with_level(
+                worker,
+                format!(
+                    "Error budget exhausted: {:.0}% of the last {} tasks failed within {} min. Pausing task fetching; probing again every {} min",
+                    failure_rate * 100.0,
+                    tasks,
+                    config.window.as_secs() / 60,
+                    config.probe_interval.as_secs() / 60
+                ),
+                EventType::Error,
+                LogLevel::Error,
+            ),
+            BudgetEvent::Resumed => Event::new_with_level(
+                worker,
+                "Error budget recovered: probe task succeeded, resuming task fetching".to_string(),
+                EventType::Success,
+                LogLevel::Info,
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct BudgetState {
+    outcomes: VecDeque<(Instant, bool)>,
+    paused_at: Option<Instant>,
+    last_probe: Option<Instant>,
+}
+
+/// Shared error budget for a node's fetchers, provers and submitter.
+#[derive(Debug)]
+pub struct ErrorBudget {
+    /// `None` disables the budget.
+    config: Option<ErrorBudgetConfig>,
+    /// Extra wait before the first probe after a pause, so paused nodes do not probe together.
+    probe_offset: Duration,
+    state: Mutex<BudgetState>,
+}
+
+impl ErrorBudget {
+    pub fn new(config: Option<ErrorBudgetConfig>) -> Self {
+        Self {
+            config,
+            probe_offset: Duration::ZERO,
+            state: Mutex::new(BudgetState::default()),
+        }
+    }
+
+    /// Delays the first probe after a pause by this node's share of a quarter probe interval.
+    pub fn paced(mut self) -> Self {
+        if let Some(config) = &self.config {
+            self.probe_offset =
+                crate::pacing::offset("error_budget_probe", config.probe_interval / 4);
+        }
+        self
+    }
+
+    /// The active settings, if the budget is enabled.
+    pub fn config(&self) -> Option<&ErrorBudgetConfig> {
+        self.config.as_ref()
+    }
+
+    /// Records a task outcome. Returns a change in the pause state, if any.
+    pub fn record(&self, success: bool) -> Option<BudgetEvent> {
+        self.record_at(success, Instant::now())
+    }
+
+    fn record_at(&self, success: bool, now: Instant) -> Option<BudgetEvent> {
+        let config = self.config.as_ref()?;
+        let mut state = self.state.lock().ok()?;
+
+        if state.paused_at.is_some() {
+            if success {
+                *state = BudgetState::default();
+                return Some(BudgetEvent::Resumed);
+            }
+            return None;
+        }
+
+        state.outcomes.push_back((now, success));
+        while let Some((at, _)) = state.outcomes.front() {
+            if now.duration_since(*at) > config.window {
+                state.outcomes.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        let tasks = state.outcomes.len();
+        if tasks < config.min_tasks {
+            return None;
+        }
+        let failures = state.outcomes.iter().filter(|(_, ok)| !ok).count();
+        let failure_rate = failures as f64 / tasks as f64;
+        if failure_rate > config.max_failure_rate {
+            state.paused_at = Some(now);
+            return Some(BudgetEvent::Paused {
+                failure_rate,
+                tasks,
+            });
+        }
+        None
+    }
+
+    /// Records a task outcome and reports any change in the pause state as `worker`.
+    pub async fn report(&self, success: bool, worker: Worker, event_sender: &mpsc::Sender<Event>) {
+        if let (Some(change), Some(config)) = (self.record(success), &self.config) {
+            let _ = event_sender.send(change.to_event(worker, config)).await;
+        }
+    }
+
+    /// Whether fetching is paused because the budget was exhausted.
+    pub fn is_paused(&self) -> bool {
+        self.state
+            .lock()
+            .map(|state| state.paused_at.is_some())
+            .unwrap_or(false)
+    }
+
+    /// Whether a fetch may go ahead now. While paused, this allows one probe per interval.
+    pub fn allow_fetch(&self) -> bool {
+        self.allow_fetch_at(Instant::now())
+    }
+
+    fn allow_fetch_at(&self, now: Instant) -> bool {
+        let Some(config) = &self.config else {
+            return true;
+        };
+        let Ok(mut state) = self.state.lock() else {
+            return true;
+        };
+        let Some(paused_at) = state.paused_at else {
+            return true;
+        };
+        let (since, wait) = match state.last_probe {
+            Some(last_probe) => (last_probe, config.probe_interval),
+            None => (paused_at, config.probe_interval + self.probe_offset),
+        };
+        if now.duration_since(since) >= wait {
+            state.last_probe = Some(now);
+            return true;
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn budget() -> ErrorBudget {
+        ErrorBudget::new(Some(ErrorBudgetConfig {
+            max_failure_rate: 0.5,
+            window: Duration::from_secs(60),
+            min_tasks: 4,
+            probe_interval: Duration::from_secs(30),
+        }))
+    }
+
+    #[test]
+    // A disabled budget never pauses.
+    fn test_disabled_budget() {
+        let budget = ErrorBudget::new(None);
+        for _ in 0..100 {
+            assert_eq!(budget.record(false), None);
+        }
+        assert!(budget.allow_fetch());
+    }
+
+    #[test]
+    // Exceeding the failure rate should pause, and a probe should be allowed once per interval.
+    fn test_pause_and_probe() {
+        let budget = budget();
+        let start = Instant::now();
+        assert_eq!(budget.record_at(true, start), None);
+        assert_eq!(budget.record_at(false, start), None);
+        assert_eq!(budget.record_at(false, start), None);
+        assert!(matches!(
+            budget.record_at(false, start),
+            Some(BudgetEvent::Paused { tasks: 4, .. })
+        ));
+        assert!(budget.is_paused());
+
+        assert!(!budget.allow_fetch_at(start + Duration::from_secs(10)));
+        assert!(budget.allow_fetch_at(start + Duration::from_secs(30)));
+        assert!(!budget.allow_fetch_at(start + Duration::from_secs(40)));
+
+        // A successful probe task resumes fetching.
+        assert_eq!(budget.record(true), Some(BudgetEvent::Resumed));
+        assert!(!budget.is_paused());
+        assert!(budget.allow_fetch());
+    }
+
+    #[test]
+    // The budget is opt-in: it is only enabled by a failure rate flag, which is clamped.
+    fn test_from_flags() {
+        assert_eq!(ErrorBudgetConfig::from_flags(None, Some(5), None), None);
+        let config = ErrorBudgetConfig::from_flags(Some(1.5), Some(5), None).unwrap();
+        assert_eq!(config.max_failure_rate, 1.0);
+        assert_eq!(config.window, Duration::from_secs(300));
+        assert_eq!(
+            config.probe_interval,
+            ErrorBudgetConfig::default().probe_interval
+        );
+    }
+
+    #[test]
+    // Failures older than the window should not count against the budget.
+    fn test_old_failures_expire() {
+        let budget = budget();
+        let start = Instant::now();
+        for _ in 0..3 {
+            budget.record_at(false, start);
+        }
+        let later = start + Duration::from_secs(120);
+        for _ in 0..3 {
+            assert_eq!(budget.record_at(true, later), None);
+        }
+        assert_eq!(budget.record_at(false, later), None);
+        assert!(!budget.is_paused());
+    }
+}
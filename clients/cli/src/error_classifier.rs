@@ -1,8 +1,13 @@
 use crate::orchestrator::error::OrchestratorError;
 use crate::prover::ProverError;
 use log::LevelFilter;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(
+    Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
 pub enum LogLevel {
     Trace = 0,
     Debug = 1,
@@ -11,6 +16,19 @@ pub enum LogLevel {
     Error = 4,
 }
 
+impl Display for LogLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            LogLevel::Trace => "trace",
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warn => "warn",
+            LogLevel::Error => "error",
+        };
+        write!(f, "{}", name)
+    }
+}
+
 impl From<LogLevel> for LevelFilter {
     fn from(level: LogLevel) -> Self {
         match level {
@@ -34,14 +52,19 @@ impl ErrorClassifier {
     pub fn classify_fetch_error(&self, error: &OrchestratorError) -> LogLevel {
         match error {
             // Non-critical: Temporary server issues
-            OrchestratorError::Http { status, .. } if *status == 429 => LogLevel::Debug,
+            OrchestratorError::RateLimited { .. } => LogLevel::Debug,
+            OrchestratorError::MaintenanceMode(_) => LogLevel::Debug,
             OrchestratorError::Http { status, .. } if (500..=599).contains(status) => {
                 LogLevel::Warn
             }
 
             // Critical: Auth, malformed responses
-            OrchestratorError::Http { status, .. } if *status == 401 => LogLevel::Error,
+            OrchestratorError::Unauthorized { .. } => LogLevel::Error,
+            OrchestratorError::NodeNotRegistered { .. } => LogLevel::Error,
+            OrchestratorError::VersionTooOld { .. } => LogLevel::Error,
             OrchestratorError::Http { status, .. } if *status == 403 => LogLevel::Error,
+            OrchestratorError::SchemaDrift(_) => LogLevel::Error,
+            OrchestratorError::TlsInterception(_) => LogLevel::Error,
 
             // Network issues - usually temporary
             _ => LogLevel::Warn,
@@ -59,6 +82,7 @@ impl ErrorClassifier {
             ProverError::MalformedTask(_) => LogLevel::Error,
             ProverError::GuestProgram(_) => LogLevel::Error,
             ProverError::Serialization(_) => LogLevel::Error,
+            ProverError::NotAllowlisted(_) => LogLevel::Error,
 
             // Default to warning for other Stwo errors
             ProverError::Stwo(_) => LogLevel::Warn,
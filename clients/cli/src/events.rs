@@ -3,7 +3,7 @@
 //! Types and implementations for worker events and logging
 
 use crate::error_classifier::LogLevel;
-use crate::logging::should_log_with_env;
+use crate::logging::{module_level, should_log_with_env};
 use chrono::Local;
 use std::fmt::Display;
 
@@ -17,6 +17,36 @@ pub enum Worker {
     ProofSubmitter,
     /// Worker that checks for new CLI versions.
     VersionChecker,
+    /// Orchestrator maintenance announcements (start and end of a window).
+    Maintenance,
+    /// Switches between resource profiles as their time windows begin and end.
+    Scheduler,
+}
+
+impl Worker {
+    /// Name of the worker in recordings and JSON logs, e.g. `task_fetcher` or `prover:0`.
+    pub fn label(&self) -> String {
+        match self {
+            Worker::TaskFetcher => "task_fetcher".to_string(),
+            Worker::Prover(id) => format!("prover:{}", id),
+            Worker::ProofSubmitter => "proof_submitter".to_string(),
+            Worker::VersionChecker => "version_checker".to_string(),
+            Worker::Maintenance => "maintenance".to_string(),
+            Worker::Scheduler => "scheduler".to_string(),
+        }
+    }
+
+    /// Module whose log level applies to the worker's events (see `logging::MODULES`).
+    pub fn module(&self) -> &'static str {
+        match self {
+            Worker::TaskFetcher => "fetcher",
+            Worker::Prover(_) => "prover",
+            Worker::ProofSubmitter => "submitter",
+            Worker::VersionChecker => "version",
+            Worker::Maintenance => "orchestrator",
+            Worker::Scheduler => "scheduler",
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, strum::Display)]
@@ -27,6 +57,16 @@ pub enum EventType {
     Shutdown,
 }
 
+/// What an event is about, written as separate fields by `--log-format json`.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct EventFields {
+    pub node_id: Option<u64>,
+    pub task_id: Option<String>,
+    /// The proxy, without credentials
+    pub proxy: Option<String>,
+    pub duration_ms: Option<u64>,
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Event {
     pub worker: Worker,
@@ -34,6 +74,9 @@ pub struct Event {
     pub timestamp: String,
     pub event_type: EventType,
     pub log_level: LogLevel,
+    /// Module whose log level applies, the worker's unless the event is about something else
+    pub module: &'static str,
+    pub fields: EventFields,
 }
 
 impl Event {
@@ -44,6 +87,8 @@ impl Event {
             timestamp: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
             event_type,
             log_level: LogLevel::Info,
+            module: kind.module(),
+            fields: EventFields::default(),
         }
     }
 
@@ -59,6 +104,8 @@ impl Event {
             timestamp: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
             event_type,
             log_level,
+            module: kind.module(),
+            fields: EventFields::default(),
         }
     }
 
@@ -107,7 +154,49 @@ impl Event {
         Self::new_with_level(Worker::VersionChecker, msg, event_type, log_level)
     }
 
+    pub fn maintenance_with_level(msg: String, event_type: EventType, log_level: LogLevel) -> Self {
+        Self::new_with_level(Worker::Maintenance, msg, event_type, log_level)
+    }
+
+    pub fn scheduler_with_level(msg: String, event_type: EventType, log_level: LogLevel) -> Self {
+        Self::new_with_level(Worker::Scheduler, msg, event_type, log_level)
+    }
+
+    /// The same event, about the node `node_id`.
+    pub fn with_node(mut self, node_id: Option<u64>) -> Self {
+        self.fields.node_id = node_id;
+        self
+    }
+
+    /// The same event, about the task `task_id`.
+    pub fn with_task(mut self, task_id: &str) -> Self {
+        self.fields.task_id = Some(task_id.to_string());
+        self
+    }
+
+    /// The same event, about the proxy shown as `proxy`.
+    pub fn with_proxy(mut self, proxy: &str) -> Self {
+        self.fields.proxy = Some(proxy.to_string());
+        self
+    }
+
+    /// The same event, for something that took `duration`.
+    pub fn with_duration(mut self, duration: std::time::Duration) -> Self {
+        self.fields.duration_ms = Some(duration.as_millis() as u64);
+        self
+    }
+
+    /// The same event, filtered by the log level of `module`.
+    pub fn in_module(mut self, module: &'static str) -> Self {
+        self.module = module;
+        self
+    }
+
     pub fn should_display(&self) -> bool {
+        // A level given for the event's module applies to all of its events
+        if let Some(level) = module_level(self.module) {
+            return self.log_level >= level;
+        }
         // Always show success events and info level events
         if self.event_type == EventType::Success || self.log_level >= LogLevel::Info {
             return true;
@@ -123,6 +212,8 @@ impl Display for Event {
             Worker::Prover(worker_id) => format!("Prover {}", worker_id),
             Worker::ProofSubmitter => "Proof Submitter".to_string(),
             Worker::VersionChecker => "Version Checker".to_string(),
+            Worker::Maintenance => "Maintenance".to_string(),
+            Worker::Scheduler => "Scheduler".to_string(),
         };
         write!(
             f,
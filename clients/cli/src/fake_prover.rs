@@ -0,0 +1,135 @@
+//! Simulated prover for load testing
+//!
+//! `--prover fake` replaces proving with a fixed delay (`--fake-duration`), so the scheduler,
+//! proxy rotation and dashboard can be exercised on machines that cannot run the real prover.
+//! The prover itself is never called: every simulated result is the same few placeholder
+//! bytes, which no orchestrator would accept as a proof.
+//!
+//! Simulated proofs are only submitted to an orchestrator on a loopback address (a local mock
+//! server). Against any other orchestrator they are discarded after "proving".
+
+use crate::environment::Environment;
+use std::net::IpAddr;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Which prover produces proofs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ProverBackend {
+    /// Prove tasks with the Stwo zkVM prover.
+    #[default]
+    Stwo,
+    /// Wait for `--fake-duration`, then return a placeholder proof.
+    Fake,
+}
+
+/// Default time a simulated proof takes.
+pub const DEFAULT_FAKE_DURATION: Duration = Duration::from_secs(30);
+
+static FAKE_DURATION: OnceLock<Duration> = OnceLock::new();
+
+/// The serialized "proof" returned for every simulated task.
+const PLACEHOLDER_PROOF: &[u8] = b"nexus-cli simulated proof";
+
+/// Switches all workers to the simulated prover. Only the first call has an effect.
+pub fn enable(duration: Duration) {
+    let _ = FAKE_DURATION.set(duration);
+}
+
+/// Whether the simulated prover is in use.
+pub fn is_enabled() -> bool {
+    FAKE_DURATION.get().is_some()
+}
+
+/// Waits for the configured duration, then returns the placeholder proof, serialized.
+pub async fn prove() -> Vec<u8> {
+    let duration = FAKE_DURATION
+        .get()
+        .copied()
+        .unwrap_or(DEFAULT_FAKE_DURATION);
+    tokio::time::sleep(duration).await;
+    PLACEHOLDER_PROOF.to_vec()
+}
+
+/// Whether proofs may be submitted to this orchestrator. Real proofs always may; simulated
+/// ones only go to a mock orchestrator on a loopback address.
+pub fn may_submit(environment: &Environment) -> bool {
+    !is_enabled() || is_local_orchestrator(environment)
+}
+
+fn is_local_orchestrator(environment: &Environment) -> bool {
+    let Environment::Custom { orchestrator_url } = environment else {
+        return false;
+    };
+    let Some(rest) = orchestrator_url.split_once("://").map(|(_, rest)| rest) else {
+        return false;
+    };
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    // Strip the port, keeping bracketed IPv6 addresses intact
+    let host = match authority.strip_prefix('[') {
+        Some(bracketed) => bracketed.split(']').next().unwrap_or_default(),
+        None => authority.split(':').next().unwrap_or_default(),
+    };
+    host.eq_ignore_ascii_case("localhost")
+        || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Parses a duration such as `30s`, `500ms`, `2m` or a plain number of seconds.
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration: {}", value))?;
+    let seconds = match unit {
+        "ms" => number / 1000.0,
+        "" | "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        _ => {
+            return Err(format!(
+                "unknown duration unit in {} (use ms, s, m or h)",
+                value
+            ));
+        }
+    };
+    Duration::try_from_secs_f64(seconds).map_err(|_| format!("invalid duration: {}", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Durations should accept the common units and bare seconds.
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("45"), Ok(Duration::from_secs(45)));
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration("2m"), Ok(Duration::from_secs(120)));
+        assert_eq!(parse_duration("1.5s"), Ok(Duration::from_millis(1500)));
+        assert!(parse_duration("10d").is_err());
+        assert!(parse_duration("fast").is_err());
+    }
+
+    #[test]
+    // Only loopback orchestrators count as mock servers.
+    fn test_local_orchestrator_detection() {
+        let custom = |url: &str| Environment::Custom {
+            orchestrator_url: url.to_string(),
+        };
+        assert!(is_local_orchestrator(&custom("http://127.0.0.1:8080")));
+        assert!(is_local_orchestrator(&custom("http://localhost/api")));
+        assert!(is_local_orchestrator(&custom("http://[::1]:9000")));
+        assert!(!is_local_orchestrator(&custom(
+            "https://orchestrator.example.com"
+        )));
+        assert!(!is_local_orchestrator(&custom(
+            "http://localhost.example.com"
+        )));
+        assert!(!is_local_orchestrator(&Environment::Production));
+    }
+}
@@ -0,0 +1,370 @@
+//! Earnings goal
+//!
+//! Users can set a daily or weekly goal in the config file:
+//!
+//! ```json
+//! "goal": { "proofs": 200, "period": "day" }
+//! ```
+//!
+//! The orchestrator API does not report points, so the goal counts accepted proofs, which are
+//! what earn them. Progress is kept in `~/.nexus/goal.json` so restarts do not reset it, shown
+//! in the dashboard with a projection for the period at the current pace, and announced as an
+//! event once per period when the goal is met or the pace falls behind.
+//!
+//! Progress is counted per environment (see `Environment::label`), so proofs accepted by a
+//! test orchestrator do not count toward the production goal.
+
+use crate::environment::Environment;
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, Local, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+/// Projected shortfall (as a fraction of the goal) at which the pace counts as falling behind.
+const BEHIND_THRESHOLD: f64 = 0.9;
+
+/// Share of the period that must pass before the pace is judged.
+const MIN_ELAPSED_FOR_PACE: f64 = 0.25;
+
+/// Length of a goal period, starting at local midnight (Monday for weeks).
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum GoalPeriod {
+    Day,
+    Week,
+}
+
+impl GoalPeriod {
+    fn start_containing(self, now: DateTime<Local>) -> DateTime<Local> {
+        let days_back = match self {
+            GoalPeriod::Day => 0,
+            GoalPeriod::Week => now.weekday().num_days_from_monday(),
+        };
+        let date = now.date_naive() - ChronoDuration::days(i64::from(days_back));
+        Local
+            .from_local_datetime(&date.and_time(NaiveTime::MIN))
+            .earliest()
+            .unwrap_or(now)
+    }
+
+    fn length(self) -> ChronoDuration {
+        match self {
+            GoalPeriod::Day => ChronoDuration::days(1),
+            GoalPeriod::Week => ChronoDuration::weeks(1),
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            GoalPeriod::Day => "today",
+            GoalPeriod::Week => "this week",
+        }
+    }
+}
+
+/// Accepted proofs to reach per period.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EarningsGoal {
+    pub proofs: u64,
+    pub period: GoalPeriod,
+}
+
+/// Progress toward the goal in the current period.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GoalProgress {
+    pub goal: EarningsGoal,
+    pub achieved: u64,
+    /// Proofs expected by the end of the period at the pace so far.
+    pub projected: u64,
+    /// Share of the period that has passed, from 0.0 to 1.0.
+    pub elapsed: f64,
+}
+
+impl std::fmt::Display for GoalProgress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}/{} proofs {} (on pace for {})",
+            self.achieved,
+            self.goal.proofs,
+            self.goal.period.label(),
+            self.projected
+        )
+    }
+}
+
+/// A summary worth announcing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GoalNotice {
+    Met(GoalProgress),
+    FallingBehind(GoalProgress),
+}
+
+/// Counts persisted between runs.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+struct GoalState {
+    /// Unix timestamp of the period's start.
+    period_start: Option<i64>,
+    achieved: u64,
+    announced_met: bool,
+    announced_behind: bool,
+}
+
+/// Counts of every environment, by environment label.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(from = "SavedGoal")]
+struct GoalHistory {
+    environments: BTreeMap<String, GoalState>,
+}
+
+/// Counts as saved by this release, or by earlier ones, which kept a single count.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedGoal {
+    ByEnvironment {
+        environments: BTreeMap<String, GoalState>,
+    },
+    Production(GoalState),
+}
+
+impl From<SavedGoal> for GoalHistory {
+    fn from(saved: SavedGoal) -> Self {
+        let environments = match saved {
+            SavedGoal::ByEnvironment { environments } => environments,
+            SavedGoal::Production(state) => {
+                BTreeMap::from([(Environment::Production.label(), state)])
+            }
+        };
+        Self { environments }
+    }
+}
+
+#[derive(Debug)]
+struct GoalTracker {
+    goal: EarningsGoal,
+    /// Label of the environment whose proofs are counted
+    environment: String,
+    state: GoalState,
+    /// Counts of the other environments, saved along
+    others: GoalHistory,
+    path: Option<PathBuf>,
+}
+
+impl GoalTracker {
+    fn new(
+        goal: EarningsGoal,
+        environment: String,
+        mut history: GoalHistory,
+        path: Option<PathBuf>,
+    ) -> Self {
+        let state = history
+            .environments
+            .remove(&environment)
+            .unwrap_or_default();
+        Self {
+            goal,
+            environment,
+            state,
+            others: history,
+            path,
+        }
+    }
+
+    /// Starts a new period if the current one has ended.
+    fn roll(&mut self, now: DateTime<Local>) {
+        let start = self.goal.period.start_containing(now);
+        if self.state.period_start != Some(start.timestamp()) {
+            self.state = GoalState {
+                period_start: Some(start.timestamp()),
+                ..GoalState::default()
+            };
+        }
+    }
+
+    fn progress(&mut self, now: DateTime<Local>) -> GoalProgress {
+        self.roll(now);
+        let start = self.state.period_start.unwrap_or(now.timestamp());
+        let length = self.goal.period.length().num_seconds() as f64;
+        let elapsed = ((now.timestamp() - start) as f64 / length).clamp(0.0, 1.0);
+        let projected = if elapsed > 0.0 {
+            (self.state.achieved as f64 / elapsed).round() as u64
+        } else {
+            self.state.achieved
+        };
+        GoalProgress {
+            goal: self.goal,
+            achieved: self.state.achieved,
+            projected,
+            elapsed,
+        }
+    }
+
+    /// Counts an accepted proof and returns a notice if one is due.
+    fn record(&mut self, now: DateTime<Local>) -> Option<GoalNotice> {
+        self.roll(now);
+        self.state.achieved += 1;
+        let notice = self.notice(now);
+        self.save();
+        notice
+    }
+
+    /// A notice not yet given this period, if the goal is met or the pace falls behind.
+    fn notice(&mut self, now: DateTime<Local>) -> Option<GoalNotice> {
+        let progress = self.progress(now);
+        if progress.achieved >= self.goal.proofs {
+            if !self.state.announced_met {
+                self.state.announced_met = true;
+                return Some(GoalNotice::Met(progress));
+            }
+            return None;
+        }
+        let behind = progress.elapsed >= MIN_ELAPSED_FOR_PACE
+            && (progress.projected as f64) < self.goal.proofs as f64 * BEHIND_THRESHOLD;
+        if behind && !self.state.announced_behind {
+            self.state.announced_behind = true;
+            return Some(GoalNotice::FallingBehind(progress));
+        }
+        None
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let mut history = self.others.clone();
+        history
+            .environments
+            .insert(self.environment.clone(), self.state.clone());
+        if let Ok(json) = serde_json::to_string(&history) {
+            let _ = std::fs::write(path, json);
+        }
+    }
+}
+
+static TRACKER: OnceLock<Mutex<GoalTracker>> = OnceLock::new();
+
+/// Tracks progress toward `goal` in `environment`, continuing the current period's count from
+/// a previous run. Only the first call has an effect.
+pub fn init(goal: EarningsGoal, environment: &Environment) {
+    let path = crate::config::get_config_path()
+        .ok()
+        .map(|path| path.with_file_name("goal.json"));
+    let history = path
+        .as_ref()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    let _ = TRACKER.set(Mutex::new(GoalTracker::new(
+        goal,
+        environment.label(),
+        history,
+        path,
+    )));
+}
+
+/// Counts an accepted proof, returning a notice if one is due.
+pub fn record_accepted_proof() -> Option<GoalNotice> {
+    TRACKER.get()?.lock().ok()?.record(Local::now())
+}
+
+/// A notice due without a new proof (e.g. the pace fell behind while idle).
+pub fn check() -> Option<GoalNotice> {
+    TRACKER.get()?.lock().ok()?.notice(Local::now())
+}
+
+/// Progress toward the goal, if one is set.
+pub fn progress() -> Option<GoalProgress> {
+    Some(TRACKER.get()?.lock().ok()?.progress(Local::now()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(day: u32, hour: u32) -> DateTime<Local> {
+        // 2025-06-02 is a Monday
+        Local
+            .with_ymd_and_hms(2025, 6, day, hour, 0, 0)
+            .earliest()
+            .unwrap()
+    }
+
+    #[test]
+    // A daily goal should project the pace, announce once when met, and reset the next day.
+    fn test_daily_goal_progress() {
+        let goal = EarningsGoal {
+            proofs: 4,
+            period: GoalPeriod::Day,
+        };
+        let mut tracker =
+            GoalTracker::new(goal, "production".to_string(), GoalHistory::default(), None);
+        assert_eq!(tracker.record(at(2, 6)), None);
+        let progress = tracker.progress(at(2, 12));
+        assert_eq!(progress.achieved, 1);
+        assert_eq!(progress.projected, 2);
+
+        // Half the day gone at half the needed pace
+        assert!(matches!(
+            tracker.notice(at(2, 12)),
+            Some(GoalNotice::FallingBehind(_))
+        ));
+        assert_eq!(tracker.notice(at(2, 13)), None);
+
+        for _ in 0..2 {
+            assert_eq!(tracker.record(at(2, 14)), None);
+        }
+        assert!(matches!(
+            tracker.record(at(2, 15)),
+            Some(GoalNotice::Met(_))
+        ));
+        assert_eq!(tracker.record(at(2, 16)), None);
+
+        assert_eq!(tracker.progress(at(3, 1)).achieved, 0);
+    }
+
+    #[test]
+    // Weekly periods should start on Monday.
+    fn test_week_starts_on_monday() {
+        let start = GoalPeriod::Week.start_containing(at(5, 18));
+        assert_eq!(start, at(2, 0));
+    }
+
+    #[test]
+    // Each environment keeps its own count, and a count saved by earlier releases is production's.
+    fn test_counts_per_environment() {
+        let goal = EarningsGoal {
+            proofs: 10,
+            period: GoalPeriod::Day,
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("goal.json");
+        let legacy = GoalState {
+            period_start: Some(GoalPeriod::Day.start_containing(at(2, 6)).timestamp()),
+            achieved: 3,
+            ..GoalState::default()
+        };
+        std::fs::write(&path, serde_json::to_string(&legacy).unwrap()).unwrap();
+        let load = || serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+
+        let mut staging = GoalTracker::new(
+            goal,
+            "staging.orchestrator.nexus.xyz".to_string(),
+            load(),
+            Some(path.clone()),
+        );
+        staging.record(at(2, 7));
+        assert_eq!(staging.progress(at(2, 8)).achieved, 1);
+
+        let mut production =
+            GoalTracker::new(goal, "production".to_string(), load(), Some(path.clone()));
+        assert_eq!(production.progress(at(2, 8)).achieved, 3);
+        production.record(at(2, 9));
+        let history: GoalHistory = load();
+        assert_eq!(history.environments["production"].achieved, 4);
+        assert_eq!(
+            history.environments["staging.orchestrator.nexus.xyz"].achieved,
+            1
+        );
+    }
+}
@@ -0,0 +1,198 @@
+//! In-place upgrades
+//!
+//! `nexus-network handoff` upgrades a running node without losing work: the node stops
+//! fetching, finishes the tasks it already holds (a proof cannot be paused and resumed, so
+//! proving progress is kept by completing it rather than serializing it), saves its session
+//! state and re-executes its binary with the same arguments. Replace the binary first (move the
+//! new one into place; a running binary cannot be overwritten). The new process picks up the
+//! state and carries on with the same PID, terminal and session:
+//!
+//! - the session start, so uptime and the dashboard timer continue,
+//! - whether fetching was paused,
+//! - each node's sticky proxy (`--proxy-assignment sticky`).
+//!
+//! Unsubmitted proofs and task states are already on disk (see `submission_journal` and
+//! `task_lifecycle`). The state travels in `~/.nexus/handoff.json`, named by the
+//! `NEXUS_HANDOFF_STATE` environment variable so only the re-executed process reads it.
+//! Handoff is available on Unix only.
+
+use crate::proxy::ProxyContext;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Names the state file for the re-executed process.
+const STATE_ENV: &str = "NEXUS_HANDOFF_STATE";
+
+/// Session state carried across a handoff.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct HandoffState {
+    /// Version of the process that handed off.
+    pub version: String,
+    /// When the session started (seconds since the Unix epoch).
+    pub session_started_at: u64,
+    pub paused: bool,
+    /// Sticky proxy of each session, by owner.
+    #[serde(default)]
+    pub sticky_proxies: BTreeMap<String, String>,
+}
+
+impl HandoffState {
+    /// Loads the state written by the previous process.
+    ///
+    /// # Errors
+    /// Returns an `std::io::Error` if the file cannot be read or parsed.
+    pub fn load_from_file(path: &Path) -> Result<Self, std::io::Error> {
+        let buf = std::fs::read(path)?;
+        serde_json::from_slice(&buf)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    fn save(&self, path: &Path) -> Result<(), std::io::Error> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)
+    }
+
+    /// How long the session has been running.
+    pub fn uptime(&self, now: u64) -> Duration {
+        Duration::from_secs(now.saturating_sub(self.session_started_at))
+    }
+}
+
+/// Path to the handoff state, next to the config file.
+pub fn state_path(config_path: &Path) -> PathBuf {
+    config_path.with_file_name("handoff.json")
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// The binary as it was when the process started. Once it is replaced, `current_exe` on
+/// Linux resolves to the deleted original, so the path is captured up front.
+static EXECUTABLE: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+/// Set once the node finished its tasks for a handoff and is shutting down to re-execute.
+static READY: AtomicBool = AtomicBool::new(false);
+
+/// Remembers the binary to re-execute. Call early, before it can be replaced.
+pub fn init() {
+    EXECUTABLE.get_or_init(|| std::env::current_exe().ok());
+}
+
+/// Whether this platform can hand off.
+pub fn supported() -> bool {
+    cfg!(unix)
+}
+
+/// Marks the node as ready to re-execute once it has shut down.
+pub fn mark_ready() {
+    READY.store(true, Ordering::Relaxed);
+}
+
+/// Whether the node is shutting down for a handoff.
+pub fn is_ready() -> bool {
+    READY.load(Ordering::Relaxed)
+}
+
+/// Takes the state handed over by the previous process, if this process was re-executed.
+///
+/// The file is removed, so processes that inherit the variable do not resume the session
+/// again.
+pub fn take_resumed() -> Option<HandoffState> {
+    let path = PathBuf::from(std::env::var_os(STATE_ENV)?);
+    let state = HandoffState::load_from_file(&path).ok();
+    let _ = std::fs::remove_file(&path);
+    state
+}
+
+/// Applies a resumed session to this process, whose proxies are `proxies`.
+pub fn restore(state: &HandoffState, proxies: &ProxyContext) {
+    let control = crate::control::control_state();
+    control.resume_session(state.uptime(now()), state.paused);
+    if let Ok(mut manager) = proxies.manager().lock() {
+        manager.restore_sticky_sessions(&state.sticky_proxies);
+    }
+}
+
+/// Captures this process's session state.
+fn capture(proxies: &ProxyContext) -> HandoffState {
+    let control = crate::control::control_state();
+    let sticky_proxies = proxies
+        .manager()
+        .lock()
+        .map(|manager| manager.sticky_sessions())
+        .unwrap_or_default();
+    HandoffState {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        session_started_at: now().saturating_sub(control.uptime().as_secs()),
+        paused: control.is_paused(),
+        sticky_proxies,
+    }
+}
+
+/// Saves the session state and replaces this process with a fresh run of its binary.
+///
+/// Only returns if the state cannot be saved or the binary cannot be executed.
+///
+/// # Errors
+/// Returns a description of what failed.
+#[cfg(unix)]
+pub fn exec(proxies: &ProxyContext) -> Result<std::convert::Infallible, String> {
+    use std::os::unix::process::CommandExt;
+
+    let executable = EXECUTABLE
+        .get()
+        .cloned()
+        .flatten()
+        .ok_or("the path of the running binary is unknown")?;
+    let config_path = crate::config::get_config_path().map_err(|e| e.to_string())?;
+    let path = state_path(&config_path);
+    capture(proxies)
+        .save(&path)
+        .map_err(|e| format!("cannot save {}: {}", path.display(), e))?;
+    let error = std::process::Command::new(&executable)
+        .args(std::env::args_os().skip(1))
+        .env(STATE_ENV, &path)
+        .exec();
+    let _ = std::fs::remove_file(&path);
+    Err(format!(
+        "cannot execute {}: {}",
+        executable.display(),
+        error
+    ))
+}
+
+#[cfg(not(unix))]
+pub fn exec(_proxies: &ProxyContext) -> Result<std::convert::Infallible, String> {
+    Err("handoff is only available on Unix".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    // The state survives the file it is handed over in, and uptime counts from the old start.
+    fn test_state_round_trip() {
+        let state = HandoffState {
+            version: "1.0.0".to_string(),
+            session_started_at: 1_000,
+            paused: true,
+            sticky_proxies: BTreeMap::from([("42".to_string(), "a:1".to_string())]),
+        };
+        let dir = tempdir().unwrap();
+        let path = state_path(&dir.path().join("config.json"));
+        state.save(&path).unwrap();
+        assert_eq!(HandoffState::load_from_file(&path).unwrap(), state);
+        assert_eq!(state.uptime(1_090), Duration::from_secs(90));
+        assert_eq!(state.uptime(500), Duration::ZERO);
+    }
+}
@@ -0,0 +1,283 @@
+//! Task history queries
+//!
+//! The web dashboard (`--web-addr`) serves the task states kept in `task_states.json`, so
+//! external dashboards can follow a node's history without reading the file:
+//!
+//! - `GET /api/history` - tasks, most recently changed first, a page at a time
+//!   (`offset`, `limit`), optionally only those in one `state`
+//! - `GET /api/history/summary` - the number of tasks per state, overall and per UTC day
+//!
+//! Both take `since` and `until` to filter on the time a task entered its current state, as
+//! Unix seconds, an RFC 3339 time or a `YYYY-MM-DD` date (midnight UTC).
+//!
+//! This is not a complete record: `task_states.json` holds every task in progress but only the
+//! 200 most recently finished ones (`task_lifecycle::MAX_FINISHED_TASKS`), so pages and summary
+//! counts never reach further back than that.
+
+use crate::task_lifecycle::{TaskRecord, TaskState, TaskStates};
+use chrono::{DateTime, NaiveDate};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Tasks per page unless `limit` says otherwise.
+const DEFAULT_LIMIT: usize = 50;
+
+/// Most tasks in one page.
+const MAX_LIMIT: usize = 200;
+
+/// Filters and page of a history request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryQuery {
+    /// Only tasks that entered their state at or after this time (seconds since the Unix epoch)
+    pub since: Option<u64>,
+    /// Only tasks that entered their state before this time
+    pub until: Option<u64>,
+    /// Only tasks in this state
+    pub state: Option<TaskState>,
+    /// Tasks to skip
+    pub offset: usize,
+    /// Tasks to return
+    pub limit: usize,
+}
+
+impl Default for HistoryQuery {
+    fn default() -> Self {
+        Self {
+            since: None,
+            until: None,
+            state: None,
+            offset: 0,
+            limit: DEFAULT_LIMIT,
+        }
+    }
+}
+
+impl HistoryQuery {
+    /// Parses the query string of a request (without the `?`).
+    ///
+    /// # Errors
+    /// Returns a description of the first parameter that is unknown or malformed.
+    pub fn parse(query: &str) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value =
+                urlencoding::decode(value).map_err(|_| format!("{} is not valid UTF-8", name))?;
+            match name {
+                "since" => parsed.since = Some(parse_time(&value)?),
+                "until" => parsed.until = Some(parse_time(&value)?),
+                "state" => {
+                    parsed.state = Some(
+                        serde_json::from_value(serde_json::Value::String(value.to_string()))
+                            .map_err(|_| format!("unknown state: {}", value))?,
+                    )
+                }
+                "offset" => {
+                    parsed.offset = value
+                        .parse()
+                        .map_err(|_| format!("invalid offset: {}", value))?
+                }
+                "limit" => match value.parse() {
+                    Ok(limit) if (1..=MAX_LIMIT).contains(&limit) => parsed.limit = limit,
+                    _ => return Err(format!("limit must be between 1 and {}", MAX_LIMIT)),
+                },
+                _ => return Err(format!("unknown parameter: {}", name)),
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn matches(&self, record: &TaskRecord) -> bool {
+        self.since.is_none_or(|since| record.since >= since)
+            && self.until.is_none_or(|until| record.since < until)
+            && self.state.is_none_or(|state| record.state == state)
+    }
+}
+
+/// Parses a time given as Unix seconds, RFC 3339 or a `YYYY-MM-DD` date.
+fn parse_time(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Ok(seconds);
+    }
+    let seconds = match DateTime::parse_from_rfc3339(value) {
+        Ok(time) => time.timestamp(),
+        Err(_) => NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .map_err(|_| format!("invalid time: {}", value))?
+            .and_hms_opt(0, 0, 0)
+            .unwrap_or_default()
+            .and_utc()
+            .timestamp(),
+    };
+    Ok(u64::try_from(seconds).unwrap_or_default())
+}
+
+/// One task of the history.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub task_id: String,
+    #[serde(flatten)]
+    pub record: TaskRecord,
+}
+
+/// A page of the history.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct HistoryPage {
+    /// Tasks matching the filters, on all pages
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+    pub tasks: Vec<HistoryEntry>,
+}
+
+/// Task counts per state.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct HistorySummary {
+    pub total: usize,
+    pub by_state: BTreeMap<String, usize>,
+    /// Counts per state for each UTC day (`YYYY-MM-DD`) with tasks
+    pub by_day: BTreeMap<String, BTreeMap<String, usize>>,
+}
+
+/// The tasks matching `query`, most recently changed first, ties broken by task ID.
+fn matching<'a>(states: &'a TaskStates, query: &HistoryQuery) -> Vec<(&'a String, &'a TaskRecord)> {
+    let mut tasks: Vec<_> = states
+        .tasks
+        .iter()
+        .filter(|(_, record)| query.matches(record))
+        .collect();
+    tasks.sort_by(|(a_id, a), (b_id, b)| b.since.cmp(&a.since).then_with(|| a_id.cmp(b_id)));
+    tasks
+}
+
+/// The page of the history `query` asks for.
+pub fn page(states: &TaskStates, query: &HistoryQuery) -> HistoryPage {
+    let tasks = matching(states, query);
+    HistoryPage {
+        total: tasks.len(),
+        offset: query.offset,
+        limit: query.limit,
+        tasks: tasks
+            .into_iter()
+            .skip(query.offset)
+            .take(query.limit)
+            .map(|(task_id, record)| HistoryEntry {
+                task_id: task_id.clone(),
+                record: record.clone(),
+            })
+            .collect(),
+    }
+}
+
+/// Counts of the tasks matching `query`; its page is ignored.
+pub fn summary(states: &TaskStates, query: &HistoryQuery) -> HistorySummary {
+    let tasks = matching(states, query);
+    let mut by_state = BTreeMap::new();
+    let mut by_day: BTreeMap<String, BTreeMap<String, usize>> = BTreeMap::new();
+    for (_, record) in &tasks {
+        *by_state.entry(record.state.to_string()).or_default() += 1;
+        let day = DateTime::from_timestamp(i64::try_from(record.since).unwrap_or_default(), 0)
+            .unwrap_or_default()
+            .format("%Y-%m-%d")
+            .to_string();
+        *by_day
+            .entry(day)
+            .or_default()
+            .entry(record.state.to_string())
+            .or_default() += 1;
+    }
+    HistorySummary {
+        total: tasks.len(),
+        by_state,
+        by_day,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn states() -> TaskStates {
+        let mut states = TaskStates::default();
+        for (task_id, state, since) in [
+            ("a", TaskState::Accepted, 1_700_000_000),
+            ("b", TaskState::Rejected, 1_700_000_100),
+            ("c", TaskState::Accepted, 1_700_090_000),
+            ("d", TaskState::Proving, 1_700_090_100),
+        ] {
+            states.tasks.insert(
+                task_id.to_string(),
+                TaskRecord {
+                    state,
+                    since,
+                    environment: None,
+                },
+            );
+        }
+        states
+    }
+
+    #[test]
+    // Parameters are decoded and validated; times may be Unix seconds, RFC 3339 or a date.
+    fn test_parse_query() {
+        let query = HistoryQuery::parse(
+            "since=2023-11-14&until=2023-11-15T00%3A00%3A00Z&state=accepted&offset=5&limit=10",
+        )
+        .unwrap();
+        assert_eq!(
+            query,
+            HistoryQuery {
+                since: Some(1_699_920_000),
+                until: Some(1_700_006_400),
+                state: Some(TaskState::Accepted),
+                offset: 5,
+                limit: 10,
+            }
+        );
+        assert_eq!(HistoryQuery::parse("").unwrap(), HistoryQuery::default());
+        assert_eq!(
+            HistoryQuery::parse("since=1700000000").unwrap().since,
+            Some(1_700_000_000)
+        );
+        assert!(HistoryQuery::parse("state=done").is_err());
+        assert!(HistoryQuery::parse("limit=0").is_err());
+        assert!(HistoryQuery::parse("limit=1000").is_err());
+        assert!(HistoryQuery::parse("since=yesterday").is_err());
+        assert!(HistoryQuery::parse("sort=asc").is_err());
+    }
+
+    #[test]
+    // Pages are most recent first and report the total before paging.
+    fn test_page() {
+        let states = states();
+        let page = page(
+            &states,
+            &HistoryQuery {
+                offset: 1,
+                limit: 2,
+                ..HistoryQuery::default()
+            },
+        );
+        assert_eq!(page.total, 4);
+        let ids: Vec<_> = page.tasks.iter().map(|t| t.task_id.as_str()).collect();
+        assert_eq!(ids, ["c", "b"]);
+
+        let accepted = page(
+            &states,
+            &HistoryQuery::parse("state=accepted&until=1700090000").unwrap(),
+        );
+        assert_eq!(accepted.total, 1);
+        assert_eq!(accepted.tasks[0].task_id, "a");
+    }
+
+    #[test]
+    // The summary counts tasks per state, overall and per UTC day.
+    fn test_summary() {
+        let summary = summary(&states(), &HistoryQuery::default());
+        assert_eq!(summary.total, 4);
+        assert_eq!(summary.by_state["accepted"], 2);
+        assert_eq!(summary.by_day.len(), 2);
+        assert_eq!(summary.by_day["2023-11-14"]["rejected"], 1);
+        assert_eq!(summary.by_day["2023-11-15"]["proving"], 1);
+    }
+}
@@ -0,0 +1,286 @@
+//! Completion hooks
+//!
+//! Scripts from the `hooks` entry of the config file run when a task completes or fails and
+//! when the session ends, so users can wire the node into anything (chat notifications,
+//! accounting, restarts) without a built-in integration:
+//!
+//! ```json
+//! "hooks": {
+//!   "on_task_complete": "/usr/local/bin/nexus-task-done",
+//!   "on_task_failed": "/usr/local/bin/nexus-task-failed",
+//!   "on_session_end": "/usr/local/bin/nexus-session-end",
+//!   "timeout_secs": 30
+//! }
+//! ```
+//!
+//! Each hook is the path of an executable, run without a shell. It gets the event as one JSON
+//! object on stdin, and each of its fields as a `NEXUS_*` variable as well:
+//!
+//! ```json
+//! {"hook":"on_task_failed","timestamp":"...","environment":"production","task_id":"123",
+//!  "program_id":"fast-fib","node_id":42,"stage":"submit","error":"HTTP 500"}
+//! ```
+//!
+//! (`NEXUS_HOOK=on_task_failed`, `NEXUS_TASK_ID=123`, ...). A task completes when its proof is
+//! submitted; it fails when proving fails or the orchestrator rejects its submission (one left
+//! pending by a network error or maintenance is retried, not failed). Hooks never hold up
+//! proving: task hooks run in the background, at most `MAX_RUNNING` at a time (others are
+//! skipped), and a script still running after `timeout_secs` is killed. Failures are logged and
+//! otherwise ignored. The session hook is waited for (up to the timeout) before the node exits.
+
+use crate::environment::Environment;
+use crate::task::Task;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::process::Stdio;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Semaphore;
+
+/// Time a hook may run, unless `timeout_secs` is set
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Task hooks running at once; further ones are skipped until one finishes
+const MAX_RUNNING: usize = 4;
+
+/// Scripts to run on task and session events.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Hooks {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_task_complete: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_task_failed: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_session_end: Option<String>,
+    /// Seconds a script may run before it is killed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+}
+
+impl Hooks {
+    /// Whether no hook is configured.
+    pub fn is_empty(&self) -> bool {
+        self == &Hooks::default()
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout_secs
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_TIMEOUT)
+    }
+}
+
+/// The configured hooks and the label of the environment they report
+static HOOKS: OnceLock<(Hooks, String)> = OnceLock::new();
+
+static RUNNING: Semaphore = Semaphore::const_new(MAX_RUNNING);
+
+/// Runs `hooks` on the node's events from now on. Only the first call has an effect.
+pub fn set_hooks(hooks: Hooks, environment: &Environment) {
+    let _ = HOOKS.set((hooks, environment.label()));
+}
+
+/// The event passed to a hook: `fields` with the hook name, time and environment.
+fn payload(hook: &str, environment: &str, fields: Map<String, Value>) -> Value {
+    let mut payload = Map::new();
+    payload.insert("hook".to_string(), Value::from(hook));
+    payload.insert(
+        "timestamp".to_string(),
+        Value::from(chrono::Utc::now().to_rfc3339()),
+    );
+    payload.insert("environment".to_string(), Value::from(environment));
+    payload.extend(fields);
+    Value::Object(payload)
+}
+
+/// The fields of `payload` as `NEXUS_*` variables; null fields are left out.
+fn env_vars(payload: &Value) -> Vec<(String, String)> {
+    let Value::Object(fields) = payload else {
+        return Vec::new();
+    };
+    fields
+        .iter()
+        .filter_map(|(key, value)| {
+            let value = match value {
+                Value::Null => return None,
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            Some((format!("NEXUS_{}", key.to_uppercase()), value))
+        })
+        .collect()
+}
+
+/// Runs `script` with `payload`, killing it after `timeout`.
+async fn run(script: &str, payload: &Value, timeout: Duration) -> Result<(), String> {
+    let mut child = tokio::process::Command::new(script)
+        .envs(env_vars(payload))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("cannot run {}: {}", script, e))?;
+    let stdin = child.stdin.take();
+    let finished = tokio::time::timeout(timeout, async {
+        if let Some(mut stdin) = stdin {
+            // Scripts that ignore stdin may exit before reading it
+            let _ = stdin.write_all(payload.to_string().as_bytes()).await;
+        }
+        child.wait().await
+    })
+    .await;
+    match finished {
+        Ok(Ok(status)) if status.success() => Ok(()),
+        Ok(Ok(status)) => Err(format!("{} exited with {}", script, status)),
+        Ok(Err(e)) => Err(format!("{} could not be waited for: {}", script, e)),
+        // Dropping the child kills it
+        Err(_) => Err(format!(
+            "{} was killed after {}s",
+            script,
+            timeout.as_secs()
+        )),
+    }
+}
+
+/// Runs a task hook in the background, if it is configured and there is room.
+fn spawn_task_hook(
+    name: &'static str,
+    select: fn(&Hooks) -> Option<&String>,
+    fields: Map<String, Value>,
+) {
+    let Some((hooks, environment)) = HOOKS.get() else {
+        return;
+    };
+    let Some(script) = select(hooks).cloned() else {
+        return;
+    };
+    let Ok(permit) = RUNNING.try_acquire() else {
+        crate::logging::warn(format!(
+            "Skipped the {} hook: {} hooks are still running",
+            name, MAX_RUNNING
+        ));
+        return;
+    };
+    let payload = payload(name, environment, fields);
+    let timeout = hooks.timeout();
+    tokio::spawn(async move {
+        if let Err(e) = run(&script, &payload, timeout).await {
+            crate::logging::warn(format!("The {} hook failed: {}", name, e));
+        }
+        drop(permit);
+    });
+}
+
+fn task_fields(task: &Task) -> Map<String, Value> {
+    let mut fields = Map::new();
+    fields.insert("task_id".to_string(), Value::from(task.task_id.as_str()));
+    fields.insert(
+        "program_id".to_string(),
+        Value::from(task.program_id.as_str()),
+    );
+    fields.insert("node_id".to_string(), Value::from(task.node_id));
+    fields
+}
+
+/// Runs the `on_task_complete` hook for a task whose proof was submitted.
+pub fn task_complete(task: &Task) {
+    spawn_task_hook(
+        "on_task_complete",
+        |hooks| hooks.on_task_complete.as_ref(),
+        task_fields(task),
+    );
+}
+
+/// Runs the `on_task_failed` hook for a task that failed at `stage` ("prove" or "submit").
+pub fn task_failed(task: &Task, stage: &str, error: &str) {
+    let mut fields = task_fields(task);
+    fields.insert("stage".to_string(), Value::from(stage));
+    fields.insert("error".to_string(), Value::from(error));
+    spawn_task_hook(
+        "on_task_failed",
+        |hooks| hooks.on_task_failed.as_ref(),
+        fields,
+    );
+}
+
+/// Runs the `on_session_end` hook with the session's totals, and waits for it.
+pub async fn session_end() {
+    let Some((hooks, environment)) = HOOKS.get() else {
+        return;
+    };
+    let Some(script) = &hooks.on_session_end else {
+        return;
+    };
+    let stats = crate::nodes::stats();
+    let mut fields = Map::new();
+    fields.insert(
+        "uptime_secs".to_string(),
+        Value::from(crate::control::control_state().status().uptime_secs),
+    );
+    fields.insert(
+        "tasks_fetched".to_string(),
+        Value::from(stats.values().map(|s| s.tasks_fetched).sum::<u64>()),
+    );
+    fields.insert(
+        "proofs_accepted".to_string(),
+        Value::from(stats.values().map(|s| s.proofs_submitted).sum::<u64>()),
+    );
+    fields.insert(
+        "submissions_failed".to_string(),
+        Value::from(stats.values().map(|s| s.submissions_failed).sum::<u64>()),
+    );
+    let payload = payload("on_session_end", environment, fields);
+    if let Err(e) = run(script, &payload, hooks.timeout()).await {
+        crate::logging::warn(format!("The on_session_end hook failed: {}", e));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Every field but nulls becomes a NEXUS_ variable, strings without their quotes.
+    fn test_env_vars() {
+        let mut task = Task::new("123".to_string(), "fast-fib".to_string(), Vec::new());
+        task.node_id = Some(42);
+        let payload = payload("on_task_complete", "production", task_fields(&task));
+        let vars = env_vars(&payload);
+        assert!(vars.contains(&("NEXUS_HOOK".to_string(), "on_task_complete".to_string())));
+        assert!(vars.contains(&("NEXUS_TASK_ID".to_string(), "123".to_string())));
+        assert!(vars.contains(&("NEXUS_NODE_ID".to_string(), "42".to_string())));
+
+        task.node_id = None;
+        let payload = payload("on_task_complete", "production", task_fields(&task));
+        assert!(
+            !env_vars(&payload)
+                .iter()
+                .any(|(name, _)| name == "NEXUS_NODE_ID")
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    // A script gets the payload on stdin; one that cannot run is reported.
+    async fn test_run_script() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out.json");
+        let script = dir.path().join("hook.sh");
+        std::fs::write(&script, format!("#!/bin/sh\ncat > {}\n", out.display())).unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let payload = payload("on_session_end", "production", Map::new());
+        run(script.to_str().unwrap(), &payload, Duration::from_secs(5))
+            .await
+            .unwrap();
+        let written: Value = serde_json::from_str(&std::fs::read_to_string(&out).unwrap()).unwrap();
+        assert_eq!(written["hook"], "on_session_end");
+
+        let missing = run("/nonexistent/hook", &payload, Duration::from_secs(1)).await;
+        assert!(missing.is_err());
+    }
+}
@@ -0,0 +1,161 @@
+//! Binary integrity self-check
+//!
+//! Release binaries are built with `NEXUS_RELEASE_ASSET` set to the name they are published
+//! under (e.g. `nexus-network-linux-x86_64`), and embed it. At startup the node hashes its own
+//! executable and compares it with the `.sha256` file published next to that asset in the
+//! GitHub release of the running version. A mismatch means a partial download or a modified
+//! binary, which otherwise shows up as inexplicable crashes in the middle of a proof. Builds
+//! from source embed no asset name and are not checked; when the release metadata cannot be
+//! fetched the check is skipped.
+
+use reqwest::ClientBuilder;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::time::Duration;
+
+/// Where the published checksums of a release are downloaded from.
+const RELEASE_DOWNLOAD_URL: &str = "https://github.com/nexus-xyz/nexus-cli/releases/download";
+
+/// How long fetching the published checksum may take before the check is skipped.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The release asset this binary was published as, if it is a release build.
+pub fn release_asset() -> Option<&'static str> {
+    option_env!("NEXUS_RELEASE_ASSET").filter(|asset| !asset.is_empty())
+}
+
+/// A binary whose hash differs from the published one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub asset: &'static str,
+    pub expected: String,
+    pub actual: String,
+}
+
+/// Compares the running executable with its published checksum.
+///
+/// Returns `Ok(None)` if the binary matches or is not a release build.
+///
+/// # Errors
+/// Returns an error if the executable cannot be read or the checksum cannot be fetched.
+pub async fn check() -> Result<Option<Mismatch>, Box<dyn std::error::Error + Send + Sync>> {
+    let Some(asset) = release_asset() else {
+        return Ok(None);
+    };
+    let exe = std::env::current_exe()?;
+    let actual = tokio::task::spawn_blocking(move || sha256_file(&exe)).await??;
+
+    let url = format!(
+        "{}/v{}/{}.sha256",
+        RELEASE_DOWNLOAD_URL,
+        env!("CARGO_PKG_VERSION"),
+        asset
+    );
+    let client = ClientBuilder::new()
+        .timeout(FETCH_TIMEOUT)
+        .user_agent(format!("nexus-cli/{}", env!("CARGO_PKG_VERSION")))
+        .build()?;
+    let response = client.get(&url).send().await?;
+    if !response.status().is_success() {
+        return Err(format!("{} returned status {}", url, response.status()).into());
+    }
+    let expected = parse_checksum(&response.text().await?)
+        .ok_or_else(|| format!("{} is not a SHA-256 checksum", url))?;
+
+    if expected == actual {
+        Ok(None)
+    } else {
+        Ok(Some(Mismatch {
+            asset,
+            expected,
+            actual,
+        }))
+    }
+}
+
+/// Warns on stderr if the running binary does not match its published checksum.
+///
+/// Failures to check are only logged, since the metadata is not always reachable.
+pub async fn warn_if_modified() {
+    match check().await {
+        Ok(None) => {}
+        Ok(Some(mismatch)) => {
+            crate::logging::warn(format!(
+                "⚠️  This binary does not match the published {} of v{} (SHA-256 {}, expected {}).",
+                mismatch.asset,
+                env!("CARGO_PKG_VERSION"),
+                mismatch.actual,
+                mismatch.expected
+            ));
+            crate::logging::warn(
+                "   It may be a partial download or modified, and can crash in the middle of a proof. Reinstall it from https://github.com/nexus-xyz/nexus-cli/releases.",
+            );
+        }
+        Err(e) => log::debug!("Skipped the binary integrity check: {}", e),
+    }
+}
+
+/// The lowercase hex SHA-256 of a file.
+pub fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+/// Reads the hash from a `.sha256` file: either the bare hash or `sha256sum` output.
+fn parse_checksum(text: &str) -> Option<String> {
+    let hash = text.split_whitespace().next()?;
+    (hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+        .then(|| hash.to_ascii_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    // Files are hashed as sha256sum would.
+    fn test_sha256_file() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"abc").unwrap();
+        assert_eq!(
+            sha256_file(file.path()).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    // Bare hashes and sha256sum lines are accepted, anything else is not.
+    fn test_parse_checksum() {
+        let hash = "08d8fb3e3e8a133c205754e77e833956753b95a049886145e20a23ecb8ee5c2b";
+        assert_eq!(
+            parse_checksum(&format!("{}\n", hash)).as_deref(),
+            Some(hash)
+        );
+        assert_eq!(
+            parse_checksum(&format!(
+                "{}  nexus-network-linux-x86_64\n",
+                hash.to_uppercase()
+            ))
+            .as_deref(),
+            Some(hash)
+        );
+        assert_eq!(parse_checksum("Not Found"), None);
+        assert_eq!(parse_checksum(""), None);
+    }
+}
@@ -0,0 +1,356 @@
+//! Submission latency SLO
+//!
+//! Every proof submission's round trip is recorded with the route it took (a proxy, or
+//! `direct`), whether or not it succeeded: a submission that timed out or was rejected took
+//! its time all the same. Only submissions for which no route could be chosen are left out.
+//! With `--submit-slo 2500`, the node raises an alert when the p95 latency of the last few
+//! minutes stays above 2.5s for `--submit-slo-minutes` (default 10), and again when it
+//! recovers.
+//!
+//! Tagging by route tells the two usual causes apart: when only some routes are slow, the
+//! proxies behind them are degrading; when every route is slow, the orchestrator is. With a
+//! single route there is nothing to compare, so a proxied breach is reported as undetermined.
+//!
+//! Latencies are kept per environment, so staging and production are never mixed.
+
+use crate::environment::Environment;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt::Display;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Submissions counted towards the percentiles.
+const WINDOW: Duration = Duration::from_secs(300);
+
+/// Most samples kept, however many submissions the window holds.
+const MAX_SAMPLES: usize = 1000;
+
+/// Samples a route needs before its own p95 is trusted.
+const MIN_ROUTE_SAMPLES: usize = 5;
+
+/// Default time the p95 must stay above the SLO before an alert.
+const DEFAULT_SUSTAIN: Duration = Duration::from_secs(600);
+
+/// Route label for requests that do not go through a proxy.
+pub const DIRECT: &str = "direct";
+
+/// The latency objective for proof submissions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SloConfig {
+    /// Highest acceptable p95 latency
+    pub p95: Duration,
+    /// How long the p95 must stay above it before an alert
+    pub sustain: Duration,
+}
+
+impl SloConfig {
+    /// The objective from command-line flags, or `None` if none was set.
+    pub fn from_flags(p95_ms: Option<u64>, sustain_minutes: Option<u64>) -> Option<Self> {
+        p95_ms.map(|ms| Self {
+            p95: Duration::from_millis(ms),
+            sustain: sustain_minutes
+                .map(|minutes| Duration::from_secs(minutes * 60))
+                .unwrap_or(DEFAULT_SUSTAIN),
+        })
+    }
+}
+
+/// Latency percentiles over the recent window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Percentiles {
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    pub samples: usize,
+}
+
+impl Percentiles {
+    fn of(mut latencies: Vec<Duration>) -> Option<Self> {
+        if latencies.is_empty() {
+            return None;
+        }
+        latencies.sort();
+        let at = |q: f64| {
+            let rank = ((latencies.len() as f64 * q).ceil() as usize).clamp(1, latencies.len());
+            latencies[rank - 1]
+        };
+        Some(Self {
+            p50: at(0.50),
+            p95: at(0.95),
+            p99: at(0.99),
+            samples: latencies.len(),
+        })
+    }
+}
+
+impl Display for Percentiles {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "p50 {}ms, p95 {}ms, p99 {}ms over {} submissions",
+            self.p50.as_millis(),
+            self.p95.as_millis(),
+            self.p99.as_millis(),
+            self.samples
+        )
+    }
+}
+
+/// The likely cause of a breach.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diagnosis {
+    /// Only these routes are slow; the others meet the objective.
+    ProxiesDegrading(Vec<String>),
+    /// Every route is slow, or requests go direct.
+    OrchestratorSlow,
+    /// Everything went through one proxy, so the two cannot be told apart.
+    Undetermined,
+}
+
+impl Display for Diagnosis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Diagnosis::ProxiesDegrading(routes) => {
+                write!(f, "proxies degrading: {}", routes.join(", "))
+            }
+            Diagnosis::OrchestratorSlow => write!(f, "orchestrator slow on every route"),
+            Diagnosis::Undetermined => {
+                write!(f, "cause undetermined, all submissions used one proxy")
+            }
+        }
+    }
+}
+
+/// A change in SLO compliance worth announcing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SloNotice {
+    Breached {
+        slo: Duration,
+        percentiles: Percentiles,
+        diagnosis: Diagnosis,
+    },
+    Recovered {
+        slo: Duration,
+        percentiles: Percentiles,
+    },
+}
+
+impl Display for SloNotice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SloNotice::Breached {
+                slo,
+                percentiles,
+                diagnosis,
+            } => write!(
+                f,
+                "Submission latency above the {}ms p95 objective ({}); {}",
+                slo.as_millis(),
+                percentiles,
+                diagnosis
+            ),
+            SloNotice::Recovered { slo, percentiles } => write!(
+                f,
+                "Submission latency back within the {}ms p95 objective ({})",
+                slo.as_millis(),
+                percentiles
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct LatencyTracker {
+    samples: VecDeque<(Instant, String, Duration)>,
+    /// When the p95 first exceeded the objective, while it still does.
+    breach_since: Option<Instant>,
+    alerted: bool,
+}
+
+impl LatencyTracker {
+    fn record(&mut self, now: Instant, route: &str, latency: Duration) {
+        if self.samples.len() >= MAX_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((now, route.to_string(), latency));
+    }
+
+    fn prune(&mut self, now: Instant) {
+        while let Some((at, _, _)) = self.samples.front() {
+            if now.duration_since(*at) <= WINDOW {
+                break;
+            }
+            self.samples.pop_front();
+        }
+    }
+
+    fn percentiles(&self) -> Option<Percentiles> {
+        Percentiles::of(
+            self.samples
+                .iter()
+                .map(|(_, _, latency)| *latency)
+                .collect(),
+        )
+    }
+
+    fn diagnose(&self, slo: Duration) -> Diagnosis {
+        let mut by_route: BTreeMap<&str, Vec<Duration>> = BTreeMap::new();
+        for (_, route, latency) in &self.samples {
+            by_route.entry(route).or_default().push(*latency);
+        }
+        let routes: Vec<(&str, Percentiles)> = by_route
+            .into_iter()
+            .filter_map(|(route, latencies)| Percentiles::of(latencies).map(|p| (route, p)))
+            .filter(|(_, p)| p.samples >= MIN_ROUTE_SAMPLES)
+            .collect();
+        let slow: Vec<String> = routes
+            .iter()
+            .filter(|(_, p)| p.p95 > slo)
+            .map(|(route, _)| route.to_string())
+            .collect();
+        match routes.as_slice() {
+            [] | [(DIRECT, _)] => Diagnosis::OrchestratorSlow,
+            [_] => Diagnosis::Undetermined,
+            _ if slow.len() == routes.len() || slow.is_empty() => Diagnosis::OrchestratorSlow,
+            _ if slow.iter().any(|route| route == DIRECT) => Diagnosis::OrchestratorSlow,
+            _ => Diagnosis::ProxiesDegrading(slow),
+        }
+    }
+
+    fn evaluate(&mut self, now: Instant, config: SloConfig) -> Option<SloNotice> {
+        self.prune(now);
+        let percentiles = self.percentiles()?;
+        if percentiles.p95 <= config.p95 {
+            self.breach_since = None;
+            if std::mem::take(&mut self.alerted) {
+                return Some(SloNotice::Recovered {
+                    slo: config.p95,
+                    percentiles,
+                });
+            }
+            return None;
+        }
+        let since = *self.breach_since.get_or_insert(now);
+        if self.alerted || now.duration_since(since) < config.sustain {
+            return None;
+        }
+        self.alerted = true;
+        Some(SloNotice::Breached {
+            slo: config.p95,
+            percentiles,
+            diagnosis: self.diagnose(config.p95),
+        })
+    }
+}
+
+/// One tracker per environment, keyed by its label, so a node that switches environments
+/// never compares one orchestrator's latency with another's.
+static TRACKERS: OnceLock<Mutex<HashMap<String, LatencyTracker>>> = OnceLock::new();
+static CONFIG: OnceLock<SloConfig> = OnceLock::new();
+
+fn trackers() -> &'static Mutex<HashMap<String, LatencyTracker>> {
+    TRACKERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Sets the objective that submissions are checked against.
+pub fn set_slo(config: SloConfig) {
+    let _ = CONFIG.set(config);
+}
+
+/// Records a submission's round trip to `environment` through `route` (a proxy, or
+/// [`DIRECT`]).
+pub fn record(environment: &Environment, route: &str, latency: Duration) {
+    if let Ok(mut trackers) = trackers().lock() {
+        trackers
+            .entry(environment.label())
+            .or_default()
+            .record(Instant::now(), route, latency);
+    }
+}
+
+/// A breach or recovery of submissions to `environment` to announce, if an objective is set.
+pub fn check(environment: &Environment) -> Option<SloNotice> {
+    let config = *CONFIG.get()?;
+    trackers()
+        .lock()
+        .ok()?
+        .get_mut(&environment.label())?
+        .evaluate(Instant::now(), config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SLO: SloConfig = SloConfig {
+        p95: Duration::from_millis(1000),
+        sustain: Duration::from_secs(60),
+    };
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    // Percentiles should use the nearest-rank method.
+    fn test_percentiles() {
+        let p = Percentiles::of((1..=100).map(ms).collect()).unwrap();
+        assert_eq!((p.p50, p.p95, p.p99), (ms(50), ms(95), ms(99)));
+        assert_eq!(Percentiles::of(Vec::new()), None);
+    }
+
+    #[test]
+    // A breach should be announced once it has lasted long enough, then its recovery.
+    fn test_breach_must_be_sustained() {
+        let start = Instant::now();
+        let mut tracker = LatencyTracker::default();
+        for _ in 0..10 {
+            tracker.record(start, DIRECT, ms(3000));
+        }
+        assert_eq!(tracker.evaluate(start, SLO), None);
+        let later = start + Duration::from_secs(61);
+        assert!(matches!(
+            tracker.evaluate(later, SLO),
+            Some(SloNotice::Breached {
+                diagnosis: Diagnosis::OrchestratorSlow,
+                ..
+            })
+        ));
+        assert_eq!(tracker.evaluate(later, SLO), None);
+
+        // Slow samples age out of the window
+        let recovered = later + WINDOW;
+        tracker.record(recovered, DIRECT, ms(100));
+        assert!(matches!(
+            tracker.evaluate(recovered, SLO),
+            Some(SloNotice::Recovered { .. })
+        ));
+    }
+
+    #[test]
+    // Slow proxies should be told apart from a slow orchestrator.
+    fn test_diagnosis_by_route() {
+        let now = Instant::now();
+        let mut tracker = LatencyTracker::default();
+        for _ in 0..5 {
+            tracker.record(now, "10.0.0.1:8080", ms(4000));
+            tracker.record(now, "10.0.0.2:8080", ms(200));
+        }
+        assert_eq!(
+            tracker.diagnose(SLO.p95),
+            Diagnosis::ProxiesDegrading(vec!["10.0.0.1:8080".to_string()])
+        );
+
+        for _ in 0..5 {
+            tracker.record(now, "10.0.0.2:8080", ms(4000));
+            tracker.record(now, "10.0.0.2:8080", ms(4000));
+        }
+        assert_eq!(tracker.diagnose(SLO.p95), Diagnosis::OrchestratorSlow);
+
+        let mut single = LatencyTracker::default();
+        for _ in 0..5 {
+            single.record(now, "10.0.0.1:8080", ms(4000));
+        }
+        assert_eq!(single.diagnose(SLO.p95), Diagnosis::Undetermined);
+    }
+}
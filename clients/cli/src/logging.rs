@@ -1,9 +1,99 @@
 use crate::error_classifier::LogLevel;
+use crate::events::Event;
+use clap::ValueEnum;
+use std::collections::BTreeMap;
 use std::env;
+use std::fmt::Display;
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
 
-pub fn get_rust_log_level() -> LogLevel {
-    let rust_log = env::var("RUST_LOG").unwrap_or_else(|_| "info".to_string());
-    parse_rust_log_level(&rust_log)
+/// Level set at runtime (control command or dashboard key), overriding `RUST_LOG`.
+/// `u8::MAX` while unset.
+static LOG_LEVEL_OVERRIDE: AtomicU8 = AtomicU8::new(u8::MAX);
+
+/// Modules that can be given their own level, with the code paths they cover.
+pub const MODULES: &[(&str, &str)] = &[
+    ("fetcher", "nexus_network::workers::online"),
+    ("orchestrator", "nexus_network::orchestrator"),
+    ("prover", "nexus_network::prover"),
+    ("proxy", "nexus_network::proxy"),
+    ("scheduler", "nexus_network::profiles"),
+    ("submitter", "nexus_network::workers::online"),
+    ("version", "nexus_network::version_checker"),
+];
+
+/// Log levels in `RUST_LOG` syntax: an optional default level and levels for single modules,
+/// e.g. `info,orchestrator=debug,prover=warn`. Modules not in `MODULES` (e.g. `hyper=debug`)
+/// apply only to lines logged by dependencies.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogFilter {
+    pub default: Option<LogLevel>,
+    pub modules: BTreeMap<String, LogLevel>,
+}
+
+impl FromStr for LogFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut filter = LogFilter::default();
+        for directive in s.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            let (module, level) = match directive.split_once('=') {
+                Some((module, level)) => (Some(module.trim()), level.trim()),
+                None => (None, directive),
+            };
+            let level = LogLevel::from_str(level, true)
+                .map_err(|_| format!("'{}' is not a log level", level))?;
+            match module {
+                // The crate itself, as in `RUST_LOG=nexus_network=debug`
+                None | Some("nexus_network" | "nexus_cli") => filter.default = Some(level),
+                Some(module) => {
+                    filter.modules.insert(module.to_string(), level);
+                }
+            }
+        }
+        Ok(filter)
+    }
+}
+
+impl LogFilter {
+    /// The filter as `tracing` directives, with module names expanded to their code paths.
+    fn directives(&self) -> String {
+        let mut directives = vec![self.default.unwrap_or(LogLevel::Info).to_string()];
+        for (module, level) in &self.modules {
+            let target = MODULES
+                .iter()
+                .find(|(name, _)| name == module)
+                .map_or(module.as_str(), |(_, target)| *target);
+            directives.push(format!("{}={}", target, level));
+        }
+        directives.join(",")
+    }
+}
+
+static LOG_FILTER: OnceLock<LogFilter> = OnceLock::new();
+
+/// Uses `filter` instead of `RUST_LOG`. Only the first call has an effect, and only before
+/// anything is logged.
+pub fn set_log_filter(filter: LogFilter) {
+    let _ = LOG_FILTER.set(filter);
+}
+
+/// The levels given with `--log-level`, or else in `RUST_LOG`.
+fn log_filter() -> &'static LogFilter {
+    LOG_FILTER.get_or_init(|| {
+        let rust_log = env::var("RUST_LOG").unwrap_or_else(|_| "info".to_string());
+        rust_log.parse().unwrap_or_else(|_| LogFilter {
+            default: Some(parse_rust_log_level(&rust_log)),
+            ..LogFilter::default()
+        })
+    })
+}
+
+/// The level of `module`, if one was given for it.
+pub fn module_level(module: &str) -> Option<LogLevel> {
+    log_filter().modules.get(module).copied()
 }
 
 pub fn parse_rust_log_level(rust_log: &str) -> LogLevel {
@@ -32,10 +122,169 @@ pub fn should_log(event_level: LogLevel, threshold: LogLevel) -> bool {
 }
 
 pub fn should_log_with_env(event_level: LogLevel) -> bool {
-    let threshold = get_rust_log_level();
+    let threshold = current_log_level();
     should_log(event_level, threshold)
 }
 
+/// Changes the log level of this process without a restart.
+pub fn set_log_level(level: LogLevel) {
+    LOG_LEVEL_OVERRIDE.store(level as u8, Ordering::Relaxed);
+}
+
+/// The log level in effect: the one set at runtime, or else the one from `RUST_LOG`.
+pub fn current_log_level() -> LogLevel {
+    match LOG_LEVEL_OVERRIDE.load(Ordering::Relaxed) {
+        0 => LogLevel::Trace,
+        1 => LogLevel::Debug,
+        2 => LogLevel::Info,
+        3 => LogLevel::Warn,
+        4 => LogLevel::Error,
+        _ => log_filter().default.unwrap_or(LogLevel::Info),
+    }
+}
+
+/// The next level when cycling from `level` in the dashboard: info, debug, trace, then back.
+/// Warnings and errors are always shown, so quieter levels are skipped.
+pub fn next_dashboard_level(level: LogLevel) -> LogLevel {
+    match level {
+        LogLevel::Info => LogLevel::Debug,
+        LogLevel::Debug => LogLevel::Trace,
+        LogLevel::Trace | LogLevel::Warn | LogLevel::Error => LogLevel::Info,
+    }
+}
+
+/// How the node writes its log lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum LogFormat {
+    /// Lines for people to read.
+    #[default]
+    Text,
+    /// One JSON object per line, with `node_id`, `task_id`, `proxy` and `duration_ms` fields
+    /// where they apply, for log aggregators.
+    Json,
+}
+
+static LOG_FORMAT: OnceLock<LogFormat> = OnceLock::new();
+
+/// Whether the terminal dashboard is showing, so text lines would garble it.
+static TERMINAL_UI: AtomicBool = AtomicBool::new(false);
+
+/// Writes log lines in `format` from now on, filtered by the levels in effect (see
+/// `set_log_filter`). A `tracing` subscriber writes the lines of dependencies and those logged
+/// through the `log` crate; with JSON it writes every line. Only the first call has an effect.
+pub fn init_log_format(format: LogFormat) {
+    if LOG_FORMAT.set(format).is_err() {
+        return;
+    }
+    let filter = tracing_subscriber::EnvFilter::builder().parse_lossy(log_filter().directives());
+    let _ = match format {
+        LogFormat::Text => tracing_subscriber::fmt()
+            .with_env_filter(filter)
+            .with_writer(text_writer)
+            .try_init(),
+        LogFormat::Json => tracing_subscriber::fmt()
+            .json()
+            .flatten_event(true)
+            .with_current_span(false)
+            .with_span_list(false)
+            .with_env_filter(filter)
+            .with_writer(json_writer)
+            .try_init(),
+    };
+}
+
+/// Stops text lines while the terminal dashboard is showing, and resumes them after.
+pub fn set_terminal_ui(active: bool) {
+    TERMINAL_UI.store(active, Ordering::Relaxed);
+}
+
+/// Text lines go to stderr, except while the dashboard is showing.
+fn text_writer() -> Box<dyn Write> {
+    if TERMINAL_UI.load(Ordering::Relaxed) {
+        Box::new(std::io::sink())
+    } else {
+        Box::new(std::io::stderr())
+    }
+}
+
+/// Whether log lines are written as JSON.
+pub fn is_json() -> bool {
+    LOG_FORMAT.get() == Some(&LogFormat::Json)
+}
+
+/// JSON lines go to stdout, unless progress events already do (see `progress`).
+fn json_writer() -> Box<dyn Write> {
+    if crate::progress::on_stdout() {
+        Box::new(std::io::stderr())
+    } else {
+        Box::new(std::io::stdout())
+    }
+}
+
+/// Writes an informational line: as text on stdout, or as a JSON event.
+pub fn info(message: impl Display) {
+    if is_json() {
+        tracing::info!("{}", message.to_string().trim());
+    } else {
+        println!("{}", message);
+    }
+}
+
+/// Writes a warning: as text on stderr (not while the dashboard is showing), or as a JSON
+/// event.
+pub fn warn(message: impl Display) {
+    if is_json() {
+        tracing::warn!("{}", message.to_string().trim());
+    } else {
+        let _ = writeln!(text_writer(), "{}", message);
+    }
+}
+
+/// Writes an error: as text on stderr (not while the dashboard is showing), or as a JSON
+/// event.
+pub fn error(message: impl Display) {
+    if is_json() {
+        tracing::error!("{}", message.to_string().trim());
+    } else {
+        let _ = writeln!(text_writer(), "{}", message);
+    }
+}
+
+/// Writes a worker event as a headless log line.
+pub fn write_event(event: &Event) {
+    if !is_json() {
+        if crate::progress::on_stdout() {
+            eprintln!("{}", event);
+        } else {
+            println!("{}", event);
+        }
+        return;
+    }
+    let fields = &event.fields;
+    macro_rules! emit {
+        ($level:expr) => {
+            tracing::event!(
+                $level,
+                worker = %event.worker.label(),
+                event_type = %event.event_type,
+                node_id = fields.node_id,
+                task_id = fields.task_id.as_deref(),
+                proxy = fields.proxy.as_deref(),
+                duration_ms = fields.duration_ms,
+                "{}",
+                event.msg
+            )
+        };
+    }
+    match event.log_level {
+        LogLevel::Trace => emit!(tracing::Level::TRACE),
+        LogLevel::Debug => emit!(tracing::Level::DEBUG),
+        LogLevel::Info => emit!(tracing::Level::INFO),
+        LogLevel::Warn => emit!(tracing::Level::WARN),
+        LogLevel::Error => emit!(tracing::Level::ERROR),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -59,6 +308,34 @@ mod tests {
         assert_eq!(parse_rust_log_level("invalid"), LogLevel::Info);
     }
 
+    #[test]
+    fn test_next_dashboard_level() {
+        assert_eq!(next_dashboard_level(LogLevel::Info), LogLevel::Debug);
+        assert_eq!(next_dashboard_level(LogLevel::Debug), LogLevel::Trace);
+        assert_eq!(next_dashboard_level(LogLevel::Trace), LogLevel::Info);
+        assert_eq!(next_dashboard_level(LogLevel::Error), LogLevel::Info);
+    }
+
+    #[test]
+    fn test_parse_log_filter() {
+        let filter: LogFilter = "info, orchestrator=debug,prover=WARN,hyper=trace"
+            .parse()
+            .unwrap();
+        assert_eq!(filter.default, Some(LogLevel::Info));
+        assert_eq!(filter.modules["orchestrator"], LogLevel::Debug);
+        assert_eq!(filter.modules["prover"], LogLevel::Warn);
+        assert_eq!(
+            filter.directives(),
+            "info,hyper=trace,nexus_network::orchestrator=debug,nexus_network::prover=warn"
+        );
+
+        let filter: LogFilter = "nexus_network=debug".parse().unwrap();
+        assert_eq!(filter.default, Some(LogLevel::Debug));
+        assert!(filter.modules.is_empty());
+        assert!("proxy=loud".parse::<LogFilter>().is_err());
+        assert!(!is_json());
+    }
+
     #[test]
     fn test_should_log() {
         assert!(should_log(LogLevel::Error, LogLevel::Debug));
@@ -1,37 +1,122 @@
 // Copyright (c) 2024 Nexus. All rights reserved.
 
+mod accessible;
 mod analytics;
+mod backpressure;
+mod capability;
+mod checkpoint;
 mod config;
+mod config_diff;
+mod config_edit;
+mod config_template;
 mod consts;
+mod container;
+mod control;
+mod difficulty;
+mod duty_cycle;
 mod environment;
+mod error_budget;
 mod error_classifier;
 mod events;
+mod fake_prover;
+mod goal;
+mod handoff;
+mod history;
+mod hooks;
+mod integrity;
 mod keys;
+mod latency_slo;
 mod logging;
+mod maintenance;
+mod memory_limit;
+mod metrics;
 #[path = "proto/nexus.orchestrator.rs"]
 mod nexus_orchestrator;
+mod node_proxies;
+mod nodes;
 mod orchestrator;
+mod pacing;
+mod performance;
+mod polling;
 mod pretty;
+mod profiles;
+mod program_allowlist;
+mod program_concurrency;
+mod program_profiles;
+mod progress;
+mod proof_checkpoint;
 mod prover;
 mod prover_runtime;
 mod proxy;
+mod proxy_check;
+mod proxy_plan;
+mod proxy_reputation;
+mod proxy_secrets;
+mod proxy_stats;
+mod receipts;
+mod reconcile;
 mod register;
+mod remote_control;
+mod rng;
+mod self_test;
+mod session;
+mod sleep_wake;
+mod start_settings;
+mod startup_summary;
+mod status;
+mod status_line;
+mod submission_journal;
+mod submission_queue;
+mod submission_verifier;
+mod support_bundle;
 pub mod system;
 mod task;
 mod task_cache;
+mod task_filter;
+mod task_lifecycle;
+mod task_profiler;
 mod ui;
+mod uptime;
 mod version_checker;
 mod version_requirements;
+mod wallets;
+mod web_dashboard;
 mod workers;
 
+use crate::capability::{CapabilityReport, get_capability_path};
 use crate::config::{Config, get_config_path};
-use crate::environment::Environment;
+use crate::config_diff::FlagOverrides;
+use crate::control::Command as ControlCommand;
+use crate::difficulty::{Benchmark, benchmark_path};
+use crate::duty_cycle::DutyCycle;
+use crate::environment::{Environment, EnvironmentName};
+use crate::error_budget::ErrorBudgetConfig;
+use crate::error_classifier::LogLevel;
+use crate::events::Event;
+use crate::fake_prover::ProverBackend;
+use crate::latency_slo::SloConfig;
+use crate::logging::{LogFilter, LogFormat};
+use crate::memory_limit::MemoryLimit;
+use crate::orchestrator::retry::{DEFAULT_MAX_ATTEMPTS, RetryPolicy};
+use crate::orchestrator::transport::HttpVersion;
 use crate::orchestrator::{Orchestrator, OrchestratorClient};
+use crate::polling::PollingConfig;
 use crate::pretty::print_cmd_info;
+use crate::profiles::ProfileSchedule;
 use crate::prover_runtime::{start_anonymous_workers, start_authenticated_workers_multi};
+use crate::proxy::{
+    DEFAULT_PROXY_FILE, DEFAULT_PROXY_RETRIES, NoProxyPolicy, ProxyAssignment, ProxyContext,
+    ProxySettings, SelectionStrategy,
+};
 use crate::register::{register_node, register_user};
+use crate::startup_summary::StartupSummary;
+use crate::status_line::StatusTracker;
+use crate::submission_verifier::VerificationConfig;
+use crate::task_filter::TaskFilter;
 use crate::version_requirements::{VersionRequirements, VersionRequirementsError};
-use clap::{ArgAction, Parser, Subcommand};
+use crate::workers::file_queue;
+use crate::workers::ipc::{self, ProverLink, Role};
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
 use crossterm::{
     event::{DisableMouseCapture, EnableMouseCapture},
     execute,
@@ -39,13 +124,111 @@ use crossterm::{
 };
 use ed25519_dalek::SigningKey;
 use ratatui::{Terminal, backend::CrosstermBackend};
+use std::io::IsTerminal;
+use std::sync::Arc;
 use std::{error::Error, io};
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 /// Command-line arguments
 struct Args {
+    /// Keep all state (config, caches, journal, control socket) in this directory instead of
+    /// ~/.nexus, with its own registration, to try flags or releases away from the real node
+    #[arg(long = "sandbox", value_name = "DIR", global = true)]
+    sandbox: Option<std::path::PathBuf>,
+
+    /// Orchestrator to work with (default: production, or NEXUS_ENVIRONMENT); `custom` needs
+    /// --orchestrator-url
+    #[arg(long = "environment", value_enum, ignore_case = true, global = true)]
+    environment: Option<EnvironmentName>,
+
+    /// Custom orchestrator URL, e.g. a local or staging instance (overrides --environment)
+    #[arg(long = "orchestrator-url", value_name = "URL", global = true)]
+    orchestrator_url: Option<String>,
+
+    /// Also trust the CA certificates in this PEM file for orchestrator connections, e.g. a
+    /// corporate proxy's root CA where TLS is intercepted
+    #[arg(long = "ca-cert", value_name = "PEM", global = true)]
+    ca_cert: Option<std::path::PathBuf>,
+
+    /// Client certificate (PEM) to present where the network requires mutual TLS; needs
+    /// --client-key
+    #[arg(
+        long = "client-cert",
+        value_name = "PEM",
+        global = true,
+        requires = "client_key"
+    )]
+    client_cert: Option<std::path::PathBuf>,
+
+    /// Private key (PEM) of the --client-cert certificate
+    #[arg(
+        long = "client-key",
+        value_name = "PEM",
+        global = true,
+        requires = "client_cert"
+    )]
+    client_key: Option<std::path::PathBuf>,
+
+    /// Do not verify the orchestrator's and proxies' TLS certificates. Insecure: anyone on the
+    /// network path can read and alter the traffic; prefer --ca-cert
+    #[arg(long = "insecure-skip-verify", action = ArgAction::SetTrue, global = true)]
+    insecure_skip_verify: bool,
+
+    /// Send control commands (status, pause, drain, ...) to the node listening on this address
+    /// (see `start --control-listen`) instead of the local one
+    #[arg(
+        long = "remote",
+        value_name = "HOST:PORT",
+        global = true,
+        requires = "control_psk"
+    )]
+    remote: Option<String>,
+
+    /// File holding this node's pre-shared key for remote control traffic
+    #[arg(long = "control-psk", value_name = "FILE", global = true)]
+    control_psk: Option<std::path::PathBuf>,
+
+    /// JSON file of further named tokens the node accepts for remote control, each limited to
+    /// the `read` or `control` scope (see `start --control-listen`)
+    #[arg(
+        long = "control-tokens",
+        value_name = "FILE",
+        global = true,
+        requires = "control_psk"
+    )]
+    control_tokens: Option<std::path::PathBuf>,
+
+    /// Name of the node's token held in the `--control-psk` file, when it is not the node's key
+    #[arg(
+        long = "control-token-name",
+        value_name = "NAME",
+        global = true,
+        requires = "remote"
+    )]
+    control_token_name: Option<String>,
+
+    /// Compression of remote control traffic
+    #[arg(long = "control-compression", value_enum, global = true, default_value_t = remote_control::Compression::Deflate)]
+    control_compression: remote_control::Compression,
+
+    /// Seed for proxy rotation, jitter and other scheduling randomness, to replay a run (see `status` for a running node's seed)
+    #[arg(long = "seed", value_name = "N", global = true)]
+    seed: Option<u64>,
+
+    /// Log levels, overall and per module, in `RUST_LOG` syntax (which it overrides), e.g.
+    /// `info,orchestrator=debug,prover=warn`; modules: fetcher, orchestrator, prover, proxy,
+    /// scheduler, submitter, version
+    #[arg(long = "log-level", value_name = "FILTER", global = true)]
+    log_level: Option<LogFilter>,
+
+    /// Format of log lines; `json` writes one object per line for log aggregators, and makes
+    /// `start` run headless
+    #[arg(long = "log-format", value_enum, global = true, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+
     /// Command to execute
     #[command(subcommand)]
     command: Command,
@@ -55,18 +238,34 @@ struct Args {
 enum Command {
     /// Start the prover
     Start {
-        /// Node ID (can specify multiple)
-        #[arg(long, value_name = "NODE_ID", action = ArgAction::Append)]
+        /// Node ID (can specify multiple, or separate with commas); each node runs in this process
+        #[arg(long, visible_alias = "node-ids", value_name = "NODE_ID", action = ArgAction::Append, value_delimiter = ',')]
         node_id: Vec<u64>,
 
-        /// Run without the terminal UI
+        /// Run the nodes listed in this TOML file when no node ID is given (default: nodes.toml next to the config)
+        #[arg(long = "nodes-file", value_name = "PATH", conflicts_with = "node_id")]
+        nodes_file: Option<std::path::PathBuf>,
+
+        /// Run without the terminal UI (implied when stdout is not a terminal)
         #[arg(long = "headless", action = ArgAction::SetTrue)]
         headless: bool,
 
+        /// Replace the dashboard with plain text for screen readers: one line per event and a status report in a fixed order
+        #[arg(long = "accessible", action = ArgAction::SetTrue)]
+        accessible: bool,
+
+        /// How often a headless node prints its status as a line of JSON, or an accessible node its status report (0 disables)
+        #[arg(long = "status-interval", value_name = "DURATION", value_parser = fake_prover::parse_duration, default_value = "60s")]
+        status_interval: std::time::Duration,
+
         /// Maximum number of threads to use for proving.
         #[arg(long = "max-threads", value_name = "MAX_THREADS")]
         max_threads: Option<u32>,
 
+        /// Memory the node may use, in GB; proofs and fetching wait while it is near the limit
+        #[arg(long = "max-memory-gb", value_name = "GB", value_parser = MemoryLimit::parse)]
+        max_memory_gb: Option<MemoryLimit>,
+
         /// Disable proxy usage even if proxies.txt exists
         #[arg(long = "no-proxy", action = ArgAction::SetTrue)]
         no_proxy: bool,
@@ -75,14 +274,201 @@ enum Command {
         #[arg(long = "proxy", value_name = "PATH")]
         proxy_file: Option<String>,
 
-        /// Custom orchestrator URL (overrides environment setting)
-        #[arg(long = "orchestrator-url", value_name = "URL")]
-        orchestrator_url: Option<String>,
+        /// Before starting, connect through the first N usable proxies to warm their TLS sessions and bench failing ones
+        #[arg(long = "warm-proxies", value_name = "N")]
+        warm_proxies: Option<usize>,
 
         /// Disable background colors in the dashboard
         #[arg(long = "no-background-color", action = ArgAction::SetTrue)]
         no_background_color: bool,
+
+        /// Ring the terminal bell and flash the dashboard header when the node starts failing
+        #[arg(long = "alert-on-error", action = ArgAction::SetTrue)]
+        alert_on_error: bool,
+
+        /// Only accept tasks for this program ID (can specify multiple)
+        #[arg(long = "allow-program", value_name = "PROGRAM_ID", action = ArgAction::Append)]
+        allow_programs: Vec<String>,
+
+        /// Never accept tasks for this program ID (can specify multiple)
+        #[arg(long = "deny-program", value_name = "PROGRAM_ID", action = ArgAction::Append)]
+        deny_programs: Vec<String>,
+
+        /// Only accept tasks of this type, e.g. proof_required or proof_hash (can specify multiple)
+        #[arg(long = "allow-task-type", value_name = "TASK_TYPE", action = ArgAction::Append)]
+        allow_task_types: Vec<String>,
+
+        /// Never accept tasks of this type (can specify multiple)
+        #[arg(long = "deny-task-type", value_name = "TASK_TYPE", action = ArgAction::Append)]
+        deny_task_types: Vec<String>,
+
+        /// Seconds between task requests (default depends on the environment)
+        #[arg(long = "poll-interval", value_name = "SECONDS")]
+        poll_interval: Option<u64>,
+
+        /// Random extra delay per poll, as a fraction of the interval (0.0 - 1.0)
+        #[arg(long = "poll-jitter", value_name = "FRACTION")]
+        poll_jitter: Option<f64>,
+
+        /// Longest interval between task requests while no tasks are available
+        #[arg(long = "max-poll-interval", value_name = "SECONDS")]
+        max_poll_interval: Option<u64>,
+
+        /// Pause task fetching when more than this fraction of tasks fail (0.0 - 1.0, off by default)
+        #[arg(long = "error-budget", value_name = "FRACTION")]
+        error_budget: Option<f64>,
+
+        /// Minutes of task outcomes counted against the error budget (default: 30)
+        #[arg(long = "error-budget-window", value_name = "MINUTES")]
+        error_budget_window: Option<u64>,
+
+        /// Minutes between probe tasks while paused by the error budget (default: 10)
+        #[arg(long = "error-budget-probe", value_name = "MINUTES")]
+        error_budget_probe: Option<u64>,
+
+        /// Check accepted submissions again later to confirm they were credited
+        #[arg(long = "verify-submissions", action = ArgAction::SetTrue)]
+        verify_submissions: bool,
+
+        /// Seconds between a submission and each verification check (default: 60)
+        #[arg(
+            long = "verify-delay",
+            value_name = "SECONDS",
+            requires = "verify_submissions"
+        )]
+        verify_delay: Option<u64>,
+
+        /// Alert when the p95 latency of proof submissions stays above this many milliseconds
+        #[arg(long = "submit-slo", value_name = "MS")]
+        submit_slo: Option<u64>,
+
+        /// Minutes the p95 must stay above --submit-slo before an alert (default: 10)
+        #[arg(
+            long = "submit-slo-minutes",
+            value_name = "MINUTES",
+            requires = "submit_slo"
+        )]
+        submit_slo_minutes: Option<u64>,
+
+        /// Record all events of this session to an NDJSON file (credentials are redacted)
+        #[arg(long = "record", value_name = "FILE")]
+        record: Option<std::path::PathBuf>,
+
+        /// Serve a read-only web dashboard on this address, e.g. 127.0.0.1:3030
+        #[arg(long = "web-addr", value_name = "ADDR")]
+        web_addr: Option<std::net::SocketAddr>,
+
+        /// Serve a team summary at /api/export on the web dashboard to holders of this token (default: $NEXUS_EXPORT_TOKEN)
+        #[arg(long = "export-token", value_name = "TOKEN", requires = "web_addr")]
+        export_token: Option<String>,
+
+        /// Write newline-delimited JSON progress events to stdout (log lines move to stderr)
+        #[arg(long = "progress-json", action = ArgAction::SetTrue, requires = "headless", conflicts_with = "progress_fd")]
+        progress_json: bool,
+
+        /// Write newline-delimited JSON progress events to this inherited file descriptor (Unix)
+        #[arg(long = "progress-fd", value_name = "FD")]
+        progress_fd: Option<i32>,
+
+        /// Run the whole node, only fetching/submitting, or only proving (see --ipc-addr)
+        #[arg(long = "role", value_enum, default_value_t = Role::All)]
+        role: Role,
+
+        /// Loopback address where a fetcher hands tasks to prover processes
+        #[arg(long = "ipc-addr", value_name = "ADDR", default_value = ipc::DEFAULT_IPC_ADDR)]
+        ipc_addr: std::net::SocketAddr,
+
+        /// Shared directory (e.g. on NFS) through which a fetcher and its provers exchange tasks, instead of --ipc-addr
+        #[arg(long = "queue-dir", value_name = "DIR")]
+        queue_dir: Option<std::path::PathBuf>,
+
+        /// Prover backend; `fake` simulates proving for load tests (see --fake-duration)
+        #[arg(long = "prover", value_enum, default_value_t = ProverBackend::Stwo)]
+        prover: ProverBackend,
+
+        /// How long each simulated proof takes with `--prover fake`, e.g. 30s or 500ms
+        #[arg(long = "fake-duration", value_name = "DURATION", value_parser = fake_prover::parse_duration, default_value = "30s")]
+        fake_duration: std::time::Duration,
+
+        /// Prove a known-answer task before joining the network, and refuse to start if it comes out wrong
+        #[arg(long = "self-test", action = ArgAction::SetTrue)]
+        self_test: bool,
+
+        /// Skip comparing this binary with the checksum published for its release at startup
+        #[arg(long = "skip-integrity-check", action = ArgAction::SetTrue)]
+        skip_integrity_check: bool,
+
+        /// Only run guest programs whose SHA-256 is in program_allowlist.json next to the config
+        #[arg(long = "program-allowlist", action = ArgAction::SetTrue)]
+        program_allowlist: bool,
+
+        /// Write a flamegraph of each proof to the profiles directory next to the config (needs a build with the profiling feature)
+        #[arg(long = "profile-tasks", action = ArgAction::SetTrue)]
+        profile_tasks: bool,
+
+        /// Rest between proofs so proving uses at most this share of the time, e.g. 90% (keeps small VPSes responsive; a proof itself is not paused, so the share holds over each proof and its rest)
+        #[arg(long = "duty-cycle", value_name = "PERCENT", value_parser = DutyCycle::parse)]
+        duty_cycle: Option<DutyCycle>,
+
+        /// How long to wait on exit for tasks in progress to finish before releasing them, e.g. 60s or 5m
+        #[arg(long = "shutdown-timeout", value_name = "DURATION", value_parser = fake_prover::parse_duration, default_value = "60s")]
+        shutdown_timeout: std::time::Duration,
+
+        /// When every proxy is blacklisted: fail the request, connect directly, or wait for one to recover
+        #[arg(long = "on-no-proxy", value_enum, default_value_t = NoProxyPolicy::Direct)]
+        on_no_proxy: NoProxyPolicy,
+
+        /// How proxies are assigned: a random one per request, or one per node for the whole run (sticky)
+        #[arg(long = "proxy-assignment", value_enum, default_value_t = ProxyAssignment::Random)]
+        proxy_assignment: ProxyAssignment,
+
+        /// How to choose among usable proxies: random, round-robin, weighted-by-latency (needs health checks) or least-recently-used
+        #[arg(long = "proxy-strategy", value_enum, default_value_t = SelectionStrategy::Random)]
+        proxy_strategy: SelectionStrategy,
+
+        /// Attempts per idempotent orchestrator request (lookups, not task requests or submissions) when the server answers 429 or 5xx or cannot be reached, with backoff between them
+        #[arg(long = "max-request-attempts", value_name = "N", default_value_t = DEFAULT_MAX_ATTEMPTS, value_parser = clap::value_parser!(u32).range(1..))]
+        max_request_attempts: u32,
+
+        /// Times a request is retried through another proxy when its proxy fails (timeout, 407, 502 or 503) before giving up
+        #[arg(long = "proxy-retries", value_name = "N", default_value_t = DEFAULT_PROXY_RETRIES)]
+        proxy_retries: u32,
+
+        /// How often to probe every proxy, taking dead ones out of rotation until they recover (0 disables)
+        #[arg(long = "proxy-check-interval", value_name = "DURATION", value_parser = fake_prover::parse_duration, default_value = "10m")]
+        proxy_check_interval: std::time::Duration,
+
+        /// Never use proxies whose exit IP is in one of these networks or AS numbers (one per line); needs --proxy-exit-lookup
+        #[arg(
+            long = "proxy-blocklist",
+            value_name = "FILE",
+            requires = "proxy_exit_lookup"
+        )]
+        proxy_blocklist: Option<std::path::PathBuf>,
+
+        /// Service that tells --proxy-blocklist each proxy's exit IP, and so sees them all (default: ipinfo.io; any service answering in its JSON format works)
+        #[arg(long = "proxy-exit-lookup", value_name = "URL", num_args = 0..=1, default_missing_value = proxy_reputation::DEFAULT_EXIT_LOOKUP_URL)]
+        proxy_exit_lookup: Option<String>,
+
+        /// HTTP version for orchestrator requests (http3 is experimental, not used via proxies)
+        #[arg(long = "http-version", value_enum, default_value_t = HttpVersion::Http2)]
+        http_version: HttpVersion,
+
+        /// Resolve orchestrator hostnames via DNS-over-HTTPS, e.g. where plain DNS is blocked (default server: 1.1.1.1)
+        #[arg(long = "doh", value_name = "URL", num_args = 0..=1, default_missing_value = orchestrator::doh::DEFAULT_DOH_URL)]
+        doh: Option<String>,
+
+        /// Also accept control commands over TCP on this address, authenticated with --control-psk
+        #[arg(long = "control-listen", value_name = "ADDR")]
+        control_listen: Option<std::net::SocketAddr>,
+
+        /// Serve Prometheus metrics at http://ADDR/metrics, e.g. 0.0.0.0:9090, instead of exporting to the backend in the config file
+        #[arg(long = "metrics-addr", value_name = "ADDR")]
+        metrics_addr: Option<std::net::SocketAddr>,
     },
+    /// Run as a container entrypoint: read settings from NEXUS_* environment variables, wait for
+    /// the orchestrator, register if needed and start headless
+    RunContainer,
     /// Register a new user
     RegisterUser {
         /// User's public Ethereum wallet address. 42-character hex string starting with '0x'
@@ -97,48 +483,629 @@ enum Command {
     },
     /// Clear the node configuration and logout.
     Logout,
+    /// Show the node configuration and the capability last reported to the orchestrator.
+    Status {
+        /// Print the status as JSON
+        #[arg(long = "json", action = ArgAction::SetTrue)]
+        json: bool,
+    },
+    /// Compare accepted submissions against the orchestrator per day and list those never credited.
+    Reconcile {
+        /// Number of days to look back
+        #[arg(long = "days", value_name = "DAYS", default_value_t = 7)]
+        days: u32,
+
+        /// Print the reconciliation as JSON
+        #[arg(long = "json", action = ArgAction::SetTrue)]
+        json: bool,
+    },
+    /// Check the orchestrator's signed receipts for accepted submissions.
+    Receipts {
+        #[command(subcommand)]
+        command: ReceiptsCommand,
+    },
+    /// Show what this machine has learned about proving each program, and each node's uptime.
+    Stats {
+        /// Show the profile of each program: proving times and how well it suits this machine
+        #[arg(long = "programs", action = ArgAction::SetTrue)]
+        programs: bool,
+
+        /// Show the uptime of each node on each of the last 7 days instead
+        #[arg(long = "uptime", action = ArgAction::SetTrue, conflicts_with = "programs")]
+        uptime: bool,
+
+        /// Print the profiles (or uptime) as JSON
+        #[arg(long = "json", action = ArgAction::SetTrue)]
+        json: bool,
+    },
+    /// Collect redacted config, diagnostics and history into an archive for bug reports.
+    SupportBundle {
+        /// Session recording (from `start --record`) to include, with its recent errors
+        #[arg(long = "recording", value_name = "FILE")]
+        recording: Option<std::path::PathBuf>,
+
+        /// Where to write the archive (default: nexus-support-<time>.tar)
+        #[arg(long = "output", short = 'o', value_name = "PATH")]
+        output: Option<std::path::PathBuf>,
+
+        /// Write the archive without asking for confirmation
+        #[arg(long = "yes", short = 'y', action = ArgAction::SetTrue)]
+        yes: bool,
+    },
+    /// Stop the running node from fetching new tasks; queued tasks still finish.
+    Pause,
+    /// Resume fetching tasks after `pause`.
+    Resume,
+    /// Let the running node finish its outstanding tasks, then exit.
+    Drain,
+    /// Upgrade the running node in place (Unix): after replacing its binary, let it finish its
+    /// outstanding tasks and restart from the new binary, keeping its session.
+    Handoff,
+    /// Make the running node re-read its proxy file now. Edits are picked up on their own
+    /// while proving; this forces a reload, e.g. after changing a file the node cannot watch.
+    #[command(alias = "reload-proxies")]
+    Reload,
+    /// Change the running node's log level, e.g. to `debug` during an incident.
+    LogLevel {
+        /// New level; warnings and errors are shown at every level
+        #[arg(value_enum, value_name = "LEVEL")]
+        level: LogLevel,
+    },
+    /// Manage the wallets this machine's nodes prove for.
+    Wallet {
+        #[command(subcommand)]
+        command: WalletCommand,
+    },
+    /// Inspect and maintain the configuration file.
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Plan a proxy pool before using it.
+    Proxy {
+        #[command(subcommand)]
+        command: ProxyCommand,
+    },
+    /// Inspect the resource profile schedule.
+    Schedule {
+        #[command(subcommand)]
+        command: ScheduleCommand,
+    },
+    /// Print the team summary served by a running node's web dashboard at /api/export.
+    Export {
+        /// Address the node's web dashboard is served on
+        #[arg(long = "addr", value_name = "ADDR", default_value = "127.0.0.1:3030")]
+        addr: std::net::SocketAddr,
+
+        /// The node's export token (default: $NEXUS_EXPORT_TOKEN)
+        #[arg(long = "token", value_name = "TOKEN")]
+        token: Option<String>,
+    },
+    /// Time a known-answer proof on this machine and pick the task difficulty `start` requests.
+    Benchmark {
+        /// Slowest known-answer proof, in seconds, that asks for large tasks
+        #[arg(long = "large-within", value_name = "SECS", default_value_t = difficulty::DEFAULT_LARGE_WITHIN_SECS)]
+        large_within: f64,
+
+        /// Slowest known-answer proof, in seconds, that asks for medium tasks
+        #[arg(long = "medium-within", value_name = "SECS", default_value_t = difficulty::DEFAULT_MEDIUM_WITHIN_SECS)]
+        medium_within: f64,
+    },
+    /// Replay a session recorded with `start --record` in the dashboard.
+    ReplaySession {
+        /// Path to the recording
+        #[arg(value_name = "FILE")]
+        file: std::path::PathBuf,
+
+        /// Playback speed multiplier (e.g. 10 for ten times faster)
+        #[arg(long = "speed", value_name = "FACTOR", default_value_t = 1.0)]
+        speed: f64,
+    },
+}
+
+#[derive(Subcommand)]
+enum WalletCommand {
+    /// List the wallets in the config file and the nodes linked to each.
+    List {
+        /// Print the wallets as JSON
+        #[arg(long = "json", action = ArgAction::SetTrue)]
+        json: bool,
+    },
+    /// Show one wallet, by name or address.
+    Show {
+        #[arg(value_name = "WALLET")]
+        wallet: String,
+
+        /// Print the wallet as JSON
+        #[arg(long = "json", action = ArgAction::SetTrue)]
+        json: bool,
+    },
+    /// Add a wallet, registering it with the orchestrator if it is new.
+    Add {
+        /// Name to refer to the wallet by
+        #[arg(value_name = "NAME")]
+        name: String,
+
+        /// The wallet's public Ethereum address
+        #[arg(long, value_name = "WALLET_ADDRESS")]
+        wallet_address: String,
+    },
+    /// Link a node registered to a wallet, or register a new node for it.
+    Link {
+        /// Wallet name or address
+        #[arg(value_name = "WALLET")]
+        wallet: String,
+
+        /// Node to link; the orchestrator must report it as the wallet's. If not provided, a new
+        /// node is registered for the wallet.
+        #[arg(long, value_name = "NODE_ID")]
+        node_id: Option<u64>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Show the effective configuration versus defaults, and where each value came from.
+    Diff {
+        /// Node ID, as passed to `start` (can specify multiple)
+        #[arg(long, value_name = "NODE_ID", action = ArgAction::Append)]
+        node_id: Vec<u64>,
+
+        /// Program IDs to accept, as passed to `start`
+        #[arg(long = "allow-program", value_name = "PROGRAM_ID", action = ArgAction::Append)]
+        allow_programs: Vec<String>,
+
+        /// Program IDs to reject, as passed to `start`
+        #[arg(long = "deny-program", value_name = "PROGRAM_ID", action = ArgAction::Append)]
+        deny_programs: Vec<String>,
+
+        /// Task types to accept, as passed to `start`
+        #[arg(long = "allow-task-type", value_name = "TASK_TYPE", action = ArgAction::Append)]
+        allow_task_types: Vec<String>,
+
+        /// Task types to reject, as passed to `start`
+        #[arg(long = "deny-task-type", value_name = "TASK_TYPE", action = ArgAction::Append)]
+        deny_task_types: Vec<String>,
+    },
+    /// Print the config file with user IDs stripped, to share or to template a fleet.
+    Export {
+        /// Also replace host-specific values (node IDs, host name) with variables
+        #[arg(long = "template", action = ArgAction::SetTrue)]
+        template: bool,
+    },
+    /// Print the value of a setting: node-id, wallet-address, environment, orchestrator-url or proxy.
+    Get {
+        /// Setting to print
+        #[arg(value_name = "KEY")]
+        key: String,
+    },
+    /// Check and save a setting; an empty value clears it.
+    Set {
+        /// Setting to change: node-id, wallet-address, environment, orchestrator-url or proxy
+        #[arg(value_name = "KEY")]
+        key: String,
+
+        /// New value
+        #[arg(value_name = "VALUE")]
+        value: String,
+    },
+    /// List the settings, their values and the files they are kept in.
+    List,
+    /// Check config.json and config.toml, and report what is wrong with them.
+    Validate,
+}
+
+#[derive(Subcommand)]
+enum ReceiptsCommand {
+    /// Verify the signature of every archived receipt, offline; fails if any does not verify.
+    Verify {
+        /// Receipt file to check instead of the archive next to the journal
+        #[arg(long = "file", value_name = "PATH")]
+        file: Option<std::path::PathBuf>,
+
+        /// Orchestrator public key (hex) the receipts must be signed by, instead of the one
+        /// pinned for the environment
+        #[arg(long = "orchestrator-key", value_name = "HEX")]
+        orchestrator_key: Option<String>,
+
+        /// Only check the receipt for this task
+        #[arg(long = "task-id", value_name = "TASK_ID")]
+        task_id: Option<String>,
+
+        /// Print the results as JSON
+        #[arg(long = "json", action = ArgAction::SetTrue)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ScheduleCommand {
+    /// Show when each resource profile will be in effect, in the config's time zone.
+    Preview {
+        /// Number of days to show
+        #[arg(long = "days", value_name = "DAYS", default_value_t = 7)]
+        days: u32,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProxyCommand {
+    /// Simulate how requests spread over the proxy pool and warn which proxies will exceed the provider's rate limit.
+    Plan {
+        /// Number of nodes to run, as passed to `start`
+        #[arg(long = "nodes", value_name = "N", default_value_t = 1)]
+        nodes: usize,
+
+        /// Requests each node makes, e.g. 60/min, 1/s or 500/h
+        #[arg(long = "rate", value_name = "RATE", value_parser = proxy_plan::parse_rate)]
+        rate: proxy_plan::Rate,
+
+        /// The provider's limit per proxy, in the same units as --rate
+        #[arg(long = "limit", value_name = "RATE", value_parser = proxy_plan::parse_rate)]
+        limit: proxy_plan::Rate,
+
+        /// Length of each simulated session
+        #[arg(long = "minutes", value_name = "MINUTES", default_value_t = 60)]
+        minutes: u32,
+
+        /// Custom path to proxy file (default: proxies.txt)
+        #[arg(long = "proxy", value_name = "PATH")]
+        proxy_file: Option<String>,
+    },
+    /// Validate a proxy file, in parallel for large files, and optionally check that each proxy reaches the orchestrator.
+    Check {
+        /// Also connect to the orchestrator through every valid proxy
+        #[arg(long = "reachable", action = ArgAction::SetTrue)]
+        reachable: bool,
+
+        /// Custom path to proxy file (default: proxies.txt)
+        #[arg(long = "proxy", value_name = "PATH")]
+        proxy_file: Option<String>,
+    },
+    /// Show request counts, latency and traffic per proxy of the node running on this machine.
+    Stats {
+        /// Print the statistics as JSON
+        #[arg(long = "json", action = ArgAction::SetTrue)]
+        json: bool,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    let nexus_environment_str = std::env::var("NEXUS_ENVIRONMENT").unwrap_or_default();
-    let environment = nexus_environment_str
-        .parse::<Environment>()
-        .unwrap_or(Environment::default());
+    // Before an upgrade can replace the binary
+    handoff::init();
+    let args = Args::parse();
+    // A container entrypoint takes its settings from the environment instead of flags
+    let (args, container) = if matches!(args.command, Command::RunContainer) {
+        let settings = container::ContainerSettings::from_env()?;
+        settings.print_summary();
+        (Args::try_parse_from(settings.start_args())?, Some(settings))
+    } else {
+        (args, None)
+    };
 
+    if let Some(dir) = args.sandbox.clone() {
+        config::set_sandbox_dir(dir.clone())
+            .map_err(|e| format!("Invalid --sandbox {}: {}", dir.display(), e))?;
+        // On stderr, like the migration notice, so JSON output stays parseable
+        eprintln!(
+            "🧪 Sandbox: state is kept in {}, separate from ~/.nexus",
+            config::sandbox_dir().unwrap_or(&dir).display()
+        );
+    }
+    // Flags `start` was not given come from NEXUS_* variables and config.toml
+    let args = if matches!(args.command, Command::Start { .. }) {
+        let argv = match &container {
+            Some(settings) => settings.start_args().into_iter().map(Into::into).collect(),
+            None => std::env::args_os().collect(),
+        };
+        let path = start_settings::settings_path(&get_config_path()?);
+        start_settings::parse_layered::<Args>(argv, &path)?
+    } else {
+        args
+    };
+    // For `start`, NEXUS_ENVIRONMENT was layered in above; other commands read it here
+    let environment_name = match (args.environment, std::env::var("NEXUS_ENVIRONMENT")) {
+        (Some(name), _) => Some(name),
+        (None, Ok(name)) => Some(
+            EnvironmentName::from_str(&name, true)
+                .map_err(|e| format!("Invalid NEXUS_ENVIRONMENT '{}': {}", name, e))?,
+        ),
+        (None, Err(_)) => None,
+    };
+    let environment = Environment::select(environment_name, args.orchestrator_url.clone())?;
+    if let Some(seed) = args.seed {
+        rng::set_seed(seed);
+    }
+    if let Some(filter) = args.log_level.clone() {
+        logging::set_log_filter(filter);
+    }
+    logging::init_log_format(args.log_format);
+    if let Some(path) = &args.ca_cert {
+        crate::orchestrator::tls::set_ca_cert(path)?;
+    }
+    if let (Some(cert), Some(key)) = (&args.client_cert, &args.client_key) {
+        crate::orchestrator::tls::set_client_identity(cert, key)?;
+    }
+    if args.insecure_skip_verify {
+        crate::orchestrator::tls::set_insecure_skip_verify();
+        eprintln!(
+            "⚠️  --insecure-skip-verify: TLS certificates are not verified, so traffic to the orchestrator can be read and altered on the way"
+        );
+    }
+    if let Some(path) = &args.control_psk {
+        let mut settings = remote_control::RemoteSettings::load(path, args.control_compression)?;
+        if let Some(tokens) = &args.control_tokens {
+            settings.load_tokens(tokens)?;
+        }
+        settings.token_name = args.control_token_name.clone();
+        settings.audit_log = Some(get_config_path()?.with_file_name("control_audit.log"));
+        remote_control::set_settings(settings);
+    }
+    if let Some(addr) = args.remote.clone() {
+        remote_control::set_remote_addr(addr);
+    }
     let config_path = get_config_path()?;
 
-    let args = Args::parse();
+    // Upgrade config files written by earlier releases before anything reads them. Reported on
+    // stderr so JSON output (e.g. `status --json`) stays parseable.
+    match Config::migrate_file(&config_path) {
+        Ok(Some(migration)) => {
+            eprintln!(
+                "Migrated {} from schema v{} (backup: {}){}",
+                config_path.display(),
+                migration.from_version,
+                migration.backup_path.display(),
+                migration
+                    .changes
+                    .iter()
+                    .map(|change| format!("\n  - {}", change))
+                    .collect::<String>()
+            );
+        }
+        Ok(None) => {}
+        Err(e) => eprintln!("⚠️  Could not migrate {}: {}", config_path.display(), e),
+    }
+
     match args.command {
         Command::Start {
             node_id,
+            nodes_file,
             headless,
+            accessible,
+            status_interval,
             max_threads,
             no_proxy,
             proxy_file,
-            orchestrator_url,
+            warm_proxies,
             no_background_color,
+            alert_on_error,
+            allow_programs,
+            deny_programs,
+            allow_task_types,
+            deny_task_types,
+            poll_interval,
+            poll_jitter,
+            max_poll_interval,
+            error_budget,
+            error_budget_window,
+            error_budget_probe,
+            verify_submissions,
+            verify_delay,
+            submit_slo,
+            submit_slo_minutes,
+            record,
+            web_addr,
+            export_token,
+            progress_json,
+            progress_fd,
+            role,
+            ipc_addr,
+            queue_dir,
+            prover,
+            fake_duration,
+            self_test,
+            skip_integrity_check,
+            program_allowlist,
+            profile_tasks,
+            duty_cycle,
+            max_memory_gb,
+            shutdown_timeout,
+            on_no_proxy,
+            proxy_assignment,
+            proxy_strategy,
+            max_request_attempts,
+            proxy_retries,
+            proxy_check_interval,
+            proxy_blocklist,
+            proxy_exit_lookup,
+            http_version,
+            doh,
+            control_listen,
+            metrics_addr,
         } => {
-            // If a custom orchestrator URL is provided, create a custom environment
-            let final_environment = if let Some(url) = orchestrator_url {
-                Environment::Custom {
-                    orchestrator_url: url,
+            if progress_json {
+                progress::init_stdout();
+            } else if let Some(fd) = progress_fd {
+                progress::init_fd(fd)
+                    .map_err(|e| format!("Invalid --progress-fd {}: {}", fd, e))?;
+            }
+            if !http_version.is_supported() {
+                return Err(format!(
+                    "{} is not supported by this build (requires the http3 feature)",
+                    http_version
+                )
+                .into());
+            }
+            crate::orchestrator::transport::set_http_version(http_version);
+            crate::orchestrator::retry::set_retry_policy(RetryPolicy {
+                max_attempts: max_request_attempts,
+                ..RetryPolicy::default()
+            });
+            if let Some(url) = doh {
+                let url = crate::orchestrator::doh::parse_doh_url(&url)?;
+                crate::orchestrator::doh::set_doh_url(url);
+            }
+            if let Some(addr) = control_listen {
+                if args.control_psk.is_none() {
+                    return Err("--control-listen needs --control-psk".into());
                 }
+                remote_control::set_listen_addr(addr);
+            }
+            if let Some(addr) = metrics_addr {
+                metrics::set_metrics_addr(addr);
+            }
+            status_line::set_interval(status_interval);
+            if accessible {
+                accessible::enable();
+            }
+            let export_token = export_token.or_else(|| {
+                std::env::var("NEXUS_EXPORT_TOKEN")
+                    .ok()
+                    .filter(|token| !token.is_empty())
+            });
+            if let Some(token) = export_token.filter(|_| web_addr.is_some()) {
+                web_dashboard::set_export_token(token);
+            }
+            let mut proxy_settings = ProxySettings {
+                no_proxy_policy: on_no_proxy,
+                assignment: proxy_assignment,
+                strategy: proxy_strategy,
+                retries: proxy_retries,
+                health_check_interval: proxy_check_interval,
+                blocklist: None,
+                exit_lookup_url: None,
+            };
+            if let (Some(path), Some(url)) = (proxy_blocklist, proxy_exit_lookup) {
+                let blocklist = proxy_reputation::Blocklist::load(&path)?;
+                eprintln!(
+                    "ℹ️ Proxies are used once their exit IP, looked up at {}, clears {} blocklist entries from {}",
+                    url,
+                    blocklist.entry_count(),
+                    path.display()
+                );
+                proxy_settings.blocklist = Some(Arc::new(blocklist));
+                proxy_settings.exit_lookup_url = Some(url);
+            }
+            if let Some(slo) = SloConfig::from_flags(submit_slo, submit_slo_minutes) {
+                latency_slo::set_slo(slo);
+            }
+            if prover == ProverBackend::Fake {
+                fake_prover::enable(fake_duration);
+                eprintln!(
+                    "⚠️  Simulated prover: proofs take {:?} and are only submitted to a local orchestrator",
+                    fake_duration
+                );
+            }
+            if !skip_integrity_check {
+                integrity::warn_if_modified().await;
+            }
+            // A fetcher does not prove, and a simulated prover has nothing to test
+            if self_test && role != Role::Fetcher && prover != ProverBackend::Fake {
+                run_self_test().await?;
+            }
+            // Task sizes follow a benchmark only once the operator has run one
+            if role != Role::Fetcher && prover != ProverBackend::Fake {
+                match Benchmark::load_from_file(&benchmark_path(&config_path)) {
+                    Ok(benchmark) => {
+                        difficulty::start_at(benchmark.difficulty());
+                        eprintln!(
+                            "ℹ️ Requesting up to {} tasks, from the benchmark of {}",
+                            difficulty::name(benchmark.difficulty()),
+                            benchmark.benchmarked_at
+                        );
+                    }
+                    Err(_) => eprintln!(
+                        "ℹ️ Requesting tasks of any difficulty; run `nexus-cli benchmark` to size them to this machine"
+                    ),
+                }
+            }
+            if let Some(duty_cycle) = duty_cycle {
+                duty_cycle::set_duty_cycle(duty_cycle);
+                eprintln!(
+                    "ℹ️ Proving limited to {}% of the time; the rest is left to the system",
+                    duty_cycle.percent()
+                );
+            }
+            if let Some(limit) = max_memory_gb {
+                memory_limit::set_limit(limit);
+                eprintln!(
+                    "ℹ️ Proofs and fetching wait while the node is near its {:.1} GB memory limit",
+                    limit.bytes() as f64 / 1e9
+                );
+            }
+            if profile_tasks {
+                if !task_profiler::is_supported() {
+                    return Err(
+                        "--profile-tasks is not supported by this build (requires the profiling feature)"
+                            .into(),
+                    );
+                }
+                let dir = task_profiler::profiles_dir(&config_path);
+                task_profiler::enable(dir.clone())?;
+                eprintln!("ℹ️ Writing a flamegraph of each proof to {}", dir.display());
+            }
+            if let Some(container) = &container {
+                container.prepare(&config_path, &environment).await?;
+            }
+            if program_allowlist {
+                let allowlist = program_allowlist::enforce(&config_path)?;
+                eprintln!(
+                    "ℹ️ Only guest programs in {} run ({} allowed hashes)",
+                    program_allowlist::allowlist_path(&config_path).display(),
+                    allowlist.hash_count()
+                );
+            }
+            let node_id = if node_id.is_empty() {
+                let node_ids = nodes::from_nodes_file(nodes_file.as_deref(), &config_path)?;
+                if !node_ids.is_empty() {
+                    eprintln!("ℹ️ Running {} nodes from the nodes file", node_ids.len());
+                }
+                node_ids
             } else {
-                environment
+                node_id
             };
+            let polling = PollingConfig::for_environment(&environment).with_overrides(
+                poll_interval,
+                poll_jitter,
+                max_poll_interval,
+            );
             start(
                 node_id,
-                final_environment,
+                environment,
                 config_path,
-                headless,
+                // The dashboard would garble the output of service managers and log collectors
+                headless || accessible || logging::is_json() || !io::stdout().is_terminal(),
                 max_threads,
                 no_proxy,
                 proxy_file,
+                proxy_settings,
+                warm_proxies,
                 no_background_color,
+                alert_on_error,
+                TaskFilter {
+                    allow_programs,
+                    deny_programs,
+                    allow_task_types,
+                    deny_task_types,
+                },
+                polling,
+                ErrorBudgetConfig::from_flags(
+                    error_budget,
+                    error_budget_window,
+                    error_budget_probe,
+                ),
+                VerificationConfig::from_flags(verify_submissions, verify_delay),
+                record.map(config::sandboxed),
+                web_addr,
+                shutdown_timeout,
+                role,
+                ipc_addr,
+                queue_dir,
             )
             .await
         }
+        Command::RunContainer => unreachable!("replaced by the equivalent start command"),
         Command::Logout => {
             print_cmd_info!("Logging out", "Clearing node configuration file...");
             Config::clear_node_config(&config_path).map_err(Into::into)
@@ -152,9 +1119,265 @@ async fn main() -> Result<(), Box<dyn Error>> {
             let orchestrator = Box::new(OrchestratorClient::new(environment));
             register_node(node_id, &config_path, orchestrator).await
         }
+        Command::Status { json } => status::print_status(&config_path, &environment, json).await,
+        Command::Reconcile { days, json } => {
+            let orchestrator = Box::new(OrchestratorClient::new(environment));
+            reconcile::print_reconciliation(&config_path, days, json, orchestrator).await
+        }
+        Command::Receipts {
+            command:
+                ReceiptsCommand::Verify {
+                    file,
+                    orchestrator_key,
+                    task_id,
+                    json,
+                },
+        } => receipts::print_verification(
+            &config_path,
+            &environment,
+            file,
+            orchestrator_key.as_deref(),
+            task_id.as_deref(),
+            json,
+        ),
+        Command::Stats {
+            programs,
+            uptime,
+            json,
+        } => {
+            if uptime {
+                return uptime::print_daily(&config_path, json);
+            }
+            program_profiles::print_stats(&config_path, programs, json)?;
+            if !json {
+                uptime::print_summary(&config_path)?;
+            }
+            Ok(())
+        }
+        Command::SupportBundle {
+            recording,
+            output,
+            yes,
+        } => {
+            support_bundle::create_support_bundle(
+                &config_path,
+                &environment,
+                recording,
+                output,
+                yes,
+            )
+            .await
+        }
+        Command::Pause => control::run_cli_command(ControlCommand::Pause).await,
+        Command::Resume => control::run_cli_command(ControlCommand::Resume).await,
+        Command::Drain => control::run_cli_command(ControlCommand::Drain).await,
+        Command::Handoff => control::run_cli_command(ControlCommand::Handoff).await,
+        Command::Reload => control::run_cli_command(ControlCommand::Reload).await,
+        Command::LogLevel { level } => {
+            control::run_cli_command(ControlCommand::LogLevel(level)).await
+        }
+        Command::Wallet { command } => match command {
+            WalletCommand::List { json } => wallets::list(&config_path, json),
+            WalletCommand::Show { wallet, json } => wallets::show(&config_path, &wallet, json),
+            WalletCommand::Add {
+                name,
+                wallet_address,
+            } => {
+                let orchestrator = Box::new(OrchestratorClient::new(environment));
+                wallets::add(&config_path, &name, &wallet_address, orchestrator).await
+            }
+            WalletCommand::Link { wallet, node_id } => {
+                let orchestrator = Box::new(OrchestratorClient::new(environment));
+                wallets::link(&config_path, &wallet, node_id, orchestrator).await
+            }
+        },
+        Command::Config {
+            command:
+                ConfigCommand::Diff {
+                    node_id,
+                    allow_programs,
+                    deny_programs,
+                    allow_task_types,
+                    deny_task_types,
+                },
+        } => config_diff::print_config_diff(
+            &config_path,
+            &FlagOverrides {
+                node_ids: node_id,
+                environment: args.environment,
+                orchestrator_url: args.orchestrator_url.clone(),
+                task_filter: TaskFilter {
+                    allow_programs,
+                    deny_programs,
+                    allow_task_types,
+                    deny_task_types,
+                },
+            },
+        ),
+        Command::Config {
+            command: ConfigCommand::Export { template },
+        } => config_template::print_export(&config_path, template),
+        Command::Config {
+            command: ConfigCommand::Get { key },
+        } => match config_edit::get(&config_path, &key)? {
+            Some(value) => {
+                println!("{}", value);
+                Ok(())
+            }
+            None => Err(format!("{} is not set", key).into()),
+        },
+        Command::Config {
+            command: ConfigCommand::Set { key, value },
+        } => {
+            let notes = config_edit::set(&config_path, &key, &value)?;
+            let summary = if value.trim().is_empty() {
+                format!("{} cleared", key)
+            } else {
+                format!(
+                    "{} = {}",
+                    key,
+                    config_edit::get(&config_path, &key)?.unwrap_or_default()
+                )
+            };
+            print_cmd_info!("✅ Setting saved", "{}", summary);
+            for note in notes {
+                eprintln!("ℹ️ {}", note);
+            }
+            Ok(())
+        }
+        Command::Config {
+            command: ConfigCommand::List,
+        } => config_edit::print_list(&config_path),
+        Command::Config {
+            command: ConfigCommand::Validate,
+        } => config_edit::print_validation::<Args>(&config_path),
+        Command::Proxy {
+            command:
+                ProxyCommand::Plan {
+                    nodes,
+                    rate,
+                    limit,
+                    minutes,
+                    proxy_file,
+                },
+        } => proxy_plan::print_plan(
+            &proxy_plan::Plan {
+                nodes,
+                rate,
+                limit,
+                minutes,
+            },
+            proxy_file.as_deref().unwrap_or(DEFAULT_PROXY_FILE),
+        ),
+        Command::Proxy {
+            command:
+                ProxyCommand::Check {
+                    reachable,
+                    proxy_file,
+                },
+        } => {
+            proxy_check::print_check(
+                proxy_file.as_deref().unwrap_or(DEFAULT_PROXY_FILE),
+                reachable,
+                environment,
+            )
+            .await
+        }
+        Command::Proxy {
+            command: ProxyCommand::Stats { json },
+        } => proxy_stats::print_stats(json).await,
+        Command::Schedule {
+            command: ScheduleCommand::Preview { days },
+        } => {
+            let schedule = match Config::load_from_file(&config_path) {
+                Ok(config) => config.profile_schedule(1, MAX_WORKERS as usize)?,
+                Err(_) => ProfileSchedule::default(),
+            };
+            profiles::print_preview(&schedule, days);
+            Ok(())
+        }
+        Command::Export { addr, token } => print_export(addr, token).await,
+        Command::Benchmark {
+            large_within,
+            medium_within,
+        } => run_benchmark(&config_path, large_within, medium_within)
+            .await
+            .map(|_| ()),
+        Command::ReplaySession { file, speed } => replay_session(&file, speed).await,
+    }
+}
+
+/// Fetches and prints a running node's export summary.
+async fn print_export(
+    addr: std::net::SocketAddr,
+    token: Option<String>,
+) -> Result<(), Box<dyn Error>> {
+    let token = token
+        .or_else(|| std::env::var("NEXUS_EXPORT_TOKEN").ok())
+        .ok_or("An export token is required: pass --token or set NEXUS_EXPORT_TOKEN")?;
+    let response = reqwest::Client::new()
+        .get(format!("http://{}/api/export", addr))
+        .bearer_auth(token)
+        .timeout(std::time::Duration::from_secs(10))
+        .send()
+        .await
+        .map_err(|e| format!("No web dashboard reachable at {}: {}", addr, e))?;
+    let status = response.status();
+    let body = response.text().await?;
+    if !status.is_success() {
+        return Err(format!("Export failed ({}): {}", status, body.trim()).into());
+    }
+    let export: serde_json::Value = serde_json::from_str(&body)?;
+    println!("{}", serde_json::to_string_pretty(&export)?);
+    Ok(())
+}
+
+/// Runs the known-answer self-test, refusing to start with diagnostics if it fails.
+async fn run_self_test() -> Result<(), Box<dyn Error>> {
+    println!("Running self-test (known-answer proof)...");
+    match tokio::task::spawn_blocking(self_test::run).await? {
+        Ok(duration) => {
+            print_cmd_info!(
+                "Self-test passed",
+                "Known-answer proof verified in {:.1}s",
+                duration.as_secs_f64()
+            );
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("❌ Self-test failed at '{}': {}", e.check, e.detail);
+            eprintln!("{}", self_test::diagnostics());
+            eprintln!(
+                "This build or machine produces incorrect proofs. Check for a corrupted download, faulty memory or an unsupported CPU, and include the details above in a bug report."
+            );
+            Err(format!("Self-test failed ({}); refusing to start", e).into())
+        }
     }
 }
 
+/// Times a known-answer proof and saves the result, from which the task difficulty is picked
+/// with the thresholds (in seconds) for large and medium tasks.
+async fn run_benchmark(
+    config_path: &std::path::Path,
+    large_within: f64,
+    medium_within: f64,
+) -> Result<Benchmark, Box<dyn Error>> {
+    println!("Benchmarking this machine (known-answer proof)...");
+    let duration = tokio::task::spawn_blocking(self_test::run).await??;
+    let benchmark = Benchmark::new(duration, large_within, medium_within);
+    benchmark.save(&benchmark_path(config_path))?;
+    print_cmd_info!(
+        "Benchmarked proving speed",
+        "Known-answer proof in {:.1}s: requesting up to {} tasks",
+        duration.as_secs_f64(),
+        difficulty::name(benchmark.difficulty())
+    );
+    Ok(benchmark)
+}
+
+/// Most prover workers a node runs.
+const MAX_WORKERS: u32 = 8;
+
 /// Starts the Nexus CLI application.
 ///
 /// # Arguments
@@ -163,6 +1386,20 @@ async fn main() -> Result<(), Box<dyn Error>> {
 /// * `config_path` - Path to the configuration file.
 /// * `headless` - If true, runs without the terminal UI.
 /// * `max_threads` - Optional maximum number of threads to use for proving.
+/// * `proxy_settings` - How proxies are chosen and used.
+/// * `warm_proxies` - How many proxies to connect through before the first task, if any.
+/// * `alert_on_error` - If true, the dashboard alerts (bell and flashing header) when the node starts failing.
+/// * `task_filter` - Program IDs and task types to accept, overriding those in the config file.
+/// * `polling` - How often to request tasks from the orchestrator.
+/// * `error_budget` - Failure rate at which task fetching pauses, if enabled.
+/// * `verification` - How accepted submissions are checked for credit, if enabled.
+/// * `record` - Optional file to record the session's events to.
+/// * `web_addr` - Optional address to serve the web dashboard on.
+/// * `shutdown_timeout` - How long to wait on exit for tasks in progress before releasing them.
+/// * `role` - Whether this process fetches, proves, or both.
+/// * `ipc_addr` - Where the fetcher and prover processes of a split deployment meet.
+/// * `queue_dir` - Shared directory used instead of `ipc_addr` by a split deployment, if set.
+#[allow(clippy::too_many_arguments)]
 async fn start(
     node_ids: Vec<u64>,
     env: Environment,
@@ -171,8 +1408,120 @@ async fn start(
     max_threads: Option<u32>,
     no_proxy: bool,
     proxy_file: Option<String>,
+    proxy_settings: ProxySettings,
+    warm_proxies: Option<usize>,
     no_background_color: bool,
+    alert_on_error: bool,
+    task_filter: TaskFilter,
+    polling: PollingConfig,
+    error_budget: Option<ErrorBudgetConfig>,
+    verification: Option<VerificationConfig>,
+    record: Option<std::path::PathBuf>,
+    web_addr: Option<std::net::SocketAddr>,
+    shutdown_timeout: std::time::Duration,
+    role: Role,
+    ipc_addr: std::net::SocketAddr,
+    queue_dir: Option<std::path::PathBuf>,
 ) -> Result<(), Box<dyn Error>> {
+    // Clamp the number of workers to [1,8]. Keep this low for now to avoid rate limiting.
+    let num_workers: usize = max_threads.unwrap_or(1).clamp(1, MAX_WORKERS) as usize;
+
+    if role == Role::All && queue_dir.is_some() {
+        return Err("--queue-dir needs --role fetcher or --role prover".into());
+    }
+    // Metrics and history are kept and reported per environment
+    progress::set_environment(&env);
+    control::control_state().set_environment(&env);
+    // Caps on concurrent proofs per program, for fetchers and prover processes alike
+    program_concurrency::set_limits(
+        Config::load_from_file(&config_path)
+            .map(|config| config.program_concurrency)
+            .unwrap_or_default(),
+    );
+    // Metrics go to the backend in the config file, unless --metrics-addr was given
+    metrics::set_backend(
+        Config::load_from_file(&config_path)
+            .map(|config| config.metrics)
+            .unwrap_or_default(),
+    );
+    hooks::set_hooks(
+        Config::load_from_file(&config_path)
+            .map(|config| config.hooks)
+            .unwrap_or_default(),
+        &env,
+    );
+    // Finished proofs not yet journaled, submitted on the next start if they are lost, and
+    // used instead of proving their tasks again. Prover processes save theirs too.
+    proof_checkpoint::init(&env);
+
+    // A prover process only talks to its fetcher, so it skips all orchestrator setup
+    if role == Role::Prover {
+        let client_id = Config::load_from_file(&config_path)
+            .ok()
+            .map(|config| config.wallet_address)
+            .filter(|wallet| !wallet.is_empty())
+            .unwrap_or_else(|| "anonymous".to_string());
+        let (shutdown_sender, _) = broadcast::channel(1);
+        let (event_receiver, join_handles) = match &queue_dir {
+            Some(dir) => {
+                let started = file_queue::start_queue_consumer(
+                    dir,
+                    num_workers,
+                    shutdown_sender.subscribe(),
+                    env.clone(),
+                    client_id,
+                )
+                .map_err(|e| format!("Cannot use queue directory {}: {}", dir.display(), e))?;
+                print_cmd_info!(
+                    "Prover process",
+                    "Proving with {} workers for tasks queued in {}",
+                    num_workers,
+                    dir.display()
+                );
+                started
+            }
+            None => {
+                let started = ipc::start_prover_process(
+                    ipc_addr,
+                    num_workers,
+                    shutdown_sender.subscribe(),
+                    env.clone(),
+                    client_id,
+                )
+                .await
+                .map_err(|e| {
+                    format!(
+                        "Cannot connect to the fetcher process at {}: {}",
+                        ipc_addr, e
+                    )
+                })?;
+                print_cmd_info!(
+                    "Prover process",
+                    "Proving with {} workers for the fetcher at {}",
+                    num_workers,
+                    ipc_addr
+                );
+                started
+            }
+        };
+        return run_frontend(
+            &[],
+            env,
+            // The prover talks only to its fetcher
+            Arc::new(ProxyContext::new(false, DEFAULT_PROXY_FILE)),
+            event_receiver,
+            join_handles,
+            shutdown_sender,
+            headless,
+            no_background_color,
+            alert_on_error,
+            record,
+            web_addr,
+            shutdown_timeout,
+        )
+        .await;
+    }
+
     // Check version requirements before starting any workers
     match VersionRequirements::fetch().await {
         Ok(requirements) => {
@@ -180,39 +1529,42 @@ async fn start(
             match requirements.check_version_constraints(current_version, None, None) {
                 Ok(Some(violation)) => match violation.constraint_type {
                     crate::version_requirements::ConstraintType::Blocking => {
-                        eprintln!("❌ Version requirement not met: {}", violation.message);
+                        logging::error(format!(
+                            "❌ Version requirement not met: {}",
+                            violation.message
+                        ));
                         std::process::exit(1);
                     }
                     crate::version_requirements::ConstraintType::Warning => {
-                        eprintln!("⚠️  {}", violation.message);
+                        logging::warn(format!("⚠️  {}", violation.message));
                     }
                     crate::version_requirements::ConstraintType::Notice => {
-                        eprintln!("ℹ️  {}", violation.message);
+                        logging::warn(format!("ℹ️  {}", violation.message));
                     }
                 },
                 Ok(None) => {
                     // No violations found, continue
                 }
                 Err(e) => {
-                    eprintln!("❌ Failed to parse version requirements: {}", e);
-                    eprintln!(
-                        "If this issue persists, please file a bug report at: https://github.com/nexus-xyz/nexus-cli/issues"
+                    logging::error(format!("❌ Failed to parse version requirements: {}", e));
+                    logging::error(
+                        "If this issue persists, please file a bug report at: https://github.com/nexus-xyz/nexus-cli/issues",
                     );
                     std::process::exit(1);
                 }
             }
         }
         Err(VersionRequirementsError::Fetch(e)) => {
-            eprintln!("❌ Failed to fetch version requirements: {}", e);
-            eprintln!(
-                "If this issue persists, please file a bug report at: https://github.com/nexus-xyz/nexus-cli/issues"
+            logging::error(format!("❌ Failed to fetch version requirements: {}", e));
+            logging::error(
+                "If this issue persists, please file a bug report at: https://github.com/nexus-xyz/nexus-cli/issues",
             );
             std::process::exit(1);
         }
         Err(e) => {
-            eprintln!("❌ Failed to check version requirements: {}", e);
-            eprintln!(
-                "If this issue persists, please file a bug report at: https://github.com/nexus-xyz/nexus-cli/issues"
+            logging::error(format!("❌ Failed to check version requirements: {}", e));
+            logging::error(
+                "If this issue persists, please file a bug report at: https://github.com/nexus-xyz/nexus-cli/issues",
             );
             std::process::exit(1);
         }
@@ -226,7 +1578,7 @@ async fn start(
 
         // Check if user is registered but node_ids are missing or invalid
         if !config.user_id.is_empty() {
-            if config.node_id.is_empty() {
+            if config.node_id.is_empty() && wallets::linked_node_ids(&config).is_empty() {
                 print_cmd_info!(
                     "✅ User registered, but no node found.",
                     "Please register a node to continue: nexus-cli register-node"
@@ -237,18 +1589,20 @@ async fn start(
                 );
             }
 
-            // Support comma-separated node IDs in config
-            let parsed_ids: Result<Vec<u64>, _> = config.node_id
-                .split(',')
-                .map(str::trim)
-                .filter(|s| !s.is_empty())
-                .map(|s| s.parse::<u64>())
-                .collect();
-            
-            match parsed_ids {
+            match config.node_ids() {
                 Ok(ids) => {
                     node_ids = ids;
-                    print_cmd_info!("✅ Found Node IDs from config file", "Node IDs: {:?}", node_ids);
+                    // Nodes proving for the other wallets in the config run alongside
+                    for node_id in wallets::linked_node_ids(&config) {
+                        if !node_ids.contains(&node_id) {
+                            node_ids.push(node_id);
+                        }
+                    }
+                    print_cmd_info!(
+                        "✅ Found Node IDs from config file",
+                        "Node IDs: {:?}",
+                        node_ids
+                    );
                 }
                 Err(_) => {
                     print_cmd_info!(
@@ -273,19 +1627,117 @@ async fn start(
         );
     }
 
+    // Task filters from the config file, overridden by any given on the command line.
+    let task_filter = if config_path.exists() {
+        Config::load_from_file(&config_path)
+            .map(|config| config.task_filter)
+            .unwrap_or_default()
+            .merge(task_filter)
+    } else {
+        task_filter
+    };
+
+    // Stagger this node's periodic work relative to others running the same defaults
+    if let Some(node_id) = node_ids.first() {
+        pacing::set_node_id(*node_id);
+    }
+
+    // Resource profiles from the config file; enough workers start for the largest of them.
+    let profiles = match Config::load_from_file(&config_path) {
+        Ok(config) => config.profile_schedule(num_workers, MAX_WORKERS as usize)?,
+        Err(_) => ProfileSchedule::new(Vec::new(), num_workers, MAX_WORKERS as usize)?,
+    };
+    let num_workers = profiles.max_workers();
+
+    // Earnings goal from the config file; progress carries over from earlier runs.
+    if let Some(goal) = Config::load_from_file(&config_path)
+        .ok()
+        .and_then(|config| config.goal)
+    {
+        goal::init(goal, &env);
+    }
+    // Proving speed per program, learned across runs.
+    program_profiles::init(&env);
+    // Task states, continuing the submissions an earlier run left unfinished.
+    task_lifecycle::init(&env);
+    // Uptime of each node, kept across runs for `nexus stats`.
+    uptime::init(&node_ids);
+    // Proxies for every request this node makes; `reload` re-reads them.
+    let proxies = Arc::new(
+        ProxyContext::new(
+            !no_proxy,
+            proxy_file.unwrap_or_else(|| DEFAULT_PROXY_FILE.to_string()),
+        )
+        .with_settings(proxy_settings),
+    );
+    control::control_state().attach_proxies(proxies.clone());
+    // The session of the process this one took over from, after `nexus-network handoff`.
+    if let Some(state) = handoff::take_resumed() {
+        handoff::restore(&state, &proxies);
+        print_cmd_info!(
+            "Resumed session",
+            "Took over from version {}, up {} min",
+            state.version,
+            control::control_state().uptime().as_secs() / 60
+        );
+    }
+
     // Create a signing key for the prover.
     let mut csprng = rand_core::OsRng;
     let signing_key: SigningKey = SigningKey::generate(&mut csprng);
-    // Set global proxy settings
-    crate::proxy::set_proxy_enabled(!no_proxy);
-    if let Some(proxy_path) = proxy_file {
-        crate::proxy::set_proxy_file_path(proxy_path);
+    let mut orchestrator_client = OrchestratorClient::with_proxies(env.clone(), proxies.clone());
+
+    // Warm up proxies while the machine is calibrated
+    let warm_up = warm_proxies.map(|count| {
+        let client = orchestrator_client.clone();
+        tokio::spawn(async move { client.warm_up_proxies(count).await })
+    });
+
+    // Calibrate this machine so submissions report its measured capability.
+    if !node_ids.is_empty() {
+        let report =
+            tokio::task::spawn_blocking(move || CapabilityReport::calibrate(num_workers)).await?;
+        if let Ok(path) = get_capability_path() {
+            let _ = report.save(&path);
+        }
+        print_cmd_info!(
+            "Calibrated node capability",
+            "{} GFLOP/s across {} prover threads (see `nexus-network status --json`)",
+            report.reported_flops_per_sec,
+            report.num_provers
+        );
+        orchestrator_client = orchestrator_client.with_capability(&report);
+    }
+    if let Some(warm_up) = warm_up {
+        let (warmed, failed) = warm_up.await?;
+        print_cmd_info!(
+            "Warmed up proxies",
+            "{} ready, {} failed and are benched for now",
+            warmed,
+            failed
+        );
     }
-    let orchestrator_client = OrchestratorClient::new(env.clone());
-    // Clamp the number of workers to [1,8]. Keep this low for now to avoid rate limiting.
-    let num_workers: usize = max_threads.unwrap_or(1).clamp(1, 8) as usize;
     let (shutdown_sender, _) = broadcast::channel(1); // Only one shutdown signal needed
 
+    // Accept control commands (status, pause, drain, reload, log level) from other local
+    // processes. The server stops on shutdown, so its handle is not awaited.
+    if let Err(e) = control::start_control_server(shutdown_sender.clone()) {
+        logging::warn(format!(
+            "⚠️  Control commands (pause, drain, reload) unavailable: {}",
+            e
+        ));
+    }
+    match remote_control::serve(shutdown_sender.subscribe()) {
+        Ok(Some(_)) => logging::info("Accepting remote control commands (--control-listen)"),
+        Ok(None) => {}
+        Err(e) => logging::warn(format!("⚠️  Remote control unavailable: {}", e)),
+    }
+    match metrics::start(proxies.clone(), shutdown_sender.subscribe()) {
+        Ok(Some(destination)) => logging::info(format!("Exporting metrics to {}", destination)),
+        Ok(None) => {}
+        Err(e) => logging::warn(format!("⚠️  Metrics unavailable: {}", e)),
+    }
+
     // Get client_id for analytics - use wallet address from API if available, otherwise "anonymous"
     let client_id = if let Some(node_id) = node_ids.first() {
         match orchestrator_client.get_node(&node_id.to_string()).await {
@@ -303,9 +1755,83 @@ async fn start(
         "anonymous".to_string()
     };
 
-    let (mut event_receiver, mut join_handles) = if node_ids.is_empty() {
+    if role == Role::Fetcher && node_ids.is_empty() {
+        return Err(
+            "The fetcher role needs a registered node (anonymous mode has no tasks to hand out)"
+                .into(),
+        );
+    }
+
+    // Show the effective configuration before any work starts
+    let mut summary = StartupSummary::new(&env, format!("{:?}", role).to_lowercase(), &config_path);
+    summary.node_ids = node_ids.clone();
+    summary.workers = num_workers;
+    summary.proxies = startup_summary::describe_proxies(&proxies);
+    summary.limits.insert(
+        "Polling",
+        format!(
+            "every {}s, up to {}s while idle",
+            polling.interval.as_secs(),
+            polling.max_idle_interval.as_secs()
+        ),
+    );
+    if !task_filter.is_empty() {
+        let lists = [
+            ("allow programs", &task_filter.allow_programs),
+            ("deny programs", &task_filter.deny_programs),
+            ("allow task types", &task_filter.allow_task_types),
+            ("deny task types", &task_filter.deny_task_types),
+        ];
+        let filters: Vec<String> = lists
+            .iter()
+            .filter(|(_, list)| !list.is_empty())
+            .map(|(name, list)| format!("{}: {}", name, list.join(", ")))
+            .collect();
+        summary.limits.insert("Task filter", filters.join("; "));
+    }
+    if let Some(budget) = &error_budget {
+        summary.limits.insert(
+            "Error budget",
+            format!(
+                "pause above {:.0}% failures over {}s (from {} tasks)",
+                budget.max_failure_rate * 100.0,
+                budget.window.as_secs(),
+                budget.min_tasks
+            ),
+        );
+    }
+    let caps = program_concurrency::limits();
+    if !caps.is_empty() {
+        let caps: Vec<String> = caps
+            .iter()
+            .map(|(program_id, limit)| format!("{}: {}", program_id, limit))
+            .collect();
+        summary.limits.insert("Program caps", caps.join(", "));
+    }
+    if !profiles.is_empty() {
+        summary.limits.insert(
+            "Resource profiles",
+            format!("workers vary by time of day ({})", profiles.timezone_name()),
+        );
+    }
+    summary.limits.insert(
+        "Shutdown timeout",
+        format!("{}s", shutdown_timeout.as_secs()),
+    );
+    summary.queue_dir = queue_dir.clone();
+    summary.recording = record.clone();
+    summary.report(&startup_summary::summary_path(&config_path));
+
+    let (event_receiver, join_handles) = if node_ids.is_empty() {
         // Anonymous mode
-        start_anonymous_workers(num_workers, shutdown_sender.subscribe(), env, client_id).await
+        start_anonymous_workers(
+            num_workers,
+            shutdown_sender.subscribe(),
+            env,
+            client_id,
+            profiles,
+        )
+        .await
     } else {
         // Authenticated mode with multiple node IDs support
         start_authenticated_workers_multi(
@@ -316,56 +1842,131 @@ async fn start(
             shutdown_sender.subscribe(),
             env,
             client_id,
+            task_filter,
+            polling,
+            error_budget,
+            verification,
+            profiles,
+            (role == Role::Fetcher).then(|| match queue_dir {
+                Some(dir) => ProverLink::Queue(dir),
+                None => ProverLink::Tcp(ipc_addr),
+            }),
         )
         .await
     };
 
-    if !headless {
-        // Terminal setup
-        enable_raw_mode()?;
-        let mut stdout = io::stdout();
-        execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    run_frontend(
+        &node_ids,
+        orchestrator_client.environment().clone(),
+        proxies.clone(),
+        event_receiver,
+        join_handles,
+        shutdown_sender,
+        headless,
+        no_background_color,
+        alert_on_error,
+        record,
+        web_addr,
+        shutdown_timeout,
+    )
+    .await?;
+
+    // Every task finished; continue the session in a fresh run of the (upgraded) binary
+    if handoff::is_ready() {
+        logging::info("Restarting to complete the handoff...");
+        handoff::exec(&proxies)?;
+    }
+    Ok(())
+}
+
+/// Shows worker events in the dashboard (or as log lines when headless) until shutdown, then
+/// waits for the workers to finish.
+#[allow(clippy::too_many_arguments)]
+async fn run_frontend(
+    node_ids: &[u64],
+    environment: Environment,
+    proxies: Arc<ProxyContext>,
+    mut event_receiver: mpsc::Receiver<Event>,
+    mut join_handles: Vec<JoinHandle<()>>,
+    shutdown_sender: broadcast::Sender<()>,
+    headless: bool,
+    no_background_color: bool,
+    alert_on_error: bool,
+    record: Option<std::path::PathBuf>,
+    web_addr: Option<std::net::SocketAddr>,
+    shutdown_timeout: std::time::Duration,
+) -> Result<(), Box<dyn Error>> {
+    let node_id = node_ids.first().copied();
+
+    // Tee events into the session recording, if requested
+    if let Some(path) = record {
+        let (recorded_receiver, recorder_handle) =
+            session::record_events(event_receiver, &path, &environment, node_id)?;
+        event_receiver = recorded_receiver;
+        join_handles.push(recorder_handle);
+    }
 
-        // Initialize the terminal with Crossterm backend.
-        let backend = CrosstermBackend::new(stdout);
-        let mut terminal = Terminal::new(backend)?;
+    // Mirror events to the web dashboard, if requested
+    if let Some(addr) = web_addr {
+        let (web_receiver, forwarder_handle) =
+            web_dashboard::serve(event_receiver, addr, node_ids, environment.clone(), proxies)
+                .await?;
+        event_receiver = web_receiver;
+        print_cmd_info!("Web dashboard", "Serving on http://{}", addr);
+        join_handles.push(forwarder_handle);
+    }
+
+    // Stop on Ctrl+C, or on SIGTERM from a service manager or container runtime
+    let signal_shutdown_sender = shutdown_sender.clone();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        let _ = signal_shutdown_sender.send(());
+    });
 
+    if !headless {
         // Create the application and run it.
         let app = ui::App::new(
-            if node_ids.is_empty() { None } else { Some(node_ids[0]) },
-            orchestrator_client.environment().clone(),
+            node_id,
+            environment.clone(),
             event_receiver,
             shutdown_sender,
             no_background_color,
+            alert_on_error,
         );
-        let res = ui::run(&mut terminal, app).await;
-
-        // Clean up the terminal after running the application.
-        disable_raw_mode()?;
-        execute!(
-            terminal.backend_mut(),
-            LeaveAlternateScreen,
-            DisableMouseCapture
-        )?;
-        terminal.show_cursor()?;
-
-        res?;
+        run_dashboard(app).await?;
     } else {
         // Headless mode: log events to console.
-
-        // Trigger shutdown on Ctrl+C
-        let shutdown_sender_clone = shutdown_sender.clone();
-        tokio::spawn(async move {
-            if tokio::signal::ctrl_c().await.is_ok() {
-                let _ = shutdown_sender_clone.send(());
-            }
-        });
-
         let mut shutdown_receiver = shutdown_sender.subscribe();
+        // Print the node's status every interval, unless disabled
+        let mut status = StatusTracker::new();
+        let status_interval = status_line::interval();
+        let mut status_ticks = {
+            let period = status_interval.unwrap_or(std::time::Duration::from_secs(3600));
+            tokio::time::interval_at(tokio::time::Instant::now() + period, period)
+        };
+        // Plain text for screen readers, unless logs are JSON for a collector
+        let accessible = accessible::is_enabled() && !logging::is_json();
+        if accessible {
+            accessible::print_status(&status.snapshot());
+        }
         loop {
             tokio::select! {
                 Some(event) = event_receiver.recv() => {
-                    println!("{}", event);
+                    status.observe(&event);
+                    if event.should_display() {
+                        if accessible {
+                            accessible::write_event(&event);
+                        } else {
+                            logging::write_event(&event);
+                        }
+                    }
+                }
+                _ = status_ticks.tick(), if status_interval.is_some() => {
+                    if accessible {
+                        accessible::print_status(&status.snapshot());
+                    } else {
+                        status.print();
+                    }
                 }
                 _ = shutdown_receiver.recv() => {
                     break;
@@ -373,10 +1974,110 @@ async fn start(
             }
         }
     }
-    println!("\nExiting...");
-    for handle in join_handles.drain(..) {
-        let _ = handle.await;
+    logging::info("\nExiting...");
+    uptime::heartbeat();
+    let finished = tokio::time::timeout(shutdown_timeout, async {
+        for handle in join_handles.drain(..) {
+            let _ = handle.await;
+        }
+    })
+    .await;
+    if finished.is_err() {
+        // The orchestrator API has no call to hand a task back, so it reassigns released tasks
+        // once they expire
+        let released = task_lifecycle::abandon_unfinished();
+        if !released.is_empty() {
+            logging::info(format!(
+                "Tasks still in progress after {}s were released: {}.",
+                shutdown_timeout.as_secs(),
+                released.join(", ")
+            ));
+        }
+    }
+    // Tasks fetched but not proved are picked up again on the next start
+    match checkpoint::save(&environment) {
+        Ok(saved) if !saved.is_empty() => logging::info(format!(
+            "Checkpointed {} unproved tasks, resumed on the next start unless they expire first: {}",
+            saved.len(),
+            saved.join(", ")
+        )),
+        Ok(_) => {}
+        Err(e) => logging::info(format!("Could not checkpoint unproved tasks: {}", e)),
     }
-    println!("Nexus CLI application exited successfully.");
+    hooks::session_end().await;
+    logging::info("Nexus CLI application exited successfully.");
     Ok(())
 }
+
+/// Waits for Ctrl+C or, on Unix, SIGTERM.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    if let Ok(mut terminate) =
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+    {
+        tokio::select! {
+            Ok(()) = tokio::signal::ctrl_c() => {}
+            _ = terminate.recv() => {}
+        }
+        return;
+    }
+    if tokio::signal::ctrl_c().await.is_err() {
+        std::future::pending::<()>().await;
+    }
+}
+
+/// Runs the dashboard in the terminal until the user quits.
+async fn run_dashboard(app: ui::App) -> Result<(), Box<dyn Error>> {
+    // Terminal setup; log lines would garble the dashboard
+    logging::set_terminal_ui(true);
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+
+    // Initialize the terminal with Crossterm backend.
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    let res = ui::run(&mut terminal, app).await;
+
+    // Clean up the terminal after running the application.
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture
+    )?;
+    terminal.show_cursor()?;
+    logging::set_terminal_ui(false);
+
+    res?;
+    Ok(())
+}
+
+/// Replays a recorded session in the dashboard, without contacting the orchestrator.
+async fn replay_session(path: &std::path::Path, speed: f64) -> Result<(), Box<dyn Error>> {
+    let recording = session::Recording::load(path)?;
+    print_cmd_info!(
+        "Replaying session",
+        "{} events from {} at {}x speed. Press Q to quit.",
+        recording.event_count(),
+        path.display(),
+        speed
+    );
+
+    let (event_sender, event_receiver) =
+        tokio::sync::mpsc::channel(crate::consts::prover::EVENT_QUEUE_SIZE);
+    let (shutdown_sender, _) = broadcast::channel(1);
+    let app = ui::App::new(
+        recording.node_id,
+        recording.environment.clone(),
+        event_receiver,
+        shutdown_sender,
+        false,
+        false,
+    );
+    let replay_handle = recording.replay(event_sender, speed);
+    let res = run_dashboard(app).await;
+    replay_handle.abort();
+    res
+}
@@ -0,0 +1,139 @@
+//! Orchestrator maintenance windows
+//!
+//! The orchestrator has no dedicated announcement endpoint, so maintenance is detected from
+//! error responses carrying the `x-maintenance-until` (RFC 3339) or `x-maintenance-message`
+//! headers. A bare `503` with `Retry-After` is rate limiting, not maintenance (see
+//! `OrchestratorError::RateLimited`).
+
+use crate::orchestrator::error::OrchestratorError;
+use chrono::{DateTime, Local, Utc};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::time::Duration;
+
+/// Header with the RFC 3339 time at which maintenance is expected to end.
+const MAINTENANCE_UNTIL_HEADER: &str = "x-maintenance-until";
+
+/// Header with a human-readable maintenance announcement.
+const MAINTENANCE_MESSAGE_HEADER: &str = "x-maintenance-message";
+
+/// Assumed window length when maintenance is announced without an end time.
+const DEFAULT_WINDOW: Duration = Duration::from_secs(300);
+
+/// An announced orchestrator maintenance window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaintenanceWindow {
+    /// When the maintenance is expected to end.
+    pub until: DateTime<Utc>,
+
+    /// Announcement text from the orchestrator, if any.
+    pub message: Option<String>,
+}
+
+impl MaintenanceWindow {
+    /// Extracts a maintenance window from an orchestrator error, if it announces one.
+    pub fn from_error(error: &OrchestratorError) -> Option<Self> {
+        match error {
+            OrchestratorError::MaintenanceMode(window) => Some(window.clone()),
+            _ => None,
+        }
+    }
+
+    /// Detects a maintenance window from a response's (lowercase) headers, whatever its status.
+    pub fn from_headers(headers: &HashMap<String, String>) -> Option<Self> {
+        let message = headers
+            .get(MAINTENANCE_MESSAGE_HEADER)
+            .map(|m| m.trim().to_string())
+            .filter(|m| !m.is_empty());
+        let until = headers
+            .get(MAINTENANCE_UNTIL_HEADER)
+            .and_then(|value| DateTime::parse_from_rfc3339(value.trim()).ok())
+            .map(|until| until.with_timezone(&Utc));
+        let retry_after = headers
+            .get("retry-after")
+            .and_then(|value| value.trim().parse::<u64>().ok());
+
+        if until.is_none() && message.is_none() {
+            return None;
+        }
+
+        let until = until.unwrap_or_else(|| {
+            let window = retry_after
+                .map(Duration::from_secs)
+                .unwrap_or(DEFAULT_WINDOW);
+            Utc::now() + chrono::Duration::from_std(window).unwrap_or_default()
+        });
+        Some(Self { until, message })
+    }
+
+    /// Time left until the window ends (zero once it has passed).
+    pub fn remaining(&self) -> Duration {
+        (self.until - Utc::now()).to_std().unwrap_or_default()
+    }
+
+    /// Whether the window has not ended yet.
+    pub fn is_active(&self) -> bool {
+        self.until > Utc::now()
+    }
+}
+
+impl Display for MaintenanceWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Orchestrator maintenance until {}",
+            self.until.with_timezone(&Local).format("%H:%M")
+        )?;
+        if let Some(message) = &self.message {
+            write!(f, ": {}", message)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(headers: &[(&str, &str)]) -> Option<MaintenanceWindow> {
+        let headers = headers
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<HashMap<_, _>>();
+        MaintenanceWindow::from_headers(&headers)
+    }
+
+    #[test]
+    // An announcement without an end time lasts as long as Retry-After says.
+    fn test_announcement_with_retry_after() {
+        let window = detect(&[
+            ("retry-after", "600"),
+            ("x-maintenance-message", "Upgrading"),
+        ])
+        .unwrap();
+        assert!(window.is_active());
+        assert!(window.remaining() > Duration::from_secs(590));
+        assert_eq!(window.message.as_deref(), Some("Upgrading"));
+    }
+
+    #[test]
+    // Explicit maintenance headers should be parsed regardless of status.
+    fn test_maintenance_headers() {
+        let until = (Utc::now() + chrono::Duration::hours(1)).to_rfc3339();
+        let window = detect(&[
+            ("x-maintenance-until", until.as_str()),
+            ("x-maintenance-message", "Database upgrade"),
+        ])
+        .unwrap();
+        assert_eq!(window.message.as_deref(), Some("Database upgrade"));
+        assert!(window.remaining() > Duration::from_secs(3500));
+        assert!(window.to_string().ends_with(": Database upgrade"));
+    }
+
+    #[test]
+    // Ordinary errors should not be mistaken for maintenance.
+    fn test_regular_errors_are_not_maintenance() {
+        assert!(detect(&[]).is_none());
+        assert!(detect(&[("retry-after", "600")]).is_none());
+    }
+}
@@ -0,0 +1,148 @@
+//! Memory limit for proving
+//!
+//! A proof's memory use only shows once it is well under way, so on a small VPS a few workers
+//! starting together can push the machine past its memory and get the whole node killed by the
+//! kernel. `--max-memory-gb <GB>` bounds what the process may use:
+//!
+//! - a proof starts only while the process uses less than `START_SHARE` of the limit, and at
+//!   least `RAMP_UP` after the previous start, so that proof's memory is counted first;
+//! - fetching holds off at the same point (see `backpressure`), so tasks are not taken on that
+//!   would wait for memory until they expire.
+//!
+//! The zkVM proves in a single call, so a running proof is never interrupted. Without
+//! `--max-memory-gb`, proofs start as before.
+
+use crate::events::{Event, EventType};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// Share of the limit the process may use when a proof starts.
+const START_SHARE: f64 = 0.75;
+
+/// Time between proof starts for their memory use to show.
+const RAMP_UP: Duration = Duration::from_secs(10);
+
+/// Time between memory checks while a proof waits to start.
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Memory the process may use, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryLimit {
+    bytes: u64,
+}
+
+impl MemoryLimit {
+    /// Parses a size in GB, e.g. `6` or `6.5`.
+    ///
+    /// # Errors
+    /// Returns a message suitable for clap if the value is not a positive number.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let gb: f64 = value
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid memory limit '{}', expected GB, e.g. 6", value))?;
+        if !gb.is_finite() || gb <= 0.0 {
+            return Err(format!("memory limit must be above 0 GB, got {}", value));
+        }
+        Ok(Self {
+            bytes: (gb * 1000.0 * 1000.0 * 1000.0) as u64,
+        })
+    }
+
+    pub fn bytes(self) -> u64 {
+        self.bytes
+    }
+
+    /// Whether a process using `used` bytes may start another proof.
+    pub fn has_room(self, used: u64) -> bool {
+        (used as f64) < self.bytes as f64 * START_SHARE
+    }
+}
+
+static LIMIT: OnceLock<MemoryLimit> = OnceLock::new();
+
+/// When the last proof started under the limit
+static LAST_START: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Bounds the memory proving may use. Only the first call has an effect.
+pub fn set_limit(limit: MemoryLimit) {
+    let _ = LIMIT.set(limit);
+}
+
+/// The configured memory limit, if there is one.
+pub fn limit() -> Option<MemoryLimit> {
+    LIMIT.get().copied()
+}
+
+/// Whether a proof may start now, given the process uses `used` bytes. Records the start if so.
+fn try_start(limit: MemoryLimit, used: u64, now: Instant) -> bool {
+    let Ok(mut last_start) = LAST_START.lock() else {
+        return true;
+    };
+    let ramped_up = last_start.is_none_or(|started| now.duration_since(started) >= RAMP_UP);
+    if !ramped_up || !limit.has_room(used) {
+        return false;
+    }
+    *last_start = Some(now);
+    true
+}
+
+/// Waits until a proof of `task_id` can start within the memory limit, reporting the wait once.
+pub async fn wait_for_room(worker_id: usize, task_id: &str, event_sender: &mpsc::Sender<Event>) {
+    let Some(limit) = limit() else {
+        return;
+    };
+    let mut reported = false;
+    loop {
+        // Reading the process memory blocks briefly
+        let used = tokio::task::spawn_blocking(crate::system::process_memory_bytes)
+            .await
+            .unwrap_or(0);
+        if try_start(limit, used, Instant::now()) {
+            return;
+        }
+        if !reported && !limit.has_room(used) {
+            reported = true;
+            let message = format!(
+                "Waiting for memory to start task {}: using {} MB of the {} MB limit",
+                task_id,
+                used / (1000 * 1000),
+                limit.bytes() / (1000 * 1000)
+            );
+            let _ = event_sender
+                .send(Event::prover(worker_id, message, EventType::Refresh))
+                .await;
+        }
+        tokio::time::sleep(CHECK_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Sizes in GB are accepted, fractions included; zero and garbage are not.
+    fn test_parse() {
+        assert_eq!(MemoryLimit::parse("6").unwrap().bytes(), 6_000_000_000);
+        assert_eq!(MemoryLimit::parse(" 0.5 ").unwrap().bytes(), 500_000_000);
+        assert!(MemoryLimit::parse("0").is_err());
+        assert!(MemoryLimit::parse("-2").is_err());
+        assert!(MemoryLimit::parse("8GB").is_err());
+    }
+
+    #[test]
+    // Proofs start below the share of the limit, one per ramp-up.
+    fn test_start_within_limit() {
+        let limit = MemoryLimit::parse("8").unwrap();
+        assert!(limit.has_room(5_999_999_999));
+        assert!(!limit.has_room(6_000_000_000));
+
+        let now = Instant::now();
+        assert!(!try_start(limit, 7_000_000_000, now));
+        assert!(try_start(limit, 1_000_000_000, now));
+        assert!(!try_start(limit, 1_000_000_000, now + RAMP_UP / 2));
+        assert!(try_start(limit, 1_000_000_000, now + RAMP_UP));
+    }
+}
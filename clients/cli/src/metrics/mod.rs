@@ -0,0 +1,199 @@
+//! Metrics
+//!
+//! Proofs, orchestrator requests and per-node counters are recorded through a `MetricsSink`,
+//! so each fleet can export them to the telemetry stack it already runs. The backend is chosen
+//! in the config file, and `--metrics-addr` selects Prometheus regardless:
+//!
+//! ```json
+//! "metrics": { "backend": "prometheus", "addr": "0.0.0.0:9090" }
+//! "metrics": { "backend": "statsd", "addr": "127.0.0.1:8125", "prefix": "nexus" }
+//! ```
+//!
+//! Without a backend nothing is recorded. Each exporter sits behind a cargo feature of the same
+//! name (both on by default), so builds can leave out the ones they do not use.
+
+#[cfg(feature = "prometheus")]
+mod prometheus;
+#[cfg(feature = "statsd")]
+mod statsd;
+
+use crate::proxy::ProxyContext;
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+/// Where metrics are exported, as set in the config file.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(tag = "backend", rename_all = "lowercase")]
+pub enum MetricsBackend {
+    /// Nothing is recorded
+    #[default]
+    None,
+    /// Serve `GET /metrics` in the Prometheus text format on `addr`
+    Prometheus { addr: SocketAddr },
+    /// Push to a statsd daemon at `addr` (`host:port`), under `prefix`
+    Statsd {
+        addr: String,
+        #[serde(default = "default_prefix")]
+        prefix: String,
+    },
+}
+
+fn default_prefix() -> String {
+    "nexus".to_string()
+}
+
+impl MetricsBackend {
+    pub fn is_none(&self) -> bool {
+        *self == MetricsBackend::None
+    }
+}
+
+/// Receives what the node records and exports it.
+pub trait MetricsSink: Send + Sync {
+    /// Counts a proof: how long it took, or `None` if proving failed.
+    fn record_proof(&self, duration: Option<Duration>);
+
+    /// Counts an orchestrator request and, if a response arrived, its latency.
+    fn record_request(&self, succeeded: bool, latency: Option<Duration>);
+
+    /// Counts a task fetched by a node.
+    fn record_fetched(&self, node_id: u64);
+
+    /// Counts a node's proof submission, accepted or not.
+    fn record_submission(&self, node_id: u64, accepted: bool);
+
+    /// Starts exporting in the background until shutdown, describing where metrics go.
+    ///
+    /// # Errors
+    /// Returns an `std::io::Error` if the endpoint cannot be bound or reached.
+    fn start(
+        &'static self,
+        proxies: Arc<ProxyContext>,
+        shutdown: broadcast::Receiver<()>,
+    ) -> std::io::Result<String>;
+}
+
+/// A sink that drops everything.
+pub struct NoopSink;
+
+impl MetricsSink for NoopSink {
+    fn record_proof(&self, _duration: Option<Duration>) {}
+
+    fn record_request(&self, _succeeded: bool, _latency: Option<Duration>) {}
+
+    fn record_fetched(&self, _node_id: u64) {}
+
+    fn record_submission(&self, _node_id: u64, _accepted: bool) {}
+
+    fn start(
+        &'static self,
+        _proxies: Arc<ProxyContext>,
+        _shutdown: broadcast::Receiver<()>,
+    ) -> std::io::Result<String> {
+        Ok("nowhere".to_string())
+    }
+}
+
+static BACKEND: OnceLock<MetricsBackend> = OnceLock::new();
+static SINK: OnceLock<Box<dyn MetricsSink>> = OnceLock::new();
+
+/// Exports metrics to `backend` once the node starts. Only the first call has an effect.
+pub fn set_backend(backend: MetricsBackend) {
+    let _ = BACKEND.set(backend);
+}
+
+/// Serves Prometheus metrics on `addr`, overriding the config file. Only the first call to
+/// this or `set_backend` has an effect.
+pub fn set_metrics_addr(addr: SocketAddr) {
+    set_backend(MetricsBackend::Prometheus { addr });
+}
+
+/// The sink for `backend`, or why it is not available in this build.
+fn create_sink(backend: &MetricsBackend) -> Result<Box<dyn MetricsSink>, String> {
+    match backend {
+        MetricsBackend::None => Ok(Box::new(NoopSink)),
+        #[cfg(feature = "prometheus")]
+        MetricsBackend::Prometheus { addr } => Ok(Box::new(prometheus::PrometheusSink::new(*addr))),
+        #[cfg(feature = "statsd")]
+        MetricsBackend::Statsd { addr, prefix } => statsd::StatsdSink::connect(addr, prefix)
+            .map(|sink| Box::new(sink) as Box<dyn MetricsSink>)
+            .map_err(|e| format!("cannot reach statsd at {}: {}", addr, e)),
+        #[allow(unreachable_patterns)]
+        _ => Err("this build has no exporter for the configured backend".to_string()),
+    }
+}
+
+fn sink() -> Option<&'static dyn MetricsSink> {
+    SINK.get().map(Box::as_ref)
+}
+
+/// Counts a proof: how long it took, or `None` if proving failed.
+pub fn record_proof(duration: Option<Duration>) {
+    if let Some(sink) = sink() {
+        sink.record_proof(duration);
+    }
+}
+
+/// Counts an orchestrator request and, if a response arrived, its latency.
+pub fn record_request(succeeded: bool, latency: Option<Duration>) {
+    if let Some(sink) = sink() {
+        sink.record_request(succeeded, latency);
+    }
+}
+
+/// Counts a task fetched by a node.
+pub fn record_fetched(node_id: u64) {
+    if let Some(sink) = sink() {
+        sink.record_fetched(node_id);
+    }
+}
+
+/// Counts a node's proof submission, accepted or not.
+pub fn record_submission(node_id: u64, accepted: bool) {
+    if let Some(sink) = sink() {
+        sink.record_submission(node_id, accepted);
+    }
+}
+
+/// Starts exporting metrics, if a backend was set, describing where they go.
+///
+/// # Errors
+/// Returns an `std::io::Error` if the backend is unavailable or cannot start.
+pub fn start(
+    proxies: Arc<ProxyContext>,
+    shutdown: broadcast::Receiver<()>,
+) -> std::io::Result<Option<String>> {
+    let Some(backend) = BACKEND.get().filter(|backend| !backend.is_none()) else {
+        return Ok(None);
+    };
+    let sink = create_sink(backend).map_err(std::io::Error::other)?;
+    let sink = SINK.get_or_init(|| sink);
+    sink.start(proxies, shutdown).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Backends are tagged by name, statsd has a default prefix, and no section means none.
+    fn test_parse_backend() {
+        let backend: MetricsBackend =
+            serde_json::from_str(r#"{"backend": "statsd", "addr": "127.0.0.1:8125"}"#).unwrap();
+        assert_eq!(
+            backend,
+            MetricsBackend::Statsd {
+                addr: "127.0.0.1:8125".to_string(),
+                prefix: "nexus".to_string(),
+            }
+        );
+        let backend: MetricsBackend =
+            serde_json::from_str(r#"{"backend": "prometheus", "addr": "0.0.0.0:9090"}"#).unwrap();
+        assert!(matches!(backend, MetricsBackend::Prometheus { .. }));
+        assert!(MetricsBackend::default().is_none());
+        assert!(create_sink(&MetricsBackend::None).is_ok());
+    }
+}
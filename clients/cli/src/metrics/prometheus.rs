@@ -0,0 +1,430 @@
+//! Prometheus metrics
+//!
+//! `start --metrics-addr 0.0.0.0:9090` (or the `prometheus` backend) serves `GET /metrics` in the
+//! Prometheus text format, so fleets can be watched from Grafana instead of scraping stdout:
+//!
+//! - `nexus_tasks_fetched_total`, `nexus_proofs_submitted_total` and
+//!   `nexus_submissions_failed_total`, per node
+//! - `nexus_proofs_total` by outcome, and `nexus_proof_duration_seconds`
+//! - `nexus_orchestrator_requests_total` by outcome, and
+//!   `nexus_orchestrator_request_duration_seconds`
+//! - `nexus_proxies` (loaded and usable) and `nexus_proxy_requests_total` per proxy
+//! - `nexus_process_memory_bytes`, `nexus_uptime_seconds` and `nexus_build_info`
+//!
+//! The orchestrator API does not report points, so accepted proofs
+//! (`nexus_proofs_submitted_total`) stand in for them. Like the web dashboard, the endpoint
+//! has no authentication and should only be bound to a trusted interface.
+
+use super::MetricsSink;
+use crate::proxy::ProxyContext;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+
+/// Upper bounds of the proof duration buckets, in seconds.
+const PROOF_BUCKETS: &[f64] = &[1.0, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0];
+
+/// Upper bounds of the orchestrator request duration buckets, in seconds.
+const REQUEST_BUCKETS: &[f64] = &[0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+/// A cumulative histogram over fixed buckets.
+#[derive(Debug, Clone, PartialEq)]
+struct Histogram {
+    bounds: &'static [f64],
+    /// Observations at or below each bound
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            counts: vec![0; bounds.len()],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        for (bound, count) in self.bounds.iter().zip(&mut self.counts) {
+            if value <= *bound {
+                *count += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+
+    fn render(&self, out: &mut String, name: &str, help: &str) {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        for (bound, count) in self.bounds.iter().zip(&self.counts) {
+            let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, bound, count);
+        }
+        let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, self.count);
+        let _ = writeln!(out, "{}_sum {}", name, self.sum);
+        let _ = writeln!(out, "{}_count {}", name, self.count);
+    }
+}
+
+/// Counters recorded while the node runs.
+#[derive(Debug, Clone, PartialEq)]
+struct Metrics {
+    proofs_completed: u64,
+    proofs_failed: u64,
+    proof_duration: Histogram,
+    requests_succeeded: u64,
+    requests_failed: u64,
+    request_duration: Histogram,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            proofs_completed: 0,
+            proofs_failed: 0,
+            proof_duration: Histogram::new(PROOF_BUCKETS),
+            requests_succeeded: 0,
+            requests_failed: 0,
+            request_duration: Histogram::new(REQUEST_BUCKETS),
+        }
+    }
+}
+
+/// Keeps counters in memory for Prometheus to scrape.
+pub struct PrometheusSink {
+    addr: SocketAddr,
+    metrics: Mutex<Metrics>,
+}
+
+impl PrometheusSink {
+    pub fn new(addr: SocketAddr) -> Self {
+        Self {
+            addr,
+            metrics: Mutex::new(Metrics::default()),
+        }
+    }
+
+    fn update(&self, f: impl FnOnce(&mut Metrics)) {
+        if let Ok(mut metrics) = self.metrics.lock() {
+            f(&mut metrics);
+        }
+    }
+}
+
+impl MetricsSink for PrometheusSink {
+    fn record_proof(&self, duration: Option<Duration>) {
+        self.update(|metrics| match duration {
+            Some(duration) => {
+                metrics.proofs_completed += 1;
+                metrics.proof_duration.observe(duration.as_secs_f64());
+            }
+            None => metrics.proofs_failed += 1,
+        });
+    }
+
+    fn record_request(&self, succeeded: bool, latency: Option<Duration>) {
+        self.update(|metrics| {
+            if succeeded {
+                metrics.requests_succeeded += 1;
+            } else {
+                metrics.requests_failed += 1;
+            }
+            if let Some(latency) = latency {
+                metrics.request_duration.observe(latency.as_secs_f64());
+            }
+        });
+    }
+
+    // Per-node counters are read from `nodes::stats` when scraped
+    fn record_fetched(&self, _node_id: u64) {}
+
+    fn record_submission(&self, _node_id: u64, _accepted: bool) {}
+
+    fn start(
+        &'static self,
+        proxies: Arc<ProxyContext>,
+        mut shutdown: broadcast::Receiver<()>,
+    ) -> std::io::Result<String> {
+        let listener = std::net::TcpListener::bind(self.addr)?;
+        listener.set_nonblocking(true)?;
+        let listener = TcpListener::from_std(listener)?;
+
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = shutdown.recv() => break,
+                    accepted = listener.accept() => {
+                        if let Ok((stream, _)) = accepted {
+                            let proxies = proxies.clone();
+                            tokio::spawn(async move {
+                                let _ = self.handle_connection(stream, proxies).await;
+                            });
+                        }
+                    }
+                }
+            }
+        });
+        Ok(format!("Prometheus on http://{}/metrics", self.addr))
+    }
+}
+
+/// Escapes a label value.
+fn label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// Every metric in the Prometheus text format.
+fn render(metrics: &Metrics, proxies: &ProxyContext) -> String {
+    let mut out = String::new();
+
+    let nodes = crate::nodes::stats();
+    header(
+        &mut out,
+        "nexus_tasks_fetched_total",
+        "counter",
+        "Tasks fetched from the orchestrator.",
+    );
+    for (node_id, stats) in &nodes {
+        let _ = writeln!(
+            out,
+            "nexus_tasks_fetched_total{{node_id=\"{}\"}} {}",
+            node_id, stats.tasks_fetched
+        );
+    }
+    header(
+        &mut out,
+        "nexus_proofs_submitted_total",
+        "counter",
+        "Proofs accepted by the orchestrator.",
+    );
+    for (node_id, stats) in &nodes {
+        let _ = writeln!(
+            out,
+            "nexus_proofs_submitted_total{{node_id=\"{}\"}} {}",
+            node_id, stats.proofs_submitted
+        );
+    }
+    header(
+        &mut out,
+        "nexus_submissions_failed_total",
+        "counter",
+        "Proof submissions that failed.",
+    );
+    for (node_id, stats) in &nodes {
+        let _ = writeln!(
+            out,
+            "nexus_submissions_failed_total{{node_id=\"{}\"}} {}",
+            node_id, stats.submissions_failed
+        );
+    }
+
+    header(
+        &mut out,
+        "nexus_proofs_total",
+        "counter",
+        "Proofs computed, by outcome.",
+    );
+    let _ = writeln!(
+        out,
+        "nexus_proofs_total{{outcome=\"completed\"}} {}",
+        metrics.proofs_completed
+    );
+    let _ = writeln!(
+        out,
+        "nexus_proofs_total{{outcome=\"failed\"}} {}",
+        metrics.proofs_failed
+    );
+    metrics.proof_duration.render(
+        &mut out,
+        "nexus_proof_duration_seconds",
+        "Time to compute a proof.",
+    );
+
+    header(
+        &mut out,
+        "nexus_orchestrator_requests_total",
+        "counter",
+        "Orchestrator requests, by outcome.",
+    );
+    let _ = writeln!(
+        out,
+        "nexus_orchestrator_requests_total{{outcome=\"success\"}} {}",
+        metrics.requests_succeeded
+    );
+    let _ = writeln!(
+        out,
+        "nexus_orchestrator_requests_total{{outcome=\"failure\"}} {}",
+        metrics.requests_failed
+    );
+    metrics.request_duration.render(
+        &mut out,
+        "nexus_orchestrator_request_duration_seconds",
+        "Time until the orchestrator responded.",
+    );
+
+    if proxies.should_use() {
+        let (loaded, usable) = proxies
+            .manager()
+            .lock()
+            .map(|mut manager| {
+                (
+                    manager.proxy_count(),
+                    manager.usable_proxies(usize::MAX).len(),
+                )
+            })
+            .unwrap_or_default();
+        header(
+            &mut out,
+            "nexus_proxies",
+            "gauge",
+            "Proxies, loaded and usable.",
+        );
+        let _ = writeln!(out, "nexus_proxies{{state=\"loaded\"}} {}", loaded);
+        let _ = writeln!(out, "nexus_proxies{{state=\"usable\"}} {}", usable);
+        header(
+            &mut out,
+            "nexus_proxy_requests_total",
+            "counter",
+            "Orchestrator requests per proxy, by outcome.",
+        );
+        for stats in proxies.stats() {
+            let proxy = label(&stats.proxy);
+            let _ = writeln!(
+                out,
+                "nexus_proxy_requests_total{{proxy=\"{}\",outcome=\"success\"}} {}",
+                proxy, stats.successes
+            );
+            let _ = writeln!(
+                out,
+                "nexus_proxy_requests_total{{proxy=\"{}\",outcome=\"failure\"}} {}",
+                proxy, stats.failures
+            );
+        }
+    }
+
+    header(
+        &mut out,
+        "nexus_process_memory_bytes",
+        "gauge",
+        "Memory used by the node process.",
+    );
+    let _ = writeln!(
+        out,
+        "nexus_process_memory_bytes {}",
+        crate::system::process_memory_bytes()
+    );
+    header(
+        &mut out,
+        "nexus_uptime_seconds",
+        "gauge",
+        "Time the node has been running, across handoffs.",
+    );
+    let _ = writeln!(
+        out,
+        "nexus_uptime_seconds {}",
+        crate::control::control_state().uptime().as_secs()
+    );
+    header(
+        &mut out,
+        "nexus_build_info",
+        "gauge",
+        "Version of the node.",
+    );
+    let _ = writeln!(
+        out,
+        "nexus_build_info{{version=\"{}\"}} 1",
+        env!("CARGO_PKG_VERSION")
+    );
+    out
+}
+
+impl PrometheusSink {
+    /// Answers a single request and closes the connection.
+    async fn handle_connection(
+        &self,
+        mut stream: TcpStream,
+        proxies: Arc<ProxyContext>,
+    ) -> std::io::Result<()> {
+        let mut buf = [0u8; 2048];
+        let n = stream.read(&mut buf).await?;
+        let request = String::from_utf8_lossy(&buf[..n]);
+        let mut parts = request.split_whitespace();
+        let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+        let (status, body) = if method != "GET" {
+            ("405 Method Not Allowed", "Method not allowed".to_string())
+        } else if path == "/metrics" {
+            // Reading the process memory blocks briefly
+            let metrics = self
+                .metrics
+                .lock()
+                .map(|metrics| metrics.clone())
+                .unwrap_or_default();
+            let body = tokio::task::spawn_blocking(move || render(&metrics, &proxies))
+                .await
+                .unwrap_or_default();
+            ("200 OK", body)
+        } else {
+            ("404 Not Found", "Not found".to_string())
+        };
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+        stream.write_all(response.as_bytes()).await?;
+        stream.shutdown().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Buckets are cumulative and the count includes observations above every bound.
+    fn test_histogram() {
+        let mut histogram = Histogram::new(&[1.0, 10.0]);
+        for value in [0.5, 5.0, 50.0] {
+            histogram.observe(value);
+        }
+        assert_eq!(histogram.counts, [1, 2]);
+        let mut out = String::new();
+        histogram.render(&mut out, "t", "Test.");
+        assert!(out.contains("t_bucket{le=\"10\"} 2\n"));
+        assert!(out.contains("t_bucket{le=\"+Inf\"} 3\n"));
+        assert!(out.contains("t_sum 55.5\n"));
+    }
+
+    #[test]
+    // Recorded proofs, requests and node counters appear in the exposition.
+    fn test_render() {
+        let sink = PrometheusSink::new("127.0.0.1:0".parse().unwrap());
+        sink.record_proof(Some(Duration::from_secs(3)));
+        sink.record_proof(None);
+        sink.record_request(true, Some(Duration::from_millis(80)));
+        crate::nodes::record_fetched(9_000_002);
+        let metrics = sink.metrics.lock().unwrap().clone();
+        let out = render(&metrics, &ProxyContext::new(false, "missing.txt"));
+        assert!(out.contains("# TYPE nexus_proof_duration_seconds histogram\n"));
+        assert!(out.contains("nexus_tasks_fetched_total{node_id=\"9000002\"} 1\n"));
+        assert!(out.contains("nexus_build_info{version="));
+        assert!(!out.contains("nexus_proxies{"));
+        assert_eq!(label("a\"b"), "a\\\"b");
+    }
+}
@@ -0,0 +1,161 @@
+//! statsd metrics
+//!
+//! The `statsd` backend pushes each count and timing to a statsd daemon over UDP as it is
+//! recorded, and the gauges (proxies, memory, uptime) every `GAUGE_INTERVAL`:
+//!
+//! - `<prefix>.proofs.completed` and `.proofs.failed`, with `<prefix>.proof.duration` in ms
+//! - `<prefix>.orchestrator.requests.success` and `.failure`, with
+//!   `<prefix>.orchestrator.request.duration` in ms
+//! - `<prefix>.node.<node_id>.tasks_fetched`, `.proofs_submitted` and `.submissions_failed`
+//! - `<prefix>.proxies.loaded` and `.usable`, `<prefix>.process.memory_bytes` and
+//!   `<prefix>.uptime_seconds`
+//!
+//! Packets that cannot be sent are dropped, as statsd clients do.
+
+use super::MetricsSink;
+use crate::proxy::ProxyContext;
+use std::net::UdpSocket;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+/// Time between gauge updates
+const GAUGE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Sends metrics to a statsd daemon.
+pub struct StatsdSink {
+    socket: UdpSocket,
+    prefix: String,
+}
+
+impl StatsdSink {
+    /// A sink sending to the daemon at `addr` (`host:port`), naming metrics under `prefix`.
+    pub fn connect(addr: &str, prefix: &str) -> std::io::Result<Self> {
+        let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+        socket.connect(addr)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket,
+            prefix: prefix.trim_end_matches('.').to_string(),
+        })
+    }
+
+    /// Sends one metric, e.g. `send("proofs.completed", 1, "c")`.
+    fn send(&self, name: &str, value: impl std::fmt::Display, kind: &str) {
+        let line = format!("{}.{}:{}|{}", self.prefix, name, value, kind);
+        let _ = self.socket.send(line.as_bytes());
+    }
+
+    fn send_gauges(&self, proxies: &ProxyContext) {
+        if proxies.should_use() {
+            let (loaded, usable) = proxies
+                .manager()
+                .lock()
+                .map(|mut manager| {
+                    (
+                        manager.proxy_count(),
+                        manager.usable_proxies(usize::MAX).len(),
+                    )
+                })
+                .unwrap_or_default();
+            self.send("proxies.loaded", loaded, "g");
+            self.send("proxies.usable", usable, "g");
+        }
+        self.send(
+            "process.memory_bytes",
+            crate::system::process_memory_bytes(),
+            "g",
+        );
+        self.send(
+            "uptime_seconds",
+            crate::control::control_state().uptime().as_secs(),
+            "g",
+        );
+    }
+}
+
+impl MetricsSink for StatsdSink {
+    fn record_proof(&self, duration: Option<Duration>) {
+        match duration {
+            Some(duration) => {
+                self.send("proofs.completed", 1, "c");
+                self.send("proof.duration", duration.as_millis(), "ms");
+            }
+            None => self.send("proofs.failed", 1, "c"),
+        }
+    }
+
+    fn record_request(&self, succeeded: bool, latency: Option<Duration>) {
+        let outcome = if succeeded { "success" } else { "failure" };
+        self.send(&format!("orchestrator.requests.{}", outcome), 1, "c");
+        if let Some(latency) = latency {
+            self.send("orchestrator.request.duration", latency.as_millis(), "ms");
+        }
+    }
+
+    fn record_fetched(&self, node_id: u64) {
+        self.send(&format!("node.{}.tasks_fetched", node_id), 1, "c");
+    }
+
+    fn record_submission(&self, node_id: u64, accepted: bool) {
+        let counter = if accepted {
+            "proofs_submitted"
+        } else {
+            "submissions_failed"
+        };
+        self.send(&format!("node.{}.{}", node_id, counter), 1, "c");
+    }
+
+    fn start(
+        &'static self,
+        proxies: Arc<ProxyContext>,
+        mut shutdown: broadcast::Receiver<()>,
+    ) -> std::io::Result<String> {
+        let addr = self.socket.peer_addr()?;
+        tokio::spawn(async move {
+            loop {
+                // Reading the process memory blocks briefly
+                let proxies = proxies.clone();
+                let _ = tokio::task::spawn_blocking(move || self.send_gauges(&proxies)).await;
+                tokio::select! {
+                    _ = shutdown.recv() => break,
+                    _ = tokio::time::sleep(GAUGE_INTERVAL) => {}
+                }
+            }
+        });
+        Ok(format!("statsd at {} as {}.*", addr, self.prefix))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Counts and timings arrive as statsd lines under the prefix.
+    fn test_send_lines() {
+        let daemon = UdpSocket::bind("127.0.0.1:0").unwrap();
+        daemon
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let sink =
+            StatsdSink::connect(&daemon.local_addr().unwrap().to_string(), "fleet.").unwrap();
+        sink.record_proof(Some(Duration::from_millis(1500)));
+        sink.record_submission(42, false);
+
+        let mut lines = Vec::new();
+        let mut buf = [0u8; 512];
+        for _ in 0..3 {
+            let n = daemon.recv(&mut buf).unwrap();
+            lines.push(String::from_utf8_lossy(&buf[..n]).to_string());
+        }
+        assert_eq!(
+            lines,
+            [
+                "fleet.proofs.completed:1|c",
+                "fleet.proof.duration:1500|ms",
+                "fleet.node.42.submissions_failed:1|c"
+            ]
+        );
+    }
+}
@@ -0,0 +1,169 @@
+//! Per-node proxy mapping
+//!
+//! When several nodes run in one process (see `nodes`), they draw from the same proxy pool, so
+//! the network can see one node's requests leave through another node's IP. A
+//! `node_proxies.toml` next to the proxy file pins nodes to their own proxies:
+//!
+//! ```toml
+//! [groups]
+//! eu = ["eu1.example.com:8080", "eu2.example.com:8080"]
+//!
+//! [nodes]
+//! 123 = "us1.example.com:8080"
+//! 456 = "eu"
+//! 789 = ["socks5://10.0.0.7:1080", "eu"]
+//! ```
+//!
+//! Proxies are named as in the logs (`host:port`, with the scheme unless it is HTTP), so the
+//! mapping never repeats credentials. A node may name proxies, groups or both. A mapped node
+//! only uses its own proxies, whatever `--proxy-assignment` says; every other node, and
+//! requests not made for a node, use the proxies no node is mapped to (or any proxy, if every
+//! one is mapped). The mapping is re-read whenever the proxy file is.
+
+use std::collections::{BTreeMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+use toml_edit::{DocumentMut, Item, Value};
+
+/// Path to the mapping used with the proxy file at `proxy_file`.
+pub fn node_proxies_path(proxy_file: &Path) -> PathBuf {
+    proxy_file.with_file_name("node_proxies.toml")
+}
+
+/// Which proxies each node may use.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NodeProxies {
+    /// Display strings of the proxies each node may use, by node ID
+    nodes: BTreeMap<String, Vec<String>>,
+    /// Every proxy some node is mapped to
+    reserved: HashSet<String>,
+}
+
+impl NodeProxies {
+    /// Reads the mapping at `path`; no file is an empty mapping.
+    ///
+    /// # Errors
+    /// Returns an `std::io::Error` if the file cannot be read or is not a valid mapping.
+    pub fn load(path: &Path) -> Result<Self, io::Error> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => {
+                Self::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Parses a mapping, or says what is wrong with it.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let document = text.parse::<DocumentMut>().map_err(|e| e.to_string())?;
+        let mut groups = BTreeMap::new();
+        if let Some(table) = document.get("groups") {
+            let table = table.as_table_like().ok_or("`groups` must be a table")?;
+            for (name, item) in table.iter() {
+                let proxies = names(item).ok_or_else(|| format!("group {} is not a list", name))?;
+                groups.insert(name.to_string(), proxies);
+            }
+        }
+
+        let mut mapping = Self::default();
+        let Some(table) = document.get("nodes") else {
+            return Ok(mapping);
+        };
+        let table = table.as_table_like().ok_or("`nodes` must be a table")?;
+        for (node_id, item) in table.iter() {
+            if node_id.parse::<u64>().is_err() {
+                return Err(format!("{} is not a node ID", node_id));
+            }
+            let entries = names(item)
+                .ok_or_else(|| format!("node {} must name a proxy, group or list", node_id))?;
+            let mut proxies = Vec::new();
+            for entry in entries {
+                let expanded = groups.get(&entry).cloned().unwrap_or_else(|| vec![entry]);
+                for proxy in &expanded {
+                    let proxy = normalize(proxy);
+                    if !proxies.contains(&proxy) {
+                        proxies.push(proxy);
+                    }
+                }
+            }
+            mapping.reserved.extend(proxies.iter().cloned());
+            mapping.nodes.insert(node_id.to_string(), proxies);
+        }
+        Ok(mapping)
+    }
+
+    /// Whether no node is mapped.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// The proxies `owner` (a node ID) is mapped to, if it is.
+    pub fn assigned(&self, owner: &str) -> Option<&[String]> {
+        self.nodes.get(owner).map(Vec::as_slice)
+    }
+
+    /// Whether some node is mapped to the proxy with this display string.
+    pub fn is_reserved(&self, proxy: &str) -> bool {
+        self.reserved.contains(proxy)
+    }
+}
+
+/// A string, or the strings of an array.
+fn names(item: &Item) -> Option<Vec<String>> {
+    match item.as_value()? {
+        Value::String(name) => Some(vec![name.value().trim().to_string()]),
+        Value::Array(array) => array
+            .iter()
+            .map(|value| value.as_str().map(|name| name.trim().to_string()))
+            .collect(),
+        _ => None,
+    }
+}
+
+/// A proxy as `ProxyConfig::to_display_string` shows it: without the `http://` scheme.
+fn normalize(proxy: &str) -> String {
+    proxy.strip_prefix("http://").unwrap_or(proxy).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Nodes may name proxies and groups; groups expand and every mapped proxy is reserved.
+    fn test_parse_mapping() {
+        let mapping = NodeProxies::parse(
+            "[groups]\neu = [\"eu1:8080\", \"http://eu2:8080\"]\n\n\
+             [nodes]\n123 = \"us1:8080\"\n456 = \"eu\"\n789 = [\"socks5://s:1080\", \"eu\"]\n",
+        )
+        .unwrap();
+        assert_eq!(mapping.assigned("123").unwrap(), ["us1:8080"]);
+        assert_eq!(mapping.assigned("456").unwrap(), ["eu1:8080", "eu2:8080"]);
+        assert_eq!(
+            mapping.assigned("789").unwrap(),
+            ["socks5://s:1080", "eu1:8080", "eu2:8080"]
+        );
+        assert!(mapping.assigned("999").is_none());
+        assert!(mapping.is_reserved("eu2:8080"));
+        assert!(!mapping.is_reserved("other:8080"));
+
+        assert!(NodeProxies::parse("").unwrap().is_empty());
+        assert!(NodeProxies::parse("[nodes]\nalpha = \"a:1\"\n").is_err());
+        assert!(NodeProxies::parse("[nodes]\n123 = 5\n").is_err());
+        assert!(NodeProxies::parse("groups = 1\n").is_err());
+    }
+
+    #[test]
+    // A missing mapping file maps no node.
+    fn test_load_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = node_proxies_path(&dir.path().join("proxies.txt"));
+        assert!(NodeProxies::load(&path).unwrap().is_empty());
+        std::fs::write(&path, "[nodes]\n1 = \"a:1\"\n").unwrap();
+        assert_eq!(
+            NodeProxies::load(&path).unwrap().assigned("1").unwrap(),
+            ["a:1"]
+        );
+    }
+}
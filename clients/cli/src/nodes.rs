@@ -0,0 +1,183 @@
+//! Running several nodes in one process
+//!
+//! `--node-id 123,456,789` (or the node IDs in a nodes file) runs every node in this process:
+//! each has its own task fetch loop and HTTP client, and with `--proxy-assignment sticky` its
+//! own proxy, while all of them share the provers. This saves the memory of a process per node
+//! and leaves one dashboard to watch. The nodes file is `nodes.toml` next to the config file,
+//! or the one given with `--nodes-file`:
+//!
+//! ```toml
+//! [[node]]
+//! id = 123
+//!
+//! [[node]]
+//! id = 456
+//! ```
+//!
+//! Fetches and submissions are counted per node, so the dashboards can tell a stalled node
+//! from a busy one.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use toml_edit::{DocumentMut, Item};
+
+/// Path to the default nodes file, next to the config file.
+pub fn nodes_path(config_path: &Path) -> PathBuf {
+    config_path.with_file_name("nodes.toml")
+}
+
+/// Reads the node IDs listed in a nodes file.
+///
+/// # Errors
+/// Returns an `std::io::Error` if the file cannot be read, is not valid TOML, or a node has no
+/// valid `id`.
+pub fn load_node_ids(path: &Path) -> Result<Vec<u64>, io::Error> {
+    let text = std::fs::read_to_string(path)?;
+    parse_node_ids(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn parse_node_ids(text: &str) -> Result<Vec<u64>, String> {
+    let document = text.parse::<DocumentMut>().map_err(|e| e.to_string())?;
+    let Some(nodes) = document.get("node") else {
+        return Ok(Vec::new());
+    };
+    let nodes = nodes
+        .as_array_of_tables()
+        .ok_or("`node` must be an array of tables ([[node]])")?;
+    let mut node_ids = Vec::new();
+    for (index, node) in nodes.iter().enumerate() {
+        let id = node
+            .get("id")
+            .and_then(Item::as_integer)
+            .and_then(|id| u64::try_from(id).ok())
+            .ok_or_else(|| format!("node #{} has no valid `id`", index + 1))?;
+        if !node_ids.contains(&id) {
+            node_ids.push(id);
+        }
+    }
+    Ok(node_ids)
+}
+
+/// The node IDs to run when none are given on the command line: those in `nodes_file`, or in
+/// the default nodes file if it exists. Empty if there is no nodes file.
+///
+/// # Errors
+/// Returns an error if the given file is missing, or either file cannot be read.
+pub fn from_nodes_file(
+    nodes_file: Option<&Path>,
+    config_path: &Path,
+) -> Result<Vec<u64>, Box<dyn std::error::Error>> {
+    let (path, required) = match nodes_file {
+        Some(path) => (path.to_path_buf(), true),
+        None => (nodes_path(config_path), false),
+    };
+    match load_node_ids(&path) {
+        Ok(node_ids) => Ok(node_ids),
+        Err(e) if e.kind() == io::ErrorKind::NotFound && !required => Ok(Vec::new()),
+        Err(e) => Err(format!("Cannot read nodes file {}: {}", path.display(), e).into()),
+    }
+}
+
+/// What one node has done in this run.
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NodeStats {
+    pub tasks_fetched: u64,
+    pub proofs_submitted: u64,
+    pub submissions_failed: u64,
+}
+
+impl fmt::Display for NodeStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} fetched, {} submitted, {} failed",
+            self.tasks_fetched, self.proofs_submitted, self.submissions_failed
+        )
+    }
+}
+
+static STATS: OnceLock<Mutex<BTreeMap<u64, NodeStats>>> = OnceLock::new();
+
+fn update(node_id: u64, f: impl FnOnce(&mut NodeStats)) {
+    let stats = STATS.get_or_init(|| Mutex::new(BTreeMap::new()));
+    if let Ok(mut stats) = stats.lock() {
+        f(stats.entry(node_id).or_default());
+    }
+}
+
+/// Counts a task fetched by a node.
+pub fn record_fetched(node_id: u64) {
+    update(node_id, |stats| stats.tasks_fetched += 1);
+    crate::metrics::record_fetched(node_id);
+}
+
+/// Counts a node's proof submission, accepted or not.
+pub fn record_submission(node_id: u64, accepted: bool) {
+    update(node_id, |stats| {
+        if accepted {
+            stats.proofs_submitted += 1;
+        } else {
+            stats.submissions_failed += 1;
+        }
+    });
+    crate::metrics::record_submission(node_id, accepted);
+}
+
+/// The stats of every node that fetched or submitted anything, by node ID.
+pub fn stats() -> BTreeMap<u64, NodeStats> {
+    STATS
+        .get()
+        .and_then(|stats| stats.lock().ok().map(|stats| stats.clone()))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Nodes are read in order without duplicates; a node without a valid id is an error.
+    fn test_parse_node_ids() {
+        let text = "[[node]]\nid = 123\n\n[[node]]\nid = 456\n\n[[node]]\nid = 123\n";
+        assert_eq!(parse_node_ids(text).unwrap(), vec![123, 456]);
+        assert_eq!(parse_node_ids("").unwrap(), Vec::<u64>::new());
+        assert!(parse_node_ids("[[node]]\nname = \"a\"\n").is_err());
+        assert!(parse_node_ids("[[node]]\nid = -1\n").is_err());
+        assert!(parse_node_ids("node = 5\n").is_err());
+    }
+
+    #[test]
+    // Only an explicitly given nodes file has to exist.
+    fn test_from_nodes_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.json");
+        assert!(from_nodes_file(None, &config_path).unwrap().is_empty());
+        assert!(from_nodes_file(Some(&dir.path().join("missing.toml")), &config_path).is_err());
+
+        std::fs::write(nodes_path(&config_path), "[[node]]\nid = 7\n").unwrap();
+        assert_eq!(from_nodes_file(None, &config_path).unwrap(), vec![7]);
+    }
+
+    #[test]
+    // Fetches and submissions are counted per node.
+    fn test_stats() {
+        record_fetched(9_000_001);
+        record_fetched(9_000_001);
+        record_submission(9_000_001, true);
+        record_submission(9_000_001, false);
+        let stats = stats()[&9_000_001];
+        assert_eq!(
+            stats,
+            NodeStats {
+                tasks_fetched: 2,
+                proofs_submitted: 1,
+                submissions_failed: 1,
+            }
+        );
+        assert_eq!(stats.to_string(), "2 fetched, 1 submitted, 1 failed");
+    }
+}
@@ -2,104 +2,665 @@
 //!
 //! A client for the Nexus Orchestrator, allowing for proof task retrieval and submission.
 
+use crate::capability::CapabilityReport;
 use crate::environment::Environment;
 use crate::nexus_orchestrator::{
     GetProofTaskRequest, GetProofTaskResponse, GetTasksResponse, NodeType, RegisterNodeRequest,
-    RegisterNodeResponse, RegisterUserRequest, SubmitProofRequest, TaskDifficulty, UserResponse,
+    RegisterNodeResponse, RegisterUserRequest, SubmitProofRequest, UserResponse,
 };
-use crate::orchestrator::Orchestrator;
 use crate::orchestrator::error::OrchestratorError;
-use crate::proxy::{should_use_proxy, get_random_proxy, proxy_file_exists, is_proxy_enabled, get_proxy_file_path};
+use crate::orchestrator::retry::{self, retry_policy};
+use crate::orchestrator::schema::SchemaDrift;
+use crate::orchestrator::transport::http_version;
+use crate::orchestrator::{Endpoint, Orchestrator, ProofSubmission};
+use crate::orchestrator::{doh, tls};
+use crate::proxy::{
+    NoProxyPolicy, ProxyAssignment, ProxyConfig, ProxyContext, ProxySelection, SHARED_SESSION,
+};
+use crate::proxy_reputation::ExitInfo;
+use crate::proxy_stats::RequestOutcome;
+use crate::receipts::SubmissionReceipt;
 use crate::system::{estimate_peak_gflops, get_memory_info};
 use crate::task::Task;
 use ed25519_dalek::{Signer, SigningKey, VerifyingKey};
 use prost::Message;
-use reqwest::{Client, ClientBuilder, Response};
-use std::sync::OnceLock;
-use std::time::Duration;
+use reqwest::header::HeaderMap;
+use reqwest::{Client, ClientBuilder, RequestBuilder, Response, StatusCode};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
 
 // Privacy-preserving country detection for network optimization.
 // Only stores 2-letter country codes (e.g., "US", "CA", "GB") to help route
 // requests to the nearest Nexus network servers for better performance.
 // No precise location, IP addresses, or personal data is collected or stored.
-static COUNTRY_CODE: OnceLock<String> = OnceLock::new();
+static COUNTRY_CODE: OnceCell<String> = OnceCell::const_new();
+
+/// The body of a GET response and the route it took (or its error), shared by every caller
+/// waiting on the request
+type SharedResponse = Result<Arc<(Vec<u8>, String)>, Arc<OrchestratorError>>;
+
+/// GET requests currently in flight, by URL
+type InFlight = Arc<Mutex<HashMap<String, Arc<OnceCell<SharedResponse>>>>>;
+
+/// How long a proxy health check may take
+const PROXY_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
 
+/// An HTTP client and the proxy it connects through, if any
 #[derive(Debug, Clone)]
-pub struct OrchestratorClient {
+struct ProxiedClient {
     client: Client,
+    proxy: Option<ProxyConfig>,
+    /// Wakes from sleep before the client was created (see `sleep_wake`); its connections
+    /// did not survive later ones
+    wakes: u64,
+}
+
+impl ProxiedClient {
+    /// The proxy's identity (see `ProxyConfig::identity`), or `DIRECT`
+    fn route(&self) -> String {
+        self.proxy.as_ref().map_or_else(
+            || crate::latency_slo::DIRECT.to_string(),
+            ProxyConfig::identity,
+        )
+    }
+}
+
+/// A request made for a task that was fetched through `proxy`, and should use it again
+#[derive(Debug, Clone, Copy)]
+struct Affinity<'a> {
+    task_id: &'a str,
+    proxy: &'a str,
+}
+
+/// Which failed requests may be sent again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Resend {
+    /// Idempotent requests (GETs): any retryable failure, through another proxy and under the
+    /// retry policy
+    Retryable,
+    /// Requests with side effects (POSTs): only ones that never reached the orchestrator,
+    /// through another proxy. Workers retry task requests and submissions themselves, on
+    /// their own schedule.
+    Unsent,
+}
+
+/// The proxy a request went through, to record on the tasks it returned
+fn proxy_of(route: &str) -> Option<String> {
+    (route != crate::latency_slo::DIRECT).then(|| route.to_string())
+}
+
+#[derive(Debug, Clone)]
+pub struct OrchestratorClient {
     environment: Environment,
+    /// Calibrated throughput reported in submission telemetry, if a calibration ran
+    reported_flops: Option<i32>,
+    /// For a client dedicated to one node: the client used for every request, chosen on first
+    /// use and replaced when its proxy fails
+    pinned: Option<Arc<Mutex<Option<ProxiedClient>>>>,
+    /// Sticky proxy session the client's requests belong to (see `ProxyAssignment::Sticky`)
+    proxy_session: String,
+    /// Proxy settings and pool the client's requests go through
+    proxies: Arc<ProxyContext>,
+    /// Identical GET requests made while one is in flight wait for its response instead
+    in_flight: InFlight,
 }
 
 impl OrchestratorClient {
+    /// Create a client using the proxies in `proxies.txt`, if the file exists
     pub fn new(environment: Environment) -> Self {
+        Self::with_proxies(environment, Arc::default())
+    }
+
+    /// Create a client whose requests go through `proxies`
+    pub fn with_proxies(environment: Environment, proxies: Arc<ProxyContext>) -> Self {
         // Initialize proxy support and show status
-        Self::initialize_proxy_support();
-        
-        let client = Self::create_client_with_proxy();
+        Self::initialize_proxy_support(&proxies);
+
         Self {
-            client,
             environment,
+            reported_flops: None,
+            pinned: None,
+            proxy_session: SHARED_SESSION.to_string(),
+            proxies,
+            in_flight: InFlight::default(),
+        }
+    }
+
+    /// Create a client dedicated to a single node, for running several nodes in one process.
+    ///
+    /// The dedicated client keeps its own connection pool, cookie store and proxy (chosen once)
+    /// for all requests, so the orchestrator sees each node as a separate client, just as it
+    /// would if every node ran in its own process. With sticky proxies, the node keeps its
+    /// proxy across clients and failures until it fails over.
+    pub fn for_node(&self, node_id: u64) -> Self {
+        Self {
+            environment: self.environment.clone(),
+            reported_flops: self.reported_flops,
+            pinned: Some(Arc::new(Mutex::new(None))),
+            proxy_session: node_id.to_string(),
+            proxies: self.proxies.clone(),
+            in_flight: InFlight::default(),
         }
     }
 
+    /// The proxy settings and pool the client's requests go through
+    pub fn proxies(&self) -> &Arc<ProxyContext> {
+        &self.proxies
+    }
+
+    /// Report the calibrated capability in submission telemetry instead of the estimated peak.
+    pub fn with_capability(mut self, report: &CapabilityReport) -> Self {
+        self.reported_flops = Some(report.reported_flops_per_sec);
+        self
+    }
+
     /// Initialize proxy support and show status once
-    fn initialize_proxy_support() {
+    fn initialize_proxy_support(proxies: &ProxyContext) {
         static INITIALIZED: OnceLock<()> = OnceLock::new();
         INITIALIZED.get_or_init(|| {
-            if should_use_proxy() {
-                match crate::proxy::get_proxy_manager().lock() {
-                    Ok(mut manager) => {
+            if proxies.should_use() {
+                match proxies.manager().lock() {
+                    Ok(manager) => {
                         if let Ok(()) = manager.ensure_proxies_loaded() {
-                            println!("✅ Proxy support enabled with {} proxies from {}", manager.proxy_count(), get_proxy_file_path());
+                            crate::logging::info(format!(
+                                "✅ Proxy support enabled with {} proxies from {}",
+                                manager.proxy_count(),
+                                proxies.file_path()
+                            ));
                         } else {
-                            println!("⚠️ Failed to load proxies from {}", get_proxy_file_path());
+                            crate::logging::warn(format!(
+                                "⚠️ Failed to load proxies from {}",
+                                proxies.file_path()
+                            ));
                         }
                     }
                     Err(_) => {
-                        println!("⚠️ Failed to access proxy manager");
+                        crate::logging::warn("⚠️ Failed to access proxy manager");
                     }
                 }
-            } else if proxy_file_exists() && !is_proxy_enabled() {
-                println!("ℹ️ Proxy disabled by --no-proxy flag");
+            } else if proxies.file_exists() && !proxies.is_enabled() {
+                crate::logging::info("ℹ️ Proxy disabled by --no-proxy flag");
             } else {
-                println!("ℹ️ No {} found, using direct connection", get_proxy_file_path());
+                crate::logging::info(format!(
+                    "ℹ️ No {} found, using direct connection",
+                    proxies.file_path()
+                ));
+            }
+            if let Some(resolver) = doh::doh_resolver() {
+                crate::logging::info(format!(
+                    "ℹ️ Resolving orchestrator hostnames via DNS-over-HTTPS ({})",
+                    resolver.url()
+                ));
+            }
+            if proxies.should_use() && proxies.settings().assignment == ProxyAssignment::Sticky {
+                crate::logging::info(
+                    "ℹ️ --proxy-assignment sticky: each node keeps one proxy for the whole run",
+                );
+            }
+            let policy = proxies.settings().no_proxy_policy;
+            if proxies.should_use() && policy != NoProxyPolicy::Direct {
+                let behavior = match policy {
+                    NoProxyPolicy::Wait => "wait for one to recover",
+                    _ => "fail",
+                };
+                crate::logging::info(format!(
+                    "ℹ️ --on-no-proxy {}: requests {} while no proxy is usable",
+                    policy, behavior
+                ));
             }
         });
     }
 
-    /// Create HTTP client with random proxy if proxies.txt exists
-    fn create_client_with_proxy() -> Client {
+    /// Create an HTTP client connecting through `proxy`, or directly
+    ///
+    /// Isolated clients also keep cookies, since they are reused for a node's whole session.
+    fn create_client(isolated: bool, proxy: Option<&ProxyConfig>) -> Client {
         let mut builder = ClientBuilder::new()
             .connect_timeout(Duration::from_secs(10))
-            .timeout(Duration::from_secs(30)); // Increased timeout for proxy requests
-
-        // Check if proxy should be used and try to use a random proxy
-        if should_use_proxy() {
-            match get_random_proxy() {
-                Ok(proxy_config) => {
-                    match proxy_config.to_reqwest_proxy() {
-                        Ok(proxy) => {
-                            // Log proxy usage at debug level to avoid spam
-                            log::debug!("Using proxy: {}", proxy_config.to_display_string());
-                            builder = builder.proxy(proxy);
-                        }
-                        Err(e) => {
-                            log::warn!("Failed to create proxy: {}", e);
-                        }
-                    }
+            .timeout(Duration::from_secs(30)) // Increased timeout for proxy requests
+            .cookie_store(isolated);
+
+        let mut via_proxy = false;
+        if let Some(proxy_config) = proxy {
+            match proxy_config.to_reqwest_proxy() {
+                Ok(proxy) => {
+                    // Log proxy usage at debug level to avoid spam
+                    log::debug!("Using proxy: {}", proxy_config.to_display_string());
+                    builder = builder.proxy(proxy);
+                    via_proxy = true;
                 }
                 Err(e) => {
-                    log::warn!("Failed to get random proxy: {}", e);
+                    log::warn!("Failed to create proxy: {}", e);
                 }
             }
         }
 
+        builder = http_version().apply(builder, via_proxy);
+        builder = doh::apply(builder);
+        builder = tls::apply(builder);
         builder.build().expect("Failed to create HTTP client")
     }
 
-    /// Get a client for a single request (with random proxy rotation)
-    fn get_client_for_request() -> Client {
-        Self::create_client_with_proxy()
+    /// Choose a proxy for `session` and create a client for it, applying the `--on-no-proxy`
+    /// policy
+    async fn create_client_with_proxy(
+        proxies: &ProxyContext,
+        isolated: bool,
+        session: &str,
+    ) -> Result<ProxiedClient, OrchestratorError> {
+        loop {
+            let proxy = match proxies.select(session) {
+                ProxySelection::Proxy(proxy) => Some(proxy),
+                ProxySelection::Direct => None,
+                ProxySelection::Exhausted { reason, retry_in } => {
+                    if proxies.settings().no_proxy_policy != NoProxyPolicy::Wait {
+                        return Err(OrchestratorError::NoProxy(reason));
+                    }
+                    log::warn!(
+                        "{}, waiting {}s for one to recover",
+                        reason,
+                        retry_in.as_secs()
+                    );
+                    tokio::time::sleep(retry_in).await;
+                    continue;
+                }
+            };
+            // Isolated clients keep their own cookies, so they cannot share a warmed client
+            let warm = match (&proxy, isolated) {
+                (Some(proxy), false) => proxies.warm_client(&proxy.identity()),
+                _ => None,
+            };
+            return Ok(ProxiedClient {
+                client: warm.unwrap_or_else(|| Self::create_client(isolated, proxy.as_ref())),
+                proxy,
+                wakes: crate::sleep_wake::wakes(),
+            });
+        }
+    }
+
+    /// Get a client for a single request: through the task's proxy while it is usable, else
+    /// with random proxy rotation (unless isolated). A task that has to leave its proxy has the
+    /// switch recorded.
+    async fn get_client_for_request(
+        &self,
+        affinity: Option<Affinity<'_>>,
+    ) -> Result<ProxiedClient, OrchestratorError> {
+        let Some(affinity) = affinity else {
+            return self.choose_client().await;
+        };
+        if let Some(client) = self.client_for_proxy(affinity.proxy) {
+            return Ok(client);
+        }
+        let client = self.choose_client().await?;
+        self.proxies
+            .record_affinity_switch(affinity.task_id, affinity.proxy, &client.route());
+        Ok(client)
+    }
+
+    /// A client connecting through the proxy with this identity (see `ProxyConfig::identity`),
+    /// unless it is no longer usable
+    fn client_for_proxy(&self, identity: &str) -> Option<ProxiedClient> {
+        let uses_proxy = |client: &ProxiedClient| client.proxy.as_ref().map(ProxyConfig::identity);
+        if let Some(Ok(pinned)) = self.pinned.as_ref().map(|pinned| pinned.lock()) {
+            if let Some(client) = pinned.as_ref().filter(|client| {
+                client.wakes == crate::sleep_wake::wakes()
+                    && uses_proxy(client).as_deref() == Some(identity)
+            }) {
+                return Some(client.clone());
+            }
+        }
+        let proxy = self.proxies.manager().lock().ok()?.usable_proxy(identity)?;
+        let isolated = self.pinned.is_some();
+        let warm = (!isolated)
+            .then(|| self.proxies.warm_client(identity))
+            .flatten();
+        Some(ProxiedClient {
+            client: warm.unwrap_or_else(|| Self::create_client(isolated, Some(&proxy))),
+            proxy: Some(proxy),
+            wakes: crate::sleep_wake::wakes(),
+        })
+    }
+
+    /// Choose a client: the pinned one if isolated, else with a freshly chosen proxy
+    async fn choose_client(&self) -> Result<ProxiedClient, OrchestratorError> {
+        let Some(pinned) = &self.pinned else {
+            return Self::create_client_with_proxy(&self.proxies, false, &self.proxy_session).await;
+        };
+        if let Some(client) = pinned
+            .lock()
+            .ok()
+            .and_then(|pinned| pinned.clone())
+            .filter(|client| client.wakes == crate::sleep_wake::wakes())
+        {
+            // Clones share the connection pool and cookie store
+            return Ok(client);
+        }
+        let client =
+            Self::create_client_with_proxy(&self.proxies, true, &self.proxy_session).await?;
+        if let Ok(mut pinned) = pinned.lock() {
+            *pinned = Some(client.clone());
+        }
+        Ok(client)
+    }
+
+    /// Send an idempotent request through a freshly chosen (or pinned) client, blacklisting
+    /// the proxy if it failed and retrying through another one (see `ProxySettings::retries`).
+    /// Requests the server answered with a retryable status, or that could not connect, are
+    /// then retried under the retry policy.
+    async fn send(
+        &self,
+        build: impl Fn(&Client) -> RequestBuilder,
+    ) -> Result<Response, OrchestratorError> {
+        self.send_with_route(None, Resend::Retryable, build)
+            .await
+            .map(|(response, _)| response)
+    }
+
+    /// Like `send`, also returning the route the request took: the proxy, or direct. `resend`
+    /// tells which failed requests may be sent again.
+    async fn send_with_route(
+        &self,
+        affinity: Option<Affinity<'_>>,
+        resend: Resend,
+        build: impl Fn(&Client) -> RequestBuilder,
+    ) -> Result<(Response, String), OrchestratorError> {
+        self.send_routed(affinity, resend, build)
+            .await
+            .map_err(|(e, _)| e)
+    }
+
+    /// Like `send_with_route`, also returning the route of the last attempt when the request
+    /// fails, unless no client could be chosen for it
+    async fn send_routed(
+        &self,
+        affinity: Option<Affinity<'_>>,
+        resend: Resend,
+        build: impl Fn(&Client) -> RequestBuilder,
+    ) -> Result<(Response, String), (OrchestratorError, Option<String>)> {
+        let policy = retry_policy();
+        let mut attempt = 1;
+        let mut retries_left = self.proxies.settings().retries;
+        loop {
+            let proxied = self
+                .get_client_for_request(affinity)
+                .await
+                .map_err(|e| (e, None))?;
+            let route = proxied.route();
+            let result = self.send_once(&proxied, &build).await;
+            if let Some(proxy) = &proxied.proxy {
+                let retry = retries_left > 0
+                    && match resend {
+                        Resend::Retryable => Self::should_retry(&result),
+                        Resend::Unsent => Self::never_sent(&result),
+                    };
+                // A proxy retried away from is benched too, so the retry (and a task's
+                // affinity) moves on to the next proxy rather than the same one
+                if retry || Self::proxy_failed(&result) {
+                    self.bench_proxy(proxy);
+                }
+                if retry {
+                    retries_left -= 1;
+                    log::warn!(
+                        "Request through proxy {} failed ({}), retrying through another proxy",
+                        route,
+                        Self::describe_failure(&result)
+                    );
+                    continue;
+                }
+            }
+            if let Err(e) = &result {
+                if tls::is_interception(e) {
+                    tls::warn_interception_once();
+                    return Err((
+                        OrchestratorError::TlsInterception(e.to_string()),
+                        Some(route),
+                    ));
+                }
+            }
+            if resend == Resend::Retryable
+                && attempt < policy.max_attempts
+                && policy.is_retryable(&result)
+            {
+                let retry_after = result.as_ref().ok().and_then(retry::retry_after);
+                if let Some(delay) = policy.delay(attempt, retry_after) {
+                    log::warn!(
+                        "Orchestrator request failed ({}), retrying in {}ms (attempt {} of {})",
+                        Self::describe_failure(&result),
+                        delay.as_millis(),
+                        attempt + 1,
+                        policy.max_attempts
+                    );
+                    attempt += 1;
+                    tokio::time::sleep(delay).await;
+                    continue;
+                }
+            }
+            return match result {
+                Ok(response) => Ok((response, route)),
+                Err(e) => Err((e.into(), Some(route))),
+            };
+        }
+    }
+
+    /// Send a request once through `proxied`, recording the outcome against its proxy
+    async fn send_once(
+        &self,
+        proxied: &ProxiedClient,
+        build: impl Fn(&Client) -> RequestBuilder,
+    ) -> Result<Response, reqwest::Error> {
+        // Held until the response arrives, counting against the proxy's request limits
+        let _permit = match &proxied.proxy {
+            Some(proxy) => Some(self.proxies.acquire(proxy).await),
+            None => None,
+        };
+        let request = build(&proxied.client).build();
+        let bytes_sent = request
+            .as_ref()
+            .ok()
+            .and_then(|request| request.body())
+            .and_then(|body| body.as_bytes())
+            .map_or(0, |body| body.len() as u64);
+        let started = Instant::now();
+        let result = match request {
+            Ok(request) => proxied.client.execute(request).await,
+            Err(e) => Err(e),
+        };
+        let response = result.as_ref().ok();
+        crate::metrics::record_request(
+            response.is_some_and(|response| response.status().as_u16() < 400),
+            response.map(|_| started.elapsed()),
+        );
+        if let Some(proxy) = &proxied.proxy {
+            self.proxies.record_sticky_result(
+                &self.proxy_session,
+                proxy,
+                Self::proxy_failed(&result),
+            );
+            let response = result.as_ref().ok();
+            self.proxies.record_request(
+                proxy,
+                &RequestOutcome {
+                    succeeded: response.is_some_and(|response| response.status().as_u16() < 400),
+                    latency: response.map(|_| started.elapsed()),
+                    bytes_sent,
+                    bytes_received: response
+                        .and_then(|response| response.content_length())
+                        .unwrap_or(0),
+                },
+            );
+        }
+        result
+    }
+
+    /// Take a failed proxy out of the rotation, dropping the clients connected through it
+    fn bench_proxy(&self, proxy: &ProxyConfig) {
+        log::warn!(
+            "Proxy {} failed, blacklisting it",
+            proxy.to_display_string()
+        );
+        self.proxies.mark_failed(proxy);
+        self.proxies.remove_warm_client(&proxy.identity());
+        self.unpin();
+    }
+
+    /// Let a dedicated client choose its proxy again on the next request
+    fn unpin(&self) {
+        if let Some(Ok(mut pinned)) = self.pinned.as_ref().map(|pinned| pinned.lock()) {
+            *pinned = None;
+        }
+    }
+
+    /// Whether a request failed because of its proxy rather than the orchestrator
+    fn proxy_failed(result: &Result<Response, reqwest::Error>) -> bool {
+        match result {
+            Err(e) => e.is_connect() || e.is_timeout(),
+            Ok(response) => response.status() == StatusCode::PROXY_AUTHENTICATION_REQUIRED,
+        }
+    }
+
+    /// Whether a proxied request is worth retrying through another proxy: its proxy failed, or
+    /// answered 502 or 503, which a proxy returns when it cannot reach the orchestrator. Those
+    /// may also come from the orchestrator itself, so they blacklist the proxy only when
+    /// retried through another one.
+    fn should_retry(result: &Result<Response, reqwest::Error>) -> bool {
+        Self::proxy_failed(result)
+            || result.as_ref().is_ok_and(|response| {
+                matches!(
+                    response.status(),
+                    StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE
+                )
+            })
+    }
+
+    /// Whether a request failed before reaching the orchestrator: no connection could be made,
+    /// or the proxy refused its credentials. Only such requests are safe to send again when
+    /// they have side effects.
+    fn never_sent(result: &Result<Response, reqwest::Error>) -> bool {
+        match result {
+            Err(e) => e.is_connect(),
+            Ok(response) => response.status() == StatusCode::PROXY_AUTHENTICATION_REQUIRED,
+        }
+    }
+
+    /// What went wrong with a failed request, for the log
+    fn describe_failure(result: &Result<Response, reqwest::Error>) -> String {
+        match result {
+            Err(e) if e.is_timeout() => "timed out".to_string(),
+            Err(_) => "could not connect".to_string(),
+            Ok(response) => format!("HTTP {}", response.status().as_u16()),
+        }
+    }
+
+    /// Connect to the orchestrator through up to `count` proxies before the first task, so
+    /// their TLS sessions are ready and proxies that refuse the connection or its credentials
+    /// are blacklisted up front.
+    ///
+    /// Returns how many proxies were warmed up and how many failed.
+    pub async fn warm_up_proxies(&self, count: usize) -> (usize, usize) {
+        if !self.proxies.should_use() {
+            return (0, 0);
+        }
+        let proxies = self
+            .proxies
+            .manager()
+            .lock()
+            .map(|mut manager| manager.usable_proxies(count))
+            .unwrap_or_default();
+        let url = self.build_url("");
+        let attempts: Vec<_> = proxies
+            .into_iter()
+            .map(|proxy| {
+                let url = url.clone();
+                tokio::spawn(async move {
+                    let client = Self::create_client(false, Some(&proxy));
+                    // Any response, even an error status, means the connection is established
+                    let result = client.head(&url).send().await;
+                    (proxy, client, Self::proxy_failed(&result))
+                })
+            })
+            .collect();
+
+        let (mut warmed, mut failed) = (0, 0);
+        for attempt in attempts {
+            let Ok((proxy, client, proxy_failed)) = attempt.await else {
+                continue;
+            };
+            if proxy_failed {
+                log::warn!(
+                    "Proxy {} failed warm-up, blacklisting it",
+                    proxy.to_display_string()
+                );
+                self.proxies.mark_failed(&proxy);
+                failed += 1;
+            } else {
+                self.proxies.add_warm_client(proxy.identity(), client);
+                warmed += 1;
+            }
+        }
+        (warmed, failed)
+    }
+
+    /// Connect to the orchestrator through `proxy` for a health check, returning the latency.
+    /// The probe is a `HEAD` of the orchestrator's root, not an API endpoint, so it costs the
+    /// orchestrator nothing beyond the connection.
+    ///
+    /// Only failures attributable to the proxy (connection, timeout, proxy authentication)
+    /// count; any response from the orchestrator means the proxy works.
+    pub async fn check_proxy(&self, proxy: ProxyConfig) -> Result<Duration, String> {
+        let client = Self::create_client(false, Some(&proxy));
+        let started = std::time::Instant::now();
+        let result = client
+            .head(self.build_url(""))
+            .timeout(PROXY_CHECK_TIMEOUT)
+            .send()
+            .await;
+        if !Self::proxy_failed(&result) {
+            return Ok(started.elapsed());
+        }
+        Err(match result {
+            Ok(response) => format!("HTTP {}", response.status()),
+            Err(e) if e.is_timeout() => "timed out".to_string(),
+            Err(e) => e.to_string(),
+        })
+    }
+
+    /// Read the orchestrator's clock from the `Date` header of a request, to check for clock skew
+    pub async fn server_time(&self) -> Result<chrono::DateTime<chrono::Utc>, String> {
+        let client = self.choose_client().await.map_err(|e| e.to_string())?;
+        let response = client
+            .client
+            .head(self.build_url(""))
+            .timeout(PROXY_CHECK_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        let date = response
+            .headers()
+            .get(reqwest::header::DATE)
+            .and_then(|date| date.to_str().ok())
+            .ok_or("no Date header")?;
+        chrono::DateTime::parse_from_rfc2822(date)
+            .map(|date| date.with_timezone(&chrono::Utc))
+            .map_err(|e| e.to_string())
+    }
+
+    /// Find where traffic through `proxy` leaves to the internet by asking the service at
+    /// `url`, for the blocklist pre-check
+    pub async fn lookup_exit(&self, proxy: ProxyConfig, url: &str) -> Result<ExitInfo, String> {
+        let client = Self::create_client(false, Some(&proxy));
+        let response = client
+            .get(url)
+            .timeout(PROXY_CHECK_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("HTTP {}", response.status()));
+        }
+        let body = response.text().await.map_err(|e| e.to_string())?;
+        ExitInfo::from_ipinfo(&body)
     }
 
     fn build_url(&self, endpoint: &str) -> String {
@@ -115,7 +676,10 @@ impl OrchestratorClient {
     }
 
     fn decode_response<T: Message + Default>(bytes: &[u8]) -> Result<T, OrchestratorError> {
-        T::decode(bytes).map_err(OrchestratorError::Decode)
+        T::decode(bytes).map_err(|e| {
+            let drift = SchemaDrift::analyze::<T>(bytes, &e).save_payload(bytes);
+            OrchestratorError::SchemaDrift(Box::new(drift))
+        })
     }
 
     async fn handle_response_status(response: Response) -> Result<Response, OrchestratorError> {
@@ -125,53 +689,102 @@ impl OrchestratorClient {
         Ok(response)
     }
 
+    /// GET an endpoint, also returning the route the request took
     async fn get_request<T: Message + Default>(
         &self,
-        endpoint: &str,
-    ) -> Result<T, OrchestratorError> {
-        let url = self.build_url(endpoint);
-        let response = self.client.get(&url).send().await?;
+        endpoint: Endpoint<'_>,
+    ) -> Result<(T, String), OrchestratorError> {
+        let url = self.build_url(&endpoint.path());
+        let response = self.get_single_flight(&url).await?;
+        let (bytes, route) = response.as_ref();
+        Ok((Self::decode_response(bytes)?, route.clone()))
+    }
 
-        let response = Self::handle_response_status(response).await?;
-        let response_bytes = response.bytes().await?;
-        Self::decode_response(&response_bytes)
+    /// GET a URL, joining an identical request that is already in flight rather than sending
+    /// another one. Responses are not cached: once a request completes, the next caller sends
+    /// a new one.
+    async fn get_single_flight(
+        &self,
+        url: &str,
+    ) -> Result<Arc<(Vec<u8>, String)>, OrchestratorError> {
+        let cell = match self.in_flight.lock() {
+            Ok(mut in_flight) => in_flight.entry(url.to_string()).or_default().clone(),
+            Err(_) => Arc::default(),
+        };
+
+        // Only one caller runs the request; the rest wait for its result
+        let result = cell
+            .get_or_init(|| async {
+                let fetch = async {
+                    let (response, route) = self
+                        .send_with_route(None, Resend::Retryable, |client| client.get(url))
+                        .await?;
+                    let response = Self::handle_response_status(response).await?;
+                    Ok::<_, OrchestratorError>((response.bytes().await?.to_vec(), route))
+                };
+                fetch.await.map(Arc::new).map_err(Arc::new)
+            })
+            .await
+            .clone();
+
+        if let Ok(mut in_flight) = self.in_flight.lock() {
+            // A newer request for the same URL may already have replaced this one
+            in_flight.retain(|key, entry| key != url || !Arc::ptr_eq(entry, &cell));
+        }
+        result.map_err(|e| e.duplicate())
     }
 
+    /// POST to an endpoint, also returning the route the request took
     async fn post_request<T: Message + Default>(
         &self,
-        endpoint: &str,
+        endpoint: Endpoint<'_>,
         body: Vec<u8>,
-    ) -> Result<T, OrchestratorError> {
-        let url = self.build_url(endpoint);
-        let client = Self::get_client_for_request();
-        let response = client
-            .post(&url)
-            .header("Content-Type", "application/octet-stream")
-            .body(body)
-            .send()
+    ) -> Result<(T, String), OrchestratorError> {
+        let url = self.build_url(&endpoint.path());
+        let (response, route) = self
+            .send_with_route(None, Resend::Unsent, |client| {
+                client
+                    .post(&url)
+                    .header("Content-Type", "application/octet-stream")
+                    .body(body.clone())
+            })
             .await?;
 
         let response = Self::handle_response_status(response).await?;
         let response_bytes = response.bytes().await?;
-        Self::decode_response(&response_bytes)
+        Ok((Self::decode_response(&response_bytes)?, route))
     }
 
+    /// POST to an endpoint that answers without a body, returning the response headers
     async fn post_request_no_response(
         &self,
-        endpoint: &str,
+        endpoint: Endpoint<'_>,
         body: Vec<u8>,
-    ) -> Result<(), OrchestratorError> {
-        let url = self.build_url(endpoint);
-        let client = Self::get_client_for_request();
-        let response = client
-            .post(&url)
-            .header("Content-Type", "application/octet-stream")
-            .body(body)
-            .send()
-            .await?;
+        affinity: Option<Affinity<'_>>,
+    ) -> Result<HeaderMap, OrchestratorError> {
+        let url = self.build_url(&endpoint.path());
+        let started = std::time::Instant::now();
+        let sent = self
+            .send_routed(affinity, Resend::Unsent, |client| {
+                client
+                    .post(&url)
+                    .header("Content-Type", "application/octet-stream")
+                    .body(body.clone())
+            })
+            .await;
+        // Proof submissions are tracked against the latency SLO by route, failed ones too, so
+        // timeouts count against it
+        let route = match &sent {
+            Ok((_, route)) => Some(route),
+            Err((_, route)) => route.as_ref(),
+        };
+        if let Some(route) = route.filter(|_| matches!(endpoint, Endpoint::SubmitProof)) {
+            crate::latency_slo::record(&self.environment, route, started.elapsed());
+        }
+        let (response, _) = sent.map_err(|(e, _)| e)?;
 
-        Self::handle_response_status(response).await?;
-        Ok(())
+        let response = Self::handle_response_status(response).await?;
+        Ok(response.headers().clone())
     }
 
     fn create_signature(
@@ -198,15 +811,13 @@ impl OrchestratorClient {
     /// information. The country information helps the Nexus network route requests to
     /// the nearest servers for better performance and reduced latency.
     ///
-    /// The detection is cached for the duration of the program run.
+    /// The detection is cached for the duration of the program run, and concurrent
+    /// submissions share a single detection.
     async fn get_country(&self) -> String {
-        if let Some(country) = COUNTRY_CODE.get() {
-            return country.clone();
-        }
-
-        let country = self.detect_country().await;
-        let _ = COUNTRY_CODE.set(country.clone());
-        country
+        COUNTRY_CODE
+            .get_or_init(|| self.detect_country())
+            .await
+            .clone()
     }
 
     async fn detect_country(&self) -> String {
@@ -225,11 +836,12 @@ impl OrchestratorClient {
     }
 
     async fn get_country_from_cloudflare(&self) -> Result<String, Box<dyn std::error::Error>> {
-        let client = Self::get_client_for_request();
-        let response = client
-            .get("https://cloudflare.com/cdn-cgi/trace")
-            .timeout(Duration::from_secs(5))
-            .send()
+        let response = self
+            .send(|client| {
+                client
+                    .get("https://cloudflare.com/cdn-cgi/trace")
+                    .timeout(Duration::from_secs(5))
+            })
             .await?;
 
         let text = response.text().await?;
@@ -247,11 +859,12 @@ impl OrchestratorClient {
     }
 
     async fn get_country_from_ipinfo(&self) -> Result<String, Box<dyn std::error::Error>> {
-        let client = Self::get_client_for_request();
-        let response = client
-            .get("https://ipinfo.io/country")
-            .timeout(Duration::from_secs(5))
-            .send()
+        let response = self
+            .send(|client| {
+                client
+                    .get("https://ipinfo.io/country")
+                    .timeout(Duration::from_secs(5))
+            })
             .await?;
 
         let country = response.text().await?;
@@ -271,12 +884,14 @@ impl Orchestrator for OrchestratorClient {
         &self.environment
     }
 
+    fn take_proxy_switch(&self, task_id: &str) -> Option<(String, String)> {
+        self.proxies.take_affinity_switch(task_id)
+    }
+
     /// Get the user ID associated with a wallet address.
     async fn get_user(&self, wallet_address: &str) -> Result<String, OrchestratorError> {
-        let wallet_path = urlencoding::encode(wallet_address).into_owned();
-        let endpoint = format!("v3/users/{}", wallet_path);
-
-        let user_response: UserResponse = self.get_request(&endpoint).await?;
+        let (user_response, _): (UserResponse, _) =
+            self.get_request(Endpoint::User { wallet_address }).await?;
         Ok(user_response.user_id)
     }
 
@@ -292,8 +907,9 @@ impl Orchestrator for OrchestratorClient {
         };
         let request_bytes = Self::encode_request(&request);
 
-        self.post_request_no_response("v3/users", request_bytes)
+        self.post_request_no_response(Endpoint::Users, request_bytes, None)
             .await
+            .map(|_| ())
     }
 
     /// Registers a new node with the orchestrator.
@@ -304,22 +920,29 @@ impl Orchestrator for OrchestratorClient {
         };
         let request_bytes = Self::encode_request(&request);
 
-        let response: RegisterNodeResponse = self.post_request("v3/nodes", request_bytes).await?;
+        let (response, _): (RegisterNodeResponse, _) =
+            self.post_request(Endpoint::Nodes, request_bytes).await?;
         Ok(response.node_id)
     }
 
     /// Get the wallet address associated with a node ID.
     async fn get_node(&self, node_id: &str) -> Result<String, OrchestratorError> {
-        let endpoint = format!("v3/nodes/{}", node_id);
-
-        let node_response: crate::nexus_orchestrator::GetNodeResponse =
-            self.get_request(&endpoint).await?;
+        let (node_response, _): (crate::nexus_orchestrator::GetNodeResponse, _) =
+            self.get_request(Endpoint::Node { node_id }).await?;
         Ok(node_response.wallet_address)
     }
 
     async fn get_tasks(&self, node_id: &str) -> Result<Vec<Task>, OrchestratorError> {
-        let response: GetTasksResponse = self.get_request(&format!("v3/tasks/{}", node_id)).await?;
-        let tasks = response.tasks.iter().map(Task::from).collect();
+        let (response, route): (GetTasksResponse, _) =
+            self.get_request(Endpoint::NodeTasks { node_id }).await?;
+        let tasks = response
+            .tasks
+            .iter()
+            .map(|task| Task {
+                proxy: proxy_of(&route),
+                ..Task::from(task)
+            })
+            .collect();
         Ok(tasks)
     }
 
@@ -332,43 +955,43 @@ impl Orchestrator for OrchestratorClient {
             node_id: node_id.to_string(),
             node_type: NodeType::CliProver as i32,
             ed25519_public_key: verifying_key.to_bytes().to_vec(),
-            max_difficulty: TaskDifficulty::Large as i32,
+            max_difficulty: crate::difficulty::requested() as i32,
         };
         let request_bytes = Self::encode_request(&request);
 
-        let response: GetProofTaskResponse = self.post_request("v3/tasks", request_bytes).await?;
-        Ok(Task::from(&response))
+        let (response, route): (GetProofTaskResponse, _) = self
+            .post_request(Endpoint::ProofTask, request_bytes)
+            .await?;
+        Ok(Task {
+            proxy: proxy_of(&route),
+            ..Task::from(&response)
+        })
     }
 
     async fn submit_proof(
         &self,
-        task_id: &str,
-        proof_hash: &str,
-        proof: Vec<u8>,
+        submission: ProofSubmission,
         signing_key: SigningKey,
-        num_provers: usize,
-        task_type: Option<crate::nexus_orchestrator::TaskType>,
-    ) -> Result<(), OrchestratorError> {
+    ) -> Result<Option<SubmissionReceipt>, OrchestratorError> {
         let (program_memory, total_memory) = get_memory_info();
-        let flops = estimate_peak_gflops(num_provers);
-        let (signature, public_key) = self.create_signature(&signing_key, task_id, proof_hash);
+        let flops = self
+            .reported_flops
+            .unwrap_or_else(|| estimate_peak_gflops(submission.num_provers) as i32);
+        let task_id = submission.task_id.clone();
+        let proof_hash = submission.proof_hash.clone();
+        let proxy = submission.proxy.clone();
+        let (signature, public_key) = self.create_signature(&signing_key, &task_id, &proof_hash);
 
         // Detect country for network optimization (privacy-preserving: only country code, no precise location)
         let location = self.get_country().await;
-        // Only attach proof if task type is not ProofHash
-        // If task_type is None, default to attaching proof for backward compatibility
-        let proof_to_send = match task_type {
-            Some(crate::nexus_orchestrator::TaskType::ProofHash) => Vec::new(),
-            _ => proof, // Attach proof for ProofRequired or None (backward compatibility)
-        };
 
         let request = SubmitProofRequest {
-            task_id: task_id.to_string(),
+            task_id: task_id.clone(),
             node_type: NodeType::CliProver as i32,
-            proof_hash: proof_hash.to_string(),
-            proof: proof_to_send,
+            proof_hash,
+            proof: submission.into_attached_proof(),
             node_telemetry: Some(crate::nexus_orchestrator::NodeTelemetry {
-                flops_per_sec: Some(flops as i32),
+                flops_per_sec: Some(flops),
                 memory_used: Some(program_memory),
                 memory_capacity: Some(total_memory),
                 // Country code for network routing optimization (privacy-preserving)
@@ -379,8 +1002,25 @@ impl Orchestrator for OrchestratorClient {
         };
         let request_bytes = Self::encode_request(&request);
 
-        self.post_request_no_response("v3/tasks/submit", request_bytes)
-            .await
+        // Submit through the proxy the task was fetched through, so its IP does not change
+        let affinity = proxy.as_deref().map(|proxy| Affinity {
+            task_id: &task_id,
+            proxy,
+        });
+        let headers = self
+            .post_request_no_response(Endpoint::SubmitProof, request_bytes, affinity)
+            .await?;
+        // Only a receipt signed by the environment's pinned key is evidence of anything
+        let Some(trusted_key) = self.environment.receipt_key() else {
+            return Ok(None);
+        };
+        Ok(SubmissionReceipt::from_headers(
+            &headers,
+            &task_id,
+            &request.proof_hash,
+            &signing_key.verifying_key(),
+        )
+        .filter(|receipt| receipt.verify(trusted_key).is_ok()))
     }
 }
 
@@ -488,6 +1128,8 @@ mod live_orchestrator_tests {
 mod tests {
     use super::*;
     use crate::nexus_orchestrator::TaskType;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
     #[tokio::test]
     /// Should conditionally attach proof based on task type.
@@ -500,14 +1142,11 @@ mod tests {
         let num_workers = 4;
 
         // Test with ProofRequired task type - should attach proof
+        let submission = ProofSubmission::new(task_id, proof_hash, proof).num_provers(num_workers);
         let result = client
             .submit_proof(
-                task_id,
-                proof_hash,
-                proof.clone(),
+                submission.clone().task_type(Some(TaskType::ProofRequired)),
                 signing_key.clone(),
-                num_workers,
-                Some(TaskType::ProofRequired),
             )
             .await;
         // This will fail because we're not actually submitting to a real orchestrator,
@@ -516,14 +1155,7 @@ mod tests {
 
         // Test with ProofHash task type - should not attach proof
         let result = client
-            .submit_proof(
-                task_id,
-                proof_hash,
-                proof,
-                signing_key,
-                num_workers,
-                Some(TaskType::ProofHash),
-            )
+            .submit_proof(submission.task_type(Some(TaskType::ProofHash)), signing_key)
             .await;
         // This will also fail, but the proof should be empty in the request
         assert!(result.is_err()); // Expected to fail due to network error
@@ -545,4 +1177,178 @@ mod tests {
             Err(e) => panic!("Failed to get wallet address for node: {}", e),
         }
     }
+
+    /// Serves `body` to every request after a short delay, counting the requests received.
+    async fn slow_server(body: Vec<u8>) -> (String, Arc<AtomicUsize>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let body = body.clone();
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 4096];
+                    let _ = stream.read(&mut buf).await;
+                    tokio::time::sleep(Duration::from_millis(200)).await;
+                    let head = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        body.len()
+                    );
+                    let _ = stream.write_all(head.as_bytes()).await;
+                    let _ = stream.write_all(&body).await;
+                });
+            }
+        });
+        (url, requests)
+    }
+
+    #[tokio::test]
+    /// Identical concurrent requests should reach the server once; later ones go out again.
+    async fn test_identical_gets_share_one_request() {
+        let body = UserResponse {
+            user_id: "user-1".to_string(),
+            ..Default::default()
+        }
+        .encode_to_vec();
+        let (url, requests) = slow_server(body).await;
+        let client = OrchestratorClient::new(Environment::Custom {
+            orchestrator_url: url,
+        });
+
+        let results = tokio::join!(
+            client.get_user("0xabc"),
+            client.get_user("0xabc"),
+            client.get_user("0xabc"),
+            client.get_user("0xabc")
+        );
+        for result in [results.0, results.1, results.2, results.3] {
+            assert_eq!(result.unwrap(), "user-1");
+        }
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        client.get_user("0xabc").await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    /// Answers every request with `status` and `body`, as a proxy would, counting the requests.
+    async fn fake_proxy(
+        status: &'static str,
+        body: Vec<u8>,
+    ) -> (std::net::SocketAddr, Arc<AtomicUsize>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let body = body.clone();
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 4096];
+                    let _ = stream.read(&mut buf).await;
+                    let head = format!(
+                        "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        status,
+                        body.len()
+                    );
+                    let _ = stream.write_all(head.as_bytes()).await;
+                    let _ = stream.write_all(&body).await;
+                });
+            }
+        });
+        (addr, requests)
+    }
+
+    #[tokio::test]
+    /// A request whose proxy refuses it should be retried through another proxy.
+    async fn test_retry_through_another_proxy() {
+        let body = UserResponse {
+            user_id: "user-1".to_string(),
+            ..Default::default()
+        }
+        .encode_to_vec();
+        let (failing, failed_requests) =
+            fake_proxy("407 Proxy Authentication Required", Vec::new()).await;
+        let (working, working_requests) = fake_proxy("200 OK", body).await;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("proxies.txt");
+        std::fs::write(&path, format!("{}:u:p\n{}:u:p\n", failing, working)).unwrap();
+        let client = OrchestratorClient::with_proxies(
+            Environment::Custom {
+                orchestrator_url: "http://orchestrator.test".to_string(),
+            },
+            Arc::new(ProxyContext::new(true, path.to_string_lossy())),
+        );
+
+        for _ in 0..5 {
+            assert_eq!(client.get_user("0xabc").await.unwrap(), "user-1");
+        }
+        assert_eq!(working_requests.load(Ordering::SeqCst), 5);
+        // The refusing proxy is blacklisted after its first failure
+        assert!(failed_requests.load(Ordering::SeqCst) <= 1);
+    }
+
+    /// Answers requests with `heads` in turn (the last one from then on), then `body`.
+    async fn flaky_server(heads: Vec<&'static str>, body: Vec<u8>) -> (String, Arc<AtomicUsize>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let count = counter.fetch_add(1, Ordering::SeqCst);
+                let head = heads[count.min(heads.len() - 1)];
+                let body = body.clone();
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 4096];
+                    let _ = stream.read(&mut buf).await;
+                    let head = format!(
+                        "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        head,
+                        body.len()
+                    );
+                    let _ = stream.write_all(head.as_bytes()).await;
+                    let _ = stream.write_all(&body).await;
+                });
+            }
+        });
+        (url, requests)
+    }
+
+    #[tokio::test]
+    /// A server that is briefly unavailable should be retried until it answers.
+    async fn test_retry_unavailable_server() {
+        let body = UserResponse {
+            user_id: "user-1".to_string(),
+            ..Default::default()
+        }
+        .encode_to_vec();
+        let (url, requests) = flaky_server(vec!["503 Service Unavailable", "200 OK"], body).await;
+        let client = OrchestratorClient::new(Environment::Custom {
+            orchestrator_url: url,
+        });
+
+        assert_eq!(client.get_user("0xabc").await.unwrap(), "user-1");
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    /// A Retry-After longer than the retry policy allows should be left to the caller.
+    async fn test_long_retry_after_is_not_waited_out() {
+        let (url, requests) = flaky_server(
+            vec!["429 Too Many Requests\r\nRetry-After: 3600", "200 OK"],
+            Vec::new(),
+        )
+        .await;
+        let client = OrchestratorClient::new(Environment::Custom {
+            orchestrator_url: url,
+        });
+
+        let error = client.get_user("0xabc").await.unwrap_err();
+        assert!(matches!(error, OrchestratorError::RateLimited { .. }));
+        assert_eq!(error.get_retry_after_seconds(), Some(3600));
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
 }
@@ -0,0 +1,243 @@
+//! DNS-over-HTTPS resolution for orchestrator hostnames.
+//!
+//! On networks where plain DNS to the orchestrator is blocked or poisoned, `--doh` resolves
+//! hostnames through a DoH server's JSON API instead. There is deliberately no fallback to the
+//! system resolver: a poisoned answer is worse than a failed lookup.
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::{Client, Url};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Used by `--doh` without a URL. Addressed by IP so the DoH server itself needs no DNS.
+pub const DEFAULT_DOH_URL: &str = "https://1.1.1.1/dns-query";
+
+/// Answers are never cached for longer than this, whatever their TTL
+const MAX_TTL: Duration = Duration::from_secs(300);
+
+/// DNS record types, as numbered in DoH JSON answers
+const TYPE_A: u16 = 1;
+const TYPE_AAAA: u16 = 28;
+
+/// A DoH JSON response; only the fields needed to collect addresses
+#[derive(Debug, Deserialize)]
+struct DohResponse {
+    #[serde(rename = "Status")]
+    status: u32,
+    #[serde(rename = "Answer", default)]
+    answer: Vec<DohAnswer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DohAnswer {
+    #[serde(rename = "type")]
+    record_type: u16,
+    #[serde(rename = "TTL", default)]
+    ttl: u64,
+    data: String,
+}
+
+impl DohResponse {
+    /// The addresses of the given record type, and the shortest TTL among them.
+    ///
+    /// CNAME records in the chain are skipped; their targets' addresses follow in the answer.
+    fn addresses(&self, record_type: u16) -> (Vec<IpAddr>, Duration) {
+        let mut ttl = MAX_TTL;
+        let addrs = self
+            .answer
+            .iter()
+            .filter(|answer| answer.record_type == record_type)
+            .filter_map(|answer| {
+                let addr = answer.data.parse().ok()?;
+                ttl = ttl.min(Duration::from_secs(answer.ttl));
+                Some(addr)
+            })
+            .collect();
+        (addrs, ttl)
+    }
+}
+
+/// Check a `--doh` URL: the server must be reached over HTTPS.
+pub fn parse_doh_url(url: &str) -> Result<Url, String> {
+    let url = Url::parse(url).map_err(|e| format!("invalid DoH URL {}: {}", url, e))?;
+    if url.scheme() != "https" {
+        return Err(format!("DoH URL {} must use https", url));
+    }
+    Ok(url)
+}
+
+/// Resolves hostnames through a DoH server, caching answers for their TTL.
+#[derive(Debug)]
+pub struct DohResolver {
+    url: Url,
+    /// Resolves the DoH server itself with the system resolver, so it is best given by IP
+    client: Client,
+    cache: Mutex<HashMap<String, (Vec<IpAddr>, Instant)>>,
+}
+
+impl DohResolver {
+    pub fn new(url: Url) -> Self {
+        let client = Client::builder()
+            .connect_timeout(Duration::from_secs(5))
+            .timeout(Duration::from_secs(10));
+        let client = crate::orchestrator::tls::apply(client)
+            .build()
+            .expect("Failed to create DoH client");
+        Self {
+            url,
+            client,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The URL of the DoH server.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    async fn query(&self, host: &str, record_type: u16) -> Result<DohResponse, String> {
+        let response = self
+            .client
+            .get(self.url.clone())
+            .query(&[("name", host), ("type", &record_type.to_string())])
+            .header("accept", "application/dns-json")
+            .send()
+            .await
+            .map_err(|e| format!("DoH query for {} failed: {}", host, e))?
+            .error_for_status()
+            .map_err(|e| format!("DoH query for {} failed: {}", host, e))?;
+        response
+            .json()
+            .await
+            .map_err(|e| format!("invalid DoH response for {}: {}", host, e))
+    }
+
+    /// Look up the IPv4 and IPv6 addresses of `host`, IPv4 first.
+    pub async fn lookup(&self, host: &str) -> Result<Vec<IpAddr>, String> {
+        if let Ok(addr) = host.parse::<IpAddr>() {
+            return Ok(vec![addr]);
+        }
+        if let Some((addrs, expires)) = self.cache.lock().unwrap().get(host) {
+            if Instant::now() < *expires {
+                return Ok(addrs.clone());
+            }
+        }
+
+        let (v4, v6) = tokio::join!(self.query(host, TYPE_A), self.query(host, TYPE_AAAA));
+        let mut addrs = Vec::new();
+        let mut ttl = MAX_TTL;
+        let mut last_error = None;
+        for (response, record_type) in [(v4, TYPE_A), (v6, TYPE_AAAA)] {
+            match response {
+                // Status 0 is NOERROR; anything else (e.g. NXDOMAIN) has no usable answer
+                Ok(response) if response.status == 0 => {
+                    let (found, found_ttl) = response.addresses(record_type);
+                    if !found.is_empty() {
+                        ttl = ttl.min(found_ttl);
+                    }
+                    addrs.extend(found);
+                }
+                Ok(response) => {
+                    last_error = Some(format!(
+                        "DoH lookup of {} returned DNS status {}",
+                        host, response.status
+                    ))
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        if addrs.is_empty() {
+            return Err(last_error.unwrap_or_else(|| format!("no addresses found for {}", host)));
+        }
+
+        log::debug!("Resolved {} via DoH: {:?}", host, addrs);
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(host.to_string(), (addrs.clone(), Instant::now() + ttl));
+        Ok(addrs)
+    }
+}
+
+/// Plugs the resolver into reqwest; the port is filled in from the request URL.
+#[derive(Debug, Clone)]
+struct SharedResolver(Arc<DohResolver>);
+
+impl Resolve for SharedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolver = self.0.clone();
+        Box::pin(async move {
+            let addrs = resolver.lookup(name.as_str()).await?;
+            let addrs: Addrs = Box::new(addrs.into_iter().map(|ip| SocketAddr::new(ip, 0)));
+            Ok(addrs)
+        })
+    }
+}
+
+static RESOLVER: OnceLock<Arc<DohResolver>> = OnceLock::new();
+
+/// Resolves orchestrator hostnames via DoH from now on. Only the first call has an effect.
+pub fn set_doh_url(url: Url) {
+    let _ = RESOLVER.set(Arc::new(DohResolver::new(url)));
+}
+
+/// The DoH resolver for orchestrator clients, if `--doh` was given.
+pub fn doh_resolver() -> Option<Arc<DohResolver>> {
+    RESOLVER.get().cloned()
+}
+
+/// Configures a client builder to resolve hostnames via DoH, if enabled.
+pub fn apply(builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+    match doh_resolver() {
+        Some(resolver) => builder.dns_resolver(Arc::new(SharedResolver(resolver))),
+        None => builder,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Only A records are collected for an A query, skipping the CNAME chain, with the lowest TTL.
+    fn test_addresses_from_answer() {
+        let response: DohResponse = serde_json::from_str(
+            r#"{"Status":0,"Answer":[
+                {"name":"orchestrator.nexus.xyz","type":5,"TTL":600,"data":"lb.nexus.xyz."},
+                {"name":"lb.nexus.xyz","type":1,"TTL":60,"data":"203.0.113.7"},
+                {"name":"lb.nexus.xyz","type":1,"TTL":120,"data":"203.0.113.8"}
+            ]}"#,
+        )
+        .unwrap();
+        let (addrs, ttl) = response.addresses(TYPE_A);
+        assert_eq!(
+            addrs,
+            vec![
+                "203.0.113.7".parse::<IpAddr>().unwrap(),
+                "203.0.113.8".parse::<IpAddr>().unwrap()
+            ]
+        );
+        assert_eq!(ttl, Duration::from_secs(60));
+        assert!(response.addresses(TYPE_AAAA).0.is_empty());
+    }
+
+    #[test]
+    // NXDOMAIN responses carry no Answer section at all.
+    fn test_response_without_answer() {
+        let response: DohResponse = serde_json::from_str(r#"{"Status":3}"#).unwrap();
+        assert_eq!(response.status, 3);
+        assert!(response.addresses(TYPE_A).0.is_empty());
+    }
+
+    #[test]
+    // Queries must not leak over plain HTTP.
+    fn test_parse_doh_url() {
+        assert!(parse_doh_url(DEFAULT_DOH_URL).is_ok());
+        assert!(parse_doh_url("https://dns.google/resolve").is_ok());
+        assert!(parse_doh_url("http://1.1.1.1/dns-query").is_err());
+        assert!(parse_doh_url("not a url").is_err());
+    }
+}
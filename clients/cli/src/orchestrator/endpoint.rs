@@ -0,0 +1,62 @@
+//! Orchestrator endpoints
+//!
+//! Every path the client calls is built here, so request methods never format URLs themselves.
+
+/// An orchestrator endpoint, with its path parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endpoint<'a> {
+    /// Look up a user by wallet address.
+    User { wallet_address: &'a str },
+    /// Register a user.
+    Users,
+    /// Look up a node.
+    Node { node_id: &'a str },
+    /// Register a node.
+    Nodes,
+    /// The tasks currently assigned to a node.
+    NodeTasks { node_id: &'a str },
+    /// Request a new proof task.
+    ProofTask,
+    /// Submit a proof.
+    SubmitProof,
+}
+
+impl Endpoint<'_> {
+    /// The path relative to the orchestrator URL, with parameters percent-encoded.
+    pub fn path(&self) -> String {
+        match self {
+            Endpoint::User { wallet_address } => {
+                format!("v3/users/{}", urlencoding::encode(wallet_address))
+            }
+            Endpoint::Users => "v3/users".to_string(),
+            Endpoint::Node { node_id } => format!("v3/nodes/{}", urlencoding::encode(node_id)),
+            Endpoint::Nodes => "v3/nodes".to_string(),
+            Endpoint::NodeTasks { node_id } => {
+                format!("v3/tasks/{}", urlencoding::encode(node_id))
+            }
+            Endpoint::ProofTask => "v3/tasks".to_string(),
+            Endpoint::SubmitProof => "v3/tasks/submit".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Paths should match the v3 API, with parameters encoded.
+    fn test_endpoint_paths() {
+        assert_eq!(
+            Endpoint::User {
+                wallet_address: "0xabc"
+            }
+            .path(),
+            "v3/users/0xabc"
+        );
+        assert_eq!(Endpoint::Node { node_id: "42" }.path(), "v3/nodes/42");
+        assert_eq!(Endpoint::NodeTasks { node_id: "42" }.path(), "v3/tasks/42");
+        assert_eq!(Endpoint::SubmitProof.path(), "v3/tasks/submit");
+        assert_eq!(Endpoint::Node { node_id: "a/b" }.path(), "v3/nodes/a%2Fb");
+    }
+}
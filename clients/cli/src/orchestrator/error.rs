@@ -1,10 +1,33 @@
 //! Error handling for the orchestrator module
 
+use crate::maintenance::MaintenanceWindow;
+use crate::orchestrator::retry::rate_limit_wait;
+use crate::orchestrator::schema::SchemaDrift;
+use chrono::Utc;
 use prost::DecodeError;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::time::Duration;
 use thiserror::Error;
 
+/// How long to wait after a rate-limited response that does not say
+const DEFAULT_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+/// Error names the orchestrator uses when it no longer accepts a CLI version
+const VERSION_TOO_OLD_NAMES: &[&str] = &["VersionTooOld", "UnsupportedVersion", "UpgradeRequired"];
+
+/// Error names the orchestrator uses for a node it does not know
+const NODE_NOT_REGISTERED_NAMES: &[&str] = &["NodeNotRegistered", "NodeNotFound"];
+
+/// Error names the orchestrator uses when a refused request may succeed if sent again, e.g.
+/// after it lost a race with a concurrent update
+const RETRY_LATER_NAMES: &[&str] = &[
+    "TryAgainLater",
+    "TemporarilyUnavailable",
+    "ConcurrentUpdate",
+    "RetryableConflict",
+];
+
 #[allow(non_snake_case)] // used for json parsing
 #[derive(Serialize, Deserialize)]
 struct RawError {
@@ -19,6 +42,10 @@ pub enum OrchestratorError {
     #[error("Decoding error: {0}")]
     Decode(#[from] DecodeError),
 
+    /// A response no longer matches the expected message, most likely after a protocol change.
+    #[error("Orchestrator response schema drift: {0}")]
+    SchemaDrift(Box<SchemaDrift>),
+
     /// Reqwest error, typically related to network issues or request failures.
     #[error("Reqwest error: {0}")]
     Reqwest(#[from] reqwest::Error),
@@ -30,6 +57,89 @@ pub enum OrchestratorError {
         message: String,
         headers: HashMap<String, String>,
     },
+
+    /// The orchestrator did not accept the node's credentials (401).
+    #[error("Unauthorized: {message}")]
+    Unauthorized { message: String },
+
+    /// The orchestrator does not know this node, e.g. because it was registered against another
+    /// environment or deleted.
+    #[error("Node not registered: {message}")]
+    NodeNotRegistered { message: String },
+
+    /// The task was already submitted, or reassigned to another node (409).
+    #[error("Task already claimed: {message}")]
+    TaskAlreadyClaimed { message: String },
+
+    /// The orchestrator no longer accepts this version of the CLI.
+    #[error("CLI version too old: {message}")]
+    VersionTooOld { message: String },
+
+    /// The orchestrator refused the request for now, saying by its error name that it may
+    /// succeed if sent again (e.g. a conflict with a concurrent update), after `retry_after`
+    /// seconds if it said.
+    #[error("Refused for now (HTTP {status}): {message}")]
+    RetryLater {
+        status: u16,
+        message: String,
+        retry_after: Option<u32>,
+    },
+
+    /// The orchestrator is down for maintenance.
+    #[error("{0}")]
+    MaintenanceMode(MaintenanceWindow),
+
+    /// The orchestrator is rate limiting this node (429, or 503 with `Retry-After`) and asked
+    /// it to wait `retry_after` before the next request.
+    #[error("Rate limited (HTTP 429), retry in {}s", retry_after.as_secs())]
+    RateLimited { retry_after: Duration },
+
+    /// Proxies are in use but none is usable, and `--on-no-proxy fail` forbids a direct connection.
+    #[error("No usable proxy: {0}")]
+    NoProxy(String),
+
+    /// The orchestrator's certificate has an untrusted issuer, most likely because a corporate
+    /// proxy intercepts TLS.
+    #[error(
+        "TLS connection intercepted (untrusted certificate issuer); pass your network's CA with --ca-cert: {0}"
+    )]
+    TlsInterception(String),
+
+    /// A transport error from a request shared by several callers, which cannot be cloned, with
+    /// what callers need to tell it apart.
+    #[error("Reqwest error: {message}")]
+    SharedReqwest {
+        kind: TransportErrorKind,
+        status: Option<u16>,
+        message: String,
+    },
+}
+
+/// What kind of transport failure a [`reqwest::Error`] was.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransportErrorKind {
+    /// The request or connection timed out.
+    Timeout,
+    /// No connection could be made.
+    Connect,
+    /// The server answered with an error status.
+    Status,
+    /// Any other failure, e.g. while reading the body.
+    Other,
+}
+
+impl TransportErrorKind {
+    fn of(error: &reqwest::Error) -> Self {
+        if error.is_timeout() {
+            Self::Timeout
+        } else if error.is_connect() {
+            Self::Connect
+        } else if error.is_status() {
+            Self::Status
+        } else {
+            Self::Other
+        }
+    }
 }
 
 impl OrchestratorError {
@@ -44,24 +154,198 @@ impl OrchestratorError {
             }
         }
 
+        // Announced maintenance takes precedence over a plain overload
+        if let Some(window) = MaintenanceWindow::from_headers(&headers) {
+            return OrchestratorError::MaintenanceMode(window);
+        }
+
+        if let Some(retry_after) = rate_limit(status, &headers) {
+            return OrchestratorError::RateLimited { retry_after };
+        }
+
         let message = response
             .text()
             .await
             .unwrap_or_else(|_| "Failed to read response text".to_string());
 
-        OrchestratorError::Http {
-            status,
-            message,
-            headers,
+        Self::from_body(status, message, headers)
+    }
+
+    /// How long a rate-limited response asks to wait, if it is one.
+    fn rate_limit(status: u16, headers: &HashMap<String, String>) -> Option<Duration> {
+        // Only a 429, or a 503 that says when to come back, is rate limiting; the rate-limit
+        // headers on other responses are informational. The wait comes from `Retry-After`, or
+        // else `X-RateLimit-Reset`.
+        let overloaded = status == 503 && headers.contains_key("retry-after");
+        if status != 429 && !overloaded {
+            return None;
+        }
+        let retry_after = rate_limit_wait(
+            headers.get("retry-after").map(String::as_str),
+            headers.get("x-ratelimit-reset").map(String::as_str),
+            Utc::now(),
+        );
+        Some(retry_after.unwrap_or(DEFAULT_RATE_LIMIT_WAIT))
+    }
+
+    /// Decodes a failure the prover loop reacts to from the status and the JSON error body
+    /// (`{"name": ..., "message": ...}`), falling back to [`OrchestratorError::Http`].
+    fn from_body(status: u16, body: String, headers: HashMap<String, String>) -> OrchestratorError {
+        let raw = serde_json::from_str::<RawError>(&body).ok();
+        let name = raw
+            .as_ref()
+            .map(|raw| raw.name.as_str())
+            .unwrap_or_default();
+        let message = || match &raw {
+            Some(raw) => raw.message.clone(),
+            None => body.clone(),
+        };
+
+        if status == 426 || VERSION_TOO_OLD_NAMES.contains(&name) {
+            return Self::VersionTooOld { message: message() };
+        }
+        if NODE_NOT_REGISTERED_NAMES.contains(&name) {
+            return Self::NodeNotRegistered { message: message() };
+        }
+        if RETRY_LATER_NAMES.contains(&name) {
+            return Self::RetryLater {
+                status,
+                message: message(),
+                retry_after: headers
+                    .get("retry-after")
+                    .and_then(|value| value.parse().ok()),
+            };
+        }
+        match status {
+            401 => Self::Unauthorized { message: message() },
+            409 => Self::TaskAlreadyClaimed { message: message() },
+            _ => Self::Http {
+                status,
+                message: body,
+                headers,
+            },
+        }
+    }
+
+    /// The HTTP status of the response this error was decoded from, or the status its variant
+    /// stands for.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Self::Http { status, .. } => Some(*status),
+            Self::Unauthorized { .. } => Some(401),
+            Self::NodeNotRegistered { .. } => Some(404),
+            Self::TaskAlreadyClaimed { .. } => Some(409),
+            Self::VersionTooOld { .. } => Some(426),
+            Self::RetryLater { status, .. } => Some(*status),
+            Self::MaintenanceMode(_) => Some(503),
+            Self::RateLimited { .. } => Some(429),
+            Self::Reqwest(e) => e.status().map(|status| status.as_u16()),
+            Self::SharedReqwest { status, .. } => *status,
+            _ => None,
+        }
+    }
+
+    /// The kind of transport failure, if the request failed in transport rather than being
+    /// answered.
+    pub fn transport_kind(&self) -> Option<TransportErrorKind> {
+        match self {
+            Self::Reqwest(e) => Some(TransportErrorKind::of(e)),
+            Self::SharedReqwest { kind, .. } => Some(*kind),
+            _ => None,
+        }
+    }
+
+    /// The status of a response that definitively refused the request. Rate limits,
+    /// maintenance, refusals for now and transport errors are not: the same request may
+    /// succeed later.
+    pub fn rejection_status(&self) -> Option<u16> {
+        match self {
+            Self::MaintenanceMode(_) | Self::RateLimited { .. } | Self::RetryLater { .. } => None,
+            _ => self.status(),
+        }
+    }
+
+    /// Whether the orchestrator refused a request for a reason that may pass, so the same
+    /// request is worth sending again later: it is rate limiting or briefly failing (408, 5xx),
+    /// or the error name in its body says so (see `RETRY_LATER_NAMES`). The free-text message
+    /// is not read; other refusals, such as an invalid proof or a plain conflict, are permanent.
+    pub fn is_transient_rejection(&self) -> bool {
+        match self {
+            Self::RateLimited { .. } | Self::RetryLater { .. } => true,
+            Self::Http { status, .. } => *status == 408 || (500..600).contains(status),
+            _ => false,
         }
     }
 
-    /// Get the Retry-After header value in seconds, if present
+    /// A copy of this error for each caller of a shared request.
+    pub fn duplicate(&self) -> OrchestratorError {
+        match self {
+            Self::Decode(e) => Self::Decode(e.clone()),
+            Self::SchemaDrift(drift) => Self::SchemaDrift(drift.clone()),
+            Self::Reqwest(e) => Self::SharedReqwest {
+                kind: TransportErrorKind::of(e),
+                status: e.status().map(|status| status.as_u16()),
+                message: e.to_string(),
+            },
+            Self::Http {
+                status,
+                message,
+                headers,
+            } => Self::Http {
+                status: *status,
+                message: message.clone(),
+                headers: headers.clone(),
+            },
+            Self::Unauthorized { message } => Self::Unauthorized {
+                message: message.clone(),
+            },
+            Self::NodeNotRegistered { message } => Self::NodeNotRegistered {
+                message: message.clone(),
+            },
+            Self::TaskAlreadyClaimed { message } => Self::TaskAlreadyClaimed {
+                message: message.clone(),
+            },
+            Self::VersionTooOld { message } => Self::VersionTooOld {
+                message: message.clone(),
+            },
+            Self::RetryLater {
+                status,
+                message,
+                retry_after,
+            } => Self::RetryLater {
+                status: *status,
+                message: message.clone(),
+                retry_after: *retry_after,
+            },
+            Self::MaintenanceMode(window) => Self::MaintenanceMode(window.clone()),
+            Self::RateLimited { retry_after } => Self::RateLimited {
+                retry_after: *retry_after,
+            },
+            Self::NoProxy(reason) => Self::NoProxy(reason.clone()),
+            Self::TlsInterception(message) => Self::TlsInterception(message.clone()),
+            Self::SharedReqwest {
+                kind,
+                status,
+                message,
+            } => Self::SharedReqwest {
+                kind: *kind,
+                status: *status,
+                message: message.clone(),
+            },
+        }
+    }
+
+    /// Get the Retry-After header value in seconds, if present, or how long a rate-limited
+    /// node has to wait
     pub fn get_retry_after_seconds(&self) -> Option<u32> {
         match self {
+            Self::RateLimited { retry_after } => {
+                Some(u32::try_from(retry_after.as_secs()).unwrap_or(u32::MAX))
+            }
             Self::Http { headers, .. } => headers
                 .get("retry-after")
                 .and_then(|value| value.parse::<u32>().ok()),
+            Self::RetryLater { retry_after, .. } => *retry_after,
             _ => None,
         }
     }
@@ -81,6 +365,11 @@ impl OrchestratorError {
 
                 None
             }
+            Self::Unauthorized { .. }
+            | Self::NodeNotRegistered { .. }
+            | Self::TaskAlreadyClaimed { .. }
+            | Self::VersionTooOld { .. }
+            | Self::RetryLater { .. } => Some(self.to_string()),
             _ => None,
         }
     }
@@ -128,4 +417,143 @@ mod tests {
 
         assert_eq!(error.get_retry_after_seconds(), None);
     }
+
+    #[test]
+    fn test_rate_limited_retry_after_seconds() {
+        let error = OrchestratorError::RateLimited {
+            retry_after: Duration::from_secs(90),
+        };
+
+        assert_eq!(error.get_retry_after_seconds(), Some(90));
+        assert!(error.to_string().contains("429"));
+    }
+
+    #[test]
+    // A 429, or a 503 with Retry-After, is rate limiting; an exhausted window on a response
+    // that otherwise succeeded or failed is not.
+    fn test_rate_limit_statuses() {
+        let headers = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>()
+        };
+        let rate_limit = OrchestratorError::rate_limit;
+
+        assert_eq!(
+            rate_limit(429, &headers(&[("retry-after", "30")])),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            rate_limit(429, &headers(&[])),
+            Some(DEFAULT_RATE_LIMIT_WAIT)
+        );
+        assert_eq!(
+            rate_limit(503, &headers(&[("retry-after", "120")])),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(rate_limit(503, &headers(&[])), None);
+        let exhausted = headers(&[("x-ratelimit-remaining", "0"), ("x-ratelimit-reset", "60")]);
+        assert_eq!(rate_limit(400, &exhausted), None);
+        assert_eq!(rate_limit(200, &exhausted), None);
+    }
+
+    #[test]
+    fn test_typed_errors_from_body() {
+        let body = |name: &str| {
+            format!(
+                r#"{{"name":"{}","message":"Node 42 not found","httpCode":404}}"#,
+                name
+            )
+        };
+
+        assert!(matches!(
+            OrchestratorError::from_body(404, body("NodeNotFound"), HashMap::new()),
+            OrchestratorError::NodeNotRegistered { message } if message == "Node 42 not found"
+        ));
+        assert!(matches!(
+            OrchestratorError::from_body(401, "Invalid signature".to_string(), HashMap::new()),
+            OrchestratorError::Unauthorized { message } if message == "Invalid signature"
+        ));
+        assert!(matches!(
+            OrchestratorError::from_body(409, String::new(), HashMap::new()),
+            OrchestratorError::TaskAlreadyClaimed { .. }
+        ));
+        assert!(matches!(
+            OrchestratorError::from_body(400, body("UnsupportedVersion"), HashMap::new()),
+            OrchestratorError::VersionTooOld { .. }
+        ));
+        assert!(matches!(
+            OrchestratorError::from_body(426, String::new(), HashMap::new()),
+            OrchestratorError::VersionTooOld { .. }
+        ));
+    }
+
+    #[test]
+    fn test_untyped_errors_stay_http() {
+        // A 404 without a node error in the body only means there is no task
+        let body = r#"{"name":"NotFoundError","message":"No tasks available","httpCode":404}"#;
+        let error = OrchestratorError::from_body(404, body.to_string(), HashMap::new());
+        assert!(matches!(error, OrchestratorError::Http { status: 404, .. }));
+        assert_eq!(error.rejection_status(), Some(404));
+
+        let rate_limited = OrchestratorError::RateLimited {
+            retry_after: Duration::from_secs(1),
+        };
+        assert_eq!(rate_limited.status(), Some(429));
+        assert_eq!(rate_limited.rejection_status(), None);
+    }
+
+    #[test]
+    fn test_transient_rejections() {
+        let http = |status: u16, body: &str| {
+            OrchestratorError::from_body(status, body.to_string(), HashMap::new())
+        };
+
+        assert!(http(502, "Bad gateway").is_transient_rejection());
+        assert!(http(408, "").is_transient_rejection());
+        // A conflict the orchestrator names as retryable is sent again, after the wait it asks
+        let conflict = OrchestratorError::from_body(
+            409,
+            r#"{"name":"ConcurrentUpdate","message":"Task is being updated","httpCode":409}"#
+                .to_string(),
+            HashMap::from([("retry-after".to_string(), "15".to_string())]),
+        );
+        assert!(conflict.is_transient_rejection());
+        assert_eq!(conflict.rejection_status(), None);
+        assert_eq!(conflict.get_retry_after_seconds(), Some(15));
+        // The message does not matter: an unnamed conflict is final, and so is a 400
+        assert!(
+            !http(
+                409,
+                r#"{"name":"ConflictError","message":"Concurrent update, try again","httpCode":409}"#
+            )
+            .is_transient_rejection()
+        );
+        assert!(
+            !http(
+                400,
+                r#"{"name":"BadRequest","message":"Proof store temporarily unavailable","httpCode":400}"#
+            )
+            .is_transient_rejection()
+        );
+        assert!(
+            OrchestratorError::RateLimited {
+                retry_after: Duration::from_secs(5)
+            }
+            .is_transient_rejection()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_keeps_transport_kind() {
+        // Nothing listens on port 1, so the connection is refused
+        let error = OrchestratorError::from(reqwest::get("http://127.0.0.1:1").await.unwrap_err());
+        assert_eq!(error.transport_kind(), Some(TransportErrorKind::Connect));
+
+        let shared = error.duplicate();
+        assert_eq!(shared.transport_kind(), Some(TransportErrorKind::Connect));
+        assert_eq!(shared.status(), None);
+        assert_eq!(shared.to_string(), error.to_string());
+    }
 }
@@ -0,0 +1,178 @@
+//! Actionable hints for orchestrator errors
+//!
+//! Raw errors such as `HTTP error with status 403` tell users what failed but not what to do
+//! about it. Each known failure class maps to a one-line hint and a page of the docs, appended
+//! to the error wherever it is shown. To cover another failure, add a row to [`HINTS`].
+
+use crate::orchestrator::error::OrchestratorError;
+use std::fmt;
+
+const CLI_NODE_DOCS: &str = "https://docs.nexus.xyz/layer-1/testnet/cli-node";
+const FAQ_DOCS: &str = "https://docs.nexus.xyz/layer-1/testnet/faq";
+
+/// A class of failures sharing a remedy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// A single HTTP status
+    Status(u16),
+    /// Any 5xx status
+    ServerError,
+    /// A response that could not be decoded
+    Decode,
+    /// A node the orchestrator does not know
+    NodeNotRegistered,
+}
+
+impl ErrorClass {
+    fn matches_status(self, status: u16) -> bool {
+        match self {
+            Self::Status(expected) => status == expected,
+            Self::ServerError => (500..600).contains(&status),
+            Self::Decode | Self::NodeNotRegistered => false,
+        }
+    }
+
+    fn matches(self, error: &OrchestratorError) -> bool {
+        match error {
+            OrchestratorError::NodeNotRegistered { .. } => self == Self::NodeNotRegistered,
+            OrchestratorError::Decode(_) | OrchestratorError::SchemaDrift(_) => {
+                self == Self::Decode
+            }
+            error => error
+                .status()
+                .is_some_and(|status| self.matches_status(status)),
+        }
+    }
+}
+
+/// What to do about a class of failures.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ErrorHint {
+    pub class: ErrorClass,
+    pub hint: &'static str,
+    pub docs_url: &'static str,
+}
+
+impl fmt::Display for ErrorHint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Hint: {} See {}", self.hint, self.docs_url)
+    }
+}
+
+/// Known failure classes, checked in order.
+pub const HINTS: &[ErrorHint] = &[
+    ErrorHint {
+        class: ErrorClass::Status(401),
+        hint: "The orchestrator did not accept this node's credentials; check the node ID, or register again with `nexus-network register-node`.",
+        docs_url: CLI_NODE_DOCS,
+    },
+    ErrorHint {
+        class: ErrorClass::NodeNotRegistered,
+        hint: "The orchestrator does not know this node; register it with `nexus-network register-node`, in the same environment you run it in.",
+        docs_url: CLI_NODE_DOCS,
+    },
+    ErrorHint {
+        class: ErrorClass::Status(403),
+        hint: "This node is not allowed to do that; check that the node belongs to your wallet and that your IP or proxy is not blocked.",
+        docs_url: FAQ_DOCS,
+    },
+    ErrorHint {
+        class: ErrorClass::Status(409),
+        hint: "The task was already submitted or reassigned; nothing to do, the node moves on to a new task.",
+        docs_url: FAQ_DOCS,
+    },
+    ErrorHint {
+        class: ErrorClass::Status(426),
+        hint: "The orchestrator no longer accepts this version of the CLI; update it.",
+        docs_url: CLI_NODE_DOCS,
+    },
+    ErrorHint {
+        class: ErrorClass::Status(429),
+        hint: "Too many requests from this address; run fewer nodes per IP or spread them over proxies with --proxy.",
+        docs_url: FAQ_DOCS,
+    },
+    ErrorHint {
+        class: ErrorClass::ServerError,
+        hint: "The orchestrator is having trouble; the node retries on its own, no action is needed unless this persists.",
+        docs_url: FAQ_DOCS,
+    },
+    ErrorHint {
+        class: ErrorClass::Decode,
+        hint: "The orchestrator's response could not be read, most likely after a protocol change; update the CLI.",
+        docs_url: CLI_NODE_DOCS,
+    },
+];
+
+/// The hint for an error, if it is a known failure.
+pub fn hint_for(error: &OrchestratorError) -> Option<&'static ErrorHint> {
+    HINTS.iter().find(|hint| hint.class.matches(error))
+}
+
+/// `message` followed by the hint for `error`, if there is one.
+pub fn with_hint(message: String, error: &OrchestratorError) -> String {
+    match hint_for(error) {
+        Some(hint) => format!("{}. {}", message, hint),
+        None => message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn http(status: u16) -> OrchestratorError {
+        OrchestratorError::Http {
+            status,
+            message: String::new(),
+            headers: HashMap::new(),
+        }
+    }
+
+    #[test]
+    // Each known status maps to its own hint, any 5xx to the server error hint.
+    fn test_hint_for_status() {
+        for status in [401, 403, 409, 426, 429] {
+            assert_eq!(
+                hint_for(&http(status)).unwrap().class,
+                ErrorClass::Status(status)
+            );
+        }
+        assert_eq!(hint_for(&http(503)).unwrap().class, ErrorClass::ServerError);
+        assert!(hint_for(&http(404)).is_none());
+        assert!(hint_for(&http(200)).is_none());
+    }
+
+    #[test]
+    // Decode errors get a hint, transport errors do not, and the hint follows the message.
+    fn test_hint_for_error() {
+        let decode = OrchestratorError::Decode(
+            <prost_types::Timestamp as prost::Message>::decode(&[0xff][..]).unwrap_err(),
+        );
+        assert_eq!(hint_for(&decode).unwrap().class, ErrorClass::Decode);
+        assert!(hint_for(&OrchestratorError::NoProxy("none".to_string())).is_none());
+        let rate_limited = OrchestratorError::RateLimited {
+            retry_after: std::time::Duration::from_secs(60),
+        };
+        assert_eq!(
+            hint_for(&rate_limited).unwrap().class,
+            ErrorClass::Status(429)
+        );
+
+        let not_registered = OrchestratorError::NodeNotRegistered {
+            message: String::new(),
+        };
+        assert_eq!(
+            hint_for(&not_registered).unwrap().class,
+            ErrorClass::NodeNotRegistered
+        );
+
+        let message = with_hint("Failed".to_string(), &http(429));
+        assert!(message.starts_with("Failed. Hint: Too many requests"));
+        assert!(message.ends_with(FAQ_DOCS));
+        assert_eq!(
+            with_hint("Failed".to_string(), &http(404)),
+            "Failed".to_string()
+        );
+    }
+}
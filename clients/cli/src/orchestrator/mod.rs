@@ -1,11 +1,22 @@
 use crate::environment::Environment;
 use crate::orchestrator::error::OrchestratorError;
+use crate::receipts::SubmissionReceipt;
 use crate::task::Task;
 use ed25519_dalek::{SigningKey, VerifyingKey};
 
 mod client;
 pub use client::OrchestratorClient;
+pub mod doh;
+mod endpoint;
+pub use endpoint::Endpoint;
 pub mod error;
+pub mod hints;
+pub mod retry;
+pub mod schema;
+mod submission;
+pub use submission::ProofSubmission;
+pub mod tls;
+pub mod transport;
 
 #[cfg(test)]
 use mockall::{automock, predicate::*};
@@ -15,6 +26,10 @@ use mockall::{automock, predicate::*};
 pub trait Orchestrator: Send + Sync {
     fn environment(&self) -> &Environment;
 
+    /// Takes the proxy switch recorded for a task whose last request could not go through the
+    /// proxy the task was fetched through: that proxy, and the route used instead.
+    fn take_proxy_switch(&self, task_id: &str) -> Option<(String, String)>;
+
     /// Get the user ID associated with a wallet address.
     async fn get_user(&self, wallet_address: &str) -> Result<String, OrchestratorError>;
 
@@ -41,14 +56,11 @@ pub trait Orchestrator: Send + Sync {
         verifying_key: VerifyingKey,
     ) -> Result<Task, OrchestratorError>;
 
-    /// Submits a proof to the orchestrator.
+    /// Submits a proof to the orchestrator, signed with the node's key, returning the
+    /// orchestrator's receipt for it if it sent one.
     async fn submit_proof(
         &self,
-        task_id: &str,
-        proof_hash: &str,
-        proof: Vec<u8>,
+        submission: ProofSubmission,
         signing_key: SigningKey,
-        num_provers: usize,
-        task_type: Option<crate::nexus_orchestrator::TaskType>,
-    ) -> Result<(), OrchestratorError>;
+    ) -> Result<Option<SubmissionReceipt>, OrchestratorError>;
 }
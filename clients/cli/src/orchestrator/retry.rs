@@ -0,0 +1,227 @@
+//! Retries of orchestrator requests.
+//!
+//! Idempotent orchestrator requests (GETs) are retried under a [`RetryPolicy`] when the server
+//! is rate limiting or briefly unavailable (429 and 5xx by default) or the connection fails,
+//! with exponential backoff and jitter between attempts. POSTs are not: the server may have
+//! acted on one that timed out, and the workers already retry task requests and submissions
+//! with their own backoff. A `Retry-After` header (or, without one,
+//! `X-RateLimit-Reset`) sets the delay instead; one longer than the policy allows is not waited
+//! out, and the response is returned so the caller can back off (or enter a maintenance
+//! window) itself.
+
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use reqwest::Response;
+use reqwest::header::RETRY_AFTER;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Header with the time a rate-limit window resets
+const X_RATELIMIT_RESET: &str = "x-ratelimit-reset";
+
+/// `X-RateLimit-Reset` values from here on are Unix times rather than seconds to wait
+const UNIX_TIME_THRESHOLD: u64 = 1_000_000_000;
+
+/// Default attempts per request, including the first
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+/// How orchestrator requests are retried.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts per request, including the first
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for each one after it
+    pub base_delay: Duration,
+    /// Longest delay between attempts, whether backed off or asked for by `Retry-After`
+    pub max_delay: Duration,
+    /// Response statuses worth retrying
+    pub retryable_statuses: Vec<u16>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            retryable_statuses: vec![429, 500, 502, 503, 504],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Whether a request that ended with `result` is worth sending again: the server answered
+    /// with a retryable status, or the connection failed or timed out.
+    pub fn is_retryable(&self, result: &Result<Response, reqwest::Error>) -> bool {
+        match result {
+            Ok(response) => self
+                .retryable_statuses
+                .contains(&response.status().as_u16()),
+            Err(e) => e.is_connect() || e.is_timeout(),
+        }
+    }
+
+    /// How long to wait before retry number `retry` (starting at 1), or `None` if the server
+    /// asked for a longer wait than the policy allows.
+    pub fn delay(&self, retry: u32, retry_after: Option<Duration>) -> Option<Duration> {
+        match retry_after {
+            Some(wait) if wait > self.max_delay => None,
+            Some(wait) => Some(wait),
+            None => Some(self.backoff(retry)),
+        }
+    }
+
+    /// Exponential backoff with jitter: a random delay between half and all of the doubled
+    /// base delay, so clients that failed together do not retry together.
+    fn backoff(&self, retry: u32) -> Duration {
+        let doublings = retry.saturating_sub(1).min(16);
+        let full = self
+            .base_delay
+            .saturating_mul(1 << doublings)
+            .min(self.max_delay);
+        let half = full / 2;
+        half + half.mul_f64(crate::rng::with_rng(|rng| rng.gen_range(0.0..=1.0)))
+    }
+}
+
+/// How long a response asks the client to wait before retrying, if it says.
+pub fn retry_after(response: &Response) -> Option<Duration> {
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+    };
+    rate_limit_wait(
+        header(RETRY_AFTER.as_str()),
+        header(X_RATELIMIT_RESET),
+        Utc::now(),
+    )
+}
+
+/// How long to wait, from the values of the `Retry-After` and `X-RateLimit-Reset` headers.
+/// `Retry-After` takes precedence.
+pub fn rate_limit_wait(
+    retry_after: Option<&str>,
+    reset: Option<&str>,
+    now: DateTime<Utc>,
+) -> Option<Duration> {
+    retry_after
+        .and_then(|value| parse_retry_after(value, now))
+        .or_else(|| reset.and_then(|value| parse_rate_limit_reset(value, now)))
+}
+
+/// Parses a `Retry-After` value: a number of seconds, or an HTTP date.
+fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&Utc) - now)
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+/// Parses an `X-RateLimit-Reset` value: the Unix time the window resets at, or (for small
+/// values) the seconds until it does.
+fn parse_rate_limit_reset(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim().parse::<u64>().ok()?;
+    if value < UNIX_TIME_THRESHOLD {
+        return Some(Duration::from_secs(value));
+    }
+    let reset = DateTime::from_timestamp(i64::try_from(value).ok()?, 0)?;
+    Some((reset - now).to_std().unwrap_or_default())
+}
+
+static RETRY_POLICY: OnceLock<RetryPolicy> = OnceLock::new();
+
+/// Sets how all orchestrator requests are retried. Only the first call has an effect.
+pub fn set_retry_policy(policy: RetryPolicy) {
+    let _ = RETRY_POLICY.set(policy);
+}
+
+/// How orchestrator requests are retried.
+pub fn retry_policy() -> &'static RetryPolicy {
+    RETRY_POLICY.get_or_init(RetryPolicy::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Backoff doubles per retry, with jitter of up to half, and is capped.
+    fn test_backoff() {
+        let policy = RetryPolicy {
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(5),
+            ..RetryPolicy::default()
+        };
+        for _ in 0..20 {
+            let first = policy.delay(1, None).unwrap();
+            assert!(first >= Duration::from_millis(500) && first <= Duration::from_secs(1));
+            let third = policy.delay(3, None).unwrap();
+            assert!(third >= Duration::from_secs(2) && third <= Duration::from_secs(4));
+            let tenth = policy.delay(10, None).unwrap();
+            assert!(tenth >= Duration::from_millis(2500) && tenth <= Duration::from_secs(5));
+        }
+    }
+
+    #[test]
+    // A Retry-After within the policy's limit replaces the backoff; a longer one is not waited out.
+    fn test_retry_after_delay() {
+        let policy = RetryPolicy::default();
+        assert_eq!(
+            policy.delay(1, Some(Duration::from_secs(7))),
+            Some(Duration::from_secs(7))
+        );
+        assert_eq!(policy.delay(1, Some(Duration::from_secs(600))), None);
+    }
+
+    #[test]
+    // Retry-After may be given in seconds or as an HTTP date.
+    fn test_parse_retry_after() {
+        let now = DateTime::parse_from_rfc3339("2015-10-21T07:28:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(
+            parse_retry_after("120", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        // A date in the past means retry now
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:00:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[test]
+    // X-RateLimit-Reset may be a Unix time or seconds to wait; Retry-After wins over it.
+    fn test_rate_limit_wait() {
+        let now = DateTime::parse_from_rfc3339("2015-10-21T07:28:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let reset = (now.timestamp() + 45).to_string();
+        assert_eq!(
+            rate_limit_wait(None, Some(&reset), now),
+            Some(Duration::from_secs(45))
+        );
+        assert_eq!(
+            rate_limit_wait(None, Some("20"), now),
+            Some(Duration::from_secs(20))
+        );
+        assert_eq!(
+            rate_limit_wait(Some("5"), Some("20"), now),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(rate_limit_wait(None, None, now), None);
+    }
+}
@@ -0,0 +1,250 @@
+//! Response schema drift detection
+//!
+//! A response that fails to decode usually means the orchestrator's protocol changed under
+//! this client. Instead of a bare decoding error, the payload is saved to
+//! `~/.nexus/schema_drift/` and its top-level fields are examined one at a time: each field is
+//! a valid message on its own, so it can be decoded in isolation. A field that fails alone
+//! changed type; one that decodes to an empty message is unknown to this client. The findings
+//! are reported as [`OrchestratorError::SchemaDrift`](super::error::OrchestratorError).
+
+use prost::Message;
+use std::fmt::Display;
+use std::ops::Range;
+use std::path::PathBuf;
+
+/// Saved payloads kept on disk; older ones are removed.
+const MAX_SAVED_PAYLOADS: usize = 20;
+
+/// What is wrong with one field of a payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldIssue {
+    /// The field exists in this client's schema but does not decode as it expects.
+    Incompatible(String),
+    /// The field is not in this client's schema (or only carries a default value).
+    Unknown,
+}
+
+/// An unexpected top-level field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldReport {
+    pub number: u32,
+    pub wire_type: &'static str,
+    pub issue: FieldIssue,
+}
+
+/// A response that no longer matches the message this client expects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaDrift {
+    /// Name of the expected message type.
+    pub message_type: String,
+    /// The original decoding error.
+    pub error: String,
+    /// Fields that did not decode as expected.
+    pub fields: Vec<FieldReport>,
+    /// Byte offset at which the payload stopped being valid protobuf, if it did.
+    pub malformed_at: Option<usize>,
+    pub payload_len: usize,
+    /// Where the payload was saved, if it could be.
+    pub saved_to: Option<PathBuf>,
+}
+
+impl SchemaDrift {
+    /// Examines a payload that failed to decode as `T`.
+    pub fn analyze<T: Message + Default>(bytes: &[u8], error: &prost::DecodeError) -> Self {
+        let (raw_fields, malformed_at) = split_fields(bytes);
+        let fields = raw_fields
+            .into_iter()
+            .filter_map(|field| {
+                let issue = match T::decode(&bytes[field.range]) {
+                    Err(e) => FieldIssue::Incompatible(e.to_string()),
+                    Ok(message) if message.encoded_len() == 0 => FieldIssue::Unknown,
+                    Ok(_) => return None,
+                };
+                Some(FieldReport {
+                    number: field.number,
+                    wire_type: wire_type_name(field.wire_type),
+                    issue,
+                })
+            })
+            .collect();
+        Self {
+            message_type: message_type_name::<T>(),
+            error: error.to_string(),
+            fields,
+            malformed_at,
+            payload_len: bytes.len(),
+            saved_to: None,
+        }
+    }
+
+    /// Saves the payload for later inspection, pruning old ones.
+    pub fn save_payload(mut self, bytes: &[u8]) -> Self {
+        self.saved_to = save_payload(&self.message_type, bytes);
+        self
+    }
+}
+
+impl Display for SchemaDrift {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({} bytes) no longer matches this client: {}",
+            self.message_type, self.payload_len, self.error
+        )?;
+        for field in &self.fields {
+            match &field.issue {
+                FieldIssue::Incompatible(e) => write!(
+                    f,
+                    "; field {} ({}) does not decode: {}",
+                    field.number, field.wire_type, e
+                )?,
+                FieldIssue::Unknown => write!(
+                    f,
+                    "; field {} ({}) is unknown",
+                    field.number, field.wire_type
+                )?,
+            }
+        }
+        if let Some(offset) = self.malformed_at {
+            write!(f, "; not valid protobuf from byte {}", offset)?;
+        }
+        if let Some(path) = &self.saved_to {
+            write!(f, "; payload saved to {}", path.display())?;
+        }
+        Ok(())
+    }
+}
+
+/// One top-level field of an encoded message, tag included.
+struct RawField {
+    number: u32,
+    wire_type: u8,
+    range: Range<usize>,
+}
+
+fn read_varint(bytes: &[u8], pos: &mut usize) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *bytes.get(*pos)?;
+        *pos += 1;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// Splits a payload into its top-level fields. Also returns the offset of the first field
+/// that could not be delimited, if any; nothing after it can be split reliably.
+fn split_fields(bytes: &[u8]) -> (Vec<RawField>, Option<usize>) {
+    let mut fields = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let start = pos;
+        let Some(key) = read_varint(bytes, &mut pos) else {
+            return (fields, Some(start));
+        };
+        let wire_type = (key & 0x7) as u8;
+        let end = match wire_type {
+            0 => read_varint(bytes, &mut pos).map(|_| pos),
+            1 => Some(pos + 8),
+            2 => read_varint(bytes, &mut pos).and_then(|len| pos.checked_add(len as usize)),
+            5 => Some(pos + 4),
+            // Groups (3, 4) are deprecated and unused by the orchestrator
+            _ => None,
+        };
+        let (Ok(number), Some(end)) = (u32::try_from(key >> 3), end) else {
+            return (fields, Some(start));
+        };
+        if number == 0 || end > bytes.len() {
+            return (fields, Some(start));
+        }
+        fields.push(RawField {
+            number,
+            wire_type,
+            range: start..end,
+        });
+        pos = end;
+    }
+    (fields, None)
+}
+
+fn wire_type_name(wire_type: u8) -> &'static str {
+    match wire_type {
+        0 => "varint",
+        1 => "64-bit",
+        2 => "length-delimited",
+        5 => "32-bit",
+        _ => "group",
+    }
+}
+
+fn message_type_name<T>() -> String {
+    let full = std::any::type_name::<T>();
+    full.rsplit("::").next().unwrap_or(full).to_string()
+}
+
+fn save_payload(message_type: &str, bytes: &[u8]) -> Option<PathBuf> {
+    let dir = crate::config::get_config_path()
+        .ok()?
+        .with_file_name("schema_drift");
+    std::fs::create_dir_all(&dir).ok()?;
+    let path = dir.join(format!(
+        "{}-{}.pb",
+        chrono::Utc::now().format("%Y%m%dT%H%M%S%.3fZ"),
+        message_type
+    ));
+    std::fs::write(&path, bytes).ok()?;
+
+    // File names start with the time, so they sort oldest first
+    if let Ok(entries) = std::fs::read_dir(&dir) {
+        let mut saved: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .collect();
+        saved.sort();
+        let excess = saved.len().saturating_sub(MAX_SAVED_PAYLOADS);
+        for old in &saved[..excess] {
+            let _ = std::fs::remove_file(old);
+        }
+    }
+    Some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nexus_orchestrator::GetProofTaskResponse;
+
+    #[test]
+    // A field whose type changed and a new field should both be reported.
+    fn test_analyze_reports_unexpected_fields() {
+        let mut payload = vec![0x0a, 8];
+        payload.extend_from_slice(b"fast-fib"); // program_id, as expected
+        payload.extend_from_slice(&[0x18, 7]); // task_id sent as a varint instead of a string
+        payload.extend_from_slice(&[0x48, 1]); // field 9, unknown to this client
+
+        let error = GetProofTaskResponse::decode(payload.as_slice()).unwrap_err();
+        let drift = SchemaDrift::analyze::<GetProofTaskResponse>(&payload, &error);
+        assert_eq!(drift.message_type, "GetProofTaskResponse");
+        assert_eq!(drift.payload_len, payload.len());
+        assert_eq!(drift.malformed_at, None);
+        assert_eq!(drift.fields.len(), 2);
+        assert_eq!(drift.fields[0].number, 3);
+        assert_eq!(drift.fields[0].wire_type, "varint");
+        assert!(matches!(drift.fields[0].issue, FieldIssue::Incompatible(_)));
+        assert_eq!(drift.fields[1].number, 9);
+        assert_eq!(drift.fields[1].issue, FieldIssue::Unknown);
+        assert!(drift.to_string().contains("field 9 (varint) is unknown"));
+    }
+
+    #[test]
+    // Splitting should stop at a field that runs past the end of the payload.
+    fn test_split_fields_stops_at_truncation() {
+        let payload = [0x08, 1, 0x12, 10, b'a'];
+        let (fields, malformed_at) = split_fields(&payload);
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields[0].range, 0..2);
+        assert_eq!(malformed_at, Some(2));
+    }
+}
@@ -0,0 +1,91 @@
+//! Proof submissions
+//!
+//! A `ProofSubmission` carries what the caller knows about a proof; the client adds the
+//! signature and node telemetry when it is sent.
+
+use crate::nexus_orchestrator::TaskType;
+
+/// A proof to submit for a task.
+///
+/// ```ignore
+/// let submission = ProofSubmission::new(&task.task_id, &proof_hash, proof_bytes)
+///     .num_provers(num_workers)
+///     .task_type(task.task_type)
+///     .proxy(task.proxy.clone());
+/// orchestrator.submit_proof(submission, signing_key).await?;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProofSubmission {
+    pub task_id: String,
+    pub proof_hash: String,
+    pub proof: Vec<u8>,
+    /// Number of provers on this node, used to estimate its throughput
+    pub num_provers: usize,
+    /// The task's type, which decides whether the proof itself is attached
+    pub task_type: Option<TaskType>,
+    /// The proxy the task was fetched through, to submit through it as well
+    pub proxy: Option<String>,
+}
+
+impl ProofSubmission {
+    pub fn new(task_id: &str, proof_hash: &str, proof: Vec<u8>) -> Self {
+        Self {
+            task_id: task_id.to_string(),
+            proof_hash: proof_hash.to_string(),
+            proof,
+            num_provers: 1,
+            task_type: None,
+            proxy: None,
+        }
+    }
+
+    pub fn num_provers(mut self, num_provers: usize) -> Self {
+        self.num_provers = num_provers;
+        self
+    }
+
+    pub fn task_type(mut self, task_type: Option<TaskType>) -> Self {
+        self.task_type = task_type;
+        self
+    }
+
+    pub fn proxy(mut self, proxy: Option<String>) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    /// The proof bytes to send. `ProofHash` tasks only need the hash; tasks without a type
+    /// get the proof attached, for backward compatibility.
+    pub fn into_attached_proof(self) -> Vec<u8> {
+        match self.task_type {
+            Some(TaskType::ProofHash) => Vec::new(),
+            _ => self.proof,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Only ProofHash tasks should leave the proof out.
+    fn test_attached_proof_by_task_type() {
+        let submission = ProofSubmission::new("task", "hash", vec![1, 2, 3]).num_provers(4);
+        assert_eq!(submission.num_provers, 4);
+        assert_eq!(submission.clone().into_attached_proof(), vec![1, 2, 3]);
+        assert_eq!(
+            submission
+                .clone()
+                .task_type(Some(TaskType::ProofRequired))
+                .into_attached_proof(),
+            vec![1, 2, 3]
+        );
+        assert!(
+            submission
+                .task_type(Some(TaskType::ProofHash))
+                .into_attached_proof()
+                .is_empty()
+        );
+    }
+}
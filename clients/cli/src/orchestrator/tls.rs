@@ -0,0 +1,165 @@
+//! TLS trust for orchestrator traffic.
+//!
+//! Corporate networks often intercept TLS, re-signing every certificate with their own CA.
+//! The orchestrator's certificate then has an issuer the client does not trust, which reqwest
+//! reports as an opaque certificate error. Such errors are recognised here and turned into
+//! guidance, and `--ca-cert` lets the network's CA be trusted in addition to the built-in roots.
+//!
+//! Networks that require mutual TLS get `--client-cert` and `--client-key`, presented to the
+//! orchestrator and to HTTPS proxies. As a last resort, `--insecure-skip-verify` turns off
+//! certificate verification altogether, with a warning on every start.
+
+use reqwest::{Certificate, ClientBuilder, Identity};
+use std::error::Error;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// What to do about an intercepted connection, shown once per process.
+pub const INTERCEPTION_HELP: &str = "\
+The orchestrator's TLS certificate was issued by an authority this client does not trust.
+This usually means a corporate proxy or antivirus is intercepting TLS connections.
+
+To connect anyway:
+  1. Ask your IT department for the proxy's root CA certificate (PEM format), or export
+     it from your system or browser certificate store.
+  2. Pass it to the client: nexus-network --ca-cert /path/to/corporate-ca.pem start
+  3. Alternatively, run the node on a network without TLS inspection.";
+
+static CA_CERTS: OnceLock<Vec<Certificate>> = OnceLock::new();
+
+static CLIENT_IDENTITY: OnceLock<Identity> = OnceLock::new();
+
+static SKIP_VERIFY: OnceLock<()> = OnceLock::new();
+
+/// Trusts the certificates in the PEM file at `path` for orchestrator requests, in addition to
+/// the built-in roots. Only the first call has an effect.
+pub fn set_ca_cert(path: &Path) -> Result<(), String> {
+    let pem =
+        std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let certs = Certificate::from_pem_bundle(&pem)
+        .map_err(|e| format!("Invalid certificate in {}: {}", path.display(), e))?;
+    if certs.is_empty() {
+        return Err(format!("No PEM certificates found in {}", path.display()));
+    }
+    let _ = CA_CERTS.set(certs);
+    Ok(())
+}
+
+/// Whether `--ca-cert` was given.
+pub fn has_ca_cert() -> bool {
+    CA_CERTS.get().is_some()
+}
+
+/// Presents the certificate chain in the PEM file at `cert` and the private key in `key` as the
+/// client's identity. Only the first call has an effect.
+pub fn set_client_identity(cert: &Path, key: &Path) -> Result<(), String> {
+    let read = |path: &Path| {
+        std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+    };
+    let mut pem = read(cert)?;
+    pem.push(b'\n');
+    pem.extend(read(key)?);
+    let identity = Identity::from_pem(&pem).map_err(|e| {
+        format!(
+            "Invalid client certificate {} or key {}: {}",
+            cert.display(),
+            key.display(),
+            e
+        )
+    })?;
+    let _ = CLIENT_IDENTITY.set(identity);
+    Ok(())
+}
+
+/// Stops verifying server certificates. Only the first call has an effect.
+pub fn set_insecure_skip_verify() {
+    let _ = SKIP_VERIFY.set(());
+}
+
+/// Whether `--insecure-skip-verify` was given.
+pub fn skips_verify() -> bool {
+    SKIP_VERIFY.get().is_some()
+}
+
+/// Configures a client builder with the TLS options: the `--ca-cert` certificates, the client
+/// identity and `--insecure-skip-verify`, if given.
+pub fn apply(builder: ClientBuilder) -> ClientBuilder {
+    let mut builder = CA_CERTS
+        .get()
+        .into_iter()
+        .flatten()
+        .fold(builder, |builder, cert| {
+            builder.add_root_certificate(cert.clone())
+        });
+    if let Some(identity) = CLIENT_IDENTITY.get() {
+        builder = builder.identity(identity.clone());
+    }
+    builder.danger_accept_invalid_certs(skips_verify())
+}
+
+/// Whether a TLS error message means the peer's certificate chain ends at an unknown CA.
+fn is_unknown_issuer(message: &str) -> bool {
+    let message = message.to_lowercase();
+    message.contains("unknownissuer") || message.contains("unknown issuer")
+}
+
+/// Whether a request failed because the connection was intercepted, judging by the whole
+/// error chain (reqwest wraps the TLS error several levels deep).
+pub fn is_interception(error: &reqwest::Error) -> bool {
+    let mut source: Option<&dyn Error> = Some(error);
+    while let Some(error) = source {
+        if is_unknown_issuer(&error.to_string()) {
+            return true;
+        }
+        source = error.source();
+    }
+    false
+}
+
+/// Logs `INTERCEPTION_HELP` as a warning the first time interception is detected. While the
+/// dashboard is showing, the failed request's error tells the user instead.
+pub fn warn_interception_once() {
+    static WARNED: OnceLock<()> = OnceLock::new();
+    WARNED.get_or_init(|| {
+        crate::logging::warn(format!("⚠️  {}", INTERCEPTION_HELP));
+        if has_ca_cert() {
+            crate::logging::warn(
+                "ℹ️ The certificate passed with --ca-cert did not sign it either.",
+            );
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // rustls reports an untrusted root as UnknownIssuer; other certificate problems are not interception.
+    fn test_unknown_issuer_detection() {
+        assert!(is_unknown_issuer("invalid peer certificate: UnknownIssuer"));
+        assert!(is_unknown_issuer(
+            "certificate verify failed: unknown issuer"
+        ));
+        assert!(!is_unknown_issuer("invalid peer certificate: Expired"));
+        assert!(!is_unknown_issuer("connection refused"));
+    }
+
+    #[test]
+    // A file without certificates is rejected rather than silently trusting nothing.
+    fn test_set_ca_cert_rejects_empty_file() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), "not a certificate\n").unwrap();
+        assert!(set_ca_cert(file.path()).is_err());
+        assert!(set_ca_cert(Path::new("/nonexistent/ca.pem")).is_err());
+    }
+
+    #[test]
+    // A client certificate without a usable key is rejected when it is set, not on first use.
+    fn test_set_client_identity_rejects_invalid_files() {
+        let cert = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(cert.path(), "not a certificate\n").unwrap();
+        assert!(set_client_identity(cert.path(), cert.path()).is_err());
+        assert!(set_client_identity(cert.path(), Path::new("/nonexistent/client.key")).is_err());
+    }
+}
@@ -0,0 +1,84 @@
+//! HTTP transport selection for orchestrator traffic.
+//!
+//! Some proxies mangle HTTP/2, so it can be disabled; QUIC (HTTP/3) is available as an
+//! experiment in builds with the `http3` feature (which also requires
+//! `RUSTFLAGS="--cfg reqwest_unstable"`).
+
+use reqwest::ClientBuilder;
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+/// HTTP protocol version used for orchestrator requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum HttpVersion {
+    /// Force HTTP/1.1.
+    #[value(name = "http1")]
+    Http1,
+    /// Negotiate HTTP/2 via ALPN, falling back to HTTP/1.1.
+    #[default]
+    #[value(name = "http2")]
+    Http2,
+    /// Use HTTP/3 over QUIC (experimental).
+    #[value(name = "http3")]
+    Http3,
+}
+
+impl HttpVersion {
+    /// Whether this binary can use the transport.
+    pub fn is_supported(&self) -> bool {
+        match self {
+            HttpVersion::Http1 | HttpVersion::Http2 => true,
+            HttpVersion::Http3 => cfg!(feature = "http3"),
+        }
+    }
+
+    /// Configures a client builder for this transport.
+    ///
+    /// HTTP proxies cannot carry QUIC, so HTTP/3 falls back to HTTP/2 when a proxy is used.
+    pub fn apply(&self, builder: ClientBuilder, via_proxy: bool) -> ClientBuilder {
+        match self {
+            HttpVersion::Http1 => builder.http1_only(),
+            HttpVersion::Http2 => builder,
+            HttpVersion::Http3 if via_proxy => builder,
+            #[cfg(feature = "http3")]
+            HttpVersion::Http3 => builder.http3_prior_knowledge(),
+            #[cfg(not(feature = "http3"))]
+            HttpVersion::Http3 => builder,
+        }
+    }
+}
+
+impl Display for HttpVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HttpVersion::Http1 => write!(f, "HTTP/1.1"),
+            HttpVersion::Http2 => write!(f, "HTTP/2"),
+            HttpVersion::Http3 => write!(f, "HTTP/3"),
+        }
+    }
+}
+
+static HTTP_VERSION: OnceLock<HttpVersion> = OnceLock::new();
+
+/// Sets the transport for all orchestrator clients. Only the first call has an effect.
+pub fn set_http_version(version: HttpVersion) {
+    let _ = HTTP_VERSION.set(version);
+}
+
+/// The transport for orchestrator clients.
+pub fn http_version() -> HttpVersion {
+    HTTP_VERSION.get().copied().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // HTTP/1.1 and HTTP/2 are always available; HTTP/3 depends on the build.
+    fn test_supported_transports() {
+        assert!(HttpVersion::Http1.is_supported());
+        assert!(HttpVersion::Http2.is_supported());
+        assert_eq!(HttpVersion::Http3.is_supported(), cfg!(feature = "http3"));
+    }
+}
@@ -0,0 +1,70 @@
+//! Client-side pacing
+//!
+//! Every node ships the same defaults, so periodic work scheduled relative to a shared event
+//! (startup of a fleet, the end of a maintenance window, an orchestrator outage pausing many
+//! error budgets at once) would make thousands of nodes hit the same servers in the same
+//! second. Such work is shifted by an offset derived from the node ID: stable for a node
+//! across restarts, and evenly spread across nodes. Anonymous nodes use a random seed (see `rng`).
+
+use rand::RngCore;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+static SEED: OnceLock<u64> = OnceLock::new();
+
+/// Derives offsets from this node's ID. Only the first call (before any offset is taken) has
+/// an effect.
+pub fn set_node_id(node_id: u64) {
+    let _ = SEED.set(node_id);
+}
+
+fn seed() -> u64 {
+    *SEED.get_or_init(|| crate::rng::with_rng(|rng| rng.next_u64()))
+}
+
+/// A stable fraction in `[0.0, 1.0)` for `activity` under `seed`.
+fn fraction_for(seed: u64, activity: &str) -> f64 {
+    // FNV-1a over the activity name, then a splitmix64 finalizer so that consecutive node IDs
+    // land far apart
+    let mut hash = 0xcbf2_9ce4_8422_2325_u64 ^ seed;
+    for byte in activity.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^= hash >> 31;
+    (hash >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// This node's offset for `activity`, between zero and `spread`.
+pub fn offset(activity: &str, spread: Duration) -> Duration {
+    spread.mul_f64(fraction_for(seed(), activity))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Offsets should be stable per node and activity, and spread out across nodes.
+    fn test_offsets_are_stable_and_spread() {
+        assert_eq!(
+            fraction_for(42, "version_check"),
+            fraction_for(42, "version_check")
+        );
+        assert_ne!(
+            fraction_for(42, "version_check"),
+            fraction_for(42, "maintenance_end")
+        );
+
+        let mut buckets = [0usize; 10];
+        for node_id in 0..10_000 {
+            let fraction = fraction_for(node_id, "version_check");
+            assert!((0.0..1.0).contains(&fraction));
+            buckets[(fraction * 10.0) as usize] += 1;
+        }
+        // Consecutive node IDs should fill every tenth of the range roughly evenly
+        assert!(buckets.iter().all(|&count| (800..1200).contains(&count)));
+    }
+}
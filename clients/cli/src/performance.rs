@@ -0,0 +1,217 @@
+//! Node performance tracking
+//!
+//! Maintains a rolling baseline of proof durations (per program) and a window of
+//! recent task outcomes, and flags anomalies such as sudden slowdowns (e.g. thermal
+//! throttling) or a drop in the success rate (e.g. a bad proxy pool).
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Display;
+use std::time::Duration;
+
+/// Number of proofs required before a program's baseline is considered reliable.
+const MIN_BASELINE_SAMPLES: u32 = 5;
+
+/// Weight of the newest sample in the exponentially weighted moving average.
+const BASELINE_SMOOTHING: f64 = 0.1;
+
+/// A proof taking this many times longer than the baseline is flagged as a slowdown.
+const SLOWDOWN_FACTOR: f64 = 2.0;
+
+/// Number of recent task outcomes used to compute the success rate.
+const OUTCOME_WINDOW: usize = 20;
+
+/// A success rate below this threshold (over a full window) is flagged as a drop.
+const MIN_SUCCESS_RATE: f64 = 0.5;
+
+/// A detected performance anomaly.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Anomaly {
+    /// A proof took much longer than the program's rolling baseline.
+    Slowdown {
+        program_id: String,
+        duration: Duration,
+        baseline: Duration,
+    },
+    /// The success rate over the recent window dropped below the threshold.
+    SuccessRateDrop { success_rate: f64 },
+}
+
+impl Display for Anomaly {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Anomaly::Slowdown {
+                program_id,
+                duration,
+                baseline,
+            } => write!(
+                f,
+                "Performance anomaly: {} proof took {:.1}s ({:.1}x the {:.1}s baseline) - check for thermal throttling or resource contention",
+                program_id,
+                duration.as_secs_f64(),
+                duration.as_secs_f64() / baseline.as_secs_f64(),
+                baseline.as_secs_f64()
+            ),
+            Anomaly::SuccessRateDrop { success_rate } => write!(
+                f,
+                "Performance anomaly: success rate dropped to {:.0}% over the last {} tasks",
+                success_rate * 100.0,
+                OUTCOME_WINDOW
+            ),
+        }
+    }
+}
+
+/// Rolling baseline of proof durations for a single program.
+#[derive(Debug, Clone)]
+struct ProofBaseline {
+    average_secs: f64,
+    samples: u32,
+}
+
+/// Tracks proof performance and task outcomes for the node.
+#[derive(Debug, Clone, Default)]
+pub struct PerformanceTracker {
+    baselines: HashMap<String, ProofBaseline>,
+    outcomes: VecDeque<bool>,
+    /// Whether a success rate drop has already been reported (cleared on recovery).
+    success_rate_alerted: bool,
+    /// Duration of the most recent proof, relative to its baseline.
+    last_speed_ratio: Option<f64>,
+}
+
+impl PerformanceTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the duration of a successful proof. Returns an anomaly if it was unusually slow.
+    pub fn record_proof(&mut self, program_id: &str, duration: Duration) -> Option<Anomaly> {
+        let secs = duration.as_secs_f64();
+        let baseline = self
+            .baselines
+            .entry(program_id.to_string())
+            .or_insert(ProofBaseline {
+                average_secs: secs,
+                samples: 0,
+            });
+
+        let mut anomaly = None;
+        if baseline.samples >= MIN_BASELINE_SAMPLES && baseline.average_secs > 0.0 {
+            let ratio = secs / baseline.average_secs;
+            self.last_speed_ratio = Some(ratio);
+            if ratio >= SLOWDOWN_FACTOR {
+                anomaly = Some(Anomaly::Slowdown {
+                    program_id: program_id.to_string(),
+                    duration,
+                    baseline: Duration::from_secs_f64(baseline.average_secs),
+                });
+            }
+        }
+
+        baseline.average_secs =
+            BASELINE_SMOOTHING * secs + (1.0 - BASELINE_SMOOTHING) * baseline.average_secs;
+        baseline.samples += 1;
+        anomaly
+    }
+
+    /// Records whether a task succeeded. Returns an anomaly if the success rate just dropped.
+    pub fn record_outcome(&mut self, success: bool) -> Option<Anomaly> {
+        if self.outcomes.len() == OUTCOME_WINDOW {
+            self.outcomes.pop_front();
+        }
+        self.outcomes.push_back(success);
+
+        let success_rate = self.success_rate()?;
+        if self.outcomes.len() < OUTCOME_WINDOW {
+            return None;
+        }
+        if success_rate < MIN_SUCCESS_RATE {
+            if !self.success_rate_alerted {
+                self.success_rate_alerted = true;
+                return Some(Anomaly::SuccessRateDrop { success_rate });
+            }
+        } else {
+            self.success_rate_alerted = false;
+        }
+        None
+    }
+
+    /// Success rate over the recent window, if any outcomes were recorded.
+    pub fn success_rate(&self) -> Option<f64> {
+        if self.outcomes.is_empty() {
+            return None;
+        }
+        let successes = self.outcomes.iter().filter(|s| **s).count();
+        Some(successes as f64 / self.outcomes.len() as f64)
+    }
+
+    /// Performance score from 0 to 100, combining success rate and speed relative to baseline.
+    pub fn score(&self) -> u32 {
+        let success_rate = self.success_rate().unwrap_or(1.0);
+        let speed = self
+            .last_speed_ratio
+            .map(|ratio| (1.0 / ratio).min(1.0))
+            .unwrap_or(1.0);
+        (success_rate * speed * 100.0).round() as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // A proof twice as slow as the established baseline should be flagged.
+    fn test_slowdown_detected_after_baseline() {
+        let mut tracker = PerformanceTracker::new();
+        for _ in 0..MIN_BASELINE_SAMPLES {
+            assert!(
+                tracker
+                    .record_proof("fast-fib", Duration::from_secs(10))
+                    .is_none()
+            );
+        }
+        let anomaly = tracker.record_proof("fast-fib", Duration::from_secs(25));
+        assert!(matches!(anomaly, Some(Anomaly::Slowdown { .. })));
+        assert!(tracker.score() < 50);
+    }
+
+    #[test]
+    // Baselines are tracked per program, so a slower program is not an anomaly.
+    fn test_baselines_are_per_program() {
+        let mut tracker = PerformanceTracker::new();
+        for _ in 0..MIN_BASELINE_SAMPLES {
+            tracker.record_proof("fast-fib", Duration::from_secs(1));
+        }
+        assert!(
+            tracker
+                .record_proof("fib_input_initial", Duration::from_secs(30))
+                .is_none()
+        );
+    }
+
+    #[test]
+    // A success rate drop should be reported once, and again only after recovering.
+    fn test_success_rate_drop_reported_once() {
+        let mut tracker = PerformanceTracker::new();
+        let mut reported = 0;
+        for _ in 0..OUTCOME_WINDOW * 2 {
+            if tracker.record_outcome(false).is_some() {
+                reported += 1;
+            }
+        }
+        assert_eq!(reported, 1);
+        assert_eq!(tracker.score(), 0);
+
+        for _ in 0..OUTCOME_WINDOW {
+            tracker.record_outcome(true);
+        }
+        assert_eq!(tracker.success_rate(), Some(1.0));
+        for _ in 0..OUTCOME_WINDOW {
+            if tracker.record_outcome(false).is_some() {
+                reported += 1;
+            }
+        }
+        assert_eq!(reported, 2);
+    }
+}
@@ -0,0 +1,128 @@
+//! Task polling schedule
+//!
+//! Controls how often the task fetcher asks the orchestrator for work. Each poll is delayed
+//! by a random jitter so that many nodes started together do not hit the orchestrator in
+//! lockstep, and the delay is lengthened while no tasks are available (idle backoff).
+
+use crate::consts::prover::BACKOFF_DURATION;
+use crate::environment::Environment;
+use rand::Rng;
+use std::time::Duration;
+
+/// Polling settings for the task fetcher.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PollingConfig {
+    /// Delay between task requests while tasks are being received.
+    pub interval: Duration,
+
+    /// Maximum random extra delay added to each wait, as a fraction of that wait (0.0 - 1.0).
+    pub jitter: f64,
+
+    /// Upper bound for the delay when it is lengthened because no tasks are available.
+    pub max_idle_interval: Duration,
+}
+
+impl PollingConfig {
+    /// Default polling settings for an environment.
+    ///
+    /// Staging and custom orchestrators are test deployments, so they are polled more eagerly
+    /// than production.
+    pub fn for_environment(environment: &Environment) -> Self {
+        match environment {
+            Environment::Production => Self {
+                interval: Duration::from_millis(BACKOFF_DURATION),
+                jitter: 0.2,
+                max_idle_interval: Duration::from_secs(600),
+            },
+            Environment::Staging | Environment::Custom { .. } => Self {
+                interval: Duration::from_secs(10),
+                jitter: 0.2,
+                max_idle_interval: Duration::from_secs(120),
+            },
+        }
+    }
+
+    /// Applies user-provided overrides (e.g. from CLI flags) on top of these settings.
+    pub fn with_overrides(
+        mut self,
+        interval_secs: Option<u64>,
+        jitter: Option<f64>,
+        max_idle_interval_secs: Option<u64>,
+    ) -> Self {
+        if let Some(secs) = interval_secs {
+            self.interval = Duration::from_secs(secs);
+        }
+        if let Some(jitter) = jitter {
+            self.jitter = jitter.clamp(0.0, 1.0);
+        }
+        if let Some(secs) = max_idle_interval_secs {
+            self.max_idle_interval = Duration::from_secs(secs);
+        }
+        // The idle backoff never makes polling faster than the base interval.
+        self.max_idle_interval = self.max_idle_interval.max(self.interval);
+        self
+    }
+
+    /// The next delay while idle: doubled from the current one, capped at `max_idle_interval`.
+    pub fn idle_interval(&self, current: Duration) -> Duration {
+        std::cmp::min(
+            std::cmp::max(current, self.interval) * 2,
+            self.max_idle_interval,
+        )
+    }
+
+    /// Samples a jitter factor to multiply a wait by, in `[1.0, 1.0 + jitter]`.
+    ///
+    /// Jitter only ever lengthens a wait, so server-provided retry times are still honored.
+    pub fn sample_jitter_factor(&self) -> f64 {
+        if self.jitter <= 0.0 {
+            return 1.0;
+        }
+        1.0 + crate::rng::with_rng(|rng| rng.gen_range(0.0..=self.jitter))
+    }
+}
+
+impl Default for PollingConfig {
+    fn default() -> Self {
+        Self::for_environment(&Environment::Production)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // The idle interval should double from the base interval and stop at the cap.
+    fn test_idle_interval_is_capped() {
+        let polling = PollingConfig::default();
+        let mut delay = polling.interval;
+        delay = polling.idle_interval(delay);
+        assert_eq!(delay, polling.interval * 2);
+        for _ in 0..10 {
+            delay = polling.idle_interval(delay);
+        }
+        assert_eq!(delay, polling.max_idle_interval);
+    }
+
+    #[test]
+    // Jitter factors should stay within the configured bounds.
+    fn test_jitter_factor_bounds() {
+        let polling = PollingConfig::default().with_overrides(None, Some(0.5), None);
+        for _ in 0..100 {
+            let factor = polling.sample_jitter_factor();
+            assert!((1.0..=1.5).contains(&factor));
+        }
+        let no_jitter = PollingConfig::default().with_overrides(None, Some(0.0), None);
+        assert_eq!(no_jitter.sample_jitter_factor(), 1.0);
+    }
+
+    #[test]
+    // Overrides should apply, and the idle cap should never be below the base interval.
+    fn test_overrides() {
+        let polling = PollingConfig::default().with_overrides(Some(30), Some(3.0), Some(10));
+        assert_eq!(polling.interval, Duration::from_secs(30));
+        assert_eq!(polling.jitter, 1.0);
+        assert_eq!(polling.max_idle_interval, Duration::from_secs(30));
+    }
+}
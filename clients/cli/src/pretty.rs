@@ -23,8 +23,12 @@ macro_rules! handle_cmd_error {
 
 macro_rules! print_cmd_info {
     ($tt:tt, $($tts:tt)*) => {
-        println!("\x1b[1;33m[INFO!!!] {}\x1b[0m", $tt);
-        println!("{}", core::format_args!($($tts)*));
+        if $crate::logging::is_json() {
+            $crate::logging::info(format!("{}: {}", $tt, core::format_args!($($tts)*)));
+        } else {
+            println!("\x1b[1;33m[INFO!!!] {}\x1b[0m", $tt);
+            println!("{}", core::format_args!($($tts)*));
+        }
     }
 }
 
@@ -0,0 +1,340 @@
+//! Resource profiles
+//!
+//! Machines shared with other work can prove with fewer workers during working hours. The
+//! config file lists named profiles, each giving a worker count for a daily window, in the
+//! IANA time zone set as `timezone` (the system's local time zone if there is none):
+//!
+//! ```json
+//! "timezone": "Europe/Berlin",
+//! "profiles": [
+//!   { "name": "day", "workers": 2, "from": "09:00", "until": "18:00" },
+//!   { "name": "night", "workers": 8, "from": "18:00", "until": "09:00" }
+//! ]
+//! ```
+//!
+//! The node starts enough workers for its largest profile and hands tasks to as many of them
+//! as the profile active at the moment allows. Outside every window, the `--max-threads`
+//! count applies. Worker counts are capped like `--max-threads`.
+//!
+//! Windows follow the wall clock of the time zone across daylight saving changes: a window
+//! starting in the hour skipped in spring starts when the clocks resume, and the hour repeated
+//! in autumn is in whichever window it falls in both times. `nexus schedule preview` lists the
+//! windows of the coming days.
+
+use crate::control::control_state;
+use crate::error_classifier::LogLevel;
+use crate::events::{Event, EventType};
+use chrono::{DateTime, DurationRound, Local, NaiveDateTime, NaiveTime, TimeDelta, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+
+/// How often the active profile is re-evaluated.
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A worker count for a daily time window.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ResourceProfile {
+    pub name: String,
+    pub workers: usize,
+    /// Start of the window, as "HH:MM" local time.
+    pub from: String,
+    /// End of the window (exclusive), as "HH:MM"; windows ending before they start span midnight.
+    pub until: String,
+}
+
+impl ResourceProfile {
+    fn window(&self) -> Result<(NaiveTime, NaiveTime), String> {
+        let parse = |value: &str| {
+            NaiveTime::parse_from_str(value, "%H:%M").map_err(|_| {
+                format!(
+                    "profile '{}': invalid time '{}' (expected HH:MM)",
+                    self.name, value
+                )
+            })
+        };
+        Ok((parse(&self.from)?, parse(&self.until)?))
+    }
+
+    /// Whether `time` falls within this profile's window.
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        match self.window() {
+            Ok((from, until)) if from <= until => from <= time && time < until,
+            Ok((from, until)) => time >= from || time < until,
+            Err(_) => false,
+        }
+    }
+}
+
+/// Resource profiles from the config file, with the worker count to use outside them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProfileSchedule {
+    profiles: Vec<ResourceProfile>,
+    default_workers: usize,
+    /// Time zone of the windows; the system's if unset
+    timezone: Option<Tz>,
+}
+
+/// A stretch of time during which the same profile (or none) is in effect.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduledPeriod {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Name of the profile in effect, if any
+    pub profile: Option<String>,
+    pub workers: usize,
+}
+
+impl ProfileSchedule {
+    /// Checks the profiles and caps their worker counts at `max_workers`.
+    ///
+    /// # Errors
+    /// Returns a description of the first invalid profile.
+    pub fn new(
+        profiles: Vec<ResourceProfile>,
+        default_workers: usize,
+        max_workers: usize,
+    ) -> Result<Self, String> {
+        let mut capped = Vec::with_capacity(profiles.len());
+        for mut profile in profiles {
+            profile.window()?;
+            if profile.workers == 0 {
+                return Err(format!(
+                    "profile '{}': needs at least one worker",
+                    profile.name
+                ));
+            }
+            profile.workers = profile.workers.min(max_workers);
+            capped.push(profile);
+        }
+        Ok(Self {
+            profiles: capped,
+            default_workers,
+            timezone: None,
+        })
+    }
+
+    /// Evaluates the windows in the IANA time zone `name`, e.g. `Europe/Berlin`, instead of the
+    /// system's.
+    ///
+    /// # Errors
+    /// Returns a description if the time zone is unknown.
+    pub fn in_timezone(mut self, name: Option<&str>) -> Result<Self, String> {
+        if let Some(name) = name {
+            let timezone = name.parse::<Tz>().map_err(|_| {
+                format!(
+                    "unknown time zone '{}' (expected an IANA name such as Europe/Berlin)",
+                    name
+                )
+            })?;
+            self.timezone = Some(timezone);
+        }
+        Ok(self)
+    }
+
+    /// Name of the time zone the windows are in.
+    pub fn timezone_name(&self) -> String {
+        self.timezone.map_or_else(
+            || "system local time".to_string(),
+            |tz| tz.name().to_string(),
+        )
+    }
+
+    /// The wall-clock time of the schedule's time zone at `at`.
+    pub fn local_time(&self, at: DateTime<Utc>) -> NaiveDateTime {
+        match self.timezone {
+            Some(tz) => at.with_timezone(&tz).naive_local(),
+            None => at.with_timezone(&Local).naive_local(),
+        }
+    }
+
+    /// `at` as shown in the schedule's time zone, e.g. `Sun 2026-03-29 03:00 CEST`.
+    fn format_time(&self, at: DateTime<Utc>) -> String {
+        const FORMAT: &str = "%a %Y-%m-%d %H:%M %Z";
+        match self.timezone {
+            Some(tz) => at.with_timezone(&tz).format(FORMAT).to_string(),
+            None => at.with_timezone(&Local).format(FORMAT).to_string(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.profiles.is_empty()
+    }
+
+    /// Workers to start so that every profile can be honoured.
+    pub fn max_workers(&self) -> usize {
+        self.profiles
+            .iter()
+            .map(|profile| profile.workers)
+            .fold(self.default_workers, usize::max)
+    }
+
+    /// The profile in effect at `time`; the first listed wins where windows overlap.
+    pub fn active_at(&self, time: NaiveTime) -> Option<&ResourceProfile> {
+        self.profiles.iter().find(|profile| profile.contains(time))
+    }
+
+    /// Workers allowed at `time`.
+    pub fn workers_at(&self, time: NaiveTime) -> usize {
+        self.active_at(time)
+            .map_or(self.default_workers, |profile| profile.workers)
+    }
+
+    /// The periods from `from` over the following `days`, stepping through each minute so that
+    /// daylight saving changes fall where the time zone puts them.
+    pub fn preview(&self, from: DateTime<Utc>, days: u32) -> Vec<ScheduledPeriod> {
+        let start = from.duration_trunc(TimeDelta::minutes(1)).unwrap_or(from);
+        let end = start + TimeDelta::days(i64::from(days));
+        let mut periods: Vec<ScheduledPeriod> = Vec::new();
+        let mut at = start;
+        while at < end {
+            let time = self.local_time(at).time();
+            let profile = self.active_at(time).map(|profile| profile.name.clone());
+            match periods.last_mut() {
+                Some(period) if period.profile == profile => {
+                    period.end = at + TimeDelta::minutes(1)
+                }
+                _ => periods.push(ScheduledPeriod {
+                    start: at,
+                    end: at + TimeDelta::minutes(1),
+                    profile,
+                    workers: self.workers_at(time),
+                }),
+            }
+            at += TimeDelta::minutes(1);
+        }
+        periods
+    }
+}
+
+/// Prints which profile is in effect over the next `days`, for `nexus schedule preview`.
+pub fn print_preview(schedule: &ProfileSchedule, days: u32) {
+    if schedule.is_empty() {
+        println!("No resource profiles in the config file; every worker proves at all times.");
+        return;
+    }
+    println!(
+        "Resource profiles for the next {} days ({}):",
+        days,
+        schedule.timezone_name()
+    );
+    for period in schedule.preview(Utc::now(), days) {
+        let what = match &period.profile {
+            Some(name) => format!("{} ({} workers)", name, period.workers),
+            None => "no profile (--max-threads workers)".to_string(),
+        };
+        println!(
+            "  {} – {}  {}",
+            schedule.format_time(period.start),
+            schedule.format_time(period.end),
+            what
+        );
+    }
+}
+
+/// Spawns a task that applies the active profile's worker count, reporting each switch.
+pub fn start_profile_switcher(
+    schedule: ProfileSchedule,
+    event_sender: mpsc::Sender<Event>,
+    mut shutdown: broadcast::Receiver<()>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut current: Option<Option<String>> = None;
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = shutdown.recv() => break,
+                _ = interval.tick() => {
+                    let now = schedule.local_time(Utc::now()).time();
+                    let active = schedule.active_at(now).map(|profile| profile.name.clone());
+                    if current.as_ref() == Some(&active) {
+                        continue;
+                    }
+                    let workers = schedule.workers_at(now);
+                    control_state().set_worker_limit(workers);
+                    let message = match &active {
+                        Some(name) => {
+                            format!("Profile '{}' active: proving with {} workers", name, workers)
+                        }
+                        None => format!("No profile active: proving with {} workers", workers),
+                    };
+                    let event = Event::scheduler_with_level(message, EventType::Refresh, LogLevel::Info);
+                    let _ = event_sender.send(event).await;
+                    current = Some(active);
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(name: &str, workers: usize, from: &str, until: &str) -> ResourceProfile {
+        ResourceProfile {
+            name: name.to_string(),
+            workers,
+            from: from.to_string(),
+            until: until.to_string(),
+        }
+    }
+
+    fn at(time: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(time, "%H:%M").unwrap()
+    }
+
+    #[test]
+    // Windows should select the right profile, including one spanning midnight.
+    fn test_active_profile_by_time() {
+        let schedule = ProfileSchedule::new(
+            vec![
+                profile("day", 2, "09:00", "18:00"),
+                profile("night", 16, "22:00", "06:00"),
+            ],
+            4,
+            8,
+        )
+        .unwrap();
+        assert_eq!(schedule.max_workers(), 8);
+        assert_eq!(schedule.active_at(at("12:00")).unwrap().name, "day");
+        assert_eq!(schedule.workers_at(at("09:00")), 2);
+        assert_eq!(schedule.workers_at(at("18:00")), 4);
+        assert_eq!(schedule.workers_at(at("23:30")), 8);
+        assert_eq!(schedule.workers_at(at("05:59")), 8);
+        assert_eq!(schedule.workers_at(at("06:00")), 4);
+    }
+
+    #[test]
+    // Windows follow the wall clock across a daylight saving change, and periods are merged.
+    fn test_preview_across_dst() {
+        let schedule = ProfileSchedule::new(vec![profile("day", 2, "09:00", "18:00")], 4, 8)
+            .unwrap()
+            .in_timezone(Some("Europe/Berlin"))
+            .unwrap();
+        // Clocks go forward on 2026-03-29: the window starts at 08:00 UTC the day before and
+        // at 07:00 UTC on the day
+        let from = "2026-03-28T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let periods = schedule.preview(from, 2);
+        let starts: Vec<String> = periods
+            .iter()
+            .filter(|period| period.profile.is_some())
+            .map(|period| period.start.format("%Y-%m-%d %H:%M").to_string())
+            .collect();
+        assert_eq!(starts, ["2026-03-28 08:00", "2026-03-29 07:00"]);
+        assert_eq!(periods.len(), 5);
+        assert_eq!(periods[0].workers, 4);
+        assert_eq!(periods.last().unwrap().end, from + TimeDelta::days(2));
+        assert!(schedule.clone().in_timezone(Some("Mars/Olympus")).is_err());
+    }
+
+    #[test]
+    // Invalid times and empty profiles should be rejected.
+    fn test_invalid_profiles() {
+        assert!(ProfileSchedule::new(vec![profile("day", 2, "9am", "18:00")], 1, 8).is_err());
+        assert!(ProfileSchedule::new(vec![profile("off", 0, "09:00", "18:00")], 1, 8).is_err());
+        assert!(ProfileSchedule::new(Vec::new(), 1, 8).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,158 @@
+//! Guest program allowlist
+//!
+//! With `start --program-allowlist`, the prover only runs guest programs whose SHA-256 is listed
+//! in `~/.nexus/program_allowlist.json`, as defense in depth for operators proving on shared
+//! infrastructure: a guest binary that was swapped, or one nobody vetted, is refused with its
+//! hash instead of executed.
+//!
+//! ```json
+//! {
+//!   "programs": [{ "program_id": "fast-fib", "sha256": "…" }]
+//! }
+//! ```
+//!
+//! The orchestrator publishes no program hashes, so the list is the operator's own and is never
+//! rewritten. Starting with an empty or unreadable allowlist fails, since every task would be
+//! refused. Tasks of programs not allowed are skipped when fetched, and refused again before
+//! proving.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// A guest program allowed to run, by the hash of its ELF binary.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AllowedProgram {
+    pub program_id: String,
+    /// Lower-case hex SHA-256 of the ELF binary
+    pub sha256: String,
+}
+
+/// Guest programs allowed to run on this machine.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Allowlist {
+    #[serde(default)]
+    pub programs: Vec<AllowedProgram>,
+}
+
+impl Allowlist {
+    /// Loads the allowlist.
+    ///
+    /// # Errors
+    /// Returns an `std::io::Error` if the file cannot be read or parsed.
+    pub fn load_from_file(path: &Path) -> Result<Self, std::io::Error> {
+        let buf = fs::read(path)?;
+        serde_json::from_slice(&buf)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    /// Number of allowed hashes.
+    pub fn hash_count(&self) -> usize {
+        self.programs.len()
+    }
+
+    /// Whether a guest binary with this hash may run.
+    pub fn allows(&self, sha256: &str) -> bool {
+        self.programs
+            .iter()
+            .any(|program| program.sha256.eq_ignore_ascii_case(sha256.trim()))
+    }
+}
+
+/// Path to the allowlist, next to the config file.
+pub fn allowlist_path(config_path: &Path) -> PathBuf {
+    config_path.with_file_name("program_allowlist.json")
+}
+
+/// Lower-case hex SHA-256 of a guest binary.
+pub fn sha256_hex(elf: &[u8]) -> String {
+    Sha256::digest(elf)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// The enforced allowlist and where it is kept
+static ENFORCED: OnceLock<(Allowlist, PathBuf)> = OnceLock::new();
+
+/// Enforces the allowlist next to the config file from now on. Returns the allowlist in
+/// effect. Only the first call has an effect.
+///
+/// # Errors
+/// Returns a message if the allowlist cannot be read or allows nothing, as no task could run.
+pub fn enforce(config_path: &Path) -> Result<&'static Allowlist, String> {
+    if let Some((allowlist, _)) = ENFORCED.get() {
+        return Ok(allowlist);
+    }
+    let path = allowlist_path(config_path);
+    let allowlist = match Allowlist::load_from_file(&path) {
+        Ok(allowlist) => allowlist,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Allowlist::default(),
+        Err(e) => return Err(format!("Cannot read {}: {}", path.display(), e)),
+    };
+    if allowlist.hash_count() == 0 {
+        return Err(format!(
+            "--program-allowlist allows no guest program: add their hashes under \"programs\" in {}",
+            path.display()
+        ));
+    }
+    let (allowlist, _) = ENFORCED.get_or_init(|| (allowlist, path));
+    Ok(allowlist)
+}
+
+/// Checks the guest binary `elf` of `program_id` against the allowlist, if it is enforced.
+///
+/// # Errors
+/// Returns a message naming the program and its hash if it may not run.
+pub fn check(program_id: &str, elf: &[u8]) -> Result<(), String> {
+    let Some((allowlist, path)) = ENFORCED.get() else {
+        return Ok(());
+    };
+    let sha256 = sha256_hex(elf);
+    if allowlist.allows(&sha256) {
+        return Ok(());
+    }
+    Err(format!(
+        "guest program {} (sha256 {}) is not in the allowlist {}; add it under \"programs\" to run it",
+        program_id,
+        sha256,
+        path.display()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    // Listed hashes are allowed in any case, and the file round-trips.
+    fn test_allows_listed_hashes() {
+        let dir = tempdir().unwrap();
+        let path = allowlist_path(&dir.path().join("config.json"));
+        let fib = sha256_hex(b"fib guest");
+        let mine = sha256_hex(b"my guest");
+        let allowlist = Allowlist {
+            programs: vec![
+                AllowedProgram {
+                    program_id: "fast-fib".to_string(),
+                    sha256: fib.clone(),
+                },
+                AllowedProgram {
+                    program_id: "mine".to_string(),
+                    sha256: mine.to_uppercase(),
+                },
+            ],
+        };
+        fs::write(&path, serde_json::to_string(&allowlist).unwrap()).unwrap();
+        let loaded = Allowlist::load_from_file(&path).unwrap();
+        assert_eq!(loaded, allowlist);
+
+        assert!(loaded.allows(&fib));
+        assert!(loaded.allows(&mine));
+        assert!(!loaded.allows(&sha256_hex(b"swapped guest")));
+        assert!(!Allowlist::default().allows(&fib));
+    }
+}
@@ -0,0 +1,112 @@
+//! Concurrency per program
+//!
+//! Programs differ widely in memory use, so a single worker count is either wasteful for light
+//! programs or runs out of memory with heavy ones. `program_concurrency` in the config file caps
+//! how many tasks of a program are proved at once, by program ID:
+//!
+//! ```json
+//! "program_concurrency": { "heavy-program": 2, "fast-fib": 8 }
+//! ```
+//!
+//! The dispatcher holds back a task whose program is at its cap until one of that program's
+//! proofs finishes, and hands out the tasks behind it meanwhile. Programs without a cap are
+//! only limited by the number of workers.
+//!
+//! Caps are per process, not per machine: each prover process (`--role prover`) and each node
+//! started separately on the same machine counts its own proofs only.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, OnceLock};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Caps and slots of the programs with a cap.
+#[derive(Debug, Default)]
+struct Limits {
+    slots: HashMap<String, (usize, Arc<Semaphore>)>,
+}
+
+impl Limits {
+    fn new(limits: BTreeMap<String, usize>) -> Self {
+        Self {
+            slots: limits
+                .into_iter()
+                .map(|(program_id, limit)| {
+                    let limit = limit.max(1);
+                    (program_id, (limit, Arc::new(Semaphore::new(limit))))
+                })
+                .collect(),
+        }
+    }
+}
+
+static LIMITS: OnceLock<Limits> = OnceLock::new();
+
+/// Caps concurrent proofs of each program in `limits`, by program ID. Only the first call has
+/// an effect.
+pub fn set_limits(limits: BTreeMap<String, usize>) {
+    let _ = LIMITS.set(Limits::new(limits));
+}
+
+/// The most tasks of `program_id` proved at once, if the program has a cap.
+pub fn limit(program_id: &str) -> Option<usize> {
+    LIMITS.get()?.slots.get(program_id).map(|(limit, _)| *limit)
+}
+
+/// Every cap, by program ID.
+pub fn limits() -> BTreeMap<String, usize> {
+    LIMITS
+        .get()
+        .map(|limits| {
+            limits
+                .slots
+                .iter()
+                .map(|(program_id, (limit, _))| (program_id.clone(), *limit))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// A slot to prove a task of `program_id` now, held until the permit is dropped: `Ok(None)`
+/// if the program has no cap, `Err` with the cap if every slot is taken.
+pub fn try_acquire(program_id: &str) -> Result<Option<OwnedSemaphorePermit>, usize> {
+    let Some((limit, slots)) = LIMITS.get().and_then(|limits| limits.slots.get(program_id)) else {
+        return Ok(None);
+    };
+    slots
+        .clone()
+        .try_acquire_owned()
+        .map(Some)
+        .map_err(|_| *limit)
+}
+
+/// Waits for a slot to prove a task of `program_id`; `None` if the program has no cap.
+pub async fn acquire(program_id: &str) -> Option<OwnedSemaphorePermit> {
+    let (_, slots) = LIMITS.get()?.slots.get(program_id)?;
+    slots.clone().acquire_owned().await.ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    // A capped program hands out as many slots as its cap; uncapped programs need none.
+    async fn test_slots_per_program() {
+        set_limits(BTreeMap::from([
+            ("heavy".to_string(), 2),
+            ("broken".to_string(), 0),
+        ]));
+        assert_eq!(limit("heavy"), Some(2));
+        assert_eq!(limit("broken"), Some(1));
+        assert_eq!(limit("light"), None);
+        assert_eq!(limits().len(), 2);
+
+        let first = try_acquire("heavy").unwrap();
+        let second = acquire("heavy").await;
+        assert!(first.is_some() && second.is_some());
+        assert_eq!(try_acquire("heavy").err(), Some(2));
+        drop(first);
+        assert!(try_acquire("heavy").unwrap().is_some());
+        assert!(try_acquire("light").unwrap().is_none());
+    }
+}
@@ -0,0 +1,409 @@
+//! Per-program performance profiles
+//!
+//! Guest programs differ widely in how long they take to prove, and the gap depends on the
+//! hardware. The observed proving time of each program is kept in `~/.nexus/programs.json`
+//! across runs, and used to size how many tasks are fetched ahead (see `backpressure`) and to
+//! order each fetched batch, so the programs expected to prove soonest are proved first.
+//! `nexus stats --programs` shows the learned profiles, so operators can see which workloads
+//! suit their machine (and, for example, deny the others in the task filter).
+//!
+//! Profiles are kept per environment (see `Environment::label`), so runs against a test
+//! orchestrator neither skew production profiles nor its success counts. Files written before
+//! that hold production profiles.
+//!
+//! Tasks carry no deadline, so fetched tasks are only reordered on these estimates, never
+//! skipped; the fit shown by `nexus stats --programs` is judged against the submission queue's assumed
+//! `TASK_LIFETIME` and is only a guide.
+
+use crate::environment::Environment;
+use crate::submission_queue::TASK_LIFETIME;
+use crate::task::Task;
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+/// Proofs required before a program's expected duration is trusted.
+const MIN_SAMPLES: u64 = 3;
+
+/// Weight of the newest proof in the moving average.
+const SMOOTHING: f64 = 0.2;
+
+/// Share of the task lifetime a program's proofs may take and still count as a good fit.
+const GOOD_FIT: f64 = 0.25;
+
+/// Share of the task lifetime beyond which a program counts as a poor fit.
+const POOR_FIT: f64 = 0.75;
+
+/// What has been observed proving one program on this machine.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ProgramProfile {
+    pub proofs: u64,
+    pub failures: u64,
+    /// Moving average of proving time, in seconds.
+    pub average_secs: f64,
+    pub fastest_secs: f64,
+    pub slowest_secs: f64,
+    /// When the program was last proved or failed (RFC 3339).
+    pub last_seen: String,
+}
+
+impl ProgramProfile {
+    /// Expected proving time, once enough proofs were observed.
+    pub fn expected_duration(&self) -> Option<Duration> {
+        (self.proofs >= MIN_SAMPLES).then(|| Duration::from_secs_f64(self.average_secs))
+    }
+
+    /// How well the program suits this machine, judged by the share of the task lifetime its
+    /// proofs take.
+    pub fn fit(&self) -> &'static str {
+        if self.proofs == 0 {
+            return "unknown";
+        }
+        let share = self.average_secs / TASK_LIFETIME.as_secs_f64();
+        if share <= GOOD_FIT {
+            "good"
+        } else if share <= POOR_FIT {
+            "tight"
+        } else {
+            "poor"
+        }
+    }
+}
+
+/// Profiles of every program proved on this machine, by program ID.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ProgramProfiles {
+    pub programs: BTreeMap<String, ProgramProfile>,
+}
+
+impl ProgramProfiles {
+    fn record_proof(&mut self, program_id: &str, duration: Duration, now: DateTime<Utc>) {
+        let secs = duration.as_secs_f64();
+        let profile = self.programs.entry(program_id.to_string()).or_default();
+        if profile.proofs == 0 {
+            profile.average_secs = secs;
+            profile.fastest_secs = secs;
+            profile.slowest_secs = secs;
+        } else {
+            profile.average_secs = SMOOTHING * secs + (1.0 - SMOOTHING) * profile.average_secs;
+            profile.fastest_secs = profile.fastest_secs.min(secs);
+            profile.slowest_secs = profile.slowest_secs.max(secs);
+        }
+        profile.proofs += 1;
+        profile.last_seen = now.to_rfc3339();
+    }
+
+    fn record_failure(&mut self, program_id: &str, now: DateTime<Utc>) {
+        let profile = self.programs.entry(program_id.to_string()).or_default();
+        profile.failures += 1;
+        profile.last_seen = now.to_rfc3339();
+    }
+
+    /// Expected proving time of the slowest program with enough proofs observed.
+    pub fn slowest_expected_duration(&self) -> Option<Duration> {
+        self.programs
+            .values()
+            .filter_map(ProgramProfile::expected_duration)
+            .max()
+    }
+
+    /// Orders `tasks` by the expected proving time of their program, quickest first. Programs
+    /// without enough proofs observed lead, so they are learned; the sort is stable, so tasks
+    /// otherwise keep the orchestrator's order.
+    pub fn order_by_expected_duration(&self, tasks: &mut [Task]) {
+        tasks.sort_by_key(|task| {
+            self.programs
+                .get(&task.program_id)
+                .and_then(ProgramProfile::expected_duration)
+        });
+    }
+}
+
+/// Profiles of every environment, by environment label.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(from = "SavedProfiles")]
+pub struct ProfileHistory {
+    pub environments: BTreeMap<String, ProgramProfiles>,
+}
+
+/// Profiles as saved by this release, or by earlier ones, which kept a single set.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedProfiles {
+    ByEnvironment {
+        environments: BTreeMap<String, ProgramProfiles>,
+    },
+    Production(ProgramProfiles),
+}
+
+impl From<SavedProfiles> for ProfileHistory {
+    fn from(saved: SavedProfiles) -> Self {
+        let environments = match saved {
+            SavedProfiles::ByEnvironment { environments } => environments,
+            SavedProfiles::Production(profiles) => {
+                BTreeMap::from([(Environment::Production.label(), profiles)])
+            }
+        };
+        Self { environments }
+    }
+}
+
+impl ProfileHistory {
+    /// Loads the saved profiles; a missing file means nothing was learned yet.
+    ///
+    /// # Errors
+    /// Returns an `std::io::Error` if the file exists but cannot be read or parsed.
+    pub fn load_from_file(path: &Path) -> Result<Self, std::io::Error> {
+        match std::fs::read(path) {
+            Ok(buf) => serde_json::from_slice(&buf)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn save(&self, path: &Path) {
+        if let Ok(json) = serde_json::to_string_pretty(self) {
+            let _ = std::fs::write(path, json);
+        }
+    }
+}
+
+/// Path to the saved profiles, next to the config file.
+pub fn profiles_path(config_path: &Path) -> PathBuf {
+    config_path.with_file_name("programs.json")
+}
+
+struct ProfileStore {
+    history: ProfileHistory,
+    /// Label of the environment this process proves for
+    environment: String,
+    path: Option<PathBuf>,
+}
+
+static STORE: OnceLock<Mutex<ProfileStore>> = OnceLock::new();
+
+/// Starts learning program profiles for `environment`, continuing from earlier runs. Only the
+/// first call has an effect.
+pub fn init(environment: &Environment) {
+    let path = crate::config::get_config_path()
+        .ok()
+        .map(|path| profiles_path(&path));
+    let history = path
+        .as_deref()
+        .and_then(|path| ProfileHistory::load_from_file(path).ok())
+        .unwrap_or_default();
+    let _ = STORE.set(Mutex::new(ProfileStore {
+        history,
+        environment: environment.label(),
+        path,
+    }));
+}
+
+fn update(f: impl FnOnce(&mut ProgramProfiles)) {
+    let Some(Ok(mut store)) = STORE.get().map(Mutex::lock) else {
+        return;
+    };
+    let store = &mut *store;
+    f(store
+        .history
+        .environments
+        .entry(store.environment.clone())
+        .or_default());
+    if let Some(path) = &store.path {
+        store.history.save(path);
+    }
+}
+
+/// Records a successful proof of `program_id`.
+pub fn record_proof(program_id: &str, duration: Duration) {
+    update(|profiles| profiles.record_proof(program_id, duration, Utc::now()));
+}
+
+/// Records a failed proof of `program_id`.
+pub fn record_failure(program_id: &str) {
+    update(|profiles| profiles.record_failure(program_id, Utc::now()));
+}
+
+/// Expected proving time of the slowest program learned for this environment.
+pub fn slowest_expected_duration() -> Option<Duration> {
+    let Some(Ok(store)) = STORE.get().map(Mutex::lock) else {
+        return None;
+    };
+    store
+        .history
+        .environments
+        .get(&store.environment)
+        .and_then(ProgramProfiles::slowest_expected_duration)
+}
+
+/// Orders a fetched batch by the profiles learned for this environment (see
+/// `ProgramProfiles::order_by_expected_duration`).
+pub fn order_fetched(tasks: &mut [Task]) {
+    let Some(Ok(store)) = STORE.get().map(Mutex::lock) else {
+        return;
+    };
+    if let Some(profiles) = store.history.environments.get(&store.environment) {
+        profiles.order_by_expected_duration(tasks);
+    }
+}
+
+/// Implements `nexus stats`, with one section per environment and, if `programs` is set, one
+/// row per program.
+pub fn print_stats(config_path: &Path, programs: bool, json: bool) -> Result<(), Box<dyn Error>> {
+    let history = ProfileHistory::load_from_file(&profiles_path(config_path))?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&history)?);
+        return Ok(());
+    }
+    if history
+        .environments
+        .values()
+        .all(|profiles| profiles.programs.is_empty())
+    {
+        println!("No proofs recorded yet. Profiles are learned while the node runs.");
+        return Ok(());
+    }
+
+    for (environment, profiles) in &history.environments {
+        let proofs: u64 = profiles.programs.values().map(|p| p.proofs).sum();
+        let failures: u64 = profiles.programs.values().map(|p| p.failures).sum();
+        println!(
+            "[{}] {} proofs and {} failures across {} programs",
+            environment,
+            proofs,
+            failures,
+            profiles.programs.len()
+        );
+        if programs {
+            print_programs(profiles);
+        }
+    }
+    if !programs {
+        println!("Run `nexus stats --programs` for the profile of each program.");
+        return Ok(());
+    }
+    println!(
+        "Fit compares the average proof with the {}-minute task lifetime: good under {:.0}%, poor over {:.0}%.",
+        TASK_LIFETIME.as_secs() / 60,
+        GOOD_FIT * 100.0,
+        POOR_FIT * 100.0
+    );
+    Ok(())
+}
+
+fn print_programs(profiles: &ProgramProfiles) {
+    println!(
+        "\n{:<24} {:>7} {:>7} {:>9} {:>9} {:>9} {:>7}  {}",
+        "Program", "Proofs", "Failed", "Average", "Fastest", "Slowest", "Fit", "Last seen"
+    );
+    for (program_id, profile) in &profiles.programs {
+        let last_seen = DateTime::parse_from_rfc3339(&profile.last_seen)
+            .map(|time| {
+                time.with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .unwrap_or_default();
+        println!(
+            "{:<24} {:>7} {:>7} {:>8.1}s {:>8.1}s {:>8.1}s {:>7}  {}",
+            program_id,
+            profile.proofs,
+            profile.failures,
+            profile.average_secs,
+            profile.fastest_secs,
+            profile.slowest_secs,
+            profile.fit(),
+            last_seen
+        );
+    }
+    println!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    // Profiles track the average and range per program, and survive a save and load.
+    fn test_record_and_round_trip() {
+        let now = Utc::now();
+        let mut profiles = ProgramProfiles::default();
+        profiles.record_proof("fast-fib", Duration::from_secs(10), now);
+        profiles.record_proof("fast-fib", Duration::from_secs(20), now);
+        profiles.record_failure("fast-fib", now);
+        profiles.record_proof("fib_input_initial", Duration::from_secs(600), now);
+
+        let fib = &profiles.programs["fast-fib"];
+        assert_eq!((fib.proofs, fib.failures), (2, 1));
+        assert!((fib.average_secs - 12.0).abs() < 1e-9);
+        assert_eq!((fib.fastest_secs, fib.slowest_secs), (10.0, 20.0));
+        assert_eq!(fib.fit(), "good");
+        assert_eq!(profiles.programs["fib_input_initial"].fit(), "tight");
+
+        let dir = tempdir().unwrap();
+        let path = profiles_path(&dir.path().join("config.json"));
+        assert_eq!(
+            ProfileHistory::load_from_file(&path).unwrap(),
+            ProfileHistory::default()
+        );
+        let history = ProfileHistory {
+            environments: BTreeMap::from([("production".to_string(), profiles)]),
+        };
+        history.save(&path);
+        assert_eq!(ProfileHistory::load_from_file(&path).unwrap(), history);
+    }
+
+    #[test]
+    // Profiles saved before they were kept per environment are read as production profiles.
+    fn test_load_single_environment_file() {
+        let mut profiles = ProgramProfiles::default();
+        profiles.record_proof("fast-fib", Duration::from_secs(10), Utc::now());
+        let dir = tempdir().unwrap();
+        let path = profiles_path(&dir.path().join("config.json"));
+        std::fs::write(&path, serde_json::to_string(&profiles).unwrap()).unwrap();
+
+        let history = ProfileHistory::load_from_file(&path).unwrap();
+        assert_eq!(history.environments.len(), 1);
+        assert_eq!(history.environments["production"], profiles);
+    }
+
+    #[test]
+    // A program's proving time is only trusted once enough proofs were observed.
+    fn test_slowest_expected_duration() {
+        let mut profiles = ProgramProfiles::default();
+        profiles.record_proof("slow", Duration::from_secs(120), Utc::now());
+        assert_eq!(profiles.slowest_expected_duration(), None);
+        for _ in 1..MIN_SAMPLES {
+            profiles.record_proof("slow", Duration::from_secs(120), Utc::now());
+        }
+        assert_eq!(
+            profiles.slowest_expected_duration(),
+            Some(Duration::from_secs(120))
+        );
+    }
+
+    #[test]
+    // A batch is ordered quickest program first, with programs not learned yet leading.
+    fn test_order_by_expected_duration() {
+        let mut profiles = ProgramProfiles::default();
+        for _ in 0..MIN_SAMPLES {
+            profiles.record_proof("slow", Duration::from_secs(300), Utc::now());
+            profiles.record_proof("fast", Duration::from_secs(30), Utc::now());
+        }
+        profiles.record_proof("new", Duration::from_secs(600), Utc::now());
+
+        let mut tasks: Vec<Task> = [("1", "slow"), ("2", "fast"), ("3", "new"), ("4", "slow")]
+            .into_iter()
+            .map(|(id, program)| Task::new(id.to_string(), program.to_string(), Vec::new()))
+            .collect();
+        profiles.order_by_expected_duration(&mut tasks);
+        let ids: Vec<&str> = tasks.iter().map(|task| task.task_id.as_str()).collect();
+        assert_eq!(ids, ["3", "2", "1", "4"]);
+    }
+}
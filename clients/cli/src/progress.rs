@@ -0,0 +1,201 @@
+//! Machine-readable progress
+//!
+//! `--progress-json` (stdout) or `--progress-fd <FD>` (an inherited file descriptor, Unix only)
+//! emits one JSON object per line as each task moves through the pipeline, so GUI wrappers
+//! and provisioning systems can build their own views without parsing log lines:
+//!
+//! ```text
+//! {"timestamp":"...","event":"task_fetched","percent":0,"task_id":"123","program_id":"fast-fib"}
+//! {"timestamp":"...","event":"proving_started","percent":25,"task_id":"123","worker":0}
+//! {"timestamp":"...","event":"proof_completed","percent":75,"task_id":"123","worker":0,"duration_ms":5120}
+//! {"timestamp":"...","event":"submitted","percent":100,"task_id":"123"}
+//! {"timestamp":"...","event":"failed","percent":null,"task_id":"123","stage":"submit","error":"..."}
+//! {"timestamp":"...","event":"state_changed","percent":null,"task_id":"123","from":"submitting","to":"accepted"}
+//! ```
+//!
+//! Proving itself does not report intermediate progress, so `percent` marks pipeline stages.
+//! Each line also carries the `environment` the node works for (see `Environment::label`), so
+//! wrappers driving several nodes can tell production from test runs.
+//! With `--progress-json` the headless log lines move to stderr, leaving stdout to the events.
+
+use crate::environment::Environment;
+use crate::task_lifecycle::TaskState;
+use serde::Serialize;
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+
+/// A step in a task's progress.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    TaskFetched {
+        task_id: String,
+        program_id: String,
+    },
+    ProvingStarted {
+        task_id: String,
+        worker: usize,
+    },
+    ProofCompleted {
+        task_id: String,
+        worker: usize,
+        duration_ms: u64,
+    },
+    Submitted {
+        task_id: String,
+    },
+    Failed {
+        task_id: String,
+        /// "prove" or "submit".
+        stage: &'static str,
+        error: String,
+    },
+    /// The task moved to another lifecycle state; `from` is null when tracking starts.
+    StateChanged {
+        task_id: String,
+        from: Option<TaskState>,
+        to: TaskState,
+    },
+}
+
+impl ProgressEvent {
+    /// How far through the pipeline the task is, or `None` for failures and state changes.
+    fn percent(&self) -> Option<u8> {
+        match self {
+            ProgressEvent::TaskFetched { .. } => Some(0),
+            ProgressEvent::ProvingStarted { .. } => Some(25),
+            ProgressEvent::ProofCompleted { .. } => Some(75),
+            ProgressEvent::Submitted { .. } => Some(100),
+            ProgressEvent::Failed { .. } | ProgressEvent::StateChanged { .. } => None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ProgressLine<'a> {
+    timestamp: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    environment: Option<&'a str>,
+    #[serde(flatten)]
+    event: &'a ProgressEvent,
+    percent: Option<u8>,
+}
+
+/// Formats an event as a single line of JSON, without the trailing newline.
+fn to_line(event: &ProgressEvent, environment: Option<&str>) -> String {
+    let line = ProgressLine {
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        environment,
+        event,
+        percent: event.percent(),
+    };
+    serde_json::to_string(&line).unwrap_or_default()
+}
+
+struct Sink {
+    writer: Mutex<Box<dyn Write + Send>>,
+    stdout: bool,
+}
+
+static PROGRESS_SINK: OnceLock<Sink> = OnceLock::new();
+static ENVIRONMENT: OnceLock<String> = OnceLock::new();
+
+/// Labels progress events with `environment`. Only the first call has an effect.
+pub fn set_environment(environment: &Environment) {
+    let _ = ENVIRONMENT.set(environment.label());
+}
+
+/// Sends progress events to stdout. Only the first sink set has an effect.
+pub fn init_stdout() {
+    let _ = PROGRESS_SINK.set(Sink {
+        writer: Mutex::new(Box::new(std::io::stdout())),
+        stdout: true,
+    });
+}
+
+/// Sends progress events to an inherited file descriptor.
+///
+/// # Errors
+/// Returns an error if `fd` is not an open descriptor of this process.
+#[cfg(unix)]
+pub fn init_fd(fd: i32) -> std::io::Result<()> {
+    use std::os::fd::FromRawFd;
+    // Check the descriptor is open before taking ownership of it
+    if !std::path::Path::new(&format!("/dev/fd/{}", fd)).exists() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("file descriptor {} is not open", fd),
+        ));
+    }
+    // SAFETY: the descriptor is open and handed to this process for progress output only.
+    let file = unsafe { std::fs::File::from_raw_fd(fd) };
+    let _ = PROGRESS_SINK.set(Sink {
+        writer: Mutex::new(Box::new(file)),
+        stdout: false,
+    });
+    Ok(())
+}
+
+/// Windows processes inherit handles rather than descriptors, so only `--progress-json` is
+/// available there.
+#[cfg(not(unix))]
+pub fn init_fd(_fd: i32) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "--progress-fd is only supported on Unix; use --progress-json instead",
+    ))
+}
+
+/// Whether progress events are written to stdout, in which case log lines belong on stderr.
+pub fn on_stdout() -> bool {
+    PROGRESS_SINK.get().is_some_and(|sink| sink.stdout)
+}
+
+/// Writes a progress event, if progress output is enabled. Write errors are ignored so a
+/// closed pipe never affects proving.
+pub fn emit(event: ProgressEvent) {
+    let Some(sink) = PROGRESS_SINK.get() else {
+        return;
+    };
+    if let Ok(mut writer) = sink.writer.lock() {
+        let environment = ENVIRONMENT.get().map(String::as_str);
+        let _ = writeln!(writer, "{}", to_line(&event, environment));
+        let _ = writer.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Lines should carry the event tag, its fields and the stage percentage.
+    fn test_progress_line_format() {
+        let line = to_line(
+            &ProgressEvent::ProvingStarted {
+                task_id: "123".to_string(),
+                worker: 2,
+            },
+            Some("production"),
+        );
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["event"], "proving_started");
+        assert_eq!(value["task_id"], "123");
+        assert_eq!(value["worker"], 2);
+        assert_eq!(value["percent"], 25);
+        assert!(value["timestamp"].is_string());
+        assert_eq!(value["environment"], "production");
+
+        let line = to_line(
+            &ProgressEvent::Failed {
+                task_id: "123".to_string(),
+                stage: "submit",
+                error: "HTTP 500".to_string(),
+            },
+            None,
+        );
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert!(value["percent"].is_null());
+        assert!(value.get("environment").is_none());
+    }
+}
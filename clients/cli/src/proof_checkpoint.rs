@@ -0,0 +1,290 @@
+//! Proof checkpoints
+//!
+//! Proving runs inside the SDK as a single call with no intermediate state to save, so there
+//! is no resuming a proof midway: the unit of work that survives a crash is the finished
+//! proof. Each proof is saved under `~/.nexus/proofs/`, keyed by task ID, with its task and
+//! environment, as soon as proving ends, in prover processes too. If the process crashes or is
+//! restarted before the proof reaches the submission journal (while it waits for upload on a
+//! slow link, say), the next start submits the saved proofs of the nodes it runs that the
+//! journal does not know, and a task that comes back anyway is not proved again.
+//!
+//! A checkpoint is removed once the journal holds the proof, or once its task is skipped.
+//! Checkpoints older than a task's lifetime are removed on startup, since their tasks have
+//! expired.
+
+use crate::checkpoint::SavedTask;
+use crate::environment::Environment;
+use crate::submission_queue::TASK_LIFETIME;
+use crate::task::Task;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::SystemTime;
+
+/// A finished proof, the task it proves and the environment the task came from.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct SavedProof {
+    environment: String,
+    task: SavedTask,
+    proof: Vec<u8>,
+}
+
+/// Finished proofs saved in a directory, one file per task.
+#[derive(Debug)]
+pub struct ProofCheckpoints {
+    dir: PathBuf,
+}
+
+impl ProofCheckpoints {
+    /// Opens (or creates) the checkpoint directory.
+    ///
+    /// # Errors
+    /// Returns an `std::io::Error` if the directory cannot be created.
+    pub fn open(dir: &Path) -> std::io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+        })
+    }
+
+    fn path(&self, task_id: &str) -> PathBuf {
+        // Task IDs come from the orchestrator; keep only safe characters in file names.
+        let safe_id: String = task_id
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(format!("{}.proof", safe_id))
+    }
+
+    /// Saves the serialized proof of `task`, fetched from `environment`, replacing any earlier
+    /// one.
+    ///
+    /// # Errors
+    /// Returns an `std::io::Error` if the checkpoint cannot be written.
+    pub fn save(&self, environment: &str, task: &Task, proof: Vec<u8>) -> std::io::Result<()> {
+        let saved = SavedProof {
+            environment: environment.to_string(),
+            task: SavedTask::from(task),
+            proof,
+        };
+        let bytes = postcard::to_allocvec(&saved).map_err(std::io::Error::other)?;
+        // Written aside and renamed, so a crash mid-write leaves no torn checkpoint
+        let path = self.path(&task.task_id);
+        let partial = path.with_extension("partial");
+        fs::write(&partial, bytes)?;
+        fs::rename(partial, path)
+    }
+
+    /// The serialized proof saved for `task`, if there is one for the same program and inputs.
+    /// A checkpoint that does not match or cannot be read is removed.
+    pub fn load(&self, task: &Task) -> Option<Vec<u8>> {
+        let path = self.path(&task.task_id);
+        let bytes = fs::read(&path).ok()?;
+        let saved = postcard::from_bytes::<SavedProof>(&bytes)
+            .ok()
+            .map(|saved| (Task::from(saved.task), saved.proof));
+        match saved {
+            Some((saved, proof))
+                if saved.program_id == task.program_id
+                    && saved.public_inputs == task.public_inputs =>
+            {
+                Some(proof)
+            }
+            _ => {
+                let _ = fs::remove_file(path);
+                None
+            }
+        }
+    }
+
+    /// Every saved proof of a task fetched from `environment`, with its task.
+    pub fn saved(&self, environment: &str) -> Vec<(Task, Vec<u8>)> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "proof"))
+            .filter_map(|entry| fs::read(entry.path()).ok())
+            .filter_map(|bytes| postcard::from_bytes::<SavedProof>(&bytes).ok())
+            .filter(|saved| saved.environment == environment)
+            .map(|saved| (Task::from(saved.task), saved.proof))
+            .collect()
+    }
+
+    /// Removes the checkpoint of a task, if there is one.
+    pub fn remove(&self, task_id: &str) {
+        let _ = fs::remove_file(self.path(task_id));
+    }
+
+    /// Removes checkpoints last written before their task's lifetime ago. Returns how many.
+    pub fn prune(&self, now: SystemTime) -> usize {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return 0;
+        };
+        let mut removed = 0;
+        for entry in entries.flatten() {
+            let expired = entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .is_ok_and(|modified| modified + TASK_LIFETIME <= now);
+            if expired && fs::remove_file(entry.path()).is_ok() {
+                removed += 1;
+            }
+        }
+        removed
+    }
+}
+
+/// Path to the checkpoint directory, next to the config file.
+pub fn checkpoints_dir(config_path: &Path) -> PathBuf {
+    config_path.with_file_name("proofs")
+}
+
+/// The checkpoint directory, and the label of the environment tasks are fetched from
+static STORE: OnceLock<(ProofCheckpoints, String)> = OnceLock::new();
+
+/// Starts checkpointing proofs of tasks fetched from `environment` next to the config file,
+/// removing expired checkpoints. Only the first call has an effect; without it, nothing is
+/// checkpointed.
+pub fn init(environment: &Environment) {
+    if STORE.get().is_some() {
+        return;
+    }
+    let Ok(config_path) = crate::config::get_config_path() else {
+        return;
+    };
+    if let Ok(store) = ProofCheckpoints::open(&checkpoints_dir(&config_path)) {
+        store.prune(SystemTime::now());
+        let _ = STORE.set((store, environment.label()));
+    }
+}
+
+/// Saves the serialized proof of `task`. A proof that cannot be saved is only not resumable.
+pub fn save(task: &Task, proof: &[u8]) {
+    let Some((store, environment)) = STORE.get() else {
+        return;
+    };
+    if let Err(e) = store.save(environment, task, proof.to_vec()) {
+        log::warn!(
+            "Could not checkpoint the proof of task {}: {}",
+            task.task_id,
+            e
+        );
+    }
+}
+
+/// The serialized proof saved for `task` by this run or an earlier one, if there is one.
+pub fn load(task: &Task) -> Option<Vec<u8>> {
+    STORE.get()?.0.load(task)
+}
+
+/// The proofs an earlier run saved for `node_ids` but never got into the journal, which
+/// `is_journaled` tells by task ID, to be submitted now.
+pub fn unsubmitted(node_ids: &[u64], is_journaled: impl Fn(&str) -> bool) -> Vec<(Task, Vec<u8>)> {
+    let Some((store, environment)) = STORE.get() else {
+        return Vec::new();
+    };
+    unsubmitted_in(store, environment, node_ids, is_journaled)
+}
+
+fn unsubmitted_in(
+    store: &ProofCheckpoints,
+    environment: &str,
+    node_ids: &[u64],
+    is_journaled: impl Fn(&str) -> bool,
+) -> Vec<(Task, Vec<u8>)> {
+    store
+        .saved(environment)
+        .into_iter()
+        .filter(|(task, _)| {
+            task.node_id
+                .is_some_and(|node_id| node_ids.contains(&node_id))
+        })
+        .filter(|(task, _)| !is_journaled(&task.task_id))
+        .collect()
+}
+
+/// Removes the checkpoint of a task whose proof is journaled or no longer needed.
+pub fn remove(task_id: &str) {
+    if let Some((store, _)) = STORE.get() {
+        store.remove(task_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    #[test]
+    // A saved proof comes back for the same task only, until it is removed.
+    fn test_save_load_remove() {
+        let dir = tempdir().unwrap();
+        let store =
+            ProofCheckpoints::open(&checkpoints_dir(&dir.path().join("config.json"))).unwrap();
+        let task = Task::new("task/1".to_string(), "fast-fib".to_string(), vec![1, 2]);
+        store.save("Production", &task, vec![7; 16]).unwrap();
+        assert_eq!(store.load(&task), Some(vec![7; 16]));
+
+        // Reassigned with other inputs, the saved proof does not apply and is dropped
+        let changed = Task::new("task/1".to_string(), "fast-fib".to_string(), vec![3]);
+        assert_eq!(store.load(&changed), None);
+        assert_eq!(store.load(&task), None);
+
+        store.save("Production", &task, vec![7; 16]).unwrap();
+        store.remove(&task.task_id);
+        assert_eq!(store.load(&task), None);
+    }
+
+    #[test]
+    // After a crash, the saved proofs of the nodes still run come back for submission, unless
+    // the journal has them or they were fetched from another environment.
+    fn test_unsubmitted_proofs() {
+        let dir = tempdir().unwrap();
+        let store = ProofCheckpoints::open(dir.path()).unwrap();
+        let task = |task_id: &str, node_id: u64| {
+            let mut task = Task::new(task_id.to_string(), "fast-fib".to_string(), vec![1]);
+            task.node_id = Some(node_id);
+            task
+        };
+        store.save("Production", &task("lost", 1), vec![1]).unwrap();
+        store
+            .save("Production", &task("journaled", 1), vec![2])
+            .unwrap();
+        store
+            .save("Production", &task("other-node", 2), vec![3])
+            .unwrap();
+        store.save("Staging", &task("staging", 1), vec![4]).unwrap();
+
+        let unsubmitted = unsubmitted_in(&store, "Production", &[1], |id| id == "journaled");
+        assert_eq!(unsubmitted.len(), 1);
+        assert_eq!(unsubmitted[0].0.task_id, "lost");
+        assert_eq!(unsubmitted[0].0.node_id, Some(1));
+        assert_eq!(unsubmitted[0].1, vec![1]);
+    }
+
+    #[test]
+    // Only checkpoints older than a task's lifetime are pruned.
+    fn test_prune_expired() {
+        let dir = tempdir().unwrap();
+        let store = ProofCheckpoints::open(dir.path()).unwrap();
+        let task = Task::new("1".to_string(), "fast-fib".to_string(), vec![]);
+        store.save("Production", &task, vec![1]).unwrap();
+
+        assert_eq!(store.prune(SystemTime::now()), 0);
+        assert_eq!(
+            store.prune(SystemTime::now() + TASK_LIFETIME + Duration::from_secs(1)),
+            1
+        );
+        assert_eq!(store.load(&task), None);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::analytics::track_verification_failed;
+use crate::duty_cycle;
 use crate::environment::Environment;
 use crate::task::Task;
 use log::error;
@@ -26,20 +27,39 @@ pub enum ProverError {
 
     #[error("Guest Program error: {0}")]
     GuestProgram(String),
+
+    #[error("Refused to run {0}")]
+    NotAllowlisted(String),
 }
 
 /// Get cached ELF bytes for default program (fib_input)
 fn get_default_elf_bytes() -> &'static [u8] {
-    DEFAULT_ELF_BYTES.get_or_init(|| {
-        include_bytes!("../assets/fib_input")
-    })
+    DEFAULT_ELF_BYTES.get_or_init(|| include_bytes!("../assets/fib_input"))
 }
 
 /// Get cached ELF bytes for initial program (fib_input_initial)  
 fn get_initial_elf_bytes() -> &'static [u8] {
-    INITIAL_ELF_BYTES.get_or_init(|| {
-        include_bytes!("../assets/fib_input_initial")
-    })
+    INITIAL_ELF_BYTES.get_or_init(|| include_bytes!("../assets/fib_input_initial"))
+}
+
+/// The guest binary proving tasks of `program_id`, if it is supported.
+fn elf_bytes(program_id: &str) -> Option<&'static [u8]> {
+    match program_id {
+        "fast-fib" => Some(get_default_elf_bytes()),
+        "fib_input_initial" => Some(get_initial_elf_bytes()),
+        _ => None,
+    }
+}
+
+/// Checks the guest binary of `program_id` against the program allowlist, if it is enforced.
+///
+/// # Errors
+/// Returns why the program may not run. Unsupported programs pass, and fail when proved.
+pub fn check_allowlisted(program_id: &str) -> Result<(), String> {
+    match elf_bytes(program_id) {
+        Some(elf) => crate::program_allowlist::check(program_id, elf),
+        None => Ok(()),
+    }
 }
 
 /// Proves a program locally with hardcoded inputs.
@@ -49,29 +69,24 @@ pub async fn prove_anonymously() -> Result<Proof, ProverError> {
     // This computes F(9) = 55 in the classic Fibonacci sequence starting with 1,1
     // Sequence: F(0)=1, F(1)=1, F(2)=2, F(3)=3, F(4)=5, F(5)=8, F(6)=13, F(7)=21, F(8)=34, F(9)=55
     let public_input: (u32, u32, u32) = (9, 1, 1);
+    check_allowlisted("fib_input_initial").map_err(ProverError::NotAllowlisted)?;
 
-    // Create prover instance (optimized: reuse ELF bytes)
-    let stwo_prover = get_initial_stwo_prover()?;
-    let (view, proof) = stwo_prover
-        .prove_with_input::<(), (u32, u32, u32)>(&(), &public_input)
-        .map_err(|e| {
-            ProverError::Stwo(format!(
-                "Failed to run fib_input_initial prover (anonymous): {}",
-                e
-            ))
-        })?;
-
-    let exit_code = view.exit_code().map_err(|e| {
-        ProverError::GuestProgram(format!("Failed to deserialize exit code: {}", e))
-    })?;
-
-    if exit_code != KnownExitCodes::ExitSuccess as u32 {
-        return Err(ProverError::GuestProgram(format!(
-            "Prover exited with non-zero exit code: {}",
-            exit_code
-        )));
-    }
+    let (proof, exit_code) = duty_cycle::run(move || {
+        // Create prover instance (optimized: reuse ELF bytes)
+        let stwo_prover = get_initial_stwo_prover()?;
+        let (view, proof) = stwo_prover
+            .prove_with_input::<(), (u32, u32, u32)>(&(), &public_input)
+            .map_err(|e| {
+                ProverError::Stwo(format!(
+                    "Failed to run fib_input_initial prover (anonymous): {}",
+                    e
+                ))
+            })?;
+        Ok::<_, ProverError>((proof, read_exit_code(&view)?))
+    })
+    .await?;
 
+    check_exit_code(exit_code)?;
     Ok(proof)
 }
 
@@ -81,77 +96,66 @@ pub async fn authenticated_proving(
     environment: &Environment,
     client_id: &str,
 ) -> Result<Proof, ProverError> {
-    let (view, proof, _) = match task.program_id.as_str() {
+    check_allowlisted(&task.program_id).map_err(ProverError::NotAllowlisted)?;
+    let (proof, exit_code) = match task.program_id.as_str() {
         "fast-fib" => {
             // fast-fib uses string inputs
             let input = get_string_public_input(task)?;
-            let stwo_prover = get_default_stwo_prover()?;
-            let elf = stwo_prover.elf.clone();
-            let (view, proof) = stwo_prover
-                .prove_with_input::<(), u32>(&(), &input)
-                .map_err(|e| ProverError::Stwo(format!("Failed to run fast-fib prover: {}", e)))?;
-            // We should verify the proof before returning it to the server
-            // otherwise, the orchestrator can punish the worker for returning an invalid proof
-            match proof.verify_expected(
-                &input,
-                nexus_sdk::KnownExitCodes::ExitSuccess as u32,
-                &(),
-                &elf,
-                &[],
-            ) {
-                Ok(_) => {
-                    // Track analytics for proof validation success (non-blocking)
-                }
-                Err(e) => {
-                    let error_msg =
-                        format!("Failed to verify proof: {} for inputs: {:?}", e, input);
-                    // Track analytics for verification failure (non-blocking)
-                    tokio::spawn(track_verification_failed(
-                        task.clone(),
-                        error_msg.clone(),
-                        environment.clone(),
-                        client_id.to_string(),
-                    ));
-                    return Err(ProverError::Stwo(error_msg));
-                }
+            let (proof, exit_code, verified) = duty_cycle::run(move || {
+                let stwo_prover = get_default_stwo_prover()?;
+                let elf = stwo_prover.elf.clone();
+                let (view, proof) = stwo_prover
+                    .prove_with_input::<(), u32>(&(), &input)
+                    .map_err(|e| {
+                        ProverError::Stwo(format!("Failed to run fast-fib prover: {}", e))
+                    })?;
+                // We should verify the proof before returning it to the server
+                // otherwise, the orchestrator can punish the worker for returning an invalid proof
+                let verified = proof
+                    .verify_expected(
+                        &input,
+                        nexus_sdk::KnownExitCodes::ExitSuccess as u32,
+                        &(),
+                        &elf,
+                        &[],
+                    )
+                    .map_err(|e| format!("Failed to verify proof: {} for inputs: {:?}", e, input));
+                Ok::<_, ProverError>((proof, read_exit_code(&view)?, verified))
+            })
+            .await?;
+            if let Err(error_msg) = verified {
+                return Err(verification_failed(task, error_msg, environment, client_id));
             }
-            (view, proof, input)
+            (proof, exit_code)
         }
         "fib_input_initial" => {
             let inputs = get_triple_public_input(task)?;
-            let stwo_prover = get_initial_stwo_prover()?;
-            let elf = stwo_prover.elf.clone();
-            let (view, proof) = stwo_prover
-                .prove_with_input::<(), (u32, u32, u32)>(&(), &inputs)
-                .map_err(|e| {
-                    ProverError::Stwo(format!("Failed to run fib_input_initial prover: {}", e))
-                })?;
-            // We should verify the proof before returning it to the server
-            // otherwise, the orchestrator can punish the worker for returning an invalid proof
-            match proof.verify_expected::<(u32, u32, u32), ()>(
-                &inputs, // three u32 inputs
-                nexus_sdk::KnownExitCodes::ExitSuccess as u32,
-                &(),  // no public output
-                &elf, // expected elf (program binary)
-                &[],  // no associated data,
-            ) {
-                Ok(_) => {
-                    // Track analytics for proof validation success (non-blocking)
-                }
-                Err(e) => {
-                    let error_msg =
-                        format!("Failed to verify proof: {} for inputs: {:?}", e, inputs);
-                    // Track analytics for verification failure (non-blocking)
-                    tokio::spawn(track_verification_failed(
-                        task.clone(),
-                        error_msg.clone(),
-                        environment.clone(),
-                        client_id.to_string(),
-                    ));
-                    return Err(ProverError::Stwo(error_msg));
-                }
+            let (proof, exit_code, verified) = duty_cycle::run(move || {
+                let stwo_prover = get_initial_stwo_prover()?;
+                let elf = stwo_prover.elf.clone();
+                let (view, proof) = stwo_prover
+                    .prove_with_input::<(), (u32, u32, u32)>(&(), &inputs)
+                    .map_err(|e| {
+                        ProverError::Stwo(format!("Failed to run fib_input_initial prover: {}", e))
+                    })?;
+                // We should verify the proof before returning it to the server
+                // otherwise, the orchestrator can punish the worker for returning an invalid proof
+                let verified = proof
+                    .verify_expected::<(u32, u32, u32), ()>(
+                        &inputs, // three u32 inputs
+                        nexus_sdk::KnownExitCodes::ExitSuccess as u32,
+                        &(),  // no public output
+                        &elf, // expected elf (program binary)
+                        &[],  // no associated data,
+                    )
+                    .map_err(|e| format!("Failed to verify proof: {} for inputs: {:?}", e, inputs));
+                Ok::<_, ProverError>((proof, read_exit_code(&view)?, verified))
+            })
+            .await?;
+            if let Err(error_msg) = verified {
+                return Err(verification_failed(task, error_msg, environment, client_id));
             }
-            (view, proof, inputs.0)
+            (proof, exit_code)
         }
         _ => {
             return Err(ProverError::MalformedTask(format!(
@@ -161,18 +165,41 @@ pub async fn authenticated_proving(
         }
     };
 
-    let exit_code = view.exit_code().map_err(|e| {
-        ProverError::GuestProgram(format!("Failed to deserialize exit code: {}", e))
-    })?;
+    check_exit_code(exit_code)?;
+    Ok(proof)
+}
 
+/// Reads the guest program's exit code from its view.
+fn read_exit_code(view: &impl Viewable) -> Result<u32, ProverError> {
+    view.exit_code()
+        .map_err(|e| ProverError::GuestProgram(format!("Failed to deserialize exit code: {}", e)))
+}
+
+fn check_exit_code(exit_code: u32) -> Result<(), ProverError> {
     if exit_code != KnownExitCodes::ExitSuccess as u32 {
         return Err(ProverError::GuestProgram(format!(
             "Prover exited with non-zero exit code: {}",
             exit_code
         )));
     }
+    Ok(())
+}
 
-    Ok(proof)
+/// Tracks a proof that failed verification and returns the error to report.
+fn verification_failed(
+    task: &Task,
+    error_msg: String,
+    environment: &Environment,
+    client_id: &str,
+) -> ProverError {
+    // Track analytics for verification failure (non-blocking)
+    tokio::spawn(track_verification_failed(
+        task.clone(),
+        error_msg.clone(),
+        environment.clone(),
+        client_id.to_string(),
+    ));
+    ProverError::Stwo(error_msg)
 }
 
 fn get_string_public_input(task: &Task) -> Result<u32, ProverError> {
@@ -3,16 +3,32 @@
 //! Main orchestrator for authenticated and anonymous proving modes.
 //! Coordinates online workers (network I/O) and offline workers (computation).
 
+use crate::backpressure;
+use crate::checkpoint;
 use crate::consts::prover::{EVENT_QUEUE_SIZE, RESULT_QUEUE_SIZE, TASK_QUEUE_SIZE};
+use crate::control::control_state;
 use crate::environment::Environment;
-use crate::events::Event;
-use crate::orchestrator::OrchestratorClient;
+use crate::error_budget::{ErrorBudget, ErrorBudgetConfig};
+use crate::events::{Event, Worker};
+use crate::orchestrator::{Orchestrator, OrchestratorClient};
+use crate::polling::PollingConfig;
+use crate::profiles::{ProfileSchedule, start_profile_switcher};
+use crate::proof_checkpoint;
+use crate::proxy::ProxyHealthChecker;
+use crate::proxy_reputation;
+use crate::sleep_wake;
+use crate::submission_journal::{SubmissionJournal, journal_dir};
+use crate::submission_verifier::VerificationConfig;
 use crate::task::Task;
 use crate::task_cache::TaskCache;
+use crate::task_filter::TaskFilter;
+use crate::task_lifecycle::{self, TaskState};
 use crate::version_checker::start_version_checker_task;
-use crate::workers::{offline, online};
+use crate::workers::ipc::ProverLink;
+use crate::workers::{file_queue, ipc, offline, online};
 use ed25519_dalek::SigningKey;
-use nexus_sdk::stwo::seq::Proof;
+use std::collections::HashMap;
+use std::sync::Arc;
 use tokio::sync::{broadcast, mpsc};
 use tokio::task::JoinHandle;
 
@@ -20,6 +36,7 @@ use tokio::task::JoinHandle;
 const MAX_COMPLETED_TASKS: usize = 500;
 
 /// Starts authenticated workers that fetch tasks from the orchestrator and process them.
+#[allow(clippy::too_many_arguments)]
 pub async fn start_authenticated_workers(
     node_id: u64,
     signing_key: SigningKey,
@@ -28,6 +45,12 @@ pub async fn start_authenticated_workers(
     shutdown: broadcast::Receiver<()>,
     environment: Environment,
     client_id: String,
+    task_filter: TaskFilter,
+    polling: PollingConfig,
+    error_budget: Option<ErrorBudgetConfig>,
+    verification: Option<VerificationConfig>,
+    profiles: ProfileSchedule,
+    prover_link: Option<ProverLink>,
 ) -> (mpsc::Receiver<Event>, Vec<JoinHandle<()>>) {
     start_authenticated_workers_multi(
         vec![node_id],
@@ -37,11 +60,21 @@ pub async fn start_authenticated_workers(
         shutdown,
         environment,
         client_id,
+        task_filter,
+        polling,
+        error_budget,
+        verification,
+        profiles,
+        prover_link,
     )
     .await
 }
 
 /// Starts authenticated workers for multiple node IDs that fetch tasks from the orchestrator and process them.
+///
+/// With a `prover_link`, tasks are proved by separate prover processes, connecting over TCP or
+/// sharing a queue directory, instead of by local workers.
+#[allow(clippy::too_many_arguments)]
 pub async fn start_authenticated_workers_multi(
     node_ids: Vec<u64>,
     signing_key: SigningKey,
@@ -50,6 +83,12 @@ pub async fn start_authenticated_workers_multi(
     shutdown: broadcast::Receiver<()>,
     environment: Environment,
     client_id: String,
+    task_filter: TaskFilter,
+    polling: PollingConfig,
+    error_budget: Option<ErrorBudgetConfig>,
+    verification: Option<VerificationConfig>,
+    profiles: ProfileSchedule,
+    prover_link: Option<ProverLink>,
 ) -> (mpsc::Receiver<Event>, Vec<JoinHandle<()>>) {
     let mut join_handles = Vec::new();
     // Worker events - single channel for all node IDs
@@ -66,34 +105,159 @@ pub async fn start_authenticated_workers_multi(
     };
     join_handles.push(version_checker_handle);
 
+    if !profiles.is_empty() {
+        join_handles.push(start_profile_switcher(
+            profiles,
+            event_sender.clone(),
+            shutdown.resubscribe(),
+        ));
+    }
+
+    // Reconnect and re-check proxies and the clock after the system sleeps
+    {
+        let client = orchestrator.clone();
+        join_handles.push(sleep_wake::spawn_watchdog(
+            move || {
+                let client = client.clone();
+                async move { client.server_time().await }
+            },
+            orchestrator.proxies().clone(),
+            event_sender.clone(),
+            shutdown.resubscribe(),
+        ));
+    }
+
+    // Keep dead proxies out of the rotation until they recover
+    let proxies = orchestrator.proxies().clone();
+    if let Some(interval) = proxies
+        .health_check_interval()
+        .filter(|_| proxies.should_use())
+    {
+        let client = orchestrator.clone();
+        join_handles.push(ProxyHealthChecker::new(interval).spawn(
+            proxies.clone(),
+            move |proxy| {
+                let client = client.clone();
+                async move { client.check_proxy(proxy).await }
+            },
+            event_sender.clone(),
+            shutdown.resubscribe(),
+        ));
+    }
+
+    // Pick up edits to the proxy file as soon as they are saved
+    if proxies.should_use() {
+        match proxies
+            .clone()
+            .spawn_file_watcher(event_sender.clone(), shutdown.resubscribe())
+        {
+            Ok(handle) => join_handles.push(handle),
+            Err(e) => {
+                let _ = event_sender
+                    .send(Event::task_fetcher_with_level(
+                        format!(
+                            "Cannot watch the proxy file ({}); it will be re-read every few minutes",
+                            e
+                        ),
+                        crate::events::EventType::Refresh,
+                        crate::error_classifier::LogLevel::Warn,
+                    ))
+                    .await;
+            }
+        }
+    }
+
+    // Keep proxies exiting from blocklisted networks out of the rotation
+    let settings = proxies.settings();
+    if let Some((blocklist, url)) = settings
+        .blocklist
+        .clone()
+        .zip(settings.exit_lookup_url.clone())
+        .filter(|_| proxies.should_use())
+    {
+        let client = orchestrator.clone();
+        join_handles.push(proxy_reputation::spawn_checker(
+            blocklist,
+            proxies.clone(),
+            move |proxy| {
+                let client = client.clone();
+                let url = url.clone();
+                async move { client.lookup_exit(proxy, &url).await }
+            },
+            event_sender.clone(),
+            shutdown.resubscribe(),
+        ));
+    }
+
     // Single task queue shared across all node IDs
     let (task_sender, task_receiver) = mpsc::channel::<Task>(TASK_QUEUE_SIZE);
-    
+
+    // Tasks left unproved at the last shutdown are proved before any new ones
+    let resumed = checkpoint::restore(&environment, &node_ids, std::time::SystemTime::now());
+    resume_checkpointed_tasks(&resumed, &task_sender, &event_sender).await;
+
+    // When running several nodes, give each its own HTTP client so the orchestrator treats
+    // them as distinct clients (connection pool, cookies and proxy are not shared). A node
+    // pinned in `node_proxies.toml` gets one even alone, so its requests use its own proxies.
+    let isolate_nodes = node_ids.len() > 1;
+    let mut node_orchestrators: HashMap<u64, Box<dyn Orchestrator>> = HashMap::new();
+
+    // Failures across all node IDs draw from one budget, since they share the same machine
+    let error_budget = Arc::new(ErrorBudget::new(error_budget).paced());
+
+    // Fetchers take on no more tasks than the shared provers can complete; prover processes
+    // add their own workers as they connect
+    let local_workers = if prover_link.is_some() {
+        0
+    } else {
+        num_workers
+    };
+    backpressure::set_workers(local_workers);
+
     // Create task fetchers for each node ID
     for node_id in &node_ids {
+        let node_orchestrator = if isolate_nodes || proxies.is_mapped(*node_id) {
+            let node_orchestrator = orchestrator.for_node(*node_id);
+            node_orchestrators.insert(*node_id, Box::new(node_orchestrator.clone()));
+            node_orchestrator
+        } else {
+            orchestrator.clone()
+        };
+
         // A bounded list of recently fetched task IDs (prevents refetching currently processing tasks)
         let enqueued_tasks = TaskCache::new(MAX_COMPLETED_TASKS);
-        
+        for task in resumed.iter().filter(|task| task.node_id == Some(*node_id)) {
+            enqueued_tasks.insert(task.task_id.clone()).await;
+        }
+
         let verifying_key = signing_key.verifying_key();
         let fetch_prover_tasks_handle = {
-            let orchestrator = orchestrator.clone();
+            let orchestrator = node_orchestrator;
             let event_sender = event_sender.clone();
             let task_sender = task_sender.clone();
             let shutdown = shutdown.resubscribe(); // Clone the receiver for task fetching
             let node_id = *node_id;
             let environment = environment.clone();
             let client_id = client_id.clone();
+            let task_filter = task_filter.clone();
+            let polling = polling.clone();
+            let error_budget = error_budget.clone();
+            let proxies = proxies.clone();
             tokio::spawn(async move {
                 online::fetch_prover_tasks(
                     node_id,
                     verifying_key,
                     Box::new(orchestrator),
+                    proxies,
                     task_sender,
                     event_sender,
                     shutdown,
                     enqueued_tasks,
                     environment,
                     client_id,
+                    task_filter,
+                    polling,
+                    error_budget,
                 )
                 .await;
             })
@@ -102,50 +266,207 @@ pub async fn start_authenticated_workers_multi(
     }
 
     // Workers - shared pool for all node IDs
-    let (result_sender, result_receiver) = mpsc::channel::<(Task, Proof)>(RESULT_QUEUE_SIZE);
+    let (result_sender, result_receiver) = mpsc::channel::<(Task, Vec<u8>)>(RESULT_QUEUE_SIZE);
+    let saved_proof_sender = result_sender.clone();
 
-    let (worker_senders, worker_handles) = offline::start_workers(
-        num_workers,
-        result_sender,
-        event_sender.clone(),
-        shutdown.resubscribe(),
-        environment.clone(),
-        client_id.clone(),
-    );
-    join_handles.extend(worker_handles);
+    match prover_link {
+        Some(ProverLink::Tcp(addr)) => {
+            // Proving is delegated to prover processes
+            match ipc::serve_provers(
+                addr,
+                task_receiver,
+                task_sender.clone(),
+                result_sender,
+                event_sender.clone(),
+                error_budget.clone(),
+                shutdown.resubscribe(),
+            )
+            .await
+            {
+                Ok(handle) => join_handles.push(handle),
+                Err(e) => {
+                    let _ = event_sender
+                        .send(Event::task_fetcher_with_level(
+                            format!("Cannot accept prover processes on {}: {}", addr, e),
+                            crate::events::EventType::Error,
+                            crate::error_classifier::LogLevel::Error,
+                        ))
+                        .await;
+                }
+            }
+        }
+        Some(ProverLink::Queue(dir)) => {
+            // Proving is delegated to provers sharing the queue directory
+            match file_queue::serve_queue(
+                &dir,
+                task_receiver,
+                result_sender,
+                event_sender.clone(),
+                error_budget.clone(),
+                shutdown.resubscribe(),
+            ) {
+                Ok(handle) => join_handles.push(handle),
+                Err(e) => {
+                    let _ = event_sender
+                        .send(Event::task_fetcher_with_level(
+                            format!("Cannot use queue directory {}: {}", dir.display(), e),
+                            crate::events::EventType::Error,
+                            crate::error_classifier::LogLevel::Error,
+                        ))
+                        .await;
+                }
+            }
+        }
+        None => {
+            let (worker_senders, worker_handles) = offline::start_workers(
+                num_workers,
+                result_sender,
+                event_sender.clone(),
+                shutdown.resubscribe(),
+                environment.clone(),
+                client_id.clone(),
+                error_budget.clone(),
+            );
+            join_handles.extend(worker_handles);
 
-    // Dispatch tasks to workers
-    let dispatcher_handle =
-        offline::start_dispatcher(task_receiver, worker_senders, shutdown.resubscribe());
-    join_handles.push(dispatcher_handle);
+            // Dispatch tasks to workers
+            let dispatcher_handle =
+                offline::start_dispatcher(task_receiver, worker_senders, shutdown.resubscribe());
+            join_handles.push(dispatcher_handle);
+        }
+    }
 
     // A bounded list of recently completed task IDs (prevents duplicate proof submissions)
     let successful_tasks = TaskCache::new(MAX_COMPLETED_TASKS);
 
+    // Write-ahead journal of submissions, kept next to the config file
+    let journal = match crate::config::get_config_path()
+        .and_then(|path| SubmissionJournal::open(&journal_dir(&path, &environment)))
+    {
+        Ok(journal) => journal,
+        Err(e) => {
+            let _ = event_sender
+                .send(Event::proof_submitter_with_level(
+                    format!(
+                        "Submission journal unavailable, interrupted submissions will not be recovered: {}",
+                        e
+                    ),
+                    crate::events::EventType::Error,
+                    crate::error_classifier::LogLevel::Warn,
+                ))
+                .await;
+            SubmissionJournal::in_memory()
+        }
+    };
+    for task_id in journal.committed_task_ids() {
+        successful_tasks.insert(task_id.clone()).await;
+    }
+    let saved_proofs =
+        proof_checkpoint::unsubmitted(&node_ids, |task_id| journal.is_journaled(task_id));
+
     // Send proofs to the orchestrator
     let submit_proofs_handle = online::submit_proofs(
         signing_key,
         Box::new(orchestrator),
+        node_orchestrators,
         num_workers,
         result_receiver,
         event_sender.clone(),
         shutdown.resubscribe(),
         successful_tasks.clone(),
+        journal,
+        error_budget,
+        verification,
         environment,
         client_id,
     )
     .await;
     join_handles.push(submit_proofs_handle);
+    submit_saved_proofs(saved_proofs, &saved_proof_sender, &event_sender).await;
 
     (event_receiver, join_handles)
 }
 
+/// Hands the proofs an earlier run finished but never submitted to the proof submitter.
+async fn submit_saved_proofs(
+    proofs: Vec<(Task, Vec<u8>)>,
+    result_sender: &mpsc::Sender<(Task, Vec<u8>)>,
+    event_sender: &mpsc::Sender<Event>,
+) {
+    let mut submitted = 0;
+    for (task, proof) in proofs {
+        for state in [TaskState::Fetched, TaskState::Proving, TaskState::Proved] {
+            task_lifecycle::advance(&task.task_id, state, Worker::TaskFetcher, event_sender).await;
+        }
+        control_state().task_started();
+        if result_sender.send((task, proof)).await.is_err() {
+            break;
+        }
+        submitted += 1;
+    }
+    if submitted > 0 {
+        let _ = event_sender
+            .send(Event::task_fetcher(
+                format!(
+                    "Submitting {} proofs finished before the last exit",
+                    submitted
+                ),
+                crate::events::EventType::Success,
+            ))
+            .await;
+    }
+}
+
+/// Queues the tasks checkpointed at the last shutdown, as many as the task queue holds.
+async fn resume_checkpointed_tasks(
+    tasks: &[Task],
+    task_sender: &mpsc::Sender<Task>,
+    event_sender: &mpsc::Sender<Event>,
+) {
+    let mut resumed = 0;
+    for task in tasks {
+        task_lifecycle::advance(
+            &task.task_id,
+            TaskState::Fetched,
+            Worker::TaskFetcher,
+            event_sender,
+        )
+        .await;
+        checkpoint::track(task);
+        if task_sender.try_send(task.clone()).is_err() {
+            checkpoint::untrack(&task.task_id);
+            task_lifecycle::advance(
+                &task.task_id,
+                TaskState::Abandoned,
+                Worker::TaskFetcher,
+                event_sender,
+            )
+            .await;
+            continue;
+        }
+        control_state().task_started();
+        resumed += 1;
+    }
+    if resumed > 0 {
+        let _ = event_sender
+            .send(Event::task_fetcher(
+                format!(
+                    "Resuming {} tasks checkpointed at the last shutdown",
+                    resumed
+                ),
+                crate::events::EventType::Success,
+            ))
+            .await;
+    }
+}
+
 /// Starts anonymous workers that repeatedly prove a program with hardcoded inputs.
 pub async fn start_anonymous_workers(
     num_workers: usize,
     shutdown: broadcast::Receiver<()>,
     environment: Environment,
     client_id: String,
+    profiles: ProfileSchedule,
 ) -> (mpsc::Receiver<Event>, Vec<JoinHandle<()>>) {
     let mut join_handles = Vec::new();
     // Worker events
@@ -162,6 +483,14 @@ pub async fn start_anonymous_workers(
     };
     join_handles.push(version_checker_handle);
 
+    if !profiles.is_empty() {
+        join_handles.push(start_profile_switcher(
+            profiles,
+            event_sender.clone(),
+            shutdown.resubscribe(),
+        ));
+    }
+
     // Start anonymous workers
     let (anonymous_event_receiver, anonymous_handles) =
         offline::start_anonymous_workers(num_workers, shutdown, environment, client_id).await;
@@ -183,10 +512,14 @@ pub async fn start_anonymous_workers(
 
 #[cfg(test)]
 mod tests {
+    use crate::error_budget::ErrorBudget;
     use crate::orchestrator::MockOrchestrator;
+    use crate::polling::PollingConfig;
     use crate::prover_runtime::{Event, MAX_COMPLETED_TASKS, online::fetch_prover_tasks};
     use crate::task::Task;
     use crate::task_cache::TaskCache;
+    use crate::task_filter::TaskFilter;
+    use std::sync::Arc;
     use std::time::Duration;
     use tokio::sync::{broadcast, mpsc};
 
@@ -225,12 +558,16 @@ mod tests {
                 node_id,
                 verifying_key,
                 orchestrator_client,
+                Arc::default(),
                 task_sender,
                 event_sender,
                 shutdown_receiver,
                 successful_tasks,
                 crate::environment::Environment::Production,
                 "test-client-id".to_string(),
+                TaskFilter::default(),
+                PollingConfig::default(),
+                Arc::new(ErrorBudget::new(None)),
             )
             .await;
         });
@@ -1,130 +1,1179 @@
 //! Proxy Management
 //!
 //! Handles loading and selecting random proxies from proxies.txt file
+//!
+//! A `ProxyContext` holds whether proxies are enabled, the proxy file and the pool loaded from
+//! it. The node creates one from its flags and hands it to every subsystem that uses proxies,
+//! so several contexts can coexist in one process.
+//!
+//! Proxies that fail at the connection level are benched for a while. What happens when every
+//! proxy is benched (or none could be loaded) is set by `--on-no-proxy`. A request whose proxy
+//! fails (a connection error or timeout, or a 407, 502 or 503 response) is retried through
+//! another proxy, up to `--proxy-retries` times, before the error reaches the workers.
+//!
+//! A `ProxyHealthChecker` also probes every proxy every few minutes (`--proxy-check-interval`)
+//! with a `HEAD` of the orchestrator's root, which does no API work, tracking latency and
+//! failures per proxy. A proxy that fails consecutive checks stays out of the rotation until
+//! a check succeeds again, however long that takes.
+//!
+//! Each line may end with request limits for its proxy, e.g.
+//! `host:port:user:pass:max_concurrent=4:max_rps=2:max_rpm=60`. Requests wait for the proxy
+//! to be below its limits, and the rotation prefers proxies that are.
+//!
+//! In multi-node setups, `node_proxies.toml` next to the proxy file pins nodes to their own
+//! proxies (see `node_proxies`).
+//!
+//! A line may take its credentials from an environment variable or from `proxy_secrets.toml`
+//! instead of holding them, e.g. `host:port:ENV:PROXY_USER_1` (see `proxy_secrets`).
+//!
+//! While proving, the proxy file is watched and reloaded as soon as it is saved (`nexus-network
+//! reload-proxies` forces a reload). A file that fails to load keeps the current proxies.
+//! Reloads read and parse the file before taking the pool's lock, so requests are not held up
+//! meanwhile, and files of thousands of lines are parsed by a bounded pool of threads.
 
+use crate::error_classifier::LogLevel;
+use crate::events::{Event, EventType};
+use crate::node_proxies::{NodeProxies, node_proxies_path};
+use crate::proxy_reputation::{Blocklist, Reputation};
+use crate::proxy_secrets::{CredentialSource, ProxySecrets, proxy_secrets_path};
+use crate::proxy_stats::{ProxyStats, RequestOutcome};
+use notify::{RecursiveMode, Watcher};
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
 use reqwest::Proxy;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt::Display;
 use std::fs;
-use std::path::Path;
-use std::sync::OnceLock;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
+use tokio::sync::{Semaphore, broadcast, mpsc};
+use tokio::task::{JoinHandle, JoinSet};
+
+/// Protocol spoken to a proxy
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProxyScheme {
+    #[default]
+    Http,
+    Https,
+    /// SOCKS5, resolving hostnames locally
+    Socks5,
+    /// SOCKS5, letting the proxy resolve hostnames
+    Socks5h,
+}
+
+impl ProxyScheme {
+    fn parse(scheme: &str) -> Result<Self, &'static str> {
+        match scheme.to_ascii_lowercase().as_str() {
+            "http" => Ok(ProxyScheme::Http),
+            "https" => Ok(ProxyScheme::Https),
+            "socks5" => Ok(ProxyScheme::Socks5),
+            "socks5h" => Ok(ProxyScheme::Socks5h),
+            _ => Err("Unsupported proxy scheme, expected http, https, socks5 or socks5h"),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ProxyScheme::Http => "http",
+            ProxyScheme::Https => "https",
+            ProxyScheme::Socks5 => "socks5",
+            ProxyScheme::Socks5h => "socks5h",
+        }
+    }
+}
+
+/// Request limits of one proxy, from `key=value` options after its credentials
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProxyLimits {
+    /// Most requests through the proxy at the same time
+    pub max_concurrent: Option<u32>,
+    /// Most requests started per second
+    pub max_rps: Option<u32>,
+    /// Most requests started per minute
+    pub max_rpm: Option<u32>,
+}
+
+impl ProxyLimits {
+    fn parse_option(&mut self, option: &str) -> Result<(), &'static str> {
+        let (key, value) = option
+            .split_once('=')
+            .ok_or("Invalid proxy option, expected key=value")?;
+        let value = value
+            .parse::<u32>()
+            .ok()
+            .filter(|value| *value > 0)
+            .ok_or("Invalid proxy limit, expected a positive number")?;
+        let limit = match key {
+            "max_concurrent" => &mut self.max_concurrent,
+            "max_rps" => &mut self.max_rps,
+            "max_rpm" => &mut self.max_rpm,
+            _ => return Err("Unknown proxy option, expected max_concurrent, max_rps or max_rpm"),
+        };
+        *limit = Some(value);
+        Ok(())
+    }
+
+    fn is_unlimited(&self) -> bool {
+        *self == Self::default()
+    }
+}
 
 /// Proxy configuration structure
 #[derive(Debug, Clone)]
 pub struct ProxyConfig {
+    pub scheme: ProxyScheme,
     pub host: String,
     pub port: u16,
     pub username: String,
     pub password: String,
+    pub limits: ProxyLimits,
+    /// Where the username and password came from
+    pub credentials: CredentialSource,
 }
 
 impl ProxyConfig {
-    /// Create a new proxy config from string format: host:port:username:password
+    /// Create a new proxy config from string format:
+    /// [scheme://]host:port:username:password[:limit=value...]
     pub fn from_string(proxy_str: &str) -> Result<Self, String> {
-        let parts: Vec<&str> = proxy_str.trim().split(':').collect();
-        if parts.len() != 4 {
-            return Err(format!("Invalid proxy format: {}", proxy_str));
+        Self::parse(proxy_str).map_err(|reason| format!("{}: {}", reason, proxy_str))
+    }
+
+    /// Parses a proxy line, or says what is wrong with it (without repeating the line, which
+    /// holds credentials). Lines without a scheme are HTTP proxies.
+    fn parse(proxy_str: &str) -> Result<Self, &'static str> {
+        Self::parse_with(proxy_str, &ProxySecrets::default())
+    }
+
+    /// Parses a proxy line, resolving a credential reference (`ENV:NAME` or `SECRET:NAME` in
+    /// place of the username and password) with `secrets`.
+    fn parse_with(proxy_str: &str, secrets: &ProxySecrets) -> Result<Self, &'static str> {
+        let proxy_str = proxy_str.trim();
+        let (scheme, rest) = match proxy_str.split_once("://") {
+            Some((scheme, rest)) => (ProxyScheme::parse(scheme)?, rest),
+            None => (ProxyScheme::Http, proxy_str),
+        };
+        let parts: Vec<&str> = rest.split(':').collect();
+        if parts.len() < 4 {
+            return Err("Invalid proxy format, expected host:port:username:password");
+        }
+        let mut limits = ProxyLimits::default();
+        for option in &parts[4..] {
+            limits.parse_option(option)?;
         }
 
         let port = parts[1]
             .parse::<u16>()
-            .map_err(|_| format!("Invalid port in proxy: {}", proxy_str))?;
+            .map_err(|_| "Invalid port in proxy")?;
+        let (username, password, credentials) = match secrets.resolve(parts[2], parts[3]) {
+            Some(resolved) => resolved?,
+            None => (
+                parts[2].to_string(),
+                parts[3].to_string(),
+                CredentialSource::Inline,
+            ),
+        };
 
         Ok(ProxyConfig {
+            scheme,
             host: parts[0].to_string(),
             port,
-            username: parts[2].to_string(),
-            password: parts[3].to_string(),
+            username,
+            password,
+            limits,
+            credentials,
         })
     }
 
     /// Convert to reqwest::Proxy
     pub fn to_reqwest_proxy(&self) -> Result<Proxy, reqwest::Error> {
-        let proxy_url = format!("http://{}:{}", self.host, self.port);
-        let proxy = Proxy::http(&proxy_url)?;
-        Ok(proxy.basic_auth(&self.username, &self.password))
+        let proxy_url = format!("{}://{}:{}", self.scheme.as_str(), self.host, self.port);
+        match self.scheme {
+            ProxyScheme::Http => {
+                let proxy = Proxy::http(&proxy_url)?;
+                Ok(proxy.basic_auth(&self.username, &self.password))
+            }
+            ProxyScheme::Https => {
+                let proxy = Proxy::all(&proxy_url)?;
+                Ok(proxy.basic_auth(&self.username, &self.password))
+            }
+            // SOCKS credentials are only read from the URL
+            ProxyScheme::Socks5 | ProxyScheme::Socks5h => {
+                let Ok(mut url) = reqwest::Url::parse(&proxy_url) else {
+                    // Let reqwest report the invalid URL
+                    return Proxy::all(&proxy_url);
+                };
+                let _ = url.set_username(&self.username);
+                let _ = url.set_password(Some(&self.password));
+                Proxy::all(url)
+            }
+        }
     }
 
     /// Get proxy as URL string for logging (without credentials)
     pub fn to_display_string(&self) -> String {
-        format!("{}:{}", self.host, self.port)
+        match self.scheme {
+            ProxyScheme::Http => format!("{}:{}", self.host, self.port),
+            scheme => format!("{}://{}:{}", scheme.as_str(), self.host, self.port),
+        }
+    }
+
+    /// Which proxy line this is, for a task to go back through the same one: the display
+    /// string, plus a digest of the username if there is one. Lines of a rotating gateway share
+    /// host and port and differ only by username, which is not shown.
+    pub fn identity(&self) -> String {
+        if self.username.is_empty() {
+            return self.to_display_string();
+        }
+        let digest = Sha256::digest(self.username.as_bytes());
+        let short: String = digest[..4].iter().map(|b| format!("{:02x}", b)).collect();
+        format!("{}#{}", self.to_display_string(), short)
+    }
+}
+
+/// Skipped lines of the proxy file listed individually in a parse report
+const MAX_PARSE_EXAMPLES: usize = 5;
+
+/// What a load of the proxy file found, reported once per load rather than line by line
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProxyParseReport {
+    pub path: String,
+    pub loaded: usize,
+    pub skipped: usize,
+    /// Skipped lines by reason
+    pub reasons: BTreeMap<&'static str, usize>,
+    /// Line numbers and reasons of the first skipped lines
+    pub examples: Vec<(usize, &'static str)>,
+}
+
+impl ProxyParseReport {
+    fn skip(&mut self, line_num: usize, reason: &'static str) {
+        self.skipped += 1;
+        *self.reasons.entry(reason).or_default() += 1;
+        if self.examples.len() < MAX_PARSE_EXAMPLES {
+            self.examples.push((line_num, reason));
+        }
+    }
+
+    /// A one-line summary, e.g. for an event
+    pub fn summary(&self) -> String {
+        let mut summary = format!("Loaded {} proxies from {}", self.loaded, self.path);
+        if self.skipped == 0 {
+            return summary;
+        }
+        let reasons: Vec<String> = self
+            .reasons
+            .iter()
+            .map(|(reason, count)| format!("{} x {}", count, reason))
+            .collect();
+        let examples: Vec<String> = self
+            .examples
+            .iter()
+            .map(|(line_num, _)| line_num.to_string())
+            .collect();
+        summary.push_str(&format!(
+            "; skipped {} invalid lines ({}), first on lines {}",
+            self.skipped,
+            reasons.join(", "),
+            examples.join(", ")
+        ));
+        summary
+    }
+}
+
+/// The proxies read from the proxy file, before they replace the pool
+pub struct LoadedProxies {
+    proxies: Vec<ProxyConfig>,
+    report: ProxyParseReport,
+    mapping: NodeProxies,
+}
+
+impl LoadedProxies {
+    /// The valid proxies, in file order
+    pub fn proxies(&self) -> &[ProxyConfig] {
+        &self.proxies
+    }
+
+    /// What the load found
+    pub fn report(&self) -> &ProxyParseReport {
+        &self.report
+    }
+}
+
+/// Parses the lines of a proxy file, in file order, resolving credential references with
+/// `secrets`. Large files are split into chunks parsed by up to `MAX_PARSE_THREADS` threads;
+/// `progress` is called with the lines parsed so far and the total as chunks complete.
+fn parse_lines(
+    content: &str,
+    secrets: &ProxySecrets,
+    progress: &(dyn Fn(usize, usize) + Sync),
+) -> Vec<(usize, Result<ProxyConfig, &'static str>)> {
+    let lines: Vec<(usize, &str)> = content
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+    let parse_chunk = |chunk: &[(usize, &str)]| -> Vec<_> {
+        chunk
+            .iter()
+            .map(|&(line_num, line)| (line_num, ProxyConfig::parse_with(line, secrets)))
+            .collect()
+    };
+    if lines.len() < PARALLEL_PARSE_THRESHOLD {
+        let parsed = parse_chunk(&lines);
+        progress(lines.len(), lines.len());
+        return parsed;
+    }
+
+    let chunks: Vec<&[(usize, &str)]> = lines.chunks(PARSE_CHUNK_LINES).collect();
+    let threads = std::thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .clamp(1, MAX_PARSE_THREADS)
+        .min(chunks.len());
+    let next_chunk = AtomicUsize::new(0);
+    let parsed_lines = AtomicUsize::new(0);
+    let mut parsed: Vec<(usize, Vec<_>)> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let index = next_chunk.fetch_add(1, Ordering::Relaxed);
+                        let Some(chunk) = chunks.get(index) else {
+                            return done;
+                        };
+                        done.push((index, parse_chunk(chunk)));
+                        let so_far =
+                            parsed_lines.fetch_add(chunk.len(), Ordering::Relaxed) + chunk.len();
+                        progress(so_far, lines.len());
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap_or_default())
+            .collect()
+    });
+    parsed.sort_by_key(|(index, _)| *index);
+    parsed.into_iter().flat_map(|(_, chunk)| chunk).collect()
+}
+
+/// Reads and parses the proxy file at `path`, with the secrets file and node mapping next to it,
+/// without touching any pool, reporting parsing progress to `progress` (see `parse_lines`).
+pub fn read_proxy_file(
+    path: &str,
+    progress: &(dyn Fn(usize, usize) + Sync),
+) -> Result<LoadedProxies, String> {
+    let proxy_file = Path::new(path);
+    if !proxy_file.exists() {
+        return Err(format!("{} file not found", path));
+    }
+    let content =
+        fs::read_to_string(proxy_file).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let secrets_path = proxy_secrets_path(proxy_file);
+    let secrets = ProxySecrets::load(&secrets_path)
+        .map_err(|e| format!("Failed to read {}: {}", secrets_path.display(), e))?;
+
+    let mut proxies = Vec::new();
+    let mut report = ProxyParseReport {
+        path: path.to_string(),
+        ..ProxyParseReport::default()
+    };
+    for (line_num, parsed) in parse_lines(&content, &secrets, progress) {
+        match parsed {
+            Ok(proxy) => proxies.push(proxy),
+            Err(reason) => report.skip(line_num, reason),
+        }
+    }
+    report.loaded = proxies.len();
+
+    let mapping_path = node_proxies_path(proxy_file);
+    let mapping = NodeProxies::load(&mapping_path)
+        .map_err(|e| format!("Failed to read {}: {}", mapping_path.display(), e))?;
+    Ok(LoadedProxies {
+        proxies,
+        report,
+        mapping,
+    })
+}
+
+/// How often the proxy file is re-read when it cannot be watched for changes
+const FALLBACK_RELOAD_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Time to let a change to the proxy file settle before reloading it, since editors often
+/// save in several writes
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// Times a request is retried through another proxy unless `--proxy-retries` says otherwise
+pub const DEFAULT_PROXY_RETRIES: u32 = 2;
+
+/// How long a failed proxy is skipped before it is tried again
+const BLACKLIST_DURATION: Duration = Duration::from_secs(300);
+
+/// Default time between health checks of the proxy pool. Failed requests already bench a
+/// proxy, so the checks only need to catch proxies that die while idle, and bring benched
+/// ones back; every check opens a connection through every proxy, which metered proxies
+/// bill for.
+pub const DEFAULT_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Consecutive failed health checks after which a proxy leaves the rotation
+const FAILED_CHECKS_BEFORE_REMOVAL: u32 = 2;
+
+/// Weight of the latest check in a proxy's latency average
+const LATENCY_SMOOTHING: f64 = 0.3;
+
+/// How often a request waiting for a proxy's concurrency limit checks again
+const CONCURRENCY_RETRY: Duration = Duration::from_millis(100);
+
+/// Most proxies probed at the same time
+pub const MAX_CONCURRENT_CHECKS: usize = 32;
+
+/// Lines of the proxy file from which parsing is split across threads
+const PARALLEL_PARSE_THRESHOLD: usize = 1000;
+
+/// Lines parsed by a thread at a time, between progress reports
+const PARSE_CHUNK_LINES: usize = 256;
+
+/// Most threads parsing the proxy file at the same time
+const MAX_PARSE_THREADS: usize = 8;
+
+/// Health of one proxy, as seen by the health checker
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProxyHealth {
+    /// Moving average of the latency of successful checks
+    pub latency: Option<Duration>,
+    /// Failed checks since the last successful one
+    pub consecutive_failures: u32,
+    pub checks: u64,
+    pub failures: u64,
+}
+
+impl ProxyHealth {
+    /// Whether the proxy is out of the rotation until a check succeeds
+    pub fn is_dead(&self) -> bool {
+        self.consecutive_failures >= FAILED_CHECKS_BEFORE_REMOVAL
+    }
+}
+
+/// A change in whether a proxy is in the rotation, after a health check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HealthChange {
+    Removed,
+    Recovered,
+}
+
+/// What to do when proxies are in use but none is currently usable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum NoProxyPolicy {
+    /// Fail the request, leaving it to the usual retry and backoff logic.
+    Fail,
+    /// Connect directly, exposing this machine's address to the orchestrator.
+    #[default]
+    Direct,
+    /// Hold requests until a blacklisted proxy recovers.
+    Wait,
+}
+
+impl Display for NoProxyPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NoProxyPolicy::Fail => write!(f, "fail"),
+            NoProxyPolicy::Direct => write!(f, "direct"),
+            NoProxyPolicy::Wait => write!(f, "wait"),
+        }
+    }
+}
+
+/// How proxies are assigned to requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ProxyAssignment {
+    /// A random proxy for each request (or each node's client).
+    #[default]
+    Random,
+    /// The same proxy for a node for the whole run, failing over only after repeated errors.
+    Sticky,
+}
+
+impl Display for ProxyAssignment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProxyAssignment::Random => write!(f, "random"),
+            ProxyAssignment::Sticky => write!(f, "sticky"),
+        }
+    }
+}
+
+/// How the next proxy is chosen from the usable ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum SelectionStrategy {
+    /// Any usable proxy, at random.
+    #[default]
+    Random,
+    /// Each usable proxy in turn, in file order.
+    RoundRobin,
+    /// At random, favoring proxies with a lower health check latency.
+    WeightedByLatency,
+    /// The usable proxy that has gone longest without being chosen.
+    LeastRecentlyUsed,
+}
+
+impl Display for SelectionStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SelectionStrategy::Random => write!(f, "random"),
+            SelectionStrategy::RoundRobin => write!(f, "round-robin"),
+            SelectionStrategy::WeightedByLatency => write!(f, "weighted-by-latency"),
+            SelectionStrategy::LeastRecentlyUsed => write!(f, "least-recently-used"),
+        }
+    }
+}
+
+/// Sticky session of clients not dedicated to one node (e.g. a single-node process)
+pub const SHARED_SESSION: &str = "shared";
+
+/// Consecutive proxy failures after which a sticky session moves to another proxy
+const STICKY_FAILOVER_AFTER: u32 = 3;
+
+/// A node's sticky proxy
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct StickySession {
+    /// Display string of the assigned proxy
+    proxy: String,
+    /// Failed requests through the proxy since the last successful one
+    consecutive_failures: u32,
+}
+
+/// A sticky session that moved to another proxy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StickyFailover {
+    pub owner: String,
+    pub from: String,
+    pub to: String,
+}
+
+/// The outcome of choosing how to connect for a request.
+#[derive(Debug, Clone)]
+pub enum ProxySelection {
+    /// Connect through this proxy.
+    Proxy(ProxyConfig),
+    /// Connect directly, because proxies are not in use or the policy allows it.
+    Direct,
+    /// No proxy is usable; one may recover after `retry_in`.
+    Exhausted { reason: String, retry_in: Duration },
+}
+
+/// Requests through a proxy with limits
+#[derive(Debug, Default)]
+struct ProxyUsage {
+    in_flight: u32,
+    /// When requests started, within the last minute
+    started: VecDeque<Instant>,
+}
+
+impl ProxyUsage {
+    /// How long until a request may start within `limits`; `None` if one may start now
+    fn wait(&self, limits: &ProxyLimits, now: Instant) -> Option<Duration> {
+        let mut wait = None;
+        if limits
+            .max_concurrent
+            .is_some_and(|max| self.in_flight >= max)
+        {
+            wait = Some(CONCURRENCY_RETRY);
+        }
+        for (limit, window) in [
+            (limits.max_rps, Duration::from_secs(1)),
+            (limits.max_rpm, Duration::from_secs(60)),
+        ] {
+            let Some(limit) = limit else {
+                continue;
+            };
+            let recent: Vec<Instant> = self
+                .started
+                .iter()
+                .copied()
+                .filter(|started| now.duration_since(*started) < window)
+                .collect();
+            // Enough of the recent requests must leave the window to make room for one more
+            if let Some(oldest) = recent.len().checked_sub(limit as usize).map(|i| recent[i]) {
+                let until = (oldest + window).saturating_duration_since(now);
+                wait = wait.max(Some(until));
+            }
+        }
+        wait
     }
 }
 
 /// Proxy manager that loads and manages proxy rotation
 pub struct ProxyManager {
+    /// The proxy file
+    file_path: String,
     proxies: Vec<ProxyConfig>,
     last_updated: Instant,
-    update_interval: Duration,
+    /// How often the proxy file is re-read; `None` while a file watcher reloads it on change
+    update_interval: Option<Duration>,
+    /// Proxies that recently failed, by display string, with the time they may be used again
+    blacklist: HashMap<String, Instant>,
+    /// Health check results, by display string
+    health: HashMap<String, ProxyHealth>,
+    /// The report of the last load, until it is taken
+    parse_report: Option<ProxyParseReport>,
+    /// Proxies loaded by the previous load, to report only changes
+    previous_loaded: Option<usize>,
+    /// Sticky sessions, by owner (e.g. node ID)
+    sticky: HashMap<String, StickySession>,
+    /// Sticky sessions that moved since they were last reported
+    failovers: Vec<StickyFailover>,
+    /// Blocklist verdicts on exit IPs, by display string (see `proxy_reputation`)
+    reputation: HashMap<String, Reputation>,
+    /// Proxies each node is pinned to (see `node_proxies`)
+    mapping: NodeProxies,
+    /// Whether every loaded proxy is pinned to some node, so unmapped owners may use any
+    all_reserved: bool,
+    strategy: SelectionStrategy,
+    /// Whether proxies are used only once their exit clears the blocklist
+    checks_exits: bool,
+    /// Index in `proxies` where round-robin selection continues
+    next_index: usize,
+    /// When each proxy was last chosen, by display string
+    last_used: HashMap<String, Instant>,
+    /// Requests through proxies with limits, by display string
+    usage: HashMap<String, ProxyUsage>,
+    /// Request statistics, by display string
+    stats: HashMap<String, ProxyStats>,
+    /// Random source for proxy selection (see `rng`)
+    rng: StdRng,
 }
 
 impl ProxyManager {
-    /// Create a new proxy manager
-    pub fn new() -> Self {
+    /// Create a new, empty proxy manager for the proxies in `file_path`; they are loaded with
+    /// `install` (see `ProxyContext::reload`)
+    pub fn new(file_path: impl Into<String>) -> Self {
         Self {
+            file_path: file_path.into(),
             proxies: Vec::new(),
             last_updated: Instant::now() - Duration::from_secs(3600), // Force initial load
-            update_interval: Duration::from_secs(300), // Reload every 5 minutes
+            update_interval: Some(FALLBACK_RELOAD_INTERVAL),
+            blacklist: HashMap::new(),
+            health: HashMap::new(),
+            parse_report: None,
+            previous_loaded: None,
+            sticky: HashMap::new(),
+            failovers: Vec::new(),
+            reputation: HashMap::new(),
+            mapping: NodeProxies::default(),
+            all_reserved: false,
+            strategy: SelectionStrategy::default(),
+            checks_exits: false,
+            next_index: 0,
+            last_used: HashMap::new(),
+            usage: HashMap::new(),
+            stats: HashMap::new(),
+            rng: crate::rng::fork(),
         }
     }
 
-    /// Load proxies from file if needed (with automatic refresh)
-    pub fn ensure_proxies_loaded(&mut self) -> Result<(), String> {
-        if self.last_updated.elapsed() > self.update_interval || self.proxies.is_empty() {
-            self.load_proxies()?;
-            self.last_updated = Instant::now();
+    /// Replaces the random source for proxy selection, e.g. with a seeded one to replay a run
+    #[cfg(test)]
+    pub fn set_rng(&mut self, rng: StdRng) {
+        self.rng = rng;
+    }
+
+    /// Whether the proxy file should be read again: the pool is empty, or the file is not
+    /// watched and was last loaded too long ago
+    pub fn needs_reload(&self) -> bool {
+        let stale = self
+            .update_interval
+            .is_some_and(|interval| self.last_updated.elapsed() > interval);
+        stale || self.proxies.is_empty()
+    }
+
+    /// Fails while no proxy is loaded. Loading happens outside the lock, in
+    /// `ProxyContext::refresh`.
+    pub fn ensure_proxies_loaded(&self) -> Result<(), String> {
+        if self.proxies.is_empty() {
+            return Err(format!("No valid proxies found in {}", self.file_path));
         }
         Ok(())
     }
 
-    /// Load proxies from proxy file
-    fn load_proxies(&mut self) -> Result<(), String> {
-        let proxy_file_path = get_proxy_file_path();
-        let proxy_file = Path::new(&proxy_file_path);
-        if !proxy_file.exists() {
-            return Err(format!("{} file not found", proxy_file_path));
+    /// Counts a load attempt that failed, so a stale pool is not re-read on every request
+    fn reload_failed(&mut self) {
+        if !self.proxies.is_empty() {
+            self.last_updated = Instant::now();
         }
+    }
 
-        let content = fs::read_to_string(proxy_file)
-            .map_err(|e| format!("Failed to read proxies.txt: {}", e))?;
+    /// Replaces the pool with proxies read by `read_proxy_file`, unless none of them is valid
+    pub fn install(&mut self, loaded: LoadedProxies) -> Result<(), String> {
+        let LoadedProxies {
+            proxies,
+            report,
+            mapping,
+        } = loaded;
+        let changed = self.previous_loaded != Some(report.loaded);
+        self.previous_loaded = Some(report.loaded);
+        if changed || report.skipped > 0 {
+            self.parse_report = Some(report);
+        }
 
-        let mut new_proxies = Vec::new();
-        for (line_num, line) in content.lines().enumerate() {
-            let line = line.trim();
-            if line.is_empty() || line.starts_with('#') {
-                continue; // Skip empty lines and comments
-            }
+        if proxies.is_empty() {
+            return Err(format!("No valid proxies found in {}", self.file_path));
+        }
+        self.proxies = proxies;
+        self.set_mapping(mapping);
+        self.last_updated = Instant::now();
+        Ok(())
+    }
 
-            match ProxyConfig::from_string(line) {
-                Ok(proxy) => new_proxies.push(proxy),
-                Err(e) => {
-                    eprintln!("Warning: Skipping invalid proxy on line {}: {}", line_num + 1, e);
-                }
-            }
+    /// Pins nodes to the proxies in `mapping`
+    fn set_mapping(&mut self, mapping: NodeProxies) {
+        self.all_reserved = self
+            .proxies
+            .iter()
+            .all(|proxy| mapping.is_reserved(&proxy.to_display_string()));
+        self.mapping = mapping;
+    }
+
+    /// Whether requests made for `owner` may go through `proxy`: a mapped node's only through
+    /// its own proxies, anyone else's through proxies no node is mapped to
+    fn may_use(&self, owner: &str, proxy: &ProxyConfig) -> bool {
+        let display = proxy.to_display_string();
+        match self.mapping.assigned(owner) {
+            Some(assigned) => assigned.contains(&display),
+            None => self.all_reserved || !self.mapping.is_reserved(&display),
         }
+    }
+
+    /// Whether `owner` (a node ID) is pinned to proxies of its own
+    pub fn is_mapped(&self, owner: &str) -> bool {
+        self.mapping.assigned(owner).is_some()
+    }
 
-        if new_proxies.is_empty() {
-            return Err("No valid proxies found in proxies.txt".to_string());
+    /// Why no proxy could be chosen for `owner`
+    fn exhausted(&self, owner: &str) -> String {
+        if self.mapping.assigned(owner).is_some() {
+            format!(
+                "All proxies of node {} are blacklisted or missing from {}",
+                owner, self.file_path
+            )
+        } else {
+            format!("All {} proxies are blacklisted", self.proxies.len())
         }
+    }
 
-        self.proxies = new_proxies;
-        println!("Loaded {} proxies from {}", self.proxies.len(), proxy_file_path);
-        Ok(())
+    /// The report of the last load, if it found invalid lines or a different number of
+    /// proxies than the load before. Each report is returned once.
+    pub fn take_parse_report(&mut self) -> Option<ProxyParseReport> {
+        self.parse_report.take()
+    }
+
+    /// Get a random proxy that `owner` may use and is not blacklisted
+    pub fn get_random_proxy(&mut self, owner: &str) -> Result<ProxyConfig, String> {
+        self.ensure_proxies_loaded()?;
+
+        if self.proxies.is_empty() {
+            return Err("No proxies available".to_string());
+        }
+
+        let now = Instant::now();
+        self.blacklist.retain(|_, until| *until > now);
+        let usable: Vec<&ProxyConfig> = self
+            .proxies
+            .iter()
+            .filter(|proxy| self.may_use(owner, proxy) && !self.is_benched(proxy))
+            .collect();
+        let ready: Vec<&ProxyConfig> = usable
+            .iter()
+            .copied()
+            .filter(|proxy| self.has_capacity(proxy, now))
+            .collect();
+
+        ready
+            .choose(&mut self.rng)
+            .or_else(|| usable.choose(&mut self.rng))
+            .map(|proxy| (*proxy).clone())
+            .ok_or_else(|| self.exhausted(owner))
     }
 
-    /// Get a random proxy
-    pub fn get_random_proxy(&mut self) -> Result<ProxyConfig, String> {
+    /// The next proxy `owner` may use according to the selection strategy, skipping
+    /// blacklisted ones
+    pub fn next_proxy(&mut self, owner: &str) -> Result<ProxyConfig, String> {
+        if self.strategy == SelectionStrategy::Random {
+            return self.get_random_proxy(owner);
+        }
         self.ensure_proxies_loaded()?;
-        
         if self.proxies.is_empty() {
             return Err("No proxies available".to_string());
         }
 
-        let mut rng = rand::thread_rng();
+        let now = Instant::now();
+        self.blacklist.retain(|_, until| *until > now);
+        let usable: Vec<usize> = (0..self.proxies.len())
+            .filter(|&index| {
+                let proxy = &self.proxies[index];
+                self.may_use(owner, proxy) && !self.is_benched(proxy)
+            })
+            .collect();
+        if usable.is_empty() {
+            return Err(self.exhausted(owner));
+        }
+        // Proxies at their limits are only chosen if every proxy is
+        let ready: Vec<usize> = usable
+            .iter()
+            .copied()
+            .filter(|&index| self.has_capacity(&self.proxies[index], now))
+            .collect();
+        let usable = if ready.is_empty() { usable } else { ready };
+        let first = usable[0];
+        let index = match self.strategy {
+            SelectionStrategy::RoundRobin => {
+                let index = usable
+                    .iter()
+                    .copied()
+                    .find(|&index| index >= self.next_index)
+                    .unwrap_or(first);
+                self.next_index = index + 1;
+                index
+            }
+            SelectionStrategy::LeastRecentlyUsed => usable
+                .iter()
+                .copied()
+                .min_by_key(|&index| self.last_used.get(&self.proxies[index].to_display_string()))
+                .unwrap_or(first),
+            SelectionStrategy::WeightedByLatency => self.weighted_by_latency(&usable),
+            SelectionStrategy::Random => unreachable!("handled above"),
+        };
+        let proxy = self.proxies[index].clone();
+        self.last_used.insert(proxy.to_display_string(), now);
+        Ok(proxy)
+    }
+
+    /// Picks one of `usable` (indices into `proxies`) at random, with odds inversely
+    /// proportional to its health check latency. Proxies not measured yet get the average odds.
+    fn weighted_by_latency(&mut self, usable: &[usize]) -> usize {
+        let weights: Vec<Option<f64>> = usable
+            .iter()
+            .map(|&index| {
+                let health = self.health.get(&self.proxies[index].to_display_string())?;
+                let latency = health.latency?.max(Duration::from_millis(1));
+                Some(1.0 / latency.as_secs_f64())
+            })
+            .collect();
+        let measured: Vec<f64> = weights.iter().flatten().copied().collect();
+        let average = if measured.is_empty() {
+            1.0
+        } else {
+            measured.iter().sum::<f64>() / measured.len() as f64
+        };
+        let weights = weights.iter().map(|weight| weight.unwrap_or(average));
+        match WeightedIndex::new(weights) {
+            Ok(distribution) => usable[distribution.sample(&mut self.rng)],
+            Err(_) => usable[0],
+        }
+    }
+
+    /// The proxy assigned to `owner` for the whole run.
+    ///
+    /// The assignment survives brief blacklisting; it only moves, preferably to a proxy no
+    /// other owner holds, once the proxy failed repeatedly, failed its health checks, left
+    /// the file or is no longer one `owner` may use.
+    pub fn sticky_proxy(&mut self, owner: &str) -> Result<ProxyConfig, String> {
+        self.ensure_proxies_loaded()?;
+        let previous = self.sticky.remove(owner);
+        if let Some(session) = &previous {
+            let dead = self
+                .health
+                .get(&session.proxy)
+                .is_some_and(ProxyHealth::is_dead);
+            let assigned = self
+                .proxies
+                .iter()
+                .find(|proxy| proxy.to_display_string() == session.proxy)
+                .filter(|_| !dead && session.consecutive_failures < STICKY_FAILOVER_AFTER)
+                .filter(|_| self.is_cleared(&session.proxy))
+                .filter(|proxy| self.may_use(owner, proxy))
+                .cloned();
+            if let Some(proxy) = assigned {
+                self.sticky.insert(owner.to_string(), session.clone());
+                return Ok(proxy);
+            }
+        }
+
+        let now = Instant::now();
+        self.blacklist.retain(|_, until| *until > now);
+        let previous = previous.map(|session| session.proxy);
+        let usable: Vec<&ProxyConfig> = self
+            .proxies
+            .iter()
+            .filter(|proxy| self.may_use(owner, proxy) && !self.is_benched(proxy))
+            .filter(|proxy| previous.as_deref() != Some(proxy.to_display_string().as_str()))
+            .collect();
+        let held: Vec<&str> = self.sticky.values().map(|s| s.proxy.as_str()).collect();
+        let free: Vec<&ProxyConfig> = usable
+            .iter()
+            .copied()
+            .filter(|proxy| !held.contains(&proxy.to_display_string().as_str()))
+            .collect();
+        let proxy = free
+            .choose(&mut self.rng)
+            .or_else(|| usable.choose(&mut self.rng))
+            .map(|proxy| (*proxy).clone())
+            .ok_or_else(|| self.exhausted(owner))?;
+
+        let display = proxy.to_display_string();
+        if let Some(from) = previous {
+            self.failovers.push(StickyFailover {
+                owner: owner.to_string(),
+                from,
+                to: display.clone(),
+            });
+        }
+        self.sticky.insert(
+            owner.to_string(),
+            StickySession {
+                proxy: display,
+                consecutive_failures: 0,
+            },
+        );
+        Ok(proxy)
+    }
+
+    /// Records a request through `owner`'s sticky proxy, counting towards a failover if the
+    /// proxy failed
+    pub fn record_sticky_result(&mut self, owner: &str, proxy: &ProxyConfig, failed: bool) {
+        let Some(session) = self.sticky.get_mut(owner) else {
+            return;
+        };
+        if session.proxy != proxy.to_display_string() {
+            return;
+        }
+        if failed {
+            session.consecutive_failures += 1;
+        } else {
+            session.consecutive_failures = 0;
+        }
+    }
+
+    /// The proxy of each sticky session, by owner
+    pub fn sticky_sessions(&self) -> BTreeMap<String, String> {
+        self.sticky
+            .iter()
+            .map(|(owner, session)| (owner.clone(), session.proxy.clone()))
+            .collect()
+    }
+
+    /// Continues sticky sessions of an earlier process. Proxies that are no longer usable
+    /// fail over on first use.
+    pub fn restore_sticky_sessions(&mut self, sessions: &BTreeMap<String, String>) {
+        for (owner, proxy) in sessions {
+            self.sticky.insert(
+                owner.clone(),
+                StickySession {
+                    proxy: proxy.clone(),
+                    consecutive_failures: 0,
+                },
+            );
+        }
+    }
+
+    /// Sticky sessions that moved since the last call
+    pub fn take_failovers(&mut self) -> Vec<StickyFailover> {
+        std::mem::take(&mut self.failovers)
+    }
+
+    /// The first `limit` proxies in file order that are not blacklisted
+    pub fn usable_proxies(&mut self, limit: usize) -> Vec<ProxyConfig> {
+        if self.ensure_proxies_loaded().is_err() {
+            return Vec::new();
+        }
+        let now = Instant::now();
+        self.blacklist.retain(|_, until| *until > now);
+        self.proxies
+            .iter()
+            .filter(|proxy| !self.is_benched(proxy))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    /// The proxy with this identity (see `ProxyConfig::identity`), unless it is blacklisted or
+    /// no longer in the file
+    pub fn usable_proxy(&mut self, identity: &str) -> Option<ProxyConfig> {
+        self.usable_proxies(usize::MAX)
+            .into_iter()
+            .find(|proxy| proxy.identity() == identity)
+    }
+
+    /// Whether a proxy is blacklisted, failed its recent health checks or is not cleared by
+    /// the blocklist
+    fn is_benched(&self, proxy: &ProxyConfig) -> bool {
+        let display = proxy.to_display_string();
+        self.blacklist.contains_key(&display)
+            || self.health.get(&display).is_some_and(ProxyHealth::is_dead)
+            || !self.is_cleared(&display)
+    }
+
+    /// Whether a proxy may be used as far as the blocklist is concerned
+    fn is_cleared(&self, display: &str) -> bool {
+        !self.checks_exits || self.reputation.get(display) == Some(&Reputation::Allowed)
+    }
+
+    /// Loaded proxies whose exit was not checked against the blocklist yet
+    pub fn unverified_proxies(&mut self) -> Vec<ProxyConfig> {
+        if self.ensure_proxies_loaded().is_err() {
+            return Vec::new();
+        }
         self.proxies
-            .choose(&mut rng)
+            .iter()
+            .filter(|proxy| !self.reputation.contains_key(&proxy.to_display_string()))
             .cloned()
-            .ok_or_else(|| "Failed to select random proxy".to_string())
+            .collect()
+    }
+
+    /// Records the blocklist verdict on a proxy's exit
+    pub fn record_reputation(&mut self, proxy: &ProxyConfig, reputation: Reputation) {
+        self.reputation
+            .insert(proxy.to_display_string(), reputation);
+    }
+
+    /// Every loaded proxy, including benched ones
+    pub fn all_proxies(&mut self) -> Vec<ProxyConfig> {
+        if self.ensure_proxies_loaded().is_err() {
+            return Vec::new();
+        }
+        self.proxies.clone()
+    }
+
+    /// Records a health check: the latency of a successful one, or why it failed.
+    ///
+    /// A successful check returns a proxy to the rotation straight away, even if it was
+    /// blacklisted after a failed request.
+    pub fn record_check(
+        &mut self,
+        proxy: &ProxyConfig,
+        result: &Result<Duration, String>,
+    ) -> Option<HealthChange> {
+        let display = proxy.to_display_string();
+        let health = self.health.entry(display.clone()).or_default();
+        let was_dead = health.is_dead();
+        health.checks += 1;
+        match result {
+            Ok(latency) => {
+                health.latency = Some(match health.latency {
+                    Some(average) => {
+                        average.mul_f64(1.0 - LATENCY_SMOOTHING)
+                            + latency.mul_f64(LATENCY_SMOOTHING)
+                    }
+                    None => *latency,
+                });
+                health.consecutive_failures = 0;
+                self.blacklist.remove(&display);
+                was_dead.then_some(HealthChange::Recovered)
+            }
+            Err(_) => {
+                health.failures += 1;
+                health.consecutive_failures += 1;
+                (!was_dead && health.is_dead()).then_some(HealthChange::Removed)
+            }
+        }
+    }
+
+    /// Health check results of a proxy, if it was checked
+    pub fn health(&self, proxy: &ProxyConfig) -> Option<&ProxyHealth> {
+        self.health.get(&proxy.to_display_string())
+    }
+
+    /// Whether a request through `proxy` may start now within its limits
+    fn has_capacity(&self, proxy: &ProxyConfig, now: Instant) -> bool {
+        self.usage
+            .get(&proxy.to_display_string())
+            .is_none_or(|usage| usage.wait(&proxy.limits, now).is_none())
+    }
+
+    /// Starts a request through `proxy` if it is below its limits, or returns how long to wait
+    /// before trying again. Each started request is ended with `finish_request`.
+    pub fn start_request(&mut self, proxy: &ProxyConfig) -> Result<(), Duration> {
+        if proxy.limits.is_unlimited() {
+            return Ok(());
+        }
+        let now = Instant::now();
+        let usage = self.usage.entry(proxy.to_display_string()).or_default();
+        while usage
+            .started
+            .front()
+            .is_some_and(|started| now.duration_since(*started) >= Duration::from_secs(60))
+        {
+            usage.started.pop_front();
+        }
+        if let Some(wait) = usage.wait(&proxy.limits, now) {
+            return Err(wait);
+        }
+        usage.in_flight += 1;
+        usage.started.push_back(now);
+        Ok(())
+    }
+
+    /// Ends a request started with `start_request`
+    pub fn finish_request(&mut self, proxy: &ProxyConfig) {
+        if let Some(usage) = self.usage.get_mut(&proxy.to_display_string()) {
+            usage.in_flight = usage.in_flight.saturating_sub(1);
+        }
+    }
+
+    /// Counts a request through `proxy` in its statistics
+    pub fn record_request(&mut self, proxy: &ProxyConfig, outcome: &RequestOutcome) {
+        let display = proxy.to_display_string();
+        self.stats
+            .entry(display.clone())
+            .or_insert_with(|| ProxyStats::new(display))
+            .record(outcome);
+    }
+
+    /// Request statistics of every loaded proxy, in file order, followed by proxies that were
+    /// used before being removed from the file
+    pub fn stats(&self) -> Vec<ProxyStats> {
+        let mut stats: Vec<ProxyStats> = self
+            .proxies
+            .iter()
+            .map(|proxy| {
+                let display = proxy.to_display_string();
+                self.stats
+                    .get(&display)
+                    .cloned()
+                    .unwrap_or_else(|| ProxyStats::new(display))
+            })
+            .collect();
+        let mut removed: Vec<&ProxyStats> = self
+            .stats
+            .values()
+            .filter(|used| !stats.iter().any(|proxy| proxy.proxy == used.proxy))
+            .collect();
+        removed.sort_by(|a, b| a.proxy.cmp(&b.proxy));
+        stats.extend(removed.into_iter().cloned());
+        stats
+    }
+
+    /// Skip a proxy for a while after it failed to connect
+    pub fn mark_failed(&mut self, proxy: &ProxyConfig) {
+        self.blacklist.insert(
+            proxy.to_display_string(),
+            Instant::now() + BLACKLIST_DURATION,
+        );
+    }
+
+    /// How long until the next blacklisted proxy may be used again
+    pub fn next_recovery(&self) -> Option<Duration> {
+        let now = Instant::now();
+        self.blacklist
+            .values()
+            .map(|until| until.saturating_duration_since(now))
+            .min()
     }
 
     /// Get proxy count
@@ -133,62 +1182,1155 @@ impl ProxyManager {
     }
 }
 
-/// Global proxy manager instance
-static PROXY_MANAGER: OnceLock<std::sync::Mutex<ProxyManager>> = OnceLock::new();
+/// Proxy file used unless `--proxy` names another
+pub const DEFAULT_PROXY_FILE: &str = "proxies.txt";
 
-/// Global proxy enabled setting
-static PROXY_ENABLED: OnceLock<std::sync::Mutex<bool>> = OnceLock::new();
+/// How a context's proxies are chosen and used, from the `start` flags
+#[derive(Debug, Clone)]
+pub struct ProxySettings {
+    /// What requests do while no proxy is usable (`--on-no-proxy`)
+    pub no_proxy_policy: NoProxyPolicy,
+    /// How proxies are assigned to requests (`--proxy-assignment`)
+    pub assignment: ProxyAssignment,
+    /// How the next proxy is chosen (`--proxy-strategy`)
+    pub strategy: SelectionStrategy,
+    /// Times a request whose proxy failed is retried through another (`--proxy-retries`)
+    pub retries: u32,
+    /// Time between proxy health checks; zero disables them (`--proxy-check-interval`)
+    pub health_check_interval: Duration,
+    /// Networks whose proxies are not used, if exits are checked (`--proxy-blocklist`)
+    pub blocklist: Option<Arc<Blocklist>>,
+    /// Service looking up the exits checked against the blocklist (`--proxy-exit-lookup`)
+    pub exit_lookup_url: Option<String>,
+}
 
-/// Global proxy file path setting
-static PROXY_FILE_PATH: OnceLock<std::sync::Mutex<String>> = OnceLock::new();
+impl Default for ProxySettings {
+    fn default() -> Self {
+        Self {
+            no_proxy_policy: NoProxyPolicy::default(),
+            assignment: ProxyAssignment::default(),
+            strategy: SelectionStrategy::default(),
+            retries: DEFAULT_PROXY_RETRIES,
+            health_check_interval: DEFAULT_HEALTH_CHECK_INTERVAL,
+            blocklist: None,
+            exit_lookup_url: None,
+        }
+    }
+}
 
-/// Get or initialize the global proxy manager
-pub fn get_proxy_manager() -> &'static std::sync::Mutex<ProxyManager> {
-    PROXY_MANAGER.get_or_init(|| std::sync::Mutex::new(ProxyManager::new()))
+/// The proxy settings and pool of one node process, or of each environment when a process
+/// runs several. Every subsystem that uses proxies is handed the context it belongs to.
+pub struct ProxyContext {
+    enabled: bool,
+    file_path: String,
+    settings: ProxySettings,
+    manager: Mutex<ProxyManager>,
+    /// Tasks that could not keep the proxy they were fetched through, by task ID, with the
+    /// proxy they were fetched through and the route used instead
+    affinity_switches: Mutex<HashMap<String, (String, String)>>,
+    /// Clients for proxies warmed up at startup, by proxy identity, so requests through those
+    /// proxies reuse the established connections
+    warm_clients: Mutex<HashMap<String, reqwest::Client>>,
 }
 
-/// Get a random proxy from the global manager
-pub fn get_random_proxy() -> Result<ProxyConfig, String> {
-    let manager = get_proxy_manager();
-    let mut manager = manager.lock().map_err(|_| "Failed to lock proxy manager")?;
-    manager.get_random_proxy()
+impl Default for ProxyContext {
+    /// Proxies from `proxies.txt`, if the file exists
+    fn default() -> Self {
+        Self::new(true, DEFAULT_PROXY_FILE)
+    }
 }
 
-/// Set whether proxy should be enabled globally
-pub fn set_proxy_enabled(enabled: bool) {
-    let setting = PROXY_ENABLED.get_or_init(|| std::sync::Mutex::new(true));
-    if let Ok(mut setting) = setting.lock() {
-        *setting = enabled;
+impl std::fmt::Debug for ProxyContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProxyContext")
+            .field("enabled", &self.enabled)
+            .field("file_path", &self.file_path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ProxyContext {
+    /// A context using the proxies in `file_path`, unless `enabled` is false (`--no-proxy`),
+    /// with the default settings
+    pub fn new(enabled: bool, file_path: impl Into<String>) -> Self {
+        let file_path = file_path.into();
+        Self {
+            enabled,
+            manager: Mutex::new(ProxyManager::new(file_path.clone())),
+            file_path,
+            settings: ProxySettings::default(),
+            affinity_switches: Mutex::default(),
+            warm_clients: Mutex::default(),
+        }
+    }
+
+    /// The same context, choosing and using its proxies according to `settings`
+    pub fn with_settings(mut self, settings: ProxySettings) -> Self {
+        if let Ok(manager) = self.manager.get_mut() {
+            manager.strategy = settings.strategy;
+            manager.checks_exits = settings.blocklist.is_some();
+        }
+        self.settings = settings;
+        self
+    }
+
+    /// How proxies are chosen and used
+    pub fn settings(&self) -> &ProxySettings {
+        &self.settings
+    }
+
+    /// The time between proxy health checks, unless they are disabled
+    pub fn health_check_interval(&self) -> Option<Duration> {
+        let interval = self.settings.health_check_interval;
+        (!interval.is_zero()).then_some(interval)
+    }
+
+    /// Record that a task's request went through `to` because its proxy `from` was unusable
+    pub fn record_affinity_switch(&self, task_id: &str, from: &str, to: &str) {
+        if let Ok(mut switches) = self.affinity_switches.lock() {
+            switches.insert(task_id.to_string(), (from.to_string(), to.to_string()));
+        }
+    }
+
+    /// Take the recorded proxy switch of a task, if there was one
+    pub fn take_affinity_switch(&self, task_id: &str) -> Option<(String, String)> {
+        self.affinity_switches.lock().ok()?.remove(task_id)
+    }
+
+    /// The client warmed up for the proxy with this identity, if there is one
+    pub fn warm_client(&self, identity: &str) -> Option<reqwest::Client> {
+        self.warm_clients.lock().ok()?.get(identity).cloned()
+    }
+
+    /// Keep a client warmed up for the proxy with this identity
+    pub fn add_warm_client(&self, identity: String, client: reqwest::Client) {
+        if let Ok(mut warm) = self.warm_clients.lock() {
+            warm.insert(identity, client);
+        }
+    }
+
+    /// Drop the client warmed up for the proxy with this identity
+    pub fn remove_warm_client(&self, identity: &str) {
+        if let Ok(mut warm) = self.warm_clients.lock() {
+            warm.remove(identity);
+        }
+    }
+
+    /// Drop every warmed-up client, e.g. when their connections did not survive the system
+    /// sleeping
+    pub fn reset_connections(&self) {
+        if let Ok(mut warm) = self.warm_clients.lock() {
+            warm.clear();
+        }
+    }
+
+    /// Whether proxies are enabled
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// The proxy file
+    pub fn file_path(&self) -> &str {
+        &self.file_path
+    }
+
+    /// Whether the proxy file exists
+    pub fn file_exists(&self) -> bool {
+        Path::new(&self.file_path).exists()
+    }
+
+    /// Whether proxies should be used (enabled and the file exists)
+    pub fn should_use(&self) -> bool {
+        self.enabled && self.file_exists()
+    }
+
+    /// The proxy pool, loaded first if it is empty or stale (see `refresh`)
+    pub fn manager(&self) -> &Mutex<ProxyManager> {
+        self.refresh();
+        &self.manager
+    }
+
+    /// Reloads the proxy file now, returning how many proxies were loaded. The file is read and
+    /// parsed before the pool is locked; the pool is replaced only if the file loads.
+    pub fn reload(&self) -> Result<usize, String> {
+        let loaded = read_proxy_file(&self.file_path, &|_, _| {});
+        let mut manager = self
+            .manager
+            .lock()
+            .map_err(|_| "Proxy manager unavailable".to_string())?;
+        match loaded.and_then(|loaded| manager.install(loaded)) {
+            Ok(()) => Ok(manager.proxy_count()),
+            Err(e) => {
+                manager.reload_failed();
+                Err(e)
+            }
+        }
+    }
+
+    /// Loads the proxy file if the pool is empty, or stale while the file is not watched. The
+    /// check holds the lock only briefly, and the file is read without it (see `reload`).
+    pub fn refresh(&self) {
+        if !self.should_use() {
+            return;
+        }
+        let needs_reload = self
+            .manager
+            .lock()
+            .is_ok_and(|manager| manager.needs_reload());
+        if needs_reload {
+            let _ = self.reload();
+        }
+    }
+
+    /// Whether `node_id` is pinned to proxies of its own in `node_proxies.toml`, loading the
+    /// proxy file first if the pool is empty
+    pub fn is_mapped(&self, node_id: u64) -> bool {
+        if !self.should_use() {
+            return false;
+        }
+        self.refresh();
+        self.manager
+            .lock()
+            .is_ok_and(|manager| manager.is_mapped(&node_id.to_string()))
+    }
+
+    /// Get the next proxy for `owner`, according to the selection strategy
+    pub fn next_proxy(&self, owner: &str) -> Result<ProxyConfig, String> {
+        self.refresh();
+        let mut manager = self
+            .manager
+            .lock()
+            .map_err(|_| "Failed to lock proxy manager")?;
+        manager.next_proxy(owner)
+    }
+
+    /// Waits until `proxy` is below its request limits, then counts a request through it
+    /// until the returned permit is dropped
+    pub async fn acquire(&self, proxy: &ProxyConfig) -> ProxyPermit<'_> {
+        loop {
+            let started = match self.manager.lock() {
+                Ok(mut manager) => manager.start_request(proxy),
+                Err(_) => Ok(()),
+            };
+            match started {
+                Ok(()) => {
+                    return ProxyPermit {
+                        context: self,
+                        proxy: proxy.clone(),
+                    };
+                }
+                Err(wait) => tokio::time::sleep(wait).await,
+            }
+        }
+    }
+
+    /// Counts a request through `proxy` in its statistics
+    pub fn record_request(&self, proxy: &ProxyConfig, outcome: &RequestOutcome) {
+        if let Ok(mut manager) = self.manager.lock() {
+            manager.record_request(proxy, outcome);
+        }
+    }
+
+    /// Request statistics of every proxy (see `ProxyManager::stats`)
+    pub fn stats(&self) -> Vec<ProxyStats> {
+        self.manager
+            .lock()
+            .map(|manager| manager.stats())
+            .unwrap_or_default()
+    }
+
+    /// Blacklist a proxy
+    pub fn mark_failed(&self, proxy: &ProxyConfig) {
+        if let Ok(mut manager) = self.manager.lock() {
+            manager.mark_failed(proxy);
+        }
+    }
+
+    /// Record a request through `owner`'s sticky proxy
+    pub fn record_sticky_result(&self, owner: &str, proxy: &ProxyConfig, failed: bool) {
+        if self.settings.assignment != ProxyAssignment::Sticky {
+            return;
+        }
+        if let Ok(mut manager) = self.manager.lock() {
+            manager.record_sticky_result(owner, proxy, failed);
+        }
+    }
+
+    /// Choose how to connect for a request made for `owner`, applying the `--on-no-proxy`
+    /// policy
+    pub fn select(&self, owner: &str) -> ProxySelection {
+        if !self.should_use() {
+            return ProxySelection::Direct;
+        }
+        let selected = match self.settings.assignment {
+            ProxyAssignment::Random => self.next_proxy(owner),
+            ProxyAssignment::Sticky => self
+                .manager()
+                .lock()
+                .map_err(|_| "Failed to lock proxy manager".to_string())
+                .and_then(|mut manager| manager.sticky_proxy(owner)),
+        };
+        let reason = match selected {
+            Ok(proxy) => return ProxySelection::Proxy(proxy),
+            Err(reason) => reason,
+        };
+        match self.settings.no_proxy_policy {
+            NoProxyPolicy::Direct => {
+                log::warn!("{}, connecting directly", reason);
+                ProxySelection::Direct
+            }
+            NoProxyPolicy::Fail | NoProxyPolicy::Wait => {
+                // With nothing blacklisted (e.g. an unreadable proxy file), check again shortly
+                let retry_in = self
+                    .manager
+                    .lock()
+                    .ok()
+                    .and_then(|manager| manager.next_recovery())
+                    .unwrap_or(Duration::from_secs(30));
+                ProxySelection::Exhausted { reason, retry_in }
+            }
+        }
+    }
+
+    /// Report the last load of the proxy file as an event, if it has not been reported yet
+    pub async fn report_file(&self, event_sender: &mpsc::Sender<Event>) {
+        let Some(report) = self
+            .manager
+            .lock()
+            .ok()
+            .and_then(|mut manager| manager.take_parse_report())
+        else {
+            return;
+        };
+        let log_level = if report.skipped > 0 {
+            LogLevel::Warn
+        } else {
+            LogLevel::Info
+        };
+        let _ = event_sender
+            .send(
+                Event::task_fetcher_with_level(report.summary(), EventType::Refresh, log_level)
+                    .in_module("proxy"),
+            )
+            .await;
+    }
+
+    /// Report sticky sessions that moved to another proxy, as events
+    pub async fn report_sticky_failovers(&self, event_sender: &mpsc::Sender<Event>) {
+        let failovers = match self.manager.lock() {
+            Ok(mut manager) => manager.take_failovers(),
+            Err(_) => return,
+        };
+        for failover in failovers {
+            let _ = event_sender
+                .send(
+                    Event::task_fetcher_with_level(
+                        if failover.owner == SHARED_SESSION {
+                            format!(
+                                "Sticky proxy {} is no longer usable, now using proxy {}",
+                                failover.from, failover.to
+                            )
+                        } else {
+                            format!(
+                                "Sticky proxy {} is no longer usable, node {} now uses proxy {}",
+                                failover.from, failover.owner, failover.to
+                            )
+                        },
+                        EventType::Refresh,
+                        LogLevel::Warn,
+                    )
+                    .in_module("proxy")
+                    .with_node(failover.owner.parse().ok())
+                    .with_proxy(&failover.to),
+                )
+                .await;
+        }
+    }
+
+    /// Reloads the proxy file as soon as it, or the secrets file or node mapping next to it,
+    /// changes, until shutdown. While the watcher runs, the proxy file is no longer re-read
+    /// periodically.
+    ///
+    /// # Errors
+    /// Returns why the file cannot be watched; it is then still re-read every few minutes.
+    pub fn spawn_file_watcher(
+        self: Arc<Self>,
+        event_sender: mpsc::Sender<Event>,
+        mut shutdown: broadcast::Receiver<()>,
+    ) -> Result<JoinHandle<()>, String> {
+        let path = PathBuf::from(&self.file_path);
+        let file_names: Vec<_> = [
+            path.clone(),
+            proxy_secrets_path(&path),
+            node_proxies_path(&path),
+        ]
+        .iter()
+        .filter_map(|path| path.file_name().map(|name| name.to_os_string()))
+        .collect();
+        // Editors often replace the file rather than write to it, so watch its directory
+        let directory = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+
+        let (change_sender, mut changes) = mpsc::unbounded_channel();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let Ok(event) = event else {
+                    return;
+                };
+                let ours = event
+                    .paths
+                    .iter()
+                    .filter_map(|path| path.file_name())
+                    .any(|name| file_names.iter().any(|ours| ours == name));
+                if ours && !event.kind.is_access() {
+                    let _ = change_sender.send(());
+                }
+            })
+            .map_err(|e| e.to_string())?;
+        watcher
+            .watch(&directory, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("cannot watch {}: {}", directory.display(), e))?;
+        if let Ok(mut manager) = self.manager.lock() {
+            manager.update_interval = None;
+        }
+
+        Ok(tokio::spawn(async move {
+            // The watcher stops when dropped
+            let _watcher = watcher;
+            loop {
+                tokio::select! {
+                    _ = shutdown.recv() => break,
+                    change = changes.recv() => {
+                        if change.is_none() {
+                            break;
+                        }
+                        tokio::time::sleep(WATCH_DEBOUNCE).await;
+                        while changes.try_recv().is_ok() {}
+
+                        if let Err(e) = self.reload() {
+                            let _ = event_sender
+                                .send(
+                                    Event::task_fetcher_with_level(
+                                        format!(
+                                            "Proxy file changed but could not be loaded, keeping the current proxies: {}",
+                                            e
+                                        ),
+                                        EventType::Refresh,
+                                        LogLevel::Warn,
+                                    )
+                                    .in_module("proxy"),
+                                )
+                                .await;
+                        }
+                        self.report_file(&event_sender).await;
+                    }
+                }
+            }
+        }))
     }
 }
 
-/// Check if proxy is enabled
-pub fn is_proxy_enabled() -> bool {
-    let setting = PROXY_ENABLED.get_or_init(|| std::sync::Mutex::new(true));
-    setting.lock().map(|s| *s).unwrap_or(true)
+/// A request counted against a proxy's limits, until dropped
+pub struct ProxyPermit<'a> {
+    context: &'a ProxyContext,
+    proxy: ProxyConfig,
 }
 
-/// Set custom proxy file path
-pub fn set_proxy_file_path(path: String) {
-    let setting = PROXY_FILE_PATH.get_or_init(|| std::sync::Mutex::new("proxies.txt".to_string()));
-    if let Ok(mut setting) = setting.lock() {
-        *setting = path;
+impl Drop for ProxyPermit<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut manager) = self.context.manager.lock() {
+            manager.finish_request(&self.proxy);
+        }
     }
 }
 
-/// Get the proxy file path (default: proxies.txt)
-pub fn get_proxy_file_path() -> String {
-    let setting = PROXY_FILE_PATH.get_or_init(|| std::sync::Mutex::new("proxies.txt".to_string()));
-    setting.lock().map(|s| s.clone()).unwrap_or_else(|_| "proxies.txt".to_string())
+/// Periodically probes every proxy in the pool, taking dead ones out of the rotation until a
+/// later check succeeds.
+pub struct ProxyHealthChecker {
+    interval: Duration,
 }
 
-/// Check if proxy file exists
-pub fn proxy_file_exists() -> bool {
-    let proxy_file_path = get_proxy_file_path();
-    Path::new(&proxy_file_path).exists()
+impl ProxyHealthChecker {
+    pub fn new(interval: Duration) -> Self {
+        Self { interval }
+    }
+
+    /// Checks the pool of `proxies` every interval, and on waking from sleep, until shutdown.
+    /// `probe` connects through a proxy and returns the latency, or why the proxy failed.
+    pub fn spawn<F, Fut>(
+        self,
+        proxies: Arc<ProxyContext>,
+        probe: F,
+        event_sender: mpsc::Sender<Event>,
+        mut shutdown: broadcast::Receiver<()>,
+    ) -> JoinHandle<()>
+    where
+        F: Fn(ProxyConfig) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Duration, String>> + Send + 'static,
+    {
+        let probe = Arc::new(probe);
+        let mut wakes = crate::sleep_wake::subscribe();
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = shutdown.recv() => break,
+                    _ = Self::check_all(&proxies, probe.clone(), &event_sender) => {}
+                }
+                // Proxies may have come or gone while the system slept
+                tokio::select! {
+                    _ = shutdown.recv() => break,
+                    _ = tokio::time::sleep(self.interval) => {}
+                    Ok(()) = wakes.changed() => {}
+                }
+            }
+        })
+    }
+
+    /// Probes every loaded proxy once, reporting proxies that leave or rejoin the rotation.
+    async fn check_all<F, Fut>(
+        context: &ProxyContext,
+        probe: Arc<F>,
+        event_sender: &mpsc::Sender<Event>,
+    ) where
+        F: Fn(ProxyConfig) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Duration, String>> + Send + 'static,
+    {
+        let proxies = context
+            .manager()
+            .lock()
+            .map(|mut manager| manager.all_proxies())
+            .unwrap_or_default();
+        let limit = Arc::new(Semaphore::new(MAX_CONCURRENT_CHECKS));
+        let mut checks = JoinSet::new();
+        for proxy in proxies {
+            let probe = probe.clone();
+            let limit = limit.clone();
+            checks.spawn(async move {
+                let _permit = limit.acquire_owned().await;
+                let result = probe(proxy.clone()).await;
+                (proxy, result)
+            });
+        }
+
+        while let Some(Ok((proxy, result))) = checks.join_next().await {
+            let change = context
+                .manager()
+                .lock()
+                .ok()
+                .and_then(|mut manager| manager.record_check(&proxy, &result));
+            let event = match (change, &result) {
+                (Some(HealthChange::Removed), Err(reason)) => Event::task_fetcher_with_level(
+                    format!(
+                        "Proxy {} failed {} health checks ({}), removed from rotation until it recovers",
+                        proxy.to_display_string(),
+                        FAILED_CHECKS_BEFORE_REMOVAL,
+                        reason
+                    ),
+                    EventType::Refresh,
+                    LogLevel::Warn,
+                ),
+                (Some(HealthChange::Recovered), Ok(latency)) => Event::task_fetcher_with_level(
+                    format!(
+                        "Proxy {} recovered ({} ms), back in rotation",
+                        proxy.to_display_string(),
+                        latency.as_millis()
+                    ),
+                    EventType::Refresh,
+                    LogLevel::Info,
+                )
+                .with_duration(*latency),
+                _ => continue,
+            };
+            let _ = event_sender
+                .send(
+                    event
+                        .in_module("proxy")
+                        .with_proxy(&proxy.to_display_string()),
+                )
+                .await;
+        }
+    }
 }
 
-/// Check if proxy should be used (enabled and file exists)
-pub fn should_use_proxy() -> bool {
-    is_proxy_enabled() && proxy_file_exists()
-} 
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager_with(proxies: &[&str]) -> ProxyManager {
+        let mut manager = ProxyManager::new(DEFAULT_PROXY_FILE);
+        manager.proxies = proxies
+            .iter()
+            .map(|line| ProxyConfig::from_string(line).unwrap())
+            .collect();
+        manager.last_updated = Instant::now();
+        manager
+    }
+
+    #[test]
+    // Contexts keep their own settings and pools, so one process can run several.
+    fn test_contexts_are_independent() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("first.txt");
+        let second = dir.path().join("second.txt");
+        fs::write(&first, "a:1:u:p\n").unwrap();
+        fs::write(&second, "b:2:u:p\n").unwrap();
+
+        let first = ProxyContext::new(true, first.to_string_lossy());
+        let second = ProxyContext::new(true, second.to_string_lossy());
+        assert_eq!(
+            first
+                .next_proxy(SHARED_SESSION)
+                .unwrap()
+                .to_display_string(),
+            "a:1"
+        );
+        assert_eq!(
+            second
+                .next_proxy(SHARED_SESSION)
+                .unwrap()
+                .to_display_string(),
+            "b:2"
+        );
+
+        first.mark_failed(&first.next_proxy(SHARED_SESSION).unwrap());
+        assert!(first.next_proxy(SHARED_SESSION).is_err());
+        assert!(second.next_proxy(SHARED_SESSION).is_ok());
+
+        let disabled = ProxyContext::new(false, second.file_path());
+        assert!(!disabled.should_use());
+        assert!(matches!(disabled.select("shared"), ProxySelection::Direct));
+    }
+
+    #[test]
+    // A watched proxy file is not re-read on a timer; an unwatched one is once it is stale.
+    fn test_watched_file_is_not_polled() {
+        let mut manager = manager_with(&["a:1:u:p"]);
+        manager.last_updated = Instant::now() - FALLBACK_RELOAD_INTERVAL * 2;
+        manager.update_interval = None;
+        assert!(!manager.needs_reload());
+        manager.update_interval = Some(FALLBACK_RELOAD_INTERVAL);
+        assert!(manager.needs_reload());
+
+        // A reload that fails keeps the pool, and is not tried again until the next interval
+        manager.reload_failed();
+        assert!(!manager.needs_reload());
+        assert_eq!(manager.proxy_count(), 1);
+        assert!(ProxyManager::new(DEFAULT_PROXY_FILE).needs_reload());
+    }
+
+    #[test]
+    // The pool is loaded through the context on first use, and reloads keep it if the file
+    // turns invalid.
+    fn test_context_loads_outside_the_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("proxies.txt");
+        fs::write(&file, "a:1:u:p\nb:2:u:p\n").unwrap();
+        let context = ProxyContext::new(true, file.to_string_lossy());
+        assert_eq!(context.manager().lock().unwrap().proxy_count(), 2);
+
+        fs::write(&file, "not a proxy\n").unwrap();
+        assert!(context.reload().is_err());
+        assert_eq!(context.manager().lock().unwrap().proxy_count(), 2);
+    }
+
+    #[test]
+    // Settings belong to their context: one checking exits against a blocklist keeps unchecked
+    // proxies out, while another in the same process uses them.
+    fn test_settings_per_context() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("proxies.txt");
+        fs::write(&file, "a:1:u:p\n").unwrap();
+        let checked =
+            ProxyContext::new(true, file.to_string_lossy()).with_settings(ProxySettings {
+                no_proxy_policy: NoProxyPolicy::Fail,
+                blocklist: Some(Arc::new(Blocklist::default())),
+                ..ProxySettings::default()
+            });
+        let unchecked = ProxyContext::new(true, file.to_string_lossy());
+
+        assert!(matches!(
+            checked.select("node"),
+            ProxySelection::Exhausted { .. }
+        ));
+        assert!(matches!(unchecked.select("node"), ProxySelection::Proxy(_)));
+        assert_eq!(
+            unchecked.health_check_interval(),
+            Some(DEFAULT_HEALTH_CHECK_INTERVAL)
+        );
+    }
+
+    #[test]
+    // Managers with the same seed choose the same proxies, so a run's rotation can be replayed.
+    fn test_seeded_selection_is_reproducible() {
+        use rand::SeedableRng;
+
+        let proxies = ["a:1:u:p", "b:2:u:p", "c:3:u:p", "d:4:u:p"];
+        let picks = |seed| {
+            let mut manager = manager_with(&proxies);
+            manager.set_rng(StdRng::seed_from_u64(seed));
+            (0..20)
+                .map(|_| {
+                    manager
+                        .get_random_proxy(SHARED_SESSION)
+                        .unwrap()
+                        .to_display_string()
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(picks(7), picks(7));
+        assert_ne!(picks(7), picks(8));
+    }
+
+    #[test]
+    // Blacklisted proxies are skipped until every proxy has failed.
+    fn test_blacklisted_proxies_are_skipped() {
+        let mut manager = manager_with(&["a:1:u:p", "b:2:u:p"]);
+        let first = manager.get_random_proxy(SHARED_SESSION).unwrap();
+        manager.mark_failed(&first);
+
+        for _ in 0..10 {
+            let proxy = manager.get_random_proxy(SHARED_SESSION).unwrap();
+            assert_ne!(proxy.to_display_string(), first.to_display_string());
+        }
+
+        let second = manager.get_random_proxy(SHARED_SESSION).unwrap();
+        manager.mark_failed(&second);
+        assert!(manager.get_random_proxy(SHARED_SESSION).is_err());
+        let recovery = manager.next_recovery().unwrap();
+        assert!(recovery > Duration::ZERO && recovery <= BLACKLIST_DURATION);
+    }
+
+    #[test]
+    // Warm-up candidates are the first usable proxies in file order.
+    fn test_usable_proxies_in_file_order() {
+        let mut manager = manager_with(&["a:1:u:p", "b:2:u:p", "c:3:u:p"]);
+        manager.mark_failed(&ProxyConfig::from_string("a:1:u:p").unwrap());
+        let usable: Vec<String> = manager
+            .usable_proxies(5)
+            .iter()
+            .map(ProxyConfig::to_display_string)
+            .collect();
+        assert_eq!(usable, vec!["b:2", "c:3"]);
+        assert_eq!(manager.usable_proxies(1).len(), 1);
+    }
+
+    #[test]
+    // A task keeps its proxy until it is blacklisted, and a switch is reported once.
+    fn test_task_proxy_affinity() {
+        let mut manager =
+            manager_with(&["a:1:u:p", "b:2:u:p", "g:3:session-1:p", "g:3:session-2:p"]);
+        let a = ProxyConfig::from_string("a:1:u:p").unwrap();
+        assert!(manager.usable_proxy(&a.identity()).is_some());
+        assert!(manager.usable_proxy("z:9").is_none());
+
+        // Lines sharing a gateway's host and port are told apart by username
+        let session = ProxyConfig::from_string("g:3:session-2:p").unwrap();
+        assert_ne!(
+            session.identity(),
+            ProxyConfig::from_string("g:3:session-1:p")
+                .unwrap()
+                .identity()
+        );
+        assert_eq!(
+            manager
+                .usable_proxy(&session.identity())
+                .map(|proxy| proxy.username),
+            Some("session-2".to_string())
+        );
+
+        manager.mark_failed(&a);
+        assert!(manager.usable_proxy(&a.identity()).is_none());
+
+        let context = ProxyContext::new(false, DEFAULT_PROXY_FILE);
+        context.record_affinity_switch("task-1", "a:1", "b:2");
+        assert_eq!(
+            context.take_affinity_switch("task-1"),
+            Some(("a:1".to_string(), "b:2".to_string()))
+        );
+        assert_eq!(context.take_affinity_switch("task-1"), None);
+    }
+
+    #[test]
+    // Round robin takes proxies in turn, least recently used prefers idle ones, and latency
+    // weighting favors fast proxies.
+    fn test_selection_strategies() {
+        let mut manager = manager_with(&["a:1:u:p", "b:2:u:p", "c:3:u:p"]);
+        manager.strategy = SelectionStrategy::RoundRobin;
+        manager.mark_failed(&ProxyConfig::from_string("b:2:u:p").unwrap());
+        let order: Vec<String> = (0..4)
+            .map(|_| {
+                manager
+                    .next_proxy(SHARED_SESSION)
+                    .unwrap()
+                    .to_display_string()
+            })
+            .collect();
+        assert_eq!(order, ["a:1", "c:3", "a:1", "c:3"]);
+
+        let mut manager = manager_with(&["a:1:u:p", "b:2:u:p", "c:3:u:p"]);
+        manager.strategy = SelectionStrategy::LeastRecentlyUsed;
+        let order: Vec<String> = (0..4)
+            .map(|_| {
+                manager
+                    .next_proxy(SHARED_SESSION)
+                    .unwrap()
+                    .to_display_string()
+            })
+            .collect();
+        assert_eq!(order, ["a:1", "b:2", "c:3", "a:1"]);
+
+        let mut manager = manager_with(&["a:1:u:p", "b:2:u:p"]);
+        manager.strategy = SelectionStrategy::WeightedByLatency;
+        let fast = ProxyConfig::from_string("a:1:u:p").unwrap();
+        let slow = ProxyConfig::from_string("b:2:u:p").unwrap();
+        manager.record_check(&fast, &Ok(Duration::from_millis(10)));
+        manager.record_check(&slow, &Ok(Duration::from_millis(1000)));
+        let fast_picks = (0..1000)
+            .filter(|_| {
+                manager
+                    .next_proxy(SHARED_SESSION)
+                    .unwrap()
+                    .to_display_string()
+                    == "a:1"
+            })
+            .count();
+        assert!(fast_picks > 900);
+    }
+
+    #[test]
+    // Mapped nodes only use their own proxies, sticky or not; everyone else the unmapped ones.
+    fn test_node_proxy_mapping() {
+        let mut manager = manager_with(&["a:1:u:p", "b:2:u:p", "c:3:u:p", "d:4:u:p"]);
+        manager.set_mapping(
+            NodeProxies::parse(
+                "[groups]\npair = [\"b:2\", \"c:3\"]\n\n[nodes]\n1 = \"a:1\"\n2 = \"pair\"\n",
+            )
+            .unwrap(),
+        );
+        for _ in 0..20 {
+            assert_eq!(manager.next_proxy("1").unwrap().to_display_string(), "a:1");
+            let pair = manager.next_proxy("2").unwrap().to_display_string();
+            assert!(pair == "b:2" || pair == "c:3");
+            assert_eq!(
+                manager
+                    .next_proxy(SHARED_SESSION)
+                    .unwrap()
+                    .to_display_string(),
+                "d:4"
+            );
+        }
+        assert_eq!(
+            manager.sticky_proxy("1").unwrap().to_display_string(),
+            "a:1"
+        );
+        assert_eq!(
+            manager.sticky_proxy("3").unwrap().to_display_string(),
+            "d:4"
+        );
+
+        manager.mark_failed(&ProxyConfig::from_string("a:1:u:p").unwrap());
+        let error = manager.next_proxy("1").unwrap_err();
+        assert!(error.contains("node 1"));
+
+        // With every proxy mapped, unmapped owners fall back to the whole pool
+        manager.set_mapping(
+            NodeProxies::parse("[nodes]\n1 = [\"a:1\", \"b:2\", \"c:3\", \"d:4\"]\n").unwrap(),
+        );
+        assert!(manager.next_proxy(SHARED_SESSION).is_ok());
+    }
+
+    #[test]
+    // A single node named in the mapping is reported as mapped, so it gets a client of its own
+    // and its pinned proxies rather than the unmapped ones.
+    fn test_single_mapped_node() {
+        let dir = tempfile::tempdir().unwrap();
+        let proxy_file = dir.path().join("proxies.txt");
+        fs::write(&proxy_file, "a:1:u:p\nb:2:u:p\n").unwrap();
+        fs::write(node_proxies_path(&proxy_file), "[nodes]\n123 = \"a:1\"\n").unwrap();
+
+        let context = ProxyContext::new(true, proxy_file.to_string_lossy());
+        assert!(context.is_mapped(123));
+        assert!(!context.is_mapped(456));
+        for _ in 0..10 {
+            assert_eq!(
+                context.next_proxy("123").unwrap().to_display_string(),
+                "a:1"
+            );
+            assert_eq!(
+                context
+                    .next_proxy(SHARED_SESSION)
+                    .unwrap()
+                    .to_display_string(),
+                "b:2"
+            );
+        }
+        assert!(!ProxyContext::new(false, context.file_path()).is_mapped(123));
+    }
+
+    #[test]
+    // A node keeps its proxy through single failures, and fails over to a free proxy after
+    // repeated ones.
+    fn test_sticky_sessions() {
+        let mut manager = manager_with(&["a:1:u:p", "b:2:u:p", "c:3:u:p"]);
+        let first = manager.sticky_proxy("node-1").unwrap();
+        let other = manager.sticky_proxy("node-2").unwrap();
+        assert_ne!(first.to_display_string(), other.to_display_string());
+
+        for _ in 0..STICKY_FAILOVER_AFTER - 1 {
+            manager.mark_failed(&first);
+            manager.record_sticky_result("node-1", &first, true);
+            let proxy = manager.sticky_proxy("node-1").unwrap();
+            assert_eq!(proxy.to_display_string(), first.to_display_string());
+        }
+        manager.record_sticky_result("node-1", &first, false);
+        manager.record_sticky_result("node-1", &first, true);
+        assert_eq!(
+            manager.sticky_proxy("node-1").unwrap().to_display_string(),
+            first.to_display_string()
+        );
+        assert!(manager.take_failovers().is_empty());
+
+        for _ in 0..STICKY_FAILOVER_AFTER {
+            manager.record_sticky_result("node-1", &first, true);
+        }
+        let moved = manager.sticky_proxy("node-1").unwrap();
+        // The only proxy neither failed nor held by node-2
+        assert_ne!(moved.to_display_string(), first.to_display_string());
+        assert_ne!(moved.to_display_string(), other.to_display_string());
+        assert_eq!(
+            manager.take_failovers(),
+            vec![StickyFailover {
+                owner: "node-1".to_string(),
+                from: first.to_display_string(),
+                to: moved.to_display_string(),
+            }]
+        );
+    }
+
+    #[test]
+    // Proxies leave the rotation after failed checks and rejoin on the next successful one.
+    fn test_health_checks_remove_and_restore_proxies() {
+        let mut manager = manager_with(&["a:1:u:p", "b:2:u:p"]);
+        let a = ProxyConfig::from_string("a:1:u:p").unwrap();
+        let failed = Err("connection refused".to_string());
+
+        assert_eq!(manager.record_check(&a, &failed), None);
+        assert_eq!(manager.usable_proxies(5).len(), 2);
+        assert_eq!(
+            manager.record_check(&a, &failed),
+            Some(HealthChange::Removed)
+        );
+        assert_eq!(manager.record_check(&a, &failed), None);
+        for _ in 0..10 {
+            assert_eq!(
+                manager
+                    .get_random_proxy(SHARED_SESSION)
+                    .unwrap()
+                    .to_display_string(),
+                "b:2"
+            );
+        }
+
+        let ok = Ok(Duration::from_millis(100));
+        assert_eq!(manager.record_check(&a, &ok), Some(HealthChange::Recovered));
+        assert_eq!(manager.usable_proxies(5).len(), 2);
+        let health = manager.health(&a).unwrap();
+        assert_eq!((health.checks, health.failures), (4, 3));
+        assert_eq!(health.latency, Some(Duration::from_millis(100)));
+
+        // A successful check also lifts a blacklisting after a failed request
+        manager.mark_failed(&a);
+        manager.record_check(&a, &ok);
+        assert!(manager.usable_proxy(&a.identity()).is_some());
+    }
+
+    #[test]
+    // Lines may name their scheme; without one they are HTTP proxies.
+    fn test_parse_scheme() {
+        let http = ProxyConfig::from_string("a:1:u:p").unwrap();
+        assert_eq!(http.scheme, ProxyScheme::Http);
+        assert_eq!(http.to_display_string(), "a:1");
+
+        let socks = ProxyConfig::from_string("SOCKS5://b:1080:u:p@ss").unwrap();
+        assert_eq!(socks.scheme, ProxyScheme::Socks5);
+        assert_eq!(socks.password, "p@ss");
+        assert_eq!(socks.to_display_string(), "socks5://b:1080");
+        assert!(socks.to_reqwest_proxy().is_ok());
+
+        let socks5h = ProxyConfig::from_string("socks5h://c:1080:u:p").unwrap();
+        assert_eq!(socks5h.scheme, ProxyScheme::Socks5h);
+        assert_eq!(
+            ProxyConfig::parse("ftp://d:21:u:p").unwrap_err(),
+            "Unsupported proxy scheme, expected http, https, socks5 or socks5h"
+        );
+    }
+
+    #[test]
+    // Credential references resolve from the secrets file; unresolved ones skip their line.
+    fn test_credential_references() {
+        let dir = tempfile::tempdir().unwrap();
+        let proxy_file = dir.path().join("proxies.txt");
+        fs::write(
+            &proxy_file,
+            "a:1:SECRET:pool\nb:2:SECRET:missing\nc:3:u:p\n",
+        )
+        .unwrap();
+        fs::write(
+            proxy_secrets_path(&proxy_file),
+            "[pool]\nusername = \"alice\"\npassword = \"hunter2\"\n",
+        )
+        .unwrap();
+
+        let loaded = read_proxy_file(proxy_file.to_str().unwrap(), &|_, _| {}).unwrap();
+        let proxies = loaded.proxies();
+        assert_eq!(proxies.len(), 2);
+        assert_eq!(proxies[0].username, "alice");
+        assert_eq!(proxies[0].password, "hunter2");
+        assert_eq!(proxies[0].credentials, CredentialSource::SecretsFile);
+        assert_eq!(proxies[1].credentials, CredentialSource::Inline);
+        assert_eq!(loaded.report().examples.len(), 1);
+        assert_eq!(loaded.report().examples[0].0, 2);
+        assert!(!loaded.report().summary().contains("hunter2"));
+    }
+
+    #[test]
+    // Limits follow the credentials, and requests beyond them wait for the proxy.
+    fn test_proxy_limits() {
+        let limited = ProxyConfig::from_string("a:1:u:p:max_concurrent=2:max_rpm=3").unwrap();
+        assert_eq!(
+            limited.limits,
+            ProxyLimits {
+                max_concurrent: Some(2),
+                max_rps: None,
+                max_rpm: Some(3),
+            }
+        );
+        assert!(ProxyConfig::parse("a:1:u:p:max_rps=0").is_err());
+        assert!(ProxyConfig::parse("a:1:u:p:burst=2").is_err());
+
+        let mut manager = manager_with(&["a:1:u:p:max_concurrent=2:max_rpm=3", "b:2:u:p"]);
+        assert!(manager.start_request(&limited).is_ok());
+        assert!(manager.start_request(&limited).is_ok());
+        assert_eq!(manager.start_request(&limited), Err(CONCURRENCY_RETRY));
+        // The full proxy is passed over while another has room
+        for _ in 0..10 {
+            assert_eq!(
+                manager
+                    .get_random_proxy(SHARED_SESSION)
+                    .unwrap()
+                    .to_display_string(),
+                "b:2"
+            );
+        }
+
+        manager.finish_request(&limited);
+        assert!(manager.start_request(&limited).is_ok());
+        manager.finish_request(&limited);
+        manager.finish_request(&limited);
+        let wait = manager.start_request(&limited).unwrap_err();
+        assert!(wait > Duration::from_secs(59) && wait <= Duration::from_secs(60));
+
+        let unlimited = ProxyConfig::from_string("b:2:u:p").unwrap();
+        for _ in 0..10 {
+            assert!(manager.start_request(&unlimited).is_ok());
+        }
+    }
+
+    #[test]
+    // Requests are counted per proxy, and every loaded proxy has statistics, used or not.
+    fn test_proxy_stats() {
+        let mut manager = manager_with(&["a:1:u:p", "b:2:u:p"]);
+        let a = ProxyConfig::from_string("a:1:u:p").unwrap();
+        let outcome = |succeeded, latency_ms: Option<u64>| RequestOutcome {
+            succeeded,
+            latency: latency_ms.map(Duration::from_millis),
+            bytes_sent: 100,
+            bytes_received: 1_000,
+        };
+        manager.record_request(&a, &outcome(true, Some(100)));
+        manager.record_request(&a, &outcome(false, Some(300)));
+        manager.record_request(&a, &outcome(false, None));
+
+        let stats = manager.stats();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].proxy, "a:1");
+        assert_eq!((stats[0].successes, stats[0].failures), (1, 2));
+        assert_eq!(stats[0].average_latency_ms, Some(200.0));
+        assert_eq!((stats[0].bytes_sent, stats[0].bytes_received), (300, 3_000));
+        assert!(stats[0].last_used.is_some());
+        assert_eq!(stats[1], ProxyStats::new("b:2".to_string()));
+
+        // Proxies removed from the file keep their statistics
+        manager.record_request(
+            &ProxyConfig::from_string("c:3:u:p").unwrap(),
+            &outcome(true, Some(50)),
+        );
+        assert_eq!(manager.stats()[2].proxy, "c:3");
+    }
+
+    #[test]
+    // Invalid lines are counted and summarized once per load, without their credentials.
+    fn test_parse_report() {
+        let mut report = ProxyParseReport {
+            path: "proxies.txt".to_string(),
+            loaded: 2,
+            ..ProxyParseReport::default()
+        };
+        assert_eq!(report.summary(), "Loaded 2 proxies from proxies.txt");
+
+        let lines = [
+            "a:1:u:secret",
+            "b:x:u:secret",
+            "c:3:u",
+            "d:4:u:p",
+            "e:y:u:p",
+        ];
+        for (line_num, line) in lines.iter().enumerate() {
+            if let Err(reason) = ProxyConfig::parse(line) {
+                report.skip(line_num + 1, reason);
+            }
+        }
+        for line_num in 10..20 {
+            report.skip(line_num, "Invalid port in proxy");
+        }
+        assert_eq!(report.skipped, 13);
+        assert_eq!(report.examples.len(), MAX_PARSE_EXAMPLES);
+        assert_eq!(report.reasons["Invalid port in proxy"], 12);
+        let summary = report.summary();
+        assert!(summary.contains("skipped 13 invalid lines"));
+        assert!(summary.contains("first on lines 2, 3, 5, 10, 11"));
+        assert!(!summary.contains("secret"));
+    }
+
+    #[test]
+    // A large file parsed across threads keeps file order, line numbers and progress totals.
+    fn test_parallel_parse_keeps_order() {
+        let mut content = String::from("# proxies\n");
+        for i in 0..3000u32 {
+            if i % 1000 == 7 {
+                content.push_str("broken\n");
+            }
+            content.push_str(&format!("10.0.{}.{}:8080:u:p\n", i / 256, i % 256));
+        }
+        let progress = Mutex::new(Vec::new());
+        let parsed = parse_lines(&content, &ProxySecrets::default(), &|done, total| {
+            progress.lock().unwrap().push((done, total))
+        });
+
+        assert_eq!(parsed.len(), 3003);
+        let hosts: Vec<String> = parsed
+            .iter()
+            .filter_map(|(_, result)| result.as_ref().ok().map(|proxy| proxy.host.clone()))
+            .collect();
+        assert_eq!(hosts.len(), 3000);
+        assert_eq!(hosts[0], "10.0.0.0");
+        assert_eq!(hosts[2999], "10.0.11.183");
+        assert_eq!(parsed[7].0, 9);
+        assert!(parsed[7].1.is_err());
+        assert!(parsed.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        let progress = progress.into_inner().unwrap();
+        assert!(progress.contains(&(3003, 3003)));
+    }
+}
@@ -0,0 +1,220 @@
+//! Proxy file validation
+//!
+//! `proxy check` loads a proxy file the way the node does, showing progress on large files,
+//! and reports the lines it skips, including credential references that do not resolve (see
+//! `proxy_secrets`), and where each proxy's credentials came from, without printing any of
+//! them. It warns when credentials sit in a file other users can read. With `--reachable` it
+//! also connects to the orchestrator through every valid proxy, at most
+//! `MAX_CONCURRENT_CHECKS` at a time, and lists those that fail, so a pool of thousands of
+//! proxies can be cleaned up before the node starts.
+
+use crate::environment::Environment;
+use crate::orchestrator::OrchestratorClient;
+use crate::proxy::{MAX_CONCURRENT_CHECKS, ProxyConfig, read_proxy_file};
+use crate::proxy_secrets::{self, CredentialSource};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Progress of a long step, redrawn on one stderr line when stderr is a terminal.
+struct Progress {
+    label: &'static str,
+    shown: bool,
+    /// Percentage last drawn, to redraw only when it changes
+    last_percent: AtomicUsize,
+}
+
+impl Progress {
+    fn new(label: &'static str) -> Self {
+        Self {
+            label,
+            shown: std::io::stderr().is_terminal(),
+            last_percent: AtomicUsize::new(usize::MAX),
+        }
+    }
+
+    fn update(&self, done: usize, total: usize) {
+        if !self.shown || total == 0 {
+            return;
+        }
+        let percent = done * 100 / total;
+        if self.last_percent.swap(percent, Ordering::Relaxed) != percent {
+            let mut stderr = std::io::stderr().lock();
+            let _ = write!(stderr, "\r{} {}/{} ({}%)", self.label, done, total, percent);
+            let _ = stderr.flush();
+        }
+    }
+
+    /// Ends the progress line, if one was drawn.
+    fn finish(&self) {
+        if self.shown && self.last_percent.load(Ordering::Relaxed) != usize::MAX {
+            eprintln!();
+        }
+    }
+}
+
+/// How many proxies took their credentials from each source.
+fn credential_sources(proxies: &[ProxyConfig]) -> BTreeMap<CredentialSource, usize> {
+    let mut sources = BTreeMap::new();
+    for proxy in proxies {
+        *sources.entry(proxy.credentials).or_default() += 1;
+    }
+    sources
+}
+
+/// Prints where credentials came from, and warns about files others can read that hold them.
+fn print_credentials(proxy_file: &str, proxies: &[ProxyConfig]) {
+    let sources = credential_sources(proxies);
+    let counts: Vec<String> = sources
+        .iter()
+        .map(|(source, count)| format!("{} {}", count, source.label()))
+        .collect();
+    println!("Credentials: {}", counts.join(", "));
+
+    let proxy_path = Path::new(proxy_file);
+    if sources.contains_key(&CredentialSource::Inline) && proxy_secrets::is_exposed(proxy_path) {
+        println!(
+            "  warning: {} holds passwords and other users can read it; move them to the environment or proxy_secrets.toml",
+            proxy_file
+        );
+    }
+    let secrets_path = proxy_secrets::proxy_secrets_path(proxy_path);
+    if proxy_secrets::is_exposed(&secrets_path) {
+        println!(
+            "  warning: other users can read {}; run chmod 600 on it",
+            secrets_path.display()
+        );
+    }
+}
+
+/// The outcome of probing one proxy.
+type ProbeResult = (ProxyConfig, Result<Duration, String>);
+
+/// Probes every proxy through `client`, at most `MAX_CONCURRENT_CHECKS` at a time, in the
+/// order they finish.
+async fn probe_all(
+    client: Arc<OrchestratorClient>,
+    proxies: Vec<ProxyConfig>,
+    progress: &Progress,
+) -> Vec<ProbeResult> {
+    let total = proxies.len();
+    let limit = Arc::new(Semaphore::new(MAX_CONCURRENT_CHECKS));
+    let mut checks = JoinSet::new();
+    for proxy in proxies {
+        let client = client.clone();
+        let limit = limit.clone();
+        checks.spawn(async move {
+            let _permit = limit.acquire_owned().await;
+            let result = client.check_proxy(proxy.clone()).await;
+            (proxy, result)
+        });
+    }
+
+    let mut results = Vec::with_capacity(total);
+    while let Some(joined) = checks.join_next().await {
+        if let Ok(result) = joined {
+            results.push(result);
+        }
+        progress.update(results.len(), total);
+    }
+    progress.finish();
+    results
+}
+
+/// The median of the latencies of the proxies that answered.
+fn median_latency(results: &[ProbeResult]) -> Option<Duration> {
+    let mut latencies: Vec<Duration> = results
+        .iter()
+        .filter_map(|(_, result)| result.as_ref().ok().copied())
+        .collect();
+    latencies.sort();
+    latencies.get(latencies.len() / 2).copied()
+}
+
+/// Implements `proxy check`: validates the proxies in `proxy_file` and, if `reachable`,
+/// connects through each of them to the orchestrator of `environment`.
+pub async fn print_check(
+    proxy_file: &str,
+    reachable: bool,
+    environment: Environment,
+) -> Result<(), Box<dyn Error>> {
+    let progress = Progress::new("Validating proxies");
+    let loaded = read_proxy_file(proxy_file, &|done, total| progress.update(done, total));
+    progress.finish();
+    let loaded = loaded?;
+    println!("{}", loaded.report().summary());
+    if loaded.proxies().is_empty() {
+        return Err(format!("No valid proxies found in {}", proxy_file).into());
+    }
+    print_credentials(proxy_file, loaded.proxies());
+    if !reachable {
+        return Ok(());
+    }
+
+    let client = Arc::new(OrchestratorClient::new(environment));
+    let progress = Progress::new("Checking proxies");
+    let mut results = probe_all(client, loaded.proxies().to_vec(), &progress).await;
+    // Report in file order rather than the order the checks finished
+    let order: HashMap<String, usize> = loaded
+        .proxies()
+        .iter()
+        .enumerate()
+        .map(|(index, proxy)| (proxy.to_display_string(), index))
+        .collect();
+    results.sort_by_key(|(proxy, _)| order.get(&proxy.to_display_string()).copied());
+
+    let failed: Vec<_> = results
+        .iter()
+        .filter_map(|(proxy, result)| result.as_ref().err().map(|reason| (proxy, reason)))
+        .collect();
+    for (proxy, reason) in &failed {
+        println!("  unreachable  {}  {}", proxy.to_display_string(), reason);
+    }
+    let median = median_latency(&results)
+        .map(|latency| format!(", median latency {} ms", latency.as_millis()))
+        .unwrap_or_default();
+    println!(
+        "{} of {} proxies reached the orchestrator{}",
+        results.len() - failed.len(),
+        results.len(),
+        median
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // The median is taken over the proxies that answered only.
+    fn test_median_latency() {
+        let proxy = ProxyConfig::from_string("127.0.0.1:8080:user:pass").unwrap();
+        let results: Vec<ProbeResult> = vec![
+            (proxy.clone(), Ok(Duration::from_millis(300))),
+            (proxy.clone(), Err("timed out".to_string())),
+            (proxy.clone(), Ok(Duration::from_millis(100))),
+            (proxy.clone(), Ok(Duration::from_millis(200))),
+        ];
+        assert_eq!(median_latency(&results), Some(Duration::from_millis(200)));
+        assert_eq!(median_latency(&results[1..2]), None);
+    }
+
+    #[test]
+    // Proxies are counted by where their credentials came from.
+    fn test_credential_sources() {
+        let inline = ProxyConfig::from_string("127.0.0.1:8080:user:pass").unwrap();
+        let mut from_env = inline.clone();
+        from_env.credentials = CredentialSource::Environment;
+        let sources = credential_sources(&[inline.clone(), from_env, inline]);
+        assert_eq!(sources[&CredentialSource::Inline], 2);
+        assert_eq!(sources[&CredentialSource::Environment], 1);
+        assert!(!sources.contains_key(&CredentialSource::SecretsFile));
+    }
+}
@@ -0,0 +1,285 @@
+//! Proxy pool planning
+//!
+//! `proxy plan --nodes 20 --rate 60/min --limit 100/min` simulates how the node would spread
+//! its requests over the loaded proxy pool and warns which proxies are likely to exceed the
+//! provider's per-proxy rate limit, before the provider starts banning them.
+//!
+//! The simulation follows the client's rotation: a single node picks a random proxy for every
+//! request, while several nodes in one process each keep one randomly chosen proxy for the
+//! whole session. Pinned nodes can pile up on the same proxy, so the busiest proxy is usually
+//! well above the average load.
+
+use crate::proxy::{ProxyManager, read_proxy_file};
+use rand::Rng;
+use std::error::Error;
+use std::fmt::Display;
+
+/// Simulated runs; each run assigns proxies afresh.
+const RUNS: u32 = 100;
+
+/// A request rate, such as `60/min`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rate {
+    pub per_minute: f64,
+}
+
+impl Display for Rate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/min", (self.per_minute * 100.0).round() / 100.0)
+    }
+}
+
+/// Parses a rate such as `60/min`, `2/s`, `1000/h` or a plain number per minute.
+pub fn parse_rate(value: &str) -> Result<Rate, String> {
+    let value = value.trim();
+    let (number, unit) = value.split_once('/').unwrap_or((value, "min"));
+    let number: f64 = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid rate: {}", value))?;
+    let per_minute = match unit.trim() {
+        "s" | "sec" => number * 60.0,
+        "m" | "min" => number,
+        "h" | "hour" => number / 60.0,
+        _ => return Err(format!("unknown rate unit in {} (use s, min or h)", value)),
+    };
+    if !per_minute.is_finite() || per_minute <= 0.0 {
+        return Err(format!("rate must be positive: {}", value));
+    }
+    Ok(Rate { per_minute })
+}
+
+/// How requests are spread over the proxies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+    /// A random proxy for every request.
+    PerRequest,
+    /// Each node keeps one random proxy for its session.
+    PinnedPerNode,
+}
+
+impl Rotation {
+    /// The rotation `start` uses for this many nodes.
+    pub fn for_nodes(nodes: usize) -> Self {
+        if nodes > 1 {
+            Rotation::PinnedPerNode
+        } else {
+            Rotation::PerRequest
+        }
+    }
+}
+
+impl Display for Rotation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rotation::PerRequest => write!(f, "a random proxy for every request"),
+            Rotation::PinnedPerNode => write!(f, "each node keeps one proxy for its session"),
+        }
+    }
+}
+
+/// The load one proxy saw across all runs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProxyLoad {
+    /// Most requests in any one minute
+    pub peak_per_minute: u32,
+    /// Runs in which it went over the limit
+    pub runs_over_limit: u32,
+}
+
+/// A planned deployment to check against a proxy pool.
+#[derive(Debug, Clone, Copy)]
+pub struct Plan {
+    pub nodes: usize,
+    /// Requests per node
+    pub rate: Rate,
+    /// The provider's limit per proxy
+    pub limit: Rate,
+    pub minutes: u32,
+}
+
+impl Plan {
+    /// The load every proxy would see if requests were spread perfectly evenly, or `None`
+    /// without proxies.
+    pub fn average_load(&self, proxies: usize) -> Option<f64> {
+        (proxies > 0).then(|| self.nodes as f64 * self.rate.per_minute / proxies as f64)
+    }
+
+    /// The fewest proxies that could carry the load without exceeding the limit.
+    pub fn min_proxies(&self) -> usize {
+        (self.nodes as f64 * self.rate.per_minute / self.limit.per_minute).ceil() as usize
+    }
+
+    /// Simulate `runs` sessions over `proxies` proxies, returning the load of each proxy.
+    pub fn simulate(&self, proxies: usize, runs: u32, rng: &mut impl Rng) -> Vec<ProxyLoad> {
+        let mut loads = vec![ProxyLoad::default(); proxies];
+        if proxies == 0 {
+            return loads;
+        }
+        let minutes = self.minutes.max(1) as usize;
+        let interval = 1.0 / self.rate.per_minute;
+        let rotation = Rotation::for_nodes(self.nodes);
+        for _ in 0..runs {
+            let mut counts = vec![0u32; proxies * minutes];
+            for _ in 0..self.nodes {
+                let pinned = rng.gen_range(0..proxies);
+                // Nodes start at random offsets, then request at a steady rate
+                let mut at = rng.gen_range(0.0..interval);
+                while at < minutes as f64 {
+                    let proxy = match rotation {
+                        Rotation::PinnedPerNode => pinned,
+                        Rotation::PerRequest => rng.gen_range(0..proxies),
+                    };
+                    counts[proxy * minutes + at as usize] += 1;
+                    at += interval;
+                }
+            }
+            for (proxy, load) in loads.iter_mut().enumerate() {
+                let peak = counts[proxy * minutes..(proxy + 1) * minutes]
+                    .iter()
+                    .copied()
+                    .max()
+                    .unwrap_or(0);
+                load.peak_per_minute = load.peak_per_minute.max(peak);
+                if peak as f64 > self.limit.per_minute {
+                    load.runs_over_limit += 1;
+                }
+            }
+        }
+        loads
+    }
+}
+
+/// Implements `proxy plan`, simulating the plan over the proxies in `proxy_file`.
+pub fn print_plan(plan: &Plan, proxy_file: &str) -> Result<(), Box<dyn Error>> {
+    if plan.nodes == 0 {
+        return Err("--nodes must be at least 1".into());
+    }
+    let proxies = {
+        let mut manager = ProxyManager::new(proxy_file);
+        manager.install(read_proxy_file(proxy_file, &|_, _| {})?)?;
+        if let Some(report) = manager.take_parse_report() {
+            println!("{}", report.summary());
+        }
+        manager.usable_proxies(usize::MAX)
+    };
+    let Some(average_load) = plan.average_load(proxies.len()) else {
+        return Err(format!("No usable proxies in {}", proxy_file).into());
+    };
+
+    let loads = crate::rng::with_rng(|rng| plan.simulate(proxies.len(), RUNS, rng));
+    println!(
+        "Simulated {} node(s) at {} each through {} proxies for {} minutes ({} runs)",
+        plan.nodes,
+        plan.rate,
+        proxies.len(),
+        plan.minutes,
+        RUNS
+    );
+    println!("Rotation: {}", Rotation::for_nodes(plan.nodes));
+    println!(
+        "Average load: {:.1}/min per proxy, provider limit {}",
+        average_load, plan.limit
+    );
+    if plan.min_proxies() > proxies.len() {
+        println!(
+            "⚠️  Even spread perfectly evenly, this load needs at least {} proxies",
+            plan.min_proxies()
+        );
+    }
+    println!();
+
+    let mut over: Vec<_> = proxies
+        .iter()
+        .zip(&loads)
+        .filter(|(_, load)| load.runs_over_limit > 0)
+        .collect();
+    if over.is_empty() {
+        println!(
+            "✅ No proxy exceeded {} (busiest minute: {} requests)",
+            plan.limit,
+            loads
+                .iter()
+                .map(|load| load.peak_per_minute)
+                .max()
+                .unwrap_or(0)
+        );
+        return Ok(());
+    }
+    over.sort_by_key(|(_, load)| std::cmp::Reverse(load.runs_over_limit));
+    for (proxy, load) in &over {
+        println!(
+            "⚠️  {:<24} peak {}/min, over the limit in {}% of runs",
+            proxy.to_display_string(),
+            load.peak_per_minute,
+            load.runs_over_limit * 100 / RUNS
+        );
+    }
+    println!(
+        "\n{} of {} proxies may exceed {}; add proxies or lower the request rate.",
+        over.len(),
+        proxies.len(),
+        plan.limit
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn plan(nodes: usize, rate: &str, limit: &str) -> Plan {
+        Plan {
+            nodes,
+            rate: parse_rate(rate).unwrap(),
+            limit: parse_rate(limit).unwrap(),
+            minutes: 10,
+        }
+    }
+
+    #[test]
+    // Rates accept a unit, defaulting to per minute.
+    fn test_parse_rate() {
+        assert_eq!(parse_rate("60/min").unwrap().per_minute, 60.0);
+        assert_eq!(parse_rate("2/s").unwrap().per_minute, 120.0);
+        assert_eq!(parse_rate("120/h").unwrap().per_minute, 2.0);
+        assert_eq!(parse_rate("30").unwrap().per_minute, 30.0);
+        assert!(parse_rate("0/min").is_err());
+        assert!(parse_rate("60/day").is_err());
+        assert!(parse_rate("fast").is_err());
+    }
+
+    #[test]
+    // Two pinned nodes on a single proxy always double its load.
+    fn test_pinned_nodes_overload_shared_proxy() {
+        let plan = plan(2, "60/min", "100/min");
+        let loads = plan.simulate(1, 20, &mut StdRng::seed_from_u64(1));
+        assert_eq!(loads[0].runs_over_limit, 20);
+        assert!(loads[0].peak_per_minute >= 119);
+        assert_eq!(plan.min_proxies(), 2);
+    }
+
+    #[test]
+    // A single node rotating per request stays well under a generous limit.
+    fn test_per_request_rotation_spreads_load() {
+        let plan = plan(1, "60/min", "30/min");
+        let loads = plan.simulate(10, 20, &mut StdRng::seed_from_u64(1));
+        assert!(loads.iter().all(|load| load.runs_over_limit == 0));
+        assert!((plan.average_load(10).unwrap() - 6.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    // Plans without nodes or proxies are refused rather than divided by zero.
+    fn test_empty_plans_are_refused() {
+        assert_eq!(plan(1, "60/min", "30/min").average_load(0), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        let proxy_file = dir.path().join("proxies.txt");
+        std::fs::write(&proxy_file, "").unwrap();
+        let proxy_file = proxy_file.to_str().unwrap();
+        assert!(print_plan(&plan(0, "60/min", "30/min"), proxy_file).is_err());
+        assert!(print_plan(&plan(1, "60/min", "30/min"), proxy_file).is_err());
+    }
+}
@@ -0,0 +1,346 @@
+//! Proxy exit IP pre-check
+//!
+//! The orchestrator rejects requests from some address ranges, typically datacenters. With
+//! `--proxy-blocklist <FILE>`, each proxy's exit IP is looked up before the proxy is used, and
+//! proxies exiting from a listed network or autonomous system never enter the rotation. The
+//! file lists one entry per line: an address (`203.0.113.7`), a network (`198.51.100.0/24`,
+//! `2001:db8::/32`) or an AS number (`AS16509`). Blank lines and `#` comments are ignored.
+//!
+//! Proxies are only used once their exit is known and allowed; lookups that fail are retried
+//! on the next pass, and proxies added to the proxy file are checked as they appear.
+//!
+//! Looking up an exit sends its address to a third party, so the pre-check also needs
+//! `--proxy-exit-lookup [URL]`: without a URL, ipinfo.io is asked; any service answering with
+//! ipinfo's JSON (`ip`, and `org` starting with the AS number) can be used instead, including
+//! a self-hosted one. Nothing is looked up without both flags.
+
+use crate::error_classifier::LogLevel;
+use crate::events::{Event, EventType};
+use crate::proxy::{ProxyConfig, ProxyContext};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::future::Future;
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Semaphore, broadcast, mpsc};
+use tokio::task::{JoinHandle, JoinSet};
+
+/// Time between passes over proxies whose exit is not known yet
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Most exit lookups made at the same time
+const MAX_CONCURRENT_LOOKUPS: usize = 16;
+
+/// Used by `--proxy-exit-lookup` without a URL. Reports the address (and AS) a request came
+/// from.
+pub const DEFAULT_EXIT_LOOKUP_URL: &str = "https://ipinfo.io/json";
+
+/// Where a proxy's traffic leaves to the internet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExitInfo {
+    pub ip: IpAddr,
+    /// Autonomous system of the address, if known
+    pub asn: Option<u32>,
+}
+
+impl ExitInfo {
+    /// Parses an ipinfo.io response, e.g. `{"ip": "203.0.113.7", "org": "AS16509 Amazon.com"}`.
+    pub fn from_ipinfo(json: &str) -> Result<Self, String> {
+        #[derive(Deserialize)]
+        struct IpInfo {
+            ip: String,
+            #[serde(default)]
+            org: Option<String>,
+        }
+        let info: IpInfo = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        let ip = info
+            .ip
+            .parse()
+            .map_err(|_| format!("invalid exit IP '{}'", info.ip))?;
+        let asn = info
+            .org
+            .as_deref()
+            .and_then(|org| org.split_whitespace().next())
+            .and_then(parse_asn);
+        Ok(Self { ip, asn })
+    }
+}
+
+impl std::fmt::Display for ExitInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.asn {
+            Some(asn) => write!(f, "{} (AS{})", self.ip, asn),
+            None => write!(f, "{}", self.ip),
+        }
+    }
+}
+
+/// Whether a proxy may be used, once its exit is known
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reputation {
+    Allowed,
+    /// Blocked by this blocklist entry
+    Blocked(String),
+}
+
+fn parse_asn(value: &str) -> Option<u32> {
+    let digits = value
+        .strip_prefix("AS")
+        .or_else(|| value.strip_prefix("as"))?;
+    digits.parse().ok()
+}
+
+/// A network, as an address and prefix length
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Network {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl Network {
+    fn parse(value: &str) -> Option<Self> {
+        let (addr, prefix) = match value.split_once('/') {
+            Some((addr, prefix)) => (addr.parse::<IpAddr>().ok()?, Some(prefix.parse().ok()?)),
+            None => (value.parse::<IpAddr>().ok()?, None),
+        };
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = prefix.unwrap_or(max);
+        (prefix <= max).then_some(Self { addr, prefix })
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        let (network, ip, bits) = match (self.addr, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                (u32::from(network) as u128, u32::from(ip) as u128, 32)
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => (u128::from(network), u128::from(ip), 128),
+            _ => return false,
+        };
+        let shift = bits - u32::from(self.prefix);
+        if shift >= 128 {
+            return true;
+        }
+        (network >> shift) == (ip >> shift)
+    }
+}
+
+impl std::fmt::Display for Network {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+/// Networks and autonomous systems whose proxies are not used
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Blocklist {
+    networks: Vec<Network>,
+    asns: HashSet<u32>,
+}
+
+impl Blocklist {
+    /// Loads a blocklist file.
+    ///
+    /// # Errors
+    /// Returns a description of the first invalid line, or why the file cannot be read.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        Self::parse(&content).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Parses blocklist entries, one per line.
+    ///
+    /// # Errors
+    /// Returns the number and content of the first invalid line.
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let mut blocklist = Self::default();
+        for (index, line) in content.lines().enumerate() {
+            let entry = line.split('#').next().unwrap_or_default().trim();
+            if entry.is_empty() {
+                continue;
+            }
+            if let Some(asn) = parse_asn(entry) {
+                blocklist.asns.insert(asn);
+            } else if let Some(network) = Network::parse(entry) {
+                blocklist.networks.push(network);
+            } else {
+                return Err(format!(
+                    "line {}: '{}' is not an address, network or AS number",
+                    index + 1,
+                    entry
+                ));
+            }
+        }
+        Ok(blocklist)
+    }
+
+    /// Number of entries.
+    pub fn entry_count(&self) -> usize {
+        self.networks.len() + self.asns.len()
+    }
+
+    /// Whether a proxy exiting at `exit` may be used.
+    pub fn check(&self, exit: &ExitInfo) -> Reputation {
+        if let Some(asn) = exit.asn.filter(|asn| self.asns.contains(asn)) {
+            return Reputation::Blocked(format!("AS{}", asn));
+        }
+        match self
+            .networks
+            .iter()
+            .find(|network| network.contains(exit.ip))
+        {
+            Some(network) => Reputation::Blocked(network.to_string()),
+            None => Reputation::Allowed,
+        }
+    }
+}
+
+/// Looks up the exit of the proxies of `proxies` not checked yet, until shutdown. `lookup`
+/// connects through a proxy and returns its exit, or why it could not be found.
+pub fn spawn_checker<F, Fut>(
+    blocklist: Arc<Blocklist>,
+    proxies: Arc<ProxyContext>,
+    lookup: F,
+    event_sender: mpsc::Sender<Event>,
+    mut shutdown: broadcast::Receiver<()>,
+) -> JoinHandle<()>
+where
+    F: Fn(ProxyConfig) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<ExitInfo, String>> + Send + 'static,
+{
+    let lookup = Arc::new(lookup);
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = shutdown.recv() => break,
+                _ = check_unverified(&blocklist, &proxies, lookup.clone(), &event_sender) => {}
+            }
+            tokio::select! {
+                _ = shutdown.recv() => break,
+                _ = tokio::time::sleep(CHECK_INTERVAL) => {}
+            }
+        }
+    })
+}
+
+/// Looks up every proxy without a verdict once, reporting the blocked ones.
+async fn check_unverified<F, Fut>(
+    blocklist: &Blocklist,
+    context: &ProxyContext,
+    lookup: Arc<F>,
+    event_sender: &mpsc::Sender<Event>,
+) where
+    F: Fn(ProxyConfig) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<ExitInfo, String>> + Send + 'static,
+{
+    let proxies = context
+        .manager()
+        .lock()
+        .map(|mut manager| manager.unverified_proxies())
+        .unwrap_or_default();
+    let limit = Arc::new(Semaphore::new(MAX_CONCURRENT_LOOKUPS));
+    let mut lookups = JoinSet::new();
+    for proxy in proxies {
+        let lookup = lookup.clone();
+        let limit = limit.clone();
+        lookups.spawn(async move {
+            let _permit = limit.acquire_owned().await;
+            let result = lookup(proxy.clone()).await;
+            (proxy, result)
+        });
+    }
+
+    while let Some(Ok((proxy, result))) = lookups.join_next().await {
+        // Unknown exits stay out of the rotation and are looked up again next pass
+        let Ok(exit) = result else {
+            continue;
+        };
+        let reputation = blocklist.check(&exit);
+        if let Ok(mut manager) = context.manager().lock() {
+            manager.record_reputation(&proxy, reputation.clone());
+        }
+        if let Reputation::Blocked(entry) = reputation {
+            let _ = event_sender
+                .send(
+                    Event::task_fetcher_with_level(
+                        format!(
+                            "Proxy {} exits from {}, blocked by {}; it will not be used",
+                            proxy.to_display_string(),
+                            exit,
+                            entry
+                        ),
+                        EventType::Refresh,
+                        LogLevel::Warn,
+                    )
+                    .in_module("proxy")
+                    .with_proxy(&proxy.to_display_string()),
+                )
+                .await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Addresses, networks and AS numbers block matching exits; other exits are allowed.
+    fn test_blocklist() {
+        let blocklist = Blocklist::parse(
+            "# datacenters\n203.0.113.7\n198.51.100.0/24  # hosting\n2001:db8::/32\nAS16509\n\n",
+        )
+        .unwrap();
+        assert_eq!(blocklist.entry_count(), 4);
+
+        let exit = |ip: &str, asn: Option<u32>| ExitInfo {
+            ip: ip.parse().unwrap(),
+            asn,
+        };
+        assert_eq!(
+            blocklist.check(&exit("203.0.113.7", None)),
+            Reputation::Blocked("203.0.113.7/32".to_string())
+        );
+        assert_eq!(
+            blocklist.check(&exit("198.51.100.200", None)),
+            Reputation::Blocked("198.51.100.0/24".to_string())
+        );
+        assert_eq!(
+            blocklist.check(&exit("2001:db8::1", None)),
+            Reputation::Blocked("2001:db8::/32".to_string())
+        );
+        assert_eq!(
+            blocklist.check(&exit("192.0.2.1", Some(16509))),
+            Reputation::Blocked("AS16509".to_string())
+        );
+        assert_eq!(
+            blocklist.check(&exit("198.51.101.1", Some(64500))),
+            Reputation::Allowed
+        );
+
+        assert!(Blocklist::parse("10.0.0.0/33").is_err());
+        assert!(Blocklist::parse("not-an-ip").is_err());
+        assert!(
+            Network::parse("0.0.0.0/0")
+                .unwrap()
+                .contains("8.8.8.8".parse().unwrap())
+        );
+    }
+
+    #[test]
+    // The exit IP and AS number are read from an ipinfo.io response.
+    fn test_exit_from_ipinfo() {
+        let exit =
+            ExitInfo::from_ipinfo(r#"{"ip": "203.0.113.7", "org": "AS16509 Amazon.com, Inc."}"#)
+                .unwrap();
+        assert_eq!(exit.ip, "203.0.113.7".parse::<IpAddr>().unwrap());
+        assert_eq!(exit.asn, Some(16509));
+        assert_eq!(exit.to_string(), "203.0.113.7 (AS16509)");
+
+        let exit = ExitInfo::from_ipinfo(r#"{"ip": "192.0.2.1"}"#).unwrap();
+        assert_eq!(exit.asn, None);
+        assert!(ExitInfo::from_ipinfo(r#"{"ip": "bogus"}"#).is_err());
+    }
+}
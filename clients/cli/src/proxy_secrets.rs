@@ -0,0 +1,208 @@
+//! Proxy credentials kept out of the proxy file
+//!
+//! `proxies.txt` is often readable by every user of the machine, so a line may name where its
+//! credentials are instead of holding them:
+//!
+//! ```text
+//! us1.example.com:8080:ENV:PROXY_USER_1
+//! eu1.example.com:8080:SECRET:eu-pool
+//! ```
+//!
+//! `ENV:NAME` reads `username:password` from the environment variable `NAME`. `SECRET:NAME`
+//! reads the `NAME` entry of `proxy_secrets.toml` next to the proxy file, which should be
+//! readable by its owner only:
+//!
+//! ```toml
+//! [eu-pool]
+//! username = "alice"
+//! password = "..."
+//! ```
+//!
+//! Lines whose credentials cannot be resolved are skipped like any other invalid line, and no
+//! error message repeats a secret. `proxy check` shows where each proxy's credentials came
+//! from. `ENV` and `SECRET` are therefore not usable as literal user names.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use toml_edit::DocumentMut;
+
+/// User name that makes a proxy line take its credentials from the environment
+const ENV_REFERENCE: &str = "ENV";
+
+/// User name that makes a proxy line take its credentials from the secrets file
+const SECRET_REFERENCE: &str = "SECRET";
+
+/// Path to the secrets file used with the proxy file at `proxy_file`.
+pub fn proxy_secrets_path(proxy_file: &Path) -> PathBuf {
+    proxy_file.with_file_name("proxy_secrets.toml")
+}
+
+/// Where a proxy's credentials came from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CredentialSource {
+    /// Written in the proxy file
+    #[default]
+    Inline,
+    Environment,
+    SecretsFile,
+}
+
+impl CredentialSource {
+    /// Where the credentials came from, as `proxy check` reports it
+    pub fn label(self) -> &'static str {
+        match self {
+            CredentialSource::Inline => "in the proxy file",
+            CredentialSource::Environment => "from the environment",
+            CredentialSource::SecretsFile => "from proxy_secrets.toml",
+        }
+    }
+}
+
+/// Credentials by name, from the secrets file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProxySecrets {
+    entries: BTreeMap<String, (String, String)>,
+}
+
+impl ProxySecrets {
+    /// Reads the secrets file at `path`; no file is no secrets.
+    ///
+    /// # Errors
+    /// Returns an `std::io::Error` if the file cannot be read or is not a valid secrets file.
+    pub fn load(path: &Path) -> Result<Self, io::Error> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => {
+                Self::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Parses a secrets file, or says what is wrong with it without quoting it.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let document = text.parse::<DocumentMut>().map_err(|e| {
+            // The parser's message quotes the offending line, which may hold a password
+            let line = e
+                .span()
+                .map(|span| text[..span.start.min(text.len())].lines().count().max(1));
+            match line {
+                Some(line) => format!("not valid TOML (line {})", line),
+                None => "not valid TOML".to_string(),
+            }
+        })?;
+        let mut secrets = Self::default();
+        for (name, item) in document.iter() {
+            let field = |key: &str| {
+                item.get(key)
+                    .and_then(|value| value.as_str())
+                    .map(str::to_string)
+            };
+            let (Some(username), Some(password)) = (field("username"), field("password")) else {
+                return Err(format!("{} needs a username and a password", name));
+            };
+            secrets
+                .entries
+                .insert(name.to_string(), (username, password));
+        }
+        Ok(secrets)
+    }
+
+    /// The credentials a proxy line names with `kind:name`, or `None` if `kind` is an ordinary
+    /// user name.
+    pub fn resolve(
+        &self,
+        kind: &str,
+        name: &str,
+    ) -> Option<Result<(String, String, CredentialSource), &'static str>> {
+        match kind {
+            ENV_REFERENCE => Some(
+                from_env(std::env::var(name).ok()).map(|(username, password)| {
+                    (username, password, CredentialSource::Environment)
+                }),
+            ),
+            SECRET_REFERENCE => Some(
+                self.entries
+                    .get(name)
+                    .map(|(username, password)| {
+                        (
+                            username.clone(),
+                            password.clone(),
+                            CredentialSource::SecretsFile,
+                        )
+                    })
+                    .ok_or("Credential name not found in proxy_secrets.toml"),
+            ),
+            _ => None,
+        }
+    }
+}
+
+/// Splits the `username:password` value of a credential environment variable.
+fn from_env(value: Option<String>) -> Result<(String, String), &'static str> {
+    let value = value.ok_or("Credential environment variable is not set")?;
+    value
+        .split_once(':')
+        .map(|(username, password)| (username.to_string(), password.to_string()))
+        .ok_or("Credential environment variable must hold username:password")
+}
+
+/// Whether users other than the owner may read the file at `path`.
+#[cfg(unix)]
+pub fn is_exposed(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|metadata| metadata.permissions().mode() & 0o077 != 0)
+}
+
+/// Whether users other than the owner may read the file at `path`.
+#[cfg(not(unix))]
+pub fn is_exposed(_path: &Path) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Entries resolve by name; a broken file is reported without quoting its contents.
+    fn test_parse_secrets() {
+        let secrets = ProxySecrets::parse(
+            "[eu-pool]\nusername = \"alice\"\npassword = \"hunter2\"\n\n[us-pool]\nusername = \"bob\"\npassword = \"pa:ss\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            secrets.resolve("SECRET", "us-pool"),
+            Some(Ok((
+                "bob".to_string(),
+                "pa:ss".to_string(),
+                CredentialSource::SecretsFile
+            )))
+        );
+        assert!(secrets.resolve("SECRET", "missing").unwrap().is_err());
+        assert_eq!(secrets.resolve("alice", "hunter2"), None);
+
+        let error = ProxySecrets::parse("[eu-pool]\npassword = hunter2\n").unwrap_err();
+        assert!(error.contains("line 2"));
+        assert!(!error.contains("hunter2"));
+        assert!(ProxySecrets::parse("[eu-pool]\nusername = \"alice\"\n").is_err());
+    }
+
+    #[test]
+    // ENV references hold username:password; the password may contain colons.
+    fn test_env_value() {
+        assert_eq!(
+            from_env(Some("carol:s3:cret".to_string())),
+            Ok(("carol".to_string(), "s3:cret".to_string()))
+        );
+        assert!(from_env(Some("carol".to_string())).is_err());
+        assert!(from_env(None).is_err());
+        assert!(
+            ProxySecrets::default()
+                .resolve("ENV", "NEXUS_TEST_PROXY_USER_UNSET")
+                .unwrap()
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,152 @@
+//! Per-proxy request statistics
+//!
+//! The node counts every orchestrator request by the proxy it went through: how many
+//! succeeded (answered with a non-error status) and failed (a transport error or an error
+//! status), their average latency, the bytes sent and received, and when the proxy was last
+//! used. `nexus-network proxy stats` asks the running node for them, to tell which proxies are
+//! burning tasks. Received bytes are taken from `Content-Length`, so bodies without one count
+//! as empty.
+
+use crate::control::{Command, send_command};
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::time::Duration;
+
+/// Requests through one proxy since the node started.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ProxyStats {
+    /// The proxy, without credentials
+    pub proxy: String,
+    pub successes: u64,
+    pub failures: u64,
+    /// Average time until the response arrived, in milliseconds, if any request got one
+    pub average_latency_ms: Option<f64>,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    /// When the proxy was last used (RFC 3339)
+    pub last_used: Option<String>,
+    /// Requests counted in the average latency
+    #[serde(skip)]
+    latency_samples: u64,
+}
+
+/// The outcome of one request through a proxy.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RequestOutcome {
+    pub succeeded: bool,
+    /// Time until the response arrived, if one did
+    pub latency: Option<Duration>,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+}
+
+impl ProxyStats {
+    /// Empty statistics for `proxy`.
+    pub fn new(proxy: String) -> Self {
+        Self {
+            proxy,
+            ..Self::default()
+        }
+    }
+
+    /// Counts a request.
+    pub fn record(&mut self, outcome: &RequestOutcome) {
+        if outcome.succeeded {
+            self.successes += 1;
+        } else {
+            self.failures += 1;
+        }
+        if let Some(latency) = outcome.latency {
+            let latency_ms = latency.as_secs_f64() * 1000.0;
+            self.latency_samples += 1;
+            self.average_latency_ms = Some(match self.average_latency_ms {
+                Some(average) => average + (latency_ms - average) / self.latency_samples as f64,
+                None => latency_ms,
+            });
+        }
+        self.bytes_sent += outcome.bytes_sent;
+        self.bytes_received += outcome.bytes_received;
+        self.last_used = Some(Utc::now().to_rfc3339());
+    }
+}
+
+/// The statistics of a running node, as printed by `proxy stats --json`.
+#[derive(Serialize, Debug)]
+struct ProxyStatsReport {
+    /// Label of the environment the node works for, if it reports one
+    environment: Option<String>,
+    proxies: Vec<ProxyStats>,
+}
+
+/// Prints the statistics of the node running on this machine, as pretty JSON if `json` is set.
+pub async fn print_stats(json: bool) -> Result<(), Box<dyn Error>> {
+    let response = send_command(Command::ProxyStats).await.map_err(|e| {
+        format!(
+            "No running node found ({}). Start one with `nexus-network start`.",
+            e
+        )
+    })?;
+    if !response.ok {
+        return Err(response.message.into());
+    }
+    let stats = ProxyStatsReport {
+        environment: response.status.and_then(|status| status.environment),
+        proxies: response.proxy_stats.unwrap_or_default(),
+    };
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(());
+    }
+    if stats.proxies.is_empty() {
+        println!("The node has no proxies loaded.");
+        return Ok(());
+    }
+
+    println!(
+        "[{}]",
+        stats
+            .environment
+            .as_deref()
+            .unwrap_or("unknown environment")
+    );
+    println!(
+        "{:<32} {:>8} {:>8} {:>9} {:>10} {:>10}  {}",
+        "Proxy", "OK", "Failed", "Latency", "Sent", "Received", "Last used"
+    );
+    for proxy in &stats.proxies {
+        let latency = proxy
+            .average_latency_ms
+            .map(|ms| format!("{:.0}ms", ms))
+            .unwrap_or_else(|| "-".to_string());
+        let last_used = proxy
+            .last_used
+            .as_deref()
+            .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+            .map(|time| {
+                time.with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .unwrap_or_else(|| "never".to_string());
+        println!(
+            "{:<32} {:>8} {:>8} {:>9} {:>10} {:>10}  {}",
+            proxy.proxy,
+            proxy.successes,
+            proxy.failures,
+            latency,
+            format_bytes(proxy.bytes_sent),
+            format_bytes(proxy.bytes_received),
+            last_used
+        );
+    }
+    Ok(())
+}
+
+fn format_bytes(bytes: u64) -> String {
+    match bytes {
+        0..1_024 => format!("{} B", bytes),
+        1_024..1_048_576 => format!("{:.1} KiB", bytes as f64 / 1_024.0),
+        _ => format!("{:.1} MiB", bytes as f64 / 1_048_576.0),
+    }
+}
@@ -0,0 +1,337 @@
+//! Submission receipts
+//!
+//! Support for an orchestrator acknowledging an accepted proof with a signed receipt: an
+//! Ed25519 signature, by the orchestrator's key, over the task, the proof hash, the node's
+//! public key and the time of acceptance. No orchestrator issues receipts yet. The format
+//! below is the one this client reads, pending one the orchestrator actually publishes; until
+//! then no environment pins a receipt key (see `Environment::receipt_key`) and nothing is
+//! kept.
+//!
+//! ```text
+//! x-nexus-receipt-key: <orchestrator public key, hex>
+//! x-nexus-receipt-accepted-at: 2026-10-15T14:05:03Z
+//! x-nexus-receipt-signature: <signature, hex>
+//! ```
+//!
+//! The signed message is
+//! `receipt-v0 | <task ID> | <proof hash> | <node public key, hex> | <accepted at>`.
+//! A receipt is only kept if it is signed by the key pinned for the environment: a receipt
+//! that carries its own key proves nothing, as anyone can sign one. `submit_proof` checks
+//! it on arrival; those that pass are kept with the committed entry in the submission
+//! journal, and appended to `receipts.ndjson` next to it, which is never compacted: it is the
+//! operator's evidence of what the orchestrator accepted, for reward disputes.
+//! `nexus receipts verify` checks them again offline, against the same pinned key or one
+//! given on the command line.
+
+use crate::environment::Environment;
+use crate::pretty::print_cmd_info;
+use crate::submission_journal::journal_dir;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// File name of the receipt archive within the journal directory.
+const RECEIPTS_FILE: &str = "receipts.ndjson";
+
+const KEY_HEADER: &str = "x-nexus-receipt-key";
+const ACCEPTED_AT_HEADER: &str = "x-nexus-receipt-accepted-at";
+const SIGNATURE_HEADER: &str = "x-nexus-receipt-signature";
+
+/// The orchestrator's signed acknowledgment of an accepted submission.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SubmissionReceipt {
+    pub task_id: String,
+    pub proof_hash: String,
+    /// The key the node signed the submission with, hex
+    pub node_key: String,
+    /// When the orchestrator accepted the proof, as it reported it
+    pub accepted_at: String,
+    /// The orchestrator's public key, hex
+    pub orchestrator_key: String,
+    /// Signature over `message()` by `orchestrator_key`, hex
+    pub signature: String,
+}
+
+impl SubmissionReceipt {
+    /// The receipt in the headers of a submission response, if the orchestrator sent one.
+    pub fn from_headers(
+        headers: &HeaderMap,
+        task_id: &str,
+        proof_hash: &str,
+        node_key: &VerifyingKey,
+    ) -> Option<Self> {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.trim().to_string())
+        };
+        Some(Self {
+            task_id: task_id.to_string(),
+            proof_hash: proof_hash.to_string(),
+            node_key: to_hex(node_key.as_bytes()),
+            accepted_at: header(ACCEPTED_AT_HEADER)?,
+            orchestrator_key: header(KEY_HEADER)?.to_lowercase(),
+            signature: header(SIGNATURE_HEADER)?.to_lowercase(),
+        })
+    }
+
+    /// The message the orchestrator signs.
+    fn message(&self) -> String {
+        format!(
+            "receipt-v0 | {} | {} | {} | {}",
+            self.task_id, self.proof_hash, self.node_key, self.accepted_at
+        )
+    }
+
+    /// Checks that the receipt is signed by `trusted_key`, the orchestrator's pinned key.
+    pub fn verify(&self, trusted_key: &str) -> Result<(), String> {
+        if !trusted_key.eq_ignore_ascii_case(&self.orchestrator_key) {
+            return Err(format!(
+                "signed by {}, not the trusted key",
+                self.orchestrator_key
+            ));
+        }
+        let key_bytes: [u8; 32] = from_hex(&self.orchestrator_key)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or("the orchestrator key is not a 32-byte hex string")?;
+        let key = VerifyingKey::from_bytes(&key_bytes)
+            .map_err(|_| "the orchestrator key is not a valid Ed25519 key")?;
+        let signature_bytes: [u8; 64] = from_hex(&self.signature)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or("the signature is not a 64-byte hex string")?;
+        key.verify(
+            self.message().as_bytes(),
+            &Signature::from_bytes(&signature_bytes),
+        )
+        .map_err(|_| "the signature does not match".to_string())
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Path of the receipt archive within the journal directory.
+pub fn archive_path(journal_dir: &Path) -> PathBuf {
+    journal_dir.join(RECEIPTS_FILE)
+}
+
+/// Appends a receipt to the archive and flushes it to disk.
+pub fn archive(journal_dir: &Path, receipt: &SubmissionReceipt) -> std::io::Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(archive_path(journal_dir))?;
+    let mut line = serde_json::to_string(receipt)?;
+    line.push('\n');
+    file.write_all(line.as_bytes())?;
+    file.sync_data()
+}
+
+/// The outcome of checking one archived receipt.
+#[derive(Serialize, Debug)]
+struct Checked {
+    task_id: String,
+    accepted_at: String,
+    valid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Checks every receipt in `contents`, one JSON receipt per line; unreadable lines are invalid.
+fn check_all(contents: &str, trusted_key: &str, task_id: Option<&str>) -> Vec<Checked> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .filter_map(
+            |(index, line)| match serde_json::from_str::<SubmissionReceipt>(line) {
+                Ok(receipt) => {
+                    if task_id.is_some_and(|id| id != receipt.task_id) {
+                        return None;
+                    }
+                    let result = receipt.verify(trusted_key);
+                    Some(Checked {
+                        task_id: receipt.task_id,
+                        accepted_at: receipt.accepted_at,
+                        valid: result.is_ok(),
+                        error: result.err(),
+                    })
+                }
+                Err(e) => task_id.is_none().then(|| Checked {
+                    task_id: format!("(line {})", index + 1),
+                    accepted_at: String::new(),
+                    valid: false,
+                    error: Some(format!("not a receipt: {}", e)),
+                }),
+            },
+        )
+        .collect()
+}
+
+/// Implements `nexus receipts verify`: checks the archived receipts (or those in `file`)
+/// against `trusted_key`, or else the key pinned for `environment`, and fails if any of them
+/// does not verify.
+pub fn print_verification(
+    config_path: &Path,
+    environment: &Environment,
+    file: Option<PathBuf>,
+    trusted_key: Option<&str>,
+    task_id: Option<&str>,
+    json: bool,
+) -> Result<(), Box<dyn Error>> {
+    let trusted_key = trusted_key.or(environment.receipt_key()).ok_or_else(|| {
+        format!(
+            "No orchestrator receipt key is pinned for {}; pass --orchestrator-key <HEX>",
+            environment
+        )
+    })?;
+    let path = file.unwrap_or_else(|| archive_path(&journal_dir(config_path, environment)));
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("cannot read {}: {}", path.display(), e).into()),
+    };
+    let checked = check_all(&contents, trusted_key, task_id);
+    let invalid = checked.iter().filter(|c| !c.valid).count();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&checked)?);
+    } else if checked.is_empty() {
+        print_cmd_info!(
+            "No receipts",
+            "No submission receipts found in {}.",
+            path.display()
+        );
+    } else {
+        for c in &checked {
+            match &c.error {
+                None => println!("  valid    {}  accepted {}", c.task_id, c.accepted_at),
+                Some(error) => println!("  INVALID  {}  {}", c.task_id, error),
+            }
+        }
+        print_cmd_info!(
+            "Receipts",
+            "{} of {} receipts verified against {}",
+            checked.len() - invalid,
+            checked.len(),
+            trusted_key
+        );
+    }
+
+    if invalid > 0 {
+        return Err(format!("{} receipts did not verify", invalid).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+    use reqwest::header::HeaderValue;
+
+    /// Signs a receipt as the orchestrator would.
+    fn sign(
+        orchestrator_key: &SigningKey,
+        task_id: &str,
+        node_key: &VerifyingKey,
+    ) -> SubmissionReceipt {
+        let mut receipt = SubmissionReceipt {
+            task_id: task_id.to_string(),
+            proof_hash: "abc".to_string(),
+            node_key: to_hex(node_key.as_bytes()),
+            accepted_at: "2026-10-15T14:05:03Z".to_string(),
+            orchestrator_key: to_hex(orchestrator_key.verifying_key().as_bytes()),
+            signature: String::new(),
+        };
+        let signature = orchestrator_key.sign(receipt.message().as_bytes());
+        receipt.signature = to_hex(&signature.to_bytes());
+        receipt
+    }
+
+    fn keys() -> (SigningKey, SigningKey) {
+        (
+            SigningKey::from_bytes(&[7; 32]),
+            SigningKey::from_bytes(&[9; 32]),
+        )
+    }
+
+    #[test]
+    // A receipt read from response headers verifies, and stops verifying once altered.
+    fn test_receipt_from_headers_verifies() {
+        let (orchestrator, node) = keys();
+        let signed = sign(&orchestrator, "task-1", &node.verifying_key());
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            KEY_HEADER,
+            HeaderValue::from_str(&signed.orchestrator_key).unwrap(),
+        );
+        headers.insert(
+            ACCEPTED_AT_HEADER,
+            HeaderValue::from_static("2026-10-15T14:05:03Z"),
+        );
+        headers.insert(
+            SIGNATURE_HEADER,
+            HeaderValue::from_str(&signed.signature.to_uppercase()).unwrap(),
+        );
+
+        let receipt =
+            SubmissionReceipt::from_headers(&headers, "task-1", "abc", &node.verifying_key())
+                .unwrap();
+        assert_eq!(receipt, signed);
+        assert!(receipt.verify(&signed.orchestrator_key).is_ok());
+
+        // A receipt signed by any other key, such as one it names itself, is not trusted
+        let other = to_hex(node.verifying_key().as_bytes());
+        assert!(receipt.verify(&other).is_err());
+        let tampered = SubmissionReceipt {
+            proof_hash: "abd".to_string(),
+            ..receipt
+        };
+        assert!(tampered.verify(&signed.orchestrator_key).is_err());
+
+        headers.remove(SIGNATURE_HEADER);
+        assert!(
+            SubmissionReceipt::from_headers(&headers, "task-1", "abc", &node.verifying_key())
+                .is_none()
+        );
+    }
+
+    #[test]
+    // Every line of the archive is checked; a line that is not a receipt counts as invalid.
+    fn test_check_archive() {
+        let (orchestrator, node) = keys();
+        let receipt = |task_id: &str| sign(&orchestrator, task_id, &node.verifying_key());
+        let contents = format!(
+            "{}\n{}\n{{\"torn\n",
+            serde_json::to_string(&receipt("task-1")).unwrap(),
+            serde_json::to_string(&receipt("task-2")).unwrap()
+        );
+
+        let trusted = to_hex(orchestrator.verifying_key().as_bytes());
+        let checked = check_all(&contents, &trusted, None);
+        assert_eq!(checked.len(), 3);
+        assert!(checked[0].valid && checked[1].valid);
+        assert!(!checked[2].valid);
+
+        let checked = check_all(&contents, &trusted, Some("task-2"));
+        assert_eq!(checked.len(), 1);
+        assert_eq!(checked[0].task_id, "task-2");
+    }
+}
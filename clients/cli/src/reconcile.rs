@@ -0,0 +1,238 @@
+//! Points reconciliation
+//!
+//! Implements `nexus reconcile`, which compares the submissions the journal recorded as
+//! accepted over the last few days against the orchestrator, prints the outcome per day, and
+//! lists tasks that were submitted but never credited.
+//!
+//! The orchestrator does not report points or a per-proof status, so credit is judged the way
+//! `--verify-submissions` judges it: a task still assigned to its node was not credited. An
+//! outcome the verifier already recorded in the journal is used as is. Submissions whose node
+//! is unknown (journaled by earlier releases) or whose node's tasks could not be listed are
+//! counted as unknown. The journal keeps only the most recent accepted submissions, so long
+//! windows on busy nodes may be incomplete.
+
+use crate::orchestrator::Orchestrator;
+use crate::pretty::print_cmd_info;
+use crate::submission_journal::{CommittedSubmission, SubmissionJournal, journal_dir};
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// What the orchestrator says about one accepted submission.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Credit {
+    Credited,
+    NotCredited,
+    Unknown,
+}
+
+/// Accepted submissions on one day, by outcome.
+#[derive(Serialize, Debug, Default, Clone, PartialEq, Eq)]
+struct DayTotals {
+    accepted: usize,
+    credited: usize,
+    not_credited: usize,
+    unknown: usize,
+}
+
+/// A submission that was accepted but not credited.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+struct Uncredited {
+    task_id: String,
+    node_id: Option<u64>,
+    accepted_at: String,
+}
+
+#[derive(Serialize, Debug)]
+struct Reconciliation {
+    /// By local date, e.g. `2026-10-14`
+    days: BTreeMap<String, DayTotals>,
+    uncredited: Vec<Uncredited>,
+}
+
+/// Tasks currently assigned to each node, or `None` where they could not be listed.
+type Assignments = HashMap<u64, Option<HashSet<String>>>;
+
+fn credit(submission: &CommittedSubmission, assigned: &Assignments) -> Credit {
+    match submission.credited {
+        Some(true) => return Credit::Credited,
+        Some(false) => return Credit::NotCredited,
+        None => {}
+    }
+    let Some(Some(task_ids)) = submission
+        .node_id
+        .and_then(|node_id| assigned.get(&node_id))
+    else {
+        return Credit::Unknown;
+    };
+    if task_ids.contains(&submission.task_id) {
+        Credit::NotCredited
+    } else {
+        Credit::Credited
+    }
+}
+
+fn local_time(timestamp: u64) -> DateTime<Local> {
+    DateTime::from_timestamp(timestamp as i64, 0)
+        .unwrap_or_default()
+        .with_timezone(&Local)
+}
+
+/// Groups the submissions accepted at or after `since` by local day and outcome.
+fn reconcile(
+    submissions: &[CommittedSubmission],
+    since: u64,
+    assigned: &Assignments,
+) -> Reconciliation {
+    let mut days: BTreeMap<String, DayTotals> = BTreeMap::new();
+    let mut uncredited = Vec::new();
+    for submission in submissions.iter().filter(|s| s.timestamp >= since) {
+        let accepted_at = local_time(submission.timestamp);
+        let totals = days
+            .entry(accepted_at.format("%Y-%m-%d").to_string())
+            .or_default();
+        totals.accepted += 1;
+        match credit(submission, assigned) {
+            Credit::Credited => totals.credited += 1,
+            Credit::Unknown => totals.unknown += 1,
+            Credit::NotCredited => {
+                totals.not_credited += 1;
+                uncredited.push(Uncredited {
+                    task_id: submission.task_id.clone(),
+                    node_id: submission.node_id,
+                    accepted_at: accepted_at.format("%Y-%m-%d %H:%M").to_string(),
+                });
+            }
+        }
+    }
+    Reconciliation { days, uncredited }
+}
+
+/// Reconciles the last `days` days of accepted submissions, printing JSON if `json` is set.
+pub async fn print_reconciliation(
+    config_path: &Path,
+    days: u32,
+    json: bool,
+    orchestrator: Box<dyn Orchestrator>,
+) -> Result<(), Box<dyn Error>> {
+    let journal = SubmissionJournal::read(&journal_dir(config_path, orchestrator.environment()))?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let since = now.saturating_sub(u64::from(days) * 86_400);
+    let submissions: Vec<_> = journal
+        .committed()
+        .into_iter()
+        .filter(|s| s.timestamp >= since)
+        .collect();
+
+    let mut assigned = Assignments::new();
+    for node_id in submissions.iter().filter_map(|s| s.node_id) {
+        if assigned.contains_key(&node_id) {
+            continue;
+        }
+        let task_ids = match orchestrator.get_tasks(&node_id.to_string()).await {
+            Ok(tasks) => Some(tasks.into_iter().map(|task| task.task_id).collect()),
+            Err(e) => {
+                eprintln!("⚠️  Could not list the tasks of node {}: {}", node_id, e);
+                None
+            }
+        };
+        assigned.insert(node_id, task_ids);
+    }
+
+    let reconciliation = reconcile(&submissions, since, &assigned);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&reconciliation)?);
+        return Ok(());
+    }
+    if submissions.is_empty() {
+        print_cmd_info!(
+            "Nothing to reconcile",
+            "No accepted submissions were journaled in the last {} days.",
+            days
+        );
+        return Ok(());
+    }
+
+    println!(
+        "{:<12} {:>9} {:>9} {:>13} {:>8}",
+        "Date", "Accepted", "Credited", "Not credited", "Unknown"
+    );
+    for (date, totals) in &reconciliation.days {
+        println!(
+            "{:<12} {:>9} {:>9} {:>13} {:>8}",
+            date, totals.accepted, totals.credited, totals.not_credited, totals.unknown
+        );
+    }
+    if reconciliation.uncredited.is_empty() {
+        println!("\n✅ Every checked submission was credited.");
+    } else {
+        println!("\n⚠️  Submitted but never credited:");
+        for task in &reconciliation.uncredited {
+            let node = task
+                .node_id
+                .map_or_else(|| "unknown node".to_string(), |id| format!("node {}", id));
+            println!(
+                "  {}  {}  accepted {}",
+                task.task_id, node, task.accepted_at
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn submission(task_id: &str, node_id: Option<u64>, timestamp: u64) -> CommittedSubmission {
+        CommittedSubmission {
+            task_id: task_id.to_string(),
+            node_id,
+            timestamp,
+            credited: None,
+        }
+    }
+
+    #[test]
+    // Tasks still assigned are not credited; tasks of unlisted or unknown nodes cannot be judged.
+    fn test_reconcile_by_assignment() {
+        let day = 1_760_000_000;
+        let assigned =
+            Assignments::from([(1, Some(HashSet::from(["stuck".to_string()]))), (2, None)]);
+        let submissions = vec![
+            submission("old", Some(1), day - 86_400 * 30),
+            submission("done", Some(1), day),
+            submission("stuck", Some(1), day),
+            submission("unlisted", Some(2), day),
+            submission("legacy", None, day),
+        ];
+
+        let reconciliation = reconcile(&submissions, day - 60, &assigned);
+        let totals: Vec<_> = reconciliation.days.values().cloned().collect();
+        assert_eq!(
+            totals,
+            vec![DayTotals {
+                accepted: 4,
+                credited: 1,
+                not_credited: 1,
+                unknown: 2,
+            }]
+        );
+        assert_eq!(reconciliation.uncredited.len(), 1);
+        assert_eq!(reconciliation.uncredited[0].task_id, "stuck");
+        assert_eq!(reconciliation.uncredited[0].node_id, Some(1));
+    }
+
+    #[test]
+    // A verification outcome already in the journal takes precedence over assignments.
+    fn test_recorded_verification_is_used() {
+        let mut verified = submission("verified", None, 100);
+        verified.credited = Some(false);
+        assert_eq!(credit(&verified, &Assignments::new()), Credit::NotCredited);
+        verified.credited = Some(true);
+        assert_eq!(credit(&verified, &Assignments::new()), Credit::Credited);
+    }
+}
@@ -3,6 +3,7 @@
 use crate::config::Config;
 use crate::keys;
 use crate::orchestrator::Orchestrator;
+use crate::orchestrator::hints;
 use crate::pretty::{
     handle_cmd_error, print_cmd_error, print_cmd_info, print_friendly_error_header,
 };
@@ -60,12 +61,12 @@ pub async fn register_user(
             wallet_address,
             user_id
         );
-        let config = Config::new(
-            user_id,
-            wallet_address.to_string(),
-            String::new(), // node_id is empty for now
-            orchestrator.environment().clone(),
-        );
+        // node_id is empty for now
+        let config = Config::builder()
+            .user_id(user_id)
+            .wallet_address(wallet_address)
+            .environment(orchestrator.environment())
+            .build()?;
         // Save the configuration file with the user ID and wallet address.
         config
             .save(config_path)
@@ -91,18 +92,21 @@ pub async fn register_user(
             } else {
                 print_cmd_error!("Failed to register user. Unable to pretty print error.");
             }
+            if let Some(hint) = hints::hint_for(&e) {
+                println!("{}", hint);
+            }
 
             return Err(e.into());
         }
     }
 
     // Save the configuration file with the user ID and wallet address.
-    let config = Config::new(
-        uuid,
-        wallet_address.to_string(),
-        String::new(), // node_id is empty for now
-        orchestrator.environment().clone(),
-    );
+    // node_id is empty for now
+    let config = Config::builder()
+        .user_id(uuid)
+        .wallet_address(wallet_address)
+        .environment(orchestrator.environment())
+        .build()?;
     config
         .save(config_path)
         .map_err(|e| handle_cmd_error!(e, "Failed to save config."))?;
@@ -181,6 +185,9 @@ pub async fn register_node(
             Err(e) => {
                 print_friendly_error_header();
                 print_cmd_error!("Failed to register node.");
+                if let Some(hint) = hints::hint_for(&e) {
+                    println!("{}", hint);
+                }
                 Err(e.into())
             }
         }
@@ -0,0 +1,706 @@
+//! Remote control
+//!
+//! Fleet operators manage nodes on other machines, often over WAN links. With
+//! `start --control-listen ADDR --control-psk FILE` a node accepts the same control commands as
+//! its local socket over TCP, and `--remote ADDR --control-psk FILE` sends `status`, `pause` and
+//! the other control commands to such a node instead of the local one.
+//!
+//! Every node instance has its own pre-shared key. Connections are secured with the Noise
+//! protocol's `NNpsk0` handshake (`Noise_NNpsk0_25519_ChaChaPoly_SHA256`, from the `snow` crate),
+//! in which both sides prove they know the key and agree on fresh session keys, so neither talks
+//! to an impostor and recorded sessions cannot be replayed. The client first names its token and
+//! compression in a short hello, which the handshake authenticates as its prologue. After the
+//! handshake, every message is compressed (deflate unless `--control-compression none`) and
+//! encrypted.
+//!
+//! A node serves at most `MAX_CONNECTIONS` connections at once, gives each `HANDSHAKE_TIMEOUT`
+//! to authenticate with frames of a few hundred bytes at most, and closes a connection that
+//! sends nothing for `IDLE_TIMEOUT`.
+//!
+//! Besides its own key, a node accepts the named tokens in the JSON file given with
+//! `--control-tokens`, so a team can hand out access without sharing the node's key:
+//!
+//! ```json
+//! [
+//!   { "name": "dashboard", "token": "<openssl rand -hex 32>", "scope": "read" },
+//!   { "name": "on-call", "token": "<openssl rand -hex 32>", "scope": "control" }
+//! ]
+//! ```
+//!
+//! A `read` token may only ask for the node's state (`status`, `proxy-stats`); a `control` token,
+//! like the node's own key, may send every command. A client keeps its token in its
+//! `--control-psk` file and names it with `--control-token-name`; the token is the handshake key,
+//! so it never crosses the wire. Every command a node receives is appended to
+//! `control_audit.log` next to the config file, with the token that sent it and whether it was
+//! allowed.
+//!
+//! Messages use the control plane's framing: a JSON document (or Noise message) prefixed with its
+//! length as a big-endian `u32`.
+
+use crate::control::{Command, Response, Scope, control_state};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::{Error, ErrorKind, Read, Write};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::{Semaphore, broadcast};
+use tokio::task::JoinHandle;
+
+/// Version of the hello and framing.
+const PROTOCOL_VERSION: u32 = 2;
+
+/// Noise handshake pattern and primitives. The pre-shared key is mixed in before the first
+/// message, so a peer without it cannot get past the handshake.
+const NOISE_PARAMS: &str = "Noise_NNpsk0_25519_ChaChaPoly_SHA256";
+
+/// Largest Noise message, tag included, as fixed by the Noise specification.
+const MAX_NOISE_MESSAGE: usize = 65535;
+
+/// Largest accepted decompressed message. Control messages are tiny.
+const MAX_MESSAGE_SIZE: usize = 64 * 1024;
+
+/// Largest frame accepted before the peer is authenticated: the hello and handshake messages.
+const MAX_HANDSHAKE_FRAME: usize = 512;
+
+/// Shortest accepted pre-shared key, in bytes.
+const MIN_PSK_LEN: usize = 16;
+
+/// Name the audit log gives sessions that use the node's own key.
+const OWNER: &str = "owner";
+
+/// Time a peer has to complete the handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Time an authenticated client may send nothing before its connection is closed.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Connections served at once; further ones are closed straight away.
+const MAX_CONNECTIONS: usize = 16;
+
+/// How messages are compressed before they are encrypted.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    None,
+    #[default]
+    Deflate,
+}
+
+impl Compression {
+    fn compress(self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            Compression::None => Ok(data.to_vec()),
+            Compression::Deflate => {
+                let mut encoder =
+                    flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+        }
+    }
+
+    fn decompress(self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            Compression::None => Ok(data.to_vec()),
+            Compression::Deflate => {
+                let mut out = Vec::new();
+                // Stop at the limit rather than inflating a hostile message without bound
+                flate2::read::DeflateDecoder::new(data)
+                    .take(MAX_MESSAGE_SIZE as u64 + 1)
+                    .read_to_end(&mut out)?;
+                if out.len() > MAX_MESSAGE_SIZE {
+                    return Err(invalid("decompressed message exceeds the limit"));
+                }
+                Ok(out)
+            }
+        }
+    }
+}
+
+/// A named token from `--control-tokens`.
+#[derive(Deserialize, Debug, Clone)]
+struct ApiToken {
+    name: String,
+    token: String,
+    scope: Scope,
+}
+
+/// Who a session speaks for, and what it may do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Grant {
+    name: String,
+    scope: Scope,
+}
+
+/// How this instance secures its remote control traffic.
+#[derive(Debug, Clone)]
+pub struct RemoteSettings {
+    psk: Vec<u8>,
+    /// Tokens accepted besides `psk`, each limited to a scope
+    tokens: Vec<ApiToken>,
+    /// Name of the token in `psk`, if it is one of the remote node's tokens rather than its key
+    pub token_name: Option<String>,
+    /// File every received command is appended to
+    pub audit_log: Option<PathBuf>,
+    pub compression: Compression,
+}
+
+impl RemoteSettings {
+    /// Reads the pre-shared key from `path`; surrounding whitespace is ignored.
+    pub fn load(path: &Path, compression: Compression) -> Result<Self, String> {
+        let psk =
+            std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let psk = psk.trim_ascii().to_vec();
+        if psk.len() < MIN_PSK_LEN {
+            return Err(format!(
+                "The key in {} is too short; generate one with `openssl rand -hex 32`",
+                path.display()
+            ));
+        }
+        Ok(Self {
+            psk,
+            tokens: Vec::new(),
+            token_name: None,
+            audit_log: None,
+            compression,
+        })
+    }
+
+    /// Also accepts the named tokens in the JSON file at `path`.
+    pub fn load_tokens(&mut self, path: &Path) -> Result<(), String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let mut tokens: Vec<ApiToken> = serde_json::from_str(&text)
+            .map_err(|e| format!("Invalid token file {}: {}", path.display(), e))?;
+        for index in 0..tokens.len() {
+            tokens[index].token = tokens[index].token.trim().to_string();
+            let token = &tokens[index];
+            if token.token.len() < MIN_PSK_LEN {
+                return Err(format!(
+                    "Token '{}' in {} is too short; generate one with `openssl rand -hex 32`",
+                    token.name,
+                    path.display()
+                ));
+            }
+            if token.name == OWNER || tokens[..index].iter().any(|t| t.name == token.name) {
+                return Err(format!(
+                    "Token name '{}' in {} is reserved or used twice",
+                    token.name,
+                    path.display()
+                ));
+            }
+        }
+        self.tokens = tokens;
+        Ok(())
+    }
+
+    /// The key and grant of the token a client names, or of the node's own key if it names none.
+    fn grant(&self, token_name: Option<&str>) -> Option<(&[u8], Grant)> {
+        let Some(name) = token_name else {
+            let grant = Grant {
+                name: OWNER.to_string(),
+                scope: Scope::Control,
+            };
+            return Some((&self.psk, grant));
+        };
+        self.tokens.iter().find(|t| t.name == name).map(|t| {
+            let grant = Grant {
+                name: t.name.clone(),
+                scope: t.scope,
+            };
+            (t.token.as_bytes(), grant)
+        })
+    }
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::new(ErrorKind::InvalidData, message.into())
+}
+
+/// Sent in the clear before the handshake, which authenticates it as its prologue.
+#[derive(Serialize, Deserialize, Debug)]
+struct ClientHello {
+    version: u32,
+    compression: Compression,
+    /// Name of the node's token the client holds; none for the node's own key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    token: Option<String>,
+}
+
+/// The node's answer to a hello: whether the handshake goes ahead.
+#[derive(Serialize, Deserialize, Debug)]
+struct HelloReply {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+async fn write_frame<W: AsyncWrite + Unpin>(writer: &mut W, bytes: &[u8]) -> std::io::Result<()> {
+    writer.write_u32(bytes.len() as u32).await?;
+    writer.write_all(bytes).await?;
+    writer.flush().await
+}
+
+/// Reads a frame of at most `limit` bytes.
+async fn read_frame<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    limit: usize,
+) -> std::io::Result<Vec<u8>> {
+    let len = reader.read_u32().await? as usize;
+    if len > limit {
+        return Err(invalid(format!("frame of {} bytes exceeds the limit", len)));
+    }
+    let mut bytes = vec![0u8; len];
+    reader.read_exact(&mut bytes).await?;
+    Ok(bytes)
+}
+
+fn noise_error(e: snow::Error) -> Error {
+    invalid(format!("noise: {}", e))
+}
+
+/// Starts a handshake keyed with `key`, hashed to the 32 bytes Noise takes, and bound to the
+/// hello.
+fn start_handshake(
+    key: &[u8],
+    hello: &[u8],
+    initiator: bool,
+) -> std::io::Result<snow::HandshakeState> {
+    let psk: [u8; 32] = Sha256::digest(key).into();
+    let builder = snow::Builder::new(NOISE_PARAMS.parse().map_err(noise_error)?)
+        .psk(0, &psk)
+        .prologue(hello);
+    let state = if initiator {
+        builder.build_initiator()
+    } else {
+        builder.build_responder()
+    };
+    state.map_err(noise_error)
+}
+
+/// An authenticated connection to or from a remote peer.
+struct Session<S> {
+    stream: S,
+    transport: snow::TransportState,
+    compression: Compression,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Session<S> {
+    /// Runs the client side of the handshake.
+    async fn connect(mut stream: S, settings: &RemoteSettings) -> std::io::Result<Self> {
+        let hello = serde_json::to_vec(&ClientHello {
+            version: PROTOCOL_VERSION,
+            compression: settings.compression,
+            token: settings.token_name.clone(),
+        })?;
+        write_frame(&mut stream, &hello).await?;
+        let reply: HelloReply =
+            serde_json::from_slice(&read_frame(&mut stream, MAX_HANDSHAKE_FRAME).await?)?;
+        if let Some(error) = reply.error {
+            return Err(Error::new(ErrorKind::PermissionDenied, error));
+        }
+
+        let mut handshake = start_handshake(&settings.psk, &hello, true)?;
+        let mut buffer = vec![0u8; MAX_NOISE_MESSAGE];
+        let len = handshake
+            .write_message(&[], &mut buffer)
+            .map_err(noise_error)?;
+        write_frame(&mut stream, &buffer[..len]).await?;
+        // A node that does not know the key hangs up rather than answer
+        let refused = || {
+            Error::new(
+                ErrorKind::PermissionDenied,
+                "the node does not know this pre-shared key",
+            )
+        };
+        let message = read_frame(&mut stream, MAX_HANDSHAKE_FRAME)
+            .await
+            .map_err(|_| refused())?;
+        handshake
+            .read_message(&message, &mut buffer)
+            .map_err(|_| refused())?;
+
+        Ok(Self {
+            stream,
+            transport: handshake.into_transport_mode().map_err(noise_error)?,
+            compression: settings.compression,
+        })
+    }
+
+    /// Runs the node side of the handshake, returning the session and what its client may do.
+    /// The client picks the compression.
+    async fn accept(mut stream: S, settings: &RemoteSettings) -> std::io::Result<(Self, Grant)> {
+        let hello_bytes = read_frame(&mut stream, MAX_HANDSHAKE_FRAME).await?;
+        let hello: ClientHello = serde_json::from_slice(&hello_bytes)?;
+        let granted = if hello.version != PROTOCOL_VERSION {
+            Err(format!(
+                "unsupported protocol version {} (this node speaks {})",
+                hello.version, PROTOCOL_VERSION
+            ))
+        } else {
+            settings.grant(hello.token.as_deref()).ok_or_else(|| {
+                format!(
+                    "this node has no token named '{}'",
+                    hello.token.as_deref().unwrap_or_default()
+                )
+            })
+        };
+        let reply = HelloReply {
+            error: granted.as_ref().err().cloned(),
+        };
+        write_frame(&mut stream, &serde_json::to_vec(&reply)?).await?;
+        let (key, grant) =
+            granted.map_err(|error| Error::new(ErrorKind::PermissionDenied, error))?;
+
+        let mut handshake = start_handshake(key, &hello_bytes, false)?;
+        let mut buffer = vec![0u8; MAX_NOISE_MESSAGE];
+        let message = read_frame(&mut stream, MAX_HANDSHAKE_FRAME).await?;
+        handshake
+            .read_message(&message, &mut buffer)
+            .map_err(|_| Error::new(ErrorKind::PermissionDenied, "wrong pre-shared key"))?;
+        let len = handshake
+            .write_message(&[], &mut buffer)
+            .map_err(noise_error)?;
+        write_frame(&mut stream, &buffer[..len]).await?;
+
+        let session = Self {
+            stream,
+            transport: handshake.into_transport_mode().map_err(noise_error)?,
+            compression: hello.compression,
+        };
+        Ok((session, grant))
+    }
+
+    async fn send<T: Serialize>(&mut self, value: &T) -> std::io::Result<()> {
+        let compressed = self.compression.compress(&serde_json::to_vec(value)?)?;
+        let mut frame = vec![0u8; MAX_NOISE_MESSAGE];
+        let len = self
+            .transport
+            .write_message(&compressed, &mut frame)
+            .map_err(noise_error)?;
+        write_frame(&mut self.stream, &frame[..len]).await
+    }
+
+    async fn receive<T: for<'de> Deserialize<'de>>(&mut self) -> std::io::Result<T> {
+        let frame = read_frame(&mut self.stream, MAX_NOISE_MESSAGE).await?;
+        let mut compressed = vec![0u8; MAX_NOISE_MESSAGE];
+        let len = self
+            .transport
+            .read_message(&frame, &mut compressed)
+            .map_err(|_| invalid("message failed authentication"))?;
+        Ok(serde_json::from_slice(
+            &self.compression.decompress(&compressed[..len])?,
+        )?)
+    }
+}
+
+/// One line of the audit log.
+#[derive(Serialize, Debug)]
+struct AuditEntry<'a> {
+    time: String,
+    token: &'a str,
+    peer: &'a str,
+    command: Command,
+    allowed: bool,
+}
+
+/// Appends a received command to the audit log, if there is one. A failed write does not hold
+/// up the command.
+fn audit(settings: &RemoteSettings, entry: &AuditEntry) {
+    let Some(path) = &settings.audit_log else {
+        return;
+    };
+    let Ok(mut line) = serde_json::to_string(entry) else {
+        return;
+    };
+    line.push('\n');
+    let _ = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(line.as_bytes()));
+}
+
+/// Answers commands on an accepted connection from `peer` until it hangs up, refusing those
+/// its token's scope does not cover.
+async fn handle_connection<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: S,
+    settings: &RemoteSettings,
+    peer: &str,
+) -> std::io::Result<()> {
+    let (mut session, grant) =
+        tokio::time::timeout(HANDSHAKE_TIMEOUT, Session::accept(stream, settings))
+            .await
+            .map_err(|_| Error::new(ErrorKind::TimedOut, "handshake timed out"))??;
+    loop {
+        let received = tokio::time::timeout(IDLE_TIMEOUT, session.receive())
+            .await
+            .map_err(|_| Error::new(ErrorKind::TimedOut, "connection idle"))?;
+        let command: Command = match received {
+            Ok(command) => command,
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        let allowed = command.scope() <= grant.scope;
+        audit(
+            settings,
+            &AuditEntry {
+                time: chrono::Utc::now().to_rfc3339(),
+                token: &grant.name,
+                peer,
+                command,
+                allowed,
+            },
+        );
+        let response = if allowed {
+            control_state().apply(command)
+        } else {
+            Response::error(format!(
+                "Token '{}' may only read the node's state",
+                grant.name
+            ))
+        };
+        session.send(&response).await?;
+    }
+}
+
+static SETTINGS: OnceLock<RemoteSettings> = OnceLock::new();
+static LISTEN_ADDR: OnceLock<SocketAddr> = OnceLock::new();
+static REMOTE_ADDR: OnceLock<String> = OnceLock::new();
+
+/// Secures remote control traffic of this process with `settings`. Only the first call has an
+/// effect.
+pub fn set_settings(settings: RemoteSettings) {
+    let _ = SETTINGS.set(settings);
+}
+
+/// Accepts remote control commands on `addr` once the node starts. Only the first call has an
+/// effect.
+pub fn set_listen_addr(addr: SocketAddr) {
+    let _ = LISTEN_ADDR.set(addr);
+}
+
+/// Sends control commands to the node at `addr` (`host:port`) instead of the local one. Only
+/// the first call has an effect.
+pub fn set_remote_addr(addr: String) {
+    let _ = REMOTE_ADDR.set(addr);
+}
+
+/// The node control commands are sent to, if not the local one.
+pub fn remote_addr() -> Option<&'static str> {
+    REMOTE_ADDR.get().map(String::as_str)
+}
+
+/// Starts accepting remote control commands, if `--control-listen` was given.
+///
+/// # Errors
+/// Returns an `std::io::Error` if the address cannot be bound or no key was configured.
+pub fn serve(mut shutdown: broadcast::Receiver<()>) -> std::io::Result<Option<JoinHandle<()>>> {
+    let Some(addr) = LISTEN_ADDR.get() else {
+        return Ok(None);
+    };
+    let settings = SETTINGS
+        .get()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "--control-psk is required"))?;
+    let listener = std::net::TcpListener::bind(addr)?;
+    listener.set_nonblocking(true)?;
+    let listener = tokio::net::TcpListener::from_std(listener)?;
+    let connections = Arc::new(Semaphore::new(MAX_CONNECTIONS));
+
+    Ok(Some(tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = shutdown.recv() => break,
+                accepted = listener.accept() => {
+                    let Ok((stream, peer)) = accepted else {
+                        continue;
+                    };
+                    // Beyond the limit, connections are closed rather than queued
+                    let Ok(permit) = connections.clone().try_acquire_owned() else {
+                        continue;
+                    };
+                    let _ = stream.set_nodelay(true);
+                    tokio::spawn(async move {
+                        let _ = handle_connection(stream, settings, &peer.to_string()).await;
+                        drop(permit);
+                    });
+                }
+            }
+        }
+    })))
+}
+
+/// Sends a command to the node at `remote_addr()`.
+///
+/// # Errors
+/// Returns an `std::io::Error` if the node cannot be reached or the handshake fails.
+pub async fn send_command(command: Command) -> std::io::Result<Response> {
+    let addr = remote_addr().ok_or_else(|| Error::new(ErrorKind::InvalidInput, "no --remote"))?;
+    let settings = SETTINGS
+        .get()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "--remote needs --control-psk"))?;
+    let stream = tokio::net::TcpStream::connect(addr).await?;
+    stream.set_nodelay(true)?;
+    let mut session = tokio::time::timeout(HANDSHAKE_TIMEOUT, Session::connect(stream, settings))
+        .await
+        .map_err(|_| Error::new(ErrorKind::TimedOut, "handshake timed out"))??;
+    session.send(&command).await?;
+    session.receive().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(psk: &str) -> RemoteSettings {
+        RemoteSettings {
+            psk: psk.as_bytes().to_vec(),
+            tokens: Vec::new(),
+            token_name: None,
+            audit_log: None,
+            compression: Compression::Deflate,
+        }
+    }
+
+    #[tokio::test]
+    // Peers sharing the key complete the handshake and exchange commands and replies.
+    async fn test_session_round_trip() {
+        let (client, server) = tokio::io::duplex(4096);
+        let node = settings("0123456789abcdef");
+        let handle = tokio::spawn(async move { handle_connection(server, &node, "test").await });
+
+        let mut session = Session::connect(client, &settings("0123456789abcdef"))
+            .await
+            .unwrap();
+        session.send(&Command::Status).await.unwrap();
+        let response: Response = session.receive().await.unwrap();
+        assert!(response.ok);
+        assert!(response.status.is_some());
+        drop(session);
+        handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    // A client with the wrong key is refused, and so is a node with the wrong key.
+    async fn test_wrong_key_is_rejected() {
+        let (client, server) = tokio::io::duplex(4096);
+        let node = settings("0123456789abcdef");
+        let handle = tokio::spawn(async move { handle_connection(server, &node, "test").await });
+        let result = Session::connect(client, &settings("fedcba9876543210")).await;
+        assert_eq!(
+            result.err().map(|e| e.kind()),
+            Some(ErrorKind::PermissionDenied)
+        );
+        assert!(handle.await.unwrap().is_err());
+    }
+
+    #[tokio::test]
+    // An unauthenticated peer cannot make the node allocate a large frame.
+    async fn test_oversized_hello_is_rejected() {
+        let (mut client, server) = tokio::io::duplex(4096);
+        let node = settings("0123456789abcdef");
+        let handle = tokio::spawn(async move { handle_connection(server, &node, "test").await });
+        client.write_u32(64 * 1024 * 1024).await.unwrap();
+        let error = handle.await.unwrap().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    // A read token may ask for the node's state but not change it, and every command is audited.
+    async fn test_read_token_scope_and_audit() {
+        let dir = tempfile::tempdir().unwrap();
+        let tokens_path = dir.path().join("control_tokens.json");
+        std::fs::write(
+            &tokens_path,
+            r#"[{"name": "dashboard", "token": "abcdefghijklmnop", "scope": "read"}]"#,
+        )
+        .unwrap();
+        let mut node = settings("0123456789abcdef");
+        node.load_tokens(&tokens_path).unwrap();
+        node.audit_log = Some(dir.path().join("control_audit.log"));
+        let audit_log = node.audit_log.clone().unwrap();
+
+        let (client, server) = tokio::io::duplex(4096);
+        let handle = tokio::spawn(async move { handle_connection(server, &node, "test").await });
+        let mut dashboard = settings("abcdefghijklmnop");
+        dashboard.token_name = Some("dashboard".to_string());
+        let mut session = Session::connect(client, &dashboard).await.unwrap();
+        session.send(&Command::Status).await.unwrap();
+        assert!(session.receive::<Response>().await.unwrap().ok);
+        session.send(&Command::Drain).await.unwrap();
+        let refused: Response = session.receive().await.unwrap();
+        assert!(!refused.ok);
+        assert!(refused.message.contains("dashboard"));
+        drop(session);
+        handle.await.unwrap().unwrap();
+
+        let lines: Vec<serde_json::Value> = std::fs::read_to_string(audit_log)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["token"], "dashboard");
+        assert_eq!(lines[0]["allowed"], true);
+        assert_eq!(lines[1]["command"], "drain");
+        assert_eq!(lines[1]["allowed"], false);
+    }
+
+    #[tokio::test]
+    // A client naming a token the node does not have is refused.
+    async fn test_unknown_token_is_refused() {
+        let (client, server) = tokio::io::duplex(4096);
+        let node = settings("0123456789abcdef");
+        let handle = tokio::spawn(async move { handle_connection(server, &node, "test").await });
+        let mut client_settings = settings("0123456789abcdef");
+        client_settings.token_name = Some("dashboard".to_string());
+        let result = Session::connect(client, &client_settings).await;
+        assert!(result.is_err());
+        assert!(handle.await.unwrap().is_err());
+    }
+
+    #[test]
+    // Token files need long enough tokens with unique names.
+    fn test_load_tokens_validates() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("control_tokens.json");
+        let mut node = settings("0123456789abcdef");
+        std::fs::write(
+            &path,
+            r#"[{"name": "a", "token": "short", "scope": "read"}]"#,
+        )
+        .unwrap();
+        assert!(node.load_tokens(&path).is_err());
+        std::fs::write(
+            &path,
+            r#"[{"name": "a", "token": "abcdefghijklmnop", "scope": "read"},
+                {"name": "a", "token": "ponmlkjihgfedcba", "scope": "control"}]"#,
+        )
+        .unwrap();
+        assert!(node.load_tokens(&path).is_err());
+        std::fs::write(
+            &path,
+            r#"[{"name": "a", "token": " abcdefghijklmnop\n", "scope": "control"}]"#,
+        )
+        .unwrap();
+        node.load_tokens(&path).unwrap();
+        let (key, grant) = node.grant(Some("a")).unwrap();
+        assert_eq!(key, b"abcdefghijklmnop");
+        assert_eq!(grant.scope, Scope::Control);
+        assert!(node.grant(Some("b")).is_none());
+        assert_eq!(node.grant(None).unwrap().1.name, OWNER);
+    }
+
+    #[test]
+    // Deflate shrinks repetitive messages and restores them unchanged.
+    fn test_compression_round_trip() {
+        let compressed = Compression::Deflate.compress(&[b'a'; 4096]).unwrap();
+        assert!(compressed.len() < 100);
+        assert_eq!(
+            Compression::Deflate.decompress(&compressed).unwrap(),
+            vec![b'a'; 4096]
+        );
+    }
+}
@@ -0,0 +1,38 @@
+//! Source of randomness for scheduling decisions
+//!
+//! Proxy selection and rotation, poll and retry jitter, the pacing seed of anonymous nodes and
+//! the proxy plan simulation all draw from one generator per process. It is seeded randomly
+//! unless `--seed` fixes the seed; either way `nexus-network status` shows the seed, so a run's
+//! scheduling can be replayed for a bug report. Components that keep their own generator (the
+//! proxy manager) fork it from this one, and tests inject a seeded one instead. Keys, nonces
+//! and tokens never come from here.
+
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use std::sync::{Mutex, OnceLock};
+
+static SEED: OnceLock<u64> = OnceLock::new();
+
+static RNG: OnceLock<Mutex<StdRng>> = OnceLock::new();
+
+/// Seeds the generator. Only the first call (before anything is drawn) has an effect.
+pub fn set_seed(seed: u64) {
+    let _ = SEED.set(seed);
+}
+
+/// The seed of the generator, chosen at random unless set.
+pub fn seed() -> u64 {
+    *SEED.get_or_init(rand::random)
+}
+
+/// Runs `f` with the generator.
+pub fn with_rng<T>(f: impl FnOnce(&mut StdRng) -> T) -> T {
+    let rng = RNG.get_or_init(|| Mutex::new(StdRng::seed_from_u64(seed())));
+    let mut rng = rng.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    f(&mut rng)
+}
+
+/// A generator of its own, seeded from this one.
+pub fn fork() -> StdRng {
+    StdRng::seed_from_u64(with_rng(|rng| rng.next_u64()))
+}
@@ -0,0 +1,121 @@
+//! Startup self-test
+//!
+//! `start --self-test` proves a tiny program with a known answer before joining the network,
+//! and checks the result locally: the guest must exit cleanly, the proof must verify for its
+//! input and survive the serialization used for submission, and it must *not* verify for a
+//! different input. A broken build, bad RAM or an unsupported CPU fails one of these, and the
+//! node refuses to start rather than submit proofs the orchestrator would reject.
+
+use crate::prover::get_initial_stwo_prover;
+use nexus_sdk::stwo::seq::Proof;
+use nexus_sdk::{KnownExitCodes, Prover, Verifiable, Viewable};
+use std::fmt;
+use std::time::{Duration, Instant};
+use sysinfo::{CpuRefreshKind, RefreshKind, System};
+
+/// Input of the known-answer task: F(9) of the Fibonacci sequence starting 1, 1.
+const KNOWN_INPUT: (u32, u32, u32) = (9, 1, 1);
+
+/// An input the known-answer proof must not verify for.
+const WRONG_INPUT: (u32, u32, u32) = (10, 1, 1);
+
+/// A failed self-test check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelfTestError {
+    /// Which check failed
+    pub check: &'static str,
+    pub detail: String,
+}
+
+impl fmt::Display for SelfTestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.check, self.detail)
+    }
+}
+
+impl std::error::Error for SelfTestError {}
+
+fn fail(check: &'static str, detail: impl ToString) -> SelfTestError {
+    SelfTestError {
+        check,
+        detail: detail.to_string(),
+    }
+}
+
+/// Proves and checks the known-answer task, returning how long it took.
+///
+/// This blocks for a few seconds, so call it from a blocking context.
+///
+/// # Errors
+/// Returns the first check that failed.
+pub fn run() -> Result<Duration, SelfTestError> {
+    let started = Instant::now();
+    let prover = get_initial_stwo_prover().map_err(|e| fail("load guest program", e))?;
+    let elf = prover.elf.clone();
+    let (view, proof) = prover
+        .prove_with_input::<(), (u32, u32, u32)>(&(), &KNOWN_INPUT)
+        .map_err(|e| fail("prove", e))?;
+
+    let exit_code = view.exit_code().map_err(|e| fail("read exit code", e))?;
+    if exit_code != KnownExitCodes::ExitSuccess as u32 {
+        return Err(fail(
+            "exit code",
+            format!("guest exited with {}, expected 0", exit_code),
+        ));
+    }
+
+    // Check the proof as the orchestrator will receive it
+    let bytes = postcard::to_allocvec(&proof).map_err(|e| fail("serialize proof", e))?;
+    let proof: Proof = postcard::from_bytes(&bytes).map_err(|e| fail("deserialize proof", e))?;
+    let verify = |input: &(u32, u32, u32)| {
+        proof.verify_expected::<(u32, u32, u32), ()>(
+            input,
+            KnownExitCodes::ExitSuccess as u32,
+            &(),
+            &elf,
+            &[],
+        )
+    };
+    verify(&KNOWN_INPUT).map_err(|e| fail("verify proof", e))?;
+    if verify(&WRONG_INPUT).is_ok() {
+        return Err(fail(
+            "reject wrong input",
+            "the proof also verified for a different input",
+        ));
+    }
+    Ok(started.elapsed())
+}
+
+/// Details of this build and machine, to include in a bug report.
+pub fn diagnostics() -> String {
+    let sys =
+        System::new_with_specifics(RefreshKind::nothing().with_cpu(CpuRefreshKind::nothing()));
+    let cpu = sys
+        .cpus()
+        .first()
+        .map(|cpu| cpu.brand().trim().to_string())
+        .filter(|brand| !brand.is_empty())
+        .unwrap_or_else(|| "unknown".to_string());
+    format!(
+        "nexus-network {} ({}/{})\nCPU: {} ({} logical cores)\nMemory: {:.1} GB",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        cpu,
+        crate::system::num_cores(),
+        crate::system::total_memory_gb()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // The known-answer task should pass every check on a working build.
+    fn test_self_test_passes() {
+        if let Err(e) = run() {
+            panic!("Self-test failed: {}\n{}", e, diagnostics());
+        }
+    }
+}
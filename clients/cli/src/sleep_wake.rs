@@ -0,0 +1,180 @@
+//! Sleep and wake
+//!
+//! When a laptop sleeps, the node's connections die with the network and its clock may drift,
+//! but nothing tells the process: on wake, requests time out one after another on connections
+//! that no longer exist. No portable API reports suspend, so a watchdog ticks every few seconds
+//! and compares the wall-clock time between ticks with the tick. A gap far longer than the tick
+//! means the machine was asleep (a clock set forward looks the same and is handled the same way).
+//!
+//! On wake, pooled and pinned connections are dropped, proxy health checks run at once, task
+//! fetching skips its backoff, and the clock is compared with the orchestrator's again.
+
+use crate::error_classifier::LogLevel;
+use crate::events::{Event, EventType};
+use crate::proxy::ProxyContext;
+use chrono::{DateTime, Utc};
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, SystemTime};
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio::task::JoinHandle;
+
+/// Time between watchdog ticks
+const TICK: Duration = Duration::from_secs(5);
+
+/// Wall-clock time beyond a tick that counts as sleep
+const MIN_SLEEP: Duration = Duration::from_secs(30);
+
+/// Difference with the orchestrator's clock worth a warning
+const MAX_CLOCK_SKEW: Duration = Duration::from_secs(30);
+
+/// Number of wakes so far, notifying the workers that react to them
+static WAKES: OnceLock<watch::Sender<u64>> = OnceLock::new();
+
+fn wakes_sender() -> &'static watch::Sender<u64> {
+    WAKES.get_or_init(|| watch::channel(0).0)
+}
+
+/// How many times the system resumed from sleep since the process started.
+pub fn wakes() -> u64 {
+    *wakes_sender().borrow()
+}
+
+/// Notified each time the system resumes from sleep.
+pub fn subscribe() -> watch::Receiver<u64> {
+    wakes_sender().subscribe()
+}
+
+/// How long the system slept, if `wall_elapsed` passed on the wall clock during a `tick`.
+fn slept(wall_elapsed: Duration, tick: Duration) -> Option<Duration> {
+    wall_elapsed
+        .checked_sub(tick)
+        .filter(|asleep| *asleep >= MIN_SLEEP)
+}
+
+/// Seconds the local clock is ahead of the orchestrator's (behind if negative), if more than
+/// `MAX_CLOCK_SKEW`.
+fn clock_skew(local: DateTime<Utc>, server: DateTime<Utc>) -> Option<i64> {
+    let skew = (local - server).num_seconds();
+    (skew.unsigned_abs() > MAX_CLOCK_SKEW.as_secs()).then_some(skew)
+}
+
+/// Watches for the system resuming from sleep until shutdown. `server_time` reads the
+/// orchestrator's clock, compared with the local one at start and after every wake; the
+/// warmed-up connections of `proxies` are dropped on wake.
+pub fn spawn_watchdog<F, Fut>(
+    server_time: F,
+    proxies: Arc<ProxyContext>,
+    event_sender: mpsc::Sender<Event>,
+    mut shutdown: broadcast::Receiver<()>,
+) -> JoinHandle<()>
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<DateTime<Utc>, String>> + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut check_clock = true;
+        loop {
+            if check_clock {
+                tokio::select! {
+                    _ = shutdown.recv() => break,
+                    _ = report_clock_skew(server_time(), &event_sender) => {}
+                }
+            }
+            let last_tick = SystemTime::now();
+            tokio::select! {
+                _ = shutdown.recv() => break,
+                _ = tokio::time::sleep(TICK) => {}
+            }
+            // A clock set back is not sleep
+            let asleep = last_tick
+                .elapsed()
+                .ok()
+                .and_then(|elapsed| slept(elapsed, TICK));
+            check_clock = asleep.is_some();
+            if let Some(asleep) = asleep {
+                resume(asleep, &proxies, &event_sender).await;
+            }
+        }
+    })
+}
+
+/// Drops the connections that died during sleep and lets the workers know.
+async fn resume(asleep: Duration, proxies: &ProxyContext, event_sender: &mpsc::Sender<Event>) {
+    proxies.reset_connections();
+    wakes_sender().send_modify(|wakes| *wakes += 1);
+    let _ = event_sender
+        .send(Event::task_fetcher_with_level(
+            format!(
+                "System resumed after {}m {}s asleep, reconnecting and re-checking proxies",
+                asleep.as_secs() / 60,
+                asleep.as_secs() % 60
+            ),
+            EventType::Refresh,
+            LogLevel::Info,
+        ))
+        .await;
+}
+
+/// Warns if the local clock is off from the orchestrator's. A clock that cannot be read is only
+/// logged, since the orchestrator may not be reachable yet.
+async fn report_clock_skew(
+    server_time: impl Future<Output = Result<DateTime<Utc>, String>>,
+    event_sender: &mpsc::Sender<Event>,
+) {
+    let server = match server_time.await {
+        Ok(server) => server,
+        Err(e) => {
+            log::debug!("Could not read the orchestrator's clock: {}", e);
+            return;
+        }
+    };
+    if let Some(skew) = clock_skew(Utc::now(), server) {
+        let _ = event_sender
+            .send(Event::task_fetcher_with_level(
+                format!(
+                    "System clock is {}s {} the orchestrator's; enable time synchronization",
+                    skew.abs(),
+                    if skew > 0 { "ahead of" } else { "behind" }
+                ),
+                EventType::Refresh,
+                LogLevel::Warn,
+            ))
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Only a wall-clock gap well beyond the tick counts as sleep.
+    fn test_slept() {
+        assert_eq!(slept(Duration::from_secs(5), TICK), None);
+        assert_eq!(slept(Duration::from_secs(20), TICK), None);
+        assert_eq!(slept(Duration::from_secs(3), TICK), None);
+        assert_eq!(
+            slept(Duration::from_secs(605), TICK),
+            Some(Duration::from_secs(600))
+        );
+    }
+
+    #[test]
+    // Skew within the tolerance is not reported, either way.
+    fn test_clock_skew() {
+        let server = Utc::now();
+        assert_eq!(
+            clock_skew(server + chrono::Duration::seconds(10), server),
+            None
+        );
+        assert_eq!(
+            clock_skew(server + chrono::Duration::seconds(90), server),
+            Some(90)
+        );
+        assert_eq!(
+            clock_skew(server - chrono::Duration::seconds(45), server),
+            Some(-45)
+        );
+    }
+}
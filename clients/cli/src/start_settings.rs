@@ -0,0 +1,246 @@
+//! Layered settings for `start`
+//!
+//! The flags of `start`, and the global flags it takes such as `--log-level`, can be kept in
+//! `~/.nexus/config.toml` or set as `NEXUS_*` environment variables instead of on the command
+//! line, so a systemd unit or compose file does not have to carry fifteen of them. Each flag
+//! is resolved in order of increasing precedence:
+//!
+//! 1. its default;
+//! 2. `config.toml`, keyed by the flag's long name (`max-threads` or `max_threads`);
+//! 3. `NEXUS_` and the long name in upper snake case, e.g. `NEXUS_MAX_THREADS`;
+//! 4. the command line.
+//!
+//! ```toml
+//! node-id = [123, 456]
+//! max-threads = 4
+//! proxy = "/etc/nexus/proxies.txt"
+//! headless = true
+//! log-level = "info,proxy=warn"
+//! environment = "production"
+//! ```
+//!
+//! Switches such as `headless` are turned on by `true` (or `1`). Flags that can be repeated
+//! take an array in the file. Unknown keys are rejected, so a typo does not go unnoticed;
+//! `--sandbox` cannot be set here, since it decides where the file is.
+
+use clap::parser::ValueSource;
+use clap::{ArgAction, Parser};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use toml_edit::{DocumentMut, Item, Value};
+
+/// Path to the settings file, next to the config file.
+pub fn settings_path(config_path: &Path) -> PathBuf {
+    config_path.with_file_name("config.toml")
+}
+
+/// A flag that can be set in the file or the environment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Flag {
+    id: String,
+    long: String,
+    switch: bool,
+    repeatable: bool,
+}
+
+impl Flag {
+    fn env_var(&self) -> String {
+        format!("NEXUS_{}", self.long.to_uppercase().replace('-', "_"))
+    }
+}
+
+/// The flags of `start` and the global flags, but `--sandbox`.
+fn layered_flags(command: &clap::Command) -> Vec<Flag> {
+    let start = command
+        .find_subcommand("start")
+        .map(|start| start.get_arguments().collect::<Vec<_>>())
+        .unwrap_or_default();
+    command
+        .get_arguments()
+        .filter(|arg| arg.is_global_set())
+        .chain(start)
+        .filter_map(|arg| {
+            let long = arg.get_long()?;
+            if matches!(long, "sandbox" | "help" | "version") {
+                return None;
+            }
+            Some(Flag {
+                id: arg.get_id().to_string(),
+                long: long.to_string(),
+                switch: matches!(arg.get_action(), ArgAction::SetTrue),
+                repeatable: matches!(arg.get_action(), ArgAction::Append),
+            })
+        })
+        .collect()
+}
+
+/// Settings read from the file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct SettingsFile {
+    /// Values by flag long name
+    values: BTreeMap<String, Vec<String>>,
+}
+
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.value().clone()),
+        Value::Integer(i) => Some(i.value().to_string()),
+        Value::Float(f) => Some(f.value().to_string()),
+        Value::Boolean(b) => Some(b.value().to_string()),
+        _ => None,
+    }
+}
+
+impl SettingsFile {
+    /// Parses the file, checking every key against `flags`.
+    fn parse(text: &str, flags: &[Flag]) -> Result<Self, String> {
+        let doc: DocumentMut = text.parse().map_err(|e| format!("{}", e))?;
+        let mut file = SettingsFile::default();
+        for (key, item) in doc.iter() {
+            let long = key.replace('_', "-");
+            let Item::Value(value) = item else {
+                return Err(format!("'{}' must be a value, not a table", key));
+            };
+            let flag = flags
+                .iter()
+                .find(|flag| flag.long == long)
+                .ok_or_else(|| format!("unknown setting '{}'", key))?;
+            let values = match value {
+                Value::Array(array) if flag.repeatable => array
+                    .iter()
+                    .map(|value| scalar(value).ok_or_else(|| format!("invalid value in '{}'", key)))
+                    .collect::<Result<Vec<_>, _>>()?,
+                Value::Array(_) => return Err(format!("'{}' takes a single value", key)),
+                value => vec![scalar(value).ok_or_else(|| format!("invalid value for '{}'", key))?],
+            };
+            file.values.insert(long, values);
+        }
+        Ok(file)
+    }
+}
+
+/// The arguments that set `flags` not given on the command line from the environment, looked up
+/// with `lookup`, or else from the file.
+fn layered_args(
+    flags: &[Flag],
+    given: impl Fn(&str) -> bool,
+    file: &SettingsFile,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Vec<String> {
+    let mut args = Vec::new();
+    for flag in flags.iter().filter(|flag| !given(&flag.id)) {
+        let values = match lookup(&flag.env_var()).filter(|value| !value.is_empty()) {
+            Some(value) => vec![value],
+            None => match file.values.get(&flag.long) {
+                Some(values) => values.clone(),
+                None => continue,
+            },
+        };
+        if flag.switch {
+            let on = values
+                .iter()
+                .any(|value| matches!(value.trim(), "true" | "1" | "yes"));
+            if on {
+                args.push(format!("--{}", flag.long));
+            }
+        } else {
+            args.extend(
+                values
+                    .iter()
+                    .map(|value| format!("--{}={}", flag.long, value)),
+            );
+        }
+    }
+    args
+}
+
+/// Parses the arguments of `start` with the flags it was not given filled in from `NEXUS_*`
+/// variables and the settings file at `path`.
+///
+/// # Errors
+/// Returns an error if the settings file is invalid, or a setting is not a valid flag value.
+pub fn parse_layered<P: Parser>(argv: Vec<OsString>, path: &Path) -> Result<P, Box<dyn Error>> {
+    let command = P::command();
+    let flags = layered_flags(&command);
+    let file = match std::fs::read_to_string(path) {
+        Ok(text) => SettingsFile::parse(&text, &flags)
+            .map_err(|e| format!("Invalid {}: {}", path.display(), e))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => SettingsFile::default(),
+        Err(e) => return Err(format!("Cannot read {}: {}", path.display(), e).into()),
+    };
+    let matches = command.try_get_matches_from(&argv)?;
+    let start = matches.subcommand_matches("start").unwrap_or(&matches);
+    let given = |id: &str| {
+        start
+            .try_get_raw(id)
+            .is_ok_and(|_| start.value_source(id) == Some(ValueSource::CommandLine))
+    };
+    let extra = layered_args(&flags, given, &file, |name| std::env::var(name).ok());
+    let args = P::try_parse_from(
+        argv.into_iter()
+            .chain(extra.into_iter().map(OsString::from)),
+    )?;
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn flags() -> Vec<Flag> {
+        let flag = |long: &str, switch: bool, repeatable: bool| Flag {
+            id: long.replace('-', "_"),
+            long: long.to_string(),
+            switch,
+            repeatable,
+        };
+        vec![
+            flag("node-id", false, true),
+            flag("max-threads", false, false),
+            flag("headless", true, false),
+            flag("log-level", false, false),
+            flag("environment", false, false),
+        ]
+    }
+
+    #[test]
+    // Keys name flags with dashes or underscores; unknown keys and misplaced arrays are rejected.
+    fn test_parse_file() {
+        let file = SettingsFile::parse(
+            "node_id = [1, 2]\nmax-threads = 4\nheadless = true\nenvironment = \"production\"\n",
+            &flags(),
+        )
+        .unwrap();
+        assert_eq!(file.values["environment"], ["production"]);
+        assert_eq!(file.values["node-id"], ["1", "2"]);
+        assert_eq!(file.values["max-threads"], ["4"]);
+
+        assert!(SettingsFile::parse("max_thread = 4", &flags()).is_err());
+        assert!(SettingsFile::parse("max-threads = [4]", &flags()).is_err());
+        assert!(SettingsFile::parse("[start]\nheadless = true", &flags()).is_err());
+    }
+
+    #[test]
+    // The command line beats the environment, which beats the file.
+    fn test_precedence() {
+        let file = SettingsFile::parse(
+            "node-id = [1, 2]\nmax-threads = 4\nheadless = true\nlog-level = \"info\"",
+            &flags(),
+        )
+        .unwrap();
+        let env = HashMap::from([
+            ("NEXUS_MAX_THREADS".to_string(), "2".to_string()),
+            ("NEXUS_HEADLESS".to_string(), "false".to_string()),
+        ]);
+        let args = layered_args(
+            &flags(),
+            |id| id == "log_level",
+            &file,
+            |name| env.get(name).cloned(),
+        );
+        assert_eq!(args, ["--node-id=1", "--node-id=2", "--max-threads=2"]);
+    }
+}
@@ -0,0 +1,188 @@
+//! Startup summary
+//!
+//! Before its workers start, `start` prints the configuration it actually runs with (flags,
+//! config file and defaults merged), so a wrong node ID, a missing proxy file or a forgotten
+//! limit is visible at once rather than discovered hours later. The same summary is written
+//! as JSON to `startup.json` next to the config file, where `nexus support-bundle` picks it up.
+
+use crate::environment::Environment;
+use crate::proxy::ProxyContext;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Path to the summary of the last start, next to the config file at `config_path`.
+pub fn summary_path(config_path: &Path) -> PathBuf {
+    config_path.with_file_name("startup.json")
+}
+
+/// The effective configuration of a node process.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct StartupSummary {
+    pub version: &'static str,
+    /// Label of the environment (see `Environment::label`)
+    pub environment: String,
+    pub orchestrator_url: String,
+    pub role: String,
+    /// Empty in anonymous mode
+    pub node_ids: Vec<u64>,
+    /// Workers started; resource profiles may run fewer at times
+    pub workers: usize,
+    pub proxies: String,
+    /// Limits in effect, by name
+    pub limits: BTreeMap<&'static str, String>,
+    /// Directory holding the config, journal and caches
+    pub data_dir: PathBuf,
+    pub queue_dir: Option<PathBuf>,
+    pub recording: Option<PathBuf>,
+}
+
+impl StartupSummary {
+    /// A summary with no limits, to be filled in by the caller.
+    pub fn new(environment: &Environment, role: String, config_path: &Path) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            environment: environment.label(),
+            orchestrator_url: environment.orchestrator_url().to_string(),
+            role,
+            node_ids: Vec::new(),
+            workers: 0,
+            proxies: String::new(),
+            limits: BTreeMap::new(),
+            data_dir: config_path
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default(),
+            queue_dir: None,
+            recording: None,
+        }
+    }
+
+    /// Labels and values, in the order they are printed.
+    fn rows(&self) -> Vec<(&str, String)> {
+        let node_ids = if self.node_ids.is_empty() {
+            "none (anonymous mode)".to_string()
+        } else {
+            self.node_ids
+                .iter()
+                .map(u64::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let mut rows = vec![
+            (
+                "Environment",
+                format!("{} ({})", self.environment, self.orchestrator_url),
+            ),
+            ("Role", self.role.clone()),
+            ("Node IDs", node_ids),
+            ("Workers", self.workers.to_string()),
+            ("Proxies", self.proxies.clone()),
+        ];
+        rows.extend(
+            self.limits
+                .iter()
+                .map(|(name, value)| (*name, value.clone())),
+        );
+        rows.push(("Data directory", self.data_dir.display().to_string()));
+        if let Some(dir) = &self.queue_dir {
+            rows.push(("Queue directory", dir.display().to_string()));
+        }
+        if let Some(path) = &self.recording {
+            rows.push(("Recording", path.display().to_string()));
+        }
+        rows
+    }
+
+    /// The summary as printed.
+    pub fn render(&self) -> String {
+        let mut text = format!("nexus-network {}, effective configuration:\n", self.version);
+        for (label, value) in self.rows() {
+            text.push_str(&format!("  {:<18} {}\n", label, value));
+        }
+        text
+    }
+
+    /// Prints the summary (on stderr if stdout carries progress events, or as one JSON event
+    /// with `--log-format json`) and saves it to `path`. A summary that cannot be saved is only
+    /// reported.
+    pub fn report(&self, path: &Path) {
+        if crate::logging::is_json() {
+            tracing::info!(
+                summary = %serde_json::to_string(self).unwrap_or_default(),
+                "Effective configuration"
+            );
+        } else if crate::progress::on_stdout() {
+            eprint!("{}", self.render());
+        } else {
+            print!("{}", self.render());
+        }
+        let saved = serde_json::to_string_pretty(self)
+            .map_err(std::io::Error::from)
+            .and_then(|json| std::fs::write(path, json));
+        if let Err(e) = saved {
+            eprintln!("⚠️  Could not save {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// How requests reach the orchestrator: through how many proxies, chosen how.
+pub fn describe_proxies(proxies: &ProxyContext) -> String {
+    if !proxies.is_enabled() {
+        return "disabled (--no-proxy)".to_string();
+    }
+    if !proxies.file_exists() {
+        return format!(
+            "none ({} not found), direct connections",
+            proxies.file_path()
+        );
+    }
+    let count = match proxies.manager().lock() {
+        Ok(manager) => match manager.ensure_proxies_loaded() {
+            Ok(()) => manager.proxy_count().to_string(),
+            Err(e) => return format!("{} unreadable: {}", proxies.file_path(), e),
+        },
+        Err(_) => "?".to_string(),
+    };
+    format!(
+        "{} from {}, {} selection, {} assignment, {} when none is usable",
+        count,
+        proxies.file_path(),
+        proxies.settings().strategy,
+        proxies.settings().assignment,
+        proxies.settings().no_proxy_policy
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // The printed summary lists limits between the proxies and the directories, and the saved
+    // one is JSON.
+    fn test_render_and_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.json");
+        let mut summary =
+            StartupSummary::new(&Environment::Production, "all".to_string(), &config_path);
+        summary.node_ids = vec![12, 34];
+        summary.workers = 4;
+        summary.proxies = describe_proxies(&ProxyContext::new(false, "proxies.txt"));
+        summary.limits.insert("Polling", "every 5s".to_string());
+
+        let text = summary.render();
+        assert!(text.contains("Node IDs           12, 34\n"));
+        assert!(text.contains("Proxies            disabled (--no-proxy)\n"));
+        let polling = text.find("Polling").unwrap();
+        assert!(text.find("Proxies").unwrap() < polling);
+        assert!(polling < text.find("Data directory").unwrap());
+
+        let path = summary_path(&config_path);
+        summary.report(&path);
+        let saved: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved["environment"], "production");
+        assert_eq!(saved["limits"]["Polling"], "every 5s");
+    }
+}
@@ -0,0 +1,122 @@
+//! Node status
+//!
+//! Implements `nexus status`, which shows the local node configuration and the capability
+//! figures last reported to the orchestrator, optionally as JSON for scripting.
+
+use crate::capability::{CapabilityReport, get_capability_path};
+use crate::config::Config;
+use crate::control::{Command, RunningStatus, send_command};
+use crate::environment::Environment;
+use crate::pretty::print_cmd_info;
+use serde::Serialize;
+use std::error::Error;
+use std::path::Path;
+use std::time::Duration;
+
+/// Snapshot of the local node state.
+#[derive(Serialize, Debug)]
+struct NodeStatus {
+    version: String,
+    environment: String,
+    config_path: String,
+    node_id: Option<String>,
+    wallet_address: Option<String>,
+    /// Capability last reported to the orchestrator, if a calibration has run.
+    capability: Option<CapabilityReport>,
+    /// State of the node running on this machine, if any.
+    running: Option<RunningStatus>,
+}
+
+/// Prints the node status, as pretty JSON if `json` is set.
+pub async fn print_status(
+    config_path: &Path,
+    environment: &Environment,
+    json: bool,
+) -> Result<(), Box<dyn Error>> {
+    let config = Config::load_from_file(config_path).ok();
+    let non_empty = |s: &String| (!s.is_empty()).then(|| s.clone());
+    let status = NodeStatus {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        environment: environment.to_string(),
+        config_path: config_path.display().to_string(),
+        node_id: config.as_ref().and_then(|c| non_empty(&c.node_id)),
+        wallet_address: config.as_ref().and_then(|c| non_empty(&c.wallet_address)),
+        capability: get_capability_path()
+            .ok()
+            .and_then(|path| CapabilityReport::load_from_file(&path).ok()),
+        running: tokio::time::timeout(Duration::from_secs(2), send_command(Command::Status))
+            .await
+            .ok()
+            .and_then(Result::ok)
+            .and_then(|response| response.status),
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&status)?);
+        return Ok(());
+    }
+
+    print_cmd_info!(
+        "Node status",
+        "Version: {}\nEnvironment: {}\nNode ID: {}\nWallet address: {}",
+        status.version,
+        status.environment,
+        status.node_id.as_deref().unwrap_or("Not registered"),
+        status.wallet_address.as_deref().unwrap_or("Not registered")
+    );
+    match &status.running {
+        Some(running) => {
+            let state = if running.handing_off {
+                "handing off"
+            } else if running.draining {
+                "draining"
+            } else if running.paused {
+                "paused"
+            } else {
+                "proving"
+            };
+            let seed = running
+                .seed
+                .map(|seed| format!("\nRandom seed: {} (replay with --seed {})", seed, seed))
+                .unwrap_or_default();
+            print_cmd_info!(
+                "Running node",
+                "PID {} (version {}, {}), up {} min, {}, {} tasks in flight{}",
+                running.pid,
+                running.version,
+                running
+                    .environment
+                    .as_deref()
+                    .unwrap_or("unknown environment"),
+                running.uptime_secs / 60,
+                state,
+                running.tasks_in_flight,
+                seed
+            );
+        }
+        None => {
+            print_cmd_info!("Running node", "No node is running on this machine.");
+        }
+    }
+    match &status.capability {
+        Some(capability) => {
+            print_cmd_info!(
+                "Reported capability",
+                "Calibrated at: {}\nThroughput: {} GFLOP/s ({:.2} per thread, {} threads, peak {:.1})\nMemory capacity: {}",
+                capability.calibrated_at,
+                capability.reported_flops_per_sec,
+                capability.measured_gflops_per_thread,
+                capability.num_provers,
+                capability.estimated_peak_gflops,
+                capability.memory_capacity
+            );
+        }
+        None => {
+            print_cmd_info!(
+                "Reported capability",
+                "No calibration has run yet. Start the prover to calibrate this machine."
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,159 @@
+//! Status lines
+//!
+//! A headless node (`--headless`, or `start` with stdout not a terminal, as under systemd or in
+//! Kubernetes) prints a snapshot of its state as a single line of JSON every
+//! `--status-interval`, so it can be followed in journald or `kubectl logs` without the
+//! dashboard:
+//!
+//! ```json
+//! {"status":"running","uptime_secs":3600,"tasks_in_flight":1,"proofs_completed":42,...}
+//! ```
+//!
+//! The orchestrator API does not report points, so accepted proofs (`proofs_accepted`) stand in
+//! for them, as in `metrics`.
+
+use crate::error_classifier::LogLevel;
+use crate::events::{Event, EventType, Worker};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Serialize;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Time between status lines, unless set with `--status-interval`
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(60);
+
+static INTERVAL: OnceLock<Duration> = OnceLock::new();
+
+/// Set the time between status lines; zero disables them. Only the first call has an effect.
+pub fn set_interval(interval: Duration) {
+    let _ = INTERVAL.set(interval);
+}
+
+/// The time between status lines, unless they are disabled
+pub fn interval() -> Option<Duration> {
+    let interval = INTERVAL.get().copied().unwrap_or(DEFAULT_INTERVAL);
+    (!interval.is_zero()).then_some(interval)
+}
+
+/// The state of the node at one point in time.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct StatusLine {
+    pub timestamp: String,
+    /// `running`, `paused` or `draining`
+    pub status: &'static str,
+    pub uptime_secs: u64,
+    /// Tasks fetched but not yet proved and submitted
+    pub tasks_in_flight: usize,
+    pub tasks_fetched: u64,
+    pub proofs_completed: u64,
+    pub last_proof_at: Option<String>,
+    pub proofs_accepted: u64,
+    pub submissions_failed: u64,
+    /// Error events since start
+    pub errors: u64,
+    pub last_error: Option<String>,
+}
+
+/// Follows worker events for what status lines report beyond the control state and node stats.
+#[derive(Debug, Default)]
+pub struct StatusTracker {
+    proofs_completed: u64,
+    last_proof_at: Option<DateTime<Utc>>,
+    errors: u64,
+    last_error: Option<String>,
+}
+
+impl StatusTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes an event into account, whether or not it is displayed.
+    pub fn observe(&mut self, event: &Event) {
+        if matches!(event.worker, Worker::Prover(_)) && event.event_type == EventType::Success {
+            self.proofs_completed += 1;
+            self.last_proof_at = Some(Utc::now());
+        }
+        if event.event_type == EventType::Error || event.log_level >= LogLevel::Error {
+            self.errors += 1;
+            self.last_error = Some(event.msg.clone());
+        }
+    }
+
+    /// The current state of the node.
+    pub fn snapshot(&self) -> StatusLine {
+        let running = crate::control::control_state().status();
+        let nodes = crate::nodes::stats();
+        let status = if running.draining {
+            "draining"
+        } else if running.paused {
+            "paused"
+        } else {
+            "running"
+        };
+        StatusLine {
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            status,
+            uptime_secs: running.uptime_secs,
+            tasks_in_flight: running.tasks_in_flight,
+            tasks_fetched: nodes.values().map(|stats| stats.tasks_fetched).sum(),
+            proofs_completed: self.proofs_completed,
+            last_proof_at: self
+                .last_proof_at
+                .map(|at| at.to_rfc3339_opts(SecondsFormat::Secs, true)),
+            proofs_accepted: nodes.values().map(|stats| stats.proofs_submitted).sum(),
+            submissions_failed: nodes.values().map(|stats| stats.submissions_failed).sum(),
+            errors: self.errors,
+            last_error: self.last_error.clone(),
+        }
+    }
+
+    /// Prints the current state as one line of JSON (on stderr if stdout carries progress
+    /// events, or as a JSON log event with `--log-format json`).
+    pub fn print(&self) {
+        let Ok(json) = serde_json::to_string(&self.snapshot()) else {
+            return;
+        };
+        if crate::logging::is_json() {
+            tracing::info!(status = %json, "Status");
+        } else if crate::progress::on_stdout() {
+            eprintln!("{}", json);
+        } else {
+            println!("{}", json);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Proofs and errors are counted from events, and the snapshot is a single line.
+    fn test_snapshot_counts_events() {
+        let mut tracker = StatusTracker::new();
+        tracker.observe(&Event::prover(
+            0,
+            "Proof completed".to_string(),
+            EventType::Success,
+        ));
+        tracker.observe(&Event::task_fetcher(
+            "Fetched 1 task".to_string(),
+            EventType::Success,
+        ));
+        tracker.observe(&Event::proof_submitter(
+            "Failed to submit proof".to_string(),
+            EventType::Error,
+        ));
+
+        let snapshot = tracker.snapshot();
+        assert_eq!(snapshot.proofs_completed, 1);
+        assert!(snapshot.last_proof_at.is_some());
+        assert_eq!(snapshot.errors, 1);
+        assert_eq!(
+            snapshot.last_error.as_deref(),
+            Some("Failed to submit proof")
+        );
+        assert!(!serde_json::to_string(&snapshot).unwrap().contains('\n'));
+    }
+}
@@ -0,0 +1,682 @@
+//! Submission journal
+//!
+//! A write-ahead journal wrapping proof submission in a two-phase pattern:
+//! a task is recorded as `Prepared` (with its proof payload saved alongside) before
+//! it is submitted, and as `Committed` or `Aborted` once the outcome is known.
+//!
+//! Entries still `Prepared` when the process starts were interrupted mid-submission
+//! (crash, kill, network drop). They are reconciled on startup by resubmitting the saved
+//! proof, which closes the lost-credit window, while committed task IDs are restored so
+//! that reassigned tasks are not submitted twice.
+//!
+//! With `--verify-submissions`, committed entries also record whether the proof was later
+//! confirmed as credited (see `submission_verifier`). Committed entries keep the submitting
+//! node and the time of acceptance across compaction, for `reconcile`, and the orchestrator's
+//! signed receipt if it sent one (also archived in full, see `receipts`).
+//!
+//! Each environment has its own journal (see [`journal_dir`]), so proofs prepared for one
+//! orchestrator are never resubmitted to another, and `reconcile` compares each orchestrator
+//! only with its own submissions.
+
+use crate::environment::Environment;
+use crate::receipts::{self, SubmissionReceipt};
+use crate::task::Task;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// File name of the journal within the journal directory.
+const JOURNAL_FILE: &str = "submissions.ndjson";
+
+/// Maximum number of committed task IDs remembered across restarts.
+const MAX_COMMITTED_TASKS: usize = 500;
+
+/// Directory of the journal of `environment`, next to the config file. Production keeps the
+/// `journal` directory of earlier releases; other environments get one named after their label.
+pub fn journal_dir(config_path: &Path, environment: &Environment) -> PathBuf {
+    let label = environment.label();
+    if label == "production" {
+        return config_path.with_file_name("journal");
+    }
+    // Labels of custom orchestrators are host:port, which not every file system accepts
+    let safe_label: String = label
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    config_path.with_file_name(format!("journal-{}", safe_label))
+}
+
+/// A single journal record.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "phase", rename_all = "snake_case")]
+enum JournalEntry {
+    /// The proof is about to be submitted.
+    Prepared {
+        task_id: String,
+        program_id: String,
+        task_type: Option<i32>,
+        proof_hash: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        node_id: Option<u64>,
+        /// The proxy the task was fetched through, to resubmit through
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        proxy: Option<String>,
+        timestamp: u64,
+    },
+    /// The orchestrator accepted the submission.
+    Committed {
+        task_id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        node_id: Option<u64>,
+        timestamp: u64,
+        /// The orchestrator's signed acknowledgment, if it sent one
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        receipt: Option<SubmissionReceipt>,
+    },
+    /// The submission failed permanently and will not be retried.
+    Aborted {
+        task_id: String,
+        reason: String,
+        timestamp: u64,
+    },
+    /// A committed submission was checked for credit after the fact.
+    Verified {
+        task_id: String,
+        credited: bool,
+        timestamp: u64,
+    },
+}
+
+/// A submission that was prepared but never committed or aborted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingSubmission {
+    pub task: Task,
+    pub proof_hash: String,
+}
+
+/// A submission the orchestrator accepted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommittedSubmission {
+    pub task_id: String,
+    /// The node that submitted it; unknown for entries written by earlier releases
+    pub node_id: Option<u64>,
+    /// When it was accepted, in seconds since the Unix epoch
+    pub timestamp: u64,
+    /// Whether it was confirmed as credited, if it was verified
+    pub credited: Option<bool>,
+}
+
+/// Write-ahead journal of proof submissions.
+#[derive(Debug)]
+pub struct SubmissionJournal {
+    /// Directory holding the journal and proof payloads; `None` keeps state in memory only.
+    dir: Option<PathBuf>,
+    pending: HashMap<String, PendingSubmission>,
+    committed: VecDeque<String>,
+    /// The node and acceptance time of each committed submission, by task ID
+    committed_by_id: HashMap<String, (Option<u64>, u64)>,
+    /// Whether committed submissions were confirmed as credited, for those verified
+    verified: HashMap<String, bool>,
+    /// The orchestrator's receipts for committed submissions, for those that have one
+    receipts: HashMap<String, SubmissionReceipt>,
+}
+
+impl SubmissionJournal {
+    /// Opens (or creates) the journal in the given directory, replaying and compacting it.
+    ///
+    /// # Errors
+    /// Returns an `std::io::Error` if the directory or journal file cannot be read or written.
+    pub fn open(dir: &Path) -> std::io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let mut journal = Self::replay(dir)?;
+        journal.dir = Some(dir.to_path_buf());
+        journal.compact()?;
+        Ok(journal)
+    }
+
+    /// Reads the journal in the given directory without changing it, for reports made while a
+    /// node may be appending to it. The journal returned is not persisted; no journal is an
+    /// empty one.
+    ///
+    /// # Errors
+    /// Returns an `std::io::Error` if the journal file cannot be read.
+    pub fn read(dir: &Path) -> std::io::Result<Self> {
+        Self::replay(dir)
+    }
+
+    /// Replays the journal file in `dir` into an in-memory journal.
+    fn replay(dir: &Path) -> std::io::Result<Self> {
+        let mut journal = Self::in_memory();
+        let path = dir.join(JOURNAL_FILE);
+        if path.exists() {
+            let reader = BufReader::new(File::open(&path)?);
+            for line in reader.lines() {
+                let line = line?;
+                // A torn final line from a crash mid-write is skipped.
+                if let Ok(entry) = serde_json::from_str::<JournalEntry>(&line) {
+                    journal.apply(entry);
+                }
+            }
+        }
+        Ok(journal)
+    }
+
+    /// Creates a journal that is not persisted, used when the journal directory is unavailable.
+    pub fn in_memory() -> Self {
+        Self {
+            dir: None,
+            pending: HashMap::new(),
+            committed: VecDeque::new(),
+            committed_by_id: HashMap::new(),
+            verified: HashMap::new(),
+            receipts: HashMap::new(),
+        }
+    }
+
+    /// Records that a proof is about to be submitted, saving the proof payload first.
+    pub fn prepare(
+        &mut self,
+        task: &Task,
+        proof_hash: &str,
+        proof_bytes: &[u8],
+    ) -> std::io::Result<()> {
+        if let Some(path) = self.proof_path(&task.task_id) {
+            fs::write(path, proof_bytes)?;
+        }
+        let entry = JournalEntry::Prepared {
+            task_id: task.task_id.clone(),
+            program_id: task.program_id.clone(),
+            task_type: task.task_type.map(|t| t as i32),
+            proof_hash: proof_hash.to_string(),
+            node_id: task.node_id,
+            proxy: task.proxy.clone(),
+            timestamp: now(),
+        };
+        self.append(&entry)?;
+        self.apply(entry);
+        Ok(())
+    }
+
+    /// Records that the orchestrator accepted the submission, with its receipt if it sent one.
+    pub fn commit(
+        &mut self,
+        task_id: &str,
+        receipt: Option<SubmissionReceipt>,
+    ) -> std::io::Result<()> {
+        let entry = JournalEntry::Committed {
+            task_id: task_id.to_string(),
+            node_id: self
+                .pending
+                .get(task_id)
+                .and_then(|pending| pending.task.node_id),
+            timestamp: now(),
+            receipt: receipt.clone(),
+        };
+        self.append(&entry)?;
+        self.apply(entry);
+        self.remove_proof(task_id);
+        match (&self.dir, receipt) {
+            (Some(dir), Some(receipt)) => receipts::archive(dir, &receipt),
+            _ => Ok(()),
+        }
+    }
+
+    /// Records that the submission failed permanently.
+    pub fn abort(&mut self, task_id: &str, reason: &str) -> std::io::Result<()> {
+        let entry = JournalEntry::Aborted {
+            task_id: task_id.to_string(),
+            reason: reason.to_string(),
+            timestamp: now(),
+        };
+        self.append(&entry)?;
+        self.apply(entry);
+        self.remove_proof(task_id);
+        Ok(())
+    }
+
+    /// Records whether a committed submission was confirmed as credited.
+    pub fn record_verification(&mut self, task_id: &str, credited: bool) -> std::io::Result<()> {
+        let entry = JournalEntry::Verified {
+            task_id: task_id.to_string(),
+            credited,
+            timestamp: now(),
+        };
+        self.append(&entry)?;
+        self.apply(entry);
+        Ok(())
+    }
+
+    /// Whether a committed submission was confirmed as credited, if it was verified.
+    pub fn verification(&self, task_id: &str) -> Option<bool> {
+        self.verified.get(task_id).copied()
+    }
+
+    /// The orchestrator's receipt for a committed submission, if it sent one.
+    pub fn receipt(&self, task_id: &str) -> Option<&SubmissionReceipt> {
+        self.receipts.get(task_id)
+    }
+
+    /// Whether a submission for this task was committed.
+    pub fn is_committed(&self, task_id: &str) -> bool {
+        self.committed_by_id.contains_key(task_id)
+    }
+
+    /// Whether this task's proof is in the journal, pending or committed.
+    pub fn is_journaled(&self, task_id: &str) -> bool {
+        self.pending.contains_key(task_id) || self.is_committed(task_id)
+    }
+
+    /// Task IDs of committed submissions, oldest first.
+    pub fn committed_task_ids(&self) -> impl Iterator<Item = &String> {
+        self.committed.iter()
+    }
+
+    /// Committed submissions, oldest first.
+    pub fn committed(&self) -> Vec<CommittedSubmission> {
+        self.committed
+            .iter()
+            .filter_map(|task_id| {
+                let (node_id, timestamp) = *self.committed_by_id.get(task_id)?;
+                Some(CommittedSubmission {
+                    task_id: task_id.clone(),
+                    node_id,
+                    timestamp,
+                    credited: self.verified.get(task_id).copied(),
+                })
+            })
+            .collect()
+    }
+
+    /// Submissions that were prepared but never resolved.
+    pub fn pending(&self) -> Vec<PendingSubmission> {
+        self.pending.values().cloned().collect()
+    }
+
+    /// Loads the saved proof payload for a pending submission.
+    pub fn load_proof(&self, task_id: &str) -> std::io::Result<Vec<u8>> {
+        match self.proof_path(task_id) {
+            Some(path) => fs::read(path).or_else(|e| match self.legacy_proof_path(task_id) {
+                Some(legacy) if e.kind() == std::io::ErrorKind::NotFound => fs::read(legacy),
+                _ => Err(e),
+            }),
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "Journal is not persisted",
+            )),
+        }
+    }
+
+    /// Applies an entry to the in-memory state.
+    fn apply(&mut self, entry: JournalEntry) {
+        match entry {
+            JournalEntry::Prepared {
+                task_id,
+                program_id,
+                task_type,
+                proof_hash,
+                node_id,
+                proxy,
+                ..
+            } => {
+                let mut task = Task::new(task_id.clone(), program_id, Vec::new());
+                task.task_type =
+                    task_type.and_then(|t| crate::nexus_orchestrator::TaskType::try_from(t).ok());
+                task.node_id = node_id;
+                task.proxy = proxy;
+                self.pending
+                    .insert(task_id, PendingSubmission { task, proof_hash });
+            }
+            JournalEntry::Committed {
+                task_id,
+                node_id,
+                timestamp,
+                receipt,
+            } => {
+                self.pending.remove(&task_id);
+                if !self.committed_by_id.contains_key(&task_id) {
+                    self.committed_by_id
+                        .insert(task_id.clone(), (node_id, timestamp));
+                    if let Some(receipt) = receipt {
+                        self.receipts.insert(task_id.clone(), receipt);
+                    }
+                    self.committed.push_back(task_id);
+                    if self.committed.len() > MAX_COMMITTED_TASKS {
+                        if let Some(oldest) = self.committed.pop_front() {
+                            self.committed_by_id.remove(&oldest);
+                            self.verified.remove(&oldest);
+                            self.receipts.remove(&oldest);
+                        }
+                    }
+                }
+            }
+            JournalEntry::Aborted { task_id, .. } => {
+                self.pending.remove(&task_id);
+            }
+            JournalEntry::Verified {
+                task_id, credited, ..
+            } => {
+                if self.committed_by_id.contains_key(&task_id) {
+                    self.verified.insert(task_id, credited);
+                }
+            }
+        }
+    }
+
+    /// Rewrites the journal with only the entries still needed: pending and recent committed.
+    fn compact(&self) -> std::io::Result<()> {
+        let Some(dir) = &self.dir else {
+            return Ok(());
+        };
+
+        let mut contents = String::new();
+        for task_id in &self.committed {
+            // Keep the original acceptance time, which `reconcile` groups submissions by
+            let (node_id, timestamp) = self.committed_by_id[task_id];
+            let entry = JournalEntry::Committed {
+                task_id: task_id.clone(),
+                node_id,
+                timestamp,
+                receipt: self.receipts.get(task_id).cloned(),
+            };
+            contents.push_str(&serde_json::to_string(&entry)?);
+            contents.push('\n');
+            if let Some(&credited) = self.verified.get(task_id) {
+                let entry = JournalEntry::Verified {
+                    task_id: task_id.clone(),
+                    credited,
+                    timestamp: now(),
+                };
+                contents.push_str(&serde_json::to_string(&entry)?);
+                contents.push('\n');
+            }
+        }
+        for pending in self.pending.values() {
+            let entry = JournalEntry::Prepared {
+                task_id: pending.task.task_id.clone(),
+                program_id: pending.task.program_id.clone(),
+                task_type: pending.task.task_type.map(|t| t as i32),
+                proof_hash: pending.proof_hash.clone(),
+                node_id: pending.task.node_id,
+                proxy: pending.task.proxy.clone(),
+                timestamp: now(),
+            };
+            contents.push_str(&serde_json::to_string(&entry)?);
+            contents.push('\n');
+        }
+
+        // Write to a temporary file and rename, so a crash never leaves a truncated journal.
+        let tmp_path = dir.join(format!("{}.tmp", JOURNAL_FILE));
+        fs::write(&tmp_path, contents)?;
+        fs::rename(tmp_path, dir.join(JOURNAL_FILE))
+    }
+
+    /// Appends an entry to the journal and flushes it to disk.
+    fn append(&self, entry: &JournalEntry) -> std::io::Result<()> {
+        let Some(dir) = &self.dir else {
+            return Ok(());
+        };
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(JOURNAL_FILE))?;
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        file.write_all(line.as_bytes())?;
+        file.sync_data()
+    }
+
+    fn proof_path(&self, task_id: &str) -> Option<PathBuf> {
+        // Task IDs come from the orchestrator; name the file by their digest, which is safe in
+        // a path and distinct for distinct IDs.
+        let digest: String = Sha256::digest(task_id.as_bytes())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        self.dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{}.proof", digest)))
+    }
+
+    /// Where earlier releases saved the proof of `task_id`, with unsafe characters replaced.
+    fn legacy_proof_path(&self, task_id: &str) -> Option<PathBuf> {
+        let safe_id: String = task_id
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{}.proof", safe_id)))
+    }
+
+    fn remove_proof(&self, task_id: &str) {
+        for path in [self.proof_path(task_id), self.legacy_proof_path(task_id)]
+            .into_iter()
+            .flatten()
+        {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nexus_orchestrator::TaskType;
+    use tempfile::tempdir;
+
+    fn task(task_id: &str) -> Task {
+        let mut task = Task::new(task_id.to_string(), "fast-fib".to_string(), vec![1]);
+        task.task_type = Some(TaskType::ProofHash);
+        task
+    }
+
+    #[test]
+    // A prepared but unresolved submission should survive a restart, with its proof.
+    fn test_pending_submission_survives_reopen() {
+        let dir = tempdir().unwrap();
+        let mut journal = SubmissionJournal::open(dir.path()).unwrap();
+        journal
+            .prepare(&task("task-1"), "hash", &[1, 2, 3])
+            .unwrap();
+        drop(journal);
+
+        let journal = SubmissionJournal::open(dir.path()).unwrap();
+        let pending = journal.pending();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].task.task_id, "task-1");
+        assert_eq!(pending[0].task.task_type, Some(TaskType::ProofHash));
+        assert_eq!(pending[0].proof_hash, "hash");
+        assert_eq!(journal.load_proof("task-1").unwrap(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    // Task IDs differing only in characters unsafe in file names keep separate proofs, and a
+    // proof saved by an earlier release under its old name is still found.
+    fn test_proof_files_do_not_collide() {
+        let dir = tempdir().unwrap();
+        let mut journal = SubmissionJournal::open(dir.path()).unwrap();
+        journal.prepare(&task("a.b"), "hash", &[1]).unwrap();
+        journal.prepare(&task("a_b"), "hash", &[2]).unwrap();
+        assert_eq!(journal.load_proof("a.b").unwrap(), vec![1]);
+        assert_eq!(journal.load_proof("a_b").unwrap(), vec![2]);
+
+        fs::write(dir.path().join("c_d.proof"), [3]).unwrap();
+        assert_eq!(journal.load_proof("c:d").unwrap(), vec![3]);
+    }
+
+    #[test]
+    // Committed submissions should be remembered across restarts and their proofs removed.
+    fn test_commit_is_remembered() {
+        let dir = tempdir().unwrap();
+        let mut journal = SubmissionJournal::open(dir.path()).unwrap();
+        journal.prepare(&task("task-1"), "hash", &[1]).unwrap();
+        journal.commit("task-1", None).unwrap();
+        drop(journal);
+
+        let journal = SubmissionJournal::open(dir.path()).unwrap();
+        assert!(journal.pending().is_empty());
+        assert!(journal.is_committed("task-1"));
+        assert!(journal.load_proof("task-1").is_err());
+    }
+
+    #[test]
+    // The submitting node and acceptance time should survive compaction.
+    fn test_committed_submission_details_are_kept() {
+        let dir = tempdir().unwrap();
+        let mut journal = SubmissionJournal::open(dir.path()).unwrap();
+        let mut node_task = task("task-1");
+        node_task.node_id = Some(42);
+        journal.prepare(&node_task, "hash", &[1]).unwrap();
+        journal.commit("task-1", None).unwrap();
+        let accepted_at = journal.committed()[0].timestamp;
+        drop(journal);
+
+        let journal = SubmissionJournal::open(dir.path()).unwrap();
+        let committed = journal.committed();
+        assert_eq!(committed.len(), 1);
+        assert_eq!(committed[0].node_id, Some(42));
+        assert_eq!(committed[0].timestamp, accepted_at);
+        assert_eq!(committed[0].credited, None);
+    }
+
+    #[test]
+    // Verification outcomes of committed submissions should survive compaction.
+    fn test_verification_is_remembered() {
+        let dir = tempdir().unwrap();
+        let mut journal = SubmissionJournal::open(dir.path()).unwrap();
+        journal.prepare(&task("task-1"), "hash", &[1]).unwrap();
+        journal.commit("task-1", None).unwrap();
+        journal.record_verification("task-1", false).unwrap();
+        journal.record_verification("unknown", true).unwrap();
+        drop(journal);
+
+        let journal = SubmissionJournal::open(dir.path()).unwrap();
+        assert_eq!(journal.verification("task-1"), Some(false));
+        assert_eq!(journal.verification("unknown"), None);
+    }
+
+    #[test]
+    // A receipt should be kept with its submission across compaction, and archived in full.
+    fn test_receipt_is_kept_and_archived() {
+        let dir = tempdir().unwrap();
+        let receipt = SubmissionReceipt {
+            task_id: "task-1".to_string(),
+            proof_hash: "hash".to_string(),
+            node_key: "aa".to_string(),
+            accepted_at: "2026-10-15T14:05:03Z".to_string(),
+            orchestrator_key: "bb".to_string(),
+            signature: "cc".to_string(),
+        };
+        let mut journal = SubmissionJournal::open(dir.path()).unwrap();
+        journal.prepare(&task("task-1"), "hash", &[1]).unwrap();
+        journal.commit("task-1", Some(receipt.clone())).unwrap();
+        journal.prepare(&task("task-2"), "hash", &[1]).unwrap();
+        journal.commit("task-2", None).unwrap();
+        drop(journal);
+
+        let journal = SubmissionJournal::open(dir.path()).unwrap();
+        assert_eq!(journal.receipt("task-1"), Some(&receipt));
+        assert_eq!(journal.receipt("task-2"), None);
+        let archived = fs::read_to_string(receipts::archive_path(dir.path())).unwrap();
+        assert_eq!(archived.lines().count(), 1);
+    }
+
+    #[test]
+    // Aborted submissions are neither pending nor committed.
+    fn test_abort_resolves_pending() {
+        let dir = tempdir().unwrap();
+        let mut journal = SubmissionJournal::open(dir.path()).unwrap();
+        journal.prepare(&task("task-1"), "hash", &[1]).unwrap();
+        journal.abort("task-1", "HTTP 400").unwrap();
+        drop(journal);
+
+        let journal = SubmissionJournal::open(dir.path()).unwrap();
+        assert!(journal.pending().is_empty());
+        assert!(!journal.is_committed("task-1"));
+    }
+
+    #[test]
+    // Reading the journal for a report should leave the file exactly as it was.
+    fn test_read_does_not_compact() {
+        let dir = tempdir().unwrap();
+        let mut journal = SubmissionJournal::open(dir.path()).unwrap();
+        journal.prepare(&task("task-1"), "hash", &[1]).unwrap();
+        journal.commit("task-1", None).unwrap();
+        drop(journal);
+        let path = dir.path().join(JOURNAL_FILE);
+        let before = fs::read_to_string(&path).unwrap();
+
+        let journal = SubmissionJournal::read(dir.path()).unwrap();
+        assert!(journal.is_committed("task-1"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), before);
+        assert!(
+            SubmissionJournal::read(&dir.path().join("missing"))
+                .unwrap()
+                .committed()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    // Corrupt lines (e.g. a torn write) should be skipped rather than failing the replay.
+    fn test_replay_skips_corrupt_lines() {
+        let dir = tempdir().unwrap();
+        let mut journal = SubmissionJournal::open(dir.path()).unwrap();
+        journal.prepare(&task("task-1"), "hash", &[1]).unwrap();
+        drop(journal);
+
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(dir.path().join(JOURNAL_FILE))
+            .unwrap();
+        write!(file, "{{\"phase\":\"commi").unwrap();
+        drop(file);
+
+        let journal = SubmissionJournal::open(dir.path()).unwrap();
+        assert_eq!(journal.pending().len(), 1);
+    }
+
+    #[test]
+    // Production keeps the original journal directory; other environments get their own.
+    fn test_journal_dir_per_environment() {
+        let config = Path::new("/home/user/.nexus/config.json");
+        assert_eq!(
+            journal_dir(config, &Environment::Production),
+            Path::new("/home/user/.nexus/journal")
+        );
+        assert_eq!(
+            journal_dir(config, &Environment::Staging),
+            Path::new("/home/user/.nexus/journal-staging")
+        );
+        let custom = Environment::Custom {
+            orchestrator_url: "http://localhost:8080".to_string(),
+        };
+        assert_eq!(
+            journal_dir(config, &custom),
+            Path::new("/home/user/.nexus/journal-localhost_8080")
+        );
+    }
+}
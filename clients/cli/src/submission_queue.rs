@@ -0,0 +1,351 @@
+//! Submission queue
+//!
+//! On a slow uplink, finished proofs wait for their upload. They are submitted earliest
+//! deadline first rather than in the order proving finished, and a large upload in progress is
+//! preempted (dropped and requeued) when a smaller proof would otherwise miss its deadline.
+//!
+//! The orchestrator does not send deadlines, so a task is assumed to expire `TASK_LIFETIME`
+//! after it was created (or fetched). Upload times are estimated from the throughput of
+//! earlier uploads; until one has finished, nothing is preempted.
+//!
+//! A proof the orchestrator refused for a transient reason (rate limiting, a brief outage, or
+//! an error body naming the refusal retryable, as after a conflict with a concurrent update) is
+//! set aside and queued again once its retry is due, up to `MAX_RETRIES` times and only while
+//! it can still make its deadline. Refusals are told apart by status and error name, never by
+//! their free-text message.
+
+use crate::nexus_orchestrator::TaskType;
+use crate::task::Task;
+use std::time::{Duration, SystemTime};
+
+/// How long after creation a task is assumed to expire.
+pub const TASK_LIFETIME: Duration = Duration::from_secs(30 * 60);
+
+/// Weight of the latest upload in the throughput estimate.
+const THROUGHPUT_SMOOTHING: f64 = 0.3;
+
+/// Times a transiently refused proof is submitted again.
+pub const MAX_RETRIES: u32 = 3;
+
+/// Wait before the first retry when the orchestrator does not say, doubled for each one after.
+const RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// A finished proof waiting to be submitted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueuedProof {
+    pub task: Task,
+    /// The serialized proof
+    pub proof_bytes: Vec<u8>,
+    /// Bytes actually uploaded; hash-only tasks do not send the proof
+    pub upload_bytes: usize,
+    pub deadline: SystemTime,
+    /// Whether its upload was already preempted once; it is not preempted again
+    pub preempted: bool,
+    /// Times it was submitted again after a transient refusal
+    pub retries: u32,
+    /// Arrival order, the last tie-breaker
+    seq: u64,
+}
+
+impl QueuedProof {
+    /// How long to wait before submitting the proof again after a transient refusal, or `None`
+    /// if it is out of retries or would miss its deadline. `retry_after` is the wait the
+    /// orchestrator asked for, if any.
+    pub fn retry_delay(&self, retry_after: Option<Duration>, now: SystemTime) -> Option<Duration> {
+        if self.retries >= MAX_RETRIES {
+            return None;
+        }
+        let delay = retry_after.unwrap_or(RETRY_DELAY * 2u32.pow(self.retries));
+        (now + delay < self.deadline).then_some(delay)
+    }
+}
+
+/// Finished proofs in submission order.
+#[derive(Debug, Default)]
+pub struct SubmissionQueue {
+    items: Vec<QueuedProof>,
+    /// Transiently refused proofs, with the time they are due to be queued again
+    retrying: Vec<(SystemTime, QueuedProof)>,
+    /// Observed upload throughput in bytes per second
+    throughput: Option<f64>,
+    next_seq: u64,
+}
+
+impl SubmissionQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues a finished proof; `now` stands in for the creation time of tasks without one.
+    pub fn push(&mut self, task: Task, proof_bytes: Vec<u8>, now: SystemTime) {
+        let upload_bytes = match task.task_type {
+            Some(TaskType::ProofHash) => 0,
+            _ => proof_bytes.len(),
+        };
+        let deadline = task.created_at.unwrap_or(now) + TASK_LIFETIME;
+        self.items.push(QueuedProof {
+            task,
+            proof_bytes,
+            upload_bytes,
+            deadline,
+            preempted: false,
+            retries: 0,
+            seq: self.next_seq,
+        });
+        self.next_seq += 1;
+    }
+
+    /// Puts back a proof whose upload was preempted.
+    pub fn requeue(&mut self, mut item: QueuedProof) {
+        item.preempted = true;
+        self.items.push(item);
+    }
+
+    /// Sets aside a transiently refused proof until `retry_at`.
+    pub fn retry_later(&mut self, mut item: QueuedProof, retry_at: SystemTime) {
+        item.retries += 1;
+        self.retrying.push((retry_at, item));
+    }
+
+    /// Queues again the set-aside proofs whose retry is due.
+    pub fn release_due(&mut self, now: SystemTime) {
+        let (due, waiting): (Vec<_>, Vec<_>) = std::mem::take(&mut self.retrying)
+            .into_iter()
+            .partition(|(retry_at, _)| *retry_at <= now);
+        self.retrying = waiting;
+        self.items.extend(due.into_iter().map(|(_, item)| item));
+    }
+
+    /// Time until the next set-aside proof is due, if there is one.
+    pub fn until_next_retry(&self, now: SystemTime) -> Option<Duration> {
+        self.retrying
+            .iter()
+            .map(|(retry_at, _)| retry_at.duration_since(now).unwrap_or_default())
+            .min()
+    }
+
+    /// Removes the proof to submit next: earliest deadline, then smallest upload.
+    pub fn pop(&mut self) -> Option<QueuedProof> {
+        let next = self
+            .items
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, item)| (item.deadline, item.upload_bytes, item.seq))
+            .map(|(index, _)| index)?;
+        Some(self.items.swap_remove(next))
+    }
+
+    /// Removes every proof, queued or set aside, in submission order.
+    pub fn drain(&mut self) -> Vec<QueuedProof> {
+        let retrying = std::mem::take(&mut self.retrying);
+        self.items
+            .extend(retrying.into_iter().map(|(_, item)| item));
+        std::iter::from_fn(|| self.pop()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Proofs waiting for upload, queued or set aside for a retry.
+    pub fn backlog(&self) -> usize {
+        self.items.len() + self.retrying.len()
+    }
+
+    /// Records a finished upload, updating the throughput estimate.
+    pub fn record_upload(&mut self, bytes: usize, elapsed: Duration) {
+        if bytes == 0 || elapsed.is_zero() {
+            return;
+        }
+        let observed = bytes as f64 / elapsed.as_secs_f64();
+        self.throughput = Some(match self.throughput {
+            Some(throughput) => {
+                throughput * (1.0 - THROUGHPUT_SMOOTHING) + observed * THROUGHPUT_SMOOTHING
+            }
+            None => observed,
+        });
+    }
+
+    /// Estimated time to upload `bytes`, once a throughput has been observed.
+    fn upload_time(&self, bytes: usize) -> Option<Duration> {
+        self.throughput
+            .map(|throughput| Duration::from_secs_f64(bytes as f64 / throughput))
+    }
+
+    /// A smaller queued proof that would miss its deadline waiting for `current`, which has
+    /// been uploading for `elapsed`, but can still make it if `current` is preempted.
+    pub fn preempting(
+        &self,
+        current: &QueuedProof,
+        elapsed: Duration,
+        now: SystemTime,
+    ) -> Option<&QueuedProof> {
+        if current.preempted {
+            return None;
+        }
+        let remaining = self
+            .upload_time(current.upload_bytes)?
+            .saturating_sub(elapsed);
+        self.items
+            .iter()
+            .filter(|item| item.upload_bytes < current.upload_bytes)
+            .filter(|item| {
+                let Some(upload) = self.upload_time(item.upload_bytes) else {
+                    return false;
+                };
+                item.deadline < now + remaining + upload && item.deadline >= now + upload
+            })
+            .min_by_key(|item| item.deadline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(task_id: &str, created_at: SystemTime) -> Task {
+        let mut task = Task::new(task_id.to_string(), "fast-fib".to_string(), vec![]);
+        task.created_at = Some(created_at);
+        task
+    }
+
+    #[test]
+    // Proofs are submitted earliest deadline first, whatever order they finished in.
+    fn test_earliest_deadline_first() {
+        let now = SystemTime::now();
+        let mut queue = SubmissionQueue::new();
+        queue.push(task("new", now), vec![0; 10], now);
+        queue.push(
+            task("old", now - Duration::from_secs(600)),
+            vec![0; 10],
+            now,
+        );
+        queue.push(
+            task("recent", now - Duration::from_secs(60)),
+            vec![0; 10],
+            now,
+        );
+        queue.push(
+            Task::new("fetched".into(), "p".into(), vec![]),
+            vec![0; 1],
+            now,
+        );
+
+        let order: Vec<_> = std::iter::from_fn(|| queue.pop())
+            .map(|item| item.task.task_id)
+            .collect();
+        assert_eq!(order, vec!["old", "recent", "fetched", "new"]);
+    }
+
+    #[test]
+    // A large upload yields to a small proof that would otherwise expire, but only once.
+    fn test_preempt_large_upload_for_near_deadline_proof() {
+        let now = SystemTime::now();
+        let mut queue = SubmissionQueue::new();
+        // 1 KB/s: the large proof takes 1000s, the small one 10s
+        queue.record_upload(1000, Duration::from_secs(1));
+        queue.push(task("large", now), vec![0; 1_000_000], now);
+        let large = queue.pop().unwrap();
+
+        // Expires in 100s: too soon to wait, late enough to upload
+        let urgent_created = now - TASK_LIFETIME + Duration::from_secs(100);
+        queue.push(task("urgent", urgent_created), vec![0; 10_000], now);
+        assert_eq!(
+            queue
+                .preempting(&large, Duration::from_secs(10), now)
+                .map(|item| item.task.task_id.as_str()),
+            Some("urgent")
+        );
+        // Nearly done: the small proof can wait
+        assert!(
+            queue
+                .preempting(&large, Duration::from_secs(995), now)
+                .is_none()
+        );
+
+        queue.requeue(large);
+        let urgent = queue.pop().unwrap();
+        assert_eq!(urgent.task.task_id, "urgent");
+        let large = queue.pop().unwrap();
+        assert!(large.preempted);
+        queue.push(task("urgent-2", urgent_created), vec![0; 10_000], now);
+        assert!(queue.preempting(&large, Duration::ZERO, now).is_none());
+    }
+
+    #[test]
+    // Without an observed throughput there is no basis for preempting.
+    fn test_no_preemption_before_first_upload() {
+        let now = SystemTime::now();
+        let mut queue = SubmissionQueue::new();
+        queue.push(task("large", now), vec![0; 1_000_000], now);
+        let large = queue.pop().unwrap();
+        queue.push(task("urgent", now - TASK_LIFETIME), vec![0; 10], now);
+        assert!(queue.preempting(&large, Duration::ZERO, now).is_none());
+    }
+
+    #[test]
+    // A refused proof waits out its retry, with backoff, until it runs out of retries or time.
+    fn test_retry_later() {
+        let now = SystemTime::now();
+        let mut queue = SubmissionQueue::new();
+        queue.push(task("busy", now), vec![0; 10], now);
+        let mut item = queue.pop().unwrap();
+
+        assert_eq!(item.retry_delay(None, now), Some(RETRY_DELAY));
+        assert_eq!(
+            item.retry_delay(Some(Duration::from_secs(5)), now),
+            Some(Duration::from_secs(5))
+        );
+        // Past the deadline
+        assert_eq!(item.retry_delay(Some(TASK_LIFETIME), now), None);
+
+        queue.retry_later(item, now + RETRY_DELAY);
+        assert!(queue.is_empty());
+        // A proof set aside still waits for upload
+        assert_eq!(queue.backlog(), 1);
+        assert_eq!(queue.until_next_retry(now), Some(RETRY_DELAY));
+        queue.release_due(now);
+        assert!(queue.is_empty());
+        queue.release_due(now + RETRY_DELAY);
+        item = queue.pop().unwrap();
+        assert_eq!(item.retries, 1);
+        assert_eq!(item.retry_delay(None, now), Some(RETRY_DELAY * 2));
+
+        item.retries = MAX_RETRIES;
+        assert_eq!(item.retry_delay(None, now), None);
+        assert_eq!(queue.until_next_retry(now), None);
+    }
+
+    #[test]
+    // Draining takes the set-aside proofs too, earliest deadline first.
+    fn test_drain() {
+        let now = SystemTime::now();
+        let mut queue = SubmissionQueue::new();
+        queue.push(task("late", now), vec![0; 10], now);
+        queue.push(
+            task("early", now - Duration::from_secs(60)),
+            vec![0; 10],
+            now,
+        );
+        let refused = queue.pop().unwrap();
+        queue.retry_later(refused, now + RETRY_DELAY);
+        queue.push(
+            task("middle", now - Duration::from_secs(30)),
+            vec![0; 10],
+            now,
+        );
+
+        let task_ids: Vec<String> = queue
+            .drain()
+            .into_iter()
+            .map(|item| item.task.task_id)
+            .collect();
+        assert_eq!(task_ids, ["early", "middle", "late"]);
+        assert!(queue.is_empty());
+        assert_eq!(queue.until_next_retry(now), None);
+    }
+}
@@ -0,0 +1,164 @@
+//! Submission verification
+//!
+//! A successful response to a submission does not guarantee the proof was credited. With
+//! `--verify-submissions`, each accepted submission is checked again after a delay: the
+//! orchestrator has no per-proof status endpoint, so a task that is still assigned to the
+//! node (listed by `get_tasks`) is taken as not credited, and one that is gone as credited.
+//! A task still assigned is re-checked a few times before it is reported as unconfirmed.
+
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Default delay before checking a submission.
+const DEFAULT_VERIFY_DELAY: Duration = Duration::from_secs(60);
+
+/// Checks made before a submission that is still assigned is reported as unconfirmed.
+const VERIFY_ATTEMPTS: u32 = 3;
+
+/// How submissions are verified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerificationConfig {
+    /// Time between a submission and its first check, and between later checks
+    pub delay: Duration,
+    /// Checks made before giving up on a submission that is still assigned
+    pub attempts: u32,
+}
+
+impl VerificationConfig {
+    /// Verification settings from command-line flags, or `None` if it is disabled.
+    pub fn from_flags(enabled: bool, delay_secs: Option<u64>) -> Option<Self> {
+        enabled.then(|| Self {
+            delay: delay_secs
+                .map(Duration::from_secs)
+                .unwrap_or(DEFAULT_VERIFY_DELAY),
+            attempts: VERIFY_ATTEMPTS,
+        })
+    }
+}
+
+/// A submission waiting to be checked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub task_id: String,
+    pub node_id: u64,
+    /// Checks already made for this submission
+    pub attempts: u32,
+    due: Instant,
+}
+
+/// What a check concluded about a submission.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// The task is no longer assigned to the node.
+    Credited,
+    /// The task is still assigned; it will be checked again.
+    Recheck,
+    /// The task was still assigned after every check.
+    Unconfirmed,
+}
+
+/// Submissions waiting to be verified.
+#[derive(Debug)]
+pub struct PendingVerifications {
+    config: Option<VerificationConfig>,
+    checks: Vec<Check>,
+}
+
+impl PendingVerifications {
+    pub fn new(config: Option<VerificationConfig>) -> Self {
+        Self {
+            config,
+            checks: Vec::new(),
+        }
+    }
+
+    /// Schedules a check of an accepted submission, if verification is enabled.
+    pub fn schedule(&mut self, task_id: &str, node_id: Option<u64>) {
+        let (Some(config), Some(node_id)) = (self.config, node_id) else {
+            return;
+        };
+        self.checks.push(Check {
+            task_id: task_id.to_string(),
+            node_id,
+            attempts: 0,
+            // Spread nodes' first checks so a shared outage's backlog is not re-checked at once
+            due: Instant::now() + config.delay + crate::pacing::offset("verify", config.delay / 4),
+        });
+    }
+
+    /// When the next check is due, if any are pending.
+    pub fn next_due(&self) -> Option<Instant> {
+        self.checks.iter().map(|check| check.due).min()
+    }
+
+    /// Removes and returns the checks that are due.
+    pub fn take_due(&mut self) -> Vec<Check> {
+        let now = Instant::now();
+        let (due, waiting) = self.checks.drain(..).partition(|check| check.due <= now);
+        self.checks = waiting;
+        due
+    }
+
+    /// Records the outcome of a check: `still_assigned` is `None` if the node's tasks could not
+    /// be listed. Checks that are not conclusive yet are rescheduled.
+    pub fn resolve(&mut self, mut check: Check, still_assigned: Option<bool>) -> Verdict {
+        let Some(config) = self.config else {
+            return Verdict::Unconfirmed;
+        };
+        if still_assigned == Some(false) {
+            return Verdict::Credited;
+        }
+        check.attempts += 1;
+        if check.attempts >= config.attempts {
+            return Verdict::Unconfirmed;
+        }
+        check.due = Instant::now() + config.delay;
+        self.checks.push(check);
+        Verdict::Recheck
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Checks should come due after the delay and be retried until the attempts run out.
+    fn test_checks_are_retried_then_unconfirmed() {
+        let delayed = VerificationConfig::from_flags(true, None).unwrap();
+        let mut pending = PendingVerifications::new(Some(delayed));
+        pending.schedule("later", Some(7));
+        assert!(pending.take_due().is_empty());
+        assert!(pending.next_due().is_some());
+
+        let config = VerificationConfig::from_flags(true, Some(0)).unwrap();
+        let mut pending = PendingVerifications::new(Some(config));
+        pending.schedule("task-1", Some(7));
+        pending.schedule("task-2", Some(7));
+        pending.schedule("no-node", None);
+
+        let mut due = pending.take_due();
+        assert_eq!(due.len(), 2);
+        assert_eq!(
+            pending.resolve(due.remove(0), Some(false)),
+            Verdict::Credited
+        );
+
+        let mut check = due.remove(0);
+        for _ in 1..config.attempts {
+            assert_eq!(pending.resolve(check, Some(true)), Verdict::Recheck);
+            check = pending.take_due().remove(0);
+        }
+        assert_eq!(pending.resolve(check, None), Verdict::Unconfirmed);
+        assert_eq!(pending.next_due(), None);
+    }
+
+    #[test]
+    // Nothing should be scheduled when verification is off.
+    fn test_disabled_verification() {
+        assert_eq!(VerificationConfig::from_flags(false, Some(5)), None);
+        let mut pending = PendingVerifications::new(None);
+        pending.schedule("task", Some(1));
+        assert_eq!(pending.next_due(), None);
+    }
+}
@@ -134,19 +134,32 @@ pub fn total_memory_gb() -> f64 {
     total_memory as f64 / 1000.0 / 1000.0 / 1000.0 // Convert to GB
 }
 
+/// Memory available to new allocations on the machine, in bytes, if it can be read.
+pub fn available_memory_bytes() -> Option<u64> {
+    let mut sys = System::new();
+    sys.refresh_memory();
+    Some(sys.available_memory()).filter(|available| *available > 0)
+}
+
+/// The machine's host name, if it can be read.
+pub fn host_name() -> Option<String> {
+    System::host_name().filter(|name| !name.is_empty())
+}
+
 /// Memory used by the current process, in GB.
 #[allow(unused)]
 pub fn process_memory_gb() -> f64 {
-    let mut sys = System::new();
-    sys.refresh_all();
-
-    let current_pid = process::id();
-    let current_process = sys
-        .process(sysinfo::Pid::from(current_pid as usize))
-        .expect("Failed to get current process");
+    process_memory_bytes() as f64 / 1000.0 / 1000.0 / 1000.0 // Convert to GB
+}
 
-    let memory = current_process.memory(); // bytes
-    memory as f64 / 1000.0 / 1000.0 / 1000.0 // Convert to GB
+/// Memory used by the current process, in bytes (0 if it cannot be read).
+pub fn process_memory_bytes() -> u64 {
+    let mut sys = System::new();
+    let current_pid = sysinfo::Pid::from(process::id() as usize);
+    sys.refresh_processes(sysinfo::ProcessesToUpdate::Some(&[current_pid]), true);
+    sys.process(current_pid)
+        .map(|process| process.memory())
+        .unwrap_or(0)
 }
 
 // We encode the memory usage to i32 type at client
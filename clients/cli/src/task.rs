@@ -5,6 +5,7 @@
 //! * GetProofTaskResponse.
 
 use std::fmt::Display;
+use std::time::SystemTime;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Task {
@@ -19,6 +20,17 @@ pub struct Task {
 
     /// The type of task (proof required or only hash)
     pub task_type: Option<crate::nexus_orchestrator::TaskType>,
+
+    /// The node this task was fetched for, so its proof is submitted by the same node
+    pub node_id: Option<u64>,
+
+    /// When the orchestrator created the task, or when it was fetched if the orchestrator did
+    /// not say. Not sent between processes, so proofs from separate provers have none.
+    pub created_at: Option<SystemTime>,
+
+    /// The proxy the task was fetched through (its display string), so its proof is submitted
+    /// through the same one. Not sent between processes; a fetcher keeps its own copy.
+    pub proxy: Option<String>,
 }
 
 impl Task {
@@ -30,6 +42,9 @@ impl Task {
             program_id,
             public_inputs,
             task_type: None,
+            node_id: None,
+            created_at: None,
+            proxy: None,
         }
     }
 }
@@ -57,6 +72,11 @@ impl From<&crate::nexus_orchestrator::Task> for Task {
                 crate::nexus_orchestrator::TaskType::try_from(task.task_type)
                     .unwrap_or(crate::nexus_orchestrator::TaskType::ProofRequired),
             ),
+            node_id: None,
+            created_at: task
+                .created_at
+                .and_then(|created_at| SystemTime::try_from(created_at).ok()),
+            proxy: None,
         }
     }
 }
@@ -69,6 +89,9 @@ impl From<&crate::nexus_orchestrator::GetProofTaskResponse> for Task {
             program_id: response.program_id.clone(),
             public_inputs: response.public_inputs.clone(),
             task_type: None, // GetProofTaskResponse doesn't include task_type
+            node_id: None,
+            created_at: Some(SystemTime::now()),
+            proxy: None,
         }
     }
 }
@@ -0,0 +1,160 @@
+//! Task filtering
+//!
+//! Lets operators specialize a machine by only accepting (or excluding) specific
+//! program IDs and task types. The orchestrator API has no way to decline a task,
+//! so skip reasons are only reported locally.
+
+use crate::nexus_orchestrator::TaskType;
+use crate::task::Task;
+use serde::{Deserialize, Serialize};
+
+/// Allowlists and denylists for program IDs and task types.
+///
+/// An empty allowlist accepts everything; denylists take precedence over allowlists.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct TaskFilter {
+    /// Only accept tasks for these program IDs.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow_programs: Vec<String>,
+
+    /// Never accept tasks for these program IDs.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deny_programs: Vec<String>,
+
+    /// Only accept tasks of these types (e.g. "proof_required", "proof_hash").
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow_task_types: Vec<String>,
+
+    /// Never accept tasks of these types.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deny_task_types: Vec<String>,
+}
+
+impl TaskFilter {
+    /// Returns true if the filter accepts every task.
+    pub fn is_empty(&self) -> bool {
+        self.allow_programs.is_empty()
+            && self.deny_programs.is_empty()
+            && self.allow_task_types.is_empty()
+            && self.deny_task_types.is_empty()
+    }
+
+    /// Combines two filters, preferring non-empty lists from `overrides` (e.g. CLI flags).
+    pub fn merge(self, overrides: TaskFilter) -> TaskFilter {
+        fn pick(base: Vec<String>, over: Vec<String>) -> Vec<String> {
+            if over.is_empty() { base } else { over }
+        }
+        TaskFilter {
+            allow_programs: pick(self.allow_programs, overrides.allow_programs),
+            deny_programs: pick(self.deny_programs, overrides.deny_programs),
+            allow_task_types: pick(self.allow_task_types, overrides.allow_task_types),
+            deny_task_types: pick(self.deny_task_types, overrides.deny_task_types),
+        }
+    }
+
+    /// Checks whether a task should be accepted.
+    ///
+    /// # Errors
+    /// Returns a human-readable skip reason if the task is rejected.
+    pub fn check(&self, task: &Task) -> Result<(), String> {
+        let program_id = task.program_id.as_str();
+        if self.deny_programs.iter().any(|p| p == program_id) {
+            return Err(format!("program '{}' is in the denylist", program_id));
+        }
+        if !self.allow_programs.is_empty() && !self.allow_programs.iter().any(|p| p == program_id) {
+            return Err(format!("program '{}' is not in the allowlist", program_id));
+        }
+
+        // Tasks without a type are treated as requiring a proof, matching submission behavior.
+        let task_type = task.task_type.unwrap_or(TaskType::ProofRequired);
+        let matches_type = |name: &String| normalize(name) == normalize(task_type.as_str_name());
+        if self.deny_task_types.iter().any(matches_type) {
+            return Err(format!(
+                "task type '{}' is in the denylist",
+                task_type.as_str_name()
+            ));
+        }
+        if !self.allow_task_types.is_empty() && !self.allow_task_types.iter().any(matches_type) {
+            return Err(format!(
+                "task type '{}' is not in the allowlist",
+                task_type.as_str_name()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Normalizes a task type name so "proof-hash", "proof_hash" and "PROOF_HASH" compare equal.
+fn normalize(name: &str) -> String {
+    name.trim().to_ascii_uppercase().replace('-', "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(program_id: &str, task_type: Option<TaskType>) -> Task {
+        let mut task = Task::new("1".to_string(), program_id.to_string(), vec![]);
+        task.task_type = task_type;
+        task
+    }
+
+    #[test]
+    // An empty filter should accept every task.
+    fn test_empty_filter_accepts_all() {
+        let filter = TaskFilter::default();
+        assert!(filter.is_empty());
+        assert!(filter.check(&task("fast-fib", None)).is_ok());
+    }
+
+    #[test]
+    // The program allowlist should reject programs not listed, and the denylist should win.
+    fn test_program_lists() {
+        let filter = TaskFilter {
+            allow_programs: vec!["fast-fib".to_string(), "fib_input_initial".to_string()],
+            deny_programs: vec!["fib_input_initial".to_string()],
+            ..Default::default()
+        };
+        assert!(filter.check(&task("fast-fib", None)).is_ok());
+        assert!(filter.check(&task("other", None)).is_err());
+        assert!(filter.check(&task("fib_input_initial", None)).is_err());
+    }
+
+    #[test]
+    // Task type names should match regardless of case and separator.
+    fn test_task_type_lists() {
+        let filter = TaskFilter {
+            allow_task_types: vec!["proof-hash".to_string()],
+            ..Default::default()
+        };
+        assert!(
+            filter
+                .check(&task("fast-fib", Some(TaskType::ProofHash)))
+                .is_ok()
+        );
+        assert!(
+            filter
+                .check(&task("fast-fib", Some(TaskType::ProofRequired)))
+                .is_err()
+        );
+        // Untyped tasks count as proof-required.
+        assert!(filter.check(&task("fast-fib", None)).is_err());
+    }
+
+    #[test]
+    // CLI overrides should replace only the lists they set.
+    fn test_merge_prefers_overrides() {
+        let config = TaskFilter {
+            allow_programs: vec!["fast-fib".to_string()],
+            deny_task_types: vec!["proof_hash".to_string()],
+            ..Default::default()
+        };
+        let flags = TaskFilter {
+            allow_programs: vec!["fib_input_initial".to_string()],
+            ..Default::default()
+        };
+        let merged = config.merge(flags);
+        assert_eq!(merged.allow_programs, vec!["fib_input_initial".to_string()]);
+        assert_eq!(merged.deny_task_types, vec!["proof_hash".to_string()]);
+    }
+}
@@ -0,0 +1,430 @@
+//! Task lifecycle
+//!
+//! Every fetched task moves through explicit states:
+//!
+//! ```text
+//! Fetched -> Proving -> Proved -> Submitting -> Accepted
+//!    |          |         |           |-------> Rejected
+//!    |          |-> Fetched (requeued) |-> Proved (upload preempted)
+//!    '----------'---------'-----------------> Abandoned
+//! ```
+//!
+//! Transitions outside these edges are refused and reported, rather than silently reordering
+//! a task's history. States are kept in `~/.nexus/task_states.json`, labelled with the
+//! environment each task was fetched from, and each change is emitted as a `state_changed`
+//! progress event.
+//!
+//! A task left mid-pipeline by an earlier run is abandoned on startup, except while
+//! `Submitting`: the submission journal resubmits those. A task whose proof was finished but
+//! not journaled goes `Fetched` again and on to `Proved`, since `proof_checkpoint` kept it.
+//! Tasks still in progress when `--shutdown-timeout` runs out on exit are abandoned the same
+//! way, and listed as released; those not yet proved go `Fetched` again when the next start
+//! resumes them from the `checkpoint`.
+//! Only tasks fetched by this process are tracked; a prover process leaves the states to
+//! its fetcher.
+
+use crate::environment::Environment;
+use crate::error_classifier::LogLevel;
+use crate::events::{Event, EventType, Worker};
+use crate::progress::ProgressEvent;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+
+/// Finished tasks kept in the state file, most recent first.
+pub const MAX_FINISHED_TASKS: usize = 200;
+
+/// Where a task is in its lifecycle.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskState {
+    Fetched,
+    Proving,
+    Proved,
+    Submitting,
+    Accepted,
+    Rejected,
+    Abandoned,
+}
+
+impl TaskState {
+    /// Whether the task is done with, one way or another.
+    pub fn is_terminal(self) -> bool {
+        matches!(
+            self,
+            TaskState::Accepted | TaskState::Rejected | TaskState::Abandoned
+        )
+    }
+
+    /// Whether a task in this state may move to `next`.
+    pub fn can_become(self, next: TaskState) -> bool {
+        use TaskState::*;
+        match (self, next) {
+            // A task is fetched again once the orchestrator reassigns it
+            (from, Fetched) if from.is_terminal() => true,
+            (from, Abandoned) => !from.is_terminal(),
+            (Fetched, Proving) => true,
+            (Proving, Proved | Fetched) => true,
+            (Proved, Submitting) => true,
+            (Submitting, Accepted | Rejected | Proved) => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for TaskState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TaskState::Fetched => "fetched",
+            TaskState::Proving => "proving",
+            TaskState::Proved => "proved",
+            TaskState::Submitting => "submitting",
+            TaskState::Accepted => "accepted",
+            TaskState::Rejected => "rejected",
+            TaskState::Abandoned => "abandoned",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A refused transition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransitionError {
+    pub task_id: String,
+    pub from: TaskState,
+    pub to: TaskState,
+}
+
+impl fmt::Display for TransitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "task {} cannot move from {} to {}",
+            self.task_id, self.from, self.to
+        )
+    }
+}
+
+impl std::error::Error for TransitionError {}
+
+/// The current state of one task.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TaskRecord {
+    pub state: TaskState,
+    /// When the task entered its state (seconds since the Unix epoch).
+    pub since: u64,
+    /// Label of the environment the task was fetched from; unknown for tasks tracked by
+    /// earlier releases.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<String>,
+}
+
+/// The state of every tracked task, by task ID.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct TaskStates {
+    pub tasks: HashMap<String, TaskRecord>,
+    /// Label of the environment this process fetches tasks from.
+    #[serde(skip)]
+    environment: Option<String>,
+}
+
+impl TaskStates {
+    /// Loads the saved states; a missing file means no task was tracked yet.
+    ///
+    /// # Errors
+    /// Returns an `std::io::Error` if the file exists but cannot be read or parsed.
+    pub fn load_from_file(path: &Path) -> Result<Self, std::io::Error> {
+        match std::fs::read(path) {
+            Ok(buf) => serde_json::from_slice(&buf)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn save(&self, path: &Path) {
+        if let Ok(json) = serde_json::to_string_pretty(self) {
+            let _ = std::fs::write(path, json);
+        }
+    }
+
+    /// The state of a task, if it is tracked.
+    pub fn state(&self, task_id: &str) -> Option<TaskState> {
+        self.tasks.get(task_id).map(|record| record.state)
+    }
+
+    /// Moves a task to `to`, returning the state it left.
+    ///
+    /// Only `Fetched` starts tracking a task; other transitions of untracked tasks are ignored
+    /// and return `Ok(None)`.
+    ///
+    /// # Errors
+    /// Returns a `TransitionError` if the task may not move to `to`; its state is unchanged.
+    pub fn transition(
+        &mut self,
+        task_id: &str,
+        to: TaskState,
+        now: u64,
+    ) -> Result<Option<TaskState>, TransitionError> {
+        let from = self.state(task_id);
+        match from {
+            None if to != TaskState::Fetched => return Ok(None),
+            Some(from) if !from.can_become(to) => {
+                return Err(TransitionError {
+                    task_id: task_id.to_string(),
+                    from,
+                    to,
+                });
+            }
+            _ => {}
+        }
+        self.tasks.insert(
+            task_id.to_string(),
+            TaskRecord {
+                state: to,
+                since: now,
+                environment: self.environment.clone(),
+            },
+        );
+        if to.is_terminal() {
+            self.prune_finished();
+        }
+        Ok(from)
+    }
+
+    /// Abandons tasks an earlier run left mid-pipeline, except those still submitting.
+    /// Returns their IDs and the states they were left in.
+    fn abandon_interrupted(&mut self, now: u64) -> Vec<(String, TaskState)> {
+        let mut abandoned = Vec::new();
+        for (task_id, record) in &mut self.tasks {
+            if !record.state.is_terminal() && record.state != TaskState::Submitting {
+                abandoned.push((task_id.clone(), record.state));
+                record.state = TaskState::Abandoned;
+                record.since = now;
+            }
+        }
+        self.prune_finished();
+        abandoned
+    }
+
+    /// Keeps only the most recently finished tasks.
+    fn prune_finished(&mut self) {
+        let mut finished: Vec<(u64, String)> = self
+            .tasks
+            .iter()
+            .filter(|(_, record)| record.state.is_terminal())
+            .map(|(task_id, record)| (record.since, task_id.clone()))
+            .collect();
+        if finished.len() <= MAX_FINISHED_TASKS {
+            return;
+        }
+        finished.sort_unstable_by(|a, b| b.cmp(a));
+        for (_, task_id) in finished.into_iter().skip(MAX_FINISHED_TASKS) {
+            self.tasks.remove(&task_id);
+        }
+    }
+}
+
+/// Path to the saved task states, next to the config file.
+pub fn states_path(config_path: &Path) -> PathBuf {
+    config_path.with_file_name("task_states.json")
+}
+
+struct StateStore {
+    states: TaskStates,
+    path: Option<PathBuf>,
+}
+
+static STORE: OnceLock<Mutex<StateStore>> = OnceLock::new();
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Starts tracking the states of tasks fetched from `environment`, abandoning tasks an earlier
+/// run left unfinished. Only the first call has an effect.
+pub fn init(environment: &Environment) {
+    if STORE.get().is_some() {
+        return;
+    }
+    let path = crate::config::get_config_path()
+        .ok()
+        .map(|path| states_path(&path));
+    let mut states = path
+        .as_deref()
+        .and_then(|path| TaskStates::load_from_file(path).ok())
+        .unwrap_or_default();
+    states.environment = Some(environment.label());
+    let abandoned = states.abandon_interrupted(now());
+    emit_abandoned(&abandoned);
+    if let Some(path) = &path {
+        states.save(path);
+    }
+    let _ = STORE.set(Mutex::new(StateStore { states, path }));
+}
+
+/// The states of all tracked tasks: those of this process once tracking started, otherwise
+/// the ones saved at `path`.
+pub fn current(path: &Path) -> TaskStates {
+    if let Some(Ok(store)) = STORE.get().map(Mutex::lock) {
+        return store.states.clone();
+    }
+    TaskStates::load_from_file(path).unwrap_or_default()
+}
+
+fn emit_abandoned(abandoned: &[(String, TaskState)]) {
+    for (task_id, from) in abandoned {
+        crate::progress::emit(ProgressEvent::StateChanged {
+            task_id: task_id.clone(),
+            from: Some(*from),
+            to: TaskState::Abandoned,
+        });
+    }
+}
+
+/// Abandons the tasks still in the pipeline when the node stops before finishing them, except
+/// those submitting, which the journal resubmits on the next start. Returns their IDs.
+pub fn abandon_unfinished() -> Vec<String> {
+    let Some(Ok(mut store)) = STORE.get().map(Mutex::lock) else {
+        return Vec::new();
+    };
+    let abandoned = store.states.abandon_interrupted(now());
+    if let Some(path) = &store.path {
+        store.states.save(path);
+    }
+    emit_abandoned(&abandoned);
+    let mut task_ids: Vec<String> = abandoned.into_iter().map(|(task_id, _)| task_id).collect();
+    task_ids.sort();
+    task_ids
+}
+
+/// Moves a task to `to`, saving and emitting the change.
+///
+/// # Errors
+/// Returns a `TransitionError` if the task may not move to `to`.
+pub fn transition(task_id: &str, to: TaskState) -> Result<(), TransitionError> {
+    let Some(Ok(mut store)) = STORE.get().map(Mutex::lock) else {
+        return Ok(());
+    };
+    let from = store.states.transition(task_id, to, now())?;
+    if from.is_none() && to != TaskState::Fetched {
+        return Ok(());
+    }
+    if let Some(path) = &store.path {
+        store.states.save(path);
+    }
+    crate::progress::emit(ProgressEvent::StateChanged {
+        task_id: task_id.to_string(),
+        from,
+        to,
+    });
+    Ok(())
+}
+
+/// Moves a task to `to`, reporting a refused transition as a warning from `worker`.
+pub async fn advance(
+    task_id: &str,
+    to: TaskState,
+    worker: Worker,
+    event_sender: &mpsc::Sender<Event>,
+) {
+    if let Err(e) = transition(task_id, to) {
+        let _ = event_sender
+            .send(Event::new_with_level(
+                worker,
+                format!("Ignoring state change: {}", e),
+                EventType::Refresh,
+                LogLevel::Warn,
+            ))
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    // The happy path and each exit are allowed; skipping or reversing a step is refused.
+    fn test_transitions() {
+        use TaskState::*;
+        let mut states = TaskStates::default();
+        assert_eq!(states.transition("1", Proving, 0), Ok(None));
+        assert_eq!(states.state("1"), None);
+
+        for (to, from) in [
+            (Fetched, None),
+            (Proving, Some(Fetched)),
+            (Proved, Some(Proving)),
+            (Submitting, Some(Proved)),
+            (Proved, Some(Submitting)),
+            (Submitting, Some(Proved)),
+            (Accepted, Some(Submitting)),
+        ] {
+            assert_eq!(states.transition("1", to, 0), Ok(from));
+        }
+        assert_eq!(
+            states.transition("1", Abandoned, 0),
+            Err(TransitionError {
+                task_id: "1".to_string(),
+                from: Accepted,
+                to: Abandoned,
+            })
+        );
+
+        states.transition("2", Fetched, 0).unwrap();
+        assert!(states.transition("2", Submitting, 0).is_err());
+        assert_eq!(states.state("2"), Some(Fetched));
+        states.transition("2", Abandoned, 0).unwrap();
+        // Reassigned after it was abandoned
+        assert_eq!(states.transition("2", Fetched, 0), Ok(Some(Abandoned)));
+    }
+
+    #[test]
+    // Interrupted tasks are abandoned on reload, unless the journal will resubmit them.
+    fn test_abandon_interrupted_and_round_trip() {
+        let mut states = TaskStates::default();
+        for (task_id, steps) in [
+            ("proving", &[TaskState::Fetched, TaskState::Proving][..]),
+            (
+                "submitting",
+                &[
+                    TaskState::Fetched,
+                    TaskState::Proving,
+                    TaskState::Proved,
+                    TaskState::Submitting,
+                ][..],
+            ),
+        ] {
+            for &to in steps {
+                states.transition(task_id, to, 1).unwrap();
+            }
+        }
+        states.tasks.get_mut("proving").unwrap().environment = Some("production".to_string());
+
+        let dir = tempdir().unwrap();
+        let path = states_path(&dir.path().join("config.json"));
+        states.save(&path);
+        let mut loaded = TaskStates::load_from_file(&path).unwrap();
+        assert_eq!(loaded, states);
+
+        assert_eq!(
+            loaded.abandon_interrupted(2),
+            vec![("proving".to_string(), TaskState::Proving)]
+        );
+        assert_eq!(loaded.state("proving"), Some(TaskState::Abandoned));
+        assert_eq!(
+            loaded.tasks["proving"].environment.as_deref(),
+            Some("production")
+        );
+        assert_eq!(loaded.state("submitting"), Some(TaskState::Submitting));
+    }
+}
@@ -0,0 +1,157 @@
+//! Per-task proving profiles
+//!
+//! `start --profile-tasks` samples the process while a task is proved and writes a flamegraph
+//! of the proof to `~/.nexus/profiles/<task ID>.svg`, so a user can show where a release got
+//! slower on their own hardware. Sampling uses pprof (`SIGPROF` at `FREQUENCY` Hz) and is only
+//! compiled with the `profiling` feature, on Unix:
+//!
+//! ```bash
+//! CARGO_PROFILE_RELEASE_STRIP=false cargo build --release --features profiling
+//! ```
+//!
+//! Release builds are otherwise stripped, which leaves the flamegraphs without symbols. The
+//! sampler covers the whole process, so one proof is profiled at a time and a task proved while
+//! another is profiled gets no flamegraph; `--max-threads 1` profiles every task.
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Samples per second
+const FREQUENCY: i32 = 99;
+
+#[cfg(feature = "profiling")]
+mod sampler {
+    use std::path::Path;
+
+    pub type Sampler = pprof::ProfilerGuard<'static>;
+
+    pub fn start(frequency: i32) -> Result<Sampler, String> {
+        pprof::ProfilerGuardBuilder::default()
+            .frequency(frequency)
+            .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+            .build()
+            .map_err(|e| e.to_string())
+    }
+
+    pub fn write(sampler: &Sampler, path: &Path) -> Result<(), String> {
+        let report = sampler.report().build().map_err(|e| e.to_string())?;
+        let file = std::fs::File::create(path).map_err(|e| e.to_string())?;
+        report.flamegraph(file).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(not(feature = "profiling"))]
+mod sampler {
+    use std::path::Path;
+
+    pub type Sampler = std::convert::Infallible;
+
+    pub fn start(_frequency: i32) -> Result<Sampler, String> {
+        Err("built without the profiling feature".to_string())
+    }
+
+    pub fn write(sampler: &Sampler, _path: &Path) -> Result<(), String> {
+        match *sampler {}
+    }
+}
+
+/// Where flamegraphs are written, if tasks are profiled
+static DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Whether a proof is being profiled
+static PROFILING: AtomicBool = AtomicBool::new(false);
+
+/// Whether this build can profile tasks.
+pub fn is_supported() -> bool {
+    cfg!(feature = "profiling")
+}
+
+/// Path to the flamegraph directory, next to the config file.
+pub fn profiles_dir(config_path: &Path) -> PathBuf {
+    config_path.with_file_name("profiles")
+}
+
+/// Profiles proofs from now on, writing flamegraphs to `dir`. Only the first call has an effect.
+///
+/// # Errors
+/// Returns an `std::io::Error` if the directory cannot be created.
+pub fn enable(dir: PathBuf) -> std::io::Result<()> {
+    std::fs::create_dir_all(&dir)?;
+    let _ = DIR.set(dir);
+    Ok(())
+}
+
+/// The flamegraph of a task in `dir`. Task IDs come from the orchestrator; only safe characters
+/// are kept in file names.
+fn flamegraph_path(dir: &Path, task_id: &str) -> PathBuf {
+    let safe_id: String = task_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    dir.join(format!("{}.svg", safe_id))
+}
+
+/// Samples the process while a task is proved.
+pub struct TaskProfile {
+    path: PathBuf,
+    sampler: sampler::Sampler,
+}
+
+impl TaskProfile {
+    /// Starts profiling the proof of `task_id`, unless tasks are not profiled or another proof
+    /// is being profiled.
+    pub fn start(task_id: &str) -> Option<Self> {
+        let dir = DIR.get()?;
+        if PROFILING.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+        match sampler::start(FREQUENCY) {
+            Ok(sampler) => Some(Self {
+                path: flamegraph_path(dir, task_id),
+                sampler,
+            }),
+            Err(e) => {
+                PROFILING.store(false, Ordering::Release);
+                log::warn!("Could not profile task {}: {}", task_id, e);
+                None
+            }
+        }
+    }
+
+    /// Stops sampling and writes the flamegraph, returning its path.
+    ///
+    /// # Errors
+    /// Returns a description of the failure if the flamegraph cannot be built or written.
+    pub fn finish(self) -> Result<PathBuf, String> {
+        sampler::write(&self.sampler, &self.path)?;
+        Ok(self.path.clone())
+    }
+}
+
+impl Drop for TaskProfile {
+    fn drop(&mut self) {
+        PROFILING.store(false, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Flamegraphs are named after their task, with unsafe characters replaced.
+    fn test_flamegraph_path() {
+        let dir = Path::new("/tmp/profiles");
+        assert_eq!(
+            flamegraph_path(dir, "task-1/../x"),
+            dir.join("task-1____x.svg")
+        );
+    }
+}
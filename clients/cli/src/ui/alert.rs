@@ -0,0 +1,127 @@
+//! Attention alerts for the dashboard.
+//!
+//! Tracks worker events to detect when the node transitions into a failing state
+//! (critical errors such as unauthorized responses, or a run of consecutive errors),
+//! so the UI can ring the terminal bell and flash the header.
+
+use crate::error_classifier::LogLevel;
+use crate::events::{Event as WorkerEvent, EventType};
+use std::time::{Duration, Instant};
+
+/// Number of consecutive error events (without a success in between) considered a failing state.
+const CONSECUTIVE_ERRORS_THRESHOLD: usize = 5;
+
+/// How long the header keeps flashing after entering a failing state.
+const FLASH_DURATION: Duration = Duration::from_secs(10);
+
+/// Interval at which the flashing header toggles on and off.
+const FLASH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Detects transitions into a failing state from the stream of worker events.
+#[derive(Debug, Clone)]
+pub struct AlertMonitor {
+    /// Number of error events seen since the last success.
+    consecutive_errors: usize,
+
+    /// Whether the node is currently considered to be failing.
+    failing: bool,
+
+    /// When the node most recently entered the failing state.
+    failing_since: Option<Instant>,
+}
+
+impl AlertMonitor {
+    pub fn new() -> Self {
+        Self {
+            consecutive_errors: 0,
+            failing: false,
+            failing_since: None,
+        }
+    }
+
+    /// Records an event. Returns true if the node just transitioned into a failing state.
+    pub fn observe(&mut self, event: &WorkerEvent) -> bool {
+        match event.event_type {
+            EventType::Success => {
+                self.consecutive_errors = 0;
+                self.failing = false;
+                self.failing_since = None;
+                false
+            }
+            EventType::Error => {
+                self.consecutive_errors += 1;
+                let critical = event.log_level >= LogLevel::Error;
+                if (critical || self.consecutive_errors >= CONSECUTIVE_ERRORS_THRESHOLD)
+                    && !self.failing
+                {
+                    self.failing = true;
+                    self.failing_since = Some(Instant::now());
+                    return true;
+                }
+                false
+            }
+            EventType::Refresh | EventType::Shutdown => false,
+        }
+    }
+
+    /// Whether the header should currently be drawn in its highlighted (flash) style.
+    pub fn flash_on(&self) -> bool {
+        match self.failing_since {
+            Some(since) if since.elapsed() < FLASH_DURATION => {
+                (since.elapsed().as_millis() / FLASH_INTERVAL.as_millis()) % 2 == 0
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Default for AlertMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_event(log_level: LogLevel) -> WorkerEvent {
+        WorkerEvent::task_fetcher_with_level("error".to_string(), EventType::Error, log_level)
+    }
+
+    #[test]
+    // A critical error should immediately put the node into a failing state.
+    fn test_critical_error_triggers_alert() {
+        let mut monitor = AlertMonitor::new();
+        assert!(monitor.observe(&error_event(LogLevel::Error)));
+        assert!(monitor.failing);
+        assert!(monitor.flash_on());
+
+        // Further errors should not re-trigger the alert.
+        assert!(!monitor.observe(&error_event(LogLevel::Error)));
+    }
+
+    #[test]
+    // Persistent non-critical errors should trigger the alert once the threshold is reached.
+    fn test_consecutive_errors_trigger_alert() {
+        let mut monitor = AlertMonitor::new();
+        for _ in 0..CONSECUTIVE_ERRORS_THRESHOLD - 1 {
+            assert!(!monitor.observe(&error_event(LogLevel::Warn)));
+        }
+        assert!(monitor.observe(&error_event(LogLevel::Warn)));
+    }
+
+    #[test]
+    // A successful event should clear the failing state.
+    fn test_success_clears_failing_state() {
+        let mut monitor = AlertMonitor::new();
+        monitor.observe(&error_event(LogLevel::Error));
+        monitor.observe(&WorkerEvent::prover(
+            0,
+            "done".to_string(),
+            EventType::Success,
+        ));
+        assert!(!monitor.failing);
+        assert!(!monitor.flash_on());
+    }
+}
@@ -46,6 +46,27 @@ pub struct DashboardState {
 
     /// Whether to disable background colors
     pub no_background_color: bool,
+
+    /// Whether the header is currently flashing to signal a failing node.
+    pub alert_flash: bool,
+
+    /// Announcement of an orchestrator maintenance window in effect, if any.
+    pub maintenance_banner: Option<String>,
+
+    /// The log level in effect, switched with the `L` key.
+    pub log_level: crate::error_classifier::LogLevel,
+
+    /// Progress toward the earnings goal, if one is set.
+    pub goal: Option<crate::goal::GoalProgress>,
+
+    /// The node's uptime over the last day and week, across runs.
+    pub uptime: Option<crate::uptime::UptimeSummary>,
+
+    /// What each node did in this run, when several run in this process.
+    pub node_stats: std::collections::BTreeMap<u64, crate::nodes::NodeStats>,
+
+    /// Resource usage, shown instead of the logs while the resources pane is open.
+    pub resources: Option<crate::ui::resources::ResourceSnapshot>,
 }
 
 impl DashboardState {
@@ -56,15 +77,18 @@ impl DashboardState {
     /// * `start_time` - The start time of the application, used for computing uptime.
     /// * `environment` - The environment in which the application is running.
     /// * `no_background_color` - Whether to disable background colors
+    /// * `alert_flash` - Whether the header should be drawn in its alert (flash) style
     pub fn new(
         node_id: Option<u64>,
         environment: Environment,
         start_time: Instant,
         events: &VecDeque<WorkerEvent>,
         no_background_color: bool,
+        alert_flash: bool,
     ) -> Self {
         // Check for version update messages in recent events
         let (update_available, latest_version, _) = Self::check_for_version_updates(events);
+        let maintenance_banner = Self::check_for_maintenance(events);
 
         Self {
             node_id,
@@ -78,9 +102,32 @@ impl DashboardState {
             update_available,
             latest_version,
             no_background_color,
+            alert_flash,
+            maintenance_banner,
+            log_level: crate::logging::current_log_level(),
+            goal: crate::goal::progress(),
+            uptime: crate::uptime::summary(),
+            node_stats: crate::nodes::stats(),
+            resources: None,
         }
     }
 
+    /// Check recent events for an ongoing maintenance window.
+    ///
+    /// A window starts with a warning and ends with an informational event; debug-level
+    /// repeats of an already announced window are ignored.
+    fn check_for_maintenance(events: &VecDeque<WorkerEvent>) -> Option<String> {
+        events
+            .iter()
+            .rev()
+            .find(|event| {
+                matches!(event.worker, Worker::Maintenance)
+                    && event.log_level >= crate::error_classifier::LogLevel::Info
+            })
+            .filter(|event| event.log_level >= crate::error_classifier::LogLevel::Warn)
+            .map(|event| event.msg.clone())
+    }
+
     /// Check recent events for version update information
     fn check_for_version_updates(
         events: &VecDeque<WorkerEvent>,
@@ -123,6 +170,8 @@ impl DashboardState {
             }
             Worker::ProofSubmitter => Color::White,
             Worker::VersionChecker => Color::LightCyan,
+            Worker::Maintenance => Color::LightYellow,
+            Worker::Scheduler => Color::Gray,
         }
     }
 
@@ -251,14 +300,40 @@ pub fn render_dashboard(f: &mut Frame, state: &DashboardState) {
         Color::Cyan
     };
 
-    let title_block = Block::default().borders(Borders::BOTTOM);
-    let title = Paragraph::new(title_text)
-        .alignment(Alignment::Center)
-        .style(
+    // Flash the header when the node has entered a failing state
+    let (title_text, title_style) = if state.alert_flash {
+        (
+            format!("⚠ NODE FAILING - CHECK LOGS ⚠  {}", title_text),
+            Style::default()
+                .fg(Color::White)
+                .bg(Color::Red)
+                .add_modifier(Modifier::BOLD),
+        )
+    } else {
+        (
+            title_text,
             Style::default()
                 .fg(title_color)
                 .add_modifier(Modifier::BOLD),
         )
+    };
+
+    // Maintenance banner below the title while the orchestrator is down for maintenance
+    let mut title_lines = vec![Line::from(title_text)];
+    if let Some(banner) = &state.maintenance_banner {
+        title_lines.push(Line::from(Span::styled(
+            format!("🛠 {}", banner),
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::LightYellow)
+                .add_modifier(Modifier::BOLD),
+        )));
+    }
+
+    let title_block = Block::default().borders(Borders::BOTTOM);
+    let title = Paragraph::new(title_lines)
+        .alignment(Alignment::Center)
+        .style(title_style)
         .block(title_block);
     f.render_widget(title, chunks[0]);
 
@@ -335,12 +410,35 @@ pub fn render_dashboard(f: &mut Frame, state: &DashboardState) {
         uptime.as_secs() % 60
     );
     status_lines.push(Line::from(uptime_string));
+    if let Some(uptime) = &state.uptime {
+        status_lines.push(Line::from(format!("UPTIME HISTORY: {}", uptime)));
+    }
+
+    // Per-node counts, when several nodes share this process
+    if state.node_stats.len() > 1 {
+        for (node_id, stats) in &state.node_stats {
+            status_lines.push(Line::from(format!("NODE {}: {}", node_id, stats)));
+        }
+    }
 
     // NEX Points
     if let Some(nex_points) = state.nex_points {
         status_lines.push(Line::from(format!("NEX POINTS: {}", nex_points)));
     }
 
+    // Earnings goal, highlighted while the pace falls short of it
+    if let Some(goal) = &state.goal {
+        let goal_color = if goal.projected < goal.goal.proofs {
+            Color::LightYellow
+        } else {
+            Color::Cyan
+        };
+        status_lines.push(Line::from(vec![Span::styled(
+            format!("GOAL: {}", goal),
+            Style::default().fg(goal_color),
+        )]));
+    }
+
     // Current Task
     if let Some(task) = &state.current_task {
         status_lines.push(Line::from(format!("CURRENT TASK: {}", task)));
@@ -382,6 +480,8 @@ pub fn render_dashboard(f: &mut Frame, state: &DashboardState) {
                 Worker::Prover(worker_id) => format!("P{}", worker_id),
                 Worker::ProofSubmitter => "Submitter".to_string(),
                 Worker::VersionChecker => "Version".to_string(),
+                Worker::Maintenance => "Maintenance".to_string(),
+                Worker::Scheduler => "Scheduler".to_string(),
             };
 
             let worker_color = DashboardState::get_worker_color(&event.worker);
@@ -442,13 +542,29 @@ pub fn render_dashboard(f: &mut Frame, state: &DashboardState) {
         )
         .wrap(Wrap { trim: true });
 
-    f.render_widget(log_widget, body_chunks[1]);
+    match &state.resources {
+        Some(resources) => {
+            crate::ui::resources::render_resources(f, body_chunks[1], resources);
+        }
+        None => f.render_widget(log_widget, body_chunks[1]),
+    }
 
     // Footer with version info
+    let resources_toggle = if state.resources.is_some() {
+        "Logs"
+    } else {
+        "Resources"
+    };
     let footer_text = if state.update_available {
-        "[Q] Quit | 🚀 New version available! Check release notes at github.com/nexus-xyz/nexus-cli"
+        format!(
+            "[Q] Quit | [L] Log level: {} | [R] {} | 🚀 New version available! Check release notes at github.com/nexus-xyz/nexus-cli",
+            state.log_level, resources_toggle
+        )
     } else {
-        "[Q] Quit"
+        format!(
+            "[Q] Quit | [L] Log level: {} | [R] {}",
+            state.log_level, resources_toggle
+        )
     };
 
     let footer = Paragraph::new(footer_text)
@@ -1,13 +1,18 @@
+mod alert;
 mod dashboard;
 mod login;
+mod resources;
 pub mod splash;
 
 use crate::environment::Environment;
 use crate::events::Event as WorkerEvent;
+use crate::ui::alert::AlertMonitor;
 use crate::ui::dashboard::{DashboardState, render_dashboard};
 use crate::ui::login::render_login;
+use crate::ui::resources::ResourceSampler;
 use crate::ui::splash::render_splash;
 use crossterm::event::{self, Event, KeyCode};
+use crossterm::{execute, style::Print};
 use ratatui::{Frame, Terminal, backend::Backend};
 use std::collections::VecDeque;
 use std::time::{Duration, Instant};
@@ -54,6 +59,18 @@ pub struct App {
 
     /// Whether to disable background colors
     no_background_color: bool,
+
+    /// Whether to ring the terminal bell and flash the header when the node starts failing.
+    alert_on_error: bool,
+
+    /// Detects transitions into a failing state.
+    alert_monitor: AlertMonitor,
+
+    /// Whether the resources pane replaces the logs, toggled with the `R` key.
+    show_resources: bool,
+
+    /// Samples this process's resource usage while the resources pane is shown.
+    resource_sampler: ResourceSampler,
 }
 
 impl App {
@@ -64,9 +81,13 @@ impl App {
         event_receiver: mpsc::Receiver<WorkerEvent>,
         shutdown_sender: broadcast::Sender<()>,
         no_background_color: bool,
+        alert_on_error: bool,
     ) -> Self {
         Self {
-            start_time: Instant::now(),
+            // Continues the timer of a session handed over by an earlier process
+            start_time: Instant::now()
+                .checked_sub(crate::control::control_state().uptime())
+                .unwrap_or_else(Instant::now),
             node_id,
             environment,
             current_screen: Screen::Splash,
@@ -74,9 +95,18 @@ impl App {
             event_receiver,
             shutdown_sender,
             no_background_color,
+            alert_on_error,
+            alert_monitor: AlertMonitor::new(),
+            show_resources: false,
+            resource_sampler: ResourceSampler::new(),
         }
     }
 
+    /// Whether the dashboard header should currently be flashing.
+    fn alert_flash(&self) -> bool {
+        self.alert_on_error && self.alert_monitor.flash_on()
+    }
+
     /// Handles a complete login process, transitioning to the dashboard screen.
     #[allow(unused)]
     pub fn login(&mut self) {
@@ -87,6 +117,7 @@ impl App {
             self.start_time,
             &self.events,
             self.no_background_color,
+            self.alert_flash(),
         );
         self.current_screen = Screen::Dashboard(state);
     }
@@ -97,10 +128,20 @@ pub async fn run<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> std::i
     let splash_start = Instant::now();
     let splash_duration = Duration::from_secs(2);
 
+    // Drains and handoffs shut the node down from outside the dashboard
+    let mut shutdown = app.shutdown_sender.subscribe();
+
     // UI event loop
     loop {
+        if shutdown.try_recv().is_ok() {
+            return Ok(());
+        }
         // Drain prover events from the async channel into app.events
         while let Ok(event) = app.event_receiver.try_recv() {
+            if app.alert_monitor.observe(&event) && app.alert_on_error {
+                // Ring the terminal bell so users with the dashboard on another screen notice.
+                let _ = execute!(std::io::stdout(), Print('\x07'));
+            }
             if app.events.len() >= MAX_EVENTS {
                 app.events.pop_front();
             }
@@ -112,13 +153,17 @@ pub async fn run<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> std::i
             Screen::Splash => {}
             Screen::Login => {}
             Screen::Dashboard(_) => {
-                let state = DashboardState::new(
+                let mut state = DashboardState::new(
                     app.node_id,
                     app.environment.clone(),
                     app.start_time,
                     &app.events,
                     app.no_background_color,
+                    app.alert_flash(),
                 );
+                if app.show_resources {
+                    state.resources = Some(app.resource_sampler.sample());
+                }
                 app.current_screen = Screen::Dashboard(state);
             }
         }
@@ -133,6 +178,7 @@ pub async fn run<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> std::i
                     app.start_time,
                     &app.events,
                     app.no_background_color,
+                    app.alert_flash(),
                 ));
                 continue;
             }
@@ -163,6 +209,7 @@ pub async fn run<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> std::i
                                 app.start_time,
                                 &app.events,
                                 app.no_background_color,
+                                app.alert_flash(),
                             ));
                         }
                     }
@@ -171,7 +218,19 @@ pub async fn run<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> std::i
                             app.login();
                         }
                     }
-                    Screen::Dashboard(_dashboard_state) => {}
+                    Screen::Dashboard(_dashboard_state) => {
+                        // Cycle the log level, e.g. to see debug events during an incident
+                        if matches!(key.code, KeyCode::Char('l') | KeyCode::Char('L')) {
+                            let level = crate::logging::current_log_level();
+                            crate::logging::set_log_level(crate::logging::next_dashboard_level(
+                                level,
+                            ));
+                        }
+                        // Swap the logs for the resources pane and back
+                        if matches!(key.code, KeyCode::Char('r') | KeyCode::Char('R')) {
+                            app.show_resources = !app.show_resources;
+                        }
+                    }
                 }
             }
         }
@@ -0,0 +1,238 @@
+//! Resources pane
+//!
+//! Pressing `R` on the dashboard swaps the log pane for a `top`-style view of this process:
+//! CPU, resident memory, thread count and disk IO, plus the busiest threads. Prover workers
+//! run on runtime threads rather than threads of their own, so usage is broken down by thread
+//! (with the thread name the runtime gave it), not by worker.
+
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph};
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+/// CPU usage is measured between samples, so sampling more often adds noise, not detail.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Threads listed, busiest first.
+const MAX_THREADS_SHOWN: usize = 12;
+
+/// Usage of one thread of this process.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThreadUsage {
+    pub tid: u32,
+    pub name: String,
+    pub cpu_percent: f32,
+}
+
+/// Resource usage of this process at one sample.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ResourceSnapshot {
+    /// CPU usage; 100% is one fully used core.
+    pub cpu_percent: f32,
+    pub rss_bytes: u64,
+    pub virtual_bytes: u64,
+    pub threads: usize,
+    pub read_bytes_per_sec: f64,
+    pub written_bytes_per_sec: f64,
+    pub total_read_bytes: u64,
+    pub total_written_bytes: u64,
+    pub busiest_threads: Vec<ThreadUsage>,
+}
+
+/// Samples this process's resource usage, at most once per [`SAMPLE_INTERVAL`].
+#[derive(Debug)]
+pub struct ResourceSampler {
+    system: System,
+    pid: Pid,
+    last_sample: Option<Instant>,
+    snapshot: ResourceSnapshot,
+}
+
+impl ResourceSampler {
+    pub fn new() -> Self {
+        Self {
+            system: System::new(),
+            pid: Pid::from_u32(std::process::id()),
+            last_sample: None,
+            snapshot: ResourceSnapshot::default(),
+        }
+    }
+
+    /// The latest snapshot, refreshed if the previous one is old enough.
+    pub fn sample(&mut self) -> ResourceSnapshot {
+        let now = Instant::now();
+        let elapsed = match self.last_sample {
+            Some(last) if now.duration_since(last) < SAMPLE_INTERVAL => {
+                return self.snapshot.clone();
+            }
+            Some(last) => now.duration_since(last).as_secs_f64(),
+            None => 0.0,
+        };
+        self.last_sample = Some(now);
+
+        // Threads are refreshed along with the process that owns them
+        self.system.refresh_processes_specifics(
+            ProcessesToUpdate::Some(&[self.pid]),
+            true,
+            ProcessRefreshKind::everything(),
+        );
+        let Some(process) = self.system.process(self.pid) else {
+            return self.snapshot.clone();
+        };
+
+        let disk = process.disk_usage();
+        let per_sec = |bytes: u64| {
+            if elapsed > 0.0 {
+                bytes as f64 / elapsed
+            } else {
+                0.0
+            }
+        };
+        let mut threads: Vec<ThreadUsage> = process
+            .tasks()
+            .map(|tasks| {
+                tasks
+                    .iter()
+                    .filter_map(|tid| self.system.process(*tid))
+                    .map(|thread| ThreadUsage {
+                        tid: thread.pid().as_u32(),
+                        name: thread.name().to_string_lossy().into_owned(),
+                        cpu_percent: thread.cpu_usage(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let thread_count = threads.len().max(1);
+        threads.sort_by(|a, b| b.cpu_percent.total_cmp(&a.cpu_percent));
+        threads.truncate(MAX_THREADS_SHOWN);
+
+        self.snapshot = ResourceSnapshot {
+            cpu_percent: process.cpu_usage(),
+            rss_bytes: process.memory(),
+            virtual_bytes: process.virtual_memory(),
+            threads: thread_count,
+            read_bytes_per_sec: per_sec(disk.read_bytes),
+            written_bytes_per_sec: per_sec(disk.written_bytes),
+            total_read_bytes: disk.total_read_bytes,
+            total_written_bytes: disk.total_written_bytes,
+            busiest_threads: threads,
+        };
+        self.snapshot.clone()
+    }
+}
+
+/// Formats a byte count with a binary unit, e.g. "1.5 GiB".
+fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{:.0} {}", value, UNITS[unit])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Renders the resources pane into `area`.
+pub fn render_resources(f: &mut Frame, area: Rect, snapshot: &ResourceSnapshot) {
+    let label = Style::default().fg(Color::DarkGray);
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("CPU      ", label),
+            Span::raw(format!(
+                "{:.1}% ({} cores)",
+                snapshot.cpu_percent,
+                crate::system::num_cores()
+            )),
+        ]),
+        Line::from(vec![
+            Span::styled("MEMORY   ", label),
+            Span::raw(format!(
+                "{} resident, {} virtual",
+                format_bytes(snapshot.rss_bytes as f64),
+                format_bytes(snapshot.virtual_bytes as f64)
+            )),
+        ]),
+        Line::from(vec![
+            Span::styled("THREADS  ", label),
+            Span::raw(snapshot.threads.to_string()),
+        ]),
+        Line::from(vec![
+            Span::styled("DISK IO  ", label),
+            Span::raw(format!(
+                "read {}/s, write {}/s (total {} / {})",
+                format_bytes(snapshot.read_bytes_per_sec),
+                format_bytes(snapshot.written_bytes_per_sec),
+                format_bytes(snapshot.total_read_bytes as f64),
+                format_bytes(snapshot.total_written_bytes as f64)
+            )),
+        ]),
+        Line::from(""),
+        Line::from(Span::styled(
+            format!("{:>8}  {:>6}  NAME", "TID", "CPU%"),
+            label.add_modifier(Modifier::BOLD),
+        )),
+    ];
+    if snapshot.busiest_threads.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "Per-thread usage is not available on this platform",
+            label,
+        )));
+    }
+    for thread in &snapshot.busiest_threads {
+        let color = if thread.cpu_percent >= 50.0 {
+            Color::LightYellow
+        } else {
+            Color::White
+        };
+        lines.push(Line::from(Span::styled(
+            format!(
+                "{:>8}  {:>6.1}  {}",
+                thread.tid, thread.cpu_percent, thread.name
+            ),
+            Style::default().fg(color),
+        )));
+    }
+
+    let widget = Paragraph::new(lines).block(
+        Block::default()
+            .title("RESOURCES")
+            .borders(Borders::NONE)
+            .style(
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ),
+    );
+    f.render_widget(widget, area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Byte counts should be shown with the largest unit that keeps them above one.
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512.0), "512 B");
+        assert_eq!(format_bytes(1536.0), "1.5 KiB");
+        assert_eq!(format_bytes(3.0 * 1024.0 * 1024.0 * 1024.0), "3.0 GiB");
+    }
+
+    #[test]
+    // Sampling should find this process and reuse a recent sample.
+    fn test_sample_current_process() {
+        let mut sampler = ResourceSampler::new();
+        let first = sampler.sample();
+        assert!(first.rss_bytes > 0);
+        assert!(first.threads >= 1);
+        assert_eq!(sampler.sample(), first);
+    }
+}
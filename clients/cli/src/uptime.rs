@@ -0,0 +1,465 @@
+//! Historical uptime per node
+//!
+//! Some reward programs weight consistent uptime, so each run records a segment (from start
+//! to the last heartbeat) for every node it proves for, in `~/.nexus/uptime.json`. A node counts
+//! as up while a process runs it, paused or not. `nexus stats` shows the share of the last day
+//! and week each node was up, `nexus stats --uptime` a breakdown by calendar day, and the
+//! dashboard the same shares for the running node.
+//!
+//! Several processes may run nodes on one machine, so saves re-read the file and only replace
+//! this process's segments. Processes take turns through `uptime.json.lock`, created
+//! exclusively for the length of a save, and the file is written aside and renamed over the
+//! old one, so neither a concurrent save nor a crash mid-write loses segments. Segments older
+//! than [`RETENTION`] are dropped.
+
+use chrono::{DateTime, Duration as ChronoDuration, Local, NaiveTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+/// How often the end of the current segment is saved, bounding what a crash loses.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long segments are kept.
+const RETENTION: ChronoDuration = ChronoDuration::days(31);
+
+/// Calendar days shown by `nexus stats --uptime`.
+const DAYS_SHOWN: i64 = 7;
+
+/// Attempts to take the save lock before a heartbeat is skipped, and the wait between them.
+const LOCK_ATTEMPTS: u32 = 20;
+const LOCK_RETRY: Duration = Duration::from_millis(50);
+
+/// Age after which a lock is taken to be left by a crashed process. A save takes milliseconds.
+const LOCK_STALE_AFTER: Duration = Duration::from_secs(10);
+
+/// A stretch of time a node was running.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct UptimeSegment {
+    /// When the run started (RFC 3339)
+    pub start: String,
+    /// The last heartbeat of the run (RFC 3339)
+    pub end: String,
+}
+
+impl UptimeSegment {
+    fn new(start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        Self {
+            start: start.to_rfc3339(),
+            end: end.to_rfc3339(),
+        }
+    }
+
+    /// The segment's start and end, if both parse.
+    fn bounds(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let start = DateTime::parse_from_rfc3339(&self.start).ok()?;
+        let end = DateTime::parse_from_rfc3339(&self.end).ok()?;
+        Some((start.with_timezone(&Utc), end.with_timezone(&Utc)))
+    }
+}
+
+/// Uptime segments of every node that ran on this machine, by node ID.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct UptimeHistory {
+    pub nodes: BTreeMap<String, Vec<UptimeSegment>>,
+}
+
+impl UptimeHistory {
+    /// Loads the saved history; a missing file means no node has run yet.
+    ///
+    /// # Errors
+    /// Returns an `std::io::Error` if the file exists but cannot be read or parsed.
+    pub fn load_from_file(path: &Path) -> Result<Self, std::io::Error> {
+        match std::fs::read(path) {
+            Ok(buf) => serde_json::from_slice(&buf)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Inserts or extends the segment of `node_id` that started at `segment.start`, and drops
+    /// segments that ended before the retention period.
+    fn upsert(&mut self, node_id: &str, segment: UptimeSegment, now: DateTime<Utc>) {
+        let segments = self.nodes.entry(node_id.to_string()).or_default();
+        match segments
+            .iter_mut()
+            .find(|saved| saved.start == segment.start)
+        {
+            Some(saved) => *saved = segment,
+            None => segments.push(segment),
+        }
+        segments.retain(|segment| {
+            segment
+                .bounds()
+                .is_some_and(|(_, end)| end > now - RETENTION)
+        });
+    }
+
+    /// The share of `[since, until)` during which `node_id` was up, between 0.0 and 1.0.
+    pub fn share(&self, node_id: &str, since: DateTime<Utc>, until: DateTime<Utc>) -> f64 {
+        let window = (until - since).num_milliseconds();
+        if window <= 0 {
+            return 0.0;
+        }
+        let mut covered: Vec<(DateTime<Utc>, DateTime<Utc>)> = self
+            .nodes
+            .get(node_id)
+            .into_iter()
+            .flatten()
+            .filter_map(UptimeSegment::bounds)
+            .map(|(start, end)| (start.max(since), end.min(until)))
+            .filter(|(start, end)| start < end)
+            .collect();
+        covered.sort();
+
+        // Segments of concurrent runs of one node may overlap, so merge them first
+        let mut up = 0;
+        let mut current: Option<(DateTime<Utc>, DateTime<Utc>)> = None;
+        for (start, end) in covered {
+            match &mut current {
+                Some((_, current_end)) if start <= *current_end => {
+                    *current_end = (*current_end).max(end);
+                }
+                _ => {
+                    if let Some((current_start, current_end)) = current {
+                        up += (current_end - current_start).num_milliseconds();
+                    }
+                    current = Some((start, end));
+                }
+            }
+        }
+        if let Some((current_start, current_end)) = current {
+            up += (current_end - current_start).num_milliseconds();
+        }
+        up as f64 / window as f64
+    }
+
+    /// The uptime of `node_id` over the last day and week before `now`.
+    pub fn summary(&self, node_id: &str, now: DateTime<Utc>) -> UptimeSummary {
+        UptimeSummary {
+            last_day: self.share(node_id, now - ChronoDuration::days(1), now),
+            last_week: self.share(node_id, now - ChronoDuration::days(7), now),
+        }
+    }
+}
+
+/// Share of the last day and week a node was up, between 0.0 and 1.0.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct UptimeSummary {
+    pub last_day: f64,
+    pub last_week: f64,
+}
+
+impl fmt::Display for UptimeSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.1}% (24h), {:.1}% (7d)",
+            self.last_day * 100.0,
+            self.last_week * 100.0
+        )
+    }
+}
+
+/// Path to the saved uptime history, next to the config file.
+pub fn uptime_path(config_path: &Path) -> PathBuf {
+    config_path.with_file_name("uptime.json")
+}
+
+/// Exclusive right to save the history at a path, across processes; released on drop.
+struct SaveLock {
+    path: PathBuf,
+}
+
+impl SaveLock {
+    /// Takes the lock of the history at `path`, waiting for another process to release it.
+    /// None if it stays taken or cannot be created.
+    fn acquire(path: &Path) -> Option<Self> {
+        let path = path.with_extension("json.lock");
+        for _ in 0..LOCK_ATTEMPTS {
+            match std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(_) => return Some(Self { path }),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    let stale = std::fs::metadata(&path)
+                        .and_then(|metadata| metadata.modified())
+                        .is_ok_and(|modified| {
+                            modified.elapsed().unwrap_or_default() > LOCK_STALE_AFTER
+                        });
+                    if stale {
+                        let _ = std::fs::remove_file(&path);
+                    } else {
+                        std::thread::sleep(LOCK_RETRY);
+                    }
+                }
+                Err(_) => return None,
+            }
+        }
+        None
+    }
+}
+
+impl Drop for SaveLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Writes `history` aside and renames it over `path`, so a crash mid-write leaves the previous
+/// history intact.
+fn save(path: &Path, history: &UptimeHistory) -> std::io::Result<()> {
+    let json = serde_json::to_string_pretty(history).map_err(std::io::Error::other)?;
+    let partial = path.with_extension("json.partial");
+    std::fs::write(&partial, json)?;
+    std::fs::rename(partial, path)
+}
+
+struct UptimeTracker {
+    node_ids: Vec<String>,
+    started: DateTime<Utc>,
+    path: Option<PathBuf>,
+    /// The history as last saved, for the dashboard
+    history: UptimeHistory,
+}
+
+impl UptimeTracker {
+    /// Extends this run's segments to `now` and saves them. If another process holds the save
+    /// lock throughout, only the history shown by the dashboard is updated; the next heartbeat
+    /// saves the segments.
+    fn heartbeat(&mut self, now: DateTime<Utc>) {
+        let lock = self.path.as_deref().and_then(SaveLock::acquire);
+        let mut history = match (&lock, &self.path) {
+            (Some(_), Some(path)) => UptimeHistory::load_from_file(path).ok(),
+            _ => None,
+        }
+        .unwrap_or_else(|| self.history.clone());
+        for node_id in &self.node_ids {
+            history.upsert(node_id, UptimeSegment::new(self.started, now), now);
+        }
+        if let (Some(_), Some(path)) = (&lock, &self.path) {
+            let _ = save(path, &history);
+        }
+        self.history = history;
+    }
+}
+
+static TRACKER: OnceLock<Mutex<UptimeTracker>> = OnceLock::new();
+
+/// Starts recording the uptime of `node_ids`, with a heartbeat every minute until the process
+/// exits. Only the first call has an effect.
+pub fn init(node_ids: &[u64]) {
+    if node_ids.is_empty() {
+        return;
+    }
+    let now = Utc::now();
+    let tracker = UptimeTracker {
+        node_ids: node_ids.iter().map(u64::to_string).collect(),
+        started: now,
+        path: crate::config::get_config_path()
+            .ok()
+            .map(|path| uptime_path(&path)),
+        history: UptimeHistory::default(),
+    };
+    if TRACKER.set(Mutex::new(tracker)).is_err() {
+        return;
+    }
+    heartbeat();
+    tokio::spawn(async {
+        let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            heartbeat();
+        }
+    });
+}
+
+/// Records that the nodes of this process are still up; called on exit as well.
+pub fn heartbeat() {
+    if let Some(Ok(mut tracker)) = TRACKER.get().map(Mutex::lock) {
+        tracker.heartbeat(Utc::now());
+    }
+}
+
+/// The uptime of the first node of this process, if it records uptime.
+pub fn summary() -> Option<UptimeSummary> {
+    let tracker = TRACKER.get()?.lock().ok()?;
+    let node_id = tracker.node_ids.first()?;
+    Some(tracker.history.summary(node_id, Utc::now()))
+}
+
+/// Uptime of one node on one calendar day.
+#[derive(Serialize, Debug)]
+struct DayUptime {
+    /// Local date, `YYYY-MM-DD`
+    date: String,
+    share: f64,
+}
+
+/// Uptime of one node, as printed by `stats --uptime --json`.
+#[derive(Serialize, Debug)]
+struct NodeUptime {
+    node_id: String,
+    #[serde(flatten)]
+    summary: UptimeSummary,
+    /// The last days, newest first; today counts up to now
+    days: Vec<DayUptime>,
+}
+
+/// Uptime of `node_id` on each of the last `DAYS_SHOWN` local calendar days, newest first.
+fn daily(history: &UptimeHistory, node_id: &str, now: DateTime<Local>) -> Vec<DayUptime> {
+    let today = now.date_naive();
+    (0..DAYS_SHOWN)
+        .filter_map(|days_ago| {
+            let date = today - ChronoDuration::days(days_ago);
+            let start = Local
+                .from_local_datetime(&date.and_time(NaiveTime::MIN))
+                .earliest()?;
+            let next = Local
+                .from_local_datetime(&(date + ChronoDuration::days(1)).and_time(NaiveTime::MIN))
+                .earliest()?;
+            let end = next.min(now);
+            Some(DayUptime {
+                date: date.format("%Y-%m-%d").to_string(),
+                share: history.share(node_id, start.with_timezone(&Utc), end.with_timezone(&Utc)),
+            })
+        })
+        .collect()
+}
+
+/// Prints the uptime summary of every node, part of `nexus stats`.
+///
+/// # Errors
+/// Returns an error if the saved history cannot be read.
+pub fn print_summary(config_path: &Path) -> Result<(), Box<dyn Error>> {
+    let history = UptimeHistory::load_from_file(&uptime_path(config_path))?;
+    let now = Utc::now();
+    for node_id in history.nodes.keys() {
+        println!("Node {} uptime: {}", node_id, history.summary(node_id, now));
+    }
+    Ok(())
+}
+
+/// Implements `nexus stats --uptime`: the uptime of every node by day, as pretty JSON if
+/// `json` is set.
+///
+/// # Errors
+/// Returns an error if the saved history cannot be read.
+pub fn print_daily(config_path: &Path, json: bool) -> Result<(), Box<dyn Error>> {
+    let history = UptimeHistory::load_from_file(&uptime_path(config_path))?;
+    let now = Local::now();
+    let nodes: Vec<NodeUptime> = history
+        .nodes
+        .keys()
+        .map(|node_id| NodeUptime {
+            node_id: node_id.clone(),
+            summary: history.summary(node_id, now.with_timezone(&Utc)),
+            days: daily(&history, node_id, now),
+        })
+        .collect();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&nodes)?);
+        return Ok(());
+    }
+    if nodes.is_empty() {
+        println!("No uptime recorded yet. Uptime is recorded while a node runs.");
+        return Ok(());
+    }
+    for node in &nodes {
+        println!("Node {}: {}", node.node_id, node.summary);
+        println!("{:<12} {:>7}", "Day", "Uptime");
+        for day in &node.days {
+            println!("{:<12} {:>6.1}%", day.date, day.share * 100.0);
+        }
+        println!();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn at(hour: i64) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2025-06-02T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+            + ChronoDuration::hours(hour)
+    }
+
+    #[test]
+    // Uptime is the covered share of the window, counting overlapping runs once.
+    fn test_share() {
+        let mut history = UptimeHistory::default();
+        history.upsert("1", UptimeSegment::new(at(0), at(6)), at(24));
+        history.upsert("1", UptimeSegment::new(at(4), at(12)), at(24));
+        history.upsert("1", UptimeSegment::new(at(18), at(30)), at(24));
+
+        assert!((history.share("1", at(0), at(24)) - 0.75).abs() < 1e-9);
+        assert!((history.share("1", at(6), at(18)) - 0.5).abs() < 1e-9);
+        assert_eq!(history.share("2", at(0), at(24)), 0.0);
+    }
+
+    #[test]
+    // A run's heartbeats extend its own segment; old segments are dropped.
+    fn test_upsert_extends_and_prunes() {
+        let mut history = UptimeHistory::default();
+        history.upsert("1", UptimeSegment::new(at(0), at(1)), at(1));
+        history.upsert("1", UptimeSegment::new(at(0), at(2)), at(2));
+        assert_eq!(history.nodes["1"], vec![UptimeSegment::new(at(0), at(2))]);
+
+        let later = at(2) + RETENTION + ChronoDuration::hours(1);
+        history.upsert("1", UptimeSegment::new(later, later), later);
+        assert_eq!(history.nodes["1"], vec![UptimeSegment::new(later, later)]);
+    }
+
+    #[test]
+    // Saves from another process are kept when this one saves.
+    fn test_heartbeat_keeps_other_processes() {
+        let dir = tempdir().unwrap();
+        let path = uptime_path(&dir.path().join("config.json"));
+        let mut other = UptimeHistory::default();
+        other.upsert("2", UptimeSegment::new(at(0), at(1)), at(1));
+        std::fs::write(&path, serde_json::to_string(&other).unwrap()).unwrap();
+
+        let mut tracker = UptimeTracker {
+            node_ids: vec!["1".to_string()],
+            started: at(0),
+            path: Some(path.clone()),
+            history: UptimeHistory::default(),
+        };
+        tracker.heartbeat(at(2));
+
+        let saved = UptimeHistory::load_from_file(&path).unwrap();
+        assert_eq!(saved.nodes.len(), 2);
+        assert_eq!(saved.nodes["1"], vec![UptimeSegment::new(at(0), at(2))]);
+        assert!(!path.with_extension("json.lock").exists());
+    }
+
+    #[test]
+    // While another process saves, the file is left alone; a lock it left behind is broken.
+    fn test_save_lock() {
+        let dir = tempdir().unwrap();
+        let path = uptime_path(&dir.path().join("config.json"));
+        let held = SaveLock::acquire(&path).unwrap();
+        assert!(SaveLock::acquire(&path).is_none());
+        drop(held);
+        assert!(SaveLock::acquire(&path).is_some());
+
+        let lock_path = path.with_extension("json.lock");
+        let stale = std::fs::File::create(&lock_path).unwrap();
+        stale
+            .set_modified(std::time::SystemTime::now() - LOCK_STALE_AFTER * 2)
+            .unwrap();
+        assert!(SaveLock::acquire(&path).is_some());
+    }
+}
@@ -90,6 +90,9 @@ use mockall::{automock, predicate::*};
 // Check for updates and constraints every 24 hours
 const VERSION_CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
 
+// Spread nodes' periodic checks over an hour, so they do not all query GitHub together
+const VERSION_CHECK_SPREAD: Duration = Duration::from_secs(60 * 60);
+
 // GitHub API endpoint for the latest release
 const GITHUB_RELEASES_URL: &str =
     "https://api.github.com/repos/nexus-xyz/nexus-cli/releases/latest";
@@ -217,7 +220,7 @@ pub async fn version_checker_task(
         version_checker,
         event_sender,
         shutdown,
-        VERSION_CHECK_INTERVAL,
+        VERSION_CHECK_INTERVAL + crate::pacing::offset("version_check", VERSION_CHECK_SPREAD),
     )
     .await;
 }
@@ -0,0 +1,395 @@
+//! Multiple wallets
+//!
+//! Operators who run nodes on behalf of several owners can keep more than one wallet in the
+//! config file. The wallet registered with `register-user` stays the primary wallet (the
+//! top-level `wallet_address`, `user_id` and `node_id`); further wallets are listed under
+//! `wallets`, each with the nodes linked to it:
+//!
+//! ```json
+//! "wallets": [
+//!   { "name": "alice", "wallet_address": "0x…", "user_id": "…", "node_ids": [1234, 5678] }
+//! ]
+//! ```
+//!
+//! A node belongs to the wallet it was registered for, and the orchestrator has no way to move
+//! it to another one, so `wallet link` only records nodes the orchestrator reports as the
+//! wallet's own (or registers a new one). `start` without `--node-id` runs the nodes of every
+//! wallet.
+
+use crate::config::Config;
+use crate::keys;
+use crate::orchestrator::Orchestrator;
+use crate::pretty::{handle_cmd_error, print_cmd_error, print_cmd_info};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::error::Error;
+use std::path::Path;
+
+/// Name under which the primary wallet is listed and addressed.
+pub const PRIMARY_WALLET: &str = "primary";
+
+/// A wallet besides the primary one, and the nodes proving for it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Wallet {
+    pub name: String,
+    pub wallet_address: String,
+    pub user_id: String,
+    #[serde(default, deserialize_with = "deserialize_node_ids")]
+    pub node_ids: Vec<u64>,
+}
+
+/// Node IDs as a list, or comma-separated as rendered from a config template.
+fn deserialize_node_ids<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u64>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NodeIds {
+        List(Vec<u64>),
+        Joined(String),
+    }
+    match NodeIds::deserialize(deserializer)? {
+        NodeIds::List(node_ids) => Ok(node_ids),
+        NodeIds::Joined(node_ids) => node_ids
+            .split(',')
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(|id| id.parse().map_err(serde::de::Error::custom))
+            .collect(),
+    }
+}
+
+/// A wallet as shown by `wallet list` and `wallet show`, primary wallet included.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct WalletSummary {
+    pub name: String,
+    pub wallet_address: String,
+    pub user_id: String,
+    pub node_ids: Vec<u64>,
+}
+
+/// Node IDs in the comma-separated `node_id` field of the config, skipping invalid ones.
+fn primary_node_ids(config: &Config) -> Vec<u64> {
+    config
+        .node_id
+        .split(',')
+        .filter_map(|id| id.trim().parse().ok())
+        .collect()
+}
+
+fn set_primary_node_ids(config: &mut Config, node_ids: &[u64]) {
+    config.node_id = node_ids
+        .iter()
+        .map(u64::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+}
+
+/// Every wallet in the config, primary first.
+pub fn summaries(config: &Config) -> Vec<WalletSummary> {
+    let primary = (!config.wallet_address.is_empty()).then(|| WalletSummary {
+        name: PRIMARY_WALLET.to_string(),
+        wallet_address: config.wallet_address.clone(),
+        user_id: config.user_id.clone(),
+        node_ids: primary_node_ids(config),
+    });
+    primary
+        .into_iter()
+        .chain(config.wallets.iter().map(|wallet| WalletSummary {
+            name: wallet.name.clone(),
+            wallet_address: wallet.wallet_address.clone(),
+            user_id: wallet.user_id.clone(),
+            node_ids: wallet.node_ids.clone(),
+        }))
+        .collect()
+}
+
+/// Finds a wallet by name or (case-insensitively) by address.
+pub fn find(config: &Config, wallet: &str) -> Option<WalletSummary> {
+    summaries(config).into_iter().find(|summary| {
+        summary.name == wallet || summary.wallet_address.eq_ignore_ascii_case(wallet)
+    })
+}
+
+/// Nodes linked to the additional wallets, which `start` runs along with the primary ones.
+pub fn linked_node_ids(config: &Config) -> Vec<u64> {
+    config
+        .wallets
+        .iter()
+        .flat_map(|wallet| wallet.node_ids.iter().copied())
+        .collect()
+}
+
+/// Records `node_id` under the named wallet in the config, removing it from any other wallet
+/// it was listed under. Returns the name of that wallet, if any.
+pub fn link_node(
+    config: &mut Config,
+    wallet: &str,
+    node_id: u64,
+) -> Result<Option<String>, String> {
+    let Some(target) = find(config, wallet) else {
+        return Err(format!("No wallet named {}", wallet));
+    };
+    let previous = summaries(config)
+        .into_iter()
+        .find(|summary| summary.node_ids.contains(&node_id))
+        .map(|summary| summary.name);
+    if previous.as_deref() == Some(target.name.as_str()) {
+        return Ok(previous);
+    }
+
+    let mut primary = primary_node_ids(config);
+    primary.retain(|id| *id != node_id);
+    for other in &mut config.wallets {
+        other.node_ids.retain(|id| *id != node_id);
+    }
+    if target.name == PRIMARY_WALLET {
+        primary.push(node_id);
+    } else if let Some(target) = config.wallets.iter_mut().find(|w| w.name == target.name) {
+        target.node_ids.push(node_id);
+    }
+    set_primary_node_ids(config, &primary);
+    Ok(previous)
+}
+
+fn format_nodes(node_ids: &[u64]) -> String {
+    if node_ids.is_empty() {
+        return "none".to_string();
+    }
+    node_ids
+        .iter()
+        .map(u64::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn load_config(config_path: &Path) -> Result<Config, Box<dyn Error>> {
+    Config::load_from_file(config_path).map_err(|e| {
+        handle_cmd_error!(e, "Failed to load config, please register a user first").into()
+    })
+}
+
+/// Implements `wallet list`.
+pub fn list(config_path: &Path, json: bool) -> Result<(), Box<dyn Error>> {
+    let wallets = summaries(&load_config(config_path)?);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&wallets)?);
+        return Ok(());
+    }
+    if wallets.is_empty() {
+        print_cmd_info!(
+            "No wallets registered.",
+            "Register one first: nexus-cli register-user --wallet-address <your-wallet-address>"
+        );
+        return Ok(());
+    }
+    for wallet in &wallets {
+        println!(
+            "{:<16} {}  nodes: {}",
+            wallet.name,
+            wallet.wallet_address,
+            format_nodes(&wallet.node_ids)
+        );
+    }
+    Ok(())
+}
+
+/// Implements `wallet show`.
+pub fn show(config_path: &Path, wallet: &str, json: bool) -> Result<(), Box<dyn Error>> {
+    let Some(summary) = find(&load_config(config_path)?, wallet) else {
+        print_cmd_error!("❌ Unknown wallet.");
+        return Err(format!("No wallet named {}. See: nexus-cli wallet list", wallet).into());
+    };
+    if json {
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+        return Ok(());
+    }
+    print_cmd_info!(
+        "Wallet",
+        "Name: {}\nWallet address: {}\nUser ID: {}\nNodes: {}",
+        summary.name,
+        summary.wallet_address,
+        summary.user_id,
+        format_nodes(&summary.node_ids)
+    );
+    Ok(())
+}
+
+/// Implements `wallet add`: registers the wallet with the orchestrator unless it already is,
+/// and adds it to the config under `name`.
+pub async fn add(
+    config_path: &Path,
+    name: &str,
+    wallet_address: &str,
+    orchestrator: Box<dyn Orchestrator>,
+) -> Result<(), Box<dyn Error>> {
+    if !keys::is_valid_eth_address(wallet_address) {
+        print_cmd_error!("❌ Invalid Ethereum wallet address.");
+        return Err(format!("Invalid Ethereum wallet address: {}", wallet_address).into());
+    }
+    let mut config = load_config(config_path)?;
+    if name == PRIMARY_WALLET || find(&config, name).is_some() {
+        return Err(format!("A wallet named {} already exists", name).into());
+    }
+    if let Some(existing) = find(&config, wallet_address) {
+        return Err(format!("This wallet is already added as {}", existing.name).into());
+    }
+
+    let user_id = match orchestrator.get_user(wallet_address).await {
+        Ok(user_id) => user_id,
+        Err(_) => {
+            let user_id = uuid::Uuid::new_v4().to_string();
+            orchestrator
+                .register_user(&user_id, wallet_address)
+                .await
+                .map_err(|e| handle_cmd_error!(e, "Failed to register wallet."))?;
+            user_id
+        }
+    };
+    config.wallets.push(Wallet {
+        name: name.to_string(),
+        wallet_address: wallet_address.to_string(),
+        user_id: user_id.clone(),
+        node_ids: Vec::new(),
+    });
+    config
+        .save(config_path)
+        .map_err(|e| handle_cmd_error!(e, "Failed to save config."))?;
+    print_cmd_info!(
+        "✅ Wallet added",
+        "Name: {}, User ID: {}. Next step - link a node: nexus-cli wallet link {} [--node-id <NODE_ID>]",
+        name,
+        user_id,
+        name
+    );
+    Ok(())
+}
+
+/// Implements `wallet link`: records a node the orchestrator reports as the wallet's own, or
+/// registers a new node for the wallet's user. The config is saved only once the orchestrator
+/// has confirmed the node.
+pub async fn link(
+    config_path: &Path,
+    wallet: &str,
+    node_id: Option<u64>,
+    orchestrator: Box<dyn Orchestrator>,
+) -> Result<(), Box<dyn Error>> {
+    let mut config = load_config(config_path)?;
+    let Some(summary) = find(&config, wallet) else {
+        return Err(format!("No wallet named {}. See: nexus-cli wallet list", wallet).into());
+    };
+    let node_id = match node_id {
+        Some(node_id) => {
+            let owner = orchestrator
+                .get_node(&node_id.to_string())
+                .await
+                .map_err(|e| handle_cmd_error!(e, "Failed to look up node."))?;
+            if !owner.eq_ignore_ascii_case(&summary.wallet_address) {
+                print_cmd_error!("❌ Node belongs to another wallet.");
+                return Err(format!(
+                    "Node {} is registered to {}, not {}. A node cannot be moved between wallets; register a new one with: nexus-cli wallet link {}",
+                    node_id, owner, summary.wallet_address, summary.name
+                )
+                .into());
+            }
+            node_id
+        }
+        None => orchestrator
+            .register_node(&summary.user_id)
+            .await
+            .map_err(|e| handle_cmd_error!(e, "Failed to register node."))?
+            .parse()?,
+    };
+    link_node(&mut config, &summary.name, node_id)?;
+    config
+        .save(config_path)
+        .map_err(|e| handle_cmd_error!(e, "Failed to save config."))?;
+    print_cmd_info!(
+        "✅ Node linked",
+        "Node {} proves for wallet {}",
+        node_id,
+        summary.name
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::Environment;
+    use crate::orchestrator::MockOrchestrator;
+
+    fn config_with_wallets() -> Config {
+        let mut config = Config::new(
+            "user-primary".to_string(),
+            "0x1111111111111111111111111111111111111111".to_string(),
+            "1,2".to_string(),
+            Environment::Production,
+        );
+        config.wallets.push(Wallet {
+            name: "alice".to_string(),
+            wallet_address: "0xAAAAaaaaAAAAaaaaAAAAaaaaAAAAaaaaAAAAaaaa".to_string(),
+            user_id: "user-alice".to_string(),
+            node_ids: vec![3],
+        });
+        config
+    }
+
+    #[test]
+    // Wallets should be found by name or by address in any case.
+    fn test_find_wallet() {
+        let config = config_with_wallets();
+        assert_eq!(find(&config, PRIMARY_WALLET).unwrap().node_ids, vec![1, 2]);
+        let alice = find(&config, "0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa").unwrap();
+        assert_eq!(alice.name, "alice");
+        assert!(find(&config, "bob").is_none());
+        assert_eq!(linked_node_ids(&config), vec![3]);
+    }
+
+    #[test]
+    // Linking a node should move it out of the wallet it was linked to before.
+    fn test_link_node_moves_it() {
+        let mut config = config_with_wallets();
+        assert_eq!(
+            link_node(&mut config, "alice", 2),
+            Ok(Some(PRIMARY_WALLET.to_string()))
+        );
+        assert_eq!(config.node_id, "1");
+        assert_eq!(config.wallets[0].node_ids, vec![3, 2]);
+
+        assert_eq!(
+            link_node(&mut config, PRIMARY_WALLET, 3),
+            Ok(Some("alice".to_string()))
+        );
+        assert_eq!(config.node_id, "1,3");
+        assert_eq!(config.wallets[0].node_ids, vec![2]);
+
+        assert_eq!(link_node(&mut config, "alice", 9), Ok(None));
+        assert!(link_node(&mut config, "bob", 9).is_err());
+    }
+
+    #[tokio::test]
+    // A node is recorded only if the orchestrator reports it as the wallet's own.
+    async fn test_link_checks_the_orchestrator() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        config_with_wallets().save(&path).unwrap();
+
+        let orchestrator = || {
+            let mut orchestrator = MockOrchestrator::new();
+            orchestrator
+                .expect_get_node()
+                .returning(|node_id| match node_id {
+                    "4" => Ok("0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa".to_string()),
+                    _ => Ok("0x2222222222222222222222222222222222222222".to_string()),
+                });
+            Box::new(orchestrator)
+        };
+        assert!(link(&path, "alice", Some(5), orchestrator()).await.is_err());
+        let node_ids = |path: &Path| {
+            Config::load_from_file(path).unwrap().wallets[0]
+                .node_ids
+                .clone()
+        };
+        assert_eq!(node_ids(&path), vec![3]);
+        link(&path, "alice", Some(4), orchestrator()).await.unwrap();
+        assert_eq!(node_ids(&path), vec![3, 4]);
+    }
+}
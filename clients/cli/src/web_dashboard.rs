@@ -0,0 +1,630 @@
+//! Web dashboard
+//!
+//! `--web-addr 127.0.0.1:3030` serves a read-only browser view of the node, for headless
+//! servers where attaching to the terminal UI is inconvenient. Like the TUI, it is rendered
+//! entirely from the worker event bus: events are forwarded unchanged to the TUI or headless
+//! log, and a snapshot of the node is kept for the page.
+//!
+//! - `GET /` - the dashboard page, which polls the snapshot every two seconds
+//! - `GET /api/state` - the snapshot as JSON
+//! - `GET /api/export` - a consolidated summary for a shared team status page: the nodes and
+//!   the wallets they prove for, recent performance and alerts. Only served when an export
+//!   token is set (`--export-token` or `NEXUS_EXPORT_TOKEN`), to requests that present it as
+//!   `Authorization: Bearer <token>`; `nexus export` fetches it.
+//! - `GET /api/history` and `GET /api/history/summary` - the task history, paged and filtered
+//!   by date and state, and counts per state and day (see [`crate::history`])
+//!
+//! The server speaks just enough HTTP/1.1 for a browser or curl; apart from the export, there
+//! is no authentication, so it should only be bound to a trusted interface.
+
+use crate::environment::Environment;
+use crate::error_classifier::LogLevel;
+use crate::events::{Event, EventType, Worker};
+use crate::history::{self, HistoryQuery};
+use crate::nodes::NodeStats;
+use crate::proxy::ProxyContext;
+use crate::session::redact;
+use crate::system;
+use crate::wallets::WalletSummary;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// The maximum number of log lines kept for the page, matching the TUI.
+const MAX_EVENTS: usize = 100;
+
+/// The number of recent warnings and errors included in the export.
+const MAX_ALERTS: usize = 20;
+
+/// Window over which the export reports recent throughput.
+const RECENT_WINDOW: Duration = Duration::from_secs(3600);
+
+/// Token that `/api/export` requests must present; the export is disabled without one.
+static EXPORT_TOKEN: OnceLock<String> = OnceLock::new();
+
+/// Enables `/api/export` for requests presenting `token`.
+pub fn set_export_token(token: String) {
+    let _ = EXPORT_TOKEN.set(token);
+}
+
+/// The dashboard page. Kept dependency-free so it works offline.
+const INDEX_HTML: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>Nexus node</title>
+<style>
+body { font-family: monospace; background: #111; color: #ddd; margin: 1.5em; }
+h1 { color: #4fc3f7; font-size: 1.3em; }
+.banner { background: #fff59d; color: #000; padding: 0.4em; margin-bottom: 1em; }
+.grid { display: flex; gap: 2em; flex-wrap: wrap; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+td, th { padding: 0.15em 0.8em; text-align: left; vertical-align: top; }
+th { color: #aaa; }
+.Success { color: #81c784; } .Error { color: #e57373; } .Refresh { color: #ffd54f; } .Shutdown { color: #aaa; }
+</style>
+</head>
+<body>
+<h1>Nexus node <span id="node"></span></h1>
+<div id="banner"></div>
+<div class="grid">
+<table id="info"></table>
+<table id="workers"><thead><tr><th>Worker</th><th>Last update</th><th>Status</th></tr></thead><tbody></tbody></table>
+</div>
+<table id="logs"><thead><tr><th>Time</th><th>Worker</th><th>Message</th></tr></thead><tbody></tbody></table>
+<script>
+function esc(s) { const d = document.createElement('div'); d.textContent = String(s); return d.innerHTML; }
+function row(cells, cls) { return '<tr' + (cls ? ' class="' + cls + '"' : '') + '>' + cells.map(c => '<td>' + esc(c) + '</td>').join('') + '</tr>'; }
+async function refresh() {
+  try {
+    const s = await (await fetch('/api/state')).json();
+    document.getElementById('node').textContent = s.node_id === null ? '(anonymous)' : s.node_id;
+    const banners = [];
+    if (s.maintenance) banners.push(s.maintenance);
+    if (s.update_notice) banners.push(s.update_notice);
+    document.getElementById('banner').innerHTML = banners.map(b => '<div class="banner">' + esc(b) + '</div>').join('');
+    const h = Math.floor(s.uptime_secs / 3600), m = Math.floor(s.uptime_secs / 60) % 60;
+    document.getElementById('info').innerHTML = [
+      ['Environment', s.environment], ['Version', s.version], ['Uptime', h + 'h ' + m + 'm'],
+      ['Cores', s.total_cores], ['RAM', s.total_ram_gb.toFixed(1) + ' GB'],
+      ['NEX points', s.nex_points === null ? 'n/a' : s.nex_points],
+      ['Proofs completed', s.tasks.proofs_completed], ['Proofs submitted', s.tasks.proofs_submitted],
+      ['Errors', s.tasks.errors],
+      ['Proxies', s.proxies.enabled ? s.proxies.count + ' loaded' : 'disabled'],
+    ].map(r => row(r)).join('');
+    document.querySelector('#workers tbody').innerHTML =
+      Object.entries(s.workers).map(([w, e]) => row([w, e.timestamp, e.msg], e.event_type)).join('');
+    document.querySelector('#logs tbody').innerHTML =
+      s.events.slice().reverse().map(e => row([e.timestamp, e.worker, e.msg], e.event_type)).join('');
+  } catch (e) {
+    document.getElementById('banner').innerHTML = '<div class="banner">Node unreachable</div>';
+  }
+}
+refresh();
+setInterval(refresh, 2000);
+</script>
+</body>
+</html>
+"#;
+
+/// An event as shown on the page.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+struct EventView {
+    timestamp: String,
+    worker: String,
+    event_type: String,
+    msg: String,
+}
+
+/// Task counters since the node started.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+struct TaskCounts {
+    proofs_completed: u64,
+    proofs_submitted: u64,
+    errors: u64,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+struct ProxyStatus {
+    enabled: bool,
+    count: usize,
+}
+
+/// The snapshot served at `/api/state`.
+#[derive(Serialize, Debug, Clone)]
+struct Snapshot {
+    node_id: Option<u64>,
+    environment: String,
+    version: String,
+    update_notice: Option<String>,
+    maintenance: Option<String>,
+    uptime_secs: u64,
+    total_cores: usize,
+    total_ram_gb: f64,
+    /// The orchestrator API does not expose points, so this is always empty (as in the TUI).
+    nex_points: Option<u64>,
+    tasks: TaskCounts,
+    proxies: ProxyStatus,
+    /// Counts per node ID, for every node that fetched or submitted anything.
+    nodes: BTreeMap<u64, NodeStats>,
+    /// Latest event per worker.
+    workers: BTreeMap<String, EventView>,
+    events: VecDeque<EventView>,
+}
+
+/// A node in the export, with the wallet it proves for.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+struct NodeExport {
+    node_id: u64,
+    wallet: Option<String>,
+    wallet_address: Option<String>,
+}
+
+/// Throughput figures in the export.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+struct Performance {
+    #[serde(flatten)]
+    tasks: TaskCounts,
+    proofs_submitted_last_hour: usize,
+    /// Average since the node started.
+    proofs_submitted_per_hour: f64,
+}
+
+/// The summary served at `/api/export`.
+#[derive(Serialize, Debug, Clone)]
+struct Export {
+    generated_at: String,
+    version: String,
+    environment: String,
+    uptime_secs: u64,
+    nodes: Vec<NodeExport>,
+    performance: Performance,
+    maintenance: Option<String>,
+    update_notice: Option<String>,
+    /// Recent warnings and errors, oldest first.
+    alerts: VecDeque<EventView>,
+}
+
+/// Node state accumulated from the event bus.
+#[derive(Debug)]
+struct WebState {
+    node_id: Option<u64>,
+    environment: Environment,
+    start_time: Instant,
+    update_notice: Option<String>,
+    maintenance: Option<String>,
+    tasks: TaskCounts,
+    workers: BTreeMap<String, EventView>,
+    events: VecDeque<EventView>,
+    /// Every node this process runs; `node_id` is the first.
+    node_ids: Vec<u64>,
+    alerts: VecDeque<EventView>,
+    /// When proofs were submitted within the recent window.
+    recent_submissions: VecDeque<Instant>,
+    /// The proxies the node's requests go through.
+    proxies: Arc<ProxyContext>,
+    /// The task states served as history when this process does not track tasks itself.
+    task_states_path: PathBuf,
+}
+
+impl WebState {
+    fn new(node_id: Option<u64>, environment: Environment) -> Self {
+        Self {
+            node_id,
+            environment,
+            start_time: Instant::now(),
+            update_notice: None,
+            maintenance: None,
+            tasks: TaskCounts::default(),
+            workers: BTreeMap::new(),
+            events: VecDeque::new(),
+            node_ids: node_id.into_iter().collect(),
+            alerts: VecDeque::new(),
+            recent_submissions: VecDeque::new(),
+            proxies: Arc::default(),
+            task_states_path: crate::config::get_config_path()
+                .map(|path| crate::task_lifecycle::states_path(&path))
+                .unwrap_or_default(),
+        }
+    }
+
+    fn observe(&mut self, event: &Event) {
+        if !event.should_display() {
+            return;
+        }
+        let view = EventView {
+            timestamp: event.timestamp.clone(),
+            worker: worker_label(&event.worker),
+            event_type: event.event_type.to_string(),
+            msg: redact(&event.msg),
+        };
+
+        match (event.worker, event.event_type) {
+            (Worker::Prover(_), EventType::Success) => self.tasks.proofs_completed += 1,
+            (Worker::ProofSubmitter, EventType::Success) => {
+                self.tasks.proofs_submitted += 1;
+                self.recent_submissions.push_back(Instant::now());
+            }
+            (_, EventType::Error) => self.tasks.errors += 1,
+            _ => {}
+        }
+        match event.worker {
+            // The version checker only reports when an update or requirement applies
+            Worker::VersionChecker => self.update_notice = Some(view.msg.clone()),
+            // A window is announced at warning level and ends with an informational event
+            Worker::Maintenance if event.log_level >= LogLevel::Info => {
+                self.maintenance = (event.log_level >= LogLevel::Warn).then(|| view.msg.clone());
+            }
+            _ => {}
+        }
+
+        if event.log_level >= LogLevel::Warn {
+            if self.alerts.len() >= MAX_ALERTS {
+                self.alerts.pop_front();
+            }
+            self.alerts.push_back(view.clone());
+        }
+        self.workers.insert(view.worker.clone(), view.clone());
+        if self.events.len() >= MAX_EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back(view);
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let proxies = ProxyStatus {
+            enabled: self.proxies.should_use(),
+            count: self
+                .proxies
+                .manager()
+                .lock()
+                .map(|manager| manager.proxy_count())
+                .unwrap_or_default(),
+        };
+        Snapshot {
+            node_id: self.node_id,
+            environment: self.environment.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            update_notice: self.update_notice.clone(),
+            maintenance: self.maintenance.clone(),
+            uptime_secs: self.start_time.elapsed().as_secs(),
+            total_cores: system::num_cores(),
+            total_ram_gb: system::total_memory_gb(),
+            nex_points: None,
+            tasks: self.tasks.clone(),
+            proxies,
+            nodes: crate::nodes::stats(),
+            workers: self.workers.clone(),
+            events: self.events.clone(),
+        }
+    }
+
+    /// The export summary; `wallets` maps nodes to the wallets they prove for.
+    fn export(&mut self, wallets: &[WalletSummary]) -> Export {
+        while let Some(oldest) = self.recent_submissions.front() {
+            if oldest.elapsed() <= RECENT_WINDOW {
+                break;
+            }
+            self.recent_submissions.pop_front();
+        }
+        let uptime = self.start_time.elapsed();
+        let nodes = self
+            .node_ids
+            .iter()
+            .map(|&node_id| {
+                let wallet = wallets
+                    .iter()
+                    .find(|wallet| wallet.node_ids.contains(&node_id));
+                NodeExport {
+                    node_id,
+                    wallet: wallet.map(|wallet| wallet.name.clone()),
+                    wallet_address: wallet.map(|wallet| wallet.wallet_address.clone()),
+                }
+            })
+            .collect();
+        Export {
+            generated_at: chrono::Utc::now().to_rfc3339(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            environment: self.environment.to_string(),
+            uptime_secs: uptime.as_secs(),
+            nodes,
+            performance: Performance {
+                tasks: self.tasks.clone(),
+                proofs_submitted_last_hour: self.recent_submissions.len(),
+                proofs_submitted_per_hour: self.tasks.proofs_submitted as f64
+                    / (uptime.as_secs_f64() / 3600.0).max(1.0 / 60.0),
+            },
+            maintenance: self.maintenance.clone(),
+            update_notice: self.update_notice.clone(),
+            alerts: self.alerts.clone(),
+        }
+    }
+}
+
+/// Whether the request carries the export token. Compared in constant time.
+fn authorized(request: &str, token: &str) -> bool {
+    let presented = request
+        .lines()
+        .skip(1)
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("authorization"))
+        .and_then(|(_, value)| value.trim().strip_prefix("Bearer "))
+        .unwrap_or("");
+    crate::control::token_matches(presented, token)
+}
+
+/// Wallets from the config file, for the export's node-to-wallet mapping.
+fn configured_wallets() -> Vec<WalletSummary> {
+    crate::config::get_config_path()
+        .and_then(|path| crate::config::Config::load_from_file(&path))
+        .map(|config| crate::wallets::summaries(&config))
+        .unwrap_or_default()
+}
+
+fn worker_label(worker: &Worker) -> String {
+    match worker {
+        Worker::TaskFetcher => "Task Fetcher".to_string(),
+        Worker::Prover(id) => format!("Prover {}", id),
+        Worker::ProofSubmitter => "Proof Submitter".to_string(),
+        Worker::VersionChecker => "Version Checker".to_string(),
+        Worker::Maintenance => "Maintenance".to_string(),
+        Worker::Scheduler => "Scheduler".to_string(),
+    }
+}
+
+/// Serves the web dashboard on `addr`, forwarding events unchanged to the returned receiver.
+///
+/// The server itself is detached and runs until the process exits.
+///
+/// # Errors
+/// Returns an `std::io::Error` if the address cannot be bound.
+pub async fn serve(
+    mut event_receiver: mpsc::Receiver<Event>,
+    addr: SocketAddr,
+    node_ids: &[u64],
+    environment: Environment,
+    proxies: Arc<ProxyContext>,
+) -> std::io::Result<(mpsc::Receiver<Event>, JoinHandle<()>)> {
+    let listener = TcpListener::bind(addr).await?;
+    let mut state = WebState::new(node_ids.first().copied(), environment);
+    state.node_ids = node_ids.to_vec();
+    state.proxies = proxies;
+    let state = Arc::new(Mutex::new(state));
+
+    let (sender, receiver) = mpsc::channel::<Event>(crate::consts::prover::EVENT_QUEUE_SIZE);
+    let forwarder = {
+        let state = state.clone();
+        tokio::spawn(async move {
+            while let Some(event) = event_receiver.recv().await {
+                if let Ok(mut state) = state.lock() {
+                    state.observe(&event);
+                }
+                if sender.send(event).await.is_err() {
+                    break;
+                }
+            }
+        })
+    };
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let state = state.clone();
+            tokio::spawn(async move {
+                let _ = handle_connection(stream, &state).await;
+            });
+        }
+    });
+
+    Ok((receiver, forwarder))
+}
+
+/// Answers a single request and closes the connection.
+async fn handle_connection(mut stream: TcpStream, state: &Mutex<WebState>) -> std::io::Result<()> {
+    let mut buf = [0u8; 2048];
+    let n = stream.read(&mut buf).await?;
+    let request = String::from_utf8_lossy(&buf[..n]);
+    let (status, content_type, body) = route(&request, state);
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Maps a raw request to a status line, content type and body.
+fn route(request: &str, state: &Mutex<WebState>) -> (&'static str, &'static str, String) {
+    let mut parts = request.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    if method != "GET" {
+        return (
+            "405 Method Not Allowed",
+            "text/plain",
+            "Method not allowed".to_string(),
+        );
+    }
+    let (path, query) = path.split_once('?').unwrap_or((path, ""));
+    match path {
+        "/" | "/index.html" => ("200 OK", "text/html; charset=utf-8", INDEX_HTML.to_string()),
+        "/api/state" => {
+            let snapshot = state.lock().map(|state| state.snapshot());
+            match snapshot.ok().and_then(|s| serde_json::to_string(&s).ok()) {
+                Some(json) => ("200 OK", "application/json", json),
+                None => (
+                    "500 Internal Server Error",
+                    "text/plain",
+                    "State unavailable".to_string(),
+                ),
+            }
+        }
+        "/api/export" => match EXPORT_TOKEN.get() {
+            None => ("404 Not Found", "text/plain", "Export disabled".to_string()),
+            Some(token) if !authorized(request, token) => (
+                "401 Unauthorized",
+                "text/plain",
+                "Missing or invalid export token".to_string(),
+            ),
+            Some(_) => {
+                let wallets = configured_wallets();
+                let export = state.lock().map(|mut state| state.export(&wallets));
+                match export.ok().and_then(|e| serde_json::to_string(&e).ok()) {
+                    Some(json) => ("200 OK", "application/json", json),
+                    None => (
+                        "500 Internal Server Error",
+                        "text/plain",
+                        "State unavailable".to_string(),
+                    ),
+                }
+            }
+        },
+        "/api/history" | "/api/history/summary" => match HistoryQuery::parse(query) {
+            Err(e) => ("400 Bad Request", "text/plain", e),
+            Ok(query) => {
+                let states_path = match state.lock() {
+                    Ok(state) => state.task_states_path.clone(),
+                    Err(_) => PathBuf::new(),
+                };
+                let states = crate::task_lifecycle::current(&states_path);
+                let json = if path == "/api/history" {
+                    serde_json::to_string(&history::page(&states, &query))
+                } else {
+                    serde_json::to_string(&history::summary(&states, &query))
+                };
+                match json {
+                    Ok(json) => ("200 OK", "application/json", json),
+                    Err(_) => (
+                        "500 Internal Server Error",
+                        "text/plain",
+                        "History unavailable".to_string(),
+                    ),
+                }
+            }
+        },
+        _ => ("404 Not Found", "text/plain", "Not found".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Events should update the counters, per-worker status and log, with credentials redacted.
+    fn test_state_tracks_events() {
+        let mut state = WebState::new(Some(7), Environment::Production);
+        state.observe(&Event::prover(
+            0,
+            "Proof done".to_string(),
+            EventType::Success,
+        ));
+        state.observe(&Event::proof_submitter(
+            "Submitted via http://u:p@proxy:8080".to_string(),
+            EventType::Success,
+        ));
+        state.observe(&Event::task_fetcher_with_level(
+            "Fetch failed".to_string(),
+            EventType::Error,
+            LogLevel::Error,
+        ));
+
+        let snapshot = state.snapshot();
+        assert_eq!(
+            snapshot.tasks,
+            TaskCounts {
+                proofs_completed: 1,
+                proofs_submitted: 1,
+                errors: 1,
+            }
+        );
+        assert_eq!(snapshot.workers.len(), 3);
+        assert_eq!(
+            snapshot.workers["Proof Submitter"].msg,
+            "Submitted via http://***@proxy:8080"
+        );
+        assert_eq!(snapshot.events.len(), 3);
+    }
+
+    #[test]
+    // The export should map nodes to wallets, count recent submissions and collect alerts.
+    fn test_export_summary() {
+        let mut state = WebState::new(Some(7), Environment::Production);
+        state.node_ids = vec![7, 8];
+        state.observe(&Event::proof_submitter(
+            "Submitted".to_string(),
+            EventType::Success,
+        ));
+        state.observe(&Event::task_fetcher_with_level(
+            "Rate limited".to_string(),
+            EventType::Error,
+            LogLevel::Warn,
+        ));
+        let wallets = [WalletSummary {
+            name: "alice".to_string(),
+            wallet_address: "0xabc".to_string(),
+            user_id: "user".to_string(),
+            node_ids: vec![8],
+        }];
+
+        let export = state.export(&wallets);
+        assert_eq!(export.nodes.len(), 2);
+        assert_eq!(export.nodes[0].wallet, None);
+        assert_eq!(export.nodes[1].wallet.as_deref(), Some("alice"));
+        assert_eq!(export.performance.proofs_submitted_last_hour, 1);
+        assert_eq!(export.alerts.len(), 1);
+        assert_eq!(export.alerts[0].msg, "Rate limited");
+    }
+
+    #[test]
+    // Only a request presenting the exact token should be authorized.
+    fn test_export_authorization() {
+        let request = "GET /api/export HTTP/1.1\r\nHost: x\r\nauthorization: Bearer s3cret\r\n\r\n";
+        assert!(authorized(request, "s3cret"));
+        assert!(!authorized(request, "s3cre"));
+        assert!(!authorized("GET /api/export HTTP/1.1\r\n\r\n", "s3cret"));
+    }
+
+    #[test]
+    // Only GET requests for known paths should succeed.
+    fn test_routes() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = WebState::new(None, Environment::Production);
+        state.task_states_path = dir.path().join("task_states.json");
+        let state = Mutex::new(state);
+        assert_eq!(route("GET / HTTP/1.1\r\n", &state).0, "200 OK");
+        let (status, content_type, body) = route("GET /api/state HTTP/1.1\r\n", &state);
+        assert_eq!((status, content_type), ("200 OK", "application/json"));
+        assert!(body.contains(r#""node_id":null"#));
+        assert_eq!(
+            route("GET /missing HTTP/1.1\r\n", &state).0,
+            "404 Not Found"
+        );
+        let (status, _, body) = route("GET /api/history?limit=5 HTTP/1.1\r\n", &state);
+        assert_eq!(status, "200 OK");
+        assert!(body.contains(r#""limit":5"#));
+        assert!(body.contains(r#""total":0"#));
+        assert!(body.contains(r#""tasks":[]"#));
+        let (status, _, body) = route("GET /api/history/summary HTTP/1.1\r\n", &state);
+        assert_eq!(status, "200 OK");
+        assert!(body.contains(r#""by_state":{}"#));
+        assert!(!body.contains(r#""tasks""#));
+        assert_eq!(
+            route("GET /api/history/summary?state=done HTTP/1.1\r\n", &state).0,
+            "400 Bad Request"
+        );
+        assert_eq!(
+            route("POST / HTTP/1.1\r\n", &state).0,
+            "405 Method Not Allowed"
+        );
+    }
+}
@@ -0,0 +1,492 @@
+//! Shared on-disk task queue
+//!
+//! An alternative to the TCP link between `--role fetcher` and `--role prover` processes for
+//! machines that share a directory (NFS or another shared volume) rather than a host. The
+//! fetcher writes each task to the queue directory; provers anywhere that can see it claim
+//! tasks, prove them, and write the proofs back for the fetcher to submit.
+//!
+//! The queue directory holds:
+//! - `pending/<task>.task`: tasks waiting for a prover
+//! - `claimed/<prover>/<task>.task`: tasks being proved; a prover claims a task by renaming
+//!   it into its own directory, a single atomic step that succeeds for exactly one prover (the
+//!   others find the task gone), and refreshes the file's modification time while it works
+//! - `done/<task>.proof` and `failed/<task>.task` (holding the task ID): outcomes for the
+//!   fetcher to collect
+//! - `tmp/`: files being written, renamed into place once complete so that no reader sees a
+//!   partial file
+//!
+//! A claim whose modification time is older than [`CLAIM_LEASE`] belongs to a prover that
+//! stopped, and the fetcher moves the task back to `pending/`.
+
+use crate::backpressure::ProverSlots;
+use crate::control::control_state;
+use crate::environment::Environment;
+use crate::error_budget::ErrorBudget;
+use crate::error_classifier::{ErrorClassifier, LogLevel};
+use crate::events::{Event, EventType, Worker};
+use crate::performance::PerformanceTracker;
+use crate::task::Task;
+use crate::workers::ipc::WireTask;
+use crate::workers::offline::prove_task;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+
+/// How long a claim stays valid without being refreshed.
+pub const CLAIM_LEASE: Duration = Duration::from_secs(300);
+
+/// How often provers refresh their claims.
+const CLAIM_REFRESH: Duration = Duration::from_secs(60);
+
+/// How often the queue directory is scanned.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Tasks the fetcher keeps waiting in `pending/`, so that fetching stays just ahead of proving.
+const MAX_PENDING: usize = 8;
+
+const PENDING: &str = "pending";
+const CLAIMED: &str = "claimed";
+const DONE: &str = "done";
+const FAILED: &str = "failed";
+const TMP: &str = "tmp";
+
+/// A finished proof, as written to `done/`.
+#[derive(Serialize, Deserialize)]
+struct ProofFile {
+    task: WireTask,
+    proof: Vec<u8>,
+}
+
+/// The queue directory and its subdirectories.
+#[derive(Debug, Clone)]
+struct QueueDir {
+    root: PathBuf,
+}
+
+impl QueueDir {
+    fn open(root: &Path) -> io::Result<Self> {
+        for sub in [PENDING, CLAIMED, DONE, FAILED, TMP] {
+            fs::create_dir_all(root.join(sub))?;
+        }
+        Ok(Self {
+            root: root.to_path_buf(),
+        })
+    }
+
+    fn path(&self, sub: &str, file_name: &str) -> PathBuf {
+        self.root.join(sub).join(file_name)
+    }
+
+    /// Writes a file into `sub` atomically, via `tmp/`.
+    fn write(&self, sub: &str, file_name: &str, bytes: &[u8]) -> io::Result<()> {
+        let tmp = self.path(
+            TMP,
+            &format!(
+                "{}.{}.{}",
+                file_name,
+                std::process::id(),
+                rand::random::<u32>()
+            ),
+        );
+        fs::write(&tmp, bytes)?;
+        fs::rename(&tmp, self.path(sub, file_name))
+    }
+
+    /// File names in `sub`, in no particular order.
+    fn list(&self, sub: &str) -> Vec<String> {
+        fs::read_dir(self.root.join(sub))
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .filter_map(|entry| entry.file_name().into_string().ok())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Where `claimant` keeps the task in `file_name` while proving it.
+    fn claim_path(&self, claimant: &str, file_name: &str) -> PathBuf {
+        self.root.join(CLAIMED).join(claimant).join(file_name)
+    }
+
+    /// Every claim of every prover.
+    fn claims(&self) -> Vec<PathBuf> {
+        self.list(CLAIMED)
+            .into_iter()
+            .flat_map(|claimant| {
+                let dir = self.root.join(CLAIMED).join(claimant);
+                fs::read_dir(dir)
+                    .map(|entries| {
+                        entries
+                            .filter_map(|entry| entry.ok())
+                            .map(|entry| entry.path())
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    /// Claims a pending task for `claimant`. Returns `None` if another prover claimed it first.
+    ///
+    /// # Errors
+    /// Returns an `std::io::Error` if the queue directory cannot be written.
+    fn claim(&self, claimant: &str, file_name: &str) -> io::Result<Option<Task>> {
+        let claimed = self.claim_path(claimant, file_name);
+        if let Some(dir) = claimed.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        match fs::rename(self.path(PENDING, file_name), &claimed) {
+            Ok(()) => {}
+            // Another prover renamed it first
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        }
+        // Start the lease from the moment of claiming, not from when the task was queued
+        let _ = touch(&claimed);
+        let bytes = fs::read(&claimed)?;
+        match postcard::from_bytes::<WireTask>(&bytes) {
+            Ok(task) => Ok(Some(task.into())),
+            Err(_) => {
+                let _ = fs::remove_file(&claimed);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Moves claims whose lease expired back to `pending/`, returning how many were moved.
+    /// Directories of provers holding no claim are removed.
+    fn requeue_expired(&self) -> usize {
+        let mut requeued = 0;
+        for claimed in self.claims() {
+            let expired = fs::metadata(&claimed)
+                .and_then(|metadata| metadata.modified())
+                .map(|modified| is_expired(modified, SystemTime::now()))
+                .unwrap_or(false);
+            let Some(file_name) = claimed.file_name() else {
+                continue;
+            };
+            if expired && fs::rename(&claimed, self.root.join(PENDING).join(file_name)).is_ok() {
+                requeued += 1;
+            }
+        }
+        for claimant in self.list(CLAIMED) {
+            // Fails, as it should, for a directory that still holds a claim
+            let _ = fs::remove_dir(self.root.join(CLAIMED).join(claimant));
+        }
+        requeued
+    }
+}
+
+fn task_file_name(task_id: &str) -> String {
+    // Task IDs come from the orchestrator; keep only safe characters in file names.
+    let safe_id: String = task_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}.task", safe_id)
+}
+
+fn touch(path: &Path) -> io::Result<()> {
+    fs::File::options()
+        .write(true)
+        .open(path)?
+        .set_modified(SystemTime::now())
+}
+
+fn is_expired(modified: SystemTime, now: SystemTime) -> bool {
+    now.duration_since(modified)
+        .is_ok_and(|age| age > CLAIM_LEASE)
+}
+
+/// Fetcher side: publishes tasks from the queue to the directory and forwards proofs written
+/// back by provers to the submitter.
+///
+/// # Errors
+/// Returns an `std::io::Error` if the queue directory cannot be created.
+pub fn serve_queue(
+    dir: &Path,
+    mut task_receiver: mpsc::Receiver<Task>,
+    results_sender: mpsc::Sender<(Task, Vec<u8>)>,
+    event_sender: mpsc::Sender<Event>,
+    error_budget: Arc<ErrorBudget>,
+    mut shutdown: broadcast::Receiver<()>,
+) -> io::Result<JoinHandle<()>> {
+    let queue = QueueDir::open(dir)?;
+    Ok(tokio::spawn(async move {
+        let _ = event_sender
+            .send(Event::task_fetcher_with_level(
+                format!("Sharing tasks with provers via {}", queue.root.display()),
+                EventType::Refresh,
+                LogLevel::Info,
+            ))
+            .await;
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        // Provers sharing the directory announce nothing, so those proving a claimed task are
+        // what counts in the fetchers' capacity
+        let mut slots = ProverSlots::default();
+        loop {
+            let pending = queue.list(PENDING).len();
+            slots.set(queue.claims().len());
+            tokio::select! {
+                _ = shutdown.recv() => break,
+                task = task_receiver.recv(), if pending < MAX_PENDING => {
+                    let Some(task) = task else {
+                        break;
+                    };
+                    let written = postcard::to_allocvec(&WireTask::from(&task))
+                        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+                        .and_then(|bytes| queue.write(PENDING, &task_file_name(&task.task_id), &bytes));
+                    if let Err(e) = written {
+                        let _ = event_sender
+                            .send(Event::task_fetcher_with_level(
+                                format!("Cannot queue task {} in {}: {}", task.task_id, queue.root.display(), e),
+                                EventType::Error,
+                                LogLevel::Error,
+                            ))
+                            .await;
+                        control_state().task_finished();
+                    }
+                }
+                _ = interval.tick() => {
+                    collect_outcomes(&queue, &results_sender, &event_sender, &error_budget).await;
+                }
+            }
+        }
+    }))
+}
+
+/// Forwards finished proofs, accounts for failures and requeues abandoned claims.
+async fn collect_outcomes(
+    queue: &QueueDir,
+    results_sender: &mpsc::Sender<(Task, Vec<u8>)>,
+    event_sender: &mpsc::Sender<Event>,
+    error_budget: &ErrorBudget,
+) {
+    for file_name in queue.list(DONE) {
+        let path = queue.path(DONE, &file_name);
+        let parsed = fs::read(&path).ok().and_then(|bytes| {
+            let file: ProofFile = postcard::from_bytes(&bytes).ok()?;
+            Some((Task::from(file.task), file.proof))
+        });
+        let _ = fs::remove_file(&path);
+        match parsed {
+            Some(result) => {
+                let _ = results_sender.send(result).await;
+            }
+            None => {
+                let _ = event_sender
+                    .send(Event::task_fetcher_with_level(
+                        format!("Discarding unreadable proof file {}", file_name),
+                        EventType::Error,
+                        LogLevel::Warn,
+                    ))
+                    .await;
+                control_state().task_finished();
+            }
+        }
+    }
+
+    for file_name in queue.list(FAILED) {
+        let path = queue.path(FAILED, &file_name);
+        if let Ok(task_id) = fs::read_to_string(&path) {
+            crate::checkpoint::untrack(&task_id);
+        }
+        let _ = fs::remove_file(path);
+        control_state().task_finished();
+        error_budget
+            .report(false, Worker::TaskFetcher, event_sender)
+            .await;
+    }
+
+    let requeued = queue.requeue_expired();
+    if requeued > 0 {
+        let _ = event_sender
+            .send(Event::task_fetcher_with_level(
+                format!(
+                    "Requeued {} task(s) whose prover stopped refreshing its claim",
+                    requeued
+                ),
+                EventType::Refresh,
+                LogLevel::Warn,
+            ))
+            .await;
+    }
+}
+
+/// Prover side: runs `num_workers` provers that claim tasks from the queue directory.
+///
+/// # Errors
+/// Returns an `std::io::Error` if the queue directory cannot be created.
+pub fn start_queue_consumer(
+    dir: &Path,
+    num_workers: usize,
+    shutdown: broadcast::Receiver<()>,
+    environment: Environment,
+    client_id: String,
+) -> io::Result<(mpsc::Receiver<Event>, Vec<JoinHandle<()>>)> {
+    let queue = QueueDir::open(dir)?;
+    let (event_sender, event_receiver) =
+        mpsc::channel::<Event>(crate::consts::prover::EVENT_QUEUE_SIZE);
+    let mut join_handles = Vec::new();
+
+    // Budget decisions are made by the fetcher, which sees every failure
+    let error_budget = Arc::new(ErrorBudget::new(None));
+    let performance = Arc::new(Mutex::new(PerformanceTracker::new()));
+    // Unique to this process, so its claims never collide with another prover's
+    let claimant = format!("{}-{:08x}", std::process::id(), rand::random::<u32>());
+    for worker_id in 0..num_workers {
+        let queue = queue.clone();
+        let event_sender = event_sender.clone();
+        let mut shutdown = shutdown.resubscribe();
+        let environment = environment.clone();
+        let client_id = client_id.clone();
+        let performance = performance.clone();
+        let error_budget = error_budget.clone();
+        let claimant = claimant.clone();
+        join_handles.push(tokio::spawn(async move {
+            let error_classifier = ErrorClassifier::new();
+            loop {
+                let mut claimed = None;
+                for file_name in queue.list(PENDING) {
+                    match queue.claim(&claimant, &file_name) {
+                        Ok(Some(task)) => {
+                            claimed = Some((file_name, task));
+                            break;
+                        }
+                        Ok(None) => {}
+                        Err(e) => {
+                            let _ = event_sender
+                                .send(Event::prover_with_level(
+                                    worker_id,
+                                    format!(
+                                        "Cannot claim {} in {}: {}",
+                                        file_name,
+                                        queue.root.display(),
+                                        e
+                                    ),
+                                    EventType::Error,
+                                    LogLevel::Warn,
+                                ))
+                                .await;
+                            break;
+                        }
+                    }
+                }
+                let Some((file_name, task)) = claimed else {
+                    tokio::select! {
+                        _ = shutdown.recv() => break,
+                        _ = tokio::time::sleep(POLL_INTERVAL) => continue,
+                    }
+                };
+                let claim = queue.claim_path(&claimant, &file_name);
+
+                let proving = prove_task(
+                    worker_id,
+                    &task,
+                    None,
+                    &environment,
+                    &client_id,
+                    &error_classifier,
+                    &performance,
+                    &error_budget,
+                    &event_sender,
+                );
+                tokio::pin!(proving);
+                let mut refresh = tokio::time::interval(CLAIM_REFRESH);
+                let proof = loop {
+                    tokio::select! {
+                        proof = &mut proving => break proof,
+                        _ = refresh.tick() => {
+                            let _ = touch(&claim);
+                        }
+                    }
+                };
+
+                let written = match proof {
+                    Some(proof) => postcard::to_allocvec(&ProofFile {
+                        task: WireTask::from(&task),
+                        proof,
+                    })
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+                    .and_then(|bytes| {
+                        queue.write(DONE, &file_name.replace(".task", ".proof"), &bytes)
+                    }),
+                    None => queue.write(FAILED, &file_name, task.task_id.as_bytes()),
+                };
+                if let Err(e) = written {
+                    let _ = event_sender
+                        .send(Event::prover_with_level(
+                            worker_id,
+                            format!("Cannot write the result of task {}: {}", task.task_id, e),
+                            EventType::Error,
+                            LogLevel::Error,
+                        ))
+                        .await;
+                }
+                let _ = fs::remove_file(&claim);
+            }
+            let _ = event_sender
+                .send(Event::prover(
+                    worker_id,
+                    format!("Worker {} stopped", worker_id),
+                    EventType::Shutdown,
+                ))
+                .await;
+        }));
+    }
+
+    Ok((event_receiver, join_handles))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    // Only one claimant should get a task, and expired claims should return to pending.
+    fn test_claim_and_requeue() {
+        let dir = tempdir().unwrap();
+        let queue = QueueDir::open(dir.path()).unwrap();
+        let mut task = Task::new("task/1".to_string(), "fast-fib".to_string(), vec![1]);
+        task.node_id = Some(7);
+        let file_name = task_file_name(&task.task_id);
+        assert_eq!(file_name, "task_1.task");
+        let bytes = postcard::to_allocvec(&WireTask::from(&task)).unwrap();
+        queue.write(PENDING, &file_name, &bytes).unwrap();
+
+        let claimed = queue.claim("a", &file_name).unwrap().unwrap();
+        assert_eq!(claimed.task_id, "task/1");
+        assert_eq!(claimed.node_id, Some(7));
+        // The task is gone from pending/, so the other prover lost the race
+        assert!(queue.claim("b", &file_name).unwrap().is_none());
+        assert_eq!(queue.claims(), vec![queue.claim_path("a", &file_name)]);
+        assert!(queue.list(TMP).is_empty());
+
+        // A fresh claim stays put
+        assert_eq!(queue.requeue_expired(), 0);
+        let stale = SystemTime::now() - CLAIM_LEASE - Duration::from_secs(1);
+        fs::File::options()
+            .write(true)
+            .open(queue.claim_path("a", &file_name))
+            .unwrap()
+            .set_modified(stale)
+            .unwrap();
+        assert_eq!(queue.requeue_expired(), 1);
+        assert_eq!(queue.list(PENDING), vec![file_name]);
+        // Directories of provers without claims are cleaned up
+        assert!(queue.list(CLAIMED).is_empty());
+    }
+}
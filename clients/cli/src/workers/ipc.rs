@@ -0,0 +1,574 @@
+//! Fetcher/prover role separation
+//!
+//! In a split deployment one `--role fetcher` process handles all orchestrator (and proxy)
+//! traffic, and one or more `--role prover` processes on the same host do the proving. The
+//! fetcher listens on a loopback TCP address; provers connect, ask for a task whenever one of
+//! their workers is idle, and send back the proof (or a failure). Only the fetcher needs
+//! network access, so the two can be scaled and firewalled independently.
+//!
+//! Connections are authenticated with a token that the fetcher writes next to the config
+//! file, so only processes of the same user can pull tasks or submit proofs. Messages are
+//! postcard-encoded and prefixed with their length.
+
+use crate::backpressure::ProverSlots;
+use crate::control::{control_state, create_token, token_matches};
+use crate::environment::Environment;
+use crate::error_budget::ErrorBudget;
+use crate::error_classifier::{ErrorClassifier, LogLevel};
+use crate::events::{Event, EventType, Worker};
+use crate::nexus_orchestrator::TaskType;
+use crate::performance::PerformanceTracker;
+use crate::task::Task;
+use crate::task_lifecycle::{self, TaskState};
+use crate::workers::offline::prove_task;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+
+/// Default address the fetcher listens on for prover processes.
+pub const DEFAULT_IPC_ADDR: &str = "127.0.0.1:3031";
+
+/// Largest accepted message, to bound memory use on malformed input.
+const MAX_FRAME_SIZE: usize = 256 * 1024 * 1024;
+
+/// Largest accepted hello. It is read before the token is checked, so anything that can reach
+/// the fetcher's port could otherwise make it allocate `MAX_FRAME_SIZE` per connection.
+const MAX_HELLO_SIZE: usize = 512;
+
+/// Which part of the node this process runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum Role {
+    /// Fetch, prove and submit in one process.
+    #[default]
+    All,
+    /// Fetch tasks and submit proofs; proving is done by connected prover processes.
+    Fetcher,
+    /// Prove tasks handed out by a fetcher process; no orchestrator access needed.
+    Prover,
+}
+
+/// How a fetcher hands tasks to separate prover processes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProverLink {
+    /// Provers connect over TCP to this address.
+    Tcp(SocketAddr),
+    /// Provers claim tasks from this shared directory (see `file_queue`).
+    Queue(PathBuf),
+}
+
+/// A task as sent between processes.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub(crate) struct WireTask {
+    task_id: String,
+    program_id: String,
+    public_inputs: Vec<u8>,
+    task_type: Option<i32>,
+    node_id: Option<u64>,
+}
+
+impl From<&Task> for WireTask {
+    fn from(task: &Task) -> Self {
+        Self {
+            task_id: task.task_id.clone(),
+            program_id: task.program_id.clone(),
+            public_inputs: task.public_inputs.clone(),
+            task_type: task.task_type.map(|t| t as i32),
+            node_id: task.node_id,
+        }
+    }
+}
+
+impl From<WireTask> for Task {
+    fn from(task: WireTask) -> Self {
+        Task {
+            task_id: task.task_id,
+            program_id: task.program_id,
+            public_inputs: task.public_inputs,
+            task_type: task.task_type.and_then(|t| TaskType::try_from(t).ok()),
+            node_id: task.node_id,
+            created_at: None,
+            proxy: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+enum Message {
+    /// Prover to fetcher, first message on a connection.
+    Hello {
+        token: String,
+        version: String,
+        workers: usize,
+    },
+    /// Prover to fetcher: a worker is idle and wants a task.
+    Ready,
+    /// Fetcher to prover.
+    Task(WireTask),
+    /// Prover to fetcher: a finished proof, postcard-encoded.
+    Proof { task: WireTask, proof: Vec<u8> },
+    /// Prover to fetcher: proving failed (details are reported by the prover process).
+    Failed { task_id: String },
+}
+
+async fn write_message(writer: &mut OwnedWriteHalf, message: &Message) -> std::io::Result<()> {
+    let bytes = postcard::to_allocvec(message)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    writer.write_u32(bytes.len() as u32).await?;
+    writer.write_all(&bytes).await
+}
+
+/// Reads a message of at most `limit` bytes.
+async fn read_message(reader: &mut OwnedReadHalf, limit: usize) -> std::io::Result<Message> {
+    let len = reader.read_u32().await? as usize;
+    if len > limit {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("message of {} bytes exceeds the limit", len),
+        ));
+    }
+    let mut bytes = vec![0u8; len];
+    reader.read_exact(&mut bytes).await?;
+    postcard::from_bytes(&bytes)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+/// Path of the token shared by the fetcher and its provers, next to the config file.
+pub fn get_token_path() -> Result<PathBuf, std::io::Error> {
+    Ok(crate::config::get_config_path()?.with_file_name("ipc.token"))
+}
+
+/// Hands tasks from the fetcher's queue to connected prover processes, and forwards their
+/// proofs to the submitter.
+///
+/// Tasks held by a prover that disconnects are put back on the queue via `requeue`.
+///
+/// # Errors
+/// Returns an `std::io::Error` if the address cannot be bound or the token cannot be written.
+pub async fn serve_provers(
+    addr: SocketAddr,
+    task_receiver: mpsc::Receiver<Task>,
+    requeue: mpsc::Sender<Task>,
+    results_sender: mpsc::Sender<(Task, Vec<u8>)>,
+    event_sender: mpsc::Sender<Event>,
+    error_budget: Arc<ErrorBudget>,
+    mut shutdown: broadcast::Receiver<()>,
+) -> std::io::Result<JoinHandle<()>> {
+    // Bound first, so a fetcher that cannot take the address leaves the running one's token
+    let listener = TcpListener::bind(addr).await?;
+    let token = create_token(&get_token_path()?)?;
+    let task_receiver = Arc::new(tokio::sync::Mutex::new(task_receiver));
+
+    let _ = event_sender
+        .send(Event::task_fetcher_with_level(
+            format!("Waiting for prover processes on {}", addr),
+            EventType::Refresh,
+            LogLevel::Info,
+        ))
+        .await;
+
+    Ok(tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = shutdown.recv() => break,
+                accepted = listener.accept() => {
+                    let Ok((stream, peer)) = accepted else {
+                        continue;
+                    };
+                    let connection = ProverConnection {
+                        token: token.clone(),
+                        task_receiver: task_receiver.clone(),
+                        requeue: requeue.clone(),
+                        results_sender: results_sender.clone(),
+                        event_sender: event_sender.clone(),
+                        error_budget: error_budget.clone(),
+                    };
+                    let shutdown = shutdown.resubscribe();
+                    tokio::spawn(async move {
+                        let message = match connection.run(stream, shutdown).await {
+                            Ok(()) => format!("Prover process {} disconnected", peer),
+                            Err(e) => format!("Prover process {} disconnected: {}", peer, e),
+                        };
+                        let _ = connection
+                            .event_sender
+                            .send(Event::task_fetcher_with_level(
+                                message,
+                                EventType::Refresh,
+                                LogLevel::Info,
+                            ))
+                            .await;
+                    });
+                }
+            }
+        }
+    }))
+}
+
+/// Fetcher side of a single prover connection.
+struct ProverConnection {
+    token: String,
+    task_receiver: Arc<tokio::sync::Mutex<mpsc::Receiver<Task>>>,
+    requeue: mpsc::Sender<Task>,
+    results_sender: mpsc::Sender<(Task, Vec<u8>)>,
+    event_sender: mpsc::Sender<Event>,
+    error_budget: Arc<ErrorBudget>,
+}
+
+impl ProverConnection {
+    async fn run(
+        &self,
+        stream: TcpStream,
+        mut shutdown: broadcast::Receiver<()>,
+    ) -> std::io::Result<()> {
+        let (mut reader, mut writer) = stream.into_split();
+        let Message::Hello {
+            token,
+            version,
+            workers,
+        } = read_message(&mut reader, MAX_HELLO_SIZE).await?
+        else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "expected a hello message",
+            ));
+        };
+        if !token_matches(&token, &self.token) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                "invalid token",
+            ));
+        }
+        let _ = self
+            .event_sender
+            .send(Event::task_fetcher_with_level(
+                format!(
+                    "Prover process connected ({} workers, version {})",
+                    workers, version
+                ),
+                EventType::Success,
+                LogLevel::Info,
+            ))
+            .await;
+        // Counted in the fetchers' capacity for as long as the prover stays connected
+        let _slots = ProverSlots::new(workers);
+
+        // Reads are not cancel-safe, so they run in their own task
+        let (message_sender, mut messages) = mpsc::channel::<std::io::Result<Message>>(8);
+        let reader_handle = tokio::spawn(async move {
+            loop {
+                let message = read_message(&mut reader, MAX_FRAME_SIZE).await;
+                let failed = message.is_err();
+                if message_sender.send(message).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        let mut requested = 0usize;
+        let mut in_flight: HashMap<String, Task> = HashMap::new();
+        let mut stopping = false;
+        let result = loop {
+            tokio::select! {
+                _ = shutdown.recv() => {
+                    stopping = true;
+                    break Ok(());
+                }
+                task = async { self.task_receiver.lock().await.recv().await }, if requested > 0 => {
+                    let Some(task) = task else {
+                        break Ok(()); // Task queue closed
+                    };
+                    requested -= 1;
+                    self.advance(&task.task_id, TaskState::Proving).await;
+                    if let Err(e) = write_message(&mut writer, &Message::Task(WireTask::from(&task))).await {
+                        in_flight.insert(task.task_id.clone(), task);
+                        break Err(e);
+                    }
+                    in_flight.insert(task.task_id.clone(), task);
+                }
+                message = messages.recv() => {
+                    match message {
+                        Some(Ok(Message::Ready)) => requested += 1,
+                        Some(Ok(Message::Proof { task, proof })) => {
+                            let task = in_flight.remove(&task.task_id).unwrap_or_else(|| task.into());
+                            self.advance(&task.task_id, TaskState::Proved).await;
+                            let _ = self.results_sender.send((task, proof)).await;
+                        }
+                        Some(Ok(Message::Failed { task_id })) => {
+                            in_flight.remove(&task_id);
+                            crate::checkpoint::untrack(&task_id);
+                            self.advance(&task_id, TaskState::Abandoned).await;
+                            control_state().task_finished();
+                            self.error_budget.report(false, Worker::TaskFetcher, &self.event_sender).await;
+                        }
+                        Some(Ok(_)) => {}
+                        Some(Err(e)) => break Err(e),
+                        None => break Ok(()),
+                    }
+                }
+            }
+        };
+        reader_handle.abort();
+
+        // Give unfinished tasks to another prover. The queue is usually full, so this waits for
+        // room; when the node stops, they stay tracked and are resumed on the next start.
+        for (_, task) in in_flight {
+            let task_id = task.task_id.clone();
+            self.advance(&task_id, TaskState::Fetched).await;
+            if stopping {
+                continue;
+            }
+            let requeued = tokio::select! {
+                sent = self.requeue.send(task) => sent.is_ok(),
+                _ = shutdown.recv() => {
+                    stopping = true;
+                    false
+                }
+            };
+            if !requeued && !stopping {
+                self.abandon(&task_id).await;
+            }
+        }
+        match result {
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(()),
+            other => other,
+        }
+    }
+
+    /// Gives up a task that could not be handed to another prover.
+    async fn abandon(&self, task_id: &str) {
+        crate::checkpoint::untrack(task_id);
+        self.advance(task_id, TaskState::Abandoned).await;
+        control_state().task_finished();
+        let _ = self
+            .event_sender
+            .send(Event::task_fetcher_with_level(
+                format!(
+                    "Dropped task {}: its prover disconnected and the task queue is closed",
+                    task_id
+                ),
+                EventType::Error,
+                LogLevel::Warn,
+            ))
+            .await;
+    }
+
+    /// Moves a task this connection handed out to `to`.
+    async fn advance(&self, task_id: &str, to: TaskState) {
+        task_lifecycle::advance(task_id, to, Worker::TaskFetcher, &self.event_sender).await;
+    }
+}
+
+/// Runs `num_workers` provers that take tasks from the fetcher at `addr`.
+///
+/// # Errors
+/// Returns an `std::io::Error` if the token cannot be read or the fetcher is unreachable.
+pub async fn start_prover_process(
+    addr: SocketAddr,
+    num_workers: usize,
+    shutdown: broadcast::Receiver<()>,
+    environment: Environment,
+    client_id: String,
+) -> std::io::Result<(mpsc::Receiver<Event>, Vec<JoinHandle<()>>)> {
+    let token_path = get_token_path()?;
+    let token = std::fs::read_to_string(&token_path).map_err(|e| {
+        std::io::Error::new(
+            e.kind(),
+            format!(
+                "cannot read {} (is the fetcher running?): {}",
+                token_path.display(),
+                e
+            ),
+        )
+    })?;
+    let (mut reader, mut writer) = TcpStream::connect(addr).await?.into_split();
+    write_message(
+        &mut writer,
+        &Message::Hello {
+            token: token.trim().to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            workers: num_workers,
+        },
+    )
+    .await?;
+
+    let (event_sender, event_receiver) =
+        mpsc::channel::<Event>(crate::consts::prover::EVENT_QUEUE_SIZE);
+    let writer = Arc::new(tokio::sync::Mutex::new(writer));
+    let mut join_handles = Vec::new();
+
+    // Tasks from the fetcher go to whichever worker is idle
+    let (task_sender, task_receiver) = mpsc::channel::<Task>(num_workers.max(1));
+    let task_receiver = Arc::new(tokio::sync::Mutex::new(task_receiver));
+    {
+        let event_sender = event_sender.clone();
+        let mut shutdown = shutdown.resubscribe();
+        join_handles.push(tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = shutdown.recv() => break,
+                    message = read_message(&mut reader, MAX_FRAME_SIZE) => match message {
+                        Ok(Message::Task(task)) => {
+                            if task_sender.send(task.into()).await.is_err() {
+                                break;
+                            }
+                        }
+                        Ok(_) => {}
+                        Err(e) => {
+                            let _ = event_sender
+                                .send(Event::task_fetcher_with_level(
+                                    format!("Lost connection to the fetcher process: {}", e),
+                                    EventType::Error,
+                                    LogLevel::Error,
+                                ))
+                                .await;
+                            break;
+                        }
+                    }
+                }
+            }
+        }));
+    }
+
+    // Budget decisions are made by the fetcher, which is told about every failure
+    let error_budget = Arc::new(ErrorBudget::new(None));
+    let performance = Arc::new(Mutex::new(PerformanceTracker::new()));
+    for worker_id in 0..num_workers {
+        let writer = writer.clone();
+        let task_receiver = task_receiver.clone();
+        let event_sender = event_sender.clone();
+        let mut shutdown = shutdown.resubscribe();
+        let environment = environment.clone();
+        let client_id = client_id.clone();
+        let performance = performance.clone();
+        let error_budget = error_budget.clone();
+        join_handles.push(tokio::spawn(async move {
+            let error_classifier = ErrorClassifier::new();
+            loop {
+                if write_message(&mut *writer.lock().await, &Message::Ready)
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+                let task = tokio::select! {
+                    _ = shutdown.recv() => break,
+                    task = async { task_receiver.lock().await.recv().await } => match task {
+                        Some(task) => task,
+                        None => break, // Connection closed
+                    },
+                };
+                let outcome = match prove_task(
+                    worker_id,
+                    &task,
+                    None,
+                    &environment,
+                    &client_id,
+                    &error_classifier,
+                    &performance,
+                    &error_budget,
+                    &event_sender,
+                )
+                .await
+                {
+                    Some(proof) => Message::Proof {
+                        task: WireTask::from(&task),
+                        proof,
+                    },
+                    None => Message::Failed {
+                        task_id: task.task_id.clone(),
+                    },
+                };
+                if write_message(&mut *writer.lock().await, &outcome)
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+            let _ = event_sender
+                .send(Event::prover(
+                    worker_id,
+                    format!("Worker {} stopped", worker_id),
+                    EventType::Shutdown,
+                ))
+                .await;
+        }));
+    }
+
+    Ok((event_receiver, join_handles))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Tasks should survive the trip between processes unchanged.
+    fn test_wire_task_round_trip() {
+        let mut task = Task::new("42".to_string(), "fast-fib".to_string(), vec![1, 2, 3]);
+        task.task_type = Some(TaskType::ProofHash);
+        task.node_id = Some(7);
+
+        let bytes = postcard::to_allocvec(&Message::Task(WireTask::from(&task))).unwrap();
+        let Message::Task(wire) = postcard::from_bytes(&bytes).unwrap() else {
+            panic!("expected a task message");
+        };
+        assert_eq!(Task::from(wire), task);
+    }
+
+    #[tokio::test]
+    // Messages should be framed so several can be read back from one stream.
+    async fn test_framing() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = tokio::spawn(async move {
+            let (_, mut writer) = TcpStream::connect(addr).await.unwrap().into_split();
+            write_message(&mut writer, &Message::Ready).await.unwrap();
+            write_message(
+                &mut writer,
+                &Message::Failed {
+                    task_id: "1".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+        });
+
+        let (stream, _) = listener.accept().await.unwrap();
+        let (mut reader, _writer) = stream.into_split();
+        assert_eq!(
+            read_message(&mut reader, MAX_FRAME_SIZE).await.unwrap(),
+            Message::Ready
+        );
+        assert_eq!(
+            read_message(&mut reader, MAX_FRAME_SIZE).await.unwrap(),
+            Message::Failed {
+                task_id: "1".to_string()
+            }
+        );
+        client.await.unwrap();
+    }
+
+    #[tokio::test]
+    // A hello announcing a large frame is refused before anything is allocated for it.
+    async fn test_oversized_hello_is_refused() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = tokio::spawn(async move {
+            let (_, mut writer) = TcpStream::connect(addr).await.unwrap().into_split();
+            writer.write_u32(MAX_FRAME_SIZE as u32).await.unwrap();
+        });
+
+        let (stream, _) = listener.accept().await.unwrap();
+        let (mut reader, _writer) = stream.into_split();
+        let error = read_message(&mut reader, MAX_HELLO_SIZE).await.unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        client.await.unwrap();
+    }
+}
@@ -1,2 +1,4 @@
+pub mod file_queue;
+pub mod ipc;
 pub mod offline;
 pub mod online;
@@ -6,38 +6,81 @@
 //! - Worker management
 
 use crate::analytics::{track_anonymous_proof_analytics, track_authenticated_proof_analytics};
+use crate::control::control_state;
 use crate::environment::Environment;
+use crate::error_budget::ErrorBudget;
 use crate::error_classifier::ErrorClassifier;
-use crate::events::{Event, EventType};
+use crate::error_classifier::LogLevel;
+use crate::events::{Event, EventType, Worker};
+use crate::fake_prover;
+use crate::performance::PerformanceTracker;
+use crate::progress::ProgressEvent;
 use crate::prover::authenticated_proving;
 use crate::task::Task;
-use nexus_sdk::stwo::seq::Proof;
-use std::time::Duration;
-use tokio::sync::{broadcast, mpsc};
+use crate::task_lifecycle::{self, TaskState};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, broadcast, mpsc};
 use tokio::task::JoinHandle;
 
+/// A task and the slot of its program's concurrency cap, if the program has one.
+pub type SlottedTask = (Task, Option<OwnedSemaphorePermit>);
+
+/// How often tasks held back by a program's concurrency cap are offered again.
+const CAPPED_RECHECK: Duration = Duration::from_millis(500);
+
 /// Spawns a dispatcher that forwards tasks to available workers in round-robin fashion.
+///
+/// Only the first `worker_limit()` workers receive tasks, so an active resource profile can
+/// idle the rest. Each task goes out with a slot of its program's concurrency cap; a task
+/// whose program is at its cap is held back until one of that program's proofs finishes, so
+/// it does not hold up the tasks behind it.
 pub fn start_dispatcher(
     mut task_receiver: mpsc::Receiver<Task>,
-    worker_senders: Vec<mpsc::Sender<Task>>,
+    worker_senders: Vec<mpsc::Sender<SlottedTask>>,
     mut shutdown: broadcast::Receiver<()>,
 ) -> JoinHandle<()> {
     tokio::spawn(async move {
         let mut next_worker = 0;
+        let mut held_back: VecDeque<Task> = VecDeque::new();
         loop {
-            tokio::select! {
+            let ready = tokio::select! {
                 Some(task) = task_receiver.recv() => {
-                    let target = next_worker % worker_senders.len();
-                    if let Err(_e) = worker_senders[target].send(task).await {
-                        // Channel is closed, stop dispatching tasks
-                        return;
+                    match crate::program_concurrency::try_acquire(&task.program_id) {
+                        Ok(slot) => vec![(task, slot)],
+                        Err(_) => {
+                            held_back.push_back(task);
+                            Vec::new()
+                        }
+                    }
+                }
+
+                _ = tokio::time::sleep(CAPPED_RECHECK), if !held_back.is_empty() => {
+                    let mut ready = Vec::new();
+                    for task in std::mem::take(&mut held_back) {
+                        match crate::program_concurrency::try_acquire(&task.program_id) {
+                            Ok(slot) => ready.push((task, slot)),
+                            Err(_) => held_back.push_back(task),
+                        }
                     }
-                    next_worker += 1;
+                    ready
                 }
 
                 _ = shutdown.recv() => {
                     break;
                 }
+            };
+            for task in ready {
+                let active = control_state()
+                    .worker_limit()
+                    .clamp(1, worker_senders.len());
+                let target = next_worker % active;
+                if let Err(_e) = worker_senders[target].send(task).await {
+                    // Channel is closed, stop dispatching tasks
+                    return;
+                }
+                next_worker += 1;
             }
         }
     })
@@ -47,26 +90,30 @@ pub fn start_dispatcher(
 ///
 /// # Arguments
 /// * `num_workers` - The number of worker tasks to spawn.
-/// * `results_sender` - The channel to emit results (task and proof).
+/// * `results_sender` - The channel to emit results (task and serialized proof).
 /// * `prover_event_sender` - The channel to send prover events to the main thread.
+/// * `error_budget` - The node's error budget; proof failures count against it.
 ///
 /// # Returns
 /// A tuple containing:
-/// * A vector of `Sender<Task>` for each worker, allowing tasks to be sent to them.
+/// * A vector of `Sender<SlottedTask>` for each worker, allowing tasks to be sent to them.
 /// * A vector of `JoinHandle<()>` for each worker, allowing the main thread to await their completion.
 pub fn start_workers(
     num_workers: usize,
-    results_sender: mpsc::Sender<(Task, Proof)>,
+    results_sender: mpsc::Sender<(Task, Vec<u8>)>,
     event_sender: mpsc::Sender<Event>,
     shutdown: broadcast::Receiver<()>,
     environment: Environment,
     client_id: String,
-) -> (Vec<mpsc::Sender<Task>>, Vec<JoinHandle<()>>) {
+    error_budget: Arc<ErrorBudget>,
+) -> (Vec<mpsc::Sender<SlottedTask>>, Vec<JoinHandle<()>>) {
     let mut senders = Vec::with_capacity(num_workers);
     let mut handles = Vec::with_capacity(num_workers);
+    // Performance baseline shared by all workers of this node
+    let performance = Arc::new(Mutex::new(PerformanceTracker::new()));
 
     for worker_id in 0..num_workers {
-        let (task_sender, mut task_receiver) = mpsc::channel::<Task>(8);
+        let (task_sender, mut task_receiver) = mpsc::channel::<SlottedTask>(8);
         // Clone senders and receivers for each worker.
         let prover_event_sender = event_sender.clone();
         let results_sender = results_sender.clone();
@@ -74,6 +121,8 @@ pub fn start_workers(
         let client_id = client_id.clone();
         let environment = environment.clone();
         let error_classifier = ErrorClassifier::new();
+        let performance = performance.clone();
+        let error_budget = error_budget.clone();
         let handle = tokio::spawn(async move {
             loop {
                 tokio::select! {
@@ -85,33 +134,19 @@ pub fn start_workers(
                         break; // Exit the loop on shutdown signal
                     }
                     // Check if there are tasks to process
-                    Some(task) = task_receiver.recv() => {
-                        match authenticated_proving(&task, &environment, &client_id).await {
-                            Ok(proof) => {
-                                let message = format!(
-                                    "[Task step 2 of 3] Proof completed successfully (Task ID: {})",
-                                    task.task_id
-                                );
-                                let _ = prover_event_sender
-                                    .send(Event::prover(worker_id, message, EventType::Success))
-                                    .await;
-
-                                // Track analytics for successful proof (non-blocking)
-                                tokio::spawn(track_authenticated_proof_analytics(task.clone(), environment.clone(), client_id.clone()));
-
-                                let _ = results_sender.send((task, proof)).await;
-                            }
-                            Err(e) => {
-                                let log_level = error_classifier.classify_worker_error(&e);
-                                let message = format!("Error: {}", e);
-                                let event = Event::prover_with_level(worker_id, message, EventType::Error, log_level);
-                                if event.should_display() {
-                                    let _ = prover_event_sender.send(event).await;
-                                }
-
-                                // For analytics errors, continue processing but don't send result
-                                // For other errors, also don't send result (task failed)
-                            }
+                    Some((task, slot)) = task_receiver.recv() => {
+                        if let Some(proof) = prove_task(
+                            worker_id,
+                            &task,
+                            slot,
+                            &environment,
+                            &client_id,
+                            &error_classifier,
+                            &performance,
+                            &error_budget,
+                            &prover_event_sender,
+                        ).await {
+                            let _ = results_sender.send((task, proof)).await;
                         }
                     }
                     else => break,
@@ -126,6 +161,211 @@ pub fn start_workers(
     (senders, handles)
 }
 
+/// Proves a single task, reporting progress, performance anomalies and failures as events.
+///
+/// `slot` is a slot of the program's concurrency cap taken by the caller, if any; without
+/// one, a capped program's slot is waited for here.
+///
+/// Returns the serialized proof, or `None` if proving failed.
+#[allow(clippy::too_many_arguments)]
+pub async fn prove_task(
+    worker_id: usize,
+    task: &Task,
+    slot: Option<OwnedSemaphorePermit>,
+    environment: &Environment,
+    client_id: &str,
+    error_classifier: &ErrorClassifier,
+    performance: &Mutex<PerformanceTracker>,
+    error_budget: &ErrorBudget,
+    event_sender: &mpsc::Sender<Event>,
+) -> Option<Vec<u8>> {
+    let worker = Worker::Prover(worker_id);
+    // A proof finished before a crash or restart is not computed again
+    if let Some(proof) = crate::proof_checkpoint::load(task) {
+        task_lifecycle::advance(&task.task_id, TaskState::Proving, worker, event_sender).await;
+        task_lifecycle::advance(&task.task_id, TaskState::Proved, worker, event_sender).await;
+        let message = format!("Resumed task {} from its checkpointed proof", task.task_id);
+        let _ = event_sender
+            .send(
+                Event::prover_with_level(worker_id, message, EventType::Refresh, LogLevel::Info)
+                    .with_node(task.node_id)
+                    .with_task(&task.task_id),
+            )
+            .await;
+        return Some(proof);
+    }
+    // Held until proving ends, so a program's cap counts this proof
+    let _slot = match slot
+        .map(Ok)
+        .unwrap_or_else(|| crate::program_concurrency::try_acquire(&task.program_id))
+    {
+        Ok(slot) => slot,
+        Err(limit) => {
+            let message = format!(
+                "Waiting for a slot: at most {} tasks of program {} are proved at once",
+                limit, task.program_id
+            );
+            let _ = event_sender
+                .send(Event::prover(worker_id, message, EventType::Refresh))
+                .await;
+            crate::program_concurrency::acquire(&task.program_id).await
+        }
+    };
+    crate::memory_limit::wait_for_room(worker_id, &task.task_id, event_sender).await;
+    let proof_start = Instant::now();
+    task_lifecycle::advance(&task.task_id, TaskState::Proving, worker, event_sender).await;
+    crate::progress::emit(ProgressEvent::ProvingStarted {
+        task_id: task.task_id.clone(),
+        worker: worker_id,
+    });
+    let profile = crate::task_profiler::TaskProfile::start(&task.task_id);
+    let result = if fake_prover::is_enabled() {
+        Ok(fake_prover::prove().await)
+    } else {
+        authenticated_proving(task, environment, client_id)
+            .await
+            .and_then(|proof| Ok(postcard::to_allocvec(&proof)?))
+    };
+    if let Some(profile) = profile {
+        let message = match profile.finish() {
+            Ok(path) => format!(
+                "Flamegraph of task {} written to {}",
+                task.task_id,
+                path.display()
+            ),
+            Err(e) => format!(
+                "Could not write the flamegraph of task {}: {}",
+                task.task_id, e
+            ),
+        };
+        let _ = event_sender
+            .send(
+                Event::prover_with_level(worker_id, message, EventType::Refresh, LogLevel::Info)
+                    .with_task(&task.task_id),
+            )
+            .await;
+    }
+    match result {
+        Ok(proof) => {
+            let proof_duration = proof_start.elapsed();
+            let message = format!(
+                "[Task step 2 of 3] Proof completed successfully (Task ID: {}) in {:.1}s",
+                task.task_id,
+                proof_duration.as_secs_f64()
+            );
+            let _ = event_sender
+                .send(
+                    Event::prover(worker_id, message, EventType::Success)
+                        .with_node(task.node_id)
+                        .with_task(&task.task_id)
+                        .with_duration(proof_duration),
+                )
+                .await;
+            crate::progress::emit(ProgressEvent::ProofCompleted {
+                task_id: task.task_id.clone(),
+                worker: worker_id,
+                duration_ms: proof_duration.as_millis() as u64,
+            });
+
+            task_lifecycle::advance(&task.task_id, TaskState::Proved, worker, event_sender).await;
+            crate::proof_checkpoint::save(task, &proof);
+            crate::program_profiles::record_proof(&task.program_id, proof_duration);
+            if let Some(difficulty) = crate::difficulty::record_proof(proof_duration) {
+                let message = format!(
+                    "Requesting {} tasks from now on, as this proof took {:.0}s",
+                    crate::difficulty::name(difficulty),
+                    proof_duration.as_secs_f64()
+                );
+                let _ = event_sender
+                    .send(Event::prover(worker_id, message, EventType::Refresh))
+                    .await;
+            }
+            crate::metrics::record_proof(Some(proof_duration));
+            let anomalies =
+                record_performance(performance, Some((&task.program_id, proof_duration)));
+            send_anomalies(event_sender, worker_id, anomalies).await;
+
+            // Track analytics for successful proof (non-blocking)
+            if !fake_prover::is_enabled() {
+                tokio::spawn(track_authenticated_proof_analytics(
+                    task.clone(),
+                    environment.clone(),
+                    client_id.to_string(),
+                ));
+            }
+            Some(proof)
+        }
+        Err(e) => {
+            crate::metrics::record_proof(None);
+            let log_level = error_classifier.classify_worker_error(&e);
+            crate::progress::emit(ProgressEvent::Failed {
+                task_id: task.task_id.clone(),
+                stage: "prove",
+                error: e.to_string(),
+            });
+            crate::hooks::task_failed(task, "prove", &e.to_string());
+            let message = format!("Error: {}", e);
+            let event = Event::prover_with_level(worker_id, message, EventType::Error, log_level)
+                .with_node(task.node_id)
+                .with_task(&task.task_id);
+            if event.should_display() {
+                let _ = event_sender.send(event).await;
+            }
+
+            task_lifecycle::advance(&task.task_id, TaskState::Abandoned, worker, event_sender)
+                .await;
+            crate::checkpoint::untrack(&task.task_id);
+            crate::program_profiles::record_failure(&task.program_id);
+            let anomalies = record_performance(performance, None);
+            send_anomalies(event_sender, worker_id, anomalies).await;
+            control_state().task_finished();
+            error_budget
+                .report(false, Worker::Prover(worker_id), event_sender)
+                .await;
+            None
+        }
+    }
+}
+
+/// Records a proof outcome in the shared performance tracker and returns any anomaly messages.
+///
+/// `proof` is the program ID and proving duration of a successful proof, or `None` if the task failed.
+fn record_performance(
+    performance: &Mutex<PerformanceTracker>,
+    proof: Option<(&str, Duration)>,
+) -> Vec<String> {
+    let Ok(mut tracker) = performance.lock() else {
+        return Vec::new();
+    };
+    let mut anomalies = Vec::new();
+    if let Some((program_id, duration)) = proof {
+        anomalies.extend(tracker.record_proof(program_id, duration));
+    }
+    anomalies.extend(tracker.record_outcome(proof.is_some()));
+    anomalies
+        .into_iter()
+        .map(|anomaly| format!("{} (performance score: {}/100)", anomaly, tracker.score()))
+        .collect()
+}
+
+/// Reports performance anomalies as warnings.
+async fn send_anomalies(
+    event_sender: &mpsc::Sender<Event>,
+    worker_id: usize,
+    anomalies: Vec<String>,
+) {
+    for message in anomalies {
+        let _ = event_sender
+            .send(Event::prover_with_level(
+                worker_id,
+                message,
+                EventType::Error,
+                LogLevel::Warn,
+            ))
+            .await;
+    }
+}
+
 /// Starts anonymous workers that repeatedly prove a program with hardcoded inputs.
 pub async fn start_anonymous_workers(
     num_workers: usize,
@@ -154,15 +394,27 @@ pub async fn start_anonymous_workers(
                     }
 
                     _ = tokio::time::sleep(Duration::from_millis(300)) => {
+                        // Workers beyond the active profile's count stay idle
+                        if worker_id >= control_state().worker_limit().max(1) {
+                            continue;
+                        }
                         // Perform work
-                        match crate::prover::prove_anonymously().await {
-                            Ok(_proof) => {
+                        let result = if fake_prover::is_enabled() {
+                            fake_prover::prove().await;
+                            Ok(())
+                        } else {
+                            crate::prover::prove_anonymously().await.map(|_| ())
+                        };
+                        match result {
+                            Ok(()) => {
                                 let message = "Anonymous proof completed successfully".to_string();
                                 let _ = prover_event_sender
                                     .send(Event::prover(worker_id, message, EventType::Success)).await;
 
                                 // Track analytics for successful anonymous proof (non-blocking)
-                                tokio::spawn(track_anonymous_proof_analytics(environment.clone(), client_id.clone()));
+                                if !fake_prover::is_enabled() {
+                                    tokio::spawn(track_anonymous_proof_analytics(environment.clone(), client_id.clone()));
+                                }
                             }
                             Err(e) => {
                                 let log_level = error_classifier.classify_worker_error(&e);
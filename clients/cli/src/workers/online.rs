@@ -9,42 +9,95 @@ use crate::analytics::{
     track_got_task, track_proof_accepted, track_proof_submission_error,
     track_proof_submission_success,
 };
+use crate::backpressure::{self, Backpressure, Capacity};
+use crate::checkpoint;
 use crate::consts::prover::{
-    BACKOFF_DURATION, BATCH_SIZE, LOW_WATER_MARK, MAX_404S_BEFORE_GIVING_UP, QUEUE_LOG_INTERVAL,
-    TASK_QUEUE_SIZE,
+    BATCH_SIZE, MAX_404S_BEFORE_GIVING_UP, QUEUE_LOG_INTERVAL, TASK_QUEUE_SIZE,
 };
+use crate::control::control_state;
 use crate::environment::Environment;
+use crate::error_budget::ErrorBudget;
 use crate::error_classifier::{ErrorClassifier, LogLevel};
-use crate::events::Event;
-use crate::orchestrator::Orchestrator;
+use crate::events::{Event, Worker};
+use crate::fake_prover;
+use crate::goal::{self, GoalNotice};
+use crate::latency_slo::{self, SloNotice};
+use crate::maintenance::MaintenanceWindow;
 use crate::orchestrator::error::OrchestratorError;
+use crate::orchestrator::hints;
+use crate::orchestrator::{Orchestrator, ProofSubmission};
+use crate::polling::PollingConfig;
+use crate::progress::ProgressEvent;
+use crate::proof_checkpoint;
+use crate::proxy::ProxyContext;
+use crate::receipts::SubmissionReceipt;
+use crate::submission_journal::SubmissionJournal;
+use crate::submission_queue::{QueuedProof, SubmissionQueue};
+use crate::submission_verifier::{PendingVerifications, Verdict, VerificationConfig};
 use crate::task::Task;
 use crate::task_cache::TaskCache;
+use crate::task_filter::TaskFilter;
+use crate::task_lifecycle::{self, TaskState};
 use ed25519_dalek::{SigningKey, VerifyingKey};
-use nexus_sdk::stwo::seq::Proof;
 use sha3::{Digest, Keccak256};
-use std::time::Duration;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 use tokio::sync::{broadcast, mpsc};
 use tokio::task::JoinHandle;
 
+/// Range over which nodes spread their first poll after a maintenance window ends.
+const MAINTENANCE_SPREAD: Duration = Duration::from_secs(60);
+
+/// How long to pause task requests for a node the orchestrator does not know: short enough
+/// that a node registered meanwhile (from the web app, say) starts within minutes.
+const NODE_NOT_REGISTERED_BACKOFF: Duration = Duration::from_secs(300);
+
+/// Longest pause after rejected credentials. A rejection can come from clock skew or a proxy
+/// rewriting the request and pass by itself, so the pause doubles from the polling interval
+/// while it persists.
+const UNAUTHORIZED_MAX_BACKOFF: Duration = Duration::from_secs(1800);
+
+/// How long to pause task requests once the orchestrator refuses this CLI version: nothing
+/// changes until it is updated, which the version checker reports.
+const VERSION_TOO_OLD_BACKOFF: Duration = Duration::from_secs(3600);
+
 /// State for managing task fetching behavior
 pub struct TaskFetchState {
     last_fetch_time: std::time::Instant,
     backoff_duration: Duration,
+    /// Random factor applied to the backoff for the current wait (see `PollingConfig`)
+    jitter_factor: f64,
     last_queue_log_time: std::time::Instant,
     queue_log_interval: Duration,
     error_classifier: ErrorClassifier,
+    polling: PollingConfig,
+    /// Maintenance window announced by the orchestrator, while one is in effect
+    maintenance: Option<MaintenanceWindow>,
+    /// Whether fetching is held off by backpressure
+    held: bool,
 }
 
 impl TaskFetchState {
     pub fn new() -> Self {
+        Self::with_polling(PollingConfig::default())
+    }
+
+    pub fn with_polling(polling: PollingConfig) -> Self {
+        let now = std::time::Instant::now();
         Self {
-            last_fetch_time: std::time::Instant::now()
-                - Duration::from_millis(BACKOFF_DURATION + 1000), // Allow immediate first fetch
-            backoff_duration: Duration::from_millis(BACKOFF_DURATION), // Start with 120 second backoff
-            last_queue_log_time: std::time::Instant::now(),
+            // Allow immediate first fetch
+            last_fetch_time: now
+                .checked_sub(polling.interval + Duration::from_secs(1))
+                .unwrap_or(now),
+            backoff_duration: polling.interval,
+            jitter_factor: 1.0,
+            last_queue_log_time: now,
             queue_log_interval: Duration::from_millis(QUEUE_LOG_INTERVAL), // Log queue status every 30 seconds
             error_classifier: ErrorClassifier::new(),
+            polling,
+            maintenance: None,
+            held: false,
         }
     }
 
@@ -53,12 +106,24 @@ impl TaskFetchState {
         self.last_queue_log_time.elapsed() >= self.queue_log_interval
     }
 
-    pub fn should_fetch(&self, tasks_in_queue: usize) -> bool {
-        tasks_in_queue < LOW_WATER_MARK && self.last_fetch_time.elapsed() >= self.backoff_duration
+    /// Whether the wait since the last fetch is over.
+    pub fn is_fetch_due(&self) -> bool {
+        self.last_fetch_time.elapsed() >= self.current_wait()
+    }
+
+    /// Records whether backpressure holds off fetching, returning whether that changed.
+    pub fn set_held(&mut self, held: bool) -> bool {
+        std::mem::replace(&mut self.held, held) != held
+    }
+
+    /// The wait before the next fetch, including jitter.
+    pub fn current_wait(&self) -> Duration {
+        self.backoff_duration.mul_f64(self.jitter_factor)
     }
 
     pub fn record_fetch_attempt(&mut self) {
         self.last_fetch_time = std::time::Instant::now();
+        self.jitter_factor = self.polling.sample_jitter_factor();
     }
 
     pub fn record_queue_log(&mut self) {
@@ -66,7 +131,7 @@ impl TaskFetchState {
     }
 
     pub fn reset_backoff(&mut self) {
-        self.backoff_duration = Duration::from_millis(BACKOFF_DURATION);
+        self.backoff_duration = self.polling.interval;
     }
 
     /// Set backoff duration from server's Retry-After header (in seconds)
@@ -76,32 +141,57 @@ impl TaskFetchState {
     }
 
     pub fn increase_backoff_for_error(&mut self) {
-        self.backoff_duration = std::cmp::min(
-            self.backoff_duration * 2,
-            Duration::from_millis(BACKOFF_DURATION * 2),
-        );
+        self.backoff_duration = std::cmp::min(self.backoff_duration * 2, self.polling.interval * 2);
+    }
+
+    /// Pause task requests after an error only the user can fix, for as long as suits its kind
+    pub fn pause_for_action_required(&mut self, error: &OrchestratorError) {
+        let pause = match error {
+            OrchestratorError::NodeNotRegistered { .. } => NODE_NOT_REGISTERED_BACKOFF,
+            OrchestratorError::Unauthorized { .. } => {
+                std::cmp::min(self.backoff_duration * 2, UNAUTHORIZED_MAX_BACKOFF)
+            }
+            OrchestratorError::VersionTooOld { .. } => VERSION_TOO_OLD_BACKOFF,
+            _ => self.backoff_duration,
+        };
+        self.backoff_duration = pause.max(self.polling.interval);
+    }
+
+    /// Lengthen the polling interval while the orchestrator has no tasks for this node
+    pub fn increase_backoff_for_idle(&mut self) {
+        // A longer backoff is likely from a server retry-after header, so keep it
+        if self.backoff_duration <= self.polling.max_idle_interval {
+            self.backoff_duration = self.polling.idle_interval(self.backoff_duration);
+        }
     }
 }
 
 /// Fetches tasks from the orchestrator and place them in the task queue.
-/// Uses demand-driven fetching: only fetches when queue drops below LOW_WATER_MARK.
+/// Only fetches as many tasks as the node can complete (see `backpressure`).
 #[allow(clippy::too_many_arguments)]
 pub async fn fetch_prover_tasks(
     node_id: u64,
     verifying_key: VerifyingKey,
     orchestrator_client: Box<dyn Orchestrator>,
+    proxies: Arc<ProxyContext>,
     sender: mpsc::Sender<Task>,
     event_sender: mpsc::Sender<Event>,
     mut shutdown: broadcast::Receiver<()>,
     recent_tasks: TaskCache,
     environment: Environment,
     client_id: String,
+    task_filter: TaskFilter,
+    polling: PollingConfig,
+    error_budget: Arc<ErrorBudget>,
 ) {
-    let mut state = TaskFetchState::new();
+    let mut state = TaskFetchState::with_polling(polling);
+    let mut wakes = crate::sleep_wake::subscribe();
 
     loop {
         tokio::select! {
             _ = shutdown.recv() => break,
+            // Errors on the connections that died during sleep say nothing about the orchestrator
+            Ok(()) = wakes.changed() => state.reset_backoff(),
             _ = tokio::time::sleep(Duration::from_millis(500)) => {
                 let tasks_in_queue = TASK_QUEUE_SIZE - sender.capacity();
 
@@ -110,9 +200,33 @@ pub async fn fetch_prover_tasks(
                     state.record_queue_log();
                     log_queue_status(&event_sender, tasks_in_queue, &state).await;
                 }
-
-                // Attempt fetch if conditions are met
-                if state.should_fetch(tasks_in_queue) {
+                proxies.report_file(&event_sender).await;
+                proxies.report_sticky_failovers(&event_sender).await;
+
+                // Attempt fetch if conditions are met. Fetching stops while paused or draining,
+                // an exhausted error budget only lets probes through, and backpressure limits
+                // new work to what the node can complete.
+                if state.is_fetch_due()
+                    && control_state().allow_fetch()
+                    && error_budget.allow_fetch()
+                {
+                    let reservation = backpressure::reserve(BATCH_SIZE);
+                    if state.set_held(reservation.is_err()) {
+                        let reason = reservation.as_ref().err().map(String::as_str);
+                        report_backpressure(reason, &event_sender).await;
+                    }
+                    let Ok(reservation) = reservation else {
+                        continue;
+                    };
+                    if error_budget.is_paused() {
+                        let _ = event_sender
+                            .send(Event::task_fetcher_with_level(
+                                "Error budget exhausted, fetching a probe task to check whether the node has recovered".to_string(),
+                                crate::events::EventType::Refresh,
+                                LogLevel::Info,
+                            ))
+                            .await;
+                    }
                     if let Err(should_return) = attempt_task_fetch(
                         &*orchestrator_client,
                         &node_id,
@@ -123,6 +237,8 @@ pub async fn fetch_prover_tasks(
                         &mut state,
                         &environment,
                         &client_id,
+                        &task_filter,
+                        reservation.tasks(),
                     ).await {
                         if should_return {
                             return;
@@ -146,6 +262,8 @@ async fn attempt_task_fetch(
     state: &mut TaskFetchState,
     environment: &Environment,
     client_id: &str,
+    task_filter: &TaskFilter,
+    batch_size: usize,
 ) -> Result<(), bool> {
     let _ = event_sender
         .send(Event::task_fetcher_with_level(
@@ -160,16 +278,20 @@ async fn attempt_task_fetch(
         orchestrator_client,
         node_id,
         verifying_key,
-        BATCH_SIZE,
+        batch_size,
         event_sender,
     );
     let timeout_duration = Duration::from_secs(60); // 60 second timeout
 
     match tokio::time::timeout(timeout_duration, fetch_future).await {
         Ok(fetch_result) => match fetch_result {
-            Ok(tasks) => {
+            Ok(mut tasks) => {
                 // Record successful fetch attempt timing
                 state.record_fetch_attempt();
+                end_maintenance(event_sender, state).await;
+                for task in &mut tasks {
+                    task.node_id = Some(*node_id);
+                }
                 handle_fetch_success(
                     tasks,
                     sender,
@@ -178,6 +300,7 @@ async fn attempt_task_fetch(
                     state,
                     environment,
                     client_id,
+                    task_filter,
                 )
                 .await
             }
@@ -212,9 +335,14 @@ async fn log_queue_status(
     state: &TaskFetchState,
 ) {
     let time_since_last = state.last_fetch_time.elapsed();
-    let backoff_secs = state.backoff_duration.as_secs();
+    let backoff_secs = state.current_wait().as_secs();
 
-    let message = if state.should_fetch(tasks_in_queue) {
+    let message = if let Backpressure::Hold(reason) = Capacity::current().signal() {
+        format!(
+            "Tasks to compute: {} tasks, not fetching more: {}",
+            tasks_in_queue, reason
+        )
+    } else if state.is_fetch_due() {
         format!(
             "Tasks Queue low: {} tasks to compute, ready to fetch",
             tasks_in_queue
@@ -238,7 +366,23 @@ async fn log_queue_status(
         .await;
 }
 
+/// Reports backpressure holding off fetching (with the reason), or letting it resume.
+async fn report_backpressure(reason: Option<&str>, event_sender: &mpsc::Sender<Event>) {
+    let msg = match reason {
+        Some(reason) => format!("Not fetching new tasks: {}", reason),
+        None => "Fetching new tasks again".to_string(),
+    };
+    let _ = event_sender
+        .send(Event::task_fetcher_with_level(
+            msg,
+            crate::events::EventType::Refresh,
+            LogLevel::Info,
+        ))
+        .await;
+}
+
 /// Handle successful task fetch
+#[allow(clippy::too_many_arguments)]
 async fn handle_fetch_success(
     tasks: Vec<Task>,
     sender: &mpsc::Sender<Task>,
@@ -247,6 +391,7 @@ async fn handle_fetch_success(
     state: &mut TaskFetchState,
     environment: &Environment,
     client_id: &str,
+    task_filter: &TaskFilter,
 ) -> Result<(), bool> {
     if tasks.is_empty() {
         handle_empty_task_response(sender, event_sender, state).await;
@@ -260,6 +405,7 @@ async fn handle_fetch_success(
         recent_tasks,
         environment,
         client_id,
+        task_filter,
     )
     .await?;
 
@@ -273,7 +419,13 @@ async fn handle_empty_task_response(
     event_sender: &mpsc::Sender<Event>,
     state: &mut TaskFetchState,
 ) {
-    let msg = "No tasks available yet for this node".to_string();
+    // Poll less often while the network is quiet
+    state.increase_backoff_for_idle();
+
+    let msg = format!(
+        "No tasks available yet for this node, checking again in {}s",
+        state.current_wait().as_secs()
+    );
     let _ = event_sender
         .send(Event::task_fetcher_with_level(
             msg,
@@ -281,26 +433,27 @@ async fn handle_empty_task_response(
             LogLevel::Info,
         ))
         .await;
-
-    // Only reset backoff if it's at or below default level
-    // If backoff is higher, it's likely from a server retry-after header
-    if state.backoff_duration <= Duration::from_millis(BACKOFF_DURATION) {
-        state.reset_backoff();
-    }
 }
 
 /// Process fetched tasks and handle duplicates
+///
+/// The batch is first ordered by the learned proving time of each program (see
+/// `program_profiles`). Tasks rejected by the task filter are remembered like duplicates, so an
+/// assigned task that will never be accepted only triggers a backoff instead of being reported
+/// repeatedly.
 async fn process_fetched_tasks(
-    tasks: Vec<Task>,
+    mut tasks: Vec<Task>,
     sender: &mpsc::Sender<Task>,
     event_sender: &mpsc::Sender<Event>,
     recent_tasks: &TaskCache,
     environment: &Environment,
     client_id: &str,
+    task_filter: &TaskFilter,
 ) -> Result<(usize, usize), bool> {
     let mut added_count = 0;
     let mut duplicate_count = 0;
 
+    crate::program_profiles::order_fetched(&mut tasks);
     for task in tasks {
         if recent_tasks.contains(&task.task_id).await {
             duplicate_count += 1;
@@ -308,7 +461,42 @@ async fn process_fetched_tasks(
         }
         recent_tasks.insert(task.task_id.clone()).await;
 
+        if let Err(reason) = task_filter
+            .check(&task)
+            .and_then(|()| crate::prover::check_allowlisted(&task.program_id))
+        {
+            let _ = event_sender
+                .send(
+                    Event::task_fetcher_with_level(
+                        format!("Skipping task {}: {}", task.task_id, reason),
+                        crate::events::EventType::Refresh,
+                        LogLevel::Info,
+                    )
+                    .with_node(task.node_id)
+                    .with_task(&task.task_id),
+                )
+                .await;
+            duplicate_count += 1;
+            continue;
+        }
+
+        task_lifecycle::advance(
+            &task.task_id,
+            TaskState::Fetched,
+            Worker::TaskFetcher,
+            event_sender,
+        )
+        .await;
+        checkpoint::track(&task);
         if sender.send(task.clone()).await.is_err() {
+            checkpoint::untrack(&task.task_id);
+            task_lifecycle::advance(
+                &task.task_id,
+                TaskState::Abandoned,
+                Worker::TaskFetcher,
+                event_sender,
+            )
+            .await;
             let _ = event_sender
                 .send(Event::task_fetcher(
                     "Task queue is closed".to_string(),
@@ -317,6 +505,14 @@ async fn process_fetched_tasks(
                 .await;
             return Err(true); // Signal caller to return
         }
+        control_state().task_started();
+        if let Some(node_id) = task.node_id {
+            crate::nodes::record_fetched(node_id);
+        }
+        crate::progress::emit(ProgressEvent::TaskFetched {
+            task_id: task.task_id.clone(),
+            program_id: task.program_id.clone(),
+        });
 
         // Track analytics for getting a task (non-blocking)
         tokio::spawn(track_got_task(
@@ -341,7 +537,7 @@ async fn log_fetch_results(
 ) {
     if added_count > 0 {
         log_successful_fetch(added_count, sender, event_sender).await;
-        state.reset_backoff(); // Reset to default 120s backoff
+        state.reset_backoff(); // Reset to the base polling interval
     } else if duplicate_count > 0 {
         handle_all_duplicates(duplicate_count, event_sender, state).await;
     }
@@ -416,50 +612,62 @@ async fn handle_fetch_error(
     event_sender: &mpsc::Sender<Event>,
     state: &mut TaskFetchState,
 ) {
+    if let Some(window) = MaintenanceWindow::from_error(&error) {
+        enter_maintenance(window, event_sender, state).await;
+        return;
+    }
+
     match error {
-        OrchestratorError::Http {
-            status: 429,
-            ref headers,
-            ..
-        } => {
-            // Debug: print headers for 429 responses
+        OrchestratorError::RateLimited { retry_after } => {
+            // Wait as long as the server asked rather than polling into the limit again
+            state.set_backoff_from_server(retry_after.as_secs() as u32);
             let _ = event_sender
                 .send(Event::task_fetcher_with_level(
-                    format!("429 Rate limit retry-after: {:?}", headers["retry-after"]),
-                    crate::events::EventType::Refresh,
-                    LogLevel::Debug,
+                    hints::with_hint(
+                        format!("Rate limited - retrying in {}s", retry_after.as_secs()),
+                        &error,
+                    ),
+                    crate::events::EventType::Error,
+                    LogLevel::Warn,
+                ))
+                .await;
+        }
+        OrchestratorError::NodeNotRegistered { .. }
+        | OrchestratorError::Unauthorized { .. }
+        | OrchestratorError::VersionTooOld { .. } => {
+            // Asking again will not help until the node is registered or the CLI updated
+            state.pause_for_action_required(&error);
+            let _ = event_sender
+                .send(Event::task_fetcher_with_level(
+                    hints::with_hint(
+                        format!(
+                            "{} - pausing task requests for {}s",
+                            error,
+                            state.backoff_duration.as_secs()
+                        ),
+                        &error,
+                    ),
+                    crate::events::EventType::Error,
+                    LogLevel::Error,
                 ))
                 .await;
-
-            if let Some(retry_after_seconds) = error.get_retry_after_seconds() {
-                state.set_backoff_from_server(retry_after_seconds);
-                let _ = event_sender
-                    .send(Event::task_fetcher_with_level(
-                        format!("Rate limited - retrying in {}s", retry_after_seconds),
-                        crate::events::EventType::Error,
-                        LogLevel::Warn,
-                    ))
-                    .await;
-            } else {
-                // This shouldn't happen with a properly configured server
-                state.increase_backoff_for_error();
-                let _ = event_sender
-                    .send(Event::task_fetcher_with_level(
-                        "Rate limited - no retry time specified".to_string(),
-                        crate::events::EventType::Error,
-                        LogLevel::Error,
-                    ))
-                    .await;
-            }
         }
         _ => {
             state.increase_backoff_for_error();
-            let log_level = state.error_classifier.classify_fetch_error(&error);
+            // Errors are expected while the orchestrator is down for maintenance
+            let log_level = if state.maintenance.as_ref().is_some_and(|w| w.is_active()) {
+                LogLevel::Debug
+            } else {
+                state.error_classifier.classify_fetch_error(&error)
+            };
             let event = Event::task_fetcher_with_level(
-                format!(
-                    "Failed to fetch tasks: {}, retrying in {} seconds",
-                    error,
-                    state.backoff_duration.as_secs()
+                hints::with_hint(
+                    format!(
+                        "Failed to fetch tasks: {}, retrying in {} seconds",
+                        error,
+                        state.backoff_duration.as_secs()
+                    ),
+                    &error,
                 ),
                 crate::events::EventType::Error,
                 log_level,
@@ -471,6 +679,53 @@ async fn handle_fetch_error(
     }
 }
 
+/// Switch into quiet backoff for an announced maintenance window
+async fn enter_maintenance(
+    window: MaintenanceWindow,
+    event_sender: &mpsc::Sender<Event>,
+    state: &mut TaskFetchState,
+) {
+    // Wait out the window, but never poll faster than the base interval. Nodes resume at
+    // staggered times rather than all at the announced end.
+    let resume_after =
+        window.remaining() + crate::pacing::offset("maintenance_end", MAINTENANCE_SPREAD);
+    state.backoff_duration = std::cmp::max(resume_after, state.polling.interval);
+
+    // Announce a window once. Its end time moves on every poll when the orchestrator only
+    // sends Retry-After, so any window still in effect counts as the same one.
+    let log_level = if state.maintenance.as_ref().is_some_and(|w| w.is_active()) {
+        LogLevel::Debug
+    } else {
+        LogLevel::Warn
+    };
+    let _ = event_sender
+        .send(Event::maintenance_with_level(
+            format!(
+                "{} - pausing task requests for {}s",
+                window,
+                state.backoff_duration.as_secs()
+            ),
+            crate::events::EventType::Refresh,
+            log_level,
+        ))
+        .await;
+    state.maintenance = Some(window);
+}
+
+/// Leave maintenance mode once the orchestrator responds normally again
+async fn end_maintenance(event_sender: &mpsc::Sender<Event>, state: &mut TaskFetchState) {
+    if state.maintenance.take().is_some() {
+        state.reset_backoff();
+        let _ = event_sender
+            .send(Event::maintenance_with_level(
+                "Orchestrator maintenance has ended, resuming task requests".to_string(),
+                crate::events::EventType::Refresh,
+                LogLevel::Info,
+            ))
+            .await;
+    }
+}
+
 /// Fetch a batch of tasks from the orchestrator
 async fn fetch_task_batch(
     orchestrator_client: &dyn Orchestrator,
@@ -539,12 +794,7 @@ async fn fetch_new_tasks_batch(
                 new_tasks.push(task);
                 consecutive_404s = 0; // Reset counter on success
             }
-            Err(OrchestratorError::Http {
-                status: 429,
-                message,
-                ref headers,
-            }) => {
-                // Debug: print headers for 429 responses
+            Err(error @ OrchestratorError::RateLimited { .. }) => {
                 let _ = event_sender
                     .send(Event::task_fetcher_with_level(
                         "Every node in the Prover Network is rate limited to 3 tasks per 3 minutes"
@@ -555,11 +805,7 @@ async fn fetch_new_tasks_batch(
                     .await;
 
                 // Don't handle 429 here - propagate it back to main error handler
-                return Err(OrchestratorError::Http {
-                    status: 429,
-                    message,
-                    headers: headers.clone(),
-                });
+                return Err(error);
             }
             Err(OrchestratorError::Http { status: 404, .. }) => {
                 consecutive_404s += 1;
@@ -607,68 +853,377 @@ async fn fetch_new_tasks_batch(
 }
 
 /// Submits proofs to the orchestrator
+///
+/// Proofs for tasks fetched by a node listed in `node_orchestrators` are submitted through
+/// that node's client; all others go through `orchestrator`. With a `verification` config,
+/// accepted submissions are checked again later to confirm they were credited.
 #[allow(clippy::too_many_arguments)]
 pub async fn submit_proofs(
     signing_key: SigningKey,
     orchestrator: Box<dyn Orchestrator>,
+    node_orchestrators: HashMap<u64, Box<dyn Orchestrator>>,
     num_workers: usize,
-    mut results: mpsc::Receiver<(Task, Proof)>,
+    mut results: mpsc::Receiver<(Task, Vec<u8>)>,
     event_sender: mpsc::Sender<Event>,
     mut shutdown: broadcast::Receiver<()>,
     successful_tasks: TaskCache,
+    mut journal: SubmissionJournal,
+    error_budget: Arc<ErrorBudget>,
+    verification: Option<VerificationConfig>,
     environment: Environment,
     client_id: String,
 ) -> JoinHandle<()> {
     tokio::spawn(async move {
+        reconcile_pending_submissions(
+            &mut journal,
+            &*orchestrator,
+            &signing_key,
+            num_workers,
+            &event_sender,
+            &successful_tasks,
+        )
+        .await;
+
         let mut completed_count = 0;
         let mut last_stats_time = std::time::Instant::now();
         let stats_interval = Duration::from_secs(60);
+        let mut verifications = PendingVerifications::new(verification);
+
+        let mut queue = SubmissionQueue::new();
+        let mut results_open = true;
 
         loop {
+            // Collect every finished proof before choosing which to submit next
+            while let Ok((task, proof)) = results.try_recv() {
+                queue_proof(&mut queue, task, proof);
+            }
+            queue.release_due(SystemTime::now());
+            backpressure::set_submission_backlog(queue.backlog());
+
             tokio::select! {
-                maybe_item = results.recv() => {
+                maybe_item = results.recv(), if queue.is_empty() => {
                     match maybe_item {
-                        Some((task, proof)) => {
-                            if let Some(success) = process_proof_submission(
-                                task,
-                                proof,
-                                &*orchestrator,
-                                &signing_key,
-                                num_workers,
-                                &event_sender,
-                                &successful_tasks,
-                                &environment,
-                                &client_id,
-                            ).await {
-                                if success {
-                                    completed_count += 1;
-                                }
-                            }
+                        Some((task, proof)) => queue_proof(&mut queue, task, proof),
+                        None => break,
+                    }
+                }
 
-                            // Check if it's time to report stats (avoid timer starvation)
-                            if last_stats_time.elapsed() >= stats_interval {
-                                report_performance_stats(&event_sender, completed_count, last_stats_time).await;
-                                completed_count = 0;
-                                last_stats_time = std::time::Instant::now();
+                _ = std::future::ready(()), if !queue.is_empty() => {
+                    let Some(current) = queue.pop() else {
+                        continue;
+                    };
+                    let task_id = current.task.task_id.clone();
+                    let node_id = current.task.node_id;
+                    let node_orchestrator = node_id
+                        .and_then(|node_id| node_orchestrators.get(&node_id))
+                        .unwrap_or(&orchestrator);
+                    let started = std::time::Instant::now();
+                    let outcome = {
+                        let submission = process_proof_submission(
+                            &current,
+                            &**node_orchestrator,
+                            &signing_key,
+                            num_workers,
+                            &event_sender,
+                            &successful_tasks,
+                            &mut journal,
+                            &error_budget,
+                            &environment,
+                            &client_id,
+                        );
+                        tokio::pin!(submission);
+                        // Keep queueing proofs during the upload, in case one cannot wait for it
+                        loop {
+                            tokio::select! {
+                                result = &mut submission => break Ok(result),
+                                maybe_item = results.recv(), if results_open => {
+                                    match maybe_item {
+                                        Some((task, proof)) => {
+                                            queue_proof(&mut queue, task, proof);
+                                        }
+                                        None => results_open = false,
+                                    }
+                                    let now = SystemTime::now();
+                                    if let Some(urgent) =
+                                        queue.preempting(&current, started.elapsed(), now)
+                                    {
+                                        break Err(urgent.task.task_id.clone());
+                                    }
+                                }
                             }
                         }
-                        None => break,
+                    };
+                    let result = match outcome {
+                        Ok(result) => result,
+                        Err(urgent_task_id) => {
+                            let msg = format!(
+                                "Interrupted upload of task {} ({} KB) for task {}, which is due sooner; {} proofs waiting",
+                                task_id,
+                                current.upload_bytes / 1024,
+                                urgent_task_id,
+                                queue.len() + 1
+                            );
+                            let _ = event_sender
+                                .send(Event::proof_submitter_with_level(
+                                    msg,
+                                    crate::events::EventType::Refresh,
+                                    LogLevel::Info,
+                                ))
+                                .await;
+                            task_lifecycle::advance(
+                                &task_id,
+                                TaskState::Proved,
+                                Worker::ProofSubmitter,
+                                &event_sender,
+                            )
+                            .await;
+                            queue.requeue(current);
+                            continue;
+                        }
+                    };
+                    if let SubmissionOutcome::Retry(delay) = result {
+                        task_lifecycle::advance(
+                            &task_id,
+                            TaskState::Proved,
+                            Worker::ProofSubmitter,
+                            &event_sender,
+                        )
+                        .await;
+                        queue.retry_later(current, SystemTime::now() + delay);
+                        continue;
+                    }
+                    if let (Some(node_id), Some(accepted)) = (node_id, result.accepted()) {
+                        crate::nodes::record_submission(node_id, accepted);
+                    }
+                    if result == SubmissionOutcome::Accepted {
+                        queue.record_upload(current.upload_bytes, started.elapsed());
+                        completed_count += 1;
+                        verifications.schedule(&task_id, node_id);
+                    }
+                    control_state().task_finished();
+                    report_latency_slo(&environment, &event_sender).await;
+
+                    // Check if it's time to report stats (avoid timer starvation)
+                    if last_stats_time.elapsed() >= stats_interval {
+                        report_performance_stats(&event_sender, completed_count, last_stats_time).await;
+                        completed_count = 0;
+                        last_stats_time = std::time::Instant::now();
                     }
                 }
 
                 _ = tokio::time::sleep(stats_interval) => {
                     // Fallback timer in case there's no activity
                     report_performance_stats(&event_sender, completed_count, last_stats_time).await;
+                    report_goal_notice(goal::check(), &event_sender).await;
+                    report_latency_slo(&environment, &event_sender).await;
                     completed_count = 0;
                     last_stats_time = std::time::Instant::now();
                 }
 
+                _ = tokio::time::sleep_until(
+                    verifications
+                        .next_due()
+                        .unwrap_or_else(|| tokio::time::Instant::now() + stats_interval)
+                ), if verifications.next_due().is_some() => {
+                    verify_due_submissions(
+                        &mut verifications,
+                        &*orchestrator,
+                        &node_orchestrators,
+                        &mut journal,
+                        &event_sender,
+                    ).await;
+                }
+
+                _ = tokio::time::sleep(
+                    queue
+                        .until_next_retry(SystemTime::now())
+                        .unwrap_or(stats_interval)
+                ), if queue.until_next_retry(SystemTime::now()).is_some() => {
+                    // Refused proofs are queued again at the top of the loop
+                }
+
                 _ = shutdown.recv() => break,
             }
         }
+
+        flush_on_shutdown(
+            queue,
+            &mut results,
+            &mut journal,
+            &*orchestrator,
+            &signing_key,
+            num_workers,
+            &event_sender,
+            &successful_tasks,
+            &environment,
+        )
+        .await;
     })
 }
 
+/// Queues a finished proof for submission; its task no longer needs a checkpoint.
+fn queue_proof(queue: &mut SubmissionQueue, task: Task, proof: Vec<u8>) {
+    checkpoint::untrack(&task.task_id);
+    queue.push(task, proof, SystemTime::now());
+}
+
+/// Submits the proofs still queued when the node stops, then those the provers finish while
+/// stopping, until they are all done. Each proof is journaled first, so whatever is not
+/// submitted before the process exits is resubmitted on the next start.
+#[allow(clippy::too_many_arguments)]
+async fn flush_on_shutdown(
+    mut queue: SubmissionQueue,
+    results: &mut mpsc::Receiver<(Task, Vec<u8>)>,
+    journal: &mut SubmissionJournal,
+    orchestrator: &dyn Orchestrator,
+    signing_key: &SigningKey,
+    num_workers: usize,
+    event_sender: &mpsc::Sender<Event>,
+    successful_tasks: &TaskCache,
+    environment: &Environment,
+) {
+    loop {
+        let finished = queue.drain();
+        if !finished.is_empty() {
+            let _ = event_sender
+                .send(Event::proof_submitter_with_level(
+                    format!(
+                        "Submitting {} finished proofs before exiting",
+                        finished.len()
+                    ),
+                    crate::events::EventType::Shutdown,
+                    LogLevel::Info,
+                ))
+                .await;
+            for item in &finished {
+                journal_on_shutdown(item, journal, event_sender, successful_tasks, environment)
+                    .await;
+            }
+            reconcile_pending_submissions(
+                journal,
+                orchestrator,
+                signing_key,
+                num_workers,
+                event_sender,
+                successful_tasks,
+            )
+            .await;
+        }
+        // The channel closes once every prover has stopped
+        match results.recv().await {
+            Some((task, proof)) => queue_proof(&mut queue, task, proof),
+            None => break,
+        }
+    }
+}
+
+/// Records a finished proof in the journal as about to be submitted, unless it was already
+/// submitted or is simulated.
+async fn journal_on_shutdown(
+    item: &QueuedProof,
+    journal: &mut SubmissionJournal,
+    event_sender: &mpsc::Sender<Event>,
+    successful_tasks: &TaskCache,
+    environment: &Environment,
+) {
+    let (task, proof_bytes) = (&item.task, &item.proof_bytes);
+    if successful_tasks.contains(&task.task_id).await
+        || journal.is_committed(&task.task_id)
+        || !fake_prover::may_submit(environment)
+    {
+        proof_checkpoint::remove(&task.task_id);
+        return;
+    }
+    task_lifecycle::advance(
+        &task.task_id,
+        TaskState::Submitting,
+        Worker::ProofSubmitter,
+        event_sender,
+    )
+    .await;
+    let proof_hash = format!("{:x}", Keccak256::digest(proof_bytes));
+    if let Err(e) = journal.prepare(task, &proof_hash, proof_bytes) {
+        // The proof checkpoint is kept for a restart instead
+        let _ = event_sender
+            .send(Event::proof_submitter_with_level(
+                format!(
+                    "Failed to journal submission for task {}: {}",
+                    task.task_id, e
+                ),
+                crate::events::EventType::Error,
+                LogLevel::Warn,
+            ))
+            .await;
+    } else {
+        proof_checkpoint::remove(&task.task_id);
+    }
+}
+
+/// Checks the submissions that are due for verification and records the verdicts.
+///
+/// Each node's assigned tasks are listed once, however many of its submissions are due.
+async fn verify_due_submissions(
+    verifications: &mut PendingVerifications,
+    orchestrator: &dyn Orchestrator,
+    node_orchestrators: &HashMap<u64, Box<dyn Orchestrator>>,
+    journal: &mut SubmissionJournal,
+    event_sender: &mpsc::Sender<Event>,
+) {
+    let mut assigned: HashMap<u64, Option<HashSet<String>>> = HashMap::new();
+    for check in verifications.take_due() {
+        if !assigned.contains_key(&check.node_id) {
+            let node_orchestrator = node_orchestrators
+                .get(&check.node_id)
+                .map(|orchestrator| &**orchestrator)
+                .unwrap_or(orchestrator);
+            let task_ids = node_orchestrator
+                .get_tasks(&check.node_id.to_string())
+                .await
+                .ok()
+                .map(|tasks| tasks.into_iter().map(|task| task.task_id).collect());
+            assigned.insert(check.node_id, task_ids);
+        }
+        let still_assigned = assigned[&check.node_id]
+            .as_ref()
+            .map(|task_ids| task_ids.contains(&check.task_id));
+
+        let task_id = check.task_id.clone();
+        let checks = check.attempts + 1;
+        let (credited, msg, log_level) = match verifications.resolve(check, still_assigned) {
+            Verdict::Recheck => continue,
+            Verdict::Credited => (
+                true,
+                format!("Verified that the proof for task {} was credited", task_id),
+                LogLevel::Info,
+            ),
+            Verdict::Unconfirmed => (
+                false,
+                format!(
+                    "Proof for task {} was accepted but not confirmed as credited after {} checks",
+                    task_id, checks
+                ),
+                LogLevel::Warn,
+            ),
+        };
+        if let Err(e) = journal.record_verification(&task_id, credited) {
+            let _ = event_sender
+                .send(Event::proof_submitter_with_level(
+                    format!("Failed to journal verification of task {}: {}", task_id, e),
+                    crate::events::EventType::Error,
+                    LogLevel::Warn,
+                ))
+                .await;
+        }
+        let _ = event_sender
+            .send(Event::proof_submitter_with_level(
+                msg,
+                crate::events::EventType::Refresh,
+                log_level,
+            ))
+            .await;
+    }
+}
+
 /// Report performance statistics
 async fn report_performance_stats(
     event_sender: &mpsc::Sender<Event>,
@@ -697,22 +1252,45 @@ async fn report_performance_stats(
         .await;
 }
 
-/// Process a single proof submission
-/// Returns Some(true) if successful, Some(false) if failed, None if should skip
+/// How a proof submission ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SubmissionOutcome {
+    Accepted,
+    Failed,
+    /// Refused for a reason that may pass; worth submitting again after the delay
+    Retry(Duration),
+    /// Not submitted: a duplicate or a simulated proof
+    Skipped,
+}
+
+impl SubmissionOutcome {
+    /// Whether the proof was accepted, if the submission is over.
+    fn accepted(self) -> Option<bool> {
+        match self {
+            Self::Accepted => Some(true),
+            Self::Failed => Some(false),
+            Self::Retry(_) | Self::Skipped => None,
+        }
+    }
+}
+
+/// Submits a queued proof
 #[allow(clippy::too_many_arguments)]
 async fn process_proof_submission(
-    task: Task,
-    proof: Proof,
+    item: &QueuedProof,
     orchestrator: &dyn Orchestrator,
     signing_key: &SigningKey,
     num_workers: usize,
     event_sender: &mpsc::Sender<Event>,
     successful_tasks: &TaskCache,
+    journal: &mut SubmissionJournal,
+    error_budget: &ErrorBudget,
     environment: &Environment,
     client_id: &str,
-) -> Option<bool> {
+) -> SubmissionOutcome {
+    let (task, proof_bytes) = (&item.task, &item.proof_bytes);
     // Check for duplicate submissions
-    if successful_tasks.contains(&task.task_id).await {
+    if successful_tasks.contains(&task.task_id).await || journal.is_committed(&task.task_id) {
         let msg = format!(
             "Ignoring proof for previously submitted task {}",
             task.task_id
@@ -720,46 +1298,331 @@ async fn process_proof_submission(
         let _ = event_sender
             .send(Event::proof_submitter(msg, crate::events::EventType::Error))
             .await;
-        return None; // Skip this task
+        task_lifecycle::advance(
+            &task.task_id,
+            TaskState::Abandoned,
+            Worker::ProofSubmitter,
+            event_sender,
+        )
+        .await;
+        proof_checkpoint::remove(&task.task_id);
+        return SubmissionOutcome::Skipped;
+    }
+
+    if !fake_prover::may_submit(environment) {
+        let msg = format!(
+            "Discarded simulated proof for task {} (simulated proofs are only submitted to a local orchestrator)",
+            task.task_id
+        );
+        let _ = event_sender
+            .send(Event::proof_submitter_with_level(
+                msg,
+                crate::events::EventType::Refresh,
+                LogLevel::Info,
+            ))
+            .await;
+        task_lifecycle::advance(
+            &task.task_id,
+            TaskState::Abandoned,
+            Worker::ProofSubmitter,
+            event_sender,
+        )
+        .await;
+        proof_checkpoint::remove(&task.task_id);
+        return SubmissionOutcome::Skipped;
     }
 
-    // Serialize proof
-    let proof_bytes = postcard::to_allocvec(&proof).expect("Failed to serialize proof");
-    let proof_hash = format!("{:x}", Keccak256::digest(&proof_bytes));
+    let proof_hash = format!("{:x}", Keccak256::digest(proof_bytes));
+
+    task_lifecycle::advance(
+        &task.task_id,
+        TaskState::Submitting,
+        Worker::ProofSubmitter,
+        event_sender,
+    )
+    .await;
+    // Phase 1: record the submission before sending it, so a crash can be reconciled on restart
+    if let Err(e) = journal.prepare(task, &proof_hash, proof_bytes) {
+        // The proof checkpoint is kept for a restart instead
+        let _ = event_sender
+            .send(Event::proof_submitter_with_level(
+                format!(
+                    "Failed to journal submission for task {}: {}",
+                    task.task_id, e
+                ),
+                crate::events::EventType::Error,
+                LogLevel::Warn,
+            ))
+            .await;
+    } else {
+        proof_checkpoint::remove(&task.task_id);
+    }
 
-    // Submit to orchestrator
-    match orchestrator
+    // Submit to orchestrator, through the proxy the task was fetched through
+    let result = orchestrator
         .submit_proof(
-            &task.task_id,
-            &proof_hash,
-            proof_bytes,
+            ProofSubmission::new(&task.task_id, &proof_hash, proof_bytes.to_vec())
+                .num_provers(num_workers)
+                .task_type(task.task_type)
+                .proxy(task.proxy.clone()),
             signing_key.clone(),
-            num_workers,
-            task.task_type,
         )
-        .await
-    {
-        Ok(_) => {
+        .await;
+    report_affinity_switch(orchestrator, &task.task_id, event_sender).await;
+    // An interrupted or retried upload may have reached the orchestrator before, as in
+    // `reconcile_pending_submissions`: a `409 Conflict` then means it was accepted
+    let result = match result {
+        Err(OrchestratorError::TaskAlreadyClaimed { .. }) if item.preempted || item.retries > 0 => {
+            let _ = event_sender
+                .send(Event::proof_submitter_with_level(
+                    format!(
+                        "Proof for task {} was already received by an earlier upload",
+                        task.task_id
+                    ),
+                    crate::events::EventType::Refresh,
+                    LogLevel::Info,
+                ))
+                .await;
+            Ok(None)
+        }
+        result => result,
+    };
+    match result {
+        Ok(receipt) => {
+            // Phase 2: the orchestrator accepted the proof
+            record_journal_outcome(journal, &task.task_id, Ok(receipt), event_sender).await;
+            crate::progress::emit(ProgressEvent::Submitted {
+                task_id: task.task_id.clone(),
+            });
+            crate::hooks::task_complete(task);
+            error_budget
+                .report(true, Worker::ProofSubmitter, event_sender)
+                .await;
             // Track analytics for proof submission success (non-blocking)
             tokio::spawn(track_proof_submission_success(
                 task.clone(),
                 environment.clone(),
                 client_id.to_string(),
             ));
-            handle_submission_success(
-                &task,
-                event_sender,
-                successful_tasks,
-                environment,
-                client_id,
-            )
-            .await;
-            Some(true)
+            handle_submission_success(task, event_sender, successful_tasks, environment, client_id)
+                .await;
+            SubmissionOutcome::Accepted
         }
         Err(e) => {
-            handle_submission_error(&task, e, event_sender, environment, client_id).await;
-            Some(false)
+            let retry_delay = e
+                .is_transient_rejection()
+                .then(|| {
+                    let retry_after = e
+                        .get_retry_after_seconds()
+                        .map(|secs| Duration::from_secs(secs.into()));
+                    item.retry_delay(retry_after, SystemTime::now())
+                })
+                .flatten();
+            if let Some(delay) = retry_delay {
+                // The submission stays pending in the journal until the retry settles it
+                let _ = event_sender
+                    .send(Event::proof_submitter_with_level(
+                        format!(
+                            "Proof for task {} was refused for now ({}), retrying in {}s (retry {} of {})",
+                            task.task_id,
+                            e,
+                            delay.as_secs(),
+                            item.retries + 1,
+                            crate::submission_queue::MAX_RETRIES
+                        ),
+                        crate::events::EventType::Refresh,
+                        LogLevel::Warn,
+                    ))
+                    .await;
+                return SubmissionOutcome::Retry(delay);
+            }
+            crate::progress::emit(ProgressEvent::Failed {
+                task_id: task.task_id.clone(),
+                stage: "submit",
+                error: e.to_string(),
+            });
+            // Only an HTTP response is a definitive rejection. Transport errors and maintenance
+            // leave the submission pending, so it is resubmitted on the next start.
+            if let Some(status) = e.rejection_status() {
+                let reason = format!("HTTP {}", status);
+                record_journal_outcome(journal, &task.task_id, Err(&reason), event_sender).await;
+                crate::hooks::task_failed(task, "submit", &e.to_string());
+            }
+            if let Some(window) = MaintenanceWindow::from_error(&e) {
+                // The fetcher announces the window once; this is logged per task
+                let _ = event_sender
+                    .send(Event::maintenance_with_level(
+                        format!(
+                            "{} - proof for task {} will be resubmitted on next start",
+                            window, task.task_id
+                        ),
+                        crate::events::EventType::Refresh,
+                        LogLevel::Debug,
+                    ))
+                    .await;
+                return SubmissionOutcome::Failed;
+            }
+            // Maintenance is not the node's fault, so only other failures use up the budget
+            error_budget
+                .report(false, Worker::ProofSubmitter, event_sender)
+                .await;
+            handle_submission_error(task, e, event_sender, environment, client_id).await;
+            SubmissionOutcome::Failed
+        }
+    }
+}
+
+/// Reports a submission that could not go through the proxy its task was fetched through.
+async fn report_affinity_switch(
+    orchestrator: &dyn Orchestrator,
+    task_id: &str,
+    event_sender: &mpsc::Sender<Event>,
+) {
+    if let Some((from, to)) = orchestrator.take_proxy_switch(task_id) {
+        let _ = event_sender
+            .send(
+                Event::proof_submitter_with_level(
+                    format!(
+                        "Task {}: proxy {} was unavailable, submitted through {}",
+                        task_id, from, to
+                    ),
+                    crate::events::EventType::Refresh,
+                    LogLevel::Warn,
+                )
+                .with_task(task_id)
+                .with_proxy(&to),
+            )
+            .await;
+    }
+}
+
+/// Records the outcome of a journaled submission: committed with the orchestrator's receipt, if
+/// any, or aborted with a reason.
+async fn record_journal_outcome(
+    journal: &mut SubmissionJournal,
+    task_id: &str,
+    outcome: Result<Option<SubmissionReceipt>, &str>,
+    event_sender: &mpsc::Sender<Event>,
+) {
+    let (result, state) = match outcome {
+        Ok(receipt) => (journal.commit(task_id, receipt), TaskState::Accepted),
+        Err(reason) => (journal.abort(task_id, reason), TaskState::Rejected),
+    };
+    task_lifecycle::advance(task_id, state, Worker::ProofSubmitter, event_sender).await;
+    if let Err(e) = result {
+        let _ = event_sender
+            .send(Event::proof_submitter_with_level(
+                format!(
+                    "Failed to journal submission outcome for task {}: {}",
+                    task_id, e
+                ),
+                crate::events::EventType::Error,
+                LogLevel::Warn,
+            ))
+            .await;
+    }
+}
+
+/// How a pending submission was resolved during reconciliation.
+enum Resolution {
+    Committed(Option<SubmissionReceipt>),
+    Aborted(String),
+    Pending,
+}
+
+/// Resubmits proofs left pending in the journal by a previous run.
+///
+/// The orchestrator may have received a pending submission before the crash, so a
+/// `409 Conflict` is treated as already accepted rather than as a failure.
+async fn reconcile_pending_submissions(
+    journal: &mut SubmissionJournal,
+    orchestrator: &dyn Orchestrator,
+    signing_key: &SigningKey,
+    num_workers: usize,
+    event_sender: &mpsc::Sender<Event>,
+    successful_tasks: &TaskCache,
+) {
+    for pending in journal.pending() {
+        let task_id = pending.task.task_id.clone();
+        let proof_bytes = match journal.load_proof(&task_id) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                let reason = format!("saved proof unavailable: {}", e);
+                record_journal_outcome(journal, &task_id, Err(&reason), event_sender).await;
+                continue;
+            }
+        };
+
+        let result = orchestrator
+            .submit_proof(
+                ProofSubmission::new(&task_id, &pending.proof_hash, proof_bytes)
+                    .num_provers(num_workers)
+                    .task_type(pending.task.task_type)
+                    .proxy(pending.task.proxy.clone()),
+                signing_key.clone(),
+            )
+            .await;
+        report_affinity_switch(orchestrator, &task_id, event_sender).await;
+        let (msg, log_level, resolution) = match result {
+            Ok(receipt) => (
+                format!("Recovered interrupted submission for task {}", task_id),
+                LogLevel::Info,
+                Resolution::Committed(receipt),
+            ),
+            Err(OrchestratorError::TaskAlreadyClaimed { .. }) => (
+                format!(
+                    "Interrupted submission for task {} was already accepted",
+                    task_id
+                ),
+                LogLevel::Info,
+                Resolution::Committed(None),
+            ),
+            // Briefly failing is not a verdict on the proof
+            Err(e) if e.is_transient_rejection() => (
+                format!(
+                    "Could not reconcile submission for task {}, will retry on next start: {}",
+                    task_id, e
+                ),
+                LogLevel::Warn,
+                Resolution::Pending,
+            ),
+            Err(e) => match e.rejection_status() {
+                Some(status) => (
+                    format!(
+                        "Interrupted submission for task {} was rejected. Status: {}",
+                        task_id, status
+                    ),
+                    LogLevel::Warn,
+                    Resolution::Aborted(format!("HTTP {}", status)),
+                ),
+                None => (
+                    format!(
+                        "Could not reconcile submission for task {}, will retry on next start: {}",
+                        task_id, e
+                    ),
+                    LogLevel::Warn,
+                    Resolution::Pending,
+                ),
+            },
+        };
+
+        match resolution {
+            Resolution::Committed(receipt) => {
+                successful_tasks.insert(task_id.clone()).await;
+                record_journal_outcome(journal, &task_id, Ok(receipt), event_sender).await;
+            }
+            Resolution::Aborted(reason) => {
+                record_journal_outcome(journal, &task_id, Err(&reason), event_sender).await;
+            }
+            Resolution::Pending => {}
         }
+        let _ = event_sender
+            .send(Event::proof_submitter_with_level(
+                msg,
+                crate::events::EventType::Refresh,
+                log_level,
+            ))
+            .await;
     }
 }
 
@@ -784,10 +1647,53 @@ async fn handle_submission_success(
     ));
 
     let _ = event_sender
-        .send(Event::proof_submitter_with_level(
-            msg,
+        .send(
+            Event::proof_submitter_with_level(
+                msg,
+                crate::events::EventType::Success,
+                LogLevel::Info,
+            )
+            .with_node(task.node_id)
+            .with_task(&task.task_id),
+        )
+        .await;
+    report_goal_notice(goal::record_accepted_proof(), event_sender).await;
+}
+
+/// Announces that the earnings goal was met or is falling behind.
+async fn report_goal_notice(notice: Option<GoalNotice>, event_sender: &mpsc::Sender<Event>) {
+    let (msg, event_type, level) = match notice {
+        Some(GoalNotice::Met(progress)) => (
+            format!("Goal met: {}", progress),
             crate::events::EventType::Success,
             LogLevel::Info,
+        ),
+        Some(GoalNotice::FallingBehind(progress)) => (
+            format!("Falling behind goal: {}", progress),
+            crate::events::EventType::Error,
+            LogLevel::Warn,
+        ),
+        None => return,
+    };
+    let _ = event_sender
+        .send(Event::proof_submitter_with_level(msg, event_type, level))
+        .await;
+}
+
+/// Announces breaches of the submission latency SLO, and recoveries.
+async fn report_latency_slo(environment: &Environment, event_sender: &mpsc::Sender<Event>) {
+    let Some(notice) = latency_slo::check(environment) else {
+        return;
+    };
+    let (event_type, level) = match notice {
+        SloNotice::Breached { .. } => (crate::events::EventType::Error, LogLevel::Warn),
+        SloNotice::Recovered { .. } => (crate::events::EventType::Success, LogLevel::Info),
+    };
+    let _ = event_sender
+        .send(Event::proof_submitter_with_level(
+            notice.to_string(),
+            event_type,
+            level,
         ))
         .await;
 }
@@ -800,16 +1706,20 @@ async fn handle_submission_error(
     environment: &Environment,
     client_id: &str,
 ) {
-    let (msg, status_code) = match error {
-        OrchestratorError::Http { status, .. } => (
+    let hint = hints::hint_for(&error);
+    let (msg, status_code) = match error.status() {
+        Some(status) => (
             format!(
                 "Failed to submit proof for task {}. Status: {}",
                 task.task_id, status
             ),
             Some(status),
         ),
-        e => (
-            format!("Failed to submit proof for task {}: {}", task.task_id, e),
+        None => (
+            format!(
+                "Failed to submit proof for task {}: {}",
+                task.task_id, error
+            ),
             None,
         ),
     };
@@ -824,16 +1734,24 @@ async fn handle_submission_error(
     ));
 
     let _ = event_sender
-        .send(Event::proof_submitter(
-            msg.to_string(),
-            crate::events::EventType::Error,
-        ))
+        .send(
+            Event::proof_submitter(
+                match hint {
+                    Some(hint) => format!("{}. {}", msg, hint),
+                    None => msg,
+                },
+                crate::events::EventType::Error,
+            )
+            .with_node(task.node_id)
+            .with_task(&task.task_id),
+        )
         .await;
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::consts::prover::BACKOFF_DURATION;
     use std::time::Duration;
 
     #[test]
@@ -862,6 +1780,54 @@ mod tests {
         assert_eq!(state.backoff_duration, Duration::from_secs(3600));
     }
 
+    #[test]
+    fn test_idle_backoff_lengthens_and_resets() {
+        let mut state = TaskFetchState::new();
+        let polling = PollingConfig::default();
+
+        // Test that repeated empty responses lengthen polling up to the idle cap
+        for _ in 0..10 {
+            state.increase_backoff_for_idle();
+        }
+        assert_eq!(state.backoff_duration, polling.max_idle_interval);
+
+        // Test that receiving tasks returns to the base interval
+        state.reset_backoff();
+        assert_eq!(state.backoff_duration, polling.interval);
+
+        // Test that a longer server retry time is not shortened by idle backoff
+        state.set_backoff_from_server(3600);
+        state.increase_backoff_for_idle();
+        assert_eq!(state.backoff_duration, Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn test_action_required_pauses_by_kind() {
+        let mut state = TaskFetchState::new();
+        let interval = PollingConfig::default().interval;
+        let message = String::new;
+
+        // Test that an outdated CLI pauses longest
+        state.pause_for_action_required(&OrchestratorError::VersionTooOld { message: message() });
+        assert_eq!(state.backoff_duration, VERSION_TOO_OLD_BACKOFF);
+
+        // Test that an unknown node is asked about again within minutes
+        state.pause_for_action_required(&OrchestratorError::NodeNotRegistered {
+            message: message(),
+        });
+        assert_eq!(state.backoff_duration, NODE_NOT_REGISTERED_BACKOFF);
+
+        // Test that rejected credentials start at the interval and double up to the cap
+        state.reset_backoff();
+        let unauthorized = OrchestratorError::Unauthorized { message: message() };
+        state.pause_for_action_required(&unauthorized);
+        assert_eq!(state.backoff_duration, interval * 2);
+        for _ in 0..10 {
+            state.pause_for_action_required(&unauthorized);
+        }
+        assert_eq!(state.backoff_duration, UNAUTHORIZED_MAX_BACKOFF);
+    }
+
     #[test]
     fn test_reset_backoff() {
         let mut state = TaskFetchState::new();
@@ -873,4 +1839,78 @@ mod tests {
             Duration::from_millis(BACKOFF_DURATION)
         );
     }
+
+    /// Submits `item` to an orchestrator that answers `409 Conflict`, returning the outcome
+    /// and whether the journal committed the submission.
+    async fn submit_conflicting(item: &QueuedProof) -> (SubmissionOutcome, bool) {
+        let dir = tempfile::tempdir().unwrap();
+        let mut journal = SubmissionJournal::open(dir.path()).unwrap();
+        let mut orchestrator = crate::orchestrator::MockOrchestrator::new();
+        orchestrator.expect_submit_proof().returning(|_, _| {
+            Err(OrchestratorError::TaskAlreadyClaimed {
+                message: "already submitted".to_string(),
+            })
+        });
+        orchestrator.expect_take_proxy_switch().returning(|_| None);
+        let (sender, _receiver) = mpsc::channel(100);
+        let outcome = process_proof_submission(
+            item,
+            &orchestrator,
+            &SigningKey::generate(&mut rand::rngs::OsRng),
+            1,
+            &sender,
+            &TaskCache::new(10),
+            &mut journal,
+            &ErrorBudget::new(None),
+            &Environment::Production,
+            "test-client-id",
+        )
+        .await;
+        (outcome, journal.is_committed(&item.task.task_id))
+    }
+
+    #[tokio::test]
+    async fn test_conflict_after_interrupted_upload_is_accepted() {
+        let mut queue = SubmissionQueue::new();
+        let task = |task_id: &str| Task::new(task_id.to_string(), "fast-fib".to_string(), vec![1]);
+        let now = SystemTime::now();
+        queue.push(task("first-upload"), vec![1, 2, 3], now);
+        queue.push(task("preempted"), vec![1, 2, 3], now);
+        let first = queue.pop().unwrap();
+        let mut preempted = queue.pop().unwrap();
+        preempted.preempted = true;
+
+        // Test that a conflict on a first upload is still a rejection
+        assert_eq!(
+            submit_conflicting(&first).await,
+            (SubmissionOutcome::Failed, false)
+        );
+
+        // Test that a conflict on an upload that may have gone through before is an acceptance
+        assert_eq!(
+            submit_conflicting(&preempted).await,
+            (SubmissionOutcome::Accepted, true)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_announced_once_per_window() {
+        let mut state = TaskFetchState::new();
+        let (sender, mut receiver) = mpsc::channel(8);
+        let window = |minutes| MaintenanceWindow {
+            until: chrono::Utc::now() + chrono::Duration::minutes(minutes),
+            message: None,
+        };
+
+        // Test that a window moved by Retry-After on each poll is announced only once
+        enter_maintenance(window(5), &sender, &mut state).await;
+        enter_maintenance(window(6), &sender, &mut state).await;
+        assert_eq!(receiver.recv().await.unwrap().log_level, LogLevel::Warn);
+        assert_eq!(receiver.recv().await.unwrap().log_level, LogLevel::Debug);
+
+        // Test that a window after the previous one ended is announced again
+        state.maintenance = Some(window(-1));
+        enter_maintenance(window(5), &sender, &mut state).await;
+        assert_eq!(receiver.recv().await.unwrap().log_level, LogLevel::Warn);
+    }
 }
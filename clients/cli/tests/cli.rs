@@ -71,3 +71,43 @@ fn logout_deletes_config_file() {
     // Confirm the file was deleted
     assert!(!config_path.exists());
 }
+
+#[test]
+/// Status command should report the configured node as JSON.
+fn status_json_reports_node_id() {
+    let tmp = temp_config_dir();
+    let config_path = config_file_path(&tmp);
+    fs::create_dir_all(config_path.parent().unwrap()).unwrap();
+    fs::write(&config_path, r#"{"node_id": "12345"}"#).unwrap();
+
+    let mut cmd = Command::cargo_bin(BINARY_NAME).unwrap();
+    cmd.arg("status")
+        .arg("--json")
+        .env("HOME", tmp.path()) // simulate different $HOME
+        .assert()
+        .success()
+        .stdout(contains(r#""node_id": "12345""#))
+        .stdout(contains(r#""capability": null"#));
+}
+
+#[test]
+/// Config files from earlier releases should be migrated with a backup, and `config diff`
+/// should attribute values to their source.
+fn config_diff_reports_sources_after_migration() {
+    let tmp = temp_config_dir();
+    let config_path = config_file_path(&tmp);
+    fs::create_dir_all(config_path.parent().unwrap()).unwrap();
+    fs::write(&config_path, r#"{"node_id": 12345}"#).unwrap();
+
+    let mut cmd = Command::cargo_bin(BINARY_NAME).unwrap();
+    cmd.arg("config")
+        .arg("diff")
+        .env("HOME", tmp.path()) // simulate different $HOME
+        .assert()
+        .success()
+        .stdout(contains("node_id"))
+        .stdout(contains("file"))
+        .stdout(contains("12345"));
+
+    assert!(config_path.with_file_name("config.json.v0.bak").exists());
+}